@@ -1,4 +1,4 @@
-/// Example: Reading CfRadial1 data with Rust
+//! Example: Reading CfRadial1 data with Rust
 
 use radish::backends::{RadarBackend, CfRadial1Backend};
 use std::path::Path;
@@ -39,7 +39,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             println!("  Units: {}", dbz.units);
 
             // Access data
-            let data = &dbz.data;
+            let data = dbz.data();
             let max_val = data.iter()
                 .filter(|v| !v.is_nan())
                 .fold(f32::NEG_INFINITY, |a, &b| a.max(b));
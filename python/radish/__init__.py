@@ -9,9 +9,20 @@ from radish._radish import (
     VolumeMetadata,
     SweepData,
     MomentData,
+    LazyVolume,
+    GriddedData,
+)
+from radish.readers import (
+    can_open,
+    open,
+    open_lazy,
     read_cfradial1,
+    read_many,
+    read_sweep,
+    scan,
     scan_cfradial1,
 )
+from radish.writers import write_cfradial2, write_odim, write_zarr
 
 __version__ = "0.1.0"
 
@@ -20,6 +31,17 @@ __all__ = [
     "VolumeMetadata",
     "SweepData",
     "MomentData",
+    "LazyVolume",
+    "GriddedData",
     "read_cfradial1",
     "scan_cfradial1",
+    "open",
+    "scan",
+    "open_lazy",
+    "read_many",
+    "read_sweep",
+    "can_open",
+    "write_cfradial2",
+    "write_odim",
+    "write_zarr",
 ]
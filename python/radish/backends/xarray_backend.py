@@ -1,60 +1,365 @@
 """Xarray backend for radish"""
 
+import os
 from typing import Any, Dict, Iterable, Optional
 import numpy as np
 
 try:
     import xarray as xr
-    from xarray.backends import BackendEntrypoint
+    from xarray.backends import BackendArray, BackendEntrypoint
     from xarray.core import indexing
     XARRAY_AVAILABLE = True
 except ImportError:
     XARRAY_AVAILABLE = False
+    BackendArray = object  # type: ignore
     BackendEntrypoint = object  # type: ignore
 
 try:
-    from datatree import DataTree
+    from xarray import DataTree
     DATATREE_AVAILABLE = True
 except ImportError:
-    DATATREE_AVAILABLE = False
+    try:
+        from datatree import DataTree
+        DATATREE_AVAILABLE = True
+    except ImportError:
+        DATATREE_AVAILABLE = False
 
-from radish import read_cfradial1, VolumeData
+import radish
+
+
+# CF attributes of the sweep coordinates
+COORDINATE_ATTRS = {
+    "azimuth": {
+        "standard_name": "ray_azimuth_angle",
+        "long_name": "azimuth_angle_from_true_north",
+        "units": "degrees",
+        "axis": "radial_azimuth_coordinate",
+    },
+    "elevation": {
+        "standard_name": "ray_elevation_angle",
+        "long_name": "elevation_angle_from_horizontal_plane",
+        "units": "degrees",
+        "axis": "radial_elevation_coordinate",
+    },
+    "range": {
+        "standard_name": "projection_range_coordinate",
+        "long_name": "range_to_measurement_volume",
+        "units": "meters",
+        "axis": "radial_range_coordinate",
+    },
+    "time": {
+        "standard_name": "time",
+        "long_name": "time_of_ray",
+    },
+    "latitude": {
+        "standard_name": "latitude",
+        "long_name": "latitude",
+        "units": "degrees_north",
+    },
+    "longitude": {
+        "standard_name": "longitude",
+        "long_name": "longitude",
+        "units": "degrees_east",
+    },
+    "altitude": {
+        "standard_name": "altitude",
+        "long_name": "altitude",
+        "units": "meters",
+    },
+}
+
+
+class RadishBackendArray(BackendArray):
+    """
+    Moment decoded into numpy only when xarray indexes it.
+
+    The moment stays in its (possibly packed) Rust storage until then; the
+    decoded array is kept for later indexing.
+    """
+
+    def __init__(self, moment):
+        self.moment = moment
+        self.shape = moment.shape
+        self.dtype = np.dtype(np.float32)
+        self._data = None
+
+    def __getitem__(self, key):
+        return indexing.explicit_indexing_adapter(
+            key, self.shape, indexing.IndexingSupport.BASIC, self._raw_indexing_method
+        )
+
+    def _raw_indexing_method(self, key):
+        if self._data is None:
+            self._data = self.moment.masked_data(fill_with=np.nan)
+        return self._data[key]
+
+
+def sweep_to_dataset(sweep, latitude, longitude, altitude, lazy=False) -> "xr.Dataset":
+    """
+    Convert a sweep to an xarray Dataset.
+
+    Moments are indexed by azimuth (elevation for RHIs) and range, with
+    invalid gates set to NaN. Ray times are datetime64 coordinates along
+    the ray dimension. With `lazy`, moments are decoded on first access.
+    """
+    if not XARRAY_AVAILABLE:
+        raise ImportError(
+            "xarray is required for to_xarray. "
+            "Install with: pip install radish[xarray]"
+        )
+
+    sweep_mode = sweep.sweep_mode
+    rhi = sweep_mode in ("elevation_surveillance", "manual_rhi")
+    ray_dim = "elevation" if rhi else "azimuth"
+
+    seconds = np.array(sweep.time, dtype=np.float64)
+    finite = np.isfinite(seconds)
+    time = np.round(np.where(finite, seconds, 0.0) * 1e9).astype("int64").view("datetime64[ns]")
+    time[~finite] = np.datetime64("NaT")
+
+    coords = {
+        "azimuth": ([ray_dim], np.array(sweep.azimuth), COORDINATE_ATTRS["azimuth"]),
+        "elevation": ([ray_dim], np.array(sweep.elevation), COORDINATE_ATTRS["elevation"]),
+        "time": ([ray_dim], time, COORDINATE_ATTRS["time"]),
+        "range": (["range"], np.array(sweep.range), COORDINATE_ATTRS["range"]),
+        "latitude": ((), latitude, COORDINATE_ATTRS["latitude"]),
+        "longitude": ((), longitude, COORDINATE_ATTRS["longitude"]),
+        "altitude": ((), altitude, COORDINATE_ATTRS["altitude"]),
+    }
+
+    data_vars = {}
+    for moment_name in sweep.moment_names():
+        moment = sweep.get_moment(moment_name)
+        if moment is None:
+            continue
+        attrs = {"units": moment.units}
+        if moment.standard_name:
+            attrs["standard_name"] = moment.standard_name
+        if moment.long_name:
+            attrs["long_name"] = moment.long_name
+        attrs["coordinates"] = "elevation azimuth range"
+        if lazy:
+            data = indexing.LazilyIndexedArray(RadishBackendArray(moment))
+        else:
+            data = moment.masked_data(fill_with=np.nan)
+        data_vars[moment_name] = ([ray_dim, "range"], data, attrs)
+
+    attrs = {
+        "sweep_number": int(sweep.sweep_number),
+        "sweep_mode": sweep_mode,
+        "fixed_angle": float(sweep.fixed_angle),
+    }
+
+    return xr.Dataset(data_vars=data_vars, coords=coords, attrs=attrs)
+
+
+def grid_to_dataset(grid) -> "xr.Dataset":
+    """
+    Convert gridded data to an xarray Dataset.
+
+    Fields are indexed by `z`, `y` and `x`, with missing cells set to NaN,
+    and the grid time is a scalar coordinate.
+    """
+    if not XARRAY_AVAILABLE:
+        raise ImportError(
+            "xarray is required for to_xarray. "
+            "Install with: pip install radish[xarray]"
+        )
+
+    coords = {
+        "x": (["x"], grid.x, {"long_name": "x_coordinate_of_grid", "units": "meters", "axis": "X"}),
+        "y": (["y"], grid.y, {"long_name": "y_coordinate_of_grid", "units": "meters", "axis": "Y"}),
+        "z": (["z"], grid.z, {"long_name": "altitude_above_mean_sea_level", "units": "meters", "axis": "Z"}),
+        "time": ((), _datetime64(grid.time), COORDINATE_ATTRS["time"]),
+    }
+    data_vars = {
+        name: (["z", "y", "x"], grid.get_field(name), grid.get_field_attrs(name))
+        for name in grid.field_names()
+    }
+    attrs = dict(grid.attributes, projection=grid.projection, source="radish")
+
+    return xr.Dataset(data_vars=data_vars, coords=coords, attrs=attrs)
+
+
+def _datetime64(seconds) -> "np.datetime64":
+    """Seconds since 1970-01-01 UTC as datetime64[ns]"""
+    return np.datetime64(int(round(seconds * 1e9)), "ns")
+
+
+def volume_to_datatree(volume) -> "DataTree":
+    """
+    Convert a volume to a DataTree in the CfRadial2 layout used by xradar.
+
+    The root group holds the volume metadata and the `sweep_N` groups hold
+    the sweeps, with their sweep number, mode and fixed angle as variables.
+    """
+    if not DATATREE_AVAILABLE:
+        raise ImportError(
+            "xarray>=2024.10 or datatree is required for to_datatree. "
+            "Install with: pip install radish[xarray]"
+        )
+    return DataTree.from_dict(volume_to_groups(volume))
+
+
+def volume_to_groups(volume, lazy=False, sweeps=None) -> Dict[str, "xr.Dataset"]:
+    """
+    Datasets of the CfRadial2 groups of a volume, keyed by group path.
+
+    `volume` is a `VolumeData` or a `LazyVolume`, whose sweeps are read
+    here. With `sweeps`, only the sweeps of those indices get a group.
+    """
+    metadata = volume.metadata
+    names = _group_names(metadata, volume.num_sweeps)
+    groups = {"/": root_dataset(metadata, volume.num_sweeps, getattr(volume, "history", None))}
+    for i in range(volume.num_sweeps) if sweeps is None else sweeps:
+        groups[f"/{names[i]}"] = sweep_group_dataset(volume.get_sweep(i), metadata, lazy=lazy)
+    return groups
+
+
+def sweep_group_dataset(sweep, metadata, lazy=False) -> "xr.Dataset":
+    """Dataset of a CfRadial2 sweep group"""
+    ds = sweep_to_dataset(sweep, metadata.latitude, metadata.longitude, metadata.altitude, lazy=lazy)
+    return ds.assign(
+        sweep_number=((), np.int32(sweep.sweep_number)),
+        sweep_mode=((), sweep.sweep_mode),
+        sweep_fixed_angle=((), sweep.fixed_angle, {"units": "degrees"}),
+    )
+
+
+def root_dataset(metadata, num_sweeps, history=None) -> "xr.Dataset":
+    """Root group of the CfRadial2 layout: volume-level variables and attributes"""
+    data_vars = {
+        "volume_number": ((), np.int32(metadata.volume_number)),
+        "platform_type": ((), metadata.platform_type or "fixed"),
+        "time_coverage_start": ((), _datetime64(metadata.time_coverage_start)),
+        "time_coverage_end": ((), _datetime64(metadata.time_coverage_end)),
+        "sweep_group_name": (["sweep"], np.array(_group_names(metadata, num_sweeps))),
+        "sweep_fixed_angle": (["sweep"], np.array(metadata.sweep_fixed_angles), {"units": "degrees"}),
+    }
+    coords = {
+        "latitude": ((), metadata.latitude, COORDINATE_ATTRS["latitude"]),
+        "longitude": ((), metadata.longitude, COORDINATE_ATTRS["longitude"]),
+        "altitude": ((), metadata.altitude, COORDINATE_ATTRS["altitude"]),
+    }
+    attrs = {
+        "Conventions": "Cf/Radial",
+        "version": "2.0",
+        "instrument_name": metadata.instrument_name,
+        "institution": metadata.institution,
+        "source": "radish",
+    }
+    if metadata.site_name:
+        attrs["site_name"] = metadata.site_name
+    if history:
+        attrs["history"] = history
+
+    return xr.Dataset(data_vars=data_vars, coords=coords, attrs=attrs)
+
+
+def _group_names(metadata, num_sweeps):
+    """Sweep group names, `sweep_N` where the file names none"""
+    names = metadata.sweep_group_names
+    return [names[i] if i < len(names) else f"sweep_{i}" for i in range(num_sweeps)]
+
+
+def _sweep_index(metadata, group) -> int:
+    """Index of the sweep stored in a group such as "sweep_0" or "/sweep_0" """
+    name = group.strip("/")
+    names = _group_names(metadata, metadata.num_sweeps)
+    if name in names:
+        return names.index(name)
+    raise ValueError(f"Unknown group {group!r}; groups are '/' and {names}")
+
+
+def _sweep_indices(metadata, sweep):
+    """Indices of the sweeps selected by index, group name or a list of either"""
+    if sweep is None:
+        return None
+    if isinstance(sweep, (int, np.integer, str)):
+        sweep = [sweep]
+    indices = []
+    for s in sweep:
+        if isinstance(s, str):
+            indices.append(_sweep_index(metadata, s))
+        elif 0 <= s < metadata.num_sweeps:
+            indices.append(int(s))
+        else:
+            raise IndexError(f"Sweep {s} out of range for {metadata.num_sweeps} sweeps")
+    return indices
+
+
+def _drop(ds, drop_variables):
+    if drop_variables is None:
+        return ds
+    if isinstance(drop_variables, str):
+        drop_variables = [drop_variables]
+    return ds.drop_vars(list(drop_variables), errors="ignore")
 
 
 class RadishBackendEntrypoint(BackendEntrypoint):
-    """Xarray backend for reading radar files with radish"""
+    """
+    Xarray backend for reading radar files with radish
+
+    Any format radish reads is detected from the file. Groups follow the
+    CfRadial2 layout: `group="/"` gives the volume metadata and
+    `group="sweep_N"` a sweep, whose moments are decoded from the Rust
+    storage only when accessed. Files are opened as a `LazyVolume`, so only
+    the sweeps of the requested groups are read.
+    """
 
     description = "Read weather radar data files using the radish library"
     url = "https://github.com/mgrover1/radish"
+    open_dataset_parameters = ("filename_or_obj", "drop_variables", "group")
 
     def open_dataset(
         self,
         filename_or_obj,
         *,
         drop_variables: Optional[Iterable[str]] = None,
+        group: Optional[str] = None,
         **kwargs
     ):
         """
-        Open a single dataset (sweep).
+        Open a single group of a radar file, the first sweep by default.
 
-        For multi-sweep files, use open_datatree instead.
+        Only the requested sweep is read; use open_datatree for the volume.
         """
-        # Read the volume
-        volume = read_cfradial1(str(filename_or_obj))
+        with radish.open_lazy(filename_or_obj) as volume:
+            metadata = volume.metadata
+            if group is not None and group.strip("/") == "":
+                ds = root_dataset(metadata, metadata.num_sweeps)
+            else:
+                index = _sweep_index(metadata, group) if group is not None else 0
+                ds = sweep_group_dataset(volume.get_sweep(index), metadata, lazy=True)
+                ds.attrs["instrument_name"] = metadata.instrument_name
 
-        # Get the first sweep
-        sweep = volume.get_sweep(0)
+        return _drop(ds, drop_variables)
 
-        # Convert to xarray Dataset
-        dataset = self._sweep_to_dataset(sweep, volume.metadata)
+    def open_groups_as_dict(
+        self,
+        filename_or_obj,
+        *,
+        drop_variables: Optional[Iterable[str]] = None,
+        sweep=None,
+        **kwargs
+    ) -> Dict[str, "xr.Dataset"]:
+        """
+        Open the groups of a radar file, keyed by group path.
 
-        return dataset
+        `sweep` selects the sweep groups by index, group name or a list of
+        either; the other sweeps are not read. All sweeps by default.
+        """
+        with radish.open_lazy(filename_or_obj) as volume:
+            sweeps = _sweep_indices(volume.metadata, sweep)
+            groups = volume_to_groups(volume, lazy=True, sweeps=sweeps)
+        return {path: _drop(ds, drop_variables) for path, ds in groups.items()}
 
     def open_datatree(
         self,
         filename_or_obj,
         *,
         drop_variables: Optional[Iterable[str]] = None,
+        sweep=None,
         **kwargs
     ):
         """
@@ -62,93 +367,27 @@ class RadishBackendEntrypoint(BackendEntrypoint):
 
         Returns a DataTree with:
         - Root group: volume metadata
-        - sweep_N groups: individual sweep data
+        - sweep_N groups: individual sweep data, only those selected by
+          `sweep` (see open_groups_as_dict)
         """
         if not DATATREE_AVAILABLE:
             raise ImportError(
-                "datatree is required for open_datatree. "
-                "Install with: pip install datatree"
+                "xarray>=2024.10 or datatree is required for open_datatree. "
+                "Install with: pip install radish[xarray]"
             )
 
-        # Read the volume
-        volume = read_cfradial1(str(filename_or_obj))
-
-        # Create datasets for each sweep
-        datasets = {}
-
-        # Root dataset with volume metadata
-        root_ds = self._create_root_dataset(volume.metadata)
-        datasets["/"] = root_ds
-
-        # Sweep datasets
-        for i in range(volume.num_sweeps):
-            sweep = volume.get_sweep(i)
-            sweep_ds = self._sweep_to_dataset(sweep, volume.metadata)
-            datasets[f"/sweep_{i}"] = sweep_ds
-
-        # Create DataTree
-        return DataTree.from_dict(datasets)
-
-    def _create_root_dataset(self, metadata) -> "xr.Dataset":
-        """Create root dataset with volume metadata"""
-        coords = {
-            "latitude": metadata.latitude,
-            "longitude": metadata.longitude,
-            "altitude": metadata.altitude,
-        }
-
-        data_vars = {
-            "sweep_fixed_angle": (["sweep"], np.array(metadata.sweep_fixed_angles)),
-        }
-
-        attrs = {
-            "instrument_name": metadata.instrument_name,
-            "Conventions": "CF/Radial",
-        }
-
-        return xr.Dataset(data_vars=data_vars, coords=coords, attrs=attrs)
-
-    def _sweep_to_dataset(self, sweep, volume_metadata) -> "xr.Dataset":
-        """Convert a sweep to an xarray Dataset"""
-        # Coordinates
-        coords = {
-            "azimuth": (["time"], np.array(sweep.azimuth)),
-            "elevation": (["time"], np.array(sweep.elevation)),
-            "range": (["range"], np.array(sweep.range)),
-        }
-
-        # Data variables (moments)
-        data_vars = {}
-        for moment_name in sweep.moment_names():
-            moment = sweep.get_moment(moment_name)
-            if moment is not None:
-                data_vars[moment_name] = (
-                    ["time", "range"],
-                    moment.data(),
-                    {"units": moment.units}
-                )
-
-        # Attributes
-        attrs = {
-            "sweep_number": int(sweep.sweep_number),
-            "fixed_angle": float(sweep.fixed_angle),
-            "instrument_name": volume_metadata.instrument_name,
-        }
-
-        return xr.Dataset(data_vars=data_vars, coords=coords, attrs=attrs)
+        groups = self.open_groups_as_dict(filename_or_obj, drop_variables=drop_variables, sweep=sweep)
+        return DataTree.from_dict(groups)
 
     @classmethod
     def guess_can_open(cls, filename_or_obj):
         """Guess if the file can be opened by this backend"""
+        if not isinstance(filename_or_obj, (str, os.PathLike)):
+            return False
         try:
-            path = str(filename_or_obj)
-            # Check file extension
-            if path.endswith((".nc", ".nc4", ".netcdf")):
-                # Could do more sophisticated checking here
-                return True
+            return radish.can_open(filename_or_obj)
         except (TypeError, AttributeError):
-            pass
-        return False
+            return False
 
 
 # For backwards compatibility
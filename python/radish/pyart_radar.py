@@ -0,0 +1,162 @@
+"""Conversion of radish volumes to Py-ART Radar objects"""
+
+from datetime import datetime, timezone
+
+import numpy as np
+
+try:
+    from pyart.core import Radar
+    PYART_AVAILABLE = True
+except ImportError:
+    PYART_AVAILABLE = False
+
+
+# Units of the instrument parameters, keyed by CfRadial variable name
+INSTRUMENT_PARAMETER_UNITS = {
+    "radar_beam_width_h": "degrees",
+    "radar_beam_width_v": "degrees",
+    "radar_antenna_gain_h": "dB",
+    "radar_antenna_gain_v": "dB",
+    "radar_receiver_bandwidth": "s-1",
+    "frequency": "s-1",
+}
+
+# Instrument parameters Py-ART keeps in `radar_calibration`
+CALIBRATION_PARAMETERS = ("r_calib_radar_constant_h", "r_calib_radar_constant_v")
+
+
+def volume_to_pyart(volume) -> "Radar":
+    """
+    Convert a volume to a Py-ART Radar.
+
+    Sweeps are stacked along the ray dimension, in volume order. Sweeps
+    with fewer gates than the longest range are padded with masked gates,
+    as are sweeps missing a moment that other sweeps have.
+    """
+    if not PYART_AVAILABLE:
+        raise ImportError(
+            "Py-ART is required for to_pyart. "
+            "Install with: pip install arm_pyart"
+        )
+
+    metadata = volume.metadata
+    sweeps = [volume.get_sweep(i) for i in range(volume.num_sweeps)]
+    ray_counts = [sweep.num_rays for sweep in sweeps]
+    num_gates = max((sweep.num_gates for sweep in sweeps), default=0)
+    longest = max(sweeps, key=lambda sweep: sweep.num_gates, default=None)
+    range_m = np.array(longest.range if longest is not None else [], dtype=np.float32)
+
+    # Ray times relative to the volume start, truncated to whole seconds
+    start = datetime.fromtimestamp(int(metadata.time_coverage_start), tz=timezone.utc)
+    epoch = _concat(sweeps, "time", dtype=np.float64)
+    time = {
+        "standard_name": "time",
+        "long_name": "time_in_seconds_since_volume_start",
+        "units": f"seconds since {start:%Y-%m-%dT%H:%M:%SZ}",
+        "calendar": "gregorian",
+        "data": epoch - start.timestamp(),
+    }
+
+    fields = {}
+    names = sorted({name for sweep in sweeps for name in sweep.moment_names()})
+    for name in names:
+        data = np.ma.masked_all((sum(ray_counts), num_gates), dtype=np.float32)
+        attrs = {}
+        row = 0
+        for sweep in sweeps:
+            moment = sweep.get_moment(name)
+            if moment is not None:
+                values = moment.masked_data()
+                data[row:row + sweep.num_rays, :sweep.num_gates] = values
+                attrs = attrs or _moment_attrs(moment)
+            row += sweep.num_rays
+        fields[name] = dict(attrs, data=data)
+
+    starts = np.cumsum([0] + ray_counts[:-1]).astype(np.int32)
+    ends = (starts + np.array(ray_counts, dtype=np.int32) - 1).astype(np.int32)
+    sweep_modes = [sweep.sweep_mode for sweep in sweeps]
+
+    radar_metadata = {
+        "Conventions": "CF/Radial",
+        "instrument_name": metadata.instrument_name,
+        "institution": metadata.institution,
+        "source": "radish",
+    }
+    if volume.history:
+        radar_metadata["history"] = volume.history
+
+    instrument_parameters, calibration = _instrument_parameters(metadata)
+
+    return Radar(
+        time,
+        _variable(range_m, "range_to_measurement_volume", "meters", standard_name="projection_range_coordinate"),
+        fields,
+        radar_metadata,
+        _scan_type(sweep_modes),
+        _variable([metadata.latitude], "latitude", "degrees_north", dtype=np.float64),
+        _variable([metadata.longitude], "longitude", "degrees_east", dtype=np.float64),
+        _variable([metadata.altitude], "altitude", "meters", dtype=np.float64),
+        _variable([sweep.sweep_number for sweep in sweeps], "sweep_index_number_0_based", "count", dtype=np.int32),
+        {"long_name": "sweep_mode", "units": "unitless", "data": np.array(sweep_modes, dtype="S")},
+        _variable([sweep.fixed_angle for sweep in sweeps], "target_angle_for_sweep", "degrees"),
+        _variable(starts, "index_of_first_ray_in_sweep", "count", dtype=np.int32),
+        _variable(ends, "index_of_last_ray_in_sweep", "count", dtype=np.int32),
+        _variable(_concat(sweeps, "azimuth"), "azimuth_angle_from_true_north", "degrees"),
+        _variable(_concat(sweeps, "elevation"), "elevation_angle_from_horizontal_plane", "degrees"),
+        instrument_parameters=instrument_parameters or None,
+        radar_calibration=calibration or None,
+    )
+
+
+def _variable(data, long_name, units, standard_name=None, dtype=np.float32):
+    """Py-ART variable dictionary"""
+    variable = {"long_name": long_name, "units": units, "data": np.asarray(data, dtype=dtype)}
+    if standard_name is not None:
+        variable["standard_name"] = standard_name
+    return variable
+
+
+def _concat(sweeps, name, dtype=np.float32):
+    """Per-ray values of every sweep, in volume order"""
+    if not sweeps:
+        return np.array([], dtype=dtype)
+    return np.concatenate([np.asarray(getattr(sweep, name), dtype=dtype) for sweep in sweeps])
+
+
+def _moment_attrs(moment):
+    """Field attributes of a moment"""
+    attrs = {"units": moment.units}
+    if moment.standard_name:
+        attrs["standard_name"] = moment.standard_name
+    if moment.long_name:
+        attrs["long_name"] = moment.long_name
+    return attrs
+
+
+def _scan_type(sweep_modes):
+    """Py-ART scan type of a volume from its sweep modes"""
+    modes = set(sweep_modes)
+    if modes <= {"azimuth_surveillance", "sector", "manual_ppi"} and modes:
+        return "ppi"
+    if modes <= {"elevation_surveillance", "manual_rhi"} and modes:
+        return "rhi"
+    if modes == {"vertical_pointing"}:
+        return "vpt"
+    return "other"
+
+
+def _instrument_parameters(metadata):
+    """Py-ART `instrument_parameters` and `radar_calibration` dictionaries"""
+    parameters = dict(metadata.instrument_parameters)
+    if metadata.frequency is not None:
+        parameters["frequency"] = metadata.frequency
+
+    instrument_parameters = {}
+    calibration = {}
+    for name, value in parameters.items():
+        if name in CALIBRATION_PARAMETERS:
+            calibration[name] = {"units": "dB", "data": np.array([value], dtype=np.float32)}
+        else:
+            units = INSTRUMENT_PARAMETER_UNITS.get(name, "")
+            instrument_parameters[name] = {"units": units, "data": np.array([value], dtype=np.float32)}
+    return instrument_parameters, calibration
@@ -0,0 +1,109 @@
+"""Readers accepting paths, URLs, bytes and file-like objects
+
+Strings and `os.PathLike` paths are read by path. Remote URLs (see
+`radish.remote`), `bytes`-like buffers and binary file-like objects are read
+from memory, never touching the disk. The backend is chosen from the file
+contents.
+
+Every reader takes `anon` and fsspec `storage_options` keyword arguments
+for remote files; they are ignored for local ones.
+"""
+
+import os
+
+from radish import _radish
+from radish.remote import fetch, is_remote
+
+def open(source, anon=False, **storage_options):
+    """
+    Read a radar file of any supported format.
+
+    `source` is a path (`str` or `os.PathLike`), an `s3://`, `gs://` or
+    `https://` URL, a `bytes` buffer or a binary file-like object. Remote
+    files are downloaded into memory first (see `radish.remote`), anonymously
+    with `anon`; other keyword arguments are passed to the fsspec filesystem
+    of `s3://` and `gs://` URLs.
+    """
+    return _radish.open(load(source, anon, storage_options))
+
+
+def scan(source, anon=False, **storage_options):
+    """Scan a radar file of any supported format for metadata only"""
+    return _radish.scan(load(source, anon, storage_options))
+
+
+def read_cfradial1(source, standardize_names=False, anon=False, **storage_options):
+    """
+    Read a CfRadial1 file from a path, URL, `bytes` buffer or file-like object.
+
+    With `standardize_names`, vendor moment names are renamed to their
+    CfRadial2 standard names.
+    """
+    data = load(source, anon, storage_options)
+    return _radish.read_cfradial1(data, standardize_names=standardize_names)
+
+
+def scan_cfradial1(source, anon=False, **storage_options):
+    """Scan a CfRadial1 file for metadata only"""
+    return _radish.scan_cfradial1(load(source, anon, storage_options))
+
+
+def read_many(sources, moments=None, n_threads=None, anon=False, **storage_options):
+    """
+    Read many radar files of any supported format in parallel.
+
+    `sources` are paths, URLs, `bytes` buffers or binary file-like objects.
+    Remote files are downloaded one after the other before reading starts.
+    With `moments`, only those moments are kept. Returns the volumes in the
+    order of `sources`.
+    """
+    data = [load(source, anon, storage_options) for source in sources]
+    return _radish.read_many(data, moments=moments, n_threads=n_threads)
+
+
+def open_lazy(source, anon=False, **storage_options):
+    """
+    Open a radar file of any supported format, reading sweeps on first access.
+
+    Remote files are downloaded once and kept in memory until closed.
+    """
+    return _radish.open_lazy(load(source, anon, storage_options))
+
+
+def read_sweep(source, index, anon=False, **storage_options):
+    """Read one sweep of a radar file of any supported format"""
+    return _radish.read_sweep(load(source, anon, storage_options), index)
+
+
+def can_open(source):
+    """
+    Whether a radar file, given by path, `bytes` or file-like object, can be read.
+
+    Remote URLs are not downloaded and give `False`.
+    """
+    if is_remote(source):
+        return False
+    return _radish.can_open(in_memory(source))
+
+
+def load(source, anon=False, storage_options=None):
+    """Path or `bytes` the Rust readers accept for a source, downloading URLs"""
+    if is_remote(source):
+        return fetch(source, anon=anon, **(storage_options or {}))
+    return in_memory(source)
+
+
+def in_memory(source):
+    """Paths as they are, file contents as `bytes` for everything else"""
+    if isinstance(source, (str, os.PathLike, bytes)):
+        return source
+    if isinstance(source, (bytearray, memoryview)):
+        return bytes(source)
+    if hasattr(source, "read"):
+        data = source.read()
+        if not isinstance(data, bytes):
+            raise TypeError("File-like objects must be opened in binary mode")
+        return data
+    raise TypeError(
+        f"Expected a path, URL, bytes or file-like object, got {type(source).__name__}"
+    )
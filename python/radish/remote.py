@@ -0,0 +1,61 @@
+"""Remote file access for radish readers
+
+Remote files are downloaded in Python and read from memory by the Rust
+backends, which do no network access of their own. `https://` URLs are
+fetched with the standard library; `s3://` and `gs://` URLs need fsspec with
+s3fs or gcsfs. With `anon`, public buckets of CfRadial or ODIM files are
+read without credentials. Only formats with a backend can be read this way:
+NEXRAD Level II objects download but do not open.
+"""
+
+import urllib.request
+from urllib.parse import urlparse
+
+# URL schemes read through fsspec
+FSSPEC_SCHEMES = ("s3", "gs", "gcs")
+
+# URL schemes read with the standard library
+HTTP_SCHEMES = ("http", "https")
+
+
+def is_remote(path) -> bool:
+    """Whether a path is a supported remote URL"""
+    if not isinstance(path, str):
+        return False
+    return urlparse(path).scheme in FSSPEC_SCHEMES + HTTP_SCHEMES
+
+
+def fetch(url, anon=False, **storage_options) -> bytes:
+    """
+    Download a remote file into memory.
+
+    `storage_options` are passed to the fsspec filesystem of `s3://` and
+    `gs://` URLs. `https://` URLs are always read anonymously and take no
+    storage options.
+    """
+    scheme = urlparse(url).scheme
+    if scheme in HTTP_SCHEMES:
+        if storage_options:
+            raise ValueError(
+                f"Storage options are not supported for {scheme}:// URLs: "
+                + ", ".join(sorted(storage_options))
+            )
+        with urllib.request.urlopen(url) as response:
+            return response.read()
+
+    try:
+        import fsspec
+    except ImportError:
+        raise ImportError(
+            f"fsspec is required to read {scheme}:// URLs. "
+            "Install with: pip install radish[remote]"
+        ) from None
+    if anon:
+        # s3fs and gcsfs spell anonymous access differently
+        storage_options = dict(storage_options)
+        if scheme == "s3":
+            storage_options.setdefault("anon", True)
+        else:
+            storage_options.setdefault("token", "anon")
+    with fsspec.open(url, "rb", **storage_options) as f:
+        return f.read()
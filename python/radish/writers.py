@@ -0,0 +1,29 @@
+"""Writers for radish volumes
+
+ODIM_H5 is written by the Rust core. CfRadial2 and Zarr are written from
+the CfRadial2 DataTree of the volume (see `VolumeData.to_datatree`), so
+they need xarray, plus netCDF4 or h5netcdf for CfRadial2 and zarr for Zarr.
+"""
+
+from radish._radish import write_odim
+
+
+def write_cfradial2(volume, path, **kwargs):
+    """
+    Write a volume to a CfRadial2 (netCDF4) file.
+
+    Keyword arguments are passed to `DataTree.to_netcdf`.
+    """
+    volume.to_datatree().to_netcdf(path, **kwargs)
+
+
+def write_zarr(volume, store, **kwargs):
+    """
+    Write a volume to a Zarr store in the CfRadial2 layout.
+
+    Keyword arguments are passed to `DataTree.to_zarr`.
+    """
+    volume.to_datatree().to_zarr(store, **kwargs)
+
+
+__all__ = ["write_cfradial2", "write_odim", "write_zarr"]
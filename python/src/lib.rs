@@ -1,17 +1,39 @@
-/// Python bindings for radish
+//! Python bindings for radish
+//!
+//! File reads, decoding, gate geolocation and processing run with the GIL
+//! released, so that Python threads reading or processing other files run
+//! concurrently.
 
 use pyo3::prelude::*;
-use pyo3::exceptions::PyRuntimeError;
-use numpy::{PyArray2, ToPyArray};
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use numpy::{IntoPyArray, PyArray1, PyArray2, PyArray3, ToPyArray};
 use ndarray::Array2;
-use std::path::PathBuf;
+use pyo3::types::{IntoPyDict, PyBytes};
+use rayon::prelude::*;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
 use radish::{
-    backends::{RadarBackend, CfRadial1Backend},
+    backends::{auto_backend, auto_backend_bytes, RadarBackend, CfRadial1Backend},
+    io::{decode_volume, encode_volume},
+    transforms::{
+        compute_kdp, dealias_with_profile, estimate_rain_rate, evad, georeference_with_options, grid_volume,
+        gridding::{RadiusOfInfluence, Weighting},
+        process_phidp,
+        qpe::PowerLaw,
+        DealiasOptions, EvadOptions, GeoreferenceOptions, GridOptions, KdpOptions, PhidpOptions, Projection,
+        RainEstimator, RainRateOptions, WindProfile,
+    },
+    writers,
+    GriddedData as RustGriddedData,
+    LazyVolume as RustLazyVolume,
     VolumeData as RustVolumeData,
     VolumeMetadata as RustVolumeMetadata,
     SweepData as RustSweepData,
     MomentData as RustMomentData,
+    NameAliases,
 };
 
 /// Python wrapper for VolumeMetadata
@@ -28,6 +50,27 @@ impl PyVolumeMetadata {
         &self.inner.instrument_name
     }
 
+    #[getter]
+    fn volume_number(&self) -> u32 {
+        self.inner.volume_number
+    }
+
+    #[getter]
+    fn institution(&self) -> &str {
+        &self.inner.institution
+    }
+
+    #[getter]
+    fn site_name(&self) -> Option<&str> {
+        self.inner.site_name.as_deref()
+    }
+
+    /// CfRadial platform type (e.g. "fixed")
+    #[getter]
+    fn platform_type(&self) -> Option<&'static str> {
+        self.inner.platform_type.map(|t| t.cf_name())
+    }
+
     #[getter]
     fn latitude(&self) -> f64 {
         self.inner.latitude
@@ -43,6 +86,49 @@ impl PyVolumeMetadata {
         self.inner.altitude
     }
 
+    /// Radar frequency (Hz)
+    #[getter]
+    fn frequency(&self) -> Option<f64> {
+        self.inner.frequency
+    }
+
+    /// Instrument parameters, keyed by CfRadial variable name
+    #[getter]
+    fn instrument_parameters(&self) -> HashMap<&'static str, f64> {
+        let Some(p) = &self.inner.instrument_parameters else {
+            return HashMap::new();
+        };
+        [
+            ("radar_beam_width_h", p.beam_width_h),
+            ("radar_beam_width_v", p.beam_width_v),
+            ("radar_antenna_gain_h", p.antenna_gain_h),
+            ("radar_antenna_gain_v", p.antenna_gain_v),
+            ("r_calib_radar_constant_h", p.radar_constant_h),
+            ("r_calib_radar_constant_v", p.radar_constant_v),
+            ("radar_receiver_bandwidth", p.receiver_bandwidth),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| Some((name, value?)))
+        .collect()
+    }
+
+    /// Start of the volume (seconds since 1970-01-01 UTC)
+    #[getter]
+    fn time_coverage_start(&self) -> f64 {
+        self.inner.time_coverage_start.timestamp_micros() as f64 / 1e6
+    }
+
+    /// End of the volume (seconds since 1970-01-01 UTC)
+    #[getter]
+    fn time_coverage_end(&self) -> f64 {
+        self.inner.time_coverage_end.timestamp_micros() as f64 / 1e6
+    }
+
+    #[getter]
+    fn sweep_group_names(&self) -> Vec<String> {
+        self.inner.sweep_group_names.clone()
+    }
+
     #[getter]
     fn sweep_fixed_angles(&self) -> Vec<f64> {
         self.inner.sweep_fixed_angles.clone()
@@ -53,6 +139,22 @@ impl PyVolumeMetadata {
         self.inner.sweep_group_names.len()
     }
 
+    /// All metadata as a dict of plain, JSON-serializable values
+    ///
+    /// Times are ISO 8601 strings, modes and the platform type CfRadial
+    /// names and instrument parameters keyed by CfRadial variable name.
+    /// Missing values are `None`.
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let mut value = to_json(&self.inner)?;
+        value["platform_type"] = self.platform_type().into();
+        value["instrument_parameters"] = to_json(&self.instrument_parameters())?;
+        if let Some(strategy) = &self.inner.scan_strategy {
+            value["scan_strategy"]["sweep_modes"] =
+                strategy.sweep_modes.iter().map(|m| m.cf_name()).collect::<Vec<_>>().into();
+        }
+        json_to_py(py, &value)
+    }
+
     fn __repr__(&self) -> String {
         format!(
             "VolumeMetadata(instrument='{}', lat={:.4}, lon={:.4}, alt={:.1}, sweeps={})",
@@ -66,9 +168,34 @@ impl PyVolumeMetadata {
 }
 
 /// Python wrapper for MomentData
-#[pyclass(name = "MomentData")]
+///
+/// The values are shared with the sweep the moment came from, and exposed
+/// to numpy as read-only views owned by this object, so that no array is
+/// copied. Packed moments are decoded once, on the first `data()` call.
+#[pyclass(name = "MomentData", frozen)]
 pub struct PyMomentData {
     inner: RustMomentData,
+    decoded: OnceLock<Array2<f32>>,
+}
+
+impl PyMomentData {
+    fn new(inner: RustMomentData) -> Self {
+        Self {
+            inner,
+            decoded: OnceLock::new(),
+        }
+    }
+
+    /// Physical values, borrowed from the storage or from the decoded cache
+    fn values(&self) -> &Array2<f32> {
+        if let Some(decoded) = self.decoded.get() {
+            return decoded;
+        }
+        match self.inner.data() {
+            Cow::Borrowed(data) => data,
+            Cow::Owned(data) => self.decoded.get_or_init(|| data),
+        }
+    }
 }
 
 #[pymethods]
@@ -83,13 +210,74 @@ impl PyMomentData {
         &self.inner.units
     }
 
+    #[getter]
+    fn standard_name(&self) -> Option<&str> {
+        self.inner.standard_name.as_deref()
+    }
+
+    #[getter]
+    fn long_name(&self) -> Option<&str> {
+        self.inner.long_name.as_deref()
+    }
+
     #[getter]
     fn shape(&self) -> (usize, usize) {
         self.inner.shape()
     }
 
-    fn data<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyArray2<f32>>> {
-        Ok(self.inner.data.to_pyarray_bound(py))
+    /// Read-only view of the physical values, without copying
+    fn data<'py>(slf: &Bound<'py, Self>) -> PyResult<Bound<'py, PyArray2<f32>>> {
+        let py = slf.py();
+        let this = slf.get();
+        let values = py.allow_threads(|| this.values());
+        // SAFETY: the values are owned by this frozen object, which the view
+        // keeps alive, and are never moved or modified: the storage is shared
+        // copy-on-write and the decoded cache is set once.
+        let array = unsafe { PyArray2::borrow_from_array_bound(values, slf.clone().into_any()) };
+        array.call_method("setflags", (), Some(&[("write", false)].into_py_dict_bound(py)))?;
+        Ok(array)
+    }
+
+    /// Data with invalid gates masked
+    ///
+    /// Returns a `numpy.ma.MaskedArray` over the `data()` view, masking
+    /// gates at the fill value or outside the valid range, with the fill
+    /// value (NaN for packed moments) as its `fill_value`. With `fill_with`,
+    /// returns a plain array with invalid gates set to that value instead,
+    /// e.g. `fill_with=np.nan`; a view when every gate is valid, a copy
+    /// otherwise.
+    #[pyo3(signature = (fill_with=None))]
+    fn masked_data<'py>(slf: &Bound<'py, Self>, fill_with: Option<f32>) -> PyResult<Bound<'py, PyAny>> {
+        let py = slf.py();
+        let this = slf.get();
+        let validity = py.allow_threads(|| this.inner.validity());
+        let all_valid = validity.iter().all(|&valid| valid);
+
+        let Some(fill) = fill_with else {
+            let data = Self::data(slf)?;
+            let kwargs = [("fill_value", this.inner.missing_value().into_py(py))].into_py_dict_bound(py);
+            if !all_valid {
+                kwargs.set_item("mask", validity.mapv(|valid| !valid).into_pyarray_bound(py))?;
+            }
+            return py
+                .import_bound("numpy.ma")?
+                .getattr("MaskedArray")?
+                .call((data,), Some(&kwargs));
+        };
+
+        if all_valid {
+            return Ok(Self::data(slf)?.into_any());
+        }
+        let data = py.allow_threads(|| {
+            let mut data = this.values().clone();
+            data.zip_mut_with(&validity, |v, &valid| {
+                if !valid {
+                    *v = fill;
+                }
+            });
+            data
+        });
+        Ok(data.into_pyarray_bound(py).into_any())
     }
 
     fn __repr__(&self) -> String {
@@ -108,6 +296,27 @@ impl PyMomentData {
 #[pyclass(name = "SweepData")]
 pub struct PySweepData {
     inner: RustSweepData,
+    /// Radar longitude, latitude (degrees) and altitude (meters) for gate geolocation
+    location: (f64, f64, f64),
+}
+
+impl PySweepData {
+    /// Compute and cache the gate x/y/z coordinates without holding the GIL
+    fn georeference(&mut self, py: Python<'_>) {
+        let coordinates = &mut self.inner.coordinates;
+        py.allow_threads(|| {
+            coordinates.gate_xyz();
+        });
+    }
+
+    /// Compute and cache the gate longitude/latitude/altitude without holding the GIL
+    fn geolocate(&mut self, py: Python<'_>) {
+        let (lon, lat, alt) = self.location;
+        let coordinates = &mut self.inner.coordinates;
+        py.allow_threads(|| {
+            coordinates.gate_geographic(lon, lat, alt);
+        });
+    }
 }
 
 #[pymethods]
@@ -122,6 +331,12 @@ impl PySweepData {
         self.inner.metadata.fixed_angle
     }
 
+    /// CfRadial sweep mode (e.g. "azimuth_surveillance")
+    #[getter]
+    fn sweep_mode(&self) -> &'static str {
+        self.inner.metadata.sweep_mode.cf_name()
+    }
+
     #[getter]
     fn num_rays(&self) -> usize {
         self.inner.num_rays()
@@ -137,9 +352,8 @@ impl PySweepData {
     }
 
     fn get_moment(&self, name: &str) -> Option<PyMomentData> {
-        self.inner.get_moment(name).map(|m| PyMomentData {
-            inner: m.clone(),
-        })
+        // Cloning shares the values with the sweep
+        self.inner.get_moment(name).map(|m| PyMomentData::new(m.clone()))
     }
 
     #[getter]
@@ -157,6 +371,150 @@ impl PySweepData {
         self.inner.coordinates.range.clone()
     }
 
+    /// Gate x distance east of the radar [rays × gates] (meters)
+    #[getter]
+    fn gate_x<'py>(&mut self, py: Python<'py>) -> Bound<'py, PyArray2<f32>> {
+        self.georeference(py);
+        self.inner.coordinates.gate_xyz().0.to_pyarray_bound(py)
+    }
+
+    /// Gate y distance north of the radar [rays × gates] (meters)
+    #[getter]
+    fn gate_y<'py>(&mut self, py: Python<'py>) -> Bound<'py, PyArray2<f32>> {
+        self.georeference(py);
+        self.inner.coordinates.gate_xyz().1.to_pyarray_bound(py)
+    }
+
+    /// Gate height above the radar [rays × gates] (meters)
+    #[getter]
+    fn gate_z<'py>(&mut self, py: Python<'py>) -> Bound<'py, PyArray2<f32>> {
+        self.georeference(py);
+        self.inner.coordinates.gate_xyz().2.to_pyarray_bound(py)
+    }
+
+    /// Gate longitude [rays × gates] (degrees east)
+    #[getter]
+    fn gate_longitude<'py>(&mut self, py: Python<'py>) -> Bound<'py, PyArray2<f64>> {
+        let (lon, lat, alt) = self.location;
+        self.geolocate(py);
+        self.inner.coordinates.gate_geographic(lon, lat, alt).0.to_pyarray_bound(py)
+    }
+
+    /// Gate latitude [rays × gates] (degrees north)
+    #[getter]
+    fn gate_latitude<'py>(&mut self, py: Python<'py>) -> Bound<'py, PyArray2<f64>> {
+        let (lon, lat, alt) = self.location;
+        self.geolocate(py);
+        self.inner.coordinates.gate_geographic(lon, lat, alt).1.to_pyarray_bound(py)
+    }
+
+    /// Gate altitude above mean sea level [rays × gates] (meters)
+    #[getter]
+    fn gate_altitude<'py>(&mut self, py: Python<'py>) -> Bound<'py, PyArray2<f32>> {
+        let (lon, lat, alt) = self.location;
+        self.geolocate(py);
+        self.inner.coordinates.gate_geographic(lon, lat, alt).2.to_pyarray_bound(py)
+    }
+
+    /// Time of each ray (seconds since 1970-01-01 UTC)
+    #[getter]
+    fn time(&self) -> Vec<f64> {
+        self.inner.coordinates.epoch_times()
+    }
+
+    /// Time of the earliest ray (seconds since 1970-01-01 UTC)
+    #[getter]
+    fn time_start(&self) -> Option<f64> {
+        self.inner.time_start().map(|t| t.timestamp_micros() as f64 / 1e6)
+    }
+
+    /// Time of the latest ray (seconds since 1970-01-01 UTC)
+    #[getter]
+    fn time_end(&self) -> Option<f64> {
+        self.inner.time_end().map(|t| t.timestamp_micros() as f64 / 1e6)
+    }
+
+    /// Midpoint of the sweep time coverage (seconds since 1970-01-01 UTC)
+    #[getter]
+    fn time_mid(&self) -> Option<f64> {
+        self.inner.time_mid().map(|t| t.timestamp_micros() as f64 / 1e6)
+    }
+
+    /// Sweep metadata as a dict of plain, JSON-serializable values
+    ///
+    /// Holds the sweep metadata, with CfRadial mode names, its shape and
+    /// start and end times (ISO 8601 strings), and the units and names of
+    /// each moment. Missing values are `None`.
+    fn metadata_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let metadata = &self.inner.metadata;
+        let mut value = to_json(metadata)?;
+        value["sweep_mode"] = metadata.sweep_mode.cf_name().into();
+        value["follow_mode"] = metadata.follow_mode.map(|m| m.cf_name()).into();
+        value["prt_mode"] = metadata.prt_mode.map(|m| m.cf_name()).into();
+        value["num_rays"] = self.num_rays().into();
+        value["num_gates"] = self.num_gates().into();
+        value["time_start"] = to_json(&self.inner.time_start())?;
+        value["time_end"] = to_json(&self.inner.time_end())?;
+        let moments: serde_json::Map<String, serde_json::Value> = self
+            .inner
+            .moments
+            .iter()
+            .map(|(name, moment)| {
+                let attrs = serde_json::json!({
+                    "units": moment.units,
+                    "standard_name": moment.standard_name,
+                    "long_name": moment.long_name,
+                });
+                (name.clone(), attrs)
+            })
+            .collect();
+        value["moments"] = moments.into();
+        json_to_py(py, &value)
+    }
+
+    /// Convert to an `xarray.Dataset` with azimuth (or elevation for RHIs)
+    /// and range coordinates, CF attributes and datetime64 times
+    fn to_xarray<'py>(slf: Bound<'py, Self>) -> PyResult<Bound<'py, PyAny>> {
+        let (lon, lat, alt) = slf.borrow().location;
+        slf.py()
+            .import_bound("radish.backends.xarray_backend")?
+            .call_method1("sweep_to_dataset", (slf, lat, lon, alt))
+    }
+
+    /// Encode the sweep and the radar location in the binary cache format
+    fn to_bytes<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        let (lon, lat, alt) = self.location;
+        let time = self.inner.time_start().unwrap_or_default();
+        let metadata = RustVolumeMetadata::new(String::new(), lat, lon, alt, time, time);
+        let volume = RustVolumeData::new(metadata, vec![self.inner.clone()]);
+        let bytes = py
+            .allow_threads(|| encode_volume(&volume))
+            .map_err(|e| PyRuntimeError::new_err(format!("Failed to encode sweep: {}", e)))?;
+        Ok(PyBytes::new_bound(py, &bytes))
+    }
+
+    /// Decode a sweep encoded by `to_bytes`
+    #[staticmethod]
+    fn from_bytes(py: Python<'_>, data: &[u8]) -> PyResult<Self> {
+        let volume = py
+            .allow_threads(|| decode_volume(data))
+            .map_err(|e| PyRuntimeError::new_err(format!("Failed to decode sweep: {}", e)))?;
+        let meta = &volume.metadata;
+        let location = (meta.longitude, meta.latitude, meta.altitude);
+        let inner = volume
+            .sweeps
+            .into_iter()
+            .next()
+            .ok_or_else(|| PyRuntimeError::new_err("Failed to decode sweep: no sweep"))?;
+        Ok(PySweepData { inner, location })
+    }
+
+    /// Pickle through the binary cache format
+    fn __reduce__<'py>(slf: &Bound<'py, Self>) -> PyResult<(Bound<'py, PyAny>, (Bound<'py, PyBytes>,))> {
+        let bytes = slf.borrow().to_bytes(slf.py())?;
+        Ok((slf.get_type().getattr("from_bytes")?, (bytes,)))
+    }
+
     fn __repr__(&self) -> String {
         format!(
             "SweepData(sweep={}, angle={:.2}°, rays={}, gates={}, moments={})",
@@ -175,6 +533,18 @@ pub struct PyVolumeData {
     inner: RustVolumeData,
 }
 
+impl PyVolumeData {
+    /// Run a transform on the volume with the GIL released
+    fn transform<F>(&self, py: Python<'_>, transform: F) -> PyResult<PyVolumeData>
+    where
+        F: FnOnce(&RustVolumeData) -> radish::Result<RustVolumeData> + Send,
+    {
+        py.allow_threads(|| transform(&self.inner))
+            .map(|volume| PyVolumeData { inner: volume })
+            .map_err(|e| PyRuntimeError::new_err(format!("Failed to process volume: {}", e)))
+    }
+}
+
 #[pymethods]
 impl PyVolumeData {
     #[getter]
@@ -190,14 +560,343 @@ impl PyVolumeData {
     }
 
     fn get_sweep(&self, index: usize) -> PyResult<PySweepData> {
+        let meta = &self.inner.metadata;
         self.inner
             .get_sweep(index)
             .map(|s| PySweepData {
                 inner: s.clone(),
+                location: (meta.longitude, meta.latitude, meta.altitude),
             })
             .ok_or_else(|| PyRuntimeError::new_err(format!("Invalid sweep index: {}", index)))
     }
 
+    /// The CF `history` attribute: history read from the file followed by
+    /// the processing history
+    #[getter]
+    fn history(&self) -> Option<String> {
+        self.inner.history_attribute()
+    }
+
+    /// Compute the gate coordinates of every sweep
+    ///
+    /// Returns a new volume whose sweeps' `gate_x`, `gate_y` and `gate_z`
+    /// use the given beam propagation model.
+    #[pyo3(signature = (*, earth_radius=None, effective_radius_factor=None))]
+    fn georeference(
+        &self,
+        py: Python<'_>,
+        earth_radius: Option<f64>,
+        effective_radius_factor: Option<f64>,
+    ) -> PyResult<PyVolumeData> {
+        let defaults = GeoreferenceOptions::default();
+        let options = GeoreferenceOptions {
+            earth_radius: earth_radius.unwrap_or(defaults.earth_radius),
+            effective_radius_factor: effective_radius_factor.unwrap_or(defaults.effective_radius_factor),
+        };
+        self.transform(py, |volume| georeference_with_options(volume, &options))
+    }
+
+    /// Retrieve the wind profile of the volume by EVAD
+    ///
+    /// Returns a dict of numpy arrays, one value per height layer: `height`
+    /// (meters above mean sea level), `u`, `v`, `vertical_velocity` (m/s),
+    /// `divergence`, `stretching_deformation`, `shearing_deformation` (1/s)
+    /// and `rms_residual` (m/s).
+    #[pyo3(signature = (*, moment=None, layer_depth=None, max_height=None))]
+    fn wind_profile<'py>(
+        &self,
+        py: Python<'py>,
+        moment: Option<String>,
+        layer_depth: Option<f64>,
+        max_height: Option<f64>,
+    ) -> PyResult<HashMap<&'static str, Bound<'py, PyArray1<f64>>>> {
+        let defaults = EvadOptions::default();
+        let options = EvadOptions {
+            moment: moment.unwrap_or(defaults.moment),
+            layer_depth: layer_depth.unwrap_or(defaults.layer_depth),
+            max_height: max_height.unwrap_or(defaults.max_height),
+            ..defaults
+        };
+        let profile = py
+            .allow_threads(|| evad(&self.inner, &options))
+            .map_err(|e| PyRuntimeError::new_err(format!("Failed to retrieve wind profile: {}", e)))?;
+        Ok(HashMap::from([
+            ("height", profile.height.into_pyarray_bound(py)),
+            ("u", profile.u.into_pyarray_bound(py)),
+            ("v", profile.v.into_pyarray_bound(py)),
+            ("vertical_velocity", profile.vertical_velocity.into_pyarray_bound(py)),
+            ("divergence", profile.divergence.into_pyarray_bound(py)),
+            ("stretching_deformation", profile.stretching_deformation.into_pyarray_bound(py)),
+            ("shearing_deformation", profile.shearing_deformation.into_pyarray_bound(py)),
+            ("rms_residual", profile.rms_residual.into_pyarray_bound(py)),
+        ]))
+    }
+
+    /// Dealias radial velocities with a wind profile as first guess
+    ///
+    /// `profile` is a `(height, u, v)` tuple of sequences (meters above mean
+    /// sea level, m/s), such as the `wind_profile()` arrays; the EVAD
+    /// profile of `moment` is used if not given. Returns a new volume with
+    /// `output_moment` added.
+    #[pyo3(signature = (
+        *,
+        profile=None,
+        moment=None,
+        output_moment=None,
+        nyquist_velocity=None,
+        window=None,
+        max_iterations=None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn dealias(
+        &self,
+        py: Python<'_>,
+        profile: Option<(Vec<f64>, Vec<f64>, Vec<f64>)>,
+        moment: Option<String>,
+        output_moment: Option<String>,
+        nyquist_velocity: Option<f64>,
+        window: Option<usize>,
+        max_iterations: Option<usize>,
+    ) -> PyResult<PyVolumeData> {
+        let defaults = DealiasOptions::default();
+        let options = DealiasOptions {
+            moment: moment.unwrap_or(defaults.moment),
+            output_moment: output_moment.unwrap_or(defaults.output_moment),
+            nyquist_velocity: nyquist_velocity.or(defaults.nyquist_velocity),
+            window: window.unwrap_or(defaults.window),
+            max_iterations: max_iterations.unwrap_or(defaults.max_iterations),
+            ..defaults
+        };
+        self.transform(py, |volume| {
+            let profile = match profile {
+                Some((height, u, v)) => WindProfile::new(height, u, v)?,
+                None => {
+                    let evad_options = EvadOptions {
+                        moment: options.moment.clone(),
+                        ..EvadOptions::default()
+                    };
+                    evad(volume, &evad_options)?.to_wind_profile()?
+                }
+            };
+            dealias_with_profile(volume, &profile, &options)
+        })
+    }
+
+    /// Remove the system phase offset, unfold and smooth PHIDP
+    ///
+    /// Returns a new volume with `output_moment` added. The system phase is
+    /// taken from the calibration, or estimated from the data, if not given.
+    #[pyo3(signature = (
+        *,
+        moment=None,
+        rhohv=None,
+        reflectivity=None,
+        output_moment=None,
+        system_phase=None,
+        wrap=None,
+        min_rhohv=None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn process_phidp(
+        &self,
+        py: Python<'_>,
+        moment: Option<String>,
+        rhohv: Option<String>,
+        reflectivity: Option<String>,
+        output_moment: Option<String>,
+        system_phase: Option<f32>,
+        wrap: Option<f32>,
+        min_rhohv: Option<f32>,
+    ) -> PyResult<PyVolumeData> {
+        let defaults = PhidpOptions::default();
+        let options = PhidpOptions {
+            moment: moment.unwrap_or(defaults.moment),
+            rhohv: rhohv.unwrap_or(defaults.rhohv),
+            reflectivity: reflectivity.unwrap_or(defaults.reflectivity),
+            output_moment: output_moment.unwrap_or(defaults.output_moment),
+            system_phase: system_phase.or(defaults.system_phase),
+            wrap: wrap.or(defaults.wrap),
+            min_rhohv: min_rhohv.unwrap_or(defaults.min_rhohv),
+            ..defaults
+        };
+        self.transform(py, |volume| process_phidp(volume, &options))
+    }
+
+    /// Estimate KDP from processed PHIDP
+    ///
+    /// Returns a new volume with `output_moment` added.
+    #[pyo3(signature = (*, moment=None, output_moment=None, window=None, min_gates=None))]
+    fn compute_kdp(
+        &self,
+        py: Python<'_>,
+        moment: Option<String>,
+        output_moment: Option<String>,
+        window: Option<usize>,
+        min_gates: Option<usize>,
+    ) -> PyResult<PyVolumeData> {
+        let defaults = KdpOptions::default();
+        let options = KdpOptions {
+            moment: moment.unwrap_or(defaults.moment),
+            output_moment: output_moment.unwrap_or(defaults.output_moment),
+            window: window.unwrap_or(defaults.window),
+            min_gates: min_gates.unwrap_or(defaults.min_gates),
+        };
+        self.transform(py, |volume| compute_kdp(volume, &options))
+    }
+
+    /// Estimate the rain rate (mm/h)
+    ///
+    /// `estimator` is one of "reflectivity", "kdp", "specific_attenuation"
+    /// or "blended", and `z_r` the `(a, b)` coefficients of `Z = a·R^b`.
+    /// Returns a new volume with `output_moment` added. With `uncertainty`,
+    /// the `RATE_SPREAD` and `RATE_QUALITY` moments are added too, the
+    /// quality index scaled by the `quality` moment if given.
+    #[pyo3(signature = (
+        *,
+        estimator=None,
+        reflectivity=None,
+        kdp=None,
+        specific_attenuation=None,
+        z_r=None,
+        min_reflectivity=None,
+        uncertainty=false,
+        quality=None,
+        output_moment=None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn estimate_rain_rate(
+        &self,
+        py: Python<'_>,
+        estimator: Option<&str>,
+        reflectivity: Option<String>,
+        kdp: Option<String>,
+        specific_attenuation: Option<String>,
+        z_r: Option<(f64, f64)>,
+        min_reflectivity: Option<f32>,
+        uncertainty: bool,
+        quality: Option<String>,
+        output_moment: Option<String>,
+    ) -> PyResult<PyVolumeData> {
+        let defaults = RainRateOptions::default();
+        let estimator = match estimator {
+            None => defaults.estimator,
+            Some("reflectivity") => RainEstimator::Reflectivity,
+            Some("kdp") => RainEstimator::Kdp,
+            Some("specific_attenuation") => RainEstimator::SpecificAttenuation,
+            Some("blended") => RainEstimator::Blended,
+            Some(other) => return Err(PyValueError::new_err(format!("Unknown rain rate estimator: {}", other))),
+        };
+        let options = RainRateOptions {
+            estimator,
+            reflectivity: reflectivity.unwrap_or(defaults.reflectivity),
+            kdp: kdp.unwrap_or(defaults.kdp),
+            specific_attenuation: specific_attenuation.unwrap_or(defaults.specific_attenuation),
+            z_r: z_r.map_or(defaults.z_r, |(a, b)| PowerLaw::new(a, b)),
+            min_reflectivity: min_reflectivity.unwrap_or(defaults.min_reflectivity),
+            uncertainty,
+            quality,
+            output_moment: output_moment.unwrap_or(defaults.output_moment),
+            ..defaults
+        };
+        self.transform(py, |volume| estimate_rain_rate(volume, &options))
+    }
+
+    /// Grid the volume onto a regular Cartesian grid
+    ///
+    /// Limits are in projected units (meters) for x and y and meters above
+    /// mean sea level for z, and `shape` is `(nz, ny, nx)`. `projection` is
+    /// a PROJ string, the grid being azimuthal equidistant about the radar
+    /// if not given. `weighting` is one of "nearest", "cressman" or
+    /// "barnes"; `roi` is a constant radius of influence (meters), growing
+    /// with the beam width if not given.
+    #[pyo3(signature = (
+        *,
+        x_limits=None,
+        y_limits=None,
+        z_limits=None,
+        shape=None,
+        moments=None,
+        weighting=None,
+        roi=None,
+        projection=None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn grid(
+        &self,
+        py: Python<'_>,
+        x_limits: Option<(f64, f64)>,
+        y_limits: Option<(f64, f64)>,
+        z_limits: Option<(f64, f64)>,
+        shape: Option<(usize, usize, usize)>,
+        moments: Option<Vec<String>>,
+        weighting: Option<&str>,
+        roi: Option<f64>,
+        projection: Option<&str>,
+    ) -> PyResult<PyGriddedData> {
+        let defaults = GridOptions::default();
+        let weighting = match weighting {
+            None => defaults.weighting,
+            Some("nearest") => Weighting::Nearest,
+            Some("cressman") => Weighting::Cressman,
+            Some("barnes") => Weighting::Barnes,
+            Some(other) => return Err(PyValueError::new_err(format!("Unknown weighting: {}", other))),
+        };
+        let projection = projection
+            .map(Projection::from_proj4)
+            .transpose()
+            .map_err(|e| PyValueError::new_err(format!("Invalid projection: {}", e)))?;
+        let options = GridOptions {
+            projection,
+            x_limits: x_limits.unwrap_or(defaults.x_limits),
+            y_limits: y_limits.unwrap_or(defaults.y_limits),
+            z_limits: z_limits.unwrap_or(defaults.z_limits),
+            shape: shape.unwrap_or(defaults.shape),
+            moments: moments.unwrap_or(defaults.moments),
+            weighting,
+            roi: roi.map_or(defaults.roi, RadiusOfInfluence::Constant),
+        };
+        py.allow_threads(|| grid_volume(&self.inner, &options))
+            .map(|grid| PyGriddedData { inner: grid })
+            .map_err(|e| PyRuntimeError::new_err(format!("Failed to grid volume: {}", e)))
+    }
+
+    /// Convert to a Py-ART `Radar`
+    fn to_pyart<'py>(slf: Bound<'py, Self>) -> PyResult<Bound<'py, PyAny>> {
+        slf.py()
+            .import_bound("radish.pyart_radar")?
+            .call_method1("volume_to_pyart", (slf,))
+    }
+
+    /// Convert to an `xarray.DataTree` in the CfRadial2 layout used by
+    /// xradar: volume metadata at the root and one `sweep_N` group per sweep
+    fn to_datatree<'py>(slf: Bound<'py, Self>) -> PyResult<Bound<'py, PyAny>> {
+        slf.py()
+            .import_bound("radish.backends.xarray_backend")?
+            .call_method1("volume_to_datatree", (slf,))
+    }
+
+    /// Encode the volume in the binary cache format
+    fn to_bytes<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        let bytes = py
+            .allow_threads(|| encode_volume(&self.inner))
+            .map_err(|e| PyRuntimeError::new_err(format!("Failed to encode volume: {}", e)))?;
+        Ok(PyBytes::new_bound(py, &bytes))
+    }
+
+    /// Decode a volume encoded by `to_bytes`
+    #[staticmethod]
+    fn from_bytes(py: Python<'_>, data: &[u8]) -> PyResult<Self> {
+        py.allow_threads(|| decode_volume(data))
+            .map(|volume| PyVolumeData { inner: volume })
+            .map_err(|e| PyRuntimeError::new_err(format!("Failed to decode volume: {}", e)))
+    }
+
+    /// Pickle through the binary cache format
+    fn __reduce__<'py>(slf: &Bound<'py, Self>) -> PyResult<(Bound<'py, PyAny>, (Bound<'py, PyBytes>,))> {
+        let bytes = slf.borrow().to_bytes(slf.py())?;
+        Ok((slf.get_type().getattr("from_bytes")?, (bytes,)))
+    }
+
     fn __repr__(&self) -> String {
         format!(
             "VolumeData(instrument='{}', sweeps={})",
@@ -207,30 +906,411 @@ impl PyVolumeData {
     }
 }
 
-/// Read a CfRadial1 file
+/// Python wrapper for GriddedData
+#[pyclass(name = "GriddedData")]
+pub struct PyGriddedData {
+    inner: RustGriddedData,
+}
+
+#[pymethods]
+impl PyGriddedData {
+    /// Nominal time of the grid (seconds since 1970-01-01 UTC)
+    #[getter]
+    fn time(&self) -> f64 {
+        self.inner.time.timestamp_micros() as f64 / 1e6
+    }
+
+    /// Grid x coordinates (projected units, usually meters)
+    #[getter]
+    fn x<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
+        PyArray1::from_slice_bound(py, &self.inner.x)
+    }
+
+    /// Grid y coordinates (projected units, usually meters)
+    #[getter]
+    fn y<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
+        PyArray1::from_slice_bound(py, &self.inner.y)
+    }
+
+    /// Grid levels (meters above mean sea level)
+    #[getter]
+    fn z<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
+        PyArray1::from_slice_bound(py, &self.inner.z)
+    }
+
+    #[getter]
+    fn shape(&self) -> (usize, usize, usize) {
+        (self.inner.z.len(), self.inner.y.len(), self.inner.x.len())
+    }
+
+    /// Projection of the x/y axes
+    #[getter]
+    fn projection(&self) -> String {
+        format!("{:?}", self.inner.projection)
+    }
+
+    #[getter]
+    fn attributes(&self) -> HashMap<String, String> {
+        self.inner.attributes.clone()
+    }
+
+    fn field_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.inner.fields.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Field values as a `(nz, ny, nx)` array, NaN where missing
+    fn get_field<'py>(&self, py: Python<'py>, name: &str) -> Option<Bound<'py, PyArray3<f32>>> {
+        let field = self.inner.fields.get(name)?;
+        let data = py.allow_threads(|| {
+            field.data.mapv(|v| if field.is_valid_value(v) { v } else { f32::NAN })
+        });
+        Some(data.into_pyarray_bound(py))
+    }
+
+    /// Field attributes: units, standard and long names and any others
+    fn get_field_attrs(&self, name: &str) -> Option<HashMap<String, String>> {
+        let field = self.inner.fields.get(name)?;
+        let mut attrs = field.attributes.clone();
+        attrs.insert("units".to_string(), field.units.clone());
+        if let Some(standard_name) = &field.standard_name {
+            attrs.insert("standard_name".to_string(), standard_name.clone());
+        }
+        if let Some(long_name) = &field.long_name {
+            attrs.insert("long_name".to_string(), long_name.clone());
+        }
+        Some(attrs)
+    }
+
+    /// Convert to an xarray Dataset with `z`, `y` and `x` dimensions
+    fn to_xarray<'py>(slf: Bound<'py, Self>) -> PyResult<Bound<'py, PyAny>> {
+        slf.py()
+            .import_bound("radish.backends.xarray_backend")?
+            .call_method1("grid_to_dataset", (slf,))
+    }
+
+    fn __repr__(&self) -> String {
+        let (nz, ny, nx) = self.shape();
+        format!("GriddedData(shape=({}, {}, {}), fields={:?})", nz, ny, nx, self.field_names())
+    }
+}
+
+/// Python wrapper for LazyVolume
+///
+/// Only the metadata is read when opened; each sweep is read on first
+/// access and its packed moments are decoded on first `data()` call.
+#[pyclass(name = "LazyVolume")]
+pub struct PyLazyVolume {
+    inner: Option<RustLazyVolume>,
+}
+
+impl PyLazyVolume {
+    fn volume(&self) -> PyResult<&RustLazyVolume> {
+        self.inner
+            .as_ref()
+            .ok_or_else(|| PyRuntimeError::new_err("Lazy volume is closed"))
+    }
+
+    fn sweep(&self, py: Python<'_>, index: usize) -> PyResult<PySweepData> {
+        let volume = self.volume()?;
+        let meta = volume.metadata();
+        py.allow_threads(|| volume.get_sweep(index))
+            .map(|s| PySweepData {
+                inner: s.clone(),
+                location: (meta.longitude, meta.latitude, meta.altitude),
+            })
+            .map_err(|e| PyRuntimeError::new_err(format!("Failed to read sweep: {}", e)))
+    }
+}
+
+#[pymethods]
+impl PyLazyVolume {
+    #[getter]
+    fn metadata(&self) -> PyResult<PyVolumeMetadata> {
+        Ok(PyVolumeMetadata {
+            inner: self.volume()?.metadata().clone(),
+        })
+    }
+
+    #[getter]
+    fn num_sweeps(&self) -> PyResult<usize> {
+        Ok(self.volume()?.num_sweeps())
+    }
+
+    #[getter]
+    fn closed(&self) -> bool {
+        self.inner.is_none()
+    }
+
+    /// Whether a sweep has already been read
+    fn is_loaded(&self, index: usize) -> PyResult<bool> {
+        Ok(self.volume()?.is_loaded(index))
+    }
+
+    /// Get a sweep, reading it from the file on first access
+    fn get_sweep(&self, py: Python<'_>, index: usize) -> PyResult<PySweepData> {
+        self.sweep(py, index)
+    }
+
+    /// Read every sweep not yet read and return the full volume
+    ///
+    /// Calibration data is only read by `open` and is not set.
+    fn load(&self, py: Python<'_>) -> PyResult<PyVolumeData> {
+        let volume = self.volume()?;
+        let sweeps = py
+            .allow_threads(|| {
+                (0..volume.num_sweeps())
+                    .map(|index| volume.get_sweep(index).cloned())
+                    .collect::<radish::Result<Vec<_>>>()
+            })
+            .map_err(|e| PyRuntimeError::new_err(format!("Failed to read sweep: {}", e)))?;
+        Ok(PyVolumeData {
+            inner: RustVolumeData::new(volume.metadata().clone(), sweeps),
+        })
+    }
+
+    /// Release the sweeps read so far; the volume can't be used afterwards
+    fn close(&mut self) {
+        self.inner = None;
+    }
+
+    fn __enter__(slf: Py<Self>) -> Py<Self> {
+        slf
+    }
+
+    fn __exit__(&mut self, _exc_type: PyObject, _exc_value: PyObject, _traceback: PyObject) -> bool {
+        self.close();
+        false
+    }
+
+    fn __repr__(&self) -> String {
+        match &self.inner {
+            Some(volume) => {
+                let loaded = (0..volume.num_sweeps()).filter(|&i| volume.is_loaded(i)).count();
+                format!(
+                    "LazyVolume(instrument='{}', sweeps={}, loaded={})",
+                    volume.metadata().instrument_name,
+                    volume.num_sweeps(),
+                    loaded
+                )
+            }
+            None => "LazyVolume(closed)".to_string(),
+        }
+    }
+}
+
+/// A file to read: its path, or its contents as `bytes`
+#[derive(FromPyObject)]
+enum Source<'py> {
+    #[pyo3(annotation = "bytes")]
+    Bytes(Bound<'py, PyBytes>),
+    #[pyo3(annotation = "str | os.PathLike")]
+    Path(PathBuf),
+}
+
+impl Source<'_> {
+    /// Borrow the source to read it with the GIL released
+    fn input(&self) -> Input<'_> {
+        match self {
+            Source::Bytes(bytes) => Input::Bytes(bytes.as_bytes()),
+            Source::Path(path) => Input::Path(path),
+        }
+    }
+}
+
+/// A file read from its path or from memory
+#[derive(Clone, Copy)]
+enum Input<'a> {
+    Bytes(&'a [u8]),
+    Path(&'a Path),
+}
+
+impl Input<'_> {
+    /// Backend selected from the file contents
+    fn backend(self) -> radish::Result<Box<dyn RadarBackend>> {
+        match self {
+            Input::Bytes(bytes) => auto_backend_bytes(bytes),
+            Input::Path(path) => auto_backend(path),
+        }
+    }
+
+    fn scan(self, backend: &dyn RadarBackend) -> radish::Result<RustVolumeMetadata> {
+        match self {
+            Input::Bytes(bytes) => backend.scan_bytes(bytes, None),
+            Input::Path(path) => backend.scan_file(path),
+        }
+    }
+
+    fn read_sweep(self, backend: &dyn RadarBackend, index: usize) -> radish::Result<RustSweepData> {
+        match self {
+            Input::Bytes(bytes) => backend.read_sweep_bytes(bytes, None, index),
+            Input::Path(path) => backend.read_sweep(path, index),
+        }
+    }
+
+    fn read_volume(self, backend: &dyn RadarBackend) -> radish::Result<RustVolumeData> {
+        match self {
+            Input::Bytes(bytes) => backend.read_volume_bytes(bytes, None),
+            Input::Path(path) => backend.read_volume(path),
+        }
+    }
+}
+
+/// Read a CfRadial1 file from its path or its contents as `bytes`
+///
+/// With `standardize_names`, vendor moment names are renamed to their
+/// CfRadial2 standard names.
 #[pyfunction]
-fn read_cfradial1(path: String) -> PyResult<PyVolumeData> {
-    let backend = CfRadial1Backend::new();
-    let path = PathBuf::from(path);
+#[pyo3(signature = (source, standardize_names=false))]
+fn read_cfradial1(py: Python<'_>, source: Source<'_>, standardize_names: bool) -> PyResult<PyVolumeData> {
+    let backend = if standardize_names {
+        CfRadial1Backend::with_standard_names(NameAliases::default())
+    } else {
+        CfRadial1Backend::new()
+    };
 
-    backend
-        .read_volume(&path)
+    let input = source.input();
+    py.allow_threads(|| input.read_volume(&backend))
         .map(|volume| PyVolumeData { inner: volume })
         .map_err(|e| PyRuntimeError::new_err(format!("Failed to read file: {}", e)))
 }
 
 /// Scan a CfRadial1 file for metadata only
 #[pyfunction]
-fn scan_cfradial1(path: String) -> PyResult<PyVolumeMetadata> {
+fn scan_cfradial1(py: Python<'_>, source: Source<'_>) -> PyResult<PyVolumeMetadata> {
     let backend = CfRadial1Backend::new();
-    let path = PathBuf::from(path);
 
-    backend
-        .scan_file(&path)
+    let input = source.input();
+    py.allow_threads(|| input.scan(&backend))
         .map(|metadata| PyVolumeMetadata { inner: metadata })
         .map_err(|e| PyRuntimeError::new_err(format!("Failed to scan file: {}", e)))
 }
 
+/// Read a radar file of any supported format
+///
+/// The backend is chosen from the file contents, so CfRadial, CINRAD and
+/// other formats all give the same `VolumeData`. Files are given by path or
+/// by their contents as `bytes`.
+#[pyfunction]
+fn open(py: Python<'_>, source: Source<'_>) -> PyResult<PyVolumeData> {
+    let input = source.input();
+    py.allow_threads(|| input.read_volume(input.backend()?.as_ref()))
+        .map(|volume| PyVolumeData { inner: volume })
+        .map_err(|e| PyRuntimeError::new_err(format!("Failed to read file: {}", e)))
+}
+
+/// Scan a radar file of any supported format for metadata only
+#[pyfunction]
+fn scan(py: Python<'_>, source: Source<'_>) -> PyResult<PyVolumeMetadata> {
+    let input = source.input();
+    py.allow_threads(|| input.scan(input.backend()?.as_ref()))
+        .map(|metadata| PyVolumeMetadata { inner: metadata })
+        .map_err(|e| PyRuntimeError::new_err(format!("Failed to scan file: {}", e)))
+}
+
+/// Read many radar files of any supported format in parallel
+///
+/// Files, given by path or as `bytes`, are read on `n_threads` threads (one
+/// per core if not given) with the GIL released. With `moments`, only those
+/// moments are kept. Returns the volumes in the order of `sources`, failing
+/// with the error of the first file in that order that can't be read, so
+/// neither the volumes nor the error depend on `n_threads`.
+#[pyfunction]
+#[pyo3(signature = (sources, moments=None, n_threads=None))]
+fn read_many(
+    py: Python<'_>,
+    sources: Vec<Source<'_>>,
+    moments: Option<Vec<String>>,
+    n_threads: Option<usize>,
+) -> PyResult<Vec<PyVolumeData>> {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(n_threads.unwrap_or(0))
+        .build()
+        .map_err(|e| PyRuntimeError::new_err(format!("Failed to start reader threads: {}", e)))?;
+    let names: Option<Vec<&str>> = moments.as_ref().map(|m| m.iter().map(String::as_str).collect());
+    let inputs: Vec<Input<'_>> = sources.iter().map(Source::input).collect();
+
+    py.allow_threads(|| {
+        pool.install(|| {
+            inputs
+                .par_iter()
+                .enumerate()
+                .map(|(index, input)| {
+                    let mut volume = input
+                        .backend()
+                        .and_then(|backend| input.read_volume(backend.as_ref()))
+                        .map_err(|e| match input {
+                            Input::Bytes(_) => format!("Failed to read bytes at index {}: {}", index, e),
+                            Input::Path(path) => format!("Failed to read {}: {}", path.display(), e),
+                        })?;
+                    if let Some(names) = &names {
+                        volume.filter_moments(names);
+                    }
+                    Ok(PyVolumeData { inner: volume })
+                })
+                .collect::<Vec<Result<_, String>>>()
+        })
+    })
+    .into_iter()
+    .collect::<Result<Vec<_>, String>>()
+    .map_err(PyRuntimeError::new_err)
+}
+
+/// Open a radar file of any supported format, reading sweeps on first access
+///
+/// Files given as `bytes` are copied and kept in memory until closed.
+#[pyfunction]
+fn open_lazy(py: Python<'_>, source: Source<'_>) -> PyResult<PyLazyVolume> {
+    let input = source.input();
+    py.allow_threads(|| match input {
+        Input::Bytes(bytes) => RustLazyVolume::from_bytes(bytes.to_vec(), None),
+        Input::Path(path) => RustLazyVolume::open(path),
+    })
+    .map(|volume| PyLazyVolume { inner: Some(volume) })
+    .map_err(|e| PyRuntimeError::new_err(format!("Failed to scan file: {}", e)))
+}
+
+/// Read one sweep of a radar file of any supported format
+#[pyfunction]
+fn read_sweep(py: Python<'_>, source: Source<'_>, index: usize) -> PyResult<PySweepData> {
+    let input = source.input();
+    py.allow_threads(|| {
+        let backend = input.backend()?;
+        let metadata = input.scan(backend.as_ref())?;
+        let sweep = input.read_sweep(backend.as_ref(), index)?;
+        Ok(PySweepData {
+            inner: sweep,
+            location: (metadata.longitude, metadata.latitude, metadata.altitude),
+        })
+    })
+    .map_err(|e: radish::RadishError| PyRuntimeError::new_err(format!("Failed to read sweep: {}", e)))
+}
+
+/// Write a volume to an ODIM_H5 polar volume file
+#[pyfunction]
+fn write_odim(py: Python<'_>, volume: PyRef<'_, PyVolumeData>, path: PathBuf) -> PyResult<()> {
+    let volume = &volume.inner;
+    py.allow_threads(|| writers::write_odim(volume, &path))
+        .map_err(|e| PyRuntimeError::new_err(format!("Failed to write file: {}", e)))
+}
+
+/// Whether a backend can read the file, given by path or as `bytes`
+#[pyfunction]
+fn can_open(source: Source<'_>) -> bool {
+    source.input().backend().is_ok()
+}
+
+/// Serialize a value to JSON, non-finite numbers becoming `null`
+fn to_json(value: &impl serde::Serialize) -> PyResult<serde_json::Value> {
+    serde_json::to_value(value).map_err(|e| PyRuntimeError::new_err(format!("Failed to serialize metadata: {}", e)))
+}
+
+/// Convert a JSON value to plain Python objects
+fn json_to_py<'py>(py: Python<'py>, value: &serde_json::Value) -> PyResult<Bound<'py, PyAny>> {
+    py.import_bound("json")?.call_method1("loads", (value.to_string(),))
+}
+
 /// Python module
 #[pymodule]
 fn _radish(m: &Bound<'_, PyModule>) -> PyResult<()> {
@@ -238,7 +1318,16 @@ fn _radish(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<PyVolumeMetadata>()?;
     m.add_class::<PySweepData>()?;
     m.add_class::<PyMomentData>()?;
+    m.add_class::<PyLazyVolume>()?;
+    m.add_class::<PyGriddedData>()?;
     m.add_function(wrap_pyfunction!(read_cfradial1, m)?)?;
     m.add_function(wrap_pyfunction!(scan_cfradial1, m)?)?;
+    m.add_function(wrap_pyfunction!(open, m)?)?;
+    m.add_function(wrap_pyfunction!(scan, m)?)?;
+    m.add_function(wrap_pyfunction!(open_lazy, m)?)?;
+    m.add_function(wrap_pyfunction!(read_many, m)?)?;
+    m.add_function(wrap_pyfunction!(read_sweep, m)?)?;
+    m.add_function(wrap_pyfunction!(can_open, m)?)?;
+    m.add_function(wrap_pyfunction!(write_odim, m)?)?;
     Ok(())
 }
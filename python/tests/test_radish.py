@@ -17,6 +17,12 @@ def test_import():
     assert hasattr(radish, "MomentData")
     assert hasattr(radish, "read_cfradial1")
     assert hasattr(radish, "scan_cfradial1")
+    assert hasattr(radish, "open")
+    assert hasattr(radish, "scan")
+    assert hasattr(radish, "open_lazy")
+    assert hasattr(radish, "write_cfradial2")
+    assert hasattr(radish, "write_odim")
+    assert hasattr(radish, "write_zarr")
 
 
 def test_version():
@@ -52,6 +58,151 @@ def test_scan_cfradial1():
     assert metadata.num_sweeps > 0
 
 
+def test_is_remote():
+    """Test recognizing remote URLs"""
+    from radish.remote import is_remote
+
+    assert is_remote("s3://bucket/odim/volume.h5")
+    assert is_remote("gs://bucket/cfrad.nc")
+    assert is_remote("https://example.com/cfrad.nc")
+    assert not is_remote("tests/data/test.nc")
+    assert not is_remote("/data/cfrad.nc")
+
+
+def test_https_storage_options():
+    """Test storage options are rejected for https URLs instead of dropped"""
+    from radish.remote import fetch
+
+    with pytest.raises(ValueError, match="key"):
+        fetch("https://example.com/cfrad.nc", key="secret")
+    with pytest.raises(ValueError):
+        radish.scan("https://example.com/cfrad.nc", requester_pays=True)
+
+
+def test_in_memory_sources():
+    """Test buffers and file-like objects are read from memory"""
+    import io
+    from pathlib import Path
+    from radish.readers import in_memory
+
+    assert in_memory("tests/data/test.nc") == "tests/data/test.nc"
+    assert in_memory(Path("test.nc")) == Path("test.nc")
+    assert in_memory(b"CDF\x01rest") == b"CDF\x01rest"
+    assert in_memory(bytearray(b"RSTM")) == b"RSTM"
+    assert in_memory(io.BytesIO(b"RSTMrest")) == b"RSTMrest"
+
+    with pytest.raises(TypeError):
+        in_memory(io.StringIO("text"))
+    with pytest.raises(TypeError):
+        in_memory(42)
+
+
+def test_can_open_bytes():
+    """Test backends are recognized from in-memory contents"""
+    import io
+
+    assert not radish.can_open(b"not a radar file")
+    assert not radish.can_open(io.BytesIO(b"not a radar file"))
+
+
+@pytest.mark.skip(reason="Requires test data")
+def test_read_path_bytes_and_file():
+    """Test reading from Path objects, bytes buffers and file-like objects"""
+    import io
+    from pathlib import Path
+
+    path = Path("tests/data/test.nc")
+    volume = radish.read_cfradial1(path)
+
+    from_bytes = radish.read_cfradial1(path.read_bytes())
+    assert from_bytes.num_sweeps == volume.num_sweeps
+
+    with path.open("rb") as f:
+        from_file = radish.open(f)
+    assert from_file.num_sweeps == volume.num_sweeps
+
+    data = path.read_bytes()
+    assert radish.can_open(data)
+    assert radish.read_many([data, path])[0].num_sweeps == volume.num_sweeps
+    assert radish.read_sweep(data, 0).num_rays == volume.get_sweep(0).num_rays
+    with radish.open_lazy(io.BytesIO(data)) as lazy:
+        assert lazy.num_sweeps == volume.num_sweeps
+
+
+@pytest.mark.skip(reason="Requires test data")
+def test_read_many():
+    """Test reading files in parallel, in order and with selected moments"""
+    paths = ["tests/data/test.nc", "tests/data/test.nc"]
+    name = radish.open(paths[0]).get_sweep(0).moment_names()[0]
+
+    volumes = radish.read_many(paths, moments=[name], n_threads=2)
+    assert len(volumes) == len(paths)
+    assert volumes[0].get_sweep(0).moment_names() == [name]
+
+    with pytest.raises(RuntimeError):
+        radish.read_many(["missing.nc"])
+
+
+def test_read_many_reports_first_failure():
+    """Test the reported error does not depend on the thread count"""
+    paths = [f"missing_{i}.nc" for i in range(8)]
+    for n_threads in (1, 4):
+        with pytest.raises(RuntimeError, match="missing_0.nc"):
+            radish.read_many(paths, n_threads=n_threads)
+
+
+@pytest.mark.skip(reason="Requires test data")
+def test_metadata_dicts():
+    """Test metadata dicts are plain and JSON-serializable"""
+    import json
+
+    volume = radish.read_cfradial1("tests/data/test.nc")
+    metadata = volume.metadata.to_dict()
+    assert metadata["instrument_name"] == volume.metadata.instrument_name
+    assert metadata["latitude"] == volume.metadata.latitude
+    json.dumps(metadata)
+
+    sweep = volume.get_sweep(0)
+    sweep_metadata = sweep.metadata_dict()
+    assert sweep_metadata["sweep_mode"] == sweep.sweep_mode
+    assert sweep_metadata["num_rays"] == sweep.num_rays
+    assert set(sweep_metadata["moments"]) == set(sweep.moment_names())
+    json.dumps(sweep_metadata)
+
+
+@pytest.mark.skip(reason="Requires test data")
+def test_open():
+    """Test reading a file with automatic format detection"""
+    volume = radish.open("tests/data/test.nc")
+
+    assert isinstance(volume, radish.VolumeData)
+    assert volume.num_sweeps > 0
+
+    metadata = radish.scan("tests/data/test.nc")
+    assert isinstance(metadata, radish.VolumeMetadata)
+    assert metadata.num_sweeps == volume.num_sweeps
+
+
+@pytest.mark.skip(reason="Requires test data")
+def test_open_lazy():
+    """Test reading sweeps on first access"""
+    with radish.open_lazy("tests/data/test.nc") as lazy:
+        assert isinstance(lazy, radish.LazyVolume)
+        assert lazy.num_sweeps > 0
+        assert not lazy.is_loaded(0)
+
+        sweep = lazy.get_sweep(0)
+        assert isinstance(sweep, radish.SweepData)
+        assert lazy.is_loaded(0)
+
+        volume = lazy.load()
+        assert volume.num_sweeps == lazy.num_sweeps
+
+    assert lazy.closed
+    with pytest.raises(RuntimeError):
+        lazy.get_sweep(0)
+
+
 @pytest.mark.skip(reason="Requires test data")
 def test_sweep_access():
     """Test accessing sweep data"""
@@ -64,6 +215,114 @@ def test_sweep_access():
     assert len(sweep.moment_names()) > 0
 
 
+@pytest.mark.skip(reason="Requires test data")
+def test_pickle():
+    """Test pickling volumes and sweeps"""
+    import pickle
+
+    volume = radish.read_cfradial1("tests/data/test.nc")
+    restored = pickle.loads(pickle.dumps(volume))
+    assert isinstance(restored, radish.VolumeData)
+    assert restored.num_sweeps == volume.num_sweeps
+    assert restored.metadata.instrument_name == volume.metadata.instrument_name
+
+    sweep = volume.get_sweep(0)
+    restored = pickle.loads(pickle.dumps(sweep))
+    assert restored.num_rays == sweep.num_rays
+    name = sweep.moment_names()[0]
+    np.testing.assert_array_equal(restored.get_moment(name).data(), sweep.get_moment(name).data())
+    np.testing.assert_allclose(restored.gate_latitude, sweep.gate_latitude)
+
+
+@pytest.mark.skip(reason="Requires test data")
+def test_write_odim(tmp_path):
+    """Test converting a volume to ODIM_H5"""
+    volume = radish.read_cfradial1("tests/data/test.nc")
+    path = tmp_path / "pvol.h5"
+
+    radish.write_odim(volume, str(path))
+    assert path.exists()
+
+
+@pytest.mark.skip(reason="Requires test data and xarray")
+def test_write_cfradial2(tmp_path):
+    """Test converting a volume to CfRadial2 and reading it back"""
+    import xarray as xr
+
+    volume = radish.read_cfradial1("tests/data/test.nc")
+    path = tmp_path / "cfrad2.nc"
+
+    radish.write_cfradial2(volume, str(path))
+    tree = xr.open_datatree(str(path))
+    assert tree.ds.attrs["instrument_name"] == volume.metadata.instrument_name
+    assert "sweep_0" in tree
+
+
+@pytest.mark.skip(reason="Requires test data")
+def test_transforms():
+    """Test the processing methods, which return new volumes"""
+    volume = radish.read_cfradial1("tests/data/test.nc")
+
+    processed = volume.georeference(effective_radius_factor=1.0).process_phidp().compute_kdp()
+    processed = processed.estimate_rain_rate(estimator="reflectivity")
+    sweep = processed.get_sweep(0)
+    assert "RATE" in sweep.moment_names()
+    assert "RATE" not in volume.get_sweep(0).moment_names()
+    assert "RATE_SPREAD" not in sweep.moment_names()
+
+    uncertain = processed.estimate_rain_rate(uncertainty=True).get_sweep(0)
+    assert {"RATE_SPREAD", "RATE_QUALITY"} <= set(uncertain.moment_names())
+
+    with pytest.raises(ValueError):
+        volume.estimate_rain_rate(estimator="unknown")
+
+
+@pytest.mark.skip(reason="Requires test data and xarray")
+def test_grid():
+    """Test gridding a volume and converting the grid to xarray"""
+    volume = radish.read_cfradial1("tests/data/test.nc")
+    name = volume.get_sweep(0).moment_names()[0]
+
+    grid = volume.grid(
+        x_limits=(-10_000, 10_000),
+        y_limits=(-10_000, 10_000),
+        z_limits=(1_000, 2_000),
+        shape=(2, 21, 21),
+        moments=[name],
+        weighting="cressman",
+        roi=1_500.0,
+    )
+    assert grid.shape == (2, 21, 21)
+    assert grid.get_field(name).shape == (2, 21, 21)
+
+    ds = grid.to_xarray()
+    assert ds[name].dims == ("z", "y", "x")
+
+
+@pytest.mark.skip(reason="Requires test data")
+def test_sweep_times():
+    """Test sweep time coverage accessors"""
+    volume = radish.read_cfradial1("tests/data/test.nc")
+    sweep = volume.get_sweep(0)
+
+    assert sweep.time_start <= sweep.time_mid <= sweep.time_end
+    assert sweep.time_mid == pytest.approx((sweep.time_start + sweep.time_end) / 2)
+
+
+@pytest.mark.skip(reason="Requires test data")
+def test_gate_geolocation():
+    """Test cached gate coordinates"""
+    volume = radish.read_cfradial1("tests/data/test.nc")
+    sweep = volume.get_sweep(0)
+
+    shape = (sweep.num_rays, sweep.num_gates)
+    assert sweep.gate_x.shape == shape
+    assert sweep.gate_z.shape == shape
+    assert sweep.gate_longitude.shape == shape
+    assert sweep.gate_latitude.shape == shape
+    assert sweep.gate_altitude.min() >= volume.metadata.altitude - 1.0
+
+
 @pytest.mark.skip(reason="Requires test data")
 def test_moment_access():
     """Test accessing moment data"""
@@ -83,6 +342,66 @@ def test_moment_access():
     assert isinstance(data, np.ndarray)
     assert data.ndim == 2
 
+    # Views of the same values, not copies
+    assert not data.flags.writeable
+    assert np.shares_memory(data, moment.data())
+
+    # Invalid gates masked, or filled on request
+    masked = moment.masked_data()
+    assert isinstance(masked, np.ma.MaskedArray)
+    filled = moment.masked_data(fill_with=np.nan)
+    assert not isinstance(filled, np.ma.MaskedArray)
+    np.testing.assert_array_equal(np.isnan(filled), np.ma.getmaskarray(masked))
+
+
+@pytest.mark.skip(reason="Requires test data and xarray")
+def test_sweep_to_xarray():
+    """Test converting a sweep to an xarray Dataset"""
+    volume = radish.read_cfradial1("tests/data/test.nc")
+    sweep = volume.get_sweep(0)
+
+    ds = sweep.to_xarray()
+    assert ds.sizes["azimuth"] == sweep.num_rays
+    assert ds.sizes["range"] == sweep.num_gates
+    assert ds["time"].dtype == np.dtype("datetime64[ns]")
+    assert ds["range"].attrs["units"] == "meters"
+    assert ds.attrs["sweep_mode"] == sweep.sweep_mode
+
+    name = sweep.moment_names()[0]
+    assert ds[name].dims == ("azimuth", "range")
+    assert ds[name].attrs["units"] == sweep.get_moment(name).units
+
+
+@pytest.mark.skip(reason="Requires test data and xarray")
+def test_volume_to_datatree():
+    """Test converting a volume to a CfRadial2 DataTree"""
+    volume = radish.read_cfradial1("tests/data/test.nc")
+
+    tree = volume.to_datatree()
+    root = tree.ds
+    assert root.attrs["instrument_name"] == volume.metadata.instrument_name
+    assert root.sizes["sweep"] == volume.num_sweeps
+    assert list(root["sweep_group_name"].values) == volume.metadata.sweep_group_names
+
+    sweep_0 = tree["sweep_0"].ds
+    assert int(sweep_0["sweep_number"]) == volume.get_sweep(0).sweep_number
+    assert "azimuth" in sweep_0.coords
+    assert "range" in sweep_0.coords
+
+
+@pytest.mark.skip(reason="Requires test data and Py-ART")
+def test_volume_to_pyart():
+    """Test converting a volume to a Py-ART Radar"""
+    volume = radish.read_cfradial1("tests/data/test.nc")
+
+    radar = volume.to_pyart()
+    assert radar.nsweeps == volume.num_sweeps
+    assert radar.nrays == sum(volume.get_sweep(i).num_rays for i in range(volume.num_sweeps))
+    assert radar.latitude["data"][0] == pytest.approx(volume.metadata.latitude)
+
+    name = volume.get_sweep(0).moment_names()[0]
+    assert radar.fields[name]["data"].shape == (radar.nrays, radar.ngates)
+
 
 @pytest.mark.skip(reason="Requires test data and xarray")
 def test_xarray_backend():
@@ -98,3 +417,54 @@ def test_xarray_backend():
     assert "azimuth" in sweep_0.coords
     assert "elevation" in sweep_0.coords
     assert "range" in sweep_0.coords
+
+
+@pytest.mark.skip(reason="Requires test data and xarray")
+def test_xarray_open_dataset_group():
+    """Test opening single groups through the xarray backend"""
+    import xarray as xr
+
+    sweep_1 = xr.open_dataset("tests/data/test.nc", engine="radish", group="sweep_1")
+    assert int(sweep_1["sweep_number"]) == 1
+    assert "range" in sweep_1.coords
+
+    root = xr.open_dataset("tests/data/test.nc", engine="radish", group="/")
+    assert "sweep_fixed_angle" in root
+
+    with pytest.raises(ValueError):
+        xr.open_dataset("tests/data/test.nc", engine="radish", group="sweep_999")
+
+
+@pytest.mark.skip(reason="Requires test data and xarray")
+def test_xarray_backend_sweep_selection():
+    """Test only the selected sweeps are read into the DataTree"""
+    import xarray as xr
+
+    tree = xr.open_datatree("tests/data/test.nc", engine="radish", sweep=[1])
+    assert "sweep_1" in tree
+    assert "sweep_0" not in tree
+
+    tree = xr.open_datatree("tests/data/test.nc", engine="radish", sweep="sweep_0")
+    assert "sweep_0" in tree
+
+    with pytest.raises(IndexError):
+        xr.open_datatree("tests/data/test.nc", engine="radish", sweep=999)
+
+
+def test_backend_array_decodes_once():
+    """Test lazily indexed moments are decoded on first access only"""
+    from radish.backends.xarray_backend import RadishBackendArray
+
+    class Moment:
+        shape = (2, 3)
+        calls = 0
+
+        def masked_data(self, fill_with=None):
+            Moment.calls += 1
+            return np.arange(6, dtype=np.float32).reshape(self.shape)
+
+    array = RadishBackendArray(Moment())
+    assert Moment.calls == 0
+    assert array._raw_indexing_method((0, slice(None))).tolist() == [0, 1, 2]
+    assert array._raw_indexing_method((1, 2)) == 5
+    assert Moment.calls == 1
@@ -0,0 +1,121 @@
+//! Benchmark for metadata-only scanning of large CfRadial1 files
+//!
+//! Writes aggregated CfRadial1 files of increasing size and checks that
+//! `scan_file` time does not grow with the amount of moment data, which it
+//! would if any data variable were read. Run with:
+//!
+//!     cargo bench -p radish --bench scan_file
+//!
+//! Set `RADISH_SCAN_BENCH_MAX_MB` to change the size of the largest file.
+
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use radish::backends::{CfRadial1Backend, RadarBackend};
+
+const NUM_GATES: usize = 1000;
+const RAYS_PER_SWEEP: usize = 360;
+const MAX_SCAN_TIME: Duration = Duration::from_millis(50);
+const ITERATIONS: usize = 20;
+
+fn main() {
+    let max_mb: usize = std::env::var("RADISH_SCAN_BENCH_MAX_MB")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(1024);
+
+    let dir = tempfile::tempdir().expect("create temporary directory");
+    let backend = CfRadial1Backend::new();
+
+    let mut sizes_mb = vec![1usize];
+    while sizes_mb[sizes_mb.len() - 1] * 8 <= max_mb {
+        sizes_mb.push(sizes_mb[sizes_mb.len() - 1] * 8);
+    }
+
+    println!("{:>10} {:>8} {:>12}", "size (MB)", "sweeps", "scan (ms)");
+    for size_mb in sizes_mb {
+        // One f32 moment: 4 bytes per gate
+        let num_rays = (size_mb * 1024 * 1024 / (4 * NUM_GATES)).max(RAYS_PER_SWEEP);
+        let num_sweeps = num_rays.div_ceil(RAYS_PER_SWEEP);
+        let path = dir.path().join(format!("aggregated_{}mb.nc", size_mb));
+        write_aggregated_file(&path, num_sweeps).expect("write benchmark file");
+
+        // Warm up the file handle cache once, then take the median
+        backend.scan_file(&path).expect("scan benchmark file");
+        let mut times: Vec<Duration> = (0..ITERATIONS)
+            .map(|_| {
+                let start = Instant::now();
+                let metadata = backend.scan_file(&path).expect("scan benchmark file");
+                let elapsed = start.elapsed();
+                assert_eq!(metadata.sweep_group_names.len(), num_sweeps);
+                elapsed
+            })
+            .collect();
+        times.sort();
+        let median = times[ITERATIONS / 2];
+
+        println!(
+            "{:>10} {:>8} {:>12.3}",
+            size_mb,
+            num_sweeps,
+            median.as_secs_f64() * 1000.0
+        );
+        assert!(
+            median < MAX_SCAN_TIME,
+            "scan_file took {:?} on a {} MB file (limit {:?})",
+            median,
+            size_mb,
+            MAX_SCAN_TIME
+        );
+
+        std::fs::remove_file(&path).ok();
+    }
+}
+
+/// Write a minimal aggregated CfRadial1 file with `num_sweeps` PPI sweeps
+fn write_aggregated_file(path: &Path, num_sweeps: usize) -> netcdf::Result<()> {
+    let num_rays = num_sweeps * RAYS_PER_SWEEP;
+    let mut file = netcdf::create(path)?;
+
+    file.add_attribute("Conventions", "CF/Radial")?;
+    file.add_attribute("instrument_name", "BENCH")?;
+    file.add_attribute("time_coverage_start", "2024-01-01T00:00:00Z")?;
+    file.add_attribute("time_coverage_end", "2024-01-01T01:00:00Z")?;
+
+    file.add_dimension("time", num_rays)?;
+    file.add_dimension("range", NUM_GATES)?;
+    file.add_dimension("sweep", num_sweeps)?;
+
+    for (name, value) in [("latitude", 40.0), ("longitude", -105.0), ("altitude", 1600.0)] {
+        file.add_variable::<f64>(name, &[])?.put_values(&[value], ..)?;
+    }
+
+    let sweep_number: Vec<i32> = (0..num_sweeps as i32).collect();
+    let fixed_angle: Vec<f64> = (0..num_sweeps).map(|i| 0.5 + (i % 20) as f64).collect();
+    let start_ray: Vec<i32> = (0..num_sweeps).map(|i| (i * RAYS_PER_SWEEP) as i32).collect();
+    let end_ray: Vec<i32> = start_ray.iter().map(|s| s + RAYS_PER_SWEEP as i32 - 1).collect();
+    file.add_variable::<i32>("sweep_number", &["sweep"])?.put_values(&sweep_number, ..)?;
+    file.add_variable::<f64>("fixed_angle", &["sweep"])?.put_values(&fixed_angle, ..)?;
+    file.add_variable::<i32>("sweep_start_ray_index", &["sweep"])?.put_values(&start_ray, ..)?;
+    file.add_variable::<i32>("sweep_end_ray_index", &["sweep"])?.put_values(&end_ray, ..)?;
+
+    let time: Vec<f64> = (0..num_rays).map(|i| i as f64 * 0.1).collect();
+    let azimuth: Vec<f32> = (0..num_rays).map(|i| (i % RAYS_PER_SWEEP) as f32).collect();
+    let elevation: Vec<f32> = (0..num_rays).map(|i| fixed_angle[i / RAYS_PER_SWEEP] as f32).collect();
+    let range: Vec<f32> = (0..NUM_GATES).map(|j| 125.0 + 250.0 * j as f32).collect();
+    file.add_variable::<f64>("time", &["time"])?.put_values(&time, ..)?;
+    file.add_variable::<f32>("azimuth", &["time"])?.put_values(&azimuth, ..)?;
+    file.add_variable::<f32>("elevation", &["time"])?.put_values(&elevation, ..)?;
+    file.add_variable::<f32>("range", &["range"])?.put_values(&range, ..)?;
+
+    // Write the moment one sweep at a time to bound memory use
+    let mut dbzh = file.add_variable::<f32>("DBZH", &["time", "range"])?;
+    dbzh.put_attribute("units", "dBZ")?;
+    let block: Vec<f32> = (0..RAYS_PER_SWEEP * NUM_GATES).map(|k| (k % 70) as f32).collect();
+    for sweep in 0..num_sweeps {
+        let start = sweep * RAYS_PER_SWEEP;
+        dbzh.put_values(&block, (start..start + RAYS_PER_SWEEP, ..))?;
+    }
+
+    Ok(())
+}
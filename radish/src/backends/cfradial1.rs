@@ -1,4 +1,4 @@
-/// CfRadial1 backend for reading CF/Radial NetCDF files
+//! CfRadial1 backend for reading CF/Radial NetCDF files
 
 use std::path::Path;
 use chrono::{DateTime, Utc, TimeZone};
@@ -7,21 +7,81 @@ use std::collections::HashMap;
 
 use crate::{
     Result, RadishError,
-    VolumeData, VolumeMetadata, SweepData, SweepMetadata, MomentData, Coordinates,
-    backends::RadarBackend,
+    VolumeData, VolumeMetadata, SweepData, SweepMetadata, RayMetadata, PlatformMotion, MomentData, MomentStorage, Coordinates,
+    NameAliases,
+    backends::RadarBackend, Provenance,
+    io::{read_attributes, read_numeric_attribute, read_string_attribute},
+    io::units::{parse_time_units, record_conversions, unit_conversion, Quantity, UnitConversion},
+    model::{InstrumentParameters, ScanStrategy, ANCESTORS_ATTRIBUTE, ALGORITHM_ATTRIBUTE, ALGORITHM_VERSION_ATTRIBUTE, PARAMETERS_HASH_ATTRIBUTE},
 };
-use radish_types::{SweepMode, PlatformType};
+use netcdf::types::{BasicType, VariableType};
+use radish_types::{FollowMode, PlatformType, PrtMode, SweepMode};
+
+/// Global attributes read into metadata fields rather than `VolumeMetadata::attributes`
+const VOLUME_FIELD_ATTRIBUTES: &[&str] = &[
+    "instrument_name",
+    "institution",
+    "time_coverage_start",
+    "time_coverage_end",
+    "platform_type",
+];
+
+/// Variable attributes read into moment fields rather than `MomentData::attributes`
+const MOMENT_FIELD_ATTRIBUTES: &[&str] = &[
+    "units",
+    "standard_name",
+    "long_name",
+    "_FillValue",
+    "scale_factor",
+    "add_offset",
+    "valid_min",
+    "valid_max",
+    "coordinates",
+    ANCESTORS_ATTRIBUTE,
+    ALGORITHM_ATTRIBUTE,
+    ALGORITHM_VERSION_ATTRIBUTE,
+    PARAMETERS_HASH_ATTRIBUTE,
+];
 
 /// Backend for reading CfRadial1 format (CF/Radial NetCDF)
-pub struct CfRadial1Backend;
+pub struct CfRadial1Backend {
+    names: Option<NameAliases>,
+}
 
 impl CfRadial1Backend {
     /// Create a new CfRadial1Backend
     pub fn new() -> Self {
-        Self
+        Self { names: None }
+    }
+
+    /// Rename moments to their standard names on read
+    ///
+    /// See [`SweepData::standardize_names`].
+    pub fn with_standard_names(names: NameAliases) -> Self {
+        Self { names: Some(names) }
+    }
+
+    /// Read every sweep of an open file
+    fn read_file(&self, file: &netcdf::File) -> Result<VolumeData> {
+        // Read metadata
+        let metadata = self.read_volume_metadata(file)?;
+        let num_sweeps = metadata.sweep_group_names.len();
+
+        // Read all sweeps
+        let mut sweeps = Vec::with_capacity(num_sweeps);
+        for i in 0..num_sweeps {
+            let sweep = self.read_sweep_data(file, i)?;
+            sweeps.push(sweep);
+        }
+
+        Ok(VolumeData::new(metadata, sweeps))
     }
 
     /// Read volume metadata from NetCDF file
+    ///
+    /// Only global attributes, dimension lengths and small scalar or
+    /// sweep-dimension variables are read, never `time`/`range` sized data,
+    /// so scanning cost does not grow with the size of the file.
     fn read_volume_metadata(&self, file: &netcdf::File) -> Result<VolumeMetadata> {
         // Read required global attributes
         let instrument_name = read_string_attr(file, "instrument_name")
@@ -46,11 +106,16 @@ impl CfRadial1Backend {
             .map(|dt| dt.with_timezone(&Utc))
             .ok_or_else(|| RadishError::MissingAttribute("time_coverage_end".to_string()))?;
 
-        // Read sweep information
-        let sweep_number = read_var_1d::<i32>(file, "sweep_number")?;
-        let sweep_fixed_angle = read_var_1d::<f64>(file, "fixed_angle")?;
+        // Read sweep information; the sweep count comes from the dimension
+        // so that only the small fixed_angle variable has to be read
+        let conversions = coordinate_conversions(file)?;
+        let mut sweep_fixed_angle = read_var_1d::<f64>(file, "fixed_angle")?;
+        convert_f64(&conversions, "fixed_angle", &mut sweep_fixed_angle);
 
-        let num_sweeps = sweep_number.len();
+        let num_sweeps = match file.dimension("sweep") {
+            Some(dim) => dim.len(),
+            None => read_var_1d::<i32>(file, "sweep_number")?.len(),
+        };
         let sweep_group_names: Vec<String> = (0..num_sweeps)
             .map(|i| format!("sweep_{}", i))
             .collect();
@@ -77,6 +142,10 @@ impl CfRadial1Backend {
         metadata.sweep_group_names = sweep_group_names;
         metadata.sweep_fixed_angles = sweep_fixed_angle;
         metadata.frequency = frequency;
+        metadata.instrument_parameters = read_instrument_parameters(file);
+        metadata.attributes = read_attributes(file.attributes(), VOLUME_FIELD_ATTRIBUTES);
+        metadata.scan_strategy = ScanStrategy::from_attributes(&metadata.attributes);
+        record_conversions(&mut metadata.attributes, &conversions);
 
         Ok(metadata)
     }
@@ -97,10 +166,12 @@ impl CfRadial1Backend {
 
         // Read sweep metadata
         let sweep_number = read_var_1d::<i32>(file, "sweep_number")?;
-        let fixed_angle = read_var_1d::<f64>(file, "fixed_angle")?;
+        let conversions = coordinate_conversions(file)?;
+        let mut fixed_angle = read_var_1d::<f64>(file, "fixed_angle")?;
+        convert_f64(&conversions, "fixed_angle", &mut fixed_angle);
         let sweep_mode = read_var_1d_str(file, "sweep_mode")?;
 
-        let metadata = SweepMetadata::new(
+        let mut metadata = SweepMetadata::new(
             sweep_number[sweep_idx] as u32,
             parse_sweep_mode(&sweep_mode[sweep_idx]),
             fixed_angle[sweep_idx],
@@ -108,16 +179,100 @@ impl CfRadial1Backend {
 
         // Read coordinates
         let time = read_var_1d::<f64>(file, "time")?;
-        let range = read_var_1d::<f32>(file, "range")?;
-        let azimuth = read_var_1d::<f32>(file, "azimuth")?;
-        let elevation = read_var_1d::<f32>(file, "elevation")?;
+        let mut range = read_var_1d::<f32>(file, "range")?;
+        let mut azimuth = read_var_1d::<f32>(file, "azimuth")?;
+        let mut elevation = read_var_1d::<f32>(file, "elevation")?;
+        for (name, values) in [("range", &mut range), ("azimuth", &mut azimuth), ("elevation", &mut elevation)] {
+            if let Some(conversion) = conversions.iter().find(|c| c.variable == name) {
+                conversion.convert_slice(values);
+            }
+        }
 
-        let coordinates = Coordinates::new(
-            time[start_idx..=end_idx].to_vec(),
+        let mut ray_time = time[start_idx..=end_idx].to_vec();
+        let time_units = file
+            .variable("time")
+            .and_then(|var| read_string_attribute(var.attributes(), "units"));
+        let time_reference = match time_units {
+            Some(units) => {
+                let (factor, reference) = parse_time_units(&units)?;
+                ray_time.iter_mut().for_each(|t| *t *= factor);
+                Some(reference)
+            }
+            None => None,
+        };
+
+        let mut coordinates = Coordinates::new(
+            ray_time,
             range.clone(),
             azimuth[start_idx..=end_idx].to_vec(),
             elevation[start_idx..=end_idx].to_vec(),
         );
+        coordinates.time_reference = time_reference;
+        if let Ok(transition) = read_var_1d::<i8>(file, "antenna_transition") {
+            coordinates.antenna_transition = transition
+                .get(start_idx..=end_idx)
+                .map(|flags| flags.iter().map(|&f| f != 0).collect());
+        }
+
+        // Per-ray instrument parameters, with fill values as NaN
+        let per_ray = |name: &str| {
+            let fill = file
+                .variable(name)
+                .and_then(|var| read_numeric_attribute::<f64>(var.attributes(), "_FillValue"));
+            read_var_1d::<f64>(file, name).ok().and_then(|values| {
+                values
+                    .get(start_idx..=end_idx)
+                    .map(|v| v.iter().map(|&x| if Some(x) == fill { f64::NAN } else { x }).collect())
+            })
+        };
+        let ray_metadata = RayMetadata {
+            nyquist_velocity: per_ray("nyquist_velocity"),
+            prt: per_ray("prt"),
+            pulse_width: per_ray("pulse_width"),
+            n_samples: read_var_1d::<i32>(file, "n_samples")
+                .ok()
+                .and_then(|values| values.get(start_idx..=end_idx).map(<[i32]>::to_vec)),
+        };
+
+        let platform_motion = PlatformMotion {
+            heading: per_ray("heading"),
+            roll: per_ray("roll"),
+            pitch: per_ray("pitch"),
+            drift: per_ray("drift"),
+            velocity_east: per_ray("eastward_velocity"),
+            velocity_north: per_ray("northward_velocity"),
+            velocity_up: per_ray("vertical_velocity"),
+        };
+
+        // Optional sweep metadata, from sweep variables or else the median of the per-ray values
+        let sweep_string = |name: &str| {
+            read_var_1d_str(file, name)
+                .ok()
+                .and_then(|values| values.get(sweep_idx).map(|s| s.trim().to_string()))
+                .filter(|s| !s.is_empty() && s != "unknown")
+        };
+        let sweep_value = |name: &str| {
+            let fill = file
+                .variable(name)
+                .and_then(|var| read_numeric_attribute::<f64>(var.attributes(), "_FillValue"));
+            read_var_1d::<f64>(file, name)
+                .ok()
+                .and_then(|values| values.get(sweep_idx).copied())
+                .filter(|&v| v.is_finite() && Some(v) != fill)
+        };
+        let ray_median = |values: Option<&Vec<f64>>| values.and_then(|v| finite_median(v));
+        metadata.follow_mode = sweep_string("follow_mode").and_then(|s| parse_follow_mode(&s));
+        metadata.prt_mode = sweep_string("prt_mode")
+            .and_then(|s| parse_prt_mode(&s, ray_median(per_ray("prt_ratio").as_ref())));
+        metadata.polarization_mode = sweep_string("polarization_mode");
+        metadata.target_scan_rate = sweep_value("target_scan_rate");
+        metadata.rays_are_indexed = sweep_string("rays_are_indexed").map(|s| s.eq_ignore_ascii_case("true"));
+        metadata.ray_angle_resolution = sweep_value("ray_angle_res");
+        metadata.nyquist_velocity = ray_median(ray_metadata.nyquist_velocity.as_ref());
+        metadata.unambiguous_range = ray_median(per_ray("unambiguous_range").as_ref());
+        metadata.prf = ray_median(ray_metadata.prt.as_ref())
+            .filter(|&prt| prt > 0.0)
+            .map(|prt| 1.0 / prt);
 
         // Read moment data
         let mut moments = HashMap::new();
@@ -133,7 +288,7 @@ impl CfRadial1Backend {
                 continue;
             }
 
-            if let Ok(var) = file.variable(&var_name) {
+            if let Some(var) = file.variable(&var_name) {
                 // Check if it's a 2D moment variable [time, range]
                 if var.dimensions().len() == 2 {
                     if let Ok(moment) = self.read_moment(file, &var_name, start_idx, end_idx, range.len()) {
@@ -143,7 +298,17 @@ impl CfRadial1Backend {
             }
         }
 
-        Ok(SweepData::new(metadata, moments, coordinates))
+        let mut sweep = SweepData::new(metadata, moments, coordinates);
+        if !ray_metadata.is_empty() {
+            sweep.ray_metadata = Some(ray_metadata);
+        }
+        if !platform_motion.is_empty() {
+            sweep.platform_motion = Some(platform_motion);
+        }
+        if let Some(names) = &self.names {
+            sweep.standardize_names(names);
+        }
+        Ok(sweep)
     }
 
     /// Read a moment variable
@@ -160,43 +325,70 @@ impl CfRadial1Backend {
 
         let num_rays = end_ray - start_ray + 1;
 
-        // Read data for this sweep
-        let data_raw: Vec<f32> = var.get((start_ray, 0), (num_rays, num_gates))
-            .map_err(|e| RadishError::NetCdf(e))?;
-
-        let data = Array2::from_shape_vec((num_rays, num_gates), data_raw)
-            .map_err(|e| RadishError::Conversion(e.to_string()))?;
-
-        // Read attributes
-        let units = var.attribute("units")
-            .and_then(|a| a.value().ok())
-            .and_then(|v| match v {
-                netcdf::AttrValue::Str(s) => Some(s),
-                netcdf::AttrValue::Uchar(u) => Some(String::from_utf8_lossy(&u).to_string()),
-                _ => None,
-            })
-            .unwrap_or_else(|| "unknown".to_string());
+        let fill_value = match var.attribute("_FillValue").and_then(|a| a.value().ok()) {
+            Some(netcdf::AttrValue::Float(f)) => Some(f),
+            Some(netcdf::AttrValue::Double(f)) => Some(f as f32),
+            Some(netcdf::AttrValue::Short(f)) => Some(f as f32),
+            Some(netcdf::AttrValue::Schar(f)) => Some(f as f32),
+            Some(netcdf::AttrValue::Uchar(f)) => Some(f as f32),
+            _ => None,
+        };
 
-        let fill_value = var.attribute("_FillValue")
+        let scale_factor = var.attribute("scale_factor")
             .and_then(|a| a.value().ok())
             .and_then(|v| match v {
                 netcdf::AttrValue::Float(f) => Some(f),
+                netcdf::AttrValue::Double(f) => Some(f as f32),
                 _ => None,
             });
 
-        let scale_factor = var.attribute("scale_factor")
+        let add_offset = var.attribute("add_offset")
             .and_then(|a| a.value().ok())
             .and_then(|v| match v {
                 netcdf::AttrValue::Float(f) => Some(f),
+                netcdf::AttrValue::Double(f) => Some(f as f32),
                 _ => None,
             });
 
-        let add_offset = var.attribute("add_offset")
+        // Read data for this sweep; packed variables stay packed, with the
+        // storage type taken from the type of the variable
+        let shape = (num_rays, num_gates);
+        let extents = (start_ray..=end_ray, 0..num_gates);
+        let to_array = |e: ndarray::ShapeError| RadishError::Conversion(e.to_string());
+        let storage = match var.vartype() {
+            VariableType::Basic(BasicType::Ubyte) => {
+                let raw = var.get_values::<u8, _>(extents).map_err(RadishError::NetCdf)?;
+                MomentStorage::U8(Array2::from_shape_vec(shape, raw).map_err(to_array)?)
+            }
+            VariableType::Basic(BasicType::Byte | BasicType::Short) => {
+                let raw = var.get_values::<i16, _>(extents).map_err(RadishError::NetCdf)?;
+                MomentStorage::I16(Array2::from_shape_vec(shape, raw).map_err(to_array)?)
+            }
+            VariableType::Basic(BasicType::Float) => {
+                let raw = var.get_values::<f32, _>(extents).map_err(RadishError::NetCdf)?;
+                MomentStorage::F32(Array2::from_shape_vec(shape, raw).map_err(to_array)?)
+            }
+            VariableType::Basic(BasicType::Double) => {
+                let raw = var.get_values::<f64, _>(extents).map_err(RadishError::NetCdf)?;
+                MomentStorage::F64(Array2::from_shape_vec(shape, raw).map_err(to_array)?)
+            }
+            other => {
+                return Err(RadishError::Unsupported(format!(
+                    "moment variable {} of type {:?}",
+                    var_name, other
+                )))
+            }
+        };
+
+        // Read attributes
+        let units = var.attribute("units")
             .and_then(|a| a.value().ok())
             .and_then(|v| match v {
-                netcdf::AttrValue::Float(f) => Some(f),
+                netcdf::AttrValue::Str(s) => Some(s),
+                netcdf::AttrValue::Uchars(u) => Some(String::from_utf8_lossy(&u).to_string()),
                 _ => None,
-            });
+            })
+            .unwrap_or_else(|| "unknown".to_string());
 
         let standard_name = var.attribute("standard_name")
             .and_then(|a| a.value().ok())
@@ -212,12 +404,33 @@ impl CfRadial1Backend {
                 _ => None,
             });
 
-        let mut moment = MomentData::new(var_name.to_string(), units, data);
+        let provenance_attributes: HashMap<String, String> = [
+            ANCESTORS_ATTRIBUTE,
+            ALGORITHM_ATTRIBUTE,
+            ALGORITHM_VERSION_ATTRIBUTE,
+            PARAMETERS_HASH_ATTRIBUTE,
+        ]
+        .into_iter()
+        .filter_map(|key| match var.attribute(key)?.value().ok()? {
+            netcdf::AttrValue::Str(s) => Some((key.to_string(), s)),
+            _ => None,
+        })
+        .collect();
+
+        let mut moment = MomentData::from_storage(var_name.to_string(), units, storage);
+        moment.provenance = Provenance::from_attributes(&provenance_attributes);
         moment.fill_value = fill_value;
         moment.scale_factor = scale_factor;
         moment.add_offset = add_offset;
+        moment.valid_min = read_numeric_attribute::<f32>(var.attributes(), "valid_min");
+        moment.valid_max = read_numeric_attribute::<f32>(var.attributes(), "valid_max");
+        if moment.fill_value.is_some() || moment.valid_min.is_some() || moment.valid_max.is_some() {
+            moment.update_mask();
+        }
         moment.standard_name = standard_name;
         moment.long_name = long_name;
+        moment.coordinates = read_string_attribute(var.attributes(), "coordinates");
+        moment.attributes = read_attributes(var.attributes(), MOMENT_FIELD_ATTRIBUTES);
 
         Ok(moment)
     }
@@ -236,6 +449,14 @@ impl RadarBackend for CfRadial1Backend {
         &["nc", "nc4", "netcdf"]
     }
 
+    /// NetCDF classic, 64-bit offset and 64-bit data files, and HDF5
+    /// (NetCDF-4) files, whose signature may follow a user block
+    fn sniff(&self, head: &[u8], _len: u64) -> bool {
+        const HDF5_SIGNATURE: &[u8] = b"\x89HDF\r\n\x1a\n";
+        [b"CDF\x01", b"CDF\x02", b"CDF\x05"].iter().any(|magic| head.starts_with(*magic))
+            || [0, 512, 1024, 2048].iter().any(|&offset| head.get(offset..offset + 8) == Some(HDF5_SIGNATURE))
+    }
+
     fn scan_file(&self, path: &Path) -> Result<VolumeMetadata> {
         let file = netcdf::open(path)?;
         self.read_volume_metadata(&file)
@@ -248,19 +469,22 @@ impl RadarBackend for CfRadial1Backend {
 
     fn read_volume(&self, path: &Path) -> Result<VolumeData> {
         let file = netcdf::open(path)?;
+        self.read_file(&file)
+    }
 
-        // Read metadata
-        let metadata = self.read_volume_metadata(&file)?;
-        let num_sweeps = metadata.sweep_group_names.len();
+    fn scan_bytes(&self, bytes: &[u8], name: Option<&str>) -> Result<VolumeMetadata> {
+        let file = netcdf::open_mem(name, bytes)?;
+        self.read_volume_metadata(&file)
+    }
 
-        // Read all sweeps
-        let mut sweeps = Vec::with_capacity(num_sweeps);
-        for i in 0..num_sweeps {
-            let sweep = self.read_sweep_data(&file, i)?;
-            sweeps.push(sweep);
-        }
+    fn read_sweep_bytes(&self, bytes: &[u8], name: Option<&str>, sweep_idx: usize) -> Result<SweepData> {
+        let file = netcdf::open_mem(name, bytes)?;
+        self.read_sweep_data(&file, sweep_idx)
+    }
 
-        Ok(VolumeData::new(metadata, sweeps))
+    fn read_volume_bytes(&self, bytes: &[u8], name: Option<&str>) -> Result<VolumeData> {
+        let file = netcdf::open_mem(name, bytes)?;
+        self.read_file(&file)
     }
 }
 
@@ -272,21 +496,70 @@ impl Default for CfRadial1Backend {
 
 // Helper functions
 
+/// Conversions of the coordinate variables from their declared units to meters and degrees
+fn coordinate_conversions(file: &netcdf::File) -> Result<Vec<UnitConversion>> {
+    let coordinates = [
+        ("range", Quantity::Length),
+        ("azimuth", Quantity::Angle),
+        ("elevation", Quantity::Angle),
+        ("fixed_angle", Quantity::Angle),
+    ];
+    let mut conversions = Vec::new();
+    for (name, quantity) in coordinates {
+        let units = file
+            .variable(name)
+            .and_then(|var| read_string_attribute(var.attributes(), "units"));
+        conversions.extend(unit_conversion(name, units.as_deref(), quantity)?);
+    }
+    Ok(conversions)
+}
+
+fn convert_f64(conversions: &[UnitConversion], name: &str, values: &mut [f64]) {
+    if let Some(conversion) = conversions.iter().find(|c| c.variable == name) {
+        for v in values {
+            *v = conversion.convert(*v);
+        }
+    }
+}
+
 fn read_string_attr(file: &netcdf::File, name: &str) -> Option<String> {
     file.attribute(name)
         .and_then(|a| a.value().ok())
         .and_then(|v| match v {
             netcdf::AttrValue::Str(s) => Some(s),
-            netcdf::AttrValue::Uchar(u) => Some(String::from_utf8_lossy(&u).to_string()),
+            netcdf::AttrValue::Uchars(u) => Some(String::from_utf8_lossy(&u).to_string()),
             _ => None,
         })
 }
 
+/// Instrument parameters from the `radar_parameters` variables and the
+/// radar constants of the first calibration
+fn read_instrument_parameters(file: &netcdf::File) -> Option<InstrumentParameters> {
+    let value = |name: &str| {
+        let fill = file
+            .variable(name)
+            .and_then(|var| read_numeric_attribute::<f64>(var.attributes(), "_FillValue"));
+        read_scalar_var::<f64>(file, name)
+            .ok()
+            .filter(|&v| v.is_finite() && Some(v) != fill)
+    };
+    let parameters = InstrumentParameters {
+        beam_width_h: value("radar_beam_width_h"),
+        beam_width_v: value("radar_beam_width_v"),
+        antenna_gain_h: value("radar_antenna_gain_h"),
+        antenna_gain_v: value("radar_antenna_gain_v"),
+        radar_constant_h: value("r_calib_radar_constant_h"),
+        radar_constant_v: value("r_calib_radar_constant_v"),
+        receiver_bandwidth: value("radar_receiver_bandwidth"),
+    };
+    (!parameters.is_empty()).then_some(parameters)
+}
+
 fn read_scalar_var<T: netcdf::Numeric>(file: &netcdf::File, name: &str) -> Result<T> {
     let var = file.variable(name)
         .ok_or_else(|| RadishError::MissingVariable(name.to_string()))?;
 
-    let value: T = var.get((0,))
+    let value: T = var.get_value((0,))
         .map_err(|e| RadishError::NetCdf(e))?;
 
     Ok(value)
@@ -296,7 +569,7 @@ fn read_var_1d<T: netcdf::Numeric>(file: &netcdf::File, name: &str) -> Result<Ve
     let var = file.variable(name)
         .ok_or_else(|| RadishError::MissingVariable(name.to_string()))?;
 
-    let data: Vec<T> = var.get(..)
+    let data: Vec<T> = var.get_values(..)
         .map_err(|e| RadishError::NetCdf(e))?;
 
     Ok(data)
@@ -340,6 +613,47 @@ fn parse_sweep_mode(mode_str: &str) -> SweepMode {
     }
 }
 
+fn parse_follow_mode(mode_str: &str) -> Option<FollowMode> {
+    match mode_str.to_lowercase().as_str() {
+        "none" => Some(FollowMode::None),
+        "sun" => Some(FollowMode::Sun),
+        "vehicle" => Some(FollowMode::Vehicle),
+        "aircraft" => Some(FollowMode::Aircraft),
+        "target" => Some(FollowMode::Target),
+        "manual" => Some(FollowMode::Manual),
+        _ => None,
+    }
+}
+
+/// PRT mode, telling staggered ratios apart by the short to long PRT ratio
+fn parse_prt_mode(mode_str: &str, prt_ratio: Option<f64>) -> Option<PrtMode> {
+    match mode_str.to_lowercase().as_str() {
+        "fixed" => Some(PrtMode::Fixed),
+        "dual" => Some(PrtMode::Dual),
+        "staggered" => Some(match prt_ratio {
+            Some(r) if (r - 0.75).abs() < 0.02 => PrtMode::Staggered3_4,
+            Some(r) if (r - 0.8).abs() < 0.02 => PrtMode::Staggered4_5,
+            _ => PrtMode::Staggered2_3,
+        }),
+        _ => None,
+    }
+}
+
+/// Median of the finite values, `None` without any
+fn finite_median(values: &[f64]) -> Option<f64> {
+    let mut finite: Vec<f64> = values.iter().copied().filter(|v| v.is_finite()).collect();
+    if finite.is_empty() {
+        return None;
+    }
+    finite.sort_by(f64::total_cmp);
+    let mid = finite.len() / 2;
+    Some(if finite.len() % 2 == 1 {
+        finite[mid]
+    } else {
+        (finite[mid - 1] + finite[mid]) / 2.0
+    })
+}
+
 fn parse_platform_type(type_str: &str) -> Option<PlatformType> {
     match type_str.to_lowercase().as_str() {
         "fixed" => Some(PlatformType::Fixed),
@@ -0,0 +1,892 @@
+//! CINRAD backend for Chinese weather radar base data
+//!
+//! Two families of files are supported:
+//!
+//! - The CINRAD standard format (2016+) written by upgraded SA/SB/SC/CC/CD
+//!   radars. Files start with the `RSTM` magic, followed by site, task and
+//!   per-cut configuration blocks and self-describing radials. Site location
+//!   and scan geometry are read from the file.
+//! - Legacy SA/SB (2432-byte) and CB (4132-byte) radial records, holding
+//!   reflectivity at 1 km and velocity/spectrum width at 250 m resolution.
+//!   These files do not store the radar location; it is set with
+//!   [`CinradBackend::with_site`] and is NaN otherwise.
+//!
+//! Legacy base data of CC/CD and SC radars, written before the standard
+//! format with a volume header and radial records of their own, is not
+//! recognized.
+//!
+//! Moments with different gate spacings in one cut are placed on the finest
+//! range grid of the cut, repeating coarser gates. Bzip2-compressed files
+//! must be decompressed before reading.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use ndarray::Array2;
+use radish_types::{PrtMode, SweepMode};
+
+use crate::model::RadarCalibration;
+use crate::{
+    backends::RadarBackend, Coordinates, MomentData, RadishError, Result, SweepData, SweepMetadata,
+    VolumeData, VolumeMetadata,
+};
+
+/// Magic bytes at the start of a standard format file
+const STANDARD_MAGIC: &[u8; 4] = b"RSTM";
+
+/// Largest common range grid of a cut, in multiples of the most gates any
+/// moment records (legacy reflectivity is 4x coarser than velocity)
+const MAX_GRID_FACTOR: usize = 16;
+
+/// Sizes of the standard format header blocks
+const GENERIC_HEADER_SIZE: usize = 32;
+const SITE_CONFIG_SIZE: usize = 128;
+const TASK_CONFIG_SIZE: usize = 256;
+const CUT_CONFIG_SIZE: usize = 256;
+const RADIAL_HEADER_SIZE: usize = 64;
+const MOMENT_HEADER_SIZE: usize = 32;
+
+/// Offset of the moment data in a legacy radial record
+const LEGACY_DATA_OFFSET: usize = 128;
+
+/// Speed of light (m/s), for the unambiguous range
+const SPEED_OF_LIGHT: f64 = 299_792_458.0;
+
+/// Radar location for files that do not store it
+#[derive(Debug, Clone)]
+pub struct CinradSite {
+    /// Station code (e.g., "Z9250")
+    pub code: String,
+    /// Station name
+    pub name: Option<String>,
+    /// Latitude (degrees North)
+    pub latitude: f64,
+    /// Longitude (degrees East)
+    pub longitude: f64,
+    /// Antenna altitude above MSL (meters)
+    pub altitude: f64,
+}
+
+/// Backend for reading CINRAD standard format and legacy SA/SB/CB base data
+#[derive(Default)]
+pub struct CinradBackend {
+    site: Option<CinradSite>,
+}
+
+impl CinradBackend {
+    /// Create a new CinradBackend
+    pub fn new() -> Self {
+        Self { site: None }
+    }
+
+    /// Use the given site location, overriding any location stored in the file
+    pub fn with_site(site: CinradSite) -> Self {
+        Self { site: Some(site) }
+    }
+
+    fn parse(&self, path: &Path, decode: Decode) -> Result<ParsedVolume> {
+        let buf = std::fs::read(path)?;
+        let file_name = path.file_name().and_then(|n| n.to_str());
+        self.parse_bytes(&buf, file_name, decode)
+    }
+
+    fn parse_bytes(&self, buf: &[u8], file_name: Option<&str>, decode: Decode) -> Result<ParsedVolume> {
+        let mut parsed = match detect_format(buf)? {
+            Format::Standard => parse_standard(buf, decode)?,
+            Format::Legacy(layout) => parse_legacy(buf, layout, file_name, decode)?,
+        };
+
+        if let Some(site) = &self.site {
+            parsed.metadata.instrument_name = site.code.clone();
+            parsed.metadata.site_name = site.name.clone().or(parsed.metadata.site_name);
+            parsed.metadata.latitude = site.latitude;
+            parsed.metadata.longitude = site.longitude;
+            parsed.metadata.altitude = site.altitude;
+        }
+        Ok(parsed)
+    }
+}
+
+impl RadarBackend for CinradBackend {
+    fn name(&self) -> &str {
+        "cinrad"
+    }
+
+    fn description(&self) -> &str {
+        "CINRAD standard format and legacy SA/SB/CB base data"
+    }
+
+    fn supported_extensions(&self) -> &[&str] {
+        &["bin", "BIN"]
+    }
+
+    fn scan_file(&self, path: &Path) -> Result<VolumeMetadata> {
+        Ok(self.parse(path, Decode::None)?.metadata)
+    }
+
+    fn read_sweep(&self, path: &Path, sweep_idx: usize) -> Result<SweepData> {
+        self.parse(path, Decode::Sweep(sweep_idx))?.into_sweep(sweep_idx)
+    }
+
+    fn read_volume(&self, path: &Path) -> Result<VolumeData> {
+        self.parse(path, Decode::All)?.into_volume()
+    }
+
+    fn scan_bytes(&self, bytes: &[u8], name: Option<&str>) -> Result<VolumeMetadata> {
+        Ok(self.parse_bytes(bytes, name, Decode::None)?.metadata)
+    }
+
+    fn read_sweep_bytes(&self, bytes: &[u8], name: Option<&str>, sweep_idx: usize) -> Result<SweepData> {
+        self.parse_bytes(bytes, name, Decode::Sweep(sweep_idx))?.into_sweep(sweep_idx)
+    }
+
+    fn read_volume_bytes(&self, bytes: &[u8], name: Option<&str>) -> Result<VolumeData> {
+        self.parse_bytes(bytes, name, Decode::All)?.into_volume()
+    }
+
+    /// Standard format magic or a legacy record layout
+    fn sniff(&self, head: &[u8], len: u64) -> bool {
+        head.starts_with(STANDARD_MAGIC) || LegacyLayout::detect(head, len as usize).is_some()
+    }
+}
+
+/// Which radials to decode moment data for
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Decode {
+    /// Headers only
+    None,
+    /// Only radials of one sweep
+    Sweep(usize),
+    /// All radials
+    All,
+}
+
+impl Decode {
+    fn wants(&self, sweep: usize) -> bool {
+        match self {
+            Decode::None => false,
+            Decode::Sweep(s) => *s == sweep,
+            Decode::All => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Format {
+    Standard,
+    Legacy(LegacyLayout),
+}
+
+fn detect_format(buf: &[u8]) -> Result<Format> {
+    if buf.starts_with(b"BZh") {
+        return Err(RadishError::Unsupported(
+            "Bzip2-compressed CINRAD files must be decompressed before reading".to_string(),
+        ));
+    }
+    if buf.starts_with(STANDARD_MAGIC) {
+        return Ok(Format::Standard);
+    }
+    LegacyLayout::detect(buf, buf.len())
+        .map(Format::Legacy)
+        .ok_or_else(|| RadishError::InvalidFormat("Not a CINRAD base data file".to_string()))
+}
+
+/// Settings of one cut (sweep)
+#[derive(Debug, Clone, Default)]
+struct CutInfo {
+    sweep_mode: Option<SweepMode>,
+    fixed_angle: Option<f64>,
+    nyquist_velocity: Option<f64>,
+    prf: Option<f64>,
+    prt_mode: Option<PrtMode>,
+    unambiguous_range: Option<f64>,
+    polarization_mode: Option<String>,
+}
+
+/// One moment of one radial, on its own range grid
+#[derive(Debug, Clone)]
+struct RadialMoment {
+    name: String,
+    /// Range to the centre of the first gate (meters)
+    first_gate: f32,
+    /// Gate spacing (meters)
+    gate_spacing: f32,
+    values: Vec<f32>,
+}
+
+#[derive(Debug, Clone)]
+struct Radial {
+    sweep: usize,
+    azimuth: f32,
+    elevation: f32,
+    time: DateTime<Utc>,
+    moments: Vec<RadialMoment>,
+}
+
+/// File contents before conversion to the common data model
+struct ParsedVolume {
+    metadata: VolumeMetadata,
+    calibration: Option<RadarCalibration>,
+    /// Cut settings, indexed by sweep
+    cuts: Vec<CutInfo>,
+    radials: Vec<Radial>,
+}
+
+impl ParsedVolume {
+    fn new(metadata: VolumeMetadata, calibration: Option<RadarCalibration>) -> Self {
+        Self {
+            metadata,
+            calibration,
+            cuts: Vec::new(),
+            radials: Vec::new(),
+        }
+    }
+
+    fn num_sweeps(&self) -> usize {
+        self.cuts.len()
+    }
+
+    /// Fill in time coverage, sweep names and fixed angles from the radials
+    fn finish_metadata(&mut self) -> Result<()> {
+        let (Some(first), Some(last)) = (
+            self.radials.iter().map(|r| r.time).min(),
+            self.radials.iter().map(|r| r.time).max(),
+        ) else {
+            return Err(RadishError::InvalidFormat("CINRAD file contains no radials".to_string()));
+        };
+        self.metadata.time_coverage_start = first;
+        self.metadata.time_coverage_end = last;
+
+        for (i, cut) in self.cuts.iter_mut().enumerate() {
+            if cut.fixed_angle.is_none() {
+                let elevations: Vec<f64> = self
+                    .radials
+                    .iter()
+                    .filter(|r| r.sweep == i)
+                    .map(|r| r.elevation as f64)
+                    .collect();
+                let mean = elevations.iter().sum::<f64>() / elevations.len().max(1) as f64;
+                cut.fixed_angle = Some((mean * 100.0).round() / 100.0);
+            }
+        }
+
+        self.metadata.generate_sweep_names(self.cuts.len());
+        self.metadata.sweep_fixed_angles = self
+            .cuts
+            .iter()
+            .map(|c| c.fixed_angle.unwrap_or(f64::NAN))
+            .collect();
+        Ok(())
+    }
+
+    fn into_sweep(self, sweep_idx: usize) -> Result<SweepData> {
+        if sweep_idx >= self.num_sweeps() {
+            return Err(RadishError::InvalidSweepIndex(sweep_idx));
+        }
+        self.build_sweep(sweep_idx)
+    }
+
+    fn into_volume(self) -> Result<VolumeData> {
+        let sweeps = (0..self.num_sweeps())
+            .map(|i| self.build_sweep(i))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut volume = VolumeData::new(self.metadata, sweeps);
+        volume.calibration = self.calibration;
+        Ok(volume)
+    }
+
+    fn build_sweep(&self, sweep_idx: usize) -> Result<SweepData> {
+        let cut = &self.cuts[sweep_idx];
+        let radials: Vec<&Radial> = self.radials.iter().filter(|r| r.sweep == sweep_idx).collect();
+
+        let mut metadata = SweepMetadata::new(
+            sweep_idx as u32,
+            cut.sweep_mode.unwrap_or(SweepMode::Azimuth),
+            cut.fixed_angle.unwrap_or(f64::NAN),
+        );
+        metadata.nyquist_velocity = cut.nyquist_velocity;
+        metadata.prf = cut.prf;
+        metadata.prt_mode = cut.prt_mode;
+        metadata.unambiguous_range = cut.unambiguous_range;
+        metadata.polarization_mode = cut.polarization_mode.clone();
+
+        // Common range grid: finest spacing, covering every moment
+        let mut names: Vec<&str> = Vec::new();
+        let mut spacing = f32::INFINITY;
+        let mut first_gate = f32::INFINITY;
+        let mut last_gate = f32::NEG_INFINITY;
+        let mut max_values = 0;
+        for m in radials.iter().flat_map(|r| &r.moments) {
+            if !names.contains(&m.name.as_str()) {
+                names.push(&m.name);
+            }
+            if m.values.is_empty() || m.gate_spacing <= 0.0 {
+                continue;
+            }
+            spacing = spacing.min(m.gate_spacing);
+            first_gate = first_gate.min(m.first_gate);
+            last_gate = last_gate.max(m.first_gate + (m.values.len() - 1) as f32 * m.gate_spacing);
+            max_values = max_values.max(m.values.len());
+        }
+        let range: Vec<f32> = if spacing.is_finite() {
+            // Gate geometry comes from the headers; refuse grids far larger
+            // than the data before allocating them
+            let extent = (last_gate - first_gate) / spacing;
+            let max_gates = MAX_GRID_FACTOR * max_values;
+            if !extent.is_finite() || extent < 0.0 || extent.round() >= max_gates as f32 {
+                return Err(RadishError::InvalidFormat(format!(
+                    "Cut {} gate geometry spans {} gates, for at most {} recorded per moment",
+                    sweep_idx, extent, max_values
+                )));
+            }
+            let num_gates = extent.round() as usize + 1;
+            (0..num_gates).map(|j| first_gate + j as f32 * spacing).collect()
+        } else {
+            Vec::new()
+        };
+
+        let mut coordinates = Coordinates::new(
+            radials
+                .iter()
+                .map(|r| r.time.timestamp_micros() as f64 / 1e6)
+                .collect(),
+            range.clone(),
+            radials.iter().map(|r| r.azimuth).collect(),
+            radials.iter().map(|r| r.elevation).collect(),
+        );
+        // Radials may end at different ranges; keep the gates each one recorded
+        let gates_per_ray: Vec<usize> = radials
+            .iter()
+            .map(|radial| {
+                radial
+                    .moments
+                    .iter()
+                    .filter(|m| !m.values.is_empty() && m.gate_spacing > 0.0)
+                    .map(|m| {
+                        let last = m.first_gate + (m.values.len() - 1) as f32 * m.gate_spacing;
+                        range.iter().take_while(|&&r| r < last + 0.5 * m.gate_spacing).count()
+                    })
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        if gates_per_ray.iter().any(|&n| n != range.len()) {
+            coordinates.n_gates_per_ray = Some(gates_per_ray);
+        }
+
+        let mut moments = HashMap::new();
+        for name in names {
+            let mut data = Array2::from_elem((radials.len(), range.len()), f32::NAN);
+            for (i, radial) in radials.iter().enumerate() {
+                let Some(m) = radial.moments.iter().find(|m| m.name == name) else {
+                    continue;
+                };
+                for (j, &r) in range.iter().enumerate() {
+                    // Source gate whose extent contains this range
+                    let k = ((r - m.first_gate) / m.gate_spacing + 0.5).floor();
+                    if k >= 0.0 && (k as usize) < m.values.len() {
+                        data[[i, j]] = m.values[k as usize];
+                    }
+                }
+            }
+            moments.insert(name.to_string(), moment_data(name, data));
+        }
+
+        Ok(SweepData::new(metadata, moments, coordinates))
+    }
+}
+
+/// Tracks sweep indices in order of first appearance of each cut number
+#[derive(Default)]
+struct SweepIndex {
+    cut_numbers: Vec<i64>,
+}
+
+impl SweepIndex {
+    /// Sweep index of a cut number, and whether it is new
+    fn index(&mut self, cut_number: i64) -> (usize, bool) {
+        match self.cut_numbers.iter().position(|&c| c == cut_number) {
+            Some(i) => (i, false),
+            None => {
+                self.cut_numbers.push(cut_number);
+                (self.cut_numbers.len() - 1, true)
+            }
+        }
+    }
+}
+
+// Standard format
+
+fn parse_standard(buf: &[u8], decode: Decode) -> Result<ParsedVolume> {
+    let site = GENERIC_HEADER_SIZE;
+    let task = site + SITE_CONFIG_SIZE;
+    let cuts_start = task + TASK_CONFIG_SIZE;
+
+    let site_code = str_at(buf, site, 8)?;
+    let site_name = str_at(buf, site + 8, 32)?;
+    let latitude = f32_at(buf, site + 40)? as f64;
+    let longitude = f32_at(buf, site + 44)? as f64;
+    let antenna_height = i32_at(buf, site + 48)? as f64;
+    let ground_height = i32_at(buf, site + 52)? as f64;
+    let frequency_mhz = f32_at(buf, site + 56)? as f64;
+
+    let task_name = str_at(buf, task, 32)?;
+    let polarization = i32_at(buf, task + 160)?;
+    let scan_type = i32_at(buf, task + 164)?;
+    let pulse_width_ns = i32_at(buf, task + 168)?;
+    let scan_start = i32_at(buf, task + 172)?;
+    let cut_number = i32_at(buf, task + 176)?.max(0) as usize;
+    let noise_h = f32_at(buf, task + 180)?;
+    let noise_v = f32_at(buf, task + 184)?;
+    let zdr_calibration = f32_at(buf, task + 204)?;
+    let phidp_calibration = f32_at(buf, task + 208)?;
+
+    let start = Utc
+        .timestamp_opt(scan_start as i64, 0)
+        .single()
+        .ok_or_else(|| RadishError::InvalidFormat("Invalid scan start time".to_string()))?;
+
+    let mut metadata = VolumeMetadata::new(site_code, latitude, longitude, antenna_height, start, start);
+    metadata.site_name = (!site_name.is_empty()).then_some(site_name);
+    metadata.altitude_agl = Some(antenna_height - ground_height);
+    metadata.frequency = Some(frequency_mhz * 1e6);
+    metadata.attributes.insert("source_format".to_string(), "CINRAD standard format".into());
+    metadata.attributes.insert("task_name".to_string(), task_name.into());
+    metadata.attributes.insert("scan_type".to_string(), i64::from(scan_type).into());
+
+    let calibration = RadarCalibration {
+        pulse_width: Some(pulse_width_ns as f64 / 1000.0),
+        noise_power_h: Some(noise_h as f64),
+        noise_power_v: Some(noise_v as f64),
+        zdr_correction: Some(zdr_calibration as f64),
+        system_phidp: Some(phidp_calibration as f64),
+        ..Default::default()
+    };
+
+    let polarization_mode = match polarization {
+        1 => Some("horizontal"),
+        2 => Some("vertical"),
+        3 => Some("hv_sim"),
+        4 => Some("hv_alt"),
+        _ => None,
+    }
+    .map(str::to_string);
+    let sweep_mode = match scan_type {
+        2 | 5 => SweepMode::Elevation,
+        3 | 4 => SweepMode::Sector,
+        _ => SweepMode::Azimuth,
+    };
+
+    // Cut configurations, indexed by (1-based) elevation number - 1
+    // The cut count comes from the file; reserve no more than the buffer can hold
+    let capacity = cut_number.min(buf.len().saturating_sub(cuts_start) / CUT_CONFIG_SIZE);
+    let mut cut_configs = Vec::with_capacity(capacity);
+    let mut resolutions = Vec::with_capacity(capacity);
+    for k in 0..cut_number {
+        let c = cuts_start + k * CUT_CONFIG_SIZE;
+        let wave_form = i32_at(buf, c + 4)?;
+        let prf1 = f32_at(buf, c + 8)? as f64;
+        let azimuth = f32_at(buf, c + 20)? as f64;
+        let elevation = f32_at(buf, c + 24)? as f64;
+        let log_resolution = i32_at(buf, c + 44)? as f32;
+        let doppler_resolution = i32_at(buf, c + 48)? as f32;
+        let start_range = i32_at(buf, c + 60)? as f32;
+        let nyquist = f32_at(buf, c + 80)? as f64;
+
+        cut_configs.push(CutInfo {
+            sweep_mode: Some(sweep_mode),
+            fixed_angle: Some(if sweep_mode == SweepMode::Elevation { azimuth } else { elevation }),
+            nyquist_velocity: (nyquist > 0.0).then_some(nyquist),
+            prf: (prf1 > 0.0).then_some(prf1),
+            prt_mode: Some(if wave_form == 5 { PrtMode::Dual } else { PrtMode::Fixed }),
+            unambiguous_range: (prf1 > 0.0).then(|| SPEED_OF_LIGHT / (2.0 * prf1)),
+            polarization_mode: polarization_mode.clone(),
+        });
+        resolutions.push((start_range, log_resolution, doppler_resolution));
+    }
+
+    let mut parsed = ParsedVolume::new(metadata, Some(calibration));
+    let mut sweeps = SweepIndex::default();
+    let mut pos = cuts_start + cut_number * CUT_CONFIG_SIZE;
+
+    while pos + RADIAL_HEADER_SIZE <= buf.len() {
+        let state = i32_at(buf, pos)?;
+        let elevation_number = i32_at(buf, pos + 16)?;
+        let azimuth = f32_at(buf, pos + 20)?;
+        let elevation = f32_at(buf, pos + 24)?;
+        let seconds = i32_at(buf, pos + 28)?;
+        let microseconds = i32_at(buf, pos + 32)?;
+        let num_moments = i32_at(buf, pos + 40)?.max(0) as usize;
+        pos += RADIAL_HEADER_SIZE;
+
+        let (sweep, is_new) = sweeps.index(elevation_number as i64);
+        if is_new {
+            let cut = usize::try_from(elevation_number - 1)
+                .ok()
+                .and_then(|k| cut_configs.get(k))
+                .cloned()
+                .unwrap_or_default();
+            parsed.cuts.push(cut);
+        }
+        let (start_range, log_res, dop_res) = usize::try_from(elevation_number - 1)
+            .ok()
+            .and_then(|k| resolutions.get(k).copied())
+            .unwrap_or((0.0, 1000.0, 250.0));
+
+        let mut moments = Vec::new();
+        for _ in 0..num_moments {
+            let data_type = i32_at(buf, pos)?;
+            let scale = i32_at(buf, pos + 4)?;
+            let offset = i32_at(buf, pos + 8)?;
+            let bin_length = i16_at(buf, pos + 12)?;
+            let length = i32_at(buf, pos + 16)?.max(0) as usize;
+            pos += MOMENT_HEADER_SIZE;
+            let raw = slice(buf, pos, length)?;
+            pos += length;
+
+            if !decode.wants(sweep) {
+                continue;
+            }
+            let Some(info) = standard_moment(data_type) else {
+                continue;
+            };
+
+            let gate_spacing = if info.doppler { dop_res } else { log_res };
+            moments.push(RadialMoment {
+                name: info.name.to_string(),
+                first_gate: start_range + gate_spacing / 2.0,
+                gate_spacing,
+                values: decode_standard(raw, bin_length, scale, offset),
+            });
+        }
+
+        parsed.radials.push(Radial {
+            sweep,
+            azimuth,
+            elevation,
+            time: timestamp(seconds as i64, microseconds as i64)?,
+            moments,
+        });
+
+        // Radial state 4 marks the end of the volume
+        if state == 4 {
+            break;
+        }
+    }
+
+    parsed.finish_metadata()?;
+    Ok(parsed)
+}
+
+/// Decode packed standard format values; codes below 5 are flags (no data,
+/// range folded, ...) and become NaN
+fn decode_standard(raw: &[u8], bin_length: i16, scale: i32, offset: i32) -> Vec<f32> {
+    let scale = if scale == 0 { 1.0 } else { scale as f32 };
+    let decode = |code: u32| {
+        if code < 5 {
+            f32::NAN
+        } else {
+            (code as f32 - offset as f32) / scale
+        }
+    };
+
+    if bin_length == 2 {
+        raw.chunks_exact(2)
+            .map(|b| decode(u16::from_le_bytes([b[0], b[1]]) as u32))
+            .collect()
+    } else {
+        raw.iter().map(|&b| decode(b as u32)).collect()
+    }
+}
+
+struct StandardMoment {
+    name: &'static str,
+    /// Uses the Doppler (rather than log) range resolution
+    doppler: bool,
+}
+
+fn standard_moment(data_type: i32) -> Option<StandardMoment> {
+    let (name, doppler) = match data_type {
+        1 => ("TH", false),
+        2 => ("DBZH", false),
+        3 => ("VRADH", true),
+        4 => ("WRADH", true),
+        5 => ("SQIH", true),
+        6 => ("CPA", false),
+        7 => ("ZDR", false),
+        8 => ("LDR", false),
+        9 => ("RHOHV", false),
+        10 => ("PHIDP", false),
+        11 => ("KDP", false),
+        12 => ("CP", false),
+        14 => ("HCL", false),
+        15 => ("CF", false),
+        16 => ("SNRH", false),
+        17 => ("SNRV", false),
+        32 => ("DBZH_CORR", false),
+        33 => ("VRADH_CORR", true),
+        34 => ("WRADH_CORR", true),
+        35 => ("ZDR_CORR", false),
+        _ => return None,
+    };
+    Some(StandardMoment { name, doppler })
+}
+
+// Legacy SA/SB/CB format
+
+/// Record layout of legacy radial files
+#[derive(Debug, Clone, Copy)]
+struct LegacyLayout {
+    record_size: usize,
+    reflectivity_gates: usize,
+    doppler_gates: usize,
+    radar_type: &'static str,
+}
+
+impl LegacyLayout {
+    const SA_SB: Self = Self {
+        record_size: 2432,
+        reflectivity_gates: 460,
+        doppler_gates: 920,
+        radar_type: "SA/SB",
+    };
+    const CB: Self = Self {
+        record_size: 4132,
+        reflectivity_gates: 800,
+        doppler_gates: 1600,
+        radar_type: "CB",
+    };
+
+    /// Detect the layout from the first record header and the file size
+    // `usize::is_multiple_of` needs Rust 1.87
+    #[allow(clippy::manual_is_multiple_of)]
+    fn detect(head: &[u8], file_len: usize) -> Option<Self> {
+        let radial_state = u16_at(head, 40).ok()?;
+        let elevation_number = u16_at(head, 44).ok()?;
+        let gates_r = u16_at(head, 54).ok()? as usize;
+        let gates_v = u16_at(head, 56).ok()? as usize;
+        if !(radial_state == 0 || radial_state == 3) || elevation_number == 0 {
+            return None;
+        }
+
+        [Self::SA_SB, Self::CB].into_iter().find(|layout| {
+            file_len >= layout.record_size
+                && file_len % layout.record_size == 0
+                && gates_r <= layout.reflectivity_gates
+                && gates_v <= layout.doppler_gates
+        })
+    }
+}
+
+fn parse_legacy(
+    buf: &[u8],
+    layout: LegacyLayout,
+    file_name: Option<&str>,
+    decode: Decode,
+) -> Result<ParsedVolume> {
+    // Filenames follow Z_RADR_I_<station>_<time>_O_DOR_<type>_CAP.bin
+    let station = file_name
+        .unwrap_or("")
+        .split('_')
+        .nth(3)
+        .filter(|s| !s.is_empty())
+        .unwrap_or("CINRAD")
+        .to_string();
+
+    let epoch = Utc.with_ymd_and_hms(1970, 1, 1, 0, 0, 0).unwrap();
+    let mut metadata = VolumeMetadata::new(station, f64::NAN, f64::NAN, f64::NAN, epoch, epoch);
+    metadata.attributes.insert(
+        "source_format".to_string(),
+        format!("CINRAD {} legacy base data", layout.radar_type).into(),
+    );
+
+    let mut parsed = ParsedVolume::new(metadata, None);
+    let mut sweeps = SweepIndex::default();
+    let r_start = LEGACY_DATA_OFFSET;
+    let v_start = r_start + layout.reflectivity_gates;
+    let w_start = v_start + layout.doppler_gates;
+
+    for record in buf.chunks_exact(layout.record_size) {
+        let ms_of_day = u32_at(record, 28)?;
+        let day = u16_at(record, 32)?;
+        let unambiguous_range = u16_at(record, 34)? as f64 * 100.0;
+        let azimuth = legacy_angle(u16_at(record, 36)?);
+        let state = u16_at(record, 40)?;
+        let elevation = legacy_angle(u16_at(record, 42)?);
+        let elevation_number = u16_at(record, 44)?;
+        let first_gate_r = u16_at(record, 46)? as f32;
+        let first_gate_v = u16_at(record, 48)? as f32;
+        let spacing_r = u16_at(record, 50)? as f32;
+        let spacing_v = u16_at(record, 52)? as f32;
+        let gates_r = (u16_at(record, 54)? as usize).min(layout.reflectivity_gates);
+        let gates_v = (u16_at(record, 56)? as usize).min(layout.doppler_gates);
+        let velocity_resolution = u16_at(record, 70)?;
+        let vcp = u16_at(record, 72)?;
+        let nyquist = u16_at(record, 88)? as f64 / 100.0;
+
+        let (sweep, is_new) = sweeps.index(elevation_number as i64);
+        if is_new {
+            parsed.cuts.push(CutInfo {
+                sweep_mode: Some(SweepMode::Azimuth),
+                nyquist_velocity: (gates_v > 0 && nyquist > 0.0).then_some(nyquist),
+                unambiguous_range: (unambiguous_range > 0.0).then_some(unambiguous_range),
+                ..Default::default()
+            });
+            // CINRAD VCP elevations differ from the NEXRAD VCPs of the same
+            // number, so no scan strategy is declared
+            parsed.metadata.attributes.insert("vcp".to_string(), i64::from(vcp).into());
+        }
+
+        let mut moments = Vec::new();
+        if decode.wants(sweep) {
+            if gates_r > 0 && spacing_r > 0.0 {
+                moments.push(RadialMoment {
+                    name: "DBZH".to_string(),
+                    first_gate: first_gate_r,
+                    gate_spacing: spacing_r,
+                    values: record[r_start..r_start + gates_r]
+                        .iter()
+                        .map(|&c| legacy_value(c, 0.5, -33.0))
+                        .collect(),
+                });
+            }
+            if gates_v > 0 && spacing_v > 0.0 {
+                // Velocity resolution code 2 is 0.5 m/s, 4 is 1 m/s
+                let (v_scale, v_offset) = if velocity_resolution == 4 {
+                    (1.0, -129.0)
+                } else {
+                    (0.5, -64.5)
+                };
+                moments.push(RadialMoment {
+                    name: "VRADH".to_string(),
+                    first_gate: first_gate_v,
+                    gate_spacing: spacing_v,
+                    values: record[v_start..v_start + gates_v]
+                        .iter()
+                        .map(|&c| legacy_value(c, v_scale, v_offset))
+                        .collect(),
+                });
+                moments.push(RadialMoment {
+                    name: "WRADH".to_string(),
+                    first_gate: first_gate_v,
+                    gate_spacing: spacing_v,
+                    values: record[w_start..w_start + gates_v]
+                        .iter()
+                        .map(|&c| legacy_value(c, 0.5, -64.5))
+                        .collect(),
+                });
+            }
+        }
+
+        // Day 1 is 1970-01-01
+        let seconds = (day as i64 - 1) * 86_400 + (ms_of_day / 1000) as i64;
+        parsed.radials.push(Radial {
+            sweep,
+            azimuth,
+            elevation,
+            time: timestamp(seconds, (ms_of_day % 1000) as i64 * 1000)?,
+            moments,
+        });
+
+        // Radial state 4 marks the end of the volume
+        if state == 4 {
+            break;
+        }
+    }
+
+    parsed.finish_metadata()?;
+    Ok(parsed)
+}
+
+/// Decode a legacy angle code to degrees
+fn legacy_angle(code: u16) -> f32 {
+    (code as f32 / 8.0) * (180.0 / 4096.0)
+}
+
+/// Decode a legacy byte value; 0 (below threshold) and 1 (range folded) become NaN
+fn legacy_value(code: u8, scale: f32, offset: f32) -> f32 {
+    if code < 2 {
+        f32::NAN
+    } else {
+        code as f32 * scale + offset
+    }
+}
+
+// Helper functions
+
+fn moment_data(name: &str, data: Array2<f32>) -> MomentData {
+    let (units, standard_name, long_name) = match name {
+        "TH" => ("dBZ", None, "Total reflectivity factor (horizontal channel)"),
+        "DBZH" => ("dBZ", Some("equivalent_reflectivity_factor"), "Equivalent reflectivity factor (horizontal channel)"),
+        "DBZH_CORR" => ("dBZ", Some("equivalent_reflectivity_factor"), "Corrected equivalent reflectivity factor"),
+        "VRADH" => ("m/s", Some("radial_velocity_of_scatterers_away_from_instrument"), "Radial velocity (horizontal channel)"),
+        "VRADH_CORR" => ("m/s", Some("radial_velocity_of_scatterers_away_from_instrument"), "Corrected radial velocity"),
+        "WRADH" => ("m/s", Some("doppler_spectrum_width"), "Doppler spectrum width (horizontal channel)"),
+        "WRADH_CORR" => ("m/s", Some("doppler_spectrum_width"), "Corrected Doppler spectrum width"),
+        "SQIH" => ("", None, "Signal quality index (horizontal channel)"),
+        "CPA" => ("", None, "Clutter phase alignment"),
+        "ZDR" => ("dB", Some("differential_reflectivity_hv"), "Differential reflectivity"),
+        "ZDR_CORR" => ("dB", Some("differential_reflectivity_hv"), "Corrected differential reflectivity"),
+        "LDR" => ("dB", Some("linear_depolarization_ratio"), "Linear depolarization ratio"),
+        "RHOHV" => ("", Some("cross_correlation_ratio_hv"), "Cross-correlation coefficient"),
+        "PHIDP" => ("degrees", Some("differential_phase_hv"), "Differential propagation phase"),
+        "KDP" => ("degrees/km", Some("specific_differential_phase_hv"), "Specific differential phase"),
+        "CP" => ("", None, "Clutter probability"),
+        "HCL" => ("", None, "Hydrometeor classification"),
+        "CF" => ("", None, "Clutter flag"),
+        "SNRH" => ("dB", Some("signal_to_noise_ratio"), "Signal-to-noise ratio (horizontal channel)"),
+        "SNRV" => ("dB", Some("signal_to_noise_ratio"), "Signal-to-noise ratio (vertical channel)"),
+        _ => ("", None, ""),
+    };
+
+    let mut moment = MomentData::new(name.to_string(), units.to_string(), data);
+    moment.standard_name = standard_name.map(str::to_string);
+    moment.long_name = (!long_name.is_empty()).then(|| long_name.to_string());
+    moment
+}
+
+fn timestamp(seconds: i64, microseconds: i64) -> Result<DateTime<Utc>> {
+    Utc.timestamp_opt(seconds, 0)
+        .single()
+        .map(|t| t + Duration::microseconds(microseconds))
+        .ok_or_else(|| RadishError::InvalidFormat(format!("Invalid radial time: {}", seconds)))
+}
+
+fn slice(buf: &[u8], offset: usize, len: usize) -> Result<&[u8]> {
+    buf.get(offset..offset + len)
+        .ok_or_else(|| RadishError::InvalidFormat("Truncated CINRAD file".to_string()))
+}
+
+fn u16_at(buf: &[u8], offset: usize) -> Result<u16> {
+    let b = slice(buf, offset, 2)?;
+    Ok(u16::from_le_bytes([b[0], b[1]]))
+}
+
+fn i16_at(buf: &[u8], offset: usize) -> Result<i16> {
+    let b = slice(buf, offset, 2)?;
+    Ok(i16::from_le_bytes([b[0], b[1]]))
+}
+
+fn u32_at(buf: &[u8], offset: usize) -> Result<u32> {
+    let b = slice(buf, offset, 4)?;
+    Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn i32_at(buf: &[u8], offset: usize) -> Result<i32> {
+    let b = slice(buf, offset, 4)?;
+    Ok(i32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn f32_at(buf: &[u8], offset: usize) -> Result<f32> {
+    let b = slice(buf, offset, 4)?;
+    Ok(f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+/// Fixed-size string field, trimmed at the first NUL
+fn str_at(buf: &[u8], offset: usize, len: usize) -> Result<String> {
+    let bytes = slice(buf, offset, len)?;
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(len);
+    Ok(String::from_utf8_lossy(&bytes[..end]).trim().to_string())
+}
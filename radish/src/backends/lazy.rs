@@ -0,0 +1,172 @@
+//! Volumes whose sweeps are read on first access
+//!
+//! [`RadarBackend::read_volume`] decodes every moment of every sweep. When
+//! only some sweeps are needed, e.g. the lowest tilt of a day of volumes, a
+//! [`LazyVolume`] scans the file for its metadata and reads each sweep
+//! through [`RadarBackend::read_sweep`] the first time it is requested,
+//! caching it for later accesses. Files held in memory (e.g. downloaded
+//! or read from an archive) are opened with [`LazyVolume::from_bytes`].
+
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::backends::{auto_backend, auto_backend_bytes, RadarBackend};
+use crate::{RadishError, Result, SweepData, VolumeData, VolumeMetadata};
+
+/// What is known about a sweep before it is read
+#[derive(Debug, Clone, PartialEq)]
+pub struct SweepDescriptor {
+    /// Sweep index in the file
+    pub index: usize,
+    /// Sweep group name
+    pub name: String,
+    /// Fixed angle (degrees), if the scan reported it
+    pub fixed_angle: Option<f64>,
+}
+
+/// Where the sweeps of a lazy volume are read from
+enum Source {
+    File(PathBuf),
+    Memory { bytes: Vec<u8>, name: Option<String> },
+}
+
+impl Source {
+    fn scan(&self, backend: &dyn RadarBackend) -> Result<VolumeMetadata> {
+        match self {
+            Source::File(path) => backend.scan_file(path),
+            Source::Memory { bytes, name } => backend.scan_bytes(bytes, name.as_deref()),
+        }
+    }
+
+    fn read_sweep(&self, backend: &dyn RadarBackend, index: usize) -> Result<SweepData> {
+        match self {
+            Source::File(path) => backend.read_sweep(path, index),
+            Source::Memory { bytes, name } => backend.read_sweep_bytes(bytes, name.as_deref(), index),
+        }
+    }
+}
+
+/// Volume metadata with sweeps loaded on demand
+pub struct LazyVolume {
+    backend: Box<dyn RadarBackend>,
+    source: Source,
+    metadata: VolumeMetadata,
+    descriptors: Vec<SweepDescriptor>,
+    sweeps: Vec<OnceLock<SweepData>>,
+}
+
+impl LazyVolume {
+    /// Scan a file with the backend selected from its contents
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let backend = auto_backend(path.as_ref())?;
+        Self::with_backend(backend, path)
+    }
+
+    /// Scan a file with a given backend
+    pub fn with_backend(backend: Box<dyn RadarBackend>, path: impl AsRef<Path>) -> Result<Self> {
+        Self::scan(backend, Source::File(path.as_ref().to_path_buf()))
+    }
+
+    /// Scan a file held in memory with the backend selected from its contents
+    ///
+    /// `name` is the file name, if known; some formats record metadata in it.
+    pub fn from_bytes(bytes: Vec<u8>, name: Option<String>) -> Result<Self> {
+        let backend = auto_backend_bytes(&bytes)?;
+        Self::scan(backend, Source::Memory { bytes, name })
+    }
+
+    fn scan(backend: Box<dyn RadarBackend>, source: Source) -> Result<Self> {
+        let metadata = source.scan(backend.as_ref())?;
+        let descriptors: Vec<SweepDescriptor> = metadata
+            .sweep_group_names
+            .iter()
+            .enumerate()
+            .map(|(index, name)| SweepDescriptor {
+                index,
+                name: name.clone(),
+                fixed_angle: metadata.sweep_fixed_angles.get(index).copied(),
+            })
+            .collect();
+        let sweeps = descriptors.iter().map(|_| OnceLock::new()).collect();
+        Ok(Self {
+            backend,
+            source,
+            metadata,
+            descriptors,
+            sweeps,
+        })
+    }
+
+    /// Volume metadata from the scan
+    pub fn metadata(&self) -> &VolumeMetadata {
+        &self.metadata
+    }
+
+    /// Path of the file, `None` for files held in memory
+    pub fn path(&self) -> Option<&Path> {
+        match &self.source {
+            Source::File(path) => Some(path),
+            Source::Memory { .. } => None,
+        }
+    }
+
+    /// Get number of sweeps
+    pub fn num_sweeps(&self) -> usize {
+        self.descriptors.len()
+    }
+
+    /// Descriptors of every sweep, in file order
+    pub fn sweep_descriptors(&self) -> &[SweepDescriptor] {
+        &self.descriptors
+    }
+
+    /// Whether a sweep has already been read
+    pub fn is_loaded(&self, index: usize) -> bool {
+        self.sweeps.get(index).is_some_and(|s| s.get().is_some())
+    }
+
+    /// Index of the first sweep whose fixed angle is within `tolerance` degrees of `angle`
+    pub fn find_sweep(&self, angle: f64, tolerance: f64) -> Option<usize> {
+        self.descriptors
+            .iter()
+            .find(|d| d.fixed_angle.is_some_and(|a| (a - angle).abs() <= tolerance))
+            .map(|d| d.index)
+    }
+
+    /// Get a sweep, reading it from the file on first access
+    pub fn get_sweep(&self, index: usize) -> Result<&SweepData> {
+        let cell = self.sweeps.get(index).ok_or(RadishError::InvalidSweepIndex(index))?;
+        if let Some(sweep) = cell.get() {
+            return Ok(sweep);
+        }
+        let sweep = self.source.read_sweep(self.backend.as_ref(), index)?;
+        // Another thread may have read it meanwhile; either copy is the same sweep
+        Ok(cell.get_or_init(|| sweep))
+    }
+
+    /// Read the remaining sweeps and return the full volume
+    ///
+    /// Calibration data is only available from [`RadarBackend::read_volume`]
+    /// and is not set.
+    pub fn into_volume(self) -> Result<VolumeData> {
+        let mut sweeps = Vec::with_capacity(self.sweeps.len());
+        for (index, cell) in self.sweeps.into_iter().enumerate() {
+            match cell.into_inner() {
+                Some(sweep) => sweeps.push(sweep),
+                None => sweeps.push(self.source.read_sweep(self.backend.as_ref(), index)?),
+            }
+        }
+        Ok(VolumeData::new(self.metadata, sweeps))
+    }
+}
+
+impl std::fmt::Debug for LazyVolume {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LazyVolume")
+            .field("backend", &self.backend.name())
+            .field("path", &self.path())
+            .field("descriptors", &self.descriptors)
+            .field("loaded", &(0..self.sweeps.len()).filter(|&i| self.is_loaded(i)).collect::<Vec<_>>())
+            .finish()
+    }
+}
@@ -1,11 +1,19 @@
-/// Backend system for reading different radar formats
+//! Backend system for reading different radar formats
 
+use std::io::Read;
 use std::path::Path;
 use crate::{Result, VolumeData, VolumeMetadata, SweepData};
 
 pub mod cfradial1;
+pub mod cinrad;
+pub mod lazy;
 
 pub use cfradial1::CfRadial1Backend;
+pub use cinrad::{CinradBackend, CinradSite};
+pub use lazy::{LazyVolume, SweepDescriptor};
+
+/// Number of leading bytes of a file read to recognize its format
+pub const SNIFF_LEN: usize = 4096;
 
 /// Trait for radar file format backends
 ///
@@ -24,7 +32,10 @@ pub trait RadarBackend: Send + Sync {
     /// Scan file to extract volume metadata without reading all data
     ///
     /// This is useful for quickly determining what's in a file before
-    /// committing to reading the full volume.
+    /// committing to reading the full volume. Implementations must only read
+    /// headers, attributes, dimensions and small per-sweep variables, never
+    /// moment data or per-ray coordinates, so that scanning stays fast for
+    /// multi-GB aggregated files.
     fn scan_file(&self, path: &Path) -> Result<VolumeMetadata>;
 
     /// Read a specific sweep from the file
@@ -37,16 +48,40 @@ pub trait RadarBackend: Send + Sync {
     /// This is the primary method for loading radar data.
     fn read_volume(&self, path: &Path) -> Result<VolumeData>;
 
+    /// Scan a file held in memory for its volume metadata
+    ///
+    /// `name` is the file name, if known, for formats that record part of
+    /// their metadata in it. The default reports that the backend only
+    /// reads files.
+    fn scan_bytes(&self, _bytes: &[u8], _name: Option<&str>) -> Result<VolumeMetadata> {
+        Err(memory_unsupported(self.name()))
+    }
+
+    /// Read a specific sweep from a file held in memory
+    fn read_sweep_bytes(&self, _bytes: &[u8], _name: Option<&str>, _sweep_idx: usize) -> Result<SweepData> {
+        Err(memory_unsupported(self.name()))
+    }
+
+    /// Read the entire volume from a file held in memory
+    fn read_volume_bytes(&self, _bytes: &[u8], _name: Option<&str>) -> Result<VolumeData> {
+        Err(memory_unsupported(self.name()))
+    }
+
+    /// Check whether the leading bytes of a file are in this backend's format
+    ///
+    /// `head` holds the first [`SNIFF_LEN`] bytes of a file of `len` bytes
+    /// (all of it for shorter files). The default recognizes nothing.
+    fn sniff(&self, _head: &[u8], _len: u64) -> bool {
+        false
+    }
+
     /// Check if this backend can read the given file
     ///
-    /// Default implementation checks file extension.
+    /// Default implementation checks the file contents with
+    /// [`sniff`](Self::sniff), then the file extension.
     fn can_read(&self, path: &Path) -> bool {
-        if let Some(ext) = path.extension() {
-            if let Some(ext_str) = ext.to_str() {
-                return self.supported_extensions().contains(&ext_str);
-            }
-        }
-        false
+        read_head(path).is_some_and(|(head, len)| self.sniff(&head, len))
+            || has_extension(path, self.supported_extensions())
     }
 }
 
@@ -54,14 +89,22 @@ pub trait RadarBackend: Send + Sync {
 pub fn available_backends() -> Vec<Box<dyn RadarBackend>> {
     vec![
         Box::new(CfRadial1Backend::new()),
+        Box::new(CinradBackend::new()),
         // Add more backends here as they're implemented
     ]
 }
 
 /// Automatically select the appropriate backend for a file
+///
+/// The backend is chosen from the leading bytes of the file, falling back
+/// to the extension for files no backend recognizes (so that reading them
+/// reports the backend's error).
 pub fn auto_backend(path: &Path) -> Result<Box<dyn RadarBackend>> {
+    if let Some(backend) = read_head(path).and_then(|(head, len)| sniff_backend(&head, len)) {
+        return Ok(backend);
+    }
     for backend in available_backends() {
-        if backend.can_read(path) {
+        if has_extension(path, backend.supported_extensions()) {
             return Ok(backend);
         }
     }
@@ -71,3 +114,35 @@ pub fn auto_backend(path: &Path) -> Result<Box<dyn RadarBackend>> {
         path.display()
     )))
 }
+
+/// Select the backend for a file held in memory from its contents
+pub fn auto_backend_bytes(bytes: &[u8]) -> Result<Box<dyn RadarBackend>> {
+    let head = &bytes[..bytes.len().min(SNIFF_LEN)];
+    sniff_backend(head, bytes.len() as u64).ok_or_else(|| {
+        crate::RadishError::InvalidFormat("No backend recognizes the data".to_string())
+    })
+}
+
+/// Select the backend recognizing the leading bytes of a file of `len` bytes
+pub fn sniff_backend(head: &[u8], len: u64) -> Option<Box<dyn RadarBackend>> {
+    available_backends().into_iter().find(|backend| backend.sniff(head, len))
+}
+
+/// Leading bytes and length of a file, `None` if it can't be read
+fn read_head(path: &Path) -> Option<(Vec<u8>, u64)> {
+    let file = std::fs::File::open(path).ok()?;
+    let len = file.metadata().ok()?.len();
+    let mut head = Vec::with_capacity(SNIFF_LEN);
+    file.take(SNIFF_LEN as u64).read_to_end(&mut head).ok()?;
+    Some((head, len))
+}
+
+fn memory_unsupported(backend: &str) -> crate::RadishError {
+    crate::RadishError::Unsupported(format!("The {} backend can't read from memory", backend))
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| extensions.contains(&ext))
+}
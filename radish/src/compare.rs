@@ -0,0 +1,432 @@
+//! Approximate comparison of radar data
+//!
+//! Writers and format converters rarely reproduce moment data bit for bit:
+//! packing to 8/16-bit integers quantizes values to the scale factor. A
+//! [`ToleranceSpec`] describes how close two moments must be, per moment,
+//! so that round trips can be validated at the precision the format
+//! actually stores. [`diff_volumes`] compares whole volumes and reports
+//! every difference in a [`DiffReport`].
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Debug;
+use std::str::FromStr;
+
+use crate::model::coordinates::azimuth_distance;
+use crate::{MomentData, RadishError, Result, SweepData, VolumeData};
+
+/// Tolerance for coordinates compared by [`diff_volumes`] (degrees, meters or seconds)
+const COORDINATE_TOLERANCE: f64 = 1e-3;
+
+/// Absolute and relative tolerance for comparing values
+///
+/// Two values `a` and `b` are close if `|a - b| <= absolute + relative * |b|`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Tolerance {
+    /// Absolute tolerance (moment units)
+    pub absolute: f32,
+    /// Relative tolerance (fraction of the expected value)
+    pub relative: f32,
+}
+
+impl Tolerance {
+    /// Exact equality
+    pub fn exact() -> Self {
+        Self::default()
+    }
+
+    /// Absolute tolerance only
+    pub fn absolute(absolute: f32) -> Self {
+        Self {
+            absolute,
+            relative: 0.0,
+        }
+    }
+
+    /// Relative tolerance only
+    pub fn relative(relative: f32) -> Self {
+        Self {
+            absolute: 0.0,
+            relative,
+        }
+    }
+
+    /// Tolerance for data packed to integers with the given scale factor
+    ///
+    /// Packing rounds to the nearest step, so values agree to half a step.
+    pub fn from_packing(scale_factor: f32) -> Self {
+        Self::absolute(scale_factor.abs() / 2.0)
+    }
+
+    /// Check whether `actual` is within tolerance of `expected`
+    ///
+    /// Two NaNs compare equal, so that missing gates match.
+    pub fn is_close(&self, actual: f32, expected: f32) -> bool {
+        if actual.is_nan() || expected.is_nan() {
+            return actual.is_nan() && expected.is_nan();
+        }
+        (actual - expected).abs() <= self.absolute + self.relative * expected.abs()
+    }
+}
+
+/// Tolerances for comparing volumes, with per-moment overrides
+///
+/// A spec can be parsed from a string such as `"*=1e-6,DBZH=0.5,ZDR=0.01:0.001"`,
+/// where each entry is `moment=absolute[:relative]` and `*` sets the default.
+/// This lets test and validation runs select tolerances at run time.
+#[derive(Debug, Clone, Default)]
+pub struct ToleranceSpec {
+    /// Tolerance for moments without an override
+    pub default: Tolerance,
+    /// Per-moment overrides
+    pub moments: HashMap<String, Tolerance>,
+}
+
+impl ToleranceSpec {
+    /// Create a spec with the given default tolerance
+    pub fn new(default: Tolerance) -> Self {
+        Self {
+            default,
+            moments: HashMap::new(),
+        }
+    }
+
+    /// Override the tolerance for one moment
+    pub fn with_moment(mut self, name: impl Into<String>, tolerance: Tolerance) -> Self {
+        self.moments.insert(name.into(), tolerance);
+        self
+    }
+
+    /// Tolerance that applies to a moment
+    pub fn for_moment(&self, name: &str) -> Tolerance {
+        self.moments.get(name).copied().unwrap_or(self.default)
+    }
+
+    /// Build a spec from the packing of the moments in a volume
+    ///
+    /// Every moment with a scale factor gets [`Tolerance::from_packing`];
+    /// other moments use `default`.
+    pub fn from_packing(volume: &VolumeData, default: Tolerance) -> Self {
+        let mut spec = Self::new(default);
+        for moment in volume.sweeps.iter().flat_map(|s| s.moments.values()) {
+            if let Some(scale) = moment.scale_factor {
+                let packed = Tolerance::from_packing(scale);
+                let entry = spec.moments.entry(moment.name.clone()).or_insert(packed);
+                // Keep the loosest tolerance if sweeps pack the moment differently
+                if packed.absolute > entry.absolute {
+                    *entry = packed;
+                }
+            }
+        }
+        spec
+    }
+}
+
+impl FromStr for ToleranceSpec {
+    type Err = RadishError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut spec = Self::default();
+        for entry in s.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (name, value) = entry
+                .split_once('=')
+                .ok_or_else(|| RadishError::InvalidFormat(format!("Invalid tolerance entry: {}", entry)))?;
+
+            let mut parts = value.splitn(2, ':');
+            let absolute = parse_tolerance_value(parts.next().unwrap_or(""), entry)?;
+            let relative = match parts.next() {
+                Some(rel) => parse_tolerance_value(rel, entry)?,
+                None => 0.0,
+            };
+
+            let tolerance = Tolerance { absolute, relative };
+            match name.trim() {
+                "*" => spec.default = tolerance,
+                name => {
+                    spec.moments.insert(name.to_string(), tolerance);
+                }
+            }
+        }
+        Ok(spec)
+    }
+}
+
+fn parse_tolerance_value(value: &str, entry: &str) -> Result<f32> {
+    value
+        .trim()
+        .parse::<f32>()
+        .ok()
+        .filter(|v| v.is_finite() && *v >= 0.0)
+        .ok_or_else(|| RadishError::InvalidFormat(format!("Invalid tolerance value in: {}", entry)))
+}
+
+/// Find the first gate where two moments differ by more than the tolerance
+///
+/// Invalid gates (masked, fill value, NaN or outside the valid range) are
+/// treated as missing and only match other missing gates. Returns the
+/// (ray, gate) index of the first mismatch, or an error if the shapes differ.
+pub fn first_mismatch(
+    actual: &MomentData,
+    expected: &MomentData,
+    tolerance: &Tolerance,
+) -> Result<Option<(usize, usize)>> {
+    if actual.shape() != expected.shape() {
+        return Err(RadishError::General(format!(
+            "Moment {} shape {:?} does not match expected {:?}",
+            actual.name,
+            actual.shape(),
+            expected.shape()
+        )));
+    }
+
+    let (num_rays, num_gates) = actual.shape();
+    let mismatch = (0..num_rays)
+        .flat_map(|i| (0..num_gates).map(move |j| (i, j)))
+        .find(|&(i, j)| {
+            let a = actual.valid_value(i, j).unwrap_or(f32::NAN);
+            let b = expected.valid_value(i, j).unwrap_or(f32::NAN);
+            !tolerance.is_close(a, b)
+        });
+
+    Ok(mismatch)
+}
+
+/// A metadata or coordinate field that differs between two volumes
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldDiff {
+    /// Field name, prefixed by `sweep_<index>.` for sweep fields
+    pub field: String,
+    /// Value in the compared volume
+    pub actual: String,
+    /// Value in the reference volume
+    pub expected: String,
+}
+
+/// A moment present in only one of two volumes
+#[derive(Debug, Clone, PartialEq)]
+pub struct MissingMoment {
+    /// Sweep index
+    pub sweep: usize,
+    /// Moment name
+    pub name: String,
+    /// Whether the moment is missing from the compared volume (`true`) or the reference (`false`)
+    pub missing_from_actual: bool,
+}
+
+/// Differences between the values of a moment in two volumes
+#[derive(Debug, Clone, PartialEq)]
+pub struct MomentDiff {
+    /// Sweep index
+    pub sweep: usize,
+    /// Moment name
+    pub name: String,
+    /// Shapes of the compared and reference moments, when they differ; no
+    /// values are compared then
+    pub shape_mismatch: Option<((usize, usize), (usize, usize))>,
+    /// Gates valid in both moments
+    pub compared: usize,
+    /// Gates valid in only one of the moments
+    pub validity_mismatches: usize,
+    /// Gates valid in both moments whose values are not within tolerance
+    pub exceeding: usize,
+    /// Largest absolute difference of the gates valid in both
+    pub max_abs_diff: f32,
+    /// Mean absolute difference of the gates valid in both
+    pub mean_abs_diff: f64,
+}
+
+impl MomentDiff {
+    /// Whether the moments agree within tolerance
+    pub fn is_close(&self) -> bool {
+        self.shape_mismatch.is_none() && self.validity_mismatches == 0 && self.exceeding == 0
+    }
+}
+
+/// Differences found by [`diff_volumes`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DiffReport {
+    /// Differing metadata and coordinate fields
+    pub fields: Vec<FieldDiff>,
+    /// Moments present in only one volume
+    pub missing_moments: Vec<MissingMoment>,
+    /// Value differences of every moment present in both volumes, including those within tolerance
+    pub moments: Vec<MomentDiff>,
+}
+
+impl DiffReport {
+    /// Whether the volumes agree within tolerance
+    pub fn is_close(&self) -> bool {
+        self.fields.is_empty() && self.missing_moments.is_empty() && self.moments.iter().all(MomentDiff::is_close)
+    }
+
+    fn field<T: PartialEq + Debug>(&mut self, field: impl Into<String>, actual: T, expected: T) {
+        if actual != expected {
+            self.fields.push(FieldDiff {
+                field: field.into(),
+                actual: format!("{:?}", actual),
+                expected: format!("{:?}", expected),
+            });
+        }
+    }
+
+    /// Record the largest difference of a coordinate, if any is above [`COORDINATE_TOLERANCE`]
+    fn coordinate<T: Copy + Into<f64>>(&mut self, field: String, actual: &[T], expected: &[T]) {
+        self.coordinate_by(field, actual, expected, |a, b| (a - b).abs());
+    }
+
+    /// Like [`Self::coordinate`], measuring differences with `distance`
+    fn coordinate_by<T: Copy + Into<f64>>(
+        &mut self,
+        field: String,
+        actual: &[T],
+        expected: &[T],
+        distance: impl Fn(f64, f64) -> f64,
+    ) {
+        if actual.len() != expected.len() {
+            self.field(format!("{}.len", field), actual.len(), expected.len());
+            return;
+        }
+        let worst = actual
+            .iter()
+            .zip(expected)
+            .map(|(&a, &b)| (a.into(), b.into()))
+            .filter(|(a, b): &(f64, f64)| !(a.is_nan() && b.is_nan()))
+            .map(|(a, b)| (distance(a, b), a, b))
+            .filter(|(d, _, _)| d.is_nan() || *d > COORDINATE_TOLERANCE)
+            .max_by(|x, y| x.0.total_cmp(&y.0));
+        if let Some((_, a, b)) = worst {
+            self.fields.push(FieldDiff {
+                field,
+                actual: a.to_string(),
+                expected: b.to_string(),
+            });
+        }
+    }
+}
+
+impl std::fmt::Display for DiffReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_close() {
+            return writeln!(f, "Volumes agree within tolerance");
+        }
+        for d in &self.fields {
+            writeln!(f, "{}: {} != {}", d.field, d.actual, d.expected)?;
+        }
+        for m in &self.missing_moments {
+            let side = if m.missing_from_actual { "actual" } else { "expected" };
+            writeln!(f, "sweep_{}.{}: missing from {}", m.sweep, m.name, side)?;
+        }
+        for m in self.moments.iter().filter(|m| !m.is_close()) {
+            match m.shape_mismatch {
+                Some((a, b)) => writeln!(f, "sweep_{}.{}: shape {:?} != {:?}", m.sweep, m.name, a, b)?,
+                None => writeln!(
+                    f,
+                    "sweep_{}.{}: {} of {} gates beyond tolerance, {} validity mismatches, max |diff| {}, mean |diff| {:.6}",
+                    m.sweep, m.name, m.exceeding, m.compared, m.validity_mismatches, m.max_abs_diff, m.mean_abs_diff
+                )?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Compare a volume with a reference volume
+///
+/// Volume metadata, the sweep metadata and coordinates of sweeps with the
+/// same index, and the moments of those sweeps are compared. Moment values
+/// use the tolerances of `tolerances`; invalid gates only match invalid
+/// gates. Coordinates agree to 1e-3 degrees, meters or seconds.
+pub fn diff_volumes(actual: &VolumeData, expected: &VolumeData, tolerances: &ToleranceSpec) -> DiffReport {
+    let mut report = DiffReport::default();
+    let (a, b) = (&actual.metadata, &expected.metadata);
+    report.field("instrument_name", &a.instrument_name, &b.instrument_name);
+    report.field("institution", &a.institution, &b.institution);
+    report.field("site_name", &a.site_name, &b.site_name);
+    report.field("platform_type", a.platform_type, b.platform_type);
+    report.coordinate("latitude".to_string(), &[a.latitude], &[b.latitude]);
+    report.coordinate("longitude".to_string(), &[a.longitude], &[b.longitude]);
+    report.coordinate("altitude".to_string(), &[a.altitude], &[b.altitude]);
+    report.field("time_coverage_start", a.time_coverage_start, b.time_coverage_start);
+    report.field("time_coverage_end", a.time_coverage_end, b.time_coverage_end);
+    report.field("frequency", a.frequency, b.frequency);
+    report.field("instrument_parameters", &a.instrument_parameters, &b.instrument_parameters);
+    report.field("num_sweeps", actual.num_sweeps(), expected.num_sweeps());
+
+    for (k, (sa, sb)) in actual.sweeps.iter().zip(&expected.sweeps).enumerate() {
+        diff_sweeps(&mut report, k, sa, sb, tolerances);
+    }
+    report
+}
+
+fn diff_sweeps(report: &mut DiffReport, k: usize, actual: &SweepData, expected: &SweepData, tolerances: &ToleranceSpec) {
+    let (a, b) = (&actual.metadata, &expected.metadata);
+    let name = |field: &str| format!("sweep_{}.{}", k, field);
+    report.field(name("sweep_mode"), a.sweep_mode, b.sweep_mode);
+    report.coordinate(name("fixed_angle"), &[a.fixed_angle], &[b.fixed_angle]);
+    report.field(name("prt_mode"), a.prt_mode, b.prt_mode);
+    report.field(name("prf"), a.prf, b.prf);
+    report.field(name("nyquist_velocity"), a.nyquist_velocity, b.nyquist_velocity);
+    report.field(name("unambiguous_range"), a.unambiguous_range, b.unambiguous_range);
+
+    let (ca, cb) = (&actual.coordinates, &expected.coordinates);
+    report.coordinate(name("time"), &ca.epoch_times(), &cb.epoch_times());
+    report.coordinate(name("range"), &ca.range, &cb.range);
+    report.coordinate_by(name("azimuth"), &ca.azimuth, &cb.azimuth, |a, b| {
+        azimuth_distance(a as f32, b as f32) as f64
+    });
+    report.coordinate(name("elevation"), &ca.elevation, &cb.elevation);
+
+    let names: BTreeSet<&String> = actual.moments.keys().chain(expected.moments.keys()).collect();
+    for moment in names {
+        match (actual.get_moment(moment), expected.get_moment(moment)) {
+            (Some(ma), Some(mb)) => report
+                .moments
+                .push(diff_moments(k, ma, mb, &tolerances.for_moment(moment))),
+            (a, _) => report.missing_moments.push(MissingMoment {
+                sweep: k,
+                name: moment.clone(),
+                missing_from_actual: a.is_none(),
+            }),
+        }
+    }
+}
+
+fn diff_moments(sweep: usize, actual: &MomentData, expected: &MomentData, tolerance: &Tolerance) -> MomentDiff {
+    let mut diff = MomentDiff {
+        sweep,
+        name: expected.name.clone(),
+        shape_mismatch: None,
+        compared: 0,
+        validity_mismatches: 0,
+        exceeding: 0,
+        max_abs_diff: 0.0,
+        mean_abs_diff: 0.0,
+    };
+    if actual.shape() != expected.shape() {
+        diff.shape_mismatch = Some((actual.shape(), expected.shape()));
+        return diff;
+    }
+
+    let (num_rays, num_gates) = expected.shape();
+    let mut sum = 0.0f64;
+    for i in 0..num_rays {
+        for j in 0..num_gates {
+            match (actual.valid_value(i, j), expected.valid_value(i, j)) {
+                (Some(a), Some(b)) => {
+                    let d = (a - b).abs();
+                    diff.compared += 1;
+                    diff.max_abs_diff = diff.max_abs_diff.max(d);
+                    sum += d as f64;
+                    if !tolerance.is_close(a, b) {
+                        diff.exceeding += 1;
+                    }
+                }
+                (None, None) => {}
+                _ => diff.validity_mismatches += 1,
+            }
+        }
+    }
+    if diff.compared > 0 {
+        diff.mean_abs_diff = sum / diff.compared as f64;
+    }
+    diff
+}
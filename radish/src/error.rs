@@ -1,4 +1,4 @@
-/// Error types for the radish library
+//! Error types for the radish library
 
 use thiserror::Error;
 
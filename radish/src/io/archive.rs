@@ -0,0 +1,400 @@
+//! Reading radar files from tar and zip archives
+//!
+//! Archives such as the NCEI historical tarballs bundle many (often
+//! individually gzip-compressed) volume files. Members are iterated in
+//! archive order without extracting the archive to disk: plain and
+//! gzip-compressed tar archives are streamed, zip archives are read through
+//! their central directory (stored and deflated members). Members ending in
+//! `.gz` are decompressed and lose the suffix. Members are read from memory
+//! and never written to disk; no member may exceed [`MAX_MEMBER_SIZE`]
+//! bytes, and deflated zip members may not exceed their declared size.
+//!
+//! Only members in a format radish has a backend for are read. There is no
+//! NEXRAD Level II backend, so NCEI NEXRAD tarballs can't be read: every
+//! member is passed over and [`read_archive_volumes`] returns no volumes.
+
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use flate2::read::{DeflateDecoder, MultiGzDecoder};
+
+use crate::backends::auto_backend_bytes;
+use crate::{RadishError, Result, VolumeData};
+
+/// Largest member, after decompression, read from an archive (4 GiB)
+pub const MAX_MEMBER_SIZE: u64 = 1 << 32;
+
+const TAR_BLOCK: usize = 512;
+const ZIP_END_OF_DIRECTORY: u32 = 0x0605_4b50;
+const ZIP_DIRECTORY_ENTRY: u32 = 0x0201_4b50;
+const ZIP_LOCAL_HEADER: u32 = 0x0403_4b50;
+
+/// Container format of an archive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    /// Uncompressed tar
+    Tar,
+    /// Gzip-compressed tar (`.tar.gz`, `.tgz`)
+    TarGz,
+    /// Zip
+    Zip,
+}
+
+impl ArchiveFormat {
+    /// Detect the format of a file from its leading bytes
+    ///
+    /// Returns `None` for files that are not archives.
+    pub fn detect(path: impl AsRef<Path>) -> Result<Option<Self>> {
+        let path = path.as_ref();
+        let mut header = Vec::with_capacity(TAR_BLOCK);
+        File::open(path)?.take(TAR_BLOCK as u64).read_to_end(&mut header)?;
+        if header.starts_with(b"PK\x03\x04") || header.starts_with(b"PK\x05\x06") {
+            Ok(Some(ArchiveFormat::Zip))
+        } else if header.starts_with(&[0x1f, 0x8b]) {
+            // A gzip file is a tarball only if its content is a tar
+            let mut inner = Vec::with_capacity(TAR_BLOCK);
+            MultiGzDecoder::new(File::open(path)?)
+                .take(TAR_BLOCK as u64)
+                .read_to_end(&mut inner)
+                .ok();
+            Ok(is_tar_header(&inner).then_some(ArchiveFormat::TarGz))
+        } else {
+            Ok(is_tar_header(&header).then_some(ArchiveFormat::Tar))
+        }
+    }
+}
+
+/// A file read from an archive
+#[derive(Debug, Clone)]
+pub struct ArchiveMember {
+    /// Path of the member within the archive (without a `.gz` suffix if it was decompressed)
+    pub name: String,
+    /// Member content
+    pub data: Vec<u8>,
+}
+
+impl ArchiveMember {
+    /// Read the member as a volume with the backend that recognizes it
+    pub fn read_volume(&self) -> Result<VolumeData> {
+        self.try_read_volume()?.ok_or_else(|| {
+            RadishError::InvalidFormat(format!("No backend found for archive member: {}", self.name))
+        })
+    }
+
+    /// Read the member as a volume, or `None` if no backend recognizes it
+    ///
+    /// Backends are selected from the member content.
+    pub fn try_read_volume(&self) -> Result<Option<VolumeData>> {
+        let file_name = Path::new(&self.name).file_name().and_then(|n| n.to_str());
+        match auto_backend_bytes(&self.data) {
+            Ok(backend) => backend.read_volume_bytes(&self.data, file_name).map(Some),
+            Err(_) => Ok(None),
+        }
+    }
+
+    fn new(name: String, data: Vec<u8>) -> Result<Self> {
+        match name.strip_suffix(".gz") {
+            Some(stem) if data.starts_with(&[0x1f, 0x8b]) => {
+                let decompressed = read_bounded(
+                    &mut MultiGzDecoder::new(data.as_slice()),
+                    MAX_MEMBER_SIZE,
+                    &format!("Compressed member {} is larger than {} bytes", name, MAX_MEMBER_SIZE),
+                )?;
+                Ok(Self {
+                    name: stem.to_string(),
+                    data: decompressed,
+                })
+            }
+            _ => Ok(Self { name, data }),
+        }
+    }
+}
+
+/// Iterator over the regular file members of an archive
+pub struct ArchiveReader {
+    inner: ReaderKind,
+}
+
+enum ReaderKind {
+    Tar(Box<dyn Read>),
+    Zip {
+        file: BufReader<File>,
+        entries: std::vec::IntoIter<ZipEntry>,
+    },
+    Done,
+}
+
+struct ZipEntry {
+    name: String,
+    method: u16,
+    compressed_size: u64,
+    uncompressed_size: u64,
+    header_offset: u64,
+}
+
+impl ArchiveReader {
+    /// Open an archive, detecting its format
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let format = ArchiveFormat::detect(path)?.ok_or_else(|| {
+            RadishError::InvalidFormat(format!("Not a tar or zip archive: {}", path.display()))
+        })?;
+        let file = BufReader::new(File::open(path)?);
+        let inner = match format {
+            ArchiveFormat::Tar => ReaderKind::Tar(Box::new(file)),
+            ArchiveFormat::TarGz => ReaderKind::Tar(Box::new(MultiGzDecoder::new(file))),
+            ArchiveFormat::Zip => {
+                let mut file = file;
+                let entries = zip_directory(&mut file)?;
+                ReaderKind::Zip {
+                    file,
+                    entries: entries.into_iter(),
+                }
+            }
+        };
+        Ok(Self { inner })
+    }
+
+    fn next_tar(reader: &mut dyn Read) -> Result<Option<ArchiveMember>> {
+        let mut long_name = None;
+        loop {
+            let mut header = [0u8; TAR_BLOCK];
+            if !read_block(reader, &mut header)? || header.iter().all(|&b| b == 0) {
+                return Ok(None);
+            }
+            if !is_tar_header(&header) {
+                return Err(RadishError::InvalidFormat("Corrupt tar header".to_string()));
+            }
+            // The size is untrusted, so the data is read as it arrives
+            let size = tar_number(&header[124..136])?;
+            if size > MAX_MEMBER_SIZE {
+                return Err(RadishError::InvalidFormat(format!(
+                    "Tar member of {} bytes is larger than {} bytes",
+                    size, MAX_MEMBER_SIZE
+                )));
+            }
+            let data = read_exactly(&mut *reader, size, "Truncated tar archive")?;
+            let padding = (TAR_BLOCK as u64 - size % TAR_BLOCK as u64) % TAR_BLOCK as u64;
+            std::io::copy(&mut reader.take(padding), &mut std::io::sink())?;
+
+            match header[156] {
+                // GNU long name of the next member
+                b'L' => long_name = Some(c_string(&data)),
+                b'0' | 0 => {
+                    let name = long_name.take().unwrap_or_else(|| {
+                        let (name, prefix) = (c_string(&header[0..100]), c_string(&header[345..500]));
+                        if prefix.is_empty() || !header[257..262].eq(b"ustar") {
+                            name
+                        } else {
+                            format!("{}/{}", prefix, name)
+                        }
+                    });
+                    return ArchiveMember::new(name, data).map(Some);
+                }
+                // Directories, links and extended headers
+                _ => long_name = None,
+            }
+        }
+    }
+
+    fn next_zip(file: &mut BufReader<File>, entry: ZipEntry) -> Result<ArchiveMember> {
+        file.seek(SeekFrom::Start(entry.header_offset))?;
+        let mut header = [0u8; 30];
+        file.read_exact(&mut header)?;
+        if le_u32(&header[0..4]) != ZIP_LOCAL_HEADER {
+            return Err(RadishError::InvalidFormat(format!("Corrupt zip member: {}", entry.name)));
+        }
+        let skip = le_u16(&header[26..28]) as i64 + le_u16(&header[28..30]) as i64;
+        file.seek(SeekFrom::Current(skip))?;
+
+        let mut compressed = (&mut *file).take(entry.compressed_size);
+        let data = match entry.method {
+            0 => {
+                let mut data = Vec::new();
+                compressed.read_to_end(&mut data)?;
+                data
+            }
+            8 => read_bounded(
+                &mut DeflateDecoder::new(compressed),
+                entry.uncompressed_size.min(MAX_MEMBER_SIZE),
+                &format!("Zip member {} inflates beyond its declared size", entry.name),
+            )?,
+            method => {
+                return Err(RadishError::Unsupported(format!(
+                    "Zip compression method {} ({})",
+                    method, entry.name
+                )))
+            }
+        };
+        ArchiveMember::new(entry.name, data)
+    }
+}
+
+impl Iterator for ArchiveReader {
+    type Item = Result<ArchiveMember>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let result = match &mut self.inner {
+            ReaderKind::Tar(reader) => Self::next_tar(reader.as_mut()).transpose(),
+            ReaderKind::Zip { file, entries } => entries.next().map(|entry| Self::next_zip(file, entry)),
+            ReaderKind::Done => None,
+        };
+        // Stop after the end of the archive or a corrupt member
+        if !matches!(result, Some(Ok(_))) {
+            self.inner = ReaderKind::Done;
+        }
+        result
+    }
+}
+
+/// Read every member of an archive that a backend can read
+///
+/// Members no backend recognizes (e.g. README files) are skipped. Returns
+/// the member names with their volumes, in archive order.
+pub fn read_archive_volumes(path: impl AsRef<Path>) -> Result<Vec<(String, VolumeData)>> {
+    let mut volumes = Vec::new();
+    for member in ArchiveReader::open(path)? {
+        let member = member?;
+        if let Some(volume) = member.try_read_volume()? {
+            volumes.push((member.name, volume));
+        }
+    }
+    Ok(volumes)
+}
+
+/// Entries of the zip central directory
+fn zip_directory(file: &mut BufReader<File>) -> Result<Vec<ZipEntry>> {
+    let corrupt = || RadishError::InvalidFormat("Corrupt zip central directory".to_string());
+
+    // The end of central directory record is within the last 64 KiB + 22 bytes
+    let len = file.seek(SeekFrom::End(0))?;
+    let tail_len = len.min(65_557);
+    file.seek(SeekFrom::Start(len - tail_len))?;
+    let mut tail = Vec::with_capacity(tail_len as usize);
+    file.read_to_end(&mut tail)?;
+    let end = (0..tail.len().saturating_sub(21))
+        .rev()
+        .find(|&i| le_u32(&tail[i..i + 4]) == ZIP_END_OF_DIRECTORY)
+        .ok_or_else(corrupt)?;
+    let count = le_u16(&tail[end + 10..end + 12]) as usize;
+    let size = le_u32(&tail[end + 12..end + 16]) as u64;
+    let offset = le_u32(&tail[end + 16..end + 20]) as u64;
+
+    file.seek(SeekFrom::Start(offset))?;
+    let directory = read_exactly(&mut *file, size, "Corrupt zip central directory")?;
+
+    let mut entries = Vec::with_capacity(count);
+    let mut pos = 0;
+    for _ in 0..count {
+        let record = directory.get(pos..pos + 46).ok_or_else(corrupt)?;
+        if le_u32(&record[0..4]) != ZIP_DIRECTORY_ENTRY {
+            return Err(corrupt());
+        }
+        let name_len = le_u16(&record[28..30]) as usize;
+        let extra_len = le_u16(&record[30..32]) as usize;
+        let comment_len = le_u16(&record[32..34]) as usize;
+        let name = directory.get(pos + 46..pos + 46 + name_len).ok_or_else(corrupt)?;
+        let name = String::from_utf8_lossy(name).into_owned();
+        if !name.ends_with('/') {
+            entries.push(ZipEntry {
+                name,
+                method: le_u16(&record[10..12]),
+                compressed_size: le_u32(&record[20..24]) as u64,
+                uncompressed_size: le_u32(&record[24..28]) as u64,
+                header_offset: le_u32(&record[42..46]) as u64,
+            });
+        }
+        pos += 46 + name_len + extra_len + comment_len;
+    }
+    Ok(entries)
+}
+
+/// Read `len` bytes, failing with `message` if the stream ends first
+///
+/// The buffer grows with the data read rather than being allocated from
+/// `len`, which comes from the file and may be arbitrarily large.
+fn read_exactly(reader: &mut dyn Read, len: u64, message: &str) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    reader.take(len).read_to_end(&mut data)?;
+    if (data.len() as u64) < len {
+        return Err(RadishError::InvalidFormat(message.to_string()));
+    }
+    Ok(data)
+}
+
+/// Read a stream of at most `limit` bytes, failing with `message` if it is longer
+///
+/// Decompressed sizes are untrusted; reading stops one byte past the limit.
+fn read_bounded(reader: &mut dyn Read, limit: u64, message: &str) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    reader.take(limit.saturating_add(1)).read_to_end(&mut data)?;
+    if data.len() as u64 > limit {
+        return Err(RadishError::InvalidFormat(message.to_string()));
+    }
+    Ok(data)
+}
+
+/// Fill a tar block, returning false at a clean end of stream
+fn read_block(reader: &mut dyn Read, block: &mut [u8; TAR_BLOCK]) -> Result<bool> {
+    let mut filled = 0;
+    while filled < TAR_BLOCK {
+        match reader.read(&mut block[filled..])? {
+            0 if filled == 0 => return Ok(false),
+            0 => return Err(RadishError::InvalidFormat("Truncated tar archive".to_string())),
+            n => filled += n,
+        }
+    }
+    Ok(true)
+}
+
+/// Whether a block is a tar header with a valid checksum
+fn is_tar_header(block: &[u8]) -> bool {
+    if block.len() < TAR_BLOCK {
+        return false;
+    }
+    let Ok(checksum) = tar_number(&block[148..156]) else {
+        return false;
+    };
+    let sum: u64 = block[..TAR_BLOCK]
+        .iter()
+        .enumerate()
+        .map(|(i, &b)| if (148..156).contains(&i) { b' ' as u64 } else { b as u64 })
+        .sum();
+    sum == checksum
+}
+
+/// Octal (or GNU base-256) numeric tar field
+fn tar_number(field: &[u8]) -> Result<u64> {
+    if field[0] & 0x80 != 0 {
+        // Base-256: the value is big-endian in the bits after the marker bit;
+        // negative (0xff) and oversized values are invalid
+        let invalid = || RadishError::InvalidFormat("Invalid base-256 tar number field".to_string());
+        if field[0] == 0xff {
+            return Err(invalid());
+        }
+        return field[1..].iter().try_fold((field[0] & 0x7f) as u64, |n, &b| {
+            n.checked_mul(256).map(|n| n | b as u64).ok_or_else(invalid)
+        });
+    }
+    let text = c_string(field);
+    let text = text.trim();
+    if text.is_empty() {
+        return Ok(0);
+    }
+    u64::from_str_radix(text, 8)
+        .map_err(|_| RadishError::InvalidFormat(format!("Invalid tar number field '{}'", text)))
+}
+
+fn c_string(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+fn le_u16(b: &[u8]) -> u16 {
+    u16::from_le_bytes([b[0], b[1]])
+}
+
+fn le_u32(b: &[u8]) -> u32 {
+    u32::from_le_bytes([b[0], b[1], b[2], b[3]])
+}
@@ -0,0 +1,551 @@
+//! Self-describing binary encoding of the serde data model
+//!
+//! Every value starts with a one-byte tag followed by its contents:
+//! little-endian scalars, `u64`-length-prefixed strings and byte strings,
+//! and `u64`-count-prefixed sequences and maps. Structs are maps keyed by
+//! field name and enum variants are the variant name followed by their
+//! contents. Floats are stored bit for bit, so NaN and infinities survive,
+//! and the tags allow untagged enums such as `AttributeValue` to be decoded.
+
+use std::fmt;
+
+use serde::de::{self, DeserializeSeed, EnumAccess, MapAccess, SeqAccess, VariantAccess, Visitor};
+use serde::ser::{self, Serialize};
+use serde::Deserialize;
+
+use crate::{RadishError, Result};
+
+const NONE: u8 = 0;
+const SOME: u8 = 1;
+const UNIT: u8 = 2;
+const BOOL: u8 = 3;
+const INT: u8 = 4;
+const UINT: u8 = 5;
+const F32: u8 = 6;
+const F64: u8 = 7;
+const STR: u8 = 8;
+const BYTES: u8 = 9;
+const SEQ: u8 = 10;
+const MAP: u8 = 11;
+const VARIANT: u8 = 12;
+
+/// Encode a value
+pub(super) fn to_bytes<T: Serialize>(value: &T) -> Result<Vec<u8>> {
+    let mut encoder = Encoder { out: Vec::new() };
+    value.serialize(&mut encoder).map_err(|e| RadishError::Conversion(e.0))?;
+    Ok(encoder.out)
+}
+
+/// Decode a value, failing with `InvalidFormat` on malformed or trailing bytes
+pub(super) fn from_bytes<'de, T: Deserialize<'de>>(bytes: &'de [u8]) -> Result<T> {
+    let invalid = |e: Error| RadishError::InvalidFormat(format!("Volume cache header: {}", e.0));
+    let mut decoder = Decoder { bytes, position: 0 };
+    let value = T::deserialize(&mut decoder).map_err(invalid)?;
+    if decoder.position != bytes.len() {
+        return Err(invalid(Error("trailing bytes".to_string())));
+    }
+    Ok(value)
+}
+
+#[derive(Debug)]
+pub(super) struct Error(String);
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error(msg.to_string())
+    }
+}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error(msg.to_string())
+    }
+}
+
+struct Encoder {
+    out: Vec<u8>,
+}
+
+impl Encoder {
+    fn len(&mut self, len: usize) {
+        self.out.extend((len as u64).to_le_bytes());
+    }
+
+    fn str(&mut self, value: &str) {
+        self.out.push(STR);
+        self.len(value.len());
+        self.out.extend(value.as_bytes());
+    }
+
+    /// Start a sequence or map whose count is filled in by [`Compound`]
+    fn compound(&mut self, tag: u8) -> Compound<'_> {
+        self.out.push(tag);
+        let count_at = self.out.len();
+        self.len(0);
+        Compound {
+            encoder: self,
+            count_at,
+            count: 0,
+        }
+    }
+}
+
+struct Compound<'a> {
+    encoder: &'a mut Encoder,
+    count_at: usize,
+    count: u64,
+}
+
+impl Compound<'_> {
+    fn element<T: Serialize + ?Sized>(&mut self, value: &T) -> std::result::Result<(), Error> {
+        self.count += 1;
+        value.serialize(&mut *self.encoder)
+    }
+
+    fn finish(self) -> std::result::Result<(), Error> {
+        self.encoder.out[self.count_at..self.count_at + 8].copy_from_slice(&self.count.to_le_bytes());
+        Ok(())
+    }
+}
+
+impl<'a> ser::Serializer for &'a mut Encoder {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = Compound<'a>;
+    type SerializeTuple = Compound<'a>;
+    type SerializeTupleStruct = Compound<'a>;
+    type SerializeTupleVariant = Compound<'a>;
+    type SerializeMap = Compound<'a>;
+    type SerializeStruct = Compound<'a>;
+    type SerializeStructVariant = Compound<'a>;
+
+    fn serialize_bool(self, v: bool) -> std::result::Result<(), Error> {
+        self.out.extend([BOOL, v as u8]);
+        Ok(())
+    }
+
+    fn serialize_i8(self, v: i8) -> std::result::Result<(), Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i16(self, v: i16) -> std::result::Result<(), Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i32(self, v: i32) -> std::result::Result<(), Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i64(self, v: i64) -> std::result::Result<(), Error> {
+        self.out.push(INT);
+        self.out.extend(v.to_le_bytes());
+        Ok(())
+    }
+
+    fn serialize_u8(self, v: u8) -> std::result::Result<(), Error> {
+        self.serialize_u64(v.into())
+    }
+
+    fn serialize_u16(self, v: u16) -> std::result::Result<(), Error> {
+        self.serialize_u64(v.into())
+    }
+
+    fn serialize_u32(self, v: u32) -> std::result::Result<(), Error> {
+        self.serialize_u64(v.into())
+    }
+
+    fn serialize_u64(self, v: u64) -> std::result::Result<(), Error> {
+        self.out.push(UINT);
+        self.out.extend(v.to_le_bytes());
+        Ok(())
+    }
+
+    fn serialize_f32(self, v: f32) -> std::result::Result<(), Error> {
+        self.out.push(F32);
+        self.out.extend(v.to_le_bytes());
+        Ok(())
+    }
+
+    fn serialize_f64(self, v: f64) -> std::result::Result<(), Error> {
+        self.out.push(F64);
+        self.out.extend(v.to_le_bytes());
+        Ok(())
+    }
+
+    fn serialize_char(self, v: char) -> std::result::Result<(), Error> {
+        self.str(v.encode_utf8(&mut [0; 4]));
+        Ok(())
+    }
+
+    fn serialize_str(self, v: &str) -> std::result::Result<(), Error> {
+        self.str(v);
+        Ok(())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> std::result::Result<(), Error> {
+        self.out.push(BYTES);
+        self.len(v.len());
+        self.out.extend(v);
+        Ok(())
+    }
+
+    fn serialize_none(self) -> std::result::Result<(), Error> {
+        self.out.push(NONE);
+        Ok(())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> std::result::Result<(), Error> {
+        self.out.push(SOME);
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> std::result::Result<(), Error> {
+        self.out.push(UNIT);
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> std::result::Result<(), Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> std::result::Result<(), Error> {
+        self.out.push(VARIANT);
+        self.str(variant);
+        self.serialize_unit()
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> std::result::Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> std::result::Result<(), Error> {
+        self.out.push(VARIANT);
+        self.str(variant);
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> std::result::Result<Compound<'a>, Error> {
+        Ok(self.compound(SEQ))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> std::result::Result<Compound<'a>, Error> {
+        Ok(self.compound(SEQ))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> std::result::Result<Compound<'a>, Error> {
+        Ok(self.compound(SEQ))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> std::result::Result<Compound<'a>, Error> {
+        self.out.push(VARIANT);
+        self.str(variant);
+        Ok(self.compound(SEQ))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> std::result::Result<Compound<'a>, Error> {
+        Ok(self.compound(MAP))
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> std::result::Result<Compound<'a>, Error> {
+        Ok(self.compound(MAP))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> std::result::Result<Compound<'a>, Error> {
+        self.out.push(VARIANT);
+        self.str(variant);
+        Ok(self.compound(MAP))
+    }
+}
+
+impl ser::SerializeSeq for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> std::result::Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> std::result::Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> std::result::Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> std::result::Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> std::result::Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> std::result::Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> std::result::Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> std::result::Result<(), Error> {
+        self.finish()
+    }
+}
+
+/// Map entries count once, for the key
+impl ser::SerializeMap for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> std::result::Result<(), Error> {
+        self.element(key)
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> std::result::Result<(), Error> {
+        value.serialize(&mut *self.encoder)
+    }
+
+    fn end(self) -> std::result::Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> std::result::Result<(), Error> {
+        self.element(key)?;
+        value.serialize(&mut *self.encoder)
+    }
+
+    fn end(self) -> std::result::Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> std::result::Result<(), Error> {
+        self.element(key)?;
+        value.serialize(&mut *self.encoder)
+    }
+
+    fn end(self) -> std::result::Result<(), Error> {
+        self.finish()
+    }
+}
+
+struct Decoder<'de> {
+    bytes: &'de [u8],
+    position: usize,
+}
+
+impl<'de> Decoder<'de> {
+    fn take(&mut self, len: usize) -> std::result::Result<&'de [u8], Error> {
+        let end = self
+            .position
+            .checked_add(len)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| Error("unexpected end".to_string()))?;
+        let slice = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(slice)
+    }
+
+    fn array<const N: usize>(&mut self) -> std::result::Result<[u8; N], Error> {
+        Ok(self.take(N)?.try_into().expect("slice of length N"))
+    }
+
+    fn len(&mut self) -> std::result::Result<usize, Error> {
+        usize::try_from(u64::from_le_bytes(self.array()?)).map_err(|e| Error(e.to_string()))
+    }
+
+    fn tag(&mut self) -> std::result::Result<u8, Error> {
+        Ok(self.take(1)?[0])
+    }
+}
+
+impl<'de> de::Deserializer<'de> for &mut Decoder<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> std::result::Result<V::Value, Error> {
+        match self.tag()? {
+            NONE => visitor.visit_none(),
+            SOME => visitor.visit_some(self),
+            UNIT => visitor.visit_unit(),
+            BOOL => visitor.visit_bool(self.tag()? != 0),
+            INT => visitor.visit_i64(i64::from_le_bytes(self.array()?)),
+            UINT => visitor.visit_u64(u64::from_le_bytes(self.array()?)),
+            F32 => visitor.visit_f32(f32::from_le_bytes(self.array()?)),
+            F64 => visitor.visit_f64(f64::from_le_bytes(self.array()?)),
+            STR => {
+                let len = self.len()?;
+                let text = std::str::from_utf8(self.take(len)?).map_err(|e| Error(e.to_string()))?;
+                visitor.visit_borrowed_str(text)
+            }
+            BYTES => {
+                let len = self.len()?;
+                visitor.visit_borrowed_bytes(self.take(len)?)
+            }
+            SEQ => {
+                let remaining = self.len()?;
+                visitor.visit_seq(Elements { decoder: self, remaining })
+            }
+            MAP => {
+                let remaining = self.len()?;
+                visitor.visit_map(Elements { decoder: self, remaining })
+            }
+            VARIANT => visitor.visit_enum(self),
+            tag => Err(Error(format!("unknown tag {}", tag))),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> std::result::Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+/// Elements of a sequence or entries of a map
+struct Elements<'a, 'de> {
+    decoder: &'a mut Decoder<'de>,
+    remaining: usize,
+}
+
+impl<'de> SeqAccess<'de> for Elements<'_, 'de> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> std::result::Result<Option<T::Value>, Error> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+        seed.deserialize(&mut *self.decoder).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        // Bounded, so that a corrupt count cannot reserve unbounded memory
+        Some(self.remaining.min(4096))
+    }
+}
+
+impl<'de> MapAccess<'de> for Elements<'_, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> std::result::Result<Option<K::Value>, Error> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+        seed.deserialize(&mut *self.decoder).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> std::result::Result<V::Value, Error> {
+        seed.deserialize(&mut *self.decoder)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining.min(4096))
+    }
+}
+
+impl<'de> EnumAccess<'de> for &mut Decoder<'de> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> std::result::Result<(V::Value, Self), Error> {
+        let variant = seed.deserialize(&mut *self)?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de> VariantAccess<'de> for &mut Decoder<'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> std::result::Result<(), Error> {
+        match self.tag()? {
+            UNIT => Ok(()),
+            tag => Err(Error(format!("expected a unit variant, found tag {}", tag))),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> std::result::Result<T::Value, Error> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> std::result::Result<V::Value, Error> {
+        de::Deserializer::deserialize_any(self, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> std::result::Result<V::Value, Error> {
+        de::Deserializer::deserialize_any(self, visitor)
+    }
+}
@@ -0,0 +1,458 @@
+//! Binary volume cache
+//!
+//! Decoding some formats costs far more than reading the decoded arrays
+//! back. [`write_volume_cache`] stores a complete volume in a compact
+//! binary file that [`read_volume_cache`] reloads without decoding, so
+//! batch pipelines can cache volumes on disk.
+//!
+//! A cache file is the magic bytes `RADISHVC`, a little-endian `u32`
+//! format version, a little-endian `u64` header length and a header
+//! holding all metadata, followed by the arrays of the volume as raw
+//! little-endian values in the order the header lists them. The header is
+//! written in a small self-describing binary encoding of the serde data
+//! model, which unlike JSON keeps non-finite floats such as the NaN
+//! location of volumes without a site. Moments keep their packing and
+//! validity masks. Cached gate locations are not stored; georeference the
+//! volume again after reading.
+//!
+//! The header is forward compatible: structs are keyed by field name, so
+//! fields unknown to the reader are ignored and missing `Option` (or
+//! `#[serde(default)]`) fields take their default. Adding such a field to
+//! a header or model struct therefore keeps the format version. Other
+//! changes, to required fields or to the payload layout, increment
+//! [`CACHE_FORMAT_VERSION`] and add a migration from the previous version
+//! to `migrate_header`, so that caches written by older radish versions
+//! keep loading; [`upgrade_volume_cache`] rewrites them in the current
+//! version. Caches of a newer version than the reader are rejected.
+
+mod encoding;
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+
+use crate::model::{Attributes, History, RadarCalibration};
+use crate::{
+    Coordinates, MomentData, MomentStorage, PlatformMotion, Provenance, RadishError, RayMetadata, Result, SweepData,
+    SweepMetadata, VolumeData, VolumeMetadata,
+};
+
+/// Magic bytes at the start of a cache file
+const MAGIC: &[u8; 8] = b"RADISHVC";
+
+/// Version of the cache format, incremented on every incompatible change
+pub const CACHE_FORMAT_VERSION: u32 = 1;
+
+/// Oldest cache format version that can be read, migrated to the current one
+pub const MIN_CACHE_FORMAT_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct VolumeHeader {
+    metadata: VolumeMetadata,
+    calibration: Option<RadarCalibration>,
+    #[serde(default)]
+    history: History,
+    sweeps: Vec<SweepHeader>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SweepHeader {
+    metadata: SweepMetadata,
+    ray_metadata: Option<RayMetadata>,
+    platform_motion: Option<PlatformMotion>,
+    time_reference: Option<DateTime<Utc>>,
+    antenna_transition: Option<Vec<bool>>,
+    n_gates_per_ray: Option<Vec<usize>>,
+    moments: Vec<MomentHeader>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct MomentHeader {
+    name: String,
+    standard_name: Option<String>,
+    long_name: Option<String>,
+    units: String,
+    storage: StorageType,
+    shape: (usize, usize),
+    masked: bool,
+    fill_value: Option<f32>,
+    scale_factor: Option<f32>,
+    add_offset: Option<f32>,
+    valid_min: Option<f32>,
+    valid_max: Option<f32>,
+    coordinates: Option<String>,
+    attributes: Attributes,
+    provenance: Option<Provenance>,
+}
+
+#[derive(Serialize, Deserialize)]
+enum StorageType {
+    U8,
+    I16,
+    F32,
+    F64,
+}
+
+/// Write a volume to a cache file
+pub fn write_volume_cache(volume: &VolumeData, path: &Path) -> Result<()> {
+    std::fs::write(path, encode_volume(volume)?)?;
+    Ok(())
+}
+
+/// Read a volume from a cache file
+///
+/// Caches of older format versions are migrated on read. Fails with
+/// `InvalidFormat` for files that are not volume caches or are truncated,
+/// and with `Unsupported` for caches of a version that cannot be read.
+pub fn read_volume_cache(path: &Path) -> Result<VolumeData> {
+    decode_volume(&std::fs::read(path)?)
+}
+
+/// Rewrite a cache file of an older format version in the current one
+///
+/// Returns whether the file was rewritten; caches already in the current
+/// version are left untouched.
+pub fn upgrade_volume_cache(path: &Path) -> Result<bool> {
+    let bytes = std::fs::read(path)?;
+    if cache_format_version(&bytes)? == CACHE_FORMAT_VERSION {
+        return Ok(false);
+    }
+    write_volume_cache(&decode_volume(&bytes)?, path)?;
+    Ok(true)
+}
+
+/// Format version of an encoded cache
+pub fn cache_format_version(bytes: &[u8]) -> Result<u32> {
+    let mut reader = Reader { bytes, position: 0 };
+    if reader.take(MAGIC.len())? != MAGIC {
+        return Err(RadishError::InvalidFormat("Not a radish volume cache".to_string()));
+    }
+    Ok(u32::from_le_bytes(reader.array()?))
+}
+
+/// Encode a volume in the cache format
+pub fn encode_volume(volume: &VolumeData) -> Result<Vec<u8>> {
+    let mut payload = Vec::new();
+    let mut sweeps = Vec::with_capacity(volume.sweeps.len());
+    for sweep in &volume.sweeps {
+        let coords = &sweep.coordinates;
+        put_f64(&mut payload, &coords.time);
+        put_f32(&mut payload, &coords.range);
+        put_f32(&mut payload, &coords.azimuth);
+        put_f32(&mut payload, &coords.elevation);
+
+        let mut names: Vec<&String> = sweep.moments.keys().collect();
+        names.sort();
+        let mut moments = Vec::with_capacity(names.len());
+        for name in names {
+            let moment = &sweep.moments[name];
+            let storage = match moment.as_packed() {
+                MomentStorage::U8(a) => {
+                    payload.extend(a.iter());
+                    StorageType::U8
+                }
+                MomentStorage::I16(a) => {
+                    payload.extend(a.iter().flat_map(|v| v.to_le_bytes()));
+                    StorageType::I16
+                }
+                MomentStorage::F32(a) => {
+                    payload.extend(a.iter().flat_map(|v| v.to_le_bytes()));
+                    StorageType::F32
+                }
+                MomentStorage::F64(a) => {
+                    payload.extend(a.iter().flat_map(|v| v.to_le_bytes()));
+                    StorageType::F64
+                }
+            };
+            if let Some(mask) = moment.mask() {
+                payload.extend(mask.iter().map(|&m| m as u8));
+            }
+            moments.push(MomentHeader {
+                name: name.clone(),
+                standard_name: moment.standard_name.clone(),
+                long_name: moment.long_name.clone(),
+                units: moment.units.clone(),
+                storage,
+                shape: moment.shape(),
+                masked: moment.mask().is_some(),
+                fill_value: moment.fill_value,
+                scale_factor: moment.scale_factor,
+                add_offset: moment.add_offset,
+                valid_min: moment.valid_min,
+                valid_max: moment.valid_max,
+                coordinates: moment.coordinates.clone(),
+                attributes: moment.attributes.clone(),
+                provenance: moment.provenance.clone(),
+            });
+        }
+
+        sweeps.push(SweepHeader {
+            metadata: sweep.metadata.clone(),
+            ray_metadata: sweep.ray_metadata.clone(),
+            platform_motion: sweep.platform_motion.clone(),
+            time_reference: coords.time_reference,
+            antenna_transition: coords.antenna_transition.clone(),
+            n_gates_per_ray: coords.n_gates_per_ray.clone(),
+            moments,
+        });
+    }
+
+    let header = VolumeHeader {
+        metadata: volume.metadata.clone(),
+        calibration: volume.calibration.clone(),
+        history: volume.history.clone(),
+        sweeps,
+    };
+    let header = encoding::to_bytes(&header)?;
+
+    let mut bytes = Vec::with_capacity(MAGIC.len() + 12 + header.len() + payload.len());
+    bytes.extend(MAGIC);
+    bytes.extend(CACHE_FORMAT_VERSION.to_le_bytes());
+    bytes.extend((header.len() as u64).to_le_bytes());
+    bytes.extend(header);
+    bytes.extend(payload);
+    Ok(bytes)
+}
+
+/// Decode a volume from the cache format, migrating older format versions
+pub fn decode_volume(bytes: &[u8]) -> Result<VolumeData> {
+    let version = cache_format_version(bytes)?;
+    if !(MIN_CACHE_FORMAT_VERSION..=CACHE_FORMAT_VERSION).contains(&version) {
+        return Err(RadishError::Unsupported(format!(
+            "Volume cache format version {} (supported {} to {})",
+            version, MIN_CACHE_FORMAT_VERSION, CACHE_FORMAT_VERSION
+        )));
+    }
+    let mut reader = Reader { bytes, position: MAGIC.len() + 4 };
+    let header_len = u64::from_le_bytes(reader.array()?) as usize;
+    let header = migrate_header(version, reader.take(header_len)?)?;
+
+    let mut sweeps = Vec::with_capacity(header.sweeps.len());
+    for sweep in header.sweeps {
+        let time = reader.vector(f64::from_le_bytes)?;
+        let range = reader.vector(f32::from_le_bytes)?;
+        let azimuth = reader.vector(f32::from_le_bytes)?;
+        let elevation = reader.vector(f32::from_le_bytes)?;
+        let mut coordinates = Coordinates::new(time, range, azimuth, elevation);
+        coordinates.time_reference = sweep.time_reference;
+        coordinates.antenna_transition = sweep.antenna_transition;
+        coordinates.n_gates_per_ray = sweep.n_gates_per_ray;
+
+        let mut moments = HashMap::with_capacity(sweep.moments.len());
+        for m in sweep.moments {
+            let n = m.shape.0.saturating_mul(m.shape.1);
+            let storage = match m.storage {
+                StorageType::U8 => MomentStorage::U8(reader.array2(m.shape, u8::from_le_bytes)?),
+                StorageType::I16 => MomentStorage::I16(reader.array2(m.shape, i16::from_le_bytes)?),
+                StorageType::F32 => MomentStorage::F32(reader.array2(m.shape, f32::from_le_bytes)?),
+                StorageType::F64 => MomentStorage::F64(reader.array2(m.shape, f64::from_le_bytes)?),
+            };
+            let mut moment = MomentData::from_storage(m.name.clone(), m.units, storage);
+            if m.masked {
+                let mask = reader.take(n)?.iter().map(|&b| b != 0).collect();
+                moment.set_mask(Some(to_array(m.shape, mask)?))?;
+            }
+            moment.standard_name = m.standard_name;
+            moment.long_name = m.long_name;
+            moment.fill_value = m.fill_value;
+            moment.scale_factor = m.scale_factor;
+            moment.add_offset = m.add_offset;
+            moment.valid_min = m.valid_min;
+            moment.valid_max = m.valid_max;
+            moment.coordinates = m.coordinates;
+            moment.attributes = m.attributes;
+            moment.provenance = m.provenance;
+            moments.insert(m.name, moment);
+        }
+
+        let mut data = SweepData::new(sweep.metadata, moments, coordinates);
+        data.ray_metadata = sweep.ray_metadata;
+        data.platform_motion = sweep.platform_motion;
+        sweeps.push(data);
+    }
+    if reader.position != bytes.len() {
+        return Err(RadishError::InvalidFormat("Trailing data in volume cache".to_string()));
+    }
+
+    let mut volume = VolumeData::new(header.metadata, sweeps);
+    volume.calibration = header.calibration;
+    volume.history = header.history;
+    Ok(volume)
+}
+
+/// Decode the header of a cache of format `version` into the current header
+///
+/// This is the migration hook of the format. When the format version is
+/// incremented, the previous header struct is kept (e.g. as
+/// `VolumeHeaderV1`) with a conversion to the current one, and its version
+/// is decoded here:
+///
+/// ```text
+/// 1 => encoding::from_bytes::<VolumeHeaderV1>(bytes).map(VolumeHeader::from),
+/// ```
+///
+/// A change to the payload layout is recorded in the header (e.g. as a
+/// default for a field the old version lacks) so that the payload decoding
+/// in [`decode_volume`] can follow it.
+fn migrate_header(version: u32, bytes: &[u8]) -> Result<VolumeHeader> {
+    match version {
+        CACHE_FORMAT_VERSION => encoding::from_bytes(bytes),
+        _ => Err(RadishError::Unsupported(format!(
+            "No migration from volume cache format version {}",
+            version
+        ))),
+    }
+}
+
+/// Append a vector as its `u64` length and values
+fn put_f64(payload: &mut Vec<u8>, values: &[f64]) {
+    payload.extend((values.len() as u64).to_le_bytes());
+    payload.extend(values.iter().flat_map(|v| v.to_le_bytes()));
+}
+
+/// Append a vector as its `u64` length and values
+fn put_f32(payload: &mut Vec<u8>, values: &[f32]) {
+    payload.extend((values.len() as u64).to_le_bytes());
+    payload.extend(values.iter().flat_map(|v| v.to_le_bytes()));
+}
+
+fn to_array<T>(shape: (usize, usize), values: Vec<T>) -> Result<Array2<T>> {
+    Array2::from_shape_vec(shape, values).map_err(|e| RadishError::Conversion(e.to_string()))
+}
+
+/// Sequential reader over the bytes of a cache file
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self
+            .position
+            .checked_add(len)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| RadishError::InvalidFormat("Truncated volume cache".to_string()))?;
+        let slice = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(slice)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        Ok(self.take(N)?.try_into().expect("slice of length N"))
+    }
+
+    fn values<T, const N: usize>(&mut self, len: usize, decode: fn([u8; N]) -> T) -> Result<Vec<T>> {
+        let bytes = self.take(len.saturating_mul(N))?;
+        Ok(bytes
+            .chunks_exact(N)
+            .map(|chunk| decode(chunk.try_into().expect("chunk of length N")))
+            .collect())
+    }
+
+    fn vector<T, const N: usize>(&mut self, decode: fn([u8; N]) -> T) -> Result<Vec<T>> {
+        let len = u64::from_le_bytes(self.array()?);
+        self.values(usize::try_from(len).unwrap_or(usize::MAX), decode)
+    }
+
+    fn array2<T, const N: usize>(&mut self, shape: (usize, usize), decode: fn([u8; N]) -> T) -> Result<Array2<T>> {
+        let len = shape.0.saturating_mul(shape.1);
+        to_array(shape, self.values(len, decode)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Pattern, VolumeBuilder};
+
+    /// Replace the header of an encoded cache
+    fn with_header<T: Serialize>(bytes: &[u8], header: &T) -> Vec<u8> {
+        let start = MAGIC.len() + 12;
+        let header_len = u64::from_le_bytes(bytes[MAGIC.len() + 4..start].try_into().unwrap()) as usize;
+        let header = encoding::to_bytes(header).unwrap();
+        let mut out = bytes[..MAGIC.len() + 4].to_vec();
+        out.extend((header.len() as u64).to_le_bytes());
+        out.extend(header);
+        out.extend(&bytes[start + header_len..]);
+        out
+    }
+
+    fn header_of(bytes: &[u8]) -> VolumeHeader {
+        let start = MAGIC.len() + 12;
+        let header_len = u64::from_le_bytes(bytes[MAGIC.len() + 4..start].try_into().unwrap()) as usize;
+        encoding::from_bytes(&bytes[start..start + header_len]).unwrap()
+    }
+
+    #[test]
+    fn test_header_forward_compatible() {
+        let mut volume = VolumeBuilder::new()
+            .elevations(&[0.5])
+            .moment("DBZH", "dBZ", Pattern::Constant(20.0))
+            .build();
+        volume.calibration = Some(RadarCalibration::default());
+        volume.history.record("radish.test", &1);
+        let bytes = encode_volume(&volume).unwrap();
+        let header = header_of(&bytes);
+
+        // A field added by a later radish is ignored
+        #[derive(Serialize)]
+        struct Extended<'a> {
+            metadata: &'a VolumeMetadata,
+            calibration: &'a Option<RadarCalibration>,
+            history: &'a History,
+            sweeps: &'a [SweepHeader],
+            compression: Option<&'a str>,
+        }
+        let extended = Extended {
+            metadata: &header.metadata,
+            calibration: &header.calibration,
+            history: &header.history,
+            sweeps: &header.sweeps,
+            compression: Some("zstd"),
+        };
+        let decoded = decode_volume(&with_header(&bytes, &extended)).unwrap();
+        assert!(decoded.calibration.is_some());
+        assert_eq!(decoded.history.entries().len(), 1);
+        assert_eq!(decoded.sweeps[0].moments["DBZH"].data(), volume.sweeps[0].moments["DBZH"].data());
+
+        // Missing optional and defaulted fields take their default
+        #[derive(Serialize)]
+        struct Reduced<'a> {
+            metadata: &'a VolumeMetadata,
+            sweeps: &'a [SweepHeader],
+        }
+        let reduced = Reduced {
+            metadata: &header.metadata,
+            sweeps: &header.sweeps,
+        };
+        let decoded = decode_volume(&with_header(&bytes, &reduced)).unwrap();
+        assert!(decoded.calibration.is_none());
+        assert!(decoded.history.entries().is_empty());
+        assert_eq!(decoded.num_sweeps(), 1);
+    }
+
+    #[test]
+    fn test_format_versions() {
+        let volume = VolumeBuilder::new()
+            .elevations(&[0.5])
+            .moment("DBZH", "dBZ", Pattern::Constant(20.0))
+            .build();
+        let bytes = encode_volume(&volume).unwrap();
+        assert_eq!(cache_format_version(&bytes).unwrap(), CACHE_FORMAT_VERSION);
+
+        for version in [MIN_CACHE_FORMAT_VERSION - 1, CACHE_FORMAT_VERSION + 1] {
+            let mut other = bytes.clone();
+            other[MAGIC.len()..MAGIC.len() + 4].copy_from_slice(&version.to_le_bytes());
+            assert!(matches!(decode_volume(&other), Err(RadishError::Unsupported(_))));
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("volume.rvc");
+        write_volume_cache(&volume, &path).unwrap();
+        assert!(!upgrade_volume_cache(&path).unwrap());
+    }
+}
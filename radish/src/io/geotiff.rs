@@ -0,0 +1,307 @@
+//! Reader for digital elevation models in GeoTIFF format
+//!
+//! Reads single-band, uncompressed GeoTIFF rasters in geographic
+//! coordinates (e.g. SRTM or Copernicus DEM tiles converted with
+//! `gdal_translate -co COMPRESS=NONE`) into a single-level [`GriddedData`]
+//! on the [`Projection::Geographic`] grid, for use as terrain by the beam
+//! blockage and coverage transforms. Strip and tile layouts, both byte
+//! orders and 8 to 64 bit integer and floating point samples are
+//! supported; the GDAL no-data value becomes NaN.
+
+use std::path::Path;
+
+use chrono::Utc;
+use ndarray::Array3;
+
+use crate::transforms::georeference::Projection;
+use crate::{GridField, GriddedData, RadishError, Result};
+
+/// Name of the elevation field (meters)
+pub const ELEVATION: &str = "ELEVATION";
+
+const TAG_WIDTH: u16 = 256;
+const TAG_HEIGHT: u16 = 257;
+const TAG_BITS_PER_SAMPLE: u16 = 258;
+const TAG_COMPRESSION: u16 = 259;
+const TAG_STRIP_OFFSETS: u16 = 273;
+const TAG_SAMPLES_PER_PIXEL: u16 = 277;
+const TAG_ROWS_PER_STRIP: u16 = 278;
+const TAG_TILE_WIDTH: u16 = 322;
+const TAG_TILE_LENGTH: u16 = 323;
+const TAG_TILE_OFFSETS: u16 = 324;
+const TAG_SAMPLE_FORMAT: u16 = 339;
+const TAG_PIXEL_SCALE: u16 = 33550;
+const TAG_TIEPOINT: u16 = 33922;
+const TAG_GEO_KEYS: u16 = 34735;
+const TAG_GDAL_NODATA: u16 = 42113;
+
+const KEY_MODEL_TYPE: u16 = 1024;
+const KEY_RASTER_TYPE: u16 = 1025;
+const MODEL_TYPE_GEOGRAPHIC: u16 = 2;
+const RASTER_PIXEL_IS_POINT: u16 = 2;
+
+/// Read a GeoTIFF elevation model as an `ELEVATION` grid (meters)
+///
+/// Grid coordinates are pixel centers in degrees, with y increasing
+/// northward.
+pub fn read_geotiff_dem(path: impl AsRef<Path>) -> Result<GriddedData> {
+    let path = path.as_ref();
+    let bytes = std::fs::read(path)?;
+    let tiff = Tiff::parse(&bytes)?;
+
+    let width = tiff.scalar(TAG_WIDTH)? as usize;
+    let height = tiff.scalar(TAG_HEIGHT)? as usize;
+    if tiff.optional_scalar(TAG_COMPRESSION).unwrap_or(1) != 1 {
+        return Err(RadishError::Unsupported(format!(
+            "Compressed GeoTIFF {} (decompress with gdal_translate -co COMPRESS=NONE)",
+            path.display()
+        )));
+    }
+    if tiff.optional_scalar(TAG_SAMPLES_PER_PIXEL).unwrap_or(1) != 1 {
+        return Err(RadishError::Unsupported("Multi-band GeoTIFF".to_string()));
+    }
+    let bits = tiff.optional_scalar(TAG_BITS_PER_SAMPLE).unwrap_or(1) as usize;
+    let format = tiff.optional_scalar(TAG_SAMPLE_FORMAT).unwrap_or(1);
+    let sample_size = bits / 8;
+    if !matches!((format, bits), (1 | 2, 8 | 16 | 32 | 64) | (3, 32 | 64)) {
+        return Err(RadishError::Unsupported(format!(
+            "GeoTIFF sample format {} with {} bits",
+            format, bits
+        )));
+    }
+
+    // Georeferencing
+    let geo_keys = tiff.values(TAG_GEO_KEYS).unwrap_or_default();
+    let geo_key = |key: u16| {
+        geo_keys
+            .get(4..)
+            .unwrap_or_default()
+            .chunks(4)
+            .find(|k| k.len() == 4 && k[0] as u16 == key && k[1] == 0.0)
+            .map(|k| k[3] as u16)
+    };
+    if geo_key(KEY_MODEL_TYPE).is_some_and(|t| t != MODEL_TYPE_GEOGRAPHIC) {
+        return Err(RadishError::Unsupported(
+            "Projected GeoTIFF (only geographic coordinates are supported)".to_string(),
+        ));
+    }
+    let scale = tiff
+        .values(TAG_PIXEL_SCALE)
+        .ok_or_else(|| RadishError::MissingAttribute("ModelPixelScale".to_string()))?;
+    let tiepoint = tiff
+        .values(TAG_TIEPOINT)
+        .ok_or_else(|| RadishError::MissingAttribute("ModelTiepoint".to_string()))?;
+    if scale.len() < 2 || tiepoint.len() < 6 {
+        return Err(RadishError::InvalidFormat("Invalid GeoTIFF georeferencing".to_string()));
+    }
+    // Coordinates of the center of the upper left pixel
+    let center = if geo_key(KEY_RASTER_TYPE) == Some(RASTER_PIXEL_IS_POINT) { 0.0 } else { 0.5 };
+    let lon0 = tiepoint[3] + (center - tiepoint[0]) * scale[0];
+    let lat0 = tiepoint[4] - (center - tiepoint[1]) * scale[1];
+
+    let nodata = tiff
+        .ascii(TAG_GDAL_NODATA)
+        .and_then(|s| s.trim().parse::<f64>().ok());
+
+    // Pixel data, in (offset, byte count, first row, first column, rows, columns) blocks
+    let blocks: Vec<(usize, usize, usize, usize, usize, usize)> =
+        if let Some(offsets) = tiff.values(TAG_TILE_OFFSETS) {
+            let tile_width = tiff.scalar(TAG_TILE_WIDTH)? as usize;
+            let tile_length = tiff.scalar(TAG_TILE_LENGTH)? as usize;
+            let across = width.div_ceil(tile_width);
+            offsets
+                .iter()
+                .enumerate()
+                .map(|(t, &offset)| {
+                    let (row, col) = (t / across * tile_length, t % across * tile_width);
+                    (offset as usize, tile_width * tile_length * sample_size, row, col, tile_length, tile_width)
+                })
+                .collect()
+        } else {
+            let offsets = tiff
+                .values(TAG_STRIP_OFFSETS)
+                .ok_or_else(|| RadishError::MissingAttribute("StripOffsets".to_string()))?;
+            let rows_per_strip = tiff.optional_scalar(TAG_ROWS_PER_STRIP).unwrap_or(height as u64) as usize;
+            offsets
+                .iter()
+                .enumerate()
+                .map(|(s, &offset)| {
+                    let row = s * rows_per_strip;
+                    let rows = rows_per_strip.min(height.saturating_sub(row));
+                    (offset as usize, rows * width * sample_size, row, 0, rows, width)
+                })
+                .collect()
+        };
+
+    let mut elevation = Array3::from_elem((1, height, width), f32::NAN);
+    for (offset, count, row0, col0, rows, cols) in blocks {
+        let block = bytes
+            .get(offset..offset + count)
+            .ok_or_else(|| RadishError::InvalidFormat("Truncated GeoTIFF pixel data".to_string()))?;
+        for r in 0..rows {
+            for c in 0..cols {
+                let (row, col) = (row0 + r, col0 + c);
+                if row >= height || col >= width {
+                    continue;
+                }
+                let start = (r * cols + c) * sample_size;
+                let v = tiff.sample(&block[start..start + sample_size], format);
+                if nodata != Some(v) {
+                    // Rows run north to south; flip so that y increases
+                    elevation[[0, height - 1 - row, col]] = v as f32;
+                }
+            }
+        }
+    }
+
+    let x: Vec<f64> = (0..width).map(|i| lon0 + i as f64 * scale[0]).collect();
+    let y: Vec<f64> = (0..height).rev().map(|j| lat0 - j as f64 * scale[1]).collect();
+    let mut grid = GriddedData::new(Utc::now(), Projection::Geographic, x, y, vec![0.0]);
+    grid.attributes
+        .insert("source".to_string(), path.display().to_string());
+    let mut field = GridField::new(ELEVATION.to_string(), "m".to_string(), elevation);
+    field.standard_name = Some("surface_altitude".to_string());
+    field.long_name = Some("Terrain elevation".to_string());
+    grid.add_field(field)?;
+    Ok(grid)
+}
+
+/// First image file directory of a TIFF file
+struct Tiff<'a> {
+    bytes: &'a [u8],
+    little_endian: bool,
+    /// (tag, type, count, value or offset field)
+    entries: Vec<(u16, u16, usize, &'a [u8])>,
+}
+
+impl<'a> Tiff<'a> {
+    fn parse(bytes: &'a [u8]) -> Result<Self> {
+        let invalid = || RadishError::InvalidFormat("Not a TIFF file".to_string());
+        let little_endian = match bytes.get(0..2) {
+            Some(b"II") => true,
+            Some(b"MM") => false,
+            _ => return Err(invalid()),
+        };
+        let mut tiff = Self {
+            bytes,
+            little_endian,
+            entries: Vec::new(),
+        };
+        let header = bytes.get(0..8).ok_or_else(invalid)?;
+        if tiff.u16(&header[2..4]) != 42 {
+            return Err(RadishError::Unsupported("BigTIFF files".to_string()));
+        }
+        let ifd = tiff.u32(&header[4..8]) as usize;
+        let count = tiff.u16(bytes.get(ifd..ifd + 2).ok_or_else(invalid)?) as usize;
+        for e in 0..count {
+            let entry = bytes.get(ifd + 2 + 12 * e..ifd + 14 + 12 * e).ok_or_else(invalid)?;
+            tiff.entries.push((
+                tiff.u16(&entry[0..2]),
+                tiff.u16(&entry[2..4]),
+                tiff.u32(&entry[4..8]) as usize,
+                &entry[8..12],
+            ));
+        }
+        Ok(tiff)
+    }
+
+    /// Raw bytes of an entry's values
+    fn data(&self, tag: u16) -> Option<(u16, usize, &'a [u8])> {
+        let &(_, kind, count, field) = self.entries.iter().find(|e| e.0 == tag)?;
+        let size = match kind {
+            1 | 2 | 6 | 7 => 1,
+            3 | 8 => 2,
+            4 | 9 | 11 => 4,
+            5 | 10 | 12 | 16 | 17 => 8,
+            _ => return None,
+        };
+        let len = size * count;
+        let data = if len <= 4 {
+            &field[..len]
+        } else {
+            let offset = self.u32(field) as usize;
+            self.bytes.get(offset..offset + len)?
+        };
+        Some((kind, size, data))
+    }
+
+    /// Numeric values of an entry
+    fn values(&self, tag: u16) -> Option<Vec<f64>> {
+        let (kind, size, data) = self.data(tag)?;
+        Some(
+            data.chunks(size)
+                .map(|b| match kind {
+                    1 | 7 => b[0] as f64,
+                    6 => b[0] as i8 as f64,
+                    3 => self.u16(b) as f64,
+                    8 => self.u16(b) as i16 as f64,
+                    4 => self.u32(b) as f64,
+                    9 => self.u32(b) as i32 as f64,
+                    11 => f32::from_bits(self.u32(b)) as f64,
+                    12 => f64::from_bits(self.u64(b)),
+                    5 => self.u32(&b[0..4]) as f64 / self.u32(&b[4..8]) as f64,
+                    10 => self.u32(&b[0..4]) as i32 as f64 / self.u32(&b[4..8]) as i32 as f64,
+                    _ => self.u64(b) as f64,
+                })
+                .collect(),
+        )
+    }
+
+    fn optional_scalar(&self, tag: u16) -> Option<u64> {
+        self.values(tag).and_then(|v| v.first().map(|&v| v as u64))
+    }
+
+    fn scalar(&self, tag: u16) -> Result<u64> {
+        self.optional_scalar(tag)
+            .ok_or_else(|| RadishError::MissingAttribute(format!("TIFF tag {}", tag)))
+    }
+
+    fn ascii(&self, tag: u16) -> Option<String> {
+        let (_, _, data) = self.data(tag)?;
+        let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+        Some(String::from_utf8_lossy(&data[..end]).into_owned())
+    }
+
+    /// Decode one pixel sample
+    fn sample(&self, b: &[u8], format: u64) -> f64 {
+        match (format, b.len()) {
+            (1, 1) => b[0] as f64,
+            (2, 1) => b[0] as i8 as f64,
+            (1, 2) => self.u16(b) as f64,
+            (2, 2) => self.u16(b) as i16 as f64,
+            (1, 4) => self.u32(b) as f64,
+            (2, 4) => self.u32(b) as i32 as f64,
+            (3, 4) => f32::from_bits(self.u32(b)) as f64,
+            (1, 8) => self.u64(b) as f64,
+            (2, 8) => self.u64(b) as i64 as f64,
+            _ => f64::from_bits(self.u64(b)),
+        }
+    }
+
+    fn u16(&self, b: &[u8]) -> u16 {
+        let b = [b[0], b[1]];
+        if self.little_endian {
+            u16::from_le_bytes(b)
+        } else {
+            u16::from_be_bytes(b)
+        }
+    }
+
+    fn u32(&self, b: &[u8]) -> u32 {
+        let b = [b[0], b[1], b[2], b[3]];
+        if self.little_endian {
+            u32::from_le_bytes(b)
+        } else {
+            u32::from_be_bytes(b)
+        }
+    }
+
+    fn u64(&self, b: &[u8]) -> u64 {
+        let b = [b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]];
+        if self.little_endian {
+            u64::from_le_bytes(b)
+        } else {
+            u64::from_be_bytes(b)
+        }
+    }
+}
@@ -0,0 +1,441 @@
+//! Reader for MRMS GRIB2 2D products
+//!
+//! Multi-Radar Multi-Sensor (MRMS) products are distributed as single
+//! message GRIB2 files, usually gzip-compressed, on a regular latitude /
+//! longitude grid (grid template 3.0) with PNG (template 5.41) or simple
+//! (template 5.0) packing. Messages are decoded into a [`GriddedData`] on
+//! the [`Projection::Geographic`] grid, so that radish composites can be
+//! validated against and blended with MRMS.
+
+use std::io::Read;
+use std::path::Path;
+
+use chrono::{DateTime, TimeZone, Utc};
+use ndarray::Array3;
+
+use crate::transforms::georeference::Projection;
+use crate::{GridField, GriddedData, RadishError, Result};
+
+/// MRMS value for missing data
+pub const MRMS_MISSING: f32 = -999.0;
+
+/// MRMS value for grid points without radar coverage
+pub const MRMS_NO_COVERAGE: f32 = -99.0;
+
+/// Read an MRMS GRIB2 product (optionally gzip-compressed)
+///
+/// Every message becomes a single-level field; messages must share the
+/// grid of the first one. The field of a single message file is named after
+/// the MRMS product in the file name (e.g. `MergedReflectivityQCComposite`
+/// for `MRMS_MergedReflectivityQCComposite_00.50_20240101-000000.grib2.gz`),
+/// other fields after their parameter codes (`discipline-category-number`).
+/// Missing and no-coverage values are NaN.
+pub fn read_mrms_grib2(path: impl AsRef<Path>) -> Result<GriddedData> {
+    let path = path.as_ref();
+    let mut bytes = std::fs::read(path)?;
+    if bytes.starts_with(&[0x1f, 0x8b]) {
+        let mut decompressed = Vec::new();
+        flate2::read::MultiGzDecoder::new(bytes.as_slice()).read_to_end(&mut decompressed)?;
+        bytes = decompressed;
+    }
+
+    let messages = parse_messages(&bytes)?;
+    let first = messages
+        .first()
+        .ok_or_else(|| RadishError::InvalidFormat("No GRIB2 messages found".to_string()))?;
+
+    let grid_def = &first.grid;
+    let mut grid = GriddedData::new(
+        first.reference_time,
+        Projection::Geographic,
+        grid_def.longitudes(),
+        grid_def.latitudes(),
+        vec![first.level.unwrap_or(0.0)],
+    );
+    grid.attributes.insert("source".to_string(), "MRMS".to_string());
+
+    let product = (messages.len() == 1).then(|| product_name(path)).flatten();
+    for message in &messages {
+        if message.grid != *grid_def {
+            return Err(RadishError::Unsupported(
+                "GRIB2 messages on different grids".to_string(),
+            ));
+        }
+
+        let code = format!("{}-{}-{}", message.discipline, message.category, message.number);
+        let name = product.clone().unwrap_or_else(|| code.clone());
+        let data = message.grid.to_array(&message.values);
+        let mut field = GridField::new(name, String::new(), data);
+        field.attributes.insert("grib2_parameter".to_string(), code);
+        if let Some(level) = message.level {
+            field.attributes.insert("level".to_string(), level.to_string());
+        }
+        grid.add_field(field)?;
+    }
+
+    Ok(grid)
+}
+
+/// MRMS product name from a file name like `MRMS_<product>_<level>_<time>.grib2`
+fn product_name(path: &Path) -> Option<String> {
+    let stem = path.file_name()?.to_str()?;
+    let stem = stem.strip_prefix("MRMS_").unwrap_or(stem);
+    let mut parts: Vec<&str> = stem.split('_').collect();
+    // Drop the level and time suffixes
+    if parts.len() < 3 || parts[parts.len() - 2].parse::<f64>().is_err() {
+        return None;
+    }
+    parts.truncate(parts.len() - 2);
+    Some(parts.join("_"))
+}
+
+/// A decoded GRIB2 message
+struct Message {
+    discipline: u8,
+    reference_time: DateTime<Utc>,
+    grid: LatLonGrid,
+    category: u8,
+    number: u8,
+    /// Height (meters MSL) of the first fixed surface, if it is an altitude
+    level: Option<f64>,
+    values: Vec<f32>,
+}
+
+/// Regular latitude/longitude grid (template 3.0)
+#[derive(Debug, Clone, PartialEq)]
+struct LatLonGrid {
+    ni: usize,
+    nj: usize,
+    lat1: f64,
+    lon1: f64,
+    di: f64,
+    dj: f64,
+    scanning_mode: u8,
+}
+
+impl LatLonGrid {
+    /// Whether rows are stored south to north
+    fn j_positive(&self) -> bool {
+        self.scanning_mode & 0x40 != 0
+    }
+
+    /// Whether columns are stored east to west
+    fn i_negative(&self) -> bool {
+        self.scanning_mode & 0x80 != 0
+    }
+
+    /// Increasing longitudes (degrees, -180 to 180 where possible)
+    fn longitudes(&self) -> Vec<f64> {
+        let west = if self.i_negative() {
+            self.lon1 - (self.ni - 1) as f64 * self.di
+        } else {
+            self.lon1
+        };
+        let west = if west > 180.0 { west - 360.0 } else { west };
+        (0..self.ni).map(|i| west + i as f64 * self.di).collect()
+    }
+
+    /// Increasing latitudes (degrees)
+    fn latitudes(&self) -> Vec<f64> {
+        let south = if self.j_positive() {
+            self.lat1
+        } else {
+            self.lat1 - (self.nj - 1) as f64 * self.dj
+        };
+        (0..self.nj).map(|j| south + j as f64 * self.dj).collect()
+    }
+
+    /// Arrange values in scanning order as a [1 × lat × lon] array
+    fn to_array(&self, values: &[f32]) -> Array3<f32> {
+        let mut data = Array3::from_elem((1, self.nj, self.ni), f32::NAN);
+        for (k, &v) in values.iter().enumerate().take(self.ni * self.nj) {
+            let (row, col) = (k / self.ni, k % self.ni);
+            let j = if self.j_positive() { row } else { self.nj - 1 - row };
+            let i = if self.i_negative() { self.ni - 1 - col } else { col };
+            data[[0, j, i]] = if v == MRMS_MISSING || v == MRMS_NO_COVERAGE {
+                f32::NAN
+            } else {
+                v
+            };
+        }
+        data
+    }
+}
+
+/// Data representation parameters (templates 5.0 and 5.41)
+struct Packing {
+    template: u16,
+    num_points: usize,
+    reference: f32,
+    binary_scale: i32,
+    decimal_scale: i32,
+    bits: u8,
+}
+
+impl Packing {
+    fn unpack(&self, raw: u64) -> f32 {
+        let value = self.reference as f64 + raw as f64 * 2f64.powi(self.binary_scale);
+        (value / 10f64.powi(self.decimal_scale)) as f32
+    }
+}
+
+fn parse_messages(bytes: &[u8]) -> Result<Vec<Message>> {
+    let mut messages = Vec::new();
+    let mut pos = 0;
+    while let Some(start) = find(&bytes[pos..], b"GRIB").map(|p| p + pos) {
+        if bytes.len() < start + 16 {
+            break;
+        }
+        let edition = bytes[start + 7];
+        if edition != 2 {
+            return Err(RadishError::Unsupported(format!("GRIB edition {}", edition)));
+        }
+        let length = be_u64(&bytes[start + 8..start + 16]) as usize;
+        let end = start
+            .checked_add(length)
+            .filter(|&e| e <= bytes.len())
+            .ok_or_else(|| RadishError::InvalidFormat("Truncated GRIB2 message".to_string()))?;
+        messages.extend(parse_message(&bytes[start..end])?);
+        pos = end;
+    }
+    Ok(messages)
+}
+
+/// Decode the fields of one message (a message may repeat sections 3-7)
+fn parse_message(message: &[u8]) -> Result<Vec<Message>> {
+    let discipline = message[6];
+    let mut reference_time = None;
+    let mut grid = None;
+    let mut product = None;
+    let mut packing = None;
+    let mut bitmap: Option<Vec<bool>> = None;
+    let mut fields = Vec::new();
+
+    let mut pos = 16;
+    while pos + 4 <= message.len() {
+        if &message[pos..pos + 4] == b"7777" {
+            break;
+        }
+        let length = be_u32(section_bytes(message, pos, 4)?) as usize;
+        let section = section_bytes(message, pos, length.max(5))?;
+        pos += length.max(5);
+
+        match section[4] {
+            1 => reference_time = Some(parse_time(section)?),
+            3 => grid = Some(parse_grid(section)?),
+            4 => product = Some(parse_product(section)?),
+            5 => packing = Some(parse_packing(section)?),
+            6 => match *section_bytes(section, 5, 1)?.first().unwrap_or(&255) {
+                255 => bitmap = None,
+                0 => {
+                    bitmap = Some(
+                        section[6..]
+                            .iter()
+                            .flat_map(|b| (0..8).map(move |k| b & (0x80 >> k) != 0))
+                            .collect(),
+                    )
+                }
+                // 254: previously defined bitmap applies
+                254 => {}
+                other => {
+                    return Err(RadishError::Unsupported(format!("GRIB2 bitmap indicator {}", other)))
+                }
+            },
+            7 => {
+                let missing = |name: &str| RadishError::InvalidFormat(format!("GRIB2 data before {}", name));
+                let grid = grid.clone().ok_or_else(|| missing("grid definition"))?;
+                let (category, number, level) = product.ok_or_else(|| missing("product definition"))?;
+                let packing = packing.as_ref().ok_or_else(|| missing("data representation"))?;
+
+                let packed = unpack_values(&section[5..], packing)?;
+                let num_points = grid.ni * grid.nj;
+                let values = match &bitmap {
+                    Some(bitmap) => {
+                        let mut packed = packed.into_iter();
+                        bitmap
+                            .iter()
+                            .take(num_points)
+                            .map(|&present| if present { packed.next().unwrap_or(MRMS_MISSING) } else { MRMS_MISSING })
+                            .collect()
+                    }
+                    None => packed,
+                };
+
+                fields.push(Message {
+                    discipline,
+                    reference_time: reference_time.ok_or_else(|| missing("identification"))?,
+                    grid,
+                    category,
+                    number,
+                    level,
+                    values,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    Ok(fields)
+}
+
+fn parse_time(section: &[u8]) -> Result<DateTime<Utc>> {
+    let s = section_bytes(section, 12, 7)?;
+    Utc.with_ymd_and_hms(
+        be_u16(&s[0..2]) as i32,
+        s[2] as u32,
+        s[3] as u32,
+        s[4] as u32,
+        s[5] as u32,
+        s[6] as u32,
+    )
+    .single()
+    .ok_or_else(|| RadishError::InvalidFormat("Invalid GRIB2 reference time".to_string()))
+}
+
+fn parse_grid(section: &[u8]) -> Result<LatLonGrid> {
+    let template = be_u16(section_bytes(section, 12, 2)?);
+    if template != 0 {
+        return Err(RadishError::Unsupported(format!(
+            "GRIB2 grid definition template 3.{}",
+            template
+        )));
+    }
+    let s = section_bytes(section, 0, 72)?;
+    // Angles are in micro-degrees unless a basic angle/subdivision is given
+    let basic_angle = be_u32(&s[38..42]);
+    let subdivisions = be_u32(&s[42..46]);
+    let unit = if basic_angle == 0 || basic_angle == u32::MAX || subdivisions == u32::MAX {
+        1e-6
+    } else {
+        basic_angle as f64 / subdivisions as f64
+    };
+
+    Ok(LatLonGrid {
+        ni: be_u32(&s[30..34]) as usize,
+        nj: be_u32(&s[34..38]) as usize,
+        lat1: be_i32_sm(&s[46..50]) as f64 * unit,
+        lon1: be_i32_sm(&s[50..54]) as f64 * unit,
+        di: be_u32(&s[63..67]) as f64 * unit,
+        dj: be_u32(&s[67..71]) as f64 * unit,
+        scanning_mode: s[71],
+    })
+}
+
+/// Parameter category, number and altitude level (templates 4.0 and 4.8)
+fn parse_product(section: &[u8]) -> Result<(u8, u8, Option<f64>)> {
+    let template = be_u16(section_bytes(section, 7, 2)?);
+    if template != 0 && template != 8 {
+        return Err(RadishError::Unsupported(format!(
+            "GRIB2 product definition template 4.{}",
+            template
+        )));
+    }
+    let s = section_bytes(section, 0, 28)?;
+    // Surface type 102: specified altitude above mean sea level (m)
+    let level = (s[22] == 102).then(|| {
+        let scale = s[23] as i8 as i32;
+        be_u32(&s[24..28]) as f64 / 10f64.powi(scale)
+    });
+    Ok((s[9], s[10], level))
+}
+
+fn parse_packing(section: &[u8]) -> Result<Packing> {
+    let s = section_bytes(section, 0, 20)?;
+    let template = be_u16(&s[9..11]);
+    if template != 0 && template != 41 {
+        return Err(RadishError::Unsupported(format!(
+            "GRIB2 data representation template 5.{}",
+            template
+        )));
+    }
+    Ok(Packing {
+        template,
+        num_points: be_u32(&s[5..9]) as usize,
+        reference: f32::from_bits(be_u32(&s[11..15])),
+        binary_scale: be_i16_sm(&s[15..17]) as i32,
+        decimal_scale: be_i16_sm(&s[17..19]) as i32,
+        bits: s[19],
+    })
+}
+
+fn unpack_values(data: &[u8], packing: &Packing) -> Result<Vec<f32>> {
+    let n = packing.num_points;
+    if packing.bits == 0 {
+        return Ok(vec![packing.unpack(0); n]);
+    }
+
+    let raw = match packing.template {
+        41 => decode_png(data)?,
+        _ => data.to_vec(),
+    };
+    let bits = packing.bits as usize;
+    if raw.len() * 8 < n * bits {
+        return Err(RadishError::InvalidFormat("GRIB2 data section too short".to_string()));
+    }
+
+    Ok((0..n).map(|k| packing.unpack(read_bits(&raw, k * bits, bits))).collect())
+}
+
+/// Decode a PNG image into its raw big-endian sample bytes
+fn decode_png(data: &[u8]) -> Result<Vec<u8>> {
+    let decoder = png::Decoder::new(data);
+    let mut reader = decoder
+        .read_info()
+        .map_err(|e| RadishError::InvalidFormat(format!("Invalid GRIB2 PNG data: {}", e)))?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader
+        .next_frame(&mut buffer)
+        .map_err(|e| RadishError::InvalidFormat(format!("Invalid GRIB2 PNG data: {}", e)))?;
+    buffer.truncate(info.buffer_size());
+    Ok(buffer)
+}
+
+/// Read `n` bits (at most 64) starting at bit `offset`
+fn read_bits(bytes: &[u8], offset: usize, n: usize) -> u64 {
+    (offset..offset + n).fold(0u64, |acc, bit| {
+        (acc << 1) | ((bytes[bit / 8] >> (7 - bit % 8)) & 1) as u64
+    })
+}
+
+fn section_bytes(bytes: &[u8], start: usize, len: usize) -> Result<&[u8]> {
+    bytes
+        .get(start..start + len)
+        .ok_or_else(|| RadishError::InvalidFormat("Truncated GRIB2 section".to_string()))
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+fn be_u16(b: &[u8]) -> u16 {
+    u16::from_be_bytes([b[0], b[1]])
+}
+
+fn be_u32(b: &[u8]) -> u32 {
+    u32::from_be_bytes([b[0], b[1], b[2], b[3]])
+}
+
+fn be_u64(b: &[u8]) -> u64 {
+    u64::from_be_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]])
+}
+
+/// GRIB2 signed integers use sign-magnitude encoding
+fn be_i16_sm(b: &[u8]) -> i16 {
+    let v = be_u16(b);
+    let magnitude = (v & 0x7fff) as i16;
+    if v & 0x8000 != 0 {
+        -magnitude
+    } else {
+        magnitude
+    }
+}
+
+fn be_i32_sm(b: &[u8]) -> i32 {
+    let v = be_u32(b);
+    let magnitude = (v & 0x7fff_ffff) as i32;
+    if v & 0x8000_0000 != 0 {
+        -magnitude
+    } else {
+        magnitude
+    }
+}
@@ -1,5 +1,26 @@
-/// I/O utilities for reading radar data files
+//! I/O utilities for reading radar data files
 
+#[cfg(feature = "archive")]
+pub mod archive;
+#[cfg(feature = "grib")]
+pub mod grib2;
+pub mod cache;
+pub mod geotiff;
 pub mod netcdf_utils;
+pub mod odim_composite;
+pub mod sounding;
+pub mod units;
 
+#[cfg(feature = "archive")]
+pub use archive::{read_archive_volumes, ArchiveFormat, ArchiveMember, ArchiveReader};
+#[cfg(feature = "grib")]
+pub use grib2::read_mrms_grib2;
+pub use cache::{
+    cache_format_version, decode_volume, encode_volume, read_volume_cache, upgrade_volume_cache, write_volume_cache,
+    CACHE_FORMAT_VERSION, MIN_CACHE_FORMAT_VERSION,
+};
+pub use geotiff::read_geotiff_dem;
 pub use netcdf_utils::*;
+pub use odim_composite::read_odim_composite;
+pub use sounding::{parse_sounding, read_model_column, read_sounding, Profile};
+pub use units::{unit_conversion, Quantity, UnitConversion};
@@ -1,10 +1,11 @@
-/// NetCDF utilities for reading radar data
+//! NetCDF utilities for reading radar data
 
+use crate::model::{AttributeValue, Attributes};
 use crate::{Result, RadishError};
 
 /// Read a string attribute from a NetCDF file or variable
 pub fn read_string_attribute(
-    attrs: impl Iterator<Item = netcdf::Attribute>,
+    mut attrs: impl Iterator<Item = netcdf::Attribute>,
     name: &str,
 ) -> Option<String> {
     attrs
@@ -12,14 +13,14 @@ pub fn read_string_attribute(
         .and_then(|a| a.value().ok())
         .and_then(|v| match v {
             netcdf::AttrValue::Str(s) => Some(s),
-            netcdf::AttrValue::Uchar(u) => Some(String::from_utf8_lossy(&u).to_string()),
+            netcdf::AttrValue::Uchars(u) => Some(String::from_utf8_lossy(&u).to_string()),
             _ => None,
         })
 }
 
 /// Read a numeric attribute from a NetCDF file or variable
 pub fn read_numeric_attribute<T: netcdf::Numeric>(
-    attrs: impl Iterator<Item = netcdf::Attribute>,
+    mut attrs: impl Iterator<Item = netcdf::Attribute>,
     name: &str,
 ) -> Option<T> {
     attrs
@@ -32,3 +33,43 @@ pub fn read_numeric_attribute<T: netcdf::Numeric>(
             _ => None,
         })
 }
+
+/// Typed value of a NetCDF attribute, `None` for unsupported types
+pub fn attribute_value(value: netcdf::AttrValue) -> Option<AttributeValue> {
+    fn ints<T: Into<i64>>(values: Vec<T>) -> Option<AttributeValue> {
+        Some(AttributeValue::Ints(values.into_iter().map(Into::into).collect()))
+    }
+    match value {
+        netcdf::AttrValue::Str(s) => Some(AttributeValue::Text(s)),
+        netcdf::AttrValue::Strs(s) => Some(AttributeValue::Texts(s)),
+        netcdf::AttrValue::Schar(v) => Some(AttributeValue::Int(v.into())),
+        netcdf::AttrValue::Short(v) => Some(AttributeValue::Int(v.into())),
+        netcdf::AttrValue::Ushort(v) => Some(AttributeValue::Int(v.into())),
+        netcdf::AttrValue::Int(v) => Some(AttributeValue::Int(v.into())),
+        netcdf::AttrValue::Uint(v) => Some(AttributeValue::Int(v.into())),
+        netcdf::AttrValue::Longlong(v) => Some(AttributeValue::Int(v)),
+        netcdf::AttrValue::Ulonglong(v) => i64::try_from(v).ok().map(AttributeValue::Int),
+        netcdf::AttrValue::Schars(v) => ints(v),
+        netcdf::AttrValue::Shorts(v) => ints(v),
+        netcdf::AttrValue::Ushorts(v) => ints(v),
+        netcdf::AttrValue::Ints(v) => ints(v),
+        netcdf::AttrValue::Uints(v) => ints(v),
+        netcdf::AttrValue::Longlongs(v) => ints(v),
+        netcdf::AttrValue::Float(v) => Some(AttributeValue::Float(v.into())),
+        netcdf::AttrValue::Double(v) => Some(AttributeValue::Float(v)),
+        netcdf::AttrValue::Floats(v) => Some(AttributeValue::Floats(v.into_iter().map(Into::into).collect())),
+        netcdf::AttrValue::Doubles(v) => Some(AttributeValue::Floats(v)),
+        _ => None,
+    }
+}
+
+/// Typed attributes of a NetCDF file or variable, leaving out those in `skip`
+pub fn read_attributes(attrs: impl Iterator<Item = netcdf::Attribute>, skip: &[&str]) -> Attributes {
+    attrs
+        .filter(|a| !skip.contains(&a.name()))
+        .filter_map(|a| {
+            let value = attribute_value(a.value().ok()?)?;
+            Some((a.name().to_string(), value))
+        })
+        .collect()
+}
@@ -0,0 +1,205 @@
+//! Reader for OPERA ODIM_H5 composite (2D image) products
+//!
+//! Composites (`/what/object` = `COMP` or `IMAGE`) are Cartesian images on
+//! a projection given as a PROJ.4 string in `/where/projdef`. Each
+//! `datasetN/dataM` group becomes one single-level field of a
+//! [`GriddedData`], unpacked with its `gain`/`offset` and with `nodata`
+//! cells set to NaN.
+
+use std::path::Path;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use hdf5::types::{FixedAscii, VarLenAscii, VarLenUnicode};
+use ndarray::Array3;
+
+use crate::transforms::georeference::Projection;
+use crate::{GridField, GriddedData, RadishError, Result};
+
+/// Read an ODIM_H5 composite or image product
+///
+/// The grid axes are the projected pixel centers, increasing northward and
+/// eastward (ODIM stores the top row first). `undetect` cells are 0 for
+/// precipitation quantities (`RATE`, `ACRR`) and NaN otherwise.
+pub fn read_odim_composite(path: impl AsRef<Path>) -> Result<GriddedData> {
+    let file = hdf5::File::open(path.as_ref())?;
+
+    let what = file.group("what")?;
+    let object = read_string(&what, "object").unwrap_or_default();
+    if !matches!(object.as_str(), "COMP" | "IMAGE") {
+        return Err(RadishError::InvalidFormat(format!(
+            "ODIM object '{}' is not a composite or image product",
+            object
+        )));
+    }
+    let time = read_datetime(&what, "date", "time")?;
+
+    let location = file.group("where")?;
+    let projdef = read_string(&location, "projdef")
+        .ok_or_else(|| RadishError::MissingAttribute("where/projdef".to_string()))?;
+    let projection = Projection::from_proj4(&projdef)?;
+    let nx = read_number(&location, "xsize")? as usize;
+    let ny = read_number(&location, "ysize")? as usize;
+    let xscale = read_number(&location, "xscale")?;
+    let yscale = read_number(&location, "yscale")?;
+    let (x_ll, y_ll) = projection.forward(
+        read_number(&location, "LL_lon")?,
+        read_number(&location, "LL_lat")?,
+    );
+
+    let x = (0..nx).map(|i| x_ll + (i as f64 + 0.5) * xscale).collect();
+    let y = (0..ny).map(|j| y_ll + (j as f64 + 0.5) * yscale).collect();
+
+    let mut datasets = numbered_groups(&file, "dataset")?;
+    datasets.sort_by_key(|(n, _)| *n);
+
+    // Product height (CAPPI/PCAPPI) if the first dataset defines one
+    let height = datasets
+        .first()
+        .and_then(|(_, name)| file.group(&format!("{}/what", name)).ok())
+        .filter(|what| {
+            read_string(what, "product").is_some_and(|p| p == "CAPPI" || p == "PCAPPI")
+        })
+        .and_then(|what| read_number(&what, "prodpar").ok())
+        .unwrap_or(0.0);
+
+    let mut grid = GriddedData::new(time, projection, x, y, vec![height]);
+    grid.attributes.insert("Conventions".to_string(), "ODIM_H5".to_string());
+    grid.attributes.insert("object".to_string(), object);
+    grid.attributes.insert("projdef".to_string(), projdef);
+    if let Some(source) = read_string(&what, "source") {
+        grid.attributes.insert("source".to_string(), source);
+    }
+
+    for (_, dataset_name) in &datasets {
+        let dataset = file.group(dataset_name)?;
+        let dataset_what = dataset.group("what").ok();
+
+        let mut data_groups = numbered_groups(&dataset, "data")?;
+        data_groups.sort_by_key(|(n, _)| *n);
+        for (_, data_name) in &data_groups {
+            let group = dataset.group(data_name)?;
+            // Data-level `what` overrides the dataset-level one
+            let data_what = group.group("what").ok();
+            let lookup = |name: &str| {
+                data_what
+                    .as_ref()
+                    .and_then(|w| read_number(w, name).ok())
+                    .or_else(|| dataset_what.as_ref().and_then(|w| read_number(w, name).ok()))
+            };
+            let quantity = data_what
+                .as_ref()
+                .and_then(|w| read_string(w, "quantity"))
+                .or_else(|| dataset_what.as_ref().and_then(|w| read_string(w, "quantity")))
+                .ok_or_else(|| {
+                    RadishError::MissingAttribute(format!("{}/{}/what/quantity", dataset_name, data_name))
+                })?;
+
+            let values = group.dataset("data")?;
+            let shape = values.shape();
+            if shape != [ny, nx] {
+                return Err(RadishError::InvalidFormat(format!(
+                    "{}/{}/data has shape {:?}, expected [{}, {}]",
+                    dataset_name, data_name, shape, ny, nx
+                )));
+            }
+            let raw = values.read_raw::<f64>()?;
+
+            let gain = lookup("gain").unwrap_or(1.0);
+            let offset = lookup("offset").unwrap_or(0.0);
+            let nodata = lookup("nodata");
+            let undetect = lookup("undetect");
+            let undetect_value = if matches!(quantity.as_str(), "RATE" | "ACRR") {
+                0.0
+            } else {
+                f32::NAN
+            };
+
+            // Flip rows so that y increases with the row index
+            let mut data = Array3::from_elem((1, ny, nx), f32::NAN);
+            for (row, chunk) in raw.chunks(nx).enumerate() {
+                let j = ny - 1 - row;
+                for (i, &v) in chunk.iter().enumerate() {
+                    data[[0, j, i]] = if nodata == Some(v) {
+                        f32::NAN
+                    } else if undetect == Some(v) {
+                        undetect_value
+                    } else {
+                        (v * gain + offset) as f32
+                    };
+                }
+            }
+
+            let name = if grid.fields.contains_key(&quantity) {
+                format!("{}_{}", quantity, dataset_name)
+            } else {
+                quantity.clone()
+            };
+            let mut field = GridField::new(name, quantity_units(&quantity).to_string(), data);
+            field.attributes.insert("quantity".to_string(), quantity);
+            if let Some(product) = dataset_what.as_ref().and_then(|w| read_string(w, "product")) {
+                field.attributes.insert("product".to_string(), product);
+            }
+            grid.add_field(field)?;
+        }
+    }
+
+    Ok(grid)
+}
+
+/// Units of common ODIM quantities
+fn quantity_units(quantity: &str) -> &'static str {
+    match quantity {
+        "TH" | "TV" | "DBZH" | "DBZV" | "MAXDBZH" => "dBZ",
+        "ZDR" => "dB",
+        "VRAD" | "VRADH" | "VRADDH" => "m/s",
+        "RATE" => "mm/h",
+        "ACRR" => "mm",
+        "HGHT" => "km",
+        _ => "",
+    }
+}
+
+/// Names of `prefixN` members of a group with their index N
+fn numbered_groups(group: &hdf5::Group, prefix: &str) -> Result<Vec<(usize, String)>> {
+    Ok(group
+        .member_names()?
+        .into_iter()
+        .filter_map(|name| {
+            let n = name.strip_prefix(prefix)?.parse::<usize>().ok()?;
+            Some((n, name))
+        })
+        .collect())
+}
+
+/// Read a string attribute stored as fixed-length or variable-length string
+fn read_string(location: &hdf5::Location, name: &str) -> Option<String> {
+    let attr = location.attr(name).ok()?;
+    if let Ok(s) = attr.read_scalar::<FixedAscii<256>>() {
+        return Some(s.as_str().trim_end_matches('\0').to_string());
+    }
+    if let Ok(s) = attr.read_scalar::<VarLenUnicode>() {
+        return Some(s.as_str().to_string());
+    }
+    attr.read_scalar::<VarLenAscii>().ok().map(|s| s.as_str().to_string())
+}
+
+/// Read a numeric attribute
+fn read_number(location: &hdf5::Location, name: &str) -> Result<f64> {
+    location
+        .attr(name)
+        .and_then(|a| a.read_scalar::<f64>())
+        .map_err(|_| RadishError::MissingAttribute(format!("{}/{}", location.name(), name)))
+}
+
+/// Parse ODIM `YYYYMMDD` and `HHMMSS` attributes
+fn read_datetime(location: &hdf5::Location, date: &str, time: &str) -> Result<DateTime<Utc>> {
+    let date_str = read_string(location, date)
+        .ok_or_else(|| RadishError::MissingAttribute(format!("what/{}", date)))?;
+    let time_str = read_string(location, time)
+        .ok_or_else(|| RadishError::MissingAttribute(format!("what/{}", time)))?;
+    let date = NaiveDate::parse_from_str(date_str.trim(), "%Y%m%d")
+        .map_err(|e| RadishError::InvalidFormat(format!("Invalid ODIM date '{}': {}", date_str, e)))?;
+    let time = NaiveTime::parse_from_str(time_str.trim(), "%H%M%S")
+        .map_err(|e| RadishError::InvalidFormat(format!("Invalid ODIM time '{}': {}", time_str, e)))?;
+    Ok(Utc.from_utc_datetime(&NaiveDateTime::new(date, time)))
+}
@@ -0,0 +1,266 @@
+//! Reader for environmental temperature and wind profiles
+//!
+//! Soundings and model columns provide the freezing and -20 °C levels used
+//! by hail products, the temperature of each gate used for precipitation
+//! phase, and the first guess wind of velocity dealiasing. Two sources are
+//! read into a [`Profile`]:
+//!
+//! - A simple text format: `#` comment lines, a header line naming the
+//!   columns, then one line per level with whitespace or comma separated
+//!   values. Recognized columns are `height` (meters MSL), `temperature`
+//!   (°C), `u` and `v` (m/s), or `speed` (m/s) and `direction` (degrees,
+//!   meteorological convention) in place of `u` and `v`; other columns are
+//!   ignored. `nan`, `-9999` and empty fields are missing.
+//! - A NetCDF model column with one-dimensional `height` (or `z`,
+//!   `altitude`), `temperature` (or `t`, `ta`; kelvin or °C), `u` (or `ua`)
+//!   and `v` (or `va`) variables.
+
+use std::path::Path;
+
+use ndarray::Array2;
+
+use crate::io::units::{unit_conversion, Quantity};
+use crate::transforms::dealias::WindProfile;
+use crate::transforms::georeference::{beam_height, GeoreferenceOptions};
+use crate::{MomentData, RadishError, Result, SweepData};
+
+/// Vertical profile of temperature and wind
+///
+/// Levels are sorted by increasing height; missing values are NaN.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Profile {
+    /// Heights above mean sea level (meters)
+    pub height: Vec<f64>,
+    /// Temperature (°C)
+    pub temperature: Vec<f64>,
+    /// Eastward wind component (m/s)
+    pub u: Vec<f64>,
+    /// Northward wind component (m/s)
+    pub v: Vec<f64>,
+}
+
+impl Profile {
+    /// Create a profile, sorting the levels by height
+    pub fn new(height: Vec<f64>, temperature: Vec<f64>, u: Vec<f64>, v: Vec<f64>) -> Result<Self> {
+        let n = height.len();
+        if temperature.len() != n || u.len() != n || v.len() != n {
+            return Err(RadishError::InvalidFormat(
+                "Profile height, temperature, u and v must have the same length".to_string(),
+            ));
+        }
+        if n == 0 {
+            return Err(RadishError::InvalidFormat("Profile is empty".to_string()));
+        }
+        if height.iter().any(|h| !h.is_finite()) {
+            return Err(RadishError::InvalidFormat("Profile heights must be finite".to_string()));
+        }
+        let mut order: Vec<usize> = (0..n).collect();
+        order.sort_by(|&a, &b| height[a].total_cmp(&height[b]));
+        let sorted = |values: &[f64]| order.iter().map(|&k| values[k]).collect();
+        Ok(Self {
+            height: sorted(&height),
+            temperature: sorted(&temperature),
+            u: sorted(&u),
+            v: sorted(&v),
+        })
+    }
+
+    /// Temperature (°C) at a height, linearly interpolated between valid levels
+    ///
+    /// NaN below the lowest or above the highest valid level.
+    pub fn temperature_at(&self, height: f64) -> f64 {
+        let mut below: Option<(f64, f64)> = None;
+        for (h, t) in self.valid_temperatures() {
+            if h == height {
+                return t;
+            }
+            if h > height {
+                return below.map_or(f64::NAN, |(h0, t0)| t0 + (height - h0) / (h - h0) * (t - t0));
+            }
+            below = Some((h, t));
+        }
+        f64::NAN
+    }
+
+    /// Lowest height (meters MSL) at which the temperature falls to `temperature` (°C)
+    ///
+    /// The lowest valid level if it is already at or below that temperature,
+    /// `None` if the profile never reaches it.
+    pub fn isotherm_height(&self, temperature: f64) -> Option<f64> {
+        let levels: Vec<(f64, f64)> = self.valid_temperatures().collect();
+        let &(h0, t0) = levels.first()?;
+        if t0 <= temperature {
+            return Some(h0);
+        }
+        levels.windows(2).find_map(|w| {
+            let ((h0, t0), (h1, t1)) = (w[0], w[1]);
+            (t0 > temperature && t1 <= temperature).then(|| h0 + (t0 - temperature) / (t0 - t1) * (h1 - h0))
+        })
+    }
+
+    /// Height (meters MSL) of the 0 °C isotherm
+    pub fn freezing_level(&self) -> Option<f64> {
+        self.isotherm_height(0.0)
+    }
+
+    /// Wind profile of the levels with valid wind, e.g. as a dealiasing first guess
+    pub fn wind_profile(&self) -> Result<WindProfile> {
+        let levels: Vec<usize> = (0..self.height.len())
+            .filter(|&k| self.u[k].is_finite() && self.v[k].is_finite())
+            .collect();
+        WindProfile::new(
+            levels.iter().map(|&k| self.height[k]).collect(),
+            levels.iter().map(|&k| self.u[k]).collect(),
+            levels.iter().map(|&k| self.v[k]).collect(),
+        )
+    }
+
+    /// Temperature (°C) of every gate of a sweep, as a moment for
+    /// `PhaseInput::TemperatureMoment`
+    ///
+    /// `altitude` is the height of the radar above mean sea level (meters).
+    pub fn temperature_moment(
+        &self,
+        sweep: &SweepData,
+        altitude: f64,
+        georeference: &GeoreferenceOptions,
+        name: &str,
+    ) -> MomentData {
+        let coords = &sweep.coordinates;
+        let data = Array2::from_shape_fn((coords.num_rays(), coords.num_gates()), |(i, j)| {
+            let height = altitude + beam_height(coords.range[j] as f64, coords.elevation[i] as f64, georeference);
+            self.temperature_at(height) as f32
+        });
+        let mut moment = MomentData::new(name.to_string(), "degC".to_string(), data);
+        moment.standard_name = Some("air_temperature".to_string());
+        moment.long_name = Some("Air temperature from environmental profile".to_string());
+        moment
+    }
+
+    fn valid_temperatures(&self) -> impl Iterator<Item = (f64, f64)> + '_ {
+        self.height
+            .iter()
+            .zip(&self.temperature)
+            .filter(|(_, t)| t.is_finite())
+            .map(|(&h, &t)| (h, t))
+    }
+}
+
+/// Read a profile in the simple text format
+pub fn read_sounding(path: impl AsRef<Path>) -> Result<Profile> {
+    parse_sounding(&std::fs::read_to_string(path.as_ref())?)
+}
+
+/// Parse a profile in the simple text format
+pub fn parse_sounding(text: &str) -> Result<Profile> {
+    let mut lines = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'));
+    let split = |line: &str| -> Vec<String> {
+        if line.contains(',') {
+            line.split(',').map(|f| f.trim().to_string()).collect()
+        } else {
+            line.split_whitespace().map(str::to_string).collect()
+        }
+    };
+    let header: Vec<String> = split(
+        lines
+            .next()
+            .ok_or_else(|| RadishError::InvalidFormat("Sounding has no header line".to_string()))?,
+    )
+    .iter()
+    .map(|h| h.to_lowercase())
+    .collect();
+    let column = |name: &str| header.iter().position(|h| h == name);
+    let height_column =
+        column("height").ok_or_else(|| RadishError::MissingVariable("height column".to_string()))?;
+
+    let mut rows: Vec<Vec<f64>> = Vec::new();
+    for (n, line) in lines.enumerate() {
+        let fields = split(line);
+        if fields.len() > header.len() {
+            return Err(RadishError::InvalidFormat(format!(
+                "Sounding level {} has {} fields for {} columns",
+                n + 1,
+                fields.len(),
+                header.len()
+            )));
+        }
+        let values = (0..header.len())
+            .map(|k| match fields.get(k).map(|f| f.to_lowercase()) {
+                None => Ok(f64::NAN),
+                Some(f) if f.is_empty() || f == "nan" => Ok(f64::NAN),
+                Some(f) => f
+                    .parse::<f64>()
+                    .map(|v| if v == -9999.0 { f64::NAN } else { v })
+                    .map_err(|_| RadishError::Conversion(format!("Invalid value '{}' at sounding level {}", f, n + 1))),
+            })
+            .collect::<Result<Vec<f64>>>()?;
+        if values[height_column].is_finite() {
+            rows.push(values);
+        }
+    }
+
+    let values = |k: Option<usize>| -> Vec<f64> { rows.iter().map(|r| k.map_or(f64::NAN, |k| r[k])).collect() };
+    let height = values(Some(height_column));
+    let temperature = values(column("temperature"));
+    let (u, v) = match (column("u"), column("v"), column("speed"), column("direction")) {
+        (Some(u), Some(v), _, _) => (values(Some(u)), values(Some(v))),
+        (_, _, Some(speed), Some(direction)) => values(Some(speed))
+            .iter()
+            .zip(values(Some(direction)))
+            .map(|(s, d)| (-s * d.to_radians().sin(), -s * d.to_radians().cos()))
+            .unzip(),
+        _ => (values(None), values(None)),
+    };
+    Profile::new(height, temperature, u, v)
+}
+
+/// Read a single model column from a NetCDF file
+pub fn read_model_column(path: impl AsRef<Path>) -> Result<Profile> {
+    let file = netcdf::open(path.as_ref())?;
+    let find = |names: &[&str]| names.iter().find_map(|&name| file.variable(name));
+    let read = |variable: &netcdf::Variable| -> Result<(Vec<f64>, Option<String>)> {
+        let mut values: Vec<f64> = variable.get_values(..)?;
+        if let Some(fill) = crate::io::read_numeric_attribute::<f64>(variable.attributes(), "_FillValue") {
+            values.iter_mut().filter(|v| **v == fill).for_each(|v| *v = f64::NAN);
+        }
+        let units = crate::io::read_string_attribute(variable.attributes(), "units");
+        Ok((values, units))
+    };
+
+    let height_variable = find(&["height", "z", "altitude"])
+        .ok_or_else(|| RadishError::MissingVariable("height".to_string()))?;
+    let (mut height, units) = read(&height_variable)?;
+    if let Some(conversion) = unit_conversion("height", units.as_deref(), Quantity::Length)? {
+        height.iter_mut().for_each(|h| *h = conversion.convert(*h));
+    }
+    let missing = || vec![f64::NAN; height.len()];
+
+    let temperature = match find(&["temperature", "t", "ta"]) {
+        Some(variable) => {
+            let (values, units) = read(&variable)?;
+            match units.as_deref().map(str::trim) {
+                Some("K" | "kelvin" | "Kelvin") => values.iter().map(|t| t - 273.15).collect(),
+                Some("degC" | "C" | "celsius" | "degree_Celsius" | "degrees_Celsius") | None => values,
+                Some(other) => {
+                    return Err(RadishError::Conversion(format!(
+                        "Unsupported units '{}' for temperature",
+                        other
+                    )))
+                }
+            }
+        }
+        None => missing(),
+    };
+    let u = match find(&["u", "ua"]) {
+        Some(variable) => read(&variable)?.0,
+        None => missing(),
+    };
+    let v = match find(&["v", "va"]) {
+        Some(variable) => read(&variable)?.0,
+        None => missing(),
+    };
+    Profile::new(height, temperature, u, v)
+}
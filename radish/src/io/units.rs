@@ -0,0 +1,151 @@
+//! Normalization of coordinate units at read time
+//!
+//! The data model stores ranges in meters and angles in degrees. Some files
+//! declare other units in their `units` attributes (ranges in km, angles in
+//! radians in some ODIM files); readers convert such coordinates on read and
+//! record the conversions in the volume attributes. Ray times are decoded
+//! from CF `"<unit> since <reference>"` units with [`parse_time_units`].
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+
+use crate::model::Attributes;
+use crate::{RadishError, Result};
+
+/// Volume attribute listing the unit conversions applied on read
+pub const UNIT_CONVERSIONS_ATTRIBUTE: &str = "unit_conversions";
+
+/// Kind of coordinate quantity, with its canonical unit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quantity {
+    /// Lengths, in meters
+    Length,
+    /// Angles, in degrees
+    Angle,
+}
+
+impl Quantity {
+    /// Canonical unit of the quantity
+    pub fn canonical_units(&self) -> &'static str {
+        match self {
+            Quantity::Length => "m",
+            Quantity::Angle => "degrees",
+        }
+    }
+
+    /// Factor converting a value in `units` to the canonical unit, if the units are known
+    pub fn factor(&self, units: &str) -> Option<f64> {
+        let units = units.trim().to_lowercase();
+        match (self, units.as_str()) {
+            (Quantity::Length, "m" | "meter" | "meters" | "metre" | "metres") => Some(1.0),
+            (Quantity::Length, "km" | "kilometer" | "kilometers" | "kilometre" | "kilometres") => Some(1000.0),
+            (Quantity::Angle, "degree" | "degrees" | "deg" | "degrees_north" | "degrees_east") => Some(1.0),
+            (Quantity::Angle, "rad" | "radian" | "radians") => Some(180.0 / std::f64::consts::PI),
+            _ => None,
+        }
+    }
+}
+
+/// Conversion of a coordinate variable to its canonical unit
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnitConversion {
+    /// Variable the conversion applies to
+    pub variable: String,
+    /// Units declared in the file
+    pub from: String,
+    /// Canonical units
+    pub to: &'static str,
+    /// Multiplicative factor from `from` to `to`
+    pub factor: f64,
+}
+
+impl UnitConversion {
+    /// Convert a value
+    pub fn convert(&self, value: f64) -> f64 {
+        value * self.factor
+    }
+
+    /// Convert values in place
+    pub fn convert_slice(&self, values: &mut [f32]) {
+        for v in values {
+            *v = (*v as f64 * self.factor) as f32;
+        }
+    }
+}
+
+impl std::fmt::Display for UnitConversion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {} -> {}", self.variable, self.from, self.to)
+    }
+}
+
+/// Conversion needed for a variable declared in `units`
+///
+/// Returns `None` when the variable is already in canonical units or has no
+/// units, and an error for units that are not a known unit of `quantity`.
+pub fn unit_conversion(variable: &str, units: Option<&str>, quantity: Quantity) -> Result<Option<UnitConversion>> {
+    let Some(units) = units.filter(|u| !u.trim().is_empty()) else {
+        return Ok(None);
+    };
+    let factor = quantity.factor(units).ok_or_else(|| {
+        RadishError::Conversion(format!(
+            "Unsupported units '{}' for {} (expected {:?})",
+            units, variable, quantity
+        ))
+    })?;
+
+    Ok((factor != 1.0).then(|| UnitConversion {
+        variable: variable.to_string(),
+        from: units.to_string(),
+        to: quantity.canonical_units(),
+        factor,
+    }))
+}
+
+/// Record conversions in attributes, as `"range: km -> m; azimuth: rad -> degrees"`
+pub fn record_conversions(attributes: &mut Attributes, conversions: &[UnitConversion]) {
+    if conversions.is_empty() {
+        return;
+    }
+    let description = conversions
+        .iter()
+        .map(|c| c.to_string())
+        .collect::<Vec<_>>()
+        .join("; ");
+    attributes.insert(UNIT_CONVERSIONS_ATTRIBUTE.to_string(), description.into());
+}
+
+/// Parse CF time units such as `"seconds since 2024-05-01T12:00:00Z"`
+///
+/// Returns the number of seconds per unit and the reference time. Units
+/// may be seconds, milliseconds, minutes, hours or days; the reference may
+/// use a `T` or a space between date and time, omit the time, and end in
+/// `Z` or `UTC`. Other time zones are not supported.
+pub fn parse_time_units(units: &str) -> Result<(f64, DateTime<Utc>)> {
+    let invalid = || RadishError::Conversion(format!("Unsupported time units '{}'", units));
+    let (unit, reference) = units.trim().split_once(" since ").ok_or_else(invalid)?;
+    let factor = match unit.trim().to_lowercase().as_str() {
+        "seconds" | "second" | "secs" | "sec" | "s" => 1.0,
+        "milliseconds" | "millisecond" | "msec" | "ms" => 1e-3,
+        "minutes" | "minute" | "mins" | "min" => 60.0,
+        "hours" | "hour" | "hrs" | "hr" | "h" => 3600.0,
+        "days" | "day" | "d" => 86400.0,
+        _ => return Err(invalid()),
+    };
+
+    let reference = reference.trim();
+    let reference = reference
+        .strip_suffix("UTC")
+        .or_else(|| reference.strip_suffix('Z'))
+        .unwrap_or(reference)
+        .trim();
+    let time = ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(reference, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(reference, "%Y-%m-%d")
+                .ok()
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+        })
+        .ok_or_else(invalid)?;
+    Ok((factor, time.and_utc()))
+}
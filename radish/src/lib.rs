@@ -1,18 +1,20 @@
-/// Radish: High-performance weather radar data library
-///
-/// This library provides fast, memory-efficient reading of multiple weather radar
-/// formats with a unified interface, normalizing to the CfRadial2/FM301 standard.
+//! Radish: High-performance weather radar data library
+//!
+//! This library provides fast, memory-efficient reading of multiple weather radar
+//! formats with a unified interface, normalizing to the CfRadial2/FM301 standard.
 
 pub mod error;
+pub mod compare;
 pub mod model;
 pub mod backends;
 pub mod io;
 pub mod transforms;
+pub mod writers;
 
 // Re-export commonly used types
 pub use error::{RadishError, Result};
-pub use model::{VolumeData, VolumeMetadata, SweepData, SweepMetadata, MomentData, Coordinates};
-pub use backends::RadarBackend;
+pub use model::{VolumeData, VolumeMetadata, SweepData, SweepMetadata, RayMetadata, PlatformMotion, MomentData, MomentStorage, Coordinates, GriddedData, GridField, NameAliases, Provenance, AttributeValue};
+pub use backends::{LazyVolume, RadarBackend};
 
 #[cfg(test)]
 mod tests {
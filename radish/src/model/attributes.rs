@@ -0,0 +1,137 @@
+//! Typed attributes
+//!
+//! Files carry attributes beyond those mapped onto metadata fields:
+//! processing history, site identifiers, calibration constants. Backends
+//! keep them in the `attributes` of the volume and of each moment with
+//! their type, text, integer or floating point, scalar or array, so that
+//! writers can emit them as they were read.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Attributes by name
+pub type Attributes = HashMap<String, AttributeValue>;
+
+/// Value of an attribute
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum AttributeValue {
+    /// Text
+    Text(String),
+    /// Integer, of any width and signedness in the file
+    Int(i64),
+    /// Floating point number, single or double precision in the file
+    Float(f64),
+    /// Array of text
+    Texts(Vec<String>),
+    /// Array of integers
+    Ints(Vec<i64>),
+    /// Array of floating point numbers
+    Floats(Vec<f64>),
+}
+
+impl AttributeValue {
+    /// The text of a text attribute
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            AttributeValue::Text(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// The value of a numeric scalar attribute, or of a text attribute holding a number
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            AttributeValue::Int(v) => Some(*v as f64),
+            AttributeValue::Float(v) => Some(*v),
+            AttributeValue::Text(s) => s.trim().parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// The value of an integer attribute, or of a text attribute holding an integer
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            AttributeValue::Int(v) => Some(*v),
+            AttributeValue::Text(s) => s.trim().parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+/// Text as is, numbers as Rust formats them, arrays separated by spaces
+impl fmt::Display for AttributeValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn join<T: fmt::Display>(values: &[T]) -> String {
+            values.iter().map(T::to_string).collect::<Vec<_>>().join(" ")
+        }
+        match self {
+            AttributeValue::Text(s) => f.write_str(s),
+            AttributeValue::Int(v) => write!(f, "{}", v),
+            AttributeValue::Float(v) => write!(f, "{}", v),
+            AttributeValue::Texts(v) => f.write_str(&join(v)),
+            AttributeValue::Ints(v) => f.write_str(&join(v)),
+            AttributeValue::Floats(v) => f.write_str(&join(v)),
+        }
+    }
+}
+
+impl From<String> for AttributeValue {
+    fn from(value: String) -> Self {
+        AttributeValue::Text(value)
+    }
+}
+
+impl From<&str> for AttributeValue {
+    fn from(value: &str) -> Self {
+        AttributeValue::Text(value.to_string())
+    }
+}
+
+impl From<i64> for AttributeValue {
+    fn from(value: i64) -> Self {
+        AttributeValue::Int(value)
+    }
+}
+
+impl From<f64> for AttributeValue {
+    fn from(value: f64) -> Self {
+        AttributeValue::Float(value)
+    }
+}
+
+impl From<Vec<String>> for AttributeValue {
+    fn from(value: Vec<String>) -> Self {
+        AttributeValue::Texts(value)
+    }
+}
+
+impl From<Vec<i64>> for AttributeValue {
+    fn from(value: Vec<i64>) -> Self {
+        AttributeValue::Ints(value)
+    }
+}
+
+impl From<Vec<f64>> for AttributeValue {
+    fn from(value: Vec<f64>) -> Self {
+        AttributeValue::Floats(value)
+    }
+}
+
+/// Compares the displayed value, so `"212"` equals both text and integer attributes
+impl PartialEq<str> for AttributeValue {
+    fn eq(&self, other: &str) -> bool {
+        match self {
+            AttributeValue::Text(s) => s == other,
+            _ => self.to_string().as_str() == other,
+        }
+    }
+}
+
+impl PartialEq<&str> for AttributeValue {
+    fn eq(&self, other: &&str) -> bool {
+        self == *other
+    }
+}
@@ -0,0 +1,345 @@
+//! Builders for synthetic volumes
+//!
+//! Tests, benchmarks and simulations need volumes with known contents
+//! without reading a file. [`SweepBuilder`] creates a sweep of evenly
+//! spaced rays and gates whose moments follow analytic [`Pattern`]s, and
+//! [`VolumeBuilder`] stacks such sweeps at a list of elevations, with
+//! consistent ray times and volume metadata.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use ndarray::Array2;
+use radish_types::SweepMode;
+
+use super::geometry::{antenna_to_cartesian, GeoreferenceOptions};
+use super::moment::MomentMetadata;
+use super::{Coordinates, MomentData, SweepData, SweepMetadata, VolumeData, VolumeMetadata};
+
+/// Values of a synthetic moment at every gate
+#[derive(Clone)]
+pub enum Pattern {
+    /// The same value everywhere
+    Constant(f32),
+    /// Linear in range: `offset + slope * range_km`
+    RangeRamp {
+        /// Value at the radar
+        offset: f32,
+        /// Change per kilometer of range
+        slope: f32,
+    },
+    /// A Gaussian storm cell over a uniform background
+    Cell {
+        /// Cell center east of the radar (meters)
+        x: f64,
+        /// Cell center north of the radar (meters)
+        y: f64,
+        /// Value at the center
+        peak: f32,
+        /// Distance (meters) at which the excess over the background falls to 61%
+        radius: f64,
+        /// Value far from the cell
+        background: f32,
+    },
+    /// Radial velocity of a horizontally uniform wind
+    ///
+    /// Folded into the Nyquist interval when the sweep has a Nyquist velocity.
+    UniformWind {
+        /// Wind speed (m/s)
+        speed: f32,
+        /// Direction the wind blows from (degrees, meteorological convention)
+        direction: f32,
+    },
+    /// Any function of azimuth, elevation (degrees) and range (meters)
+    Function(Arc<dyn Fn(f32, f32, f32) -> f32 + Send + Sync>),
+}
+
+impl Pattern {
+    /// A pattern from a function of azimuth, elevation (degrees) and range (meters)
+    pub fn function(f: impl Fn(f32, f32, f32) -> f32 + Send + Sync + 'static) -> Self {
+        Pattern::Function(Arc::new(f))
+    }
+
+    /// Value at a gate, before folding
+    fn value(&self, azimuth: f32, elevation: f32, range: f32) -> f32 {
+        match self {
+            Pattern::Constant(v) => *v,
+            Pattern::RangeRamp { offset, slope } => offset + slope * range / 1000.0,
+            Pattern::Cell { x, y, peak, radius, background } => {
+                let (gx, gy, _) = antenna_to_cartesian(
+                    range as f64,
+                    azimuth as f64,
+                    elevation as f64,
+                    &GeoreferenceOptions::default(),
+                );
+                let d2 = (gx - x).powi(2) + (gy - y).powi(2);
+                background + (peak - background) * (-d2 / (2.0 * radius * radius)).exp() as f32
+            }
+            Pattern::UniformWind { speed, direction } => {
+                let (az, el, dir) = (azimuth.to_radians(), elevation.to_radians(), direction.to_radians());
+                let (u, v) = (-speed * dir.sin(), -speed * dir.cos());
+                (u * az.sin() + v * az.cos()) * el.cos()
+            }
+            Pattern::Function(f) => f(azimuth, elevation, range),
+        }
+    }
+}
+
+impl std::fmt::Debug for Pattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Pattern::Constant(v) => f.debug_tuple("Constant").field(v).finish(),
+            Pattern::RangeRamp { offset, slope } => f
+                .debug_struct("RangeRamp")
+                .field("offset", offset)
+                .field("slope", slope)
+                .finish(),
+            Pattern::Cell { x, y, peak, radius, background } => f
+                .debug_struct("Cell")
+                .field("x", x)
+                .field("y", y)
+                .field("peak", peak)
+                .field("radius", radius)
+                .field("background", background)
+                .finish(),
+            Pattern::UniformWind { speed, direction } => f
+                .debug_struct("UniformWind")
+                .field("speed", speed)
+                .field("direction", direction)
+                .finish(),
+            Pattern::Function(_) => f.write_str("Function(..)"),
+        }
+    }
+}
+
+/// Builder for a synthetic sweep
+///
+/// Defaults to a 0.5° PPI of 360 rays centered on whole degrees plus half
+/// a degree, and 100 gates of 250 m, scanned in 12 s from 2024-01-01 UTC.
+#[derive(Debug, Clone)]
+pub struct SweepBuilder {
+    fixed_angle: f64,
+    sweep_mode: SweepMode,
+    num_rays: usize,
+    ray_spacing: f32,
+    first_ray: f32,
+    num_gates: usize,
+    gate_spacing: f32,
+    first_gate: f32,
+    start_time: DateTime<Utc>,
+    duration: f64,
+    nyquist_velocity: Option<f64>,
+    moments: Vec<(String, String, Pattern)>,
+}
+
+impl SweepBuilder {
+    /// A sweep at a fixed angle (degrees)
+    pub fn new(fixed_angle: f64) -> Self {
+        Self {
+            fixed_angle,
+            sweep_mode: SweepMode::Azimuth,
+            num_rays: 360,
+            ray_spacing: 1.0,
+            first_ray: 0.5,
+            num_gates: 100,
+            gate_spacing: 250.0,
+            first_gate: 125.0,
+            start_time: Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+            duration: 12.0,
+            nyquist_velocity: None,
+            moments: Vec::new(),
+        }
+    }
+
+    /// Set the fixed angle (degrees)
+    pub fn fixed_angle(mut self, angle: f64) -> Self {
+        self.fixed_angle = angle;
+        self
+    }
+
+    /// Set the sweep mode
+    ///
+    /// Rays of RHI modes ([`SweepMode::Elevation`], [`SweepMode::ManualRhi`])
+    /// step in elevation at the fixed azimuth; all others step in azimuth.
+    pub fn sweep_mode(mut self, mode: SweepMode) -> Self {
+        self.sweep_mode = mode;
+        self
+    }
+
+    /// Set the number of rays, their angular spacing and the angle of the first ray (degrees)
+    pub fn rays(mut self, num_rays: usize, spacing: f32, first: f32) -> Self {
+        self.num_rays = num_rays;
+        self.ray_spacing = spacing;
+        self.first_ray = first;
+        self
+    }
+
+    /// Set the number of gates, their spacing and the range of the first gate (meters)
+    pub fn gates(mut self, num_gates: usize, spacing: f32, first: f32) -> Self {
+        self.num_gates = num_gates;
+        self.gate_spacing = spacing;
+        self.first_gate = first;
+        self
+    }
+
+    /// Set the time of the first ray and the duration of the sweep (seconds)
+    pub fn timing(mut self, start_time: DateTime<Utc>, duration: f64) -> Self {
+        self.start_time = start_time;
+        self.duration = duration;
+        self
+    }
+
+    /// Set the Nyquist velocity (m/s), folding wind patterns into it
+    pub fn nyquist_velocity(mut self, nyquist: f64) -> Self {
+        self.nyquist_velocity = Some(nyquist);
+        self
+    }
+
+    /// Add a moment following a pattern
+    pub fn moment(mut self, name: &str, units: &str, pattern: Pattern) -> Self {
+        self.moments.push((name.to_string(), units.to_string(), pattern));
+        self
+    }
+
+    /// Build the sweep
+    pub fn build(&self) -> SweepData {
+        let rhi = matches!(self.sweep_mode, SweepMode::Elevation | SweepMode::ManualRhi);
+        let angles: Vec<f32> = (0..self.num_rays)
+            .map(|i| self.first_ray + i as f32 * self.ray_spacing)
+            .collect();
+        let (azimuth, elevation): (Vec<f32>, Vec<f32>) = if rhi {
+            (vec![self.fixed_angle as f32; self.num_rays], angles)
+        } else {
+            (angles.iter().map(|a| a.rem_euclid(360.0)).collect(), vec![self.fixed_angle as f32; self.num_rays])
+        };
+        let start = self.start_time.timestamp_micros() as f64 / 1e6;
+        let ray_duration = self.duration / self.num_rays.max(1) as f64;
+        let time = (0..self.num_rays).map(|i| start + i as f64 * ray_duration).collect();
+        let range: Vec<f32> = (0..self.num_gates)
+            .map(|j| self.first_gate + j as f32 * self.gate_spacing)
+            .collect();
+
+        let mut moments = HashMap::new();
+        for (name, units, pattern) in &self.moments {
+            let fold = match (pattern, self.nyquist_velocity) {
+                (Pattern::UniformWind { .. }, Some(nyquist)) => Some(nyquist as f32),
+                _ => None,
+            };
+            let data = Array2::from_shape_fn((self.num_rays, self.num_gates), |(i, j)| {
+                let v = pattern.value(azimuth[i], elevation[i], range[j]);
+                match fold {
+                    Some(nyquist) => (v + nyquist).rem_euclid(2.0 * nyquist) - nyquist,
+                    None => v,
+                }
+            });
+            let mut moment = MomentData::new(name.clone(), units.clone(), data);
+            if let Some(meta) = MomentMetadata::from_name(name) {
+                moment.standard_name = Some(meta.standard_name.to_string());
+                moment.long_name = Some(meta.long_name.to_string());
+            }
+            moments.insert(name.clone(), moment);
+        }
+
+        let mut metadata = SweepMetadata::new(0, self.sweep_mode, self.fixed_angle);
+        metadata.nyquist_velocity = self.nyquist_velocity;
+        SweepData::new(metadata, moments, Coordinates::new(time, range, azimuth, elevation))
+    }
+}
+
+/// Builder for a synthetic volume
+///
+/// Every sweep follows a [`SweepBuilder`] template; sweeps are scanned one
+/// after the other, each taking the template's duration. Defaults to a
+/// single 0.5° sweep of the default template, from a radar named
+/// `SYNTHETIC` at 35° N, 97° W and 300 m.
+#[derive(Debug, Clone)]
+pub struct VolumeBuilder {
+    instrument_name: String,
+    latitude: f64,
+    longitude: f64,
+    altitude: f64,
+    elevations: Vec<f64>,
+    template: SweepBuilder,
+}
+
+impl VolumeBuilder {
+    /// A volume of the default template
+    pub fn new() -> Self {
+        Self {
+            instrument_name: "SYNTHETIC".to_string(),
+            latitude: 35.0,
+            longitude: -97.0,
+            altitude: 300.0,
+            elevations: vec![0.5],
+            template: SweepBuilder::new(0.5),
+        }
+    }
+
+    /// Set the instrument name
+    pub fn instrument_name(mut self, name: &str) -> Self {
+        self.instrument_name = name.to_string();
+        self
+    }
+
+    /// Set the radar latitude, longitude (degrees) and altitude (meters)
+    pub fn location(mut self, latitude: f64, longitude: f64, altitude: f64) -> Self {
+        self.latitude = latitude;
+        self.longitude = longitude;
+        self.altitude = altitude;
+        self
+    }
+
+    /// Set the fixed angle of each sweep (degrees)
+    pub fn elevations(mut self, elevations: &[f64]) -> Self {
+        self.elevations = elevations.to_vec();
+        self
+    }
+
+    /// Set the template of every sweep; its start time is that of the volume
+    pub fn sweep(mut self, template: SweepBuilder) -> Self {
+        self.template = template;
+        self
+    }
+
+    /// Add a moment following a pattern to every sweep
+    pub fn moment(mut self, name: &str, units: &str, pattern: Pattern) -> Self {
+        self.template = self.template.moment(name, units, pattern);
+        self
+    }
+
+    /// Build the volume
+    pub fn build(&self) -> VolumeData {
+        let start = self.template.start_time;
+        let sweep_duration = Duration::microseconds((self.template.duration * 1e6) as i64);
+        let sweeps: Vec<SweepData> = self
+            .elevations
+            .iter()
+            .enumerate()
+            .map(|(k, &angle)| {
+                let mut sweep = self.template.clone().fixed_angle(angle);
+                sweep.start_time = start + sweep_duration * k as i32;
+                sweep.build()
+            })
+            .collect();
+        let end = start + sweep_duration * self.elevations.len() as i32;
+
+        let metadata = VolumeMetadata::new(
+            self.instrument_name.clone(),
+            self.latitude,
+            self.longitude,
+            self.altitude,
+            start,
+            end,
+        );
+        let mut volume = VolumeData::new(metadata, sweeps);
+        volume.reindex_sweeps();
+        volume
+    }
+}
+
+impl Default for VolumeBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
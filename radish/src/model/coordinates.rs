@@ -1,11 +1,23 @@
-/// Coordinate data structures
+//! Coordinate data structures
+
+use std::ops::Range;
+
+use chrono::{DateTime, Duration, Utc};
+use ndarray::{s, Array2, Axis};
+
+use super::geometry::{cartesian_to_geographic, compute_gate_xyz, GeoreferenceOptions};
 
 /// Coordinate data for a sweep
 #[derive(Debug, Clone)]
 pub struct Coordinates {
-    /// Time for each ray (seconds since epoch)
+    /// Time for each ray (seconds since `time_reference`)
     pub time: Vec<f64>,
 
+    /// Reference time of `time`, `None` for the Unix epoch
+    ///
+    /// Set by readers to the reference of the file's time units.
+    pub time_reference: Option<DateTime<Utc>>,
+
     /// Range gates (meters from radar)
     pub range: Vec<f32>,
 
@@ -14,6 +26,33 @@ pub struct Coordinates {
 
     /// Elevation angles (degrees)
     pub elevation: Vec<f32>,
+
+    /// Whether each ray was recorded while the antenna moved between sweeps, if known
+    pub antenna_transition: Option<Vec<bool>>,
+
+    /// Number of gates recorded on each ray, if rays have differing gate counts
+    ///
+    /// Moments of ragged sweeps are padded to the longest ray; the gates of
+    /// ray `i` beyond `n_gates_per_ray[i]` hold no data.
+    pub n_gates_per_ray: Option<Vec<usize>>,
+
+    /// Gate x distance east of the radar [rays × gates] (meters), if georeferenced
+    pub gate_x: Option<Array2<f32>>,
+
+    /// Gate y distance north of the radar [rays × gates] (meters), if georeferenced
+    pub gate_y: Option<Array2<f32>>,
+
+    /// Gate height above the radar [rays × gates] (meters), if georeferenced
+    pub gate_z: Option<Array2<f32>>,
+
+    /// Gate longitude [rays × gates] (degrees east), if georeferenced
+    pub gate_longitude: Option<Array2<f64>>,
+
+    /// Gate latitude [rays × gates] (degrees north), if georeferenced
+    pub gate_latitude: Option<Array2<f64>>,
+
+    /// Gate altitude above mean sea level [rays × gates] (meters), if georeferenced
+    pub gate_altitude: Option<Array2<f32>>,
 }
 
 impl Coordinates {
@@ -26,10 +65,91 @@ impl Coordinates {
     ) -> Self {
         Self {
             time,
+            time_reference: None,
             range,
             azimuth,
             elevation,
+            antenna_transition: None,
+            n_gates_per_ray: None,
+            gate_x: None,
+            gate_y: None,
+            gate_z: None,
+            gate_longitude: None,
+            gate_latitude: None,
+            gate_altitude: None,
+        }
+    }
+
+    /// Coordinates of the given rays, in the given order, and a span of gates
+    ///
+    /// Cached per-gate coordinates are selected with them.
+    pub fn select(&self, rays: &[usize], gates: Range<usize>) -> Self {
+        fn pick<T: Copy>(values: &[T], rays: &[usize]) -> Vec<T> {
+            rays.iter().map(|&i| values[i]).collect()
+        }
+        fn pick_gates<T: Clone>(values: &Option<Array2<T>>, rays: &[usize], gates: &Range<usize>) -> Option<Array2<T>> {
+            values
+                .as_ref()
+                .map(|v| v.select(Axis(0), rays).slice(s![.., gates.clone()]).to_owned())
         }
+        Self {
+            time: pick(&self.time, rays),
+            time_reference: self.time_reference,
+            range: self.range[gates.clone()].to_vec(),
+            azimuth: pick(&self.azimuth, rays),
+            elevation: pick(&self.elevation, rays),
+            antenna_transition: self.antenna_transition.as_ref().map(|t| pick(t, rays)),
+            n_gates_per_ray: self.n_gates_per_ray.as_ref().map(|n| {
+                rays.iter()
+                    .map(|&i| n[i].saturating_sub(gates.start).min(gates.len()))
+                    .collect()
+            }),
+            gate_x: pick_gates(&self.gate_x, rays, &gates),
+            gate_y: pick_gates(&self.gate_y, rays, &gates),
+            gate_z: pick_gates(&self.gate_z, rays, &gates),
+            gate_longitude: pick_gates(&self.gate_longitude, rays, &gates),
+            gate_latitude: pick_gates(&self.gate_latitude, rays, &gates),
+            gate_altitude: pick_gates(&self.gate_altitude, rays, &gates),
+        }
+    }
+
+    /// Time of a ray in seconds since the Unix epoch
+    pub fn epoch_time(&self, ray: usize) -> f64 {
+        self.time[ray] + self.time_reference.map_or(0.0, |t| t.timestamp_micros() as f64 / 1e6)
+    }
+
+    /// Times of every ray in seconds since the Unix epoch
+    pub fn epoch_times(&self) -> Vec<f64> {
+        (0..self.num_rays()).map(|i| self.epoch_time(i)).collect()
+    }
+
+    /// Time of a ray, `None` if it is not finite
+    pub fn ray_datetime(&self, ray: usize) -> Option<DateTime<Utc>> {
+        let seconds = self.time[ray];
+        if !seconds.is_finite() {
+            return None;
+        }
+        let reference = self.time_reference.unwrap_or(DateTime::UNIX_EPOCH);
+        reference.checked_add_signed(Duration::microseconds((seconds * 1e6).round() as i64))
+    }
+
+    /// Times of every ray, `None` for rays without a finite time
+    pub fn ray_datetimes(&self) -> Vec<Option<DateTime<Utc>>> {
+        (0..self.num_rays()).map(|i| self.ray_datetime(i)).collect()
+    }
+
+    /// Express ray times relative to another reference (`None` for the Unix epoch)
+    pub fn set_time_reference(&mut self, reference: Option<DateTime<Utc>>) {
+        let offset = |r: Option<DateTime<Utc>>| r.map_or(0, |t| t.timestamp_micros());
+        let shift = (offset(self.time_reference) - offset(reference)) as f64 / 1e6;
+        self.time.iter_mut().for_each(|t| *t += shift);
+        self.time_reference = reference;
+    }
+
+    /// CF units of `time`, e.g. `"seconds since 2024-05-01T12:00:00Z"`
+    pub fn time_units(&self) -> String {
+        let reference = self.time_reference.unwrap_or(DateTime::UNIX_EPOCH);
+        format!("seconds since {}", reference.format("%Y-%m-%dT%H:%M:%S%.fZ"))
     }
 
     /// Number of rays (azimuth/time dimension)
@@ -42,6 +162,97 @@ impl Coordinates {
         self.range.len()
     }
 
+    /// Whether rays have differing gate counts
+    pub fn is_ragged(&self) -> bool {
+        self.n_gates_per_ray
+            .as_ref()
+            .is_some_and(|n| n.iter().any(|&g| g != self.num_gates()))
+    }
+
+    /// Number of gates recorded on a ray
+    pub fn ray_gates(&self, ray: usize) -> usize {
+        self.n_gates_per_ray
+            .as_ref()
+            .map_or(self.num_gates(), |n| n[ray].min(self.num_gates()))
+    }
+
+    /// Whether each gate [rays × gates] was recorded, i.e. is not padding of a ragged sweep
+    pub fn recorded_gates(&self) -> Array2<bool> {
+        Array2::from_shape_fn((self.num_rays(), self.num_gates()), |(i, j)| j < self.ray_gates(i))
+    }
+
+    /// Whether per-gate x/y/z coordinates have been computed
+    pub fn is_georeferenced(&self) -> bool {
+        self.gate_x.is_some() && self.gate_y.is_some() && self.gate_z.is_some()
+    }
+
+    /// Per-gate x/y/z coordinates, computed with the default 4/3 Earth
+    /// radius model on first access and cached
+    pub fn gate_xyz(&mut self) -> (&Array2<f32>, &Array2<f32>, &Array2<f32>) {
+        if !self.is_georeferenced() {
+            let (x, y, z) = compute_gate_xyz(self, &GeoreferenceOptions::default());
+            self.gate_x = Some(x);
+            self.gate_y = Some(y);
+            self.gate_z = Some(z);
+        }
+
+        (
+            self.gate_x.as_ref().unwrap(),
+            self.gate_y.as_ref().unwrap(),
+            self.gate_z.as_ref().unwrap(),
+        )
+    }
+
+    /// Whether per-gate longitude/latitude/altitude have been computed
+    pub fn is_geolocated(&self) -> bool {
+        self.gate_longitude.is_some() && self.gate_latitude.is_some() && self.gate_altitude.is_some()
+    }
+
+    /// Per-gate longitude/latitude (degrees) and altitude (meters MSL) of a
+    /// radar at `radar_lon`, `radar_lat` and `radar_alt`, computed from
+    /// [`Self::gate_xyz`] on first access and cached
+    ///
+    /// The cache belongs to the radar location of the first call; clear it
+    /// with [`Self::clear_georeference`] before using another location.
+    pub fn gate_geographic(
+        &mut self,
+        radar_lon: f64,
+        radar_lat: f64,
+        radar_alt: f64,
+    ) -> (&Array2<f64>, &Array2<f64>, &Array2<f32>) {
+        if !self.is_geolocated() {
+            let (x, y, z) = self.gate_xyz();
+            let shape = x.dim();
+            let mut lon = Array2::zeros(shape);
+            let mut lat = Array2::zeros(shape);
+            for ((idx, &gx), &gy) in x.indexed_iter().zip(y.iter()) {
+                let (glon, glat) = cartesian_to_geographic(gx as f64, gy as f64, radar_lon, radar_lat);
+                lon[idx] = glon;
+                lat[idx] = glat;
+            }
+            let alt = z.mapv(|gz| (gz as f64 + radar_alt) as f32);
+            self.gate_longitude = Some(lon);
+            self.gate_latitude = Some(lat);
+            self.gate_altitude = Some(alt);
+        }
+
+        (
+            self.gate_longitude.as_ref().unwrap(),
+            self.gate_latitude.as_ref().unwrap(),
+            self.gate_altitude.as_ref().unwrap(),
+        )
+    }
+
+    /// Drop cached per-gate coordinates (e.g. after modifying angles or ranges)
+    pub fn clear_georeference(&mut self) {
+        self.gate_x = None;
+        self.gate_y = None;
+        self.gate_z = None;
+        self.gate_longitude = None;
+        self.gate_latitude = None;
+        self.gate_altitude = None;
+    }
+
     /// Validate coordinate dimensions match
     pub fn validate(&self) -> Result<(), String> {
         let num_rays = self.time.len();
@@ -62,6 +273,58 @@ impl Coordinates {
             ));
         }
 
+        if let Some(transition) = &self.antenna_transition {
+            if transition.len() != num_rays {
+                return Err(format!(
+                    "Antenna transition length ({}) doesn't match time length ({})",
+                    transition.len(),
+                    num_rays
+                ));
+            }
+        }
+
+        if let Some(gates) = &self.n_gates_per_ray {
+            if gates.len() != num_rays {
+                return Err(format!(
+                    "Gates per ray length ({}) doesn't match time length ({})",
+                    gates.len(),
+                    num_rays
+                ));
+            }
+            if let Some(&max) = gates.iter().max().filter(|&&max| max > self.num_gates()) {
+                return Err(format!(
+                    "Gates per ray ({}) exceeds range length ({})",
+                    max,
+                    self.num_gates()
+                ));
+            }
+        }
+
         Ok(())
     }
 }
+
+/// Smallest absolute difference between two azimuths (degrees)
+pub(crate) fn azimuth_distance(a: f32, b: f32) -> f32 {
+    let d = (a - b).rem_euclid(360.0);
+    d.min(360.0 - d)
+}
+
+/// Index of the gate containing slant range `range`, if within the sweep
+pub(crate) fn nearest_range_gate(ranges: &[f32], range: f32) -> Option<usize> {
+    let (first, last) = (*ranges.first()?, *ranges.last()?);
+    let half_gate = if ranges.len() > 1 {
+        (last - first) / (ranges.len() - 1) as f32 / 2.0
+    } else {
+        0.0
+    };
+    if range < first - half_gate || range > last + half_gate {
+        return None;
+    }
+    let upper = ranges.partition_point(|&r| r < range).min(ranges.len() - 1);
+    if upper > 0 && range - ranges[upper - 1] < ranges[upper] - range {
+        Some(upper - 1)
+    } else {
+        Some(upper)
+    }
+}
@@ -0,0 +1,493 @@
+//! Radar beam geometry and map projections
+//!
+//! Converts antenna coordinates (range, azimuth, elevation) into Cartesian
+//! coordinates relative to the radar using the 4/3 effective Earth radius
+//! beam propagation model (Doviak & Zrnić, 1993, eq. 2.28), and projects
+//! those gates to geographic or projected coordinates via [`Projection`].
+
+use std::collections::HashMap;
+
+use ndarray::Array2;
+
+use crate::{Coordinates, RadishError, Result};
+
+/// Mean Earth radius (meters)
+pub const EARTH_RADIUS: f64 = 6_371_000.0;
+
+/// Standard-atmosphere effective Earth radius multiplier
+pub const EFFECTIVE_RADIUS_FACTOR: f64 = 4.0 / 3.0;
+
+/// Beam propagation model parameters
+#[derive(Debug, Clone, Copy)]
+pub struct GeoreferenceOptions {
+    /// Earth radius (meters)
+    pub earth_radius: f64,
+    /// Effective radius multiplier (4/3 for standard refraction)
+    pub effective_radius_factor: f64,
+}
+
+impl GeoreferenceOptions {
+    /// Effective Earth radius used by the propagation model (meters)
+    pub fn effective_radius(&self) -> f64 {
+        self.earth_radius * self.effective_radius_factor
+    }
+}
+
+impl Default for GeoreferenceOptions {
+    fn default() -> Self {
+        Self {
+            earth_radius: EARTH_RADIUS,
+            effective_radius_factor: EFFECTIVE_RADIUS_FACTOR,
+        }
+    }
+}
+
+/// Convert a single gate to Cartesian coordinates relative to the radar
+///
+/// Returns `(x, y, z)` in meters, with x towards east, y towards north and
+/// z the beam height above the radar.
+pub fn antenna_to_cartesian(
+    range: f64,
+    azimuth: f64,
+    elevation: f64,
+    options: &GeoreferenceOptions,
+) -> (f64, f64, f64) {
+    let re = options.effective_radius();
+    let elev = elevation.to_radians();
+    let az = azimuth.to_radians();
+
+    let z = (range * range + re * re + 2.0 * range * re * elev.sin()).sqrt() - re;
+    let s = re * (range * elev.cos() / (re + z)).asin();
+
+    (s * az.sin(), s * az.cos(), z)
+}
+
+/// Beam height above the radar (meters) for a gate at `range` and `elevation`
+pub fn beam_height(range: f64, elevation: f64, options: &GeoreferenceOptions) -> f64 {
+    let re = options.effective_radius();
+    let elev = elevation.to_radians();
+    (range * range + re * re + 2.0 * range * re * elev.sin()).sqrt() - re
+}
+
+/// Great-circle distance along the surface (meters) for a gate at `range` and `elevation`
+pub fn ground_range(range: f64, elevation: f64, options: &GeoreferenceOptions) -> f64 {
+    let re = options.effective_radius();
+    let z = beam_height(range, elevation, options);
+    re * (range * elevation.to_radians().cos() / (re + z)).asin()
+}
+
+/// Slant range and beam height above the radar (meters) where a beam at
+/// `elevation` reaches the great-circle distance `ground_range`
+pub fn ground_to_antenna(ground_range: f64, elevation: f64, options: &GeoreferenceOptions) -> (f64, f64) {
+    let re = options.effective_radius();
+    let elev = elevation.to_radians();
+    let angle = ground_range / re;
+    let range = re * angle.sin() / (elev + angle).cos();
+    let height = re * elev.cos() / (elev + angle).cos() - re;
+    (range, height)
+}
+
+/// Compute per-gate x/y/z arrays [rays × gates] for a set of coordinates
+pub fn compute_gate_xyz(
+    coordinates: &Coordinates,
+    options: &GeoreferenceOptions,
+) -> (Array2<f32>, Array2<f32>, Array2<f32>) {
+    let shape = (coordinates.num_rays(), coordinates.num_gates());
+    let mut x = Array2::zeros(shape);
+    let mut y = Array2::zeros(shape);
+    let mut z = Array2::zeros(shape);
+
+    for (i, (&az, &el)) in coordinates
+        .azimuth
+        .iter()
+        .zip(coordinates.elevation.iter())
+        .enumerate()
+    {
+        for (j, &r) in coordinates.range.iter().enumerate() {
+            let (gx, gy, gz) = antenna_to_cartesian(r as f64, az as f64, el as f64, options);
+            x[[i, j]] = gx as f32;
+            y[[i, j]] = gy as f32;
+            z[[i, j]] = gz as f32;
+        }
+    }
+
+    (x, y, z)
+}
+
+/// WGS84 semi-major axis (meters)
+const WGS84_A: f64 = 6_378_137.0;
+
+/// WGS84 flattening
+const WGS84_F: f64 = 1.0 / 298.257_223_563;
+
+/// UTM central meridian scale factor
+const UTM_K0: f64 = 0.9996;
+
+/// UTM false easting (meters)
+const UTM_FALSE_EASTING: f64 = 500_000.0;
+
+/// UTM false northing for the southern hemisphere (meters)
+const UTM_FALSE_NORTHING_SOUTH: f64 = 10_000_000.0;
+
+/// Map projection for gate coordinates
+///
+/// All projections map between geographic coordinates (degrees) and
+/// projected coordinates. `Geographic` is the identity and returns
+/// `(lon, lat)` in degrees; the others return `(x, y)` in meters.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Projection {
+    /// Longitude/latitude in degrees (WGS84)
+    Geographic,
+    /// Spherical azimuthal equidistant centered on the given point
+    Aeqd { lon_0: f64, lat_0: f64 },
+    /// Universal Transverse Mercator on the WGS84 ellipsoid
+    Utm { zone: u8, north: bool },
+    /// Web Mercator (EPSG:3857)
+    WebMercator,
+    /// Lambert azimuthal equal-area on the WGS84 ellipsoid, with false easting/northing
+    Laea { lon_0: f64, lat_0: f64, x_0: f64, y_0: f64 },
+}
+
+impl Projection {
+    /// Azimuthal equidistant projection centered on a radar site
+    pub fn aeqd(lon_0: f64, lat_0: f64) -> Self {
+        Projection::Aeqd { lon_0, lat_0 }
+    }
+
+    /// UTM projection for the zone containing the given point
+    pub fn utm_for(lon: f64, lat: f64) -> Self {
+        let zone = (((lon + 180.0) / 6.0).floor() as i32).rem_euclid(60) + 1;
+        Projection::Utm {
+            zone: zone as u8,
+            north: lat >= 0.0,
+        }
+    }
+
+    /// Parse a PROJ.4 definition string (e.g. from an ODIM `projdef` attribute)
+    ///
+    /// Supports `longlat`, `aeqd`, `laea`, `utm` and the spherical Web
+    /// Mercator definition, on WGS84 (or GRS80) or, for `aeqd`, a sphere.
+    pub fn from_proj4(definition: &str) -> Result<Self> {
+        let params: HashMap<&str, &str> = definition
+            .split_whitespace()
+            .filter_map(|token| {
+                let token = token.trim_start_matches('+');
+                match token.split_once('=') {
+                    Some((key, value)) => Some((key, value)),
+                    None => (!token.is_empty()).then_some((token, "")),
+                }
+            })
+            .collect();
+
+        let number = |key: &str| -> Result<f64> {
+            match params.get(key) {
+                Some(v) => v.parse::<f64>().map_err(|_| {
+                    RadishError::InvalidFormat(format!("Invalid +{} in projection: {}", key, definition))
+                }),
+                None => Ok(0.0),
+            }
+        };
+        let wgs84 = || {
+            let ellipsoid = params.get("ellps").or(params.get("datum")).copied().unwrap_or("WGS84");
+            matches!(ellipsoid, "WGS84" | "GRS80") && !params.contains_key("a") && !params.contains_key("R")
+        };
+        let unsupported = || {
+            RadishError::Unsupported(format!("Projection definition not supported: {}", definition))
+        };
+
+        match params.get("proj").copied() {
+            Some("longlat") | Some("latlong") | Some("lonlat") | Some("latlon") => Ok(Projection::Geographic),
+            Some("aeqd") => Ok(Projection::aeqd(number("lon_0")?, number("lat_0")?)),
+            Some("laea") if wgs84() => Ok(Projection::Laea {
+                lon_0: number("lon_0")?,
+                lat_0: number("lat_0")?,
+                x_0: number("x_0")?,
+                y_0: number("y_0")?,
+            }),
+            Some("utm") if wgs84() => {
+                let zone = number("zone")?;
+                if !(1.0..=60.0).contains(&zone) {
+                    return Err(unsupported());
+                }
+                Ok(Projection::Utm {
+                    zone: zone as u8,
+                    north: !params.contains_key("south"),
+                })
+            }
+            Some("merc")
+                if params.get("a").zip(params.get("b")).is_some_and(|(a, b)| {
+                    a.parse::<f64>().ok() == Some(WGS84_A) && b.parse::<f64>().ok() == Some(WGS84_A)
+                }) =>
+            {
+                Ok(Projection::WebMercator)
+            }
+            _ => Err(unsupported()),
+        }
+    }
+
+    /// Project geographic coordinates (degrees) to this projection
+    pub fn forward(&self, lon: f64, lat: f64) -> (f64, f64) {
+        match *self {
+            Projection::Geographic => (lon, lat),
+            Projection::Aeqd { lon_0, lat_0 } => aeqd_forward(lon, lat, lon_0, lat_0),
+            Projection::Utm { zone, north } => utm_forward(lon, lat, zone, north),
+            Projection::WebMercator => {
+                let lat = lat.clamp(-85.051_128_78, 85.051_128_78);
+                let x = WGS84_A * lon.to_radians();
+                let y = WGS84_A * (std::f64::consts::FRAC_PI_4 + lat.to_radians() / 2.0).tan().ln();
+                (x, y)
+            }
+            Projection::Laea { lon_0, lat_0, x_0, y_0 } => {
+                let (x, y) = laea_forward(lon, lat, lon_0, lat_0);
+                (x + x_0, y + y_0)
+            }
+        }
+    }
+
+    /// Convert projected coordinates back to geographic `(lon, lat)` in degrees
+    pub fn inverse(&self, x: f64, y: f64) -> (f64, f64) {
+        match *self {
+            Projection::Geographic => (x, y),
+            Projection::Aeqd { lon_0, lat_0 } => aeqd_inverse(x, y, lon_0, lat_0),
+            Projection::Utm { zone, north } => utm_inverse(x, y, zone, north),
+            Projection::WebMercator => {
+                let lon = (x / WGS84_A).to_degrees();
+                let lat = (2.0 * (y / WGS84_A).exp().atan() - std::f64::consts::FRAC_PI_2).to_degrees();
+                (lon, lat)
+            }
+            Projection::Laea { lon_0, lat_0, x_0, y_0 } => laea_inverse(x - x_0, y - y_0, lon_0, lat_0),
+        }
+    }
+
+    /// Transform coordinates from this projection into another one
+    pub fn transform_to(&self, other: &Projection, x: f64, y: f64) -> (f64, f64) {
+        let (lon, lat) = self.inverse(x, y);
+        other.forward(lon, lat)
+    }
+}
+
+/// Convert radar-relative x/y (meters) to geographic `(lon, lat)` in degrees
+pub fn cartesian_to_geographic(x: f64, y: f64, radar_lon: f64, radar_lat: f64) -> (f64, f64) {
+    aeqd_inverse(x, y, radar_lon, radar_lat)
+}
+
+/// Convert geographic coordinates (degrees) to radar-relative x/y (meters)
+pub fn geographic_to_cartesian(lon: f64, lat: f64, radar_lon: f64, radar_lat: f64) -> (f64, f64) {
+    aeqd_forward(lon, lat, radar_lon, radar_lat)
+}
+
+/// Project every gate of a sweep into `projection`
+///
+/// Gate x/y are computed lazily if the coordinates have not been
+/// georeferenced yet. Returns `(x, y)` arrays [rays × gates]; for
+/// [`Projection::Geographic`] these are longitude and latitude in degrees.
+pub fn project_gates(
+    coordinates: &mut Coordinates,
+    radar_lon: f64,
+    radar_lat: f64,
+    projection: &Projection,
+) -> (Array2<f64>, Array2<f64>) {
+    let (gate_x, gate_y, _) = coordinates.gate_xyz();
+    let shape = gate_x.dim();
+    let mut px = Array2::zeros(shape);
+    let mut py = Array2::zeros(shape);
+
+    for ((idx, &x), &y) in gate_x.indexed_iter().zip(gate_y.iter()) {
+        let (lon, lat) = cartesian_to_geographic(x as f64, y as f64, radar_lon, radar_lat);
+        let (u, v) = projection.forward(lon, lat);
+        px[idx] = u;
+        py[idx] = v;
+    }
+
+    (px, py)
+}
+
+/// Gate longitude/latitude (degrees) for a sweep
+pub fn gate_lonlat(
+    coordinates: &mut Coordinates,
+    radar_lon: f64,
+    radar_lat: f64,
+) -> (Array2<f64>, Array2<f64>) {
+    project_gates(coordinates, radar_lon, radar_lat, &Projection::Geographic)
+}
+
+fn aeqd_forward(lon: f64, lat: f64, lon_0: f64, lat_0: f64) -> (f64, f64) {
+    let (phi, phi0) = (lat.to_radians(), lat_0.to_radians());
+    let dlam = (lon - lon_0).to_radians();
+
+    let cos_c = (phi0.sin() * phi.sin() + phi0.cos() * phi.cos() * dlam.cos()).clamp(-1.0, 1.0);
+    let c = cos_c.acos();
+    let k = if c.abs() < 1e-12 { 1.0 } else { c / c.sin() };
+
+    let x = EARTH_RADIUS * k * phi.cos() * dlam.sin();
+    let y = EARTH_RADIUS * k * (phi0.cos() * phi.sin() - phi0.sin() * phi.cos() * dlam.cos());
+    (x, y)
+}
+
+fn aeqd_inverse(x: f64, y: f64, lon_0: f64, lat_0: f64) -> (f64, f64) {
+    let rho = (x * x + y * y).sqrt();
+    if rho < 1e-9 {
+        return (lon_0, lat_0);
+    }
+
+    let phi0 = lat_0.to_radians();
+    let c = rho / EARTH_RADIUS;
+    let lat = (c.cos() * phi0.sin() + y * c.sin() * phi0.cos() / rho).clamp(-1.0, 1.0).asin();
+    let lon = lon_0.to_radians()
+        + (x * c.sin()).atan2(rho * phi0.cos() * c.cos() - y * phi0.sin() * c.sin());
+
+    (normalize_longitude(lon.to_degrees()), lat.to_degrees())
+}
+
+fn utm_central_meridian(zone: u8) -> f64 {
+    (zone as f64 - 1.0) * 6.0 - 180.0 + 3.0
+}
+
+/// Transverse Mercator forward (Snyder, 1987, eqs. 8-9 to 8-13)
+fn utm_forward(lon: f64, lat: f64, zone: u8, north: bool) -> (f64, f64) {
+    let e2 = WGS84_F * (2.0 - WGS84_F);
+    let ep2 = e2 / (1.0 - e2);
+
+    let phi = lat.to_radians();
+    let (sin_phi, cos_phi, tan_phi) = (phi.sin(), phi.cos(), phi.tan());
+
+    let n = WGS84_A / (1.0 - e2 * sin_phi * sin_phi).sqrt();
+    let t = tan_phi * tan_phi;
+    let c = ep2 * cos_phi * cos_phi;
+    let a = cos_phi * (lon - utm_central_meridian(zone)).to_radians();
+    let m = meridian_arc(phi, e2);
+
+    let x = UTM_K0
+        * n
+        * (a + (1.0 - t + c) * a.powi(3) / 6.0
+            + (5.0 - 18.0 * t + t * t + 72.0 * c - 58.0 * ep2) * a.powi(5) / 120.0)
+        + UTM_FALSE_EASTING;
+
+    let mut y = UTM_K0
+        * (m + n
+            * tan_phi
+            * (a * a / 2.0
+                + (5.0 - t + 9.0 * c + 4.0 * c * c) * a.powi(4) / 24.0
+                + (61.0 - 58.0 * t + t * t + 600.0 * c - 330.0 * ep2) * a.powi(6) / 720.0));
+    if !north {
+        y += UTM_FALSE_NORTHING_SOUTH;
+    }
+
+    (x, y)
+}
+
+/// Transverse Mercator inverse (Snyder, 1987, eqs. 8-17 to 8-25)
+fn utm_inverse(x: f64, y: f64, zone: u8, north: bool) -> (f64, f64) {
+    let e2 = WGS84_F * (2.0 - WGS84_F);
+    let ep2 = e2 / (1.0 - e2);
+    let e1 = (1.0 - (1.0 - e2).sqrt()) / (1.0 + (1.0 - e2).sqrt());
+
+    let y = if north { y } else { y - UTM_FALSE_NORTHING_SOUTH };
+    let m = y / UTM_K0;
+    let mu = m / (WGS84_A * (1.0 - e2 / 4.0 - 3.0 * e2 * e2 / 64.0 - 5.0 * e2.powi(3) / 256.0));
+
+    let phi1 = mu
+        + (3.0 * e1 / 2.0 - 27.0 * e1.powi(3) / 32.0) * (2.0 * mu).sin()
+        + (21.0 * e1 * e1 / 16.0 - 55.0 * e1.powi(4) / 32.0) * (4.0 * mu).sin()
+        + (151.0 * e1.powi(3) / 96.0) * (6.0 * mu).sin()
+        + (1097.0 * e1.powi(4) / 512.0) * (8.0 * mu).sin();
+
+    let (sin1, cos1, tan1) = (phi1.sin(), phi1.cos(), phi1.tan());
+    let c1 = ep2 * cos1 * cos1;
+    let t1 = tan1 * tan1;
+    let n1 = WGS84_A / (1.0 - e2 * sin1 * sin1).sqrt();
+    let r1 = WGS84_A * (1.0 - e2) / (1.0 - e2 * sin1 * sin1).powf(1.5);
+    let d = (x - UTM_FALSE_EASTING) / (n1 * UTM_K0);
+
+    let lat = phi1
+        - (n1 * tan1 / r1)
+            * (d * d / 2.0
+                - (5.0 + 3.0 * t1 + 10.0 * c1 - 4.0 * c1 * c1 - 9.0 * ep2) * d.powi(4) / 24.0
+                + (61.0 + 90.0 * t1 + 298.0 * c1 + 45.0 * t1 * t1 - 252.0 * ep2 - 3.0 * c1 * c1)
+                    * d.powi(6)
+                    / 720.0);
+    let lon = (d - (1.0 + 2.0 * t1 + c1) * d.powi(3) / 6.0
+        + (5.0 - 2.0 * c1 + 28.0 * t1 - 3.0 * c1 * c1 + 8.0 * ep2 + 24.0 * t1 * t1) * d.powi(5)
+            / 120.0)
+        / cos1;
+
+    (
+        normalize_longitude(utm_central_meridian(zone) + lon.to_degrees()),
+        lat.to_degrees(),
+    )
+}
+
+/// Authalic latitude function q (Snyder, 1987, eq. 3-12)
+fn authalic_q(sin_phi: f64, e: f64) -> f64 {
+    let e2 = e * e;
+    (1.0 - e2)
+        * (sin_phi / (1.0 - e2 * sin_phi * sin_phi)
+            - (1.0 / (2.0 * e)) * ((1.0 - e * sin_phi) / (1.0 + e * sin_phi)).ln())
+}
+
+/// Constants of the ellipsoidal oblique LAEA: (e, qp, Rq, beta1, D)
+fn laea_constants(lat_0: f64) -> (f64, f64, f64, f64, f64) {
+    let e = (WGS84_F * (2.0 - WGS84_F)).sqrt();
+    let phi1 = lat_0.to_radians();
+    let qp = authalic_q(1.0, e);
+    let rq = WGS84_A * (qp / 2.0).sqrt();
+    let beta1 = (authalic_q(phi1.sin(), e) / qp).clamp(-1.0, 1.0).asin();
+    let m1 = phi1.cos() / (1.0 - e * e * phi1.sin().powi(2)).sqrt();
+    let d = if beta1.cos().abs() < 1e-12 {
+        1.0
+    } else {
+        WGS84_A * m1 / (rq * beta1.cos())
+    };
+    (e, qp, rq, beta1, d)
+}
+
+/// Lambert azimuthal equal-area forward (Snyder, 1987, eqs. 24-2 to 24-11)
+fn laea_forward(lon: f64, lat: f64, lon_0: f64, lat_0: f64) -> (f64, f64) {
+    let (e, qp, rq, beta1, d) = laea_constants(lat_0);
+    let beta = (authalic_q(lat.to_radians().sin(), e) / qp).clamp(-1.0, 1.0).asin();
+    let dlam = (lon - lon_0).to_radians();
+
+    let denom = 1.0 + beta1.sin() * beta.sin() + beta1.cos() * beta.cos() * dlam.cos();
+    let b = rq * (2.0 / denom.max(1e-15)).sqrt();
+    let x = b * d * beta.cos() * dlam.sin();
+    let y = (b / d) * (beta1.cos() * beta.sin() - beta1.sin() * beta.cos() * dlam.cos());
+    (x, y)
+}
+
+/// Lambert azimuthal equal-area inverse (Snyder, 1987, eqs. 24-28 to 24-30, 3-18)
+fn laea_inverse(x: f64, y: f64, lon_0: f64, lat_0: f64) -> (f64, f64) {
+    let (e, qp, rq, beta1, d) = laea_constants(lat_0);
+    let rho = ((x / d).powi(2) + (d * y).powi(2)).sqrt();
+    if rho < 1e-9 {
+        return (lon_0, lat_0);
+    }
+
+    let ce = 2.0 * (rho / (2.0 * rq)).clamp(-1.0, 1.0).asin();
+    let q = qp * (ce.cos() * beta1.sin() + d * y * ce.sin() * beta1.cos() / rho);
+    let lon = lon_0.to_radians()
+        + (x * ce.sin()).atan2(d * rho * beta1.cos() * ce.cos() - d * d * y * beta1.sin() * ce.sin());
+
+    let beta = (q / qp).clamp(-1.0, 1.0).asin();
+    let (e2, e4, e6) = (e * e, e.powi(4), e.powi(6));
+    let lat = beta
+        + (e2 / 3.0 + 31.0 * e4 / 180.0 + 517.0 * e6 / 5040.0) * (2.0 * beta).sin()
+        + (23.0 * e4 / 360.0 + 251.0 * e6 / 3780.0) * (4.0 * beta).sin()
+        + (761.0 * e6 / 45360.0) * (6.0 * beta).sin();
+
+    (normalize_longitude(lon.to_degrees()), lat.to_degrees())
+}
+
+/// Length of the meridian arc from the equator to `phi` (radians)
+fn meridian_arc(phi: f64, e2: f64) -> f64 {
+    let e4 = e2 * e2;
+    let e6 = e4 * e2;
+    WGS84_A
+        * ((1.0 - e2 / 4.0 - 3.0 * e4 / 64.0 - 5.0 * e6 / 256.0) * phi
+            - (3.0 * e2 / 8.0 + 3.0 * e4 / 32.0 + 45.0 * e6 / 1024.0) * (2.0 * phi).sin()
+            + (15.0 * e4 / 256.0 + 45.0 * e6 / 1024.0) * (4.0 * phi).sin()
+            - (35.0 * e6 / 3072.0) * (6.0 * phi).sin())
+}
+
+fn normalize_longitude(lon: f64) -> f64 {
+    (lon + 180.0).rem_euclid(360.0) - 180.0
+}
@@ -0,0 +1,153 @@
+//! Gridded (Cartesian) data structures
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use ndarray::Array3;
+
+use super::geometry::Projection;
+use crate::{RadishError, Result};
+
+/// A field on a regular grid
+#[derive(Debug, Clone)]
+pub struct GridField {
+    /// Field name (e.g., "DBZH")
+    pub name: String,
+
+    /// CF standard name
+    pub standard_name: Option<String>,
+
+    /// Long descriptive name
+    pub long_name: Option<String>,
+
+    /// Units
+    pub units: String,
+
+    /// 3D data array [z × y × x]; 2D products have a single level
+    pub data: Array3<f32>,
+
+    /// Fill value (missing data indicator)
+    pub fill_value: Option<f32>,
+
+    /// Additional attributes
+    pub attributes: HashMap<String, String>,
+}
+
+impl GridField {
+    /// Create a new GridField
+    pub fn new(name: String, units: String, data: Array3<f32>) -> Self {
+        Self {
+            name,
+            standard_name: None,
+            long_name: None,
+            units,
+            data,
+            fill_value: None,
+            attributes: HashMap::new(),
+        }
+    }
+
+    /// Get the shape of the data array (nz, ny, nx)
+    pub fn shape(&self) -> (usize, usize, usize) {
+        self.data.dim()
+    }
+
+    /// Check whether a value is valid data (not fill or NaN)
+    pub fn is_valid_value(&self, v: f32) -> bool {
+        !v.is_nan() && self.fill_value != Some(v)
+    }
+
+    /// Value used to mark missing cells (fill value or NaN)
+    pub fn missing_value(&self) -> f32 {
+        self.fill_value.unwrap_or(f32::NAN)
+    }
+}
+
+/// Data on a regular projected grid, e.g. gridded radar volumes and composites
+#[derive(Debug, Clone)]
+pub struct GriddedData {
+    /// Nominal time of the grid
+    pub time: DateTime<Utc>,
+
+    /// Projection of the x/y axes
+    pub projection: Projection,
+
+    /// Grid x coordinates (projected units, usually meters), increasing
+    pub x: Vec<f64>,
+
+    /// Grid y coordinates (projected units, usually meters), increasing
+    pub y: Vec<f64>,
+
+    /// Grid levels (meters above mean sea level)
+    pub z: Vec<f64>,
+
+    /// Fields on the grid, keyed by name
+    pub fields: HashMap<String, GridField>,
+
+    /// Additional attributes
+    pub attributes: HashMap<String, String>,
+}
+
+impl GriddedData {
+    /// Create an empty grid
+    pub fn new(
+        time: DateTime<Utc>,
+        projection: Projection,
+        x: Vec<f64>,
+        y: Vec<f64>,
+        z: Vec<f64>,
+    ) -> Self {
+        Self {
+            time,
+            projection,
+            x,
+            y,
+            z,
+            fields: HashMap::new(),
+            attributes: HashMap::new(),
+        }
+    }
+
+    /// Grid shape (nz, ny, nx)
+    pub fn shape(&self) -> (usize, usize, usize) {
+        (self.z.len(), self.y.len(), self.x.len())
+    }
+
+    /// Get a field by name
+    pub fn get_field(&self, name: &str) -> Option<&GridField> {
+        self.fields.get(name)
+    }
+
+    /// Get a mutable reference to a field
+    pub fn get_field_mut(&mut self, name: &str) -> Option<&mut GridField> {
+        self.fields.get_mut(name)
+    }
+
+    /// Add a field, checking that its shape matches the grid
+    ///
+    /// Single-level fields are accepted on multi-level grids.
+    pub fn add_field(&mut self, field: GridField) -> Result<()> {
+        let (nz, ny, nx) = self.shape();
+        let (fz, fy, fx) = field.shape();
+        if fy != ny || fx != nx || (fz != nz && fz != 1) {
+            return Err(RadishError::InvalidFormat(format!(
+                "Field {} shape {:?} does not match grid shape {:?}",
+                field.name,
+                field.shape(),
+                self.shape()
+            )));
+        }
+        self.fields.insert(field.name.clone(), field);
+        Ok(())
+    }
+
+    /// Get list of available field names
+    pub fn field_names(&self) -> Vec<&String> {
+        self.fields.keys().collect()
+    }
+
+    /// Geographic `(lon, lat)` in degrees of a grid column
+    pub fn lonlat(&self, ix: usize, iy: usize) -> (f64, f64) {
+        self.projection.inverse(self.x[ix], self.y[iy])
+    }
+}
@@ -0,0 +1,114 @@
+//! Processing history of volumes
+//!
+//! Every transform producing a volume appends a [`HistoryEntry`] naming the
+//! operation, its parameters, when it ran and the radish version, so that a
+//! processed volume records how it was made. Writers emit the history as
+//! the CF `history` attribute, after any history read from the file.
+
+use std::fmt::{self, Debug};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{AttributeValue, VolumeData};
+
+/// Attribute holding the processing history, one step per line
+pub const HISTORY_ATTRIBUTE: &str = "history";
+
+/// One processing step
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// Operation identifier (e.g. "radish.transforms.snr.compute_snr")
+    pub operation: String,
+    /// `Debug` representation of the parameters
+    pub parameters: String,
+    /// When the operation ran
+    pub timestamp: DateTime<Utc>,
+    /// Version of the software that ran the operation
+    pub version: String,
+}
+
+/// `<timestamp>: radish <version> <operation> <parameters>`
+impl fmt::Display for HistoryEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: radish {} {} {}",
+            self.timestamp.to_rfc3339_opts(SecondsFormat::Secs, true),
+            self.version,
+            self.operation,
+            self.parameters
+        )
+    }
+}
+
+/// Processing steps of a volume, oldest first
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct History {
+    entries: Vec<HistoryEntry>,
+}
+
+impl History {
+    /// Append a step run now by this version of radish
+    pub fn record(&mut self, operation: &str, parameters: &impl Debug) {
+        self.entries.push(HistoryEntry {
+            operation: operation.to_string(),
+            parameters: format!("{:?}", parameters),
+            timestamp: Utc::now(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        });
+    }
+
+    /// Steps, oldest first
+    pub fn entries(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+
+    /// Number of steps
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether no step was recorded
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Append the steps of another history, keeping the steps in time order
+    pub fn extend(&mut self, other: History) {
+        self.entries.extend(other.entries);
+        self.entries.sort_by_key(|e| e.timestamp);
+    }
+}
+
+/// One step per line
+impl fmt::Display for History {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (k, entry) in self.entries.iter().enumerate() {
+            if k > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", entry)?;
+        }
+        Ok(())
+    }
+}
+
+impl VolumeData {
+    /// The `history` attribute as written: the history read from the file,
+    /// if any, followed by the processing history
+    pub fn history_attribute(&self) -> Option<String> {
+        let read = self
+            .metadata
+            .attributes
+            .get(HISTORY_ATTRIBUTE)
+            .map(AttributeValue::to_string)
+            .filter(|h| !h.trim().is_empty());
+        match (read, self.history.is_empty()) {
+            (None, true) => None,
+            (None, false) => Some(self.history.to_string()),
+            (Some(read), true) => Some(read),
+            (Some(read), false) => Some(format!("{}\n{}", read.trim_end(), self.history)),
+        }
+    }
+}
@@ -0,0 +1,222 @@
+//! Memory usage of volumes
+//!
+//! [`VolumeData::memory_usage`] reports the bytes held by each sweep and
+//! moment, so that moment selection and downsampling of large archives can
+//! be weighed before reading or converting them. Moments are counted both
+//! as stored, which for packed integer moments is a half or a quarter of
+//! their size once decoded to `f32`, and as decoded.
+
+use std::mem::size_of;
+
+use ndarray::Array2;
+
+use super::{Coordinates, MomentData, SweepData, VolumeData};
+
+/// Memory used by a moment of a sweep
+#[derive(Debug, Clone, PartialEq)]
+pub struct MomentMemory {
+    /// Moment name
+    pub name: String,
+    /// Shape [rays × gates]
+    pub shape: (usize, usize),
+    /// Whether values are stored as packed integers
+    pub packed: bool,
+    /// Bytes of the stored values
+    pub stored_bytes: usize,
+    /// Bytes of the values decoded to `f32`, as returned by [`MomentData::data`]
+    pub decoded_bytes: usize,
+    /// Bytes of the validity mask
+    pub mask_bytes: usize,
+}
+
+impl MomentMemory {
+    /// Memory used by a moment
+    pub fn of(moment: &MomentData) -> Self {
+        let shape = moment.shape();
+        let storage = moment.as_packed();
+        Self {
+            name: moment.name.clone(),
+            shape,
+            packed: storage.is_packed(),
+            stored_bytes: storage.size_bytes(),
+            decoded_bytes: shape.0 * shape.1 * size_of::<f32>(),
+            mask_bytes: moment.mask().map_or(0, array_bytes),
+        }
+    }
+
+    /// Bytes held by the moment as stored, including its mask
+    pub fn total_bytes(&self) -> usize {
+        self.stored_bytes + self.mask_bytes
+    }
+}
+
+/// Memory used by a sweep
+#[derive(Debug, Clone, PartialEq)]
+pub struct SweepMemory {
+    /// Sweep index in the volume
+    pub sweep: usize,
+    /// Bytes of the coordinates, including cached Cartesian and geographic gate locations
+    pub coordinate_bytes: usize,
+    /// Bytes of the per-ray instrument parameters and platform motion
+    pub ray_metadata_bytes: usize,
+    /// Moments, sorted by name
+    pub moments: Vec<MomentMemory>,
+}
+
+impl SweepMemory {
+    /// Memory used by a sweep
+    pub fn of(sweep_index: usize, sweep: &SweepData) -> Self {
+        let mut moments: Vec<MomentMemory> = sweep.moments.values().map(MomentMemory::of).collect();
+        moments.sort_by(|a, b| a.name.cmp(&b.name));
+        let ray_metadata_bytes = sweep.ray_metadata.as_ref().map_or(0, |r| {
+            vec_bytes(r.nyquist_velocity.as_deref())
+                + vec_bytes(r.prt.as_deref())
+                + vec_bytes(r.pulse_width.as_deref())
+                + vec_bytes(r.n_samples.as_deref())
+        }) + sweep.platform_motion.as_ref().map_or(0, |m| {
+            [&m.heading, &m.roll, &m.pitch, &m.drift, &m.velocity_east, &m.velocity_north, &m.velocity_up]
+                .iter()
+                .map(|v| vec_bytes(v.as_deref()))
+                .sum::<usize>()
+        });
+        Self {
+            sweep: sweep_index,
+            coordinate_bytes: coordinate_bytes(&sweep.coordinates),
+            ray_metadata_bytes,
+            moments,
+        }
+    }
+
+    /// Bytes of the moments as stored, with their masks
+    pub fn moment_bytes(&self) -> usize {
+        self.moments.iter().map(MomentMemory::total_bytes).sum()
+    }
+
+    /// Bytes of the moments once decoded, with their masks
+    pub fn decoded_moment_bytes(&self) -> usize {
+        self.moments.iter().map(|m| m.decoded_bytes + m.mask_bytes).sum()
+    }
+
+    /// Bytes held by the sweep as stored
+    pub fn total_bytes(&self) -> usize {
+        self.coordinate_bytes + self.ray_metadata_bytes + self.moment_bytes()
+    }
+}
+
+/// Memory used by a volume, per sweep and moment
+///
+/// Counts the arrays of the volume; metadata strings and attributes, which
+/// are small in comparison, are left out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoryReport {
+    /// Sweeps, in volume order
+    pub sweeps: Vec<SweepMemory>,
+}
+
+impl MemoryReport {
+    /// Bytes held by the volume as stored
+    pub fn total_bytes(&self) -> usize {
+        self.sweeps.iter().map(SweepMemory::total_bytes).sum()
+    }
+
+    /// Bytes the volume would hold with every moment decoded to `f32`
+    pub fn decoded_bytes(&self) -> usize {
+        self.sweeps
+            .iter()
+            .map(|s| s.coordinate_bytes + s.ray_metadata_bytes + s.decoded_moment_bytes())
+            .sum()
+    }
+
+    /// Stored bytes of each moment over all sweeps, largest first
+    pub fn moment_totals(&self) -> Vec<(String, usize)> {
+        let mut totals: Vec<(String, usize)> = Vec::new();
+        for moment in self.sweeps.iter().flat_map(|s| &s.moments) {
+            match totals.iter_mut().find(|(name, _)| *name == moment.name) {
+                Some((_, bytes)) => *bytes += moment.total_bytes(),
+                None => totals.push((moment.name.clone(), moment.total_bytes())),
+            }
+        }
+        totals.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        totals
+    }
+}
+
+impl std::fmt::Display for MemoryReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Total: {} stored, {} decoded", format_bytes(self.total_bytes()), format_bytes(self.decoded_bytes()))?;
+        for sweep in &self.sweeps {
+            writeln!(
+                f,
+                "sweep_{}: {} (coordinates {})",
+                sweep.sweep,
+                format_bytes(sweep.total_bytes()),
+                format_bytes(sweep.coordinate_bytes)
+            )?;
+            for m in &sweep.moments {
+                writeln!(
+                    f,
+                    "  {}: {} stored{}, {} decoded",
+                    m.name,
+                    format_bytes(m.total_bytes()),
+                    if m.packed { " (packed)" } else { "" },
+                    format_bytes(m.decoded_bytes + m.mask_bytes)
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl VolumeData {
+    /// Bytes held by each sweep and moment of the volume
+    pub fn memory_usage(&self) -> MemoryReport {
+        MemoryReport {
+            sweeps: self
+                .sweeps
+                .iter()
+                .enumerate()
+                .map(|(k, sweep)| SweepMemory::of(k, sweep))
+                .collect(),
+        }
+    }
+}
+
+fn array_bytes<T>(array: &Array2<T>) -> usize {
+    array.len() * size_of::<T>()
+}
+
+fn vec_bytes<T>(values: Option<&[T]>) -> usize {
+    values.map_or(0, std::mem::size_of_val)
+}
+
+fn coordinate_bytes(coords: &Coordinates) -> usize {
+    vec_bytes(Some(&coords.time))
+        + vec_bytes(Some(&coords.range))
+        + vec_bytes(Some(&coords.azimuth))
+        + vec_bytes(Some(&coords.elevation))
+        + vec_bytes(coords.antenna_transition.as_deref())
+        + vec_bytes(coords.n_gates_per_ray.as_deref())
+        + [&coords.gate_x, &coords.gate_y, &coords.gate_z, &coords.gate_altitude]
+            .iter()
+            .map(|a| a.as_ref().map_or(0, array_bytes))
+            .sum::<usize>()
+        + [&coords.gate_longitude, &coords.gate_latitude]
+            .iter()
+            .map(|a| a.as_ref().map_or(0, array_bytes))
+            .sum::<usize>()
+}
+
+/// Bytes with a binary unit, e.g. `1.5 MiB`
+fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
@@ -1,14 +1,36 @@
-/// Data model for radar volumes, sweeps, and moments
-///
-/// This module defines the core data structures that represent weather radar data
-/// in a format-agnostic way, following the CfRadial2/FM301 standard.
+//! Data model for radar volumes, sweeps, and moments
+//!
+//! This module defines the core data structures that represent weather radar data
+//! in a format-agnostic way, following the CfRadial2/FM301 standard.
 
 mod volume;
 mod sweep;
 mod moment;
-mod coordinates;
+pub(crate) mod coordinates;
+pub mod geometry;
+mod grid;
+mod provenance;
+mod names;
+mod subset;
+mod builder;
+mod attributes;
+mod memory;
+mod ops;
+mod history;
+mod scan_strategy;
 
-pub use volume::{VolumeData, VolumeMetadata};
-pub use sweep::{SweepData, SweepMetadata};
-pub use moment::MomentData;
+pub use attributes::{AttributeValue, Attributes};
+pub use volume::{InstrumentParameters, RadarCalibration, VolumeData, VolumeMergeOptions, VolumeMetadata};
+pub use sweep::{PlatformMotion, RayMetadata, SweepData, SweepMetadata};
+pub use moment::{MomentData, MomentStorage};
 pub use coordinates::Coordinates;
+pub use grid::{GridField, GriddedData};
+pub use names::{NameAliases, ORIGINAL_NAME_ATTRIBUTE};
+pub use subset::VolumeSubset;
+pub use builder::{Pattern, SweepBuilder, VolumeBuilder};
+pub use history::{History, HistoryEntry, HISTORY_ATTRIBUTE};
+pub use memory::{MemoryReport, MomentMemory, SweepMemory};
+pub use scan_strategy::{ScanCheck, ScanStrategy};
+pub use provenance::{
+    Provenance, ANCESTORS_ATTRIBUTE, ALGORITHM_ATTRIBUTE, ALGORITHM_VERSION_ATTRIBUTE, PARAMETERS_HASH_ATTRIBUTE,
+};
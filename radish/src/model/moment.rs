@@ -1,9 +1,96 @@
-/// Moment (radar variable) data structures
+//! Moment (radar variable) data structures
 
-use ndarray::Array2;
+use std::borrow::Cow;
+use std::ops::Range;
+use std::sync::Arc;
+
+use ndarray::{s, Array2, Axis};
+use radish_types::Unit;
 use serde::{Deserialize, Serialize};
 
+use super::attributes::Attributes;
+use super::provenance::Provenance;
+use crate::{RadishError, Result};
+
+/// Stored values of a moment
+///
+/// Integer variants hold packed values as found in the file; they are
+/// decoded with the moment's `scale_factor` and `add_offset` when read
+/// through [`MomentData::data`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum MomentStorage {
+    /// Unsigned 8-bit packed values
+    U8(Array2<u8>),
+    /// Signed 16-bit packed values
+    I16(Array2<i16>),
+    /// Single precision values
+    F32(Array2<f32>),
+    /// Double precision values
+    F64(Array2<f64>),
+}
+
+impl MomentStorage {
+    /// Shape of the array [rays × gates]
+    pub fn shape(&self) -> (usize, usize) {
+        match self {
+            Self::U8(a) => a.dim(),
+            Self::I16(a) => a.dim(),
+            Self::F32(a) => a.dim(),
+            Self::F64(a) => a.dim(),
+        }
+    }
+
+    /// Whether values are packed integers
+    pub fn is_packed(&self) -> bool {
+        matches!(self, Self::U8(_) | Self::I16(_))
+    }
+
+    /// Bytes held by the stored values
+    pub fn size_bytes(&self) -> usize {
+        match self {
+            Self::U8(a) => a.len(),
+            Self::I16(a) => a.len() * 2,
+            Self::F32(a) => a.len() * 4,
+            Self::F64(a) => a.len() * 8,
+        }
+    }
+
+    /// Stored values of the given rays, in the given order, and a span of gates
+    pub fn select(&self, rays: &[usize], gates: Range<usize>) -> Self {
+        match self {
+            Self::U8(a) => Self::U8(a.select(Axis(0), rays).slice(s![.., gates]).to_owned()),
+            Self::I16(a) => Self::I16(a.select(Axis(0), rays).slice(s![.., gates]).to_owned()),
+            Self::F32(a) => Self::F32(a.select(Axis(0), rays).slice(s![.., gates]).to_owned()),
+            Self::F64(a) => Self::F64(a.select(Axis(0), rays).slice(s![.., gates]).to_owned()),
+        }
+    }
+
+    /// Stored value at a gate, before scaling
+    fn raw(&self, idx: (usize, usize)) -> f32 {
+        match self {
+            Self::U8(a) => a[idx] as f32,
+            Self::I16(a) => a[idx] as f32,
+            Self::F32(a) => a[idx],
+            Self::F64(a) => a[idx] as f32,
+        }
+    }
+}
+
 /// Radar moment data (e.g., reflectivity, velocity)
+///
+/// Values are kept in the representation they were read in (see
+/// [`MomentStorage`]) and decoded to `f32` physical values on access, so
+/// 8 and 16-bit packed moments take a quarter or half of the memory of
+/// decoded ones. Transforms that modify values unpack the moment.
+///
+/// A gate is valid when its value is not NaN, the fill value or outside the
+/// valid range, and the optional validity mask does not exclude it. The
+/// mask lets gates be censored without overwriting their values; the
+/// `valid_*` helpers and statistics skip invalid gates.
+///
+/// Values and mask are shared copy-on-write: clones of a moment, and of the
+/// sweeps and volumes holding it, share the arrays until one of them is
+/// modified, so read-only access never duplicates them.
 #[derive(Debug, Clone)]
 pub struct MomentData {
     /// Variable name (e.g., "DBZH", "VRADH")
@@ -18,29 +105,35 @@ pub struct MomentData {
     /// Units
     pub units: String,
 
-    /// 2D data array [rays × gates]
-    pub data: Array2<f32>,
+    /// 2D array of stored values [rays × gates]
+    storage: Arc<MomentStorage>,
 
-    /// Fill value (missing data indicator)
+    /// Validity mask [rays × gates], true for gates that may be valid
+    mask: Option<Arc<Array2<bool>>>,
+
+    /// Fill value (missing data indicator), as a stored value
     pub fill_value: Option<f32>,
 
-    /// Scale factor
+    /// Scale factor applied to stored values
     pub scale_factor: Option<f32>,
 
-    /// Add offset
+    /// Offset added to scaled stored values
     pub add_offset: Option<f32>,
 
-    /// Valid minimum
+    /// Valid minimum, as a stored value
     pub valid_min: Option<f32>,
 
-    /// Valid maximum
+    /// Valid maximum, as a stored value
     pub valid_max: Option<f32>,
 
     /// Coordinates this variable depends on
     pub coordinates: Option<String>,
 
-    /// Additional attributes
-    pub attributes: std::collections::HashMap<String, String>,
+    /// Additional attributes, as read from the file
+    pub attributes: Attributes,
+
+    /// Inputs and algorithm that produced a derived moment (`None` for measured moments)
+    pub provenance: Option<Provenance>,
 }
 
 impl MomentData {
@@ -50,65 +143,435 @@ impl MomentData {
         units: String,
         data: Array2<f32>,
     ) -> Self {
+        Self::from_storage(name, units, MomentStorage::F32(data))
+    }
+
+    /// Create a MomentData from stored values
+    ///
+    /// Set `scale_factor`, `add_offset` and `fill_value` to those of the
+    /// packing for integer storage.
+    pub fn from_storage(name: String, units: String, storage: MomentStorage) -> Self {
         Self {
             name,
             standard_name: None,
             long_name: None,
             units,
-            data,
+            storage: Arc::new(storage),
+            mask: None,
             fill_value: None,
             scale_factor: None,
             add_offset: None,
             valid_min: None,
             valid_max: None,
             coordinates: None,
-            attributes: std::collections::HashMap::new(),
+            attributes: Attributes::new(),
+            provenance: None,
         }
     }
 
+    /// Create a MomentData from rays with differing gate counts
+    ///
+    /// Rays are padded with NaN to the longest ray, and the padding is
+    /// excluded by the validity mask. The gate count of each ray, for
+    /// [`Coordinates::n_gates_per_ray`](crate::Coordinates::n_gates_per_ray),
+    /// is `rays[i].len()`.
+    pub fn from_ragged_rays(name: String, units: String, rays: &[Vec<f32>]) -> Self {
+        let num_gates = rays.iter().map(Vec::len).max().unwrap_or(0);
+        let data = Array2::from_shape_fn((rays.len(), num_gates), |(i, j)| {
+            rays[i].get(j).copied().unwrap_or(f32::NAN)
+        });
+        let mut moment = Self::new(name, units, data);
+        if rays.iter().any(|r| r.len() != num_gates) {
+            moment.mask = Some(Arc::new(Array2::from_shape_fn((rays.len(), num_gates), |(i, j)| {
+                j < rays[i].len()
+            })));
+        }
+        moment
+    }
+
+    /// Physical values of each ray truncated to its recorded gates, for writers of ragged formats
+    pub fn ragged_rays(&self, n_gates_per_ray: &[usize]) -> Result<Vec<Vec<f32>>> {
+        let (num_rays, num_gates) = self.shape();
+        if n_gates_per_ray.len() != num_rays {
+            return Err(RadishError::General(format!(
+                "Gates per ray length ({}) does not match moment {} rays ({})",
+                n_gates_per_ray.len(),
+                self.name,
+                num_rays
+            )));
+        }
+        let data = self.data();
+        Ok(data
+            .rows()
+            .into_iter()
+            .zip(n_gates_per_ray)
+            .map(|(row, &n)| row.iter().take(n.min(num_gates)).copied().collect())
+            .collect())
+    }
+
+    /// A moment with the metadata of this one and new physical values
+    ///
+    /// The new moment is unpacked: its values are not scaled, and its fill
+    /// value and valid range are those of [`Self::physical_fill_value`] and
+    /// [`Self::is_valid_value`].
+    pub fn with_data(&self, data: Array2<f32>) -> Self {
+        let (fill_value, valid_min, valid_max) = self.physical_limits();
+        Self {
+            name: self.name.clone(),
+            standard_name: self.standard_name.clone(),
+            long_name: self.long_name.clone(),
+            units: self.units.clone(),
+            storage: Arc::new(MomentStorage::F32(data)),
+            mask: None,
+            fill_value,
+            scale_factor: None,
+            add_offset: None,
+            valid_min,
+            valid_max,
+            coordinates: self.coordinates.clone(),
+            attributes: self.attributes.clone(),
+            provenance: self.provenance.clone(),
+        }
+    }
+
+    /// The given rays, in the given order, and a span of gates of this moment
+    ///
+    /// Values keep their storage and packing, and the validity mask is
+    /// selected with them.
+    pub fn select(&self, rays: &[usize], gates: Range<usize>) -> Self {
+        Self {
+            name: self.name.clone(),
+            standard_name: self.standard_name.clone(),
+            long_name: self.long_name.clone(),
+            units: self.units.clone(),
+            storage: Arc::new(self.storage.select(rays, gates.clone())),
+            mask: self
+                .mask
+                .as_ref()
+                .map(|m| Arc::new(m.select(Axis(0), rays).slice(s![.., gates]).to_owned())),
+            fill_value: self.fill_value,
+            scale_factor: self.scale_factor,
+            add_offset: self.add_offset,
+            valid_min: self.valid_min,
+            valid_max: self.valid_max,
+            coordinates: self.coordinates.clone(),
+            attributes: self.attributes.clone(),
+            provenance: self.provenance.clone(),
+        }
+    }
+
+    /// Set the provenance of a derived moment
+    pub fn with_provenance(mut self, provenance: Provenance) -> Self {
+        self.provenance = Some(provenance);
+        self
+    }
+
     /// Get the shape of the data array
     pub fn shape(&self) -> (usize, usize) {
-        let shape = self.data.shape();
-        (shape[0], shape[1])
+        self.storage.shape()
+    }
+
+    /// Physical values [rays × gates]
+    ///
+    /// Borrowed for unscaled `f32` storage, decoded otherwise. Fill gates of
+    /// scaled moments decode to NaN; unscaled moments keep their fill value.
+    /// Hoist the call out of loops over gates.
+    pub fn data(&self) -> Cow<'_, Array2<f32>> {
+        match self.storage.as_ref() {
+            MomentStorage::F32(data) if !self.is_scaled() => Cow::Borrowed(data),
+            storage => Cow::Owned(Array2::from_shape_fn(storage.shape(), |idx| self.decode(storage.raw(idx)))),
+        }
+    }
+
+    /// Physical value of a single gate
+    pub fn value(&self, ray: usize, gate: usize) -> f32 {
+        self.decode(self.storage.raw((ray, gate)))
+    }
+
+    /// Mutable physical values, unpacking the moment first if needed
+    ///
+    /// Copies the values first if they are shared with a clone.
+    pub fn data_mut(&mut self) -> &mut Array2<f32> {
+        self.apply_scale_offset();
+        match Arc::make_mut(&mut self.storage) {
+            MomentStorage::F32(data) => data,
+            _ => unreachable!("moment was unpacked"),
+        }
+    }
+
+    /// Replace the values with physical values, unpacking the moment
+    ///
+    /// The validity mask belongs to the old values and is removed.
+    pub fn set_data(&mut self, data: Array2<f32>) {
+        self.storage = Arc::new(MomentStorage::F32(data));
+        self.mask = None;
+        self.unscale();
+    }
+
+    /// Physical values, consuming the moment
+    ///
+    /// Moves the values out unless they are shared with a clone.
+    pub fn into_data(self) -> Array2<f32> {
+        if self.is_scaled() || !matches!(*self.storage, MomentStorage::F32(_)) {
+            return self.data().into_owned();
+        }
+        match Arc::unwrap_or_clone(self.storage) {
+            MomentStorage::F32(data) => data,
+            _ => unreachable!("storage is unscaled f32"),
+        }
+    }
+
+    /// Stored values, for writers to write the packing of the file back unchanged
+    pub fn as_packed(&self) -> &MomentStorage {
+        &self.storage
+    }
+
+    /// Whether this moment shares its values with another, e.g. a clone not modified since
+    pub fn shares_data_with(&self, other: &MomentData) -> bool {
+        Arc::ptr_eq(&self.storage, &other.storage)
+    }
+
+    /// Whether the values are stored as packed integers
+    pub fn is_packed(&self) -> bool {
+        self.storage.is_packed()
     }
 
-    /// Apply scale and offset to get physical values
+    /// Decode stored values to `f32` physical values
+    ///
+    /// Afterwards the storage is unscaled `f32`, `scale_factor` and
+    /// `add_offset` are unset, and the fill value and valid range are
+    /// physical values.
     pub fn apply_scale_offset(&mut self) {
-        if let (Some(scale), Some(offset)) = (self.scale_factor, self.add_offset) {
-            self.data.mapv_inplace(|v| {
-                if let Some(fill) = self.fill_value {
-                    if v == fill {
-                        return v;
-                    }
-                }
-                v * scale + offset
-            });
-            self.scale_factor = None;
-            self.add_offset = None;
+        if matches!(*self.storage, MomentStorage::F32(_)) && !self.is_scaled() {
+            return;
         }
+        self.storage = Arc::new(MomentStorage::F32(self.data().into_owned()));
+        self.unscale();
     }
 
-    /// Mask invalid values
-    pub fn mask_invalid(&mut self, mask_value: f32) {
-        if let Some(fill) = self.fill_value {
-            self.data.mapv_inplace(|v| {
-                if v == fill {
-                    mask_value
-                } else {
-                    v
-                }
-            });
-        }
-
-        if let (Some(min), Some(max)) = (self.valid_min, self.valid_max) {
-            self.data.mapv_inplace(|v| {
-                if v < min || v > max {
-                    mask_value
-                } else {
-                    v
-                }
-            });
+    /// Fill value of the physical values
+    ///
+    /// The fill value of unscaled moments; `None` for scaled moments, whose
+    /// fill gates decode to NaN.
+    pub fn physical_fill_value(&self) -> Option<f32> {
+        if self.is_scaled() {
+            None
+        } else {
+            self.fill_value
+        }
+    }
+
+    fn is_scaled(&self) -> bool {
+        self.scale_factor.is_some() || self.add_offset.is_some()
+    }
+
+    fn scale(&self, stored: f32) -> f32 {
+        stored * self.scale_factor.unwrap_or(1.0) + self.add_offset.unwrap_or(0.0)
+    }
+
+    fn decode(&self, raw: f32) -> f32 {
+        match self.fill_value {
+            Some(fill) if fill == raw && self.is_scaled() => f32::NAN,
+            Some(fill) if fill == raw => raw,
+            _ => self.scale(raw),
+        }
+    }
+
+    /// Fill value, valid minimum and valid maximum as physical values
+    fn physical_limits(&self) -> (Option<f32>, Option<f32>, Option<f32>) {
+        (
+            self.physical_fill_value(),
+            self.valid_min.map(|v| self.scale(v)),
+            self.valid_max.map(|v| self.scale(v)),
+        )
+    }
+
+    /// Drop the packing of metadata once the storage holds physical values
+    fn unscale(&mut self) {
+        (self.fill_value, self.valid_min, self.valid_max) = self.physical_limits();
+        self.scale_factor = None;
+        self.add_offset = None;
+    }
+
+    /// Check whether a physical value is valid data (not fill, NaN, or out of the valid range)
+    pub fn is_valid_value(&self, v: f32) -> bool {
+        if v.is_nan() {
+            return false;
         }
+        let (fill_value, valid_min, valid_max) = self.physical_limits();
+        if let Some(fill) = fill_value {
+            if v == fill {
+                return false;
+            }
+        }
+        if let Some(min) = valid_min {
+            if v < min {
+                return false;
+            }
+        }
+        if let Some(max) = valid_max {
+            if v > max {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Validity mask [rays × gates], if set
+    pub fn mask(&self) -> Option<&Array2<bool>> {
+        self.mask.as_deref()
+    }
+
+    /// Set or remove the validity mask
+    pub fn set_mask(&mut self, mask: Option<Array2<bool>>) -> Result<()> {
+        if let Some(mask) = &mask {
+            if mask.dim() != self.shape() {
+                return Err(RadishError::General(format!(
+                    "Mask shape {:?} does not match moment {} shape {:?}",
+                    mask.dim(),
+                    self.name,
+                    self.shape()
+                )));
+            }
+        }
+        self.mask = mask.map(Arc::new);
+        Ok(())
+    }
+
+    /// Set the validity mask from the fill value, valid range and NaN gates
+    ///
+    /// Gates already excluded by the mask stay excluded.
+    pub fn update_mask(&mut self) {
+        let data = self.data();
+        let mut mask = data.mapv(|v| self.is_valid_value(v));
+        if let Some(previous) = &self.mask {
+            mask &= &**previous;
+        }
+        self.mask = Some(Arc::new(mask));
+    }
+
+    /// Exclude gates, creating the mask if needed
+    pub fn exclude(&mut self, excluded: &Array2<bool>) -> Result<()> {
+        if excluded.dim() != self.shape() {
+            return Err(RadishError::General(format!(
+                "Excluded gates shape {:?} does not match moment {} shape {:?}",
+                excluded.dim(),
+                self.name,
+                self.shape()
+            )));
+        }
+        let mask = self
+            .mask
+            .get_or_insert_with(|| Arc::new(Array2::from_elem(excluded.dim(), true)));
+        ndarray::Zip::from(Arc::make_mut(mask)).and(excluded).for_each(|m, &e| *m &= !e);
+        Ok(())
+    }
+
+    /// Whether a gate is valid
+    pub fn is_valid(&self, ray: usize, gate: usize) -> bool {
+        self.valid_value(ray, gate).is_some()
+    }
+
+    /// Physical value of a gate if it is valid
+    pub fn valid_value(&self, ray: usize, gate: usize) -> Option<f32> {
+        if self.mask.as_ref().is_some_and(|m| !m[[ray, gate]]) {
+            return None;
+        }
+        let v = self.value(ray, gate);
+        self.is_valid_value(v).then_some(v)
+    }
+
+    /// Validity of every gate [rays × gates]
+    pub fn validity(&self) -> Array2<bool> {
+        let (num_rays, num_gates) = self.shape();
+        Array2::from_shape_fn((num_rays, num_gates), |(i, j)| self.is_valid(i, j))
+    }
+
+    /// Valid gates with their physical values, in ray-major order
+    pub fn indexed_valid_values(&self) -> impl Iterator<Item = ((usize, usize), f32)> + '_ {
+        let (num_rays, num_gates) = self.shape();
+        (0..num_rays)
+            .flat_map(move |i| (0..num_gates).map(move |j| (i, j)))
+            .filter_map(|(i, j)| self.valid_value(i, j).map(|v| ((i, j), v)))
+    }
+
+    /// Physical values of the valid gates, in ray-major order
+    pub fn valid_values(&self) -> impl Iterator<Item = f32> + '_ {
+        self.indexed_valid_values().map(|(_, v)| v)
+    }
+
+    /// Number of valid gates
+    pub fn count_valid(&self) -> usize {
+        self.valid_values().count()
+    }
+
+    /// Smallest valid value
+    pub fn min_valid(&self) -> Option<f32> {
+        self.valid_values().reduce(f32::min)
+    }
+
+    /// Largest valid value
+    pub fn max_valid(&self) -> Option<f32> {
+        self.valid_values().reduce(f32::max)
+    }
+
+    /// Mean of the valid values
+    pub fn mean_valid(&self) -> Option<f64> {
+        let (sum, count) = self
+            .valid_values()
+            .fold((0.0f64, 0usize), |(sum, count), v| (sum + v as f64, count + 1));
+        (count > 0).then(|| sum / count as f64)
+    }
+
+    /// Value used to mark missing gates written by transforms (fill value or NaN)
+    pub fn missing_value(&self) -> f32 {
+        self.physical_fill_value().unwrap_or(f32::NAN)
+    }
+
+    /// Units of the moment, if they are a known [`Unit`]
+    pub fn unit(&self) -> Option<Unit> {
+        Unit::from_symbol(&self.units)
+    }
+
+    /// Convert the values to `target` units, unpacking the moment
+    ///
+    /// Valid values and the valid range are converted and `units` is set to
+    /// the symbol of `target`; invalid gates get the missing value. Fails if
+    /// the current units are unknown or measure another quantity.
+    pub fn convert_units(&mut self, target: Unit) -> Result<()> {
+        let unit = self.unit().ok_or_else(|| {
+            RadishError::Conversion(format!("Unknown units '{}' of moment {}", self.units, self.name))
+        })?;
+        if unit.quantity() != target.quantity() {
+            return Err(RadishError::Conversion(format!(
+                "Cannot convert moment {} from {} to {}",
+                self.name, unit, target
+            )));
+        }
+        if unit == target {
+            return Ok(());
+        }
+
+        let validity = self.validity();
+        let missing = self.missing_value();
+        let convert = |v: f32| unit.convert(v as f64, target).unwrap_or(f64::NAN) as f32;
+        ndarray::Zip::from(self.data_mut()).and(&validity).for_each(|v, &valid| {
+            *v = if valid { convert(*v) } else { missing };
+        });
+        self.valid_min = self.valid_min.map(convert);
+        self.valid_max = self.valid_max.map(convert);
+        self.units = target.symbol().to_string();
+        Ok(())
+    }
+
+    /// Replace the values of invalid gates, including those excluded by the mask, by `mask_value`
+    pub fn mask_invalid(&mut self, mask_value: f32) {
+        let validity = self.validity();
+        ndarray::Zip::from(self.data_mut()).and(&validity).for_each(|v, &valid| {
+            if !valid {
+                *v = mask_value;
+            }
+        });
     }
 }
 
@@ -0,0 +1,79 @@
+//! Standardization of vendor moment names
+//!
+//! Files name the same moment differently: reflectivity is `DBZ`, `REF`,
+//! `ZH` or `corrected_reflectivity` depending on the vendor and processing
+//! chain. [`NameAliases`] maps such names to the CfRadial2 standard names
+//! used throughout radish; renamed moments keep their original name in the
+//! [`ORIGINAL_NAME_ATTRIBUTE`] attribute.
+
+use std::collections::HashMap;
+
+/// Moment attribute holding the name a moment had before standardization
+pub const ORIGINAL_NAME_ATTRIBUTE: &str = "original_name";
+
+/// Vendor names of the standard moments
+const STANDARD_ALIASES: &[(&str, &[&str])] = &[
+    ("DBZH", &["DBZ", "REF", "ZH", "DZ", "reflectivity", "corrected_reflectivity", "reflectivity_horizontal"]),
+    ("DBZV", &["ZV", "reflectivity_vertical"]),
+    ("VRADH", &["VEL", "VR", "VRAD", "velocity", "radial_velocity", "corrected_velocity"]),
+    ("WRADH", &["WIDTH", "SW", "WRAD", "spectrum_width"]),
+    ("ZDR", &["DR", "differential_reflectivity", "corrected_differential_reflectivity"]),
+    ("PHIDP", &["PHI", "PH", "differential_phase", "corrected_differential_phase"]),
+    ("KDP", &["KD", "specific_differential_phase", "corrected_specific_differential_phase"]),
+    ("RHOHV", &["RHO", "RH", "cross_correlation_ratio", "correlation_coefficient"]),
+    ("LDRH", &["LDR", "linear_depolarization_ratio"]),
+    ("SNRH", &["SNR", "signal_to_noise_ratio"]),
+    ("NCP", &["normalized_coherent_power"]),
+];
+
+/// Table mapping vendor moment names to standard names
+///
+/// Lookups ignore case. [`NameAliases::default`] holds the common vendor
+/// names; aliases can be added or removed for a particular archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NameAliases {
+    /// Standard name of each alias, keyed by the lowercase alias
+    aliases: HashMap<String, String>,
+}
+
+impl NameAliases {
+    /// A table without aliases
+    pub fn empty() -> Self {
+        Self {
+            aliases: HashMap::new(),
+        }
+    }
+
+    /// Map `alias` to `standard`, replacing any previous mapping of `alias`
+    pub fn with_alias(mut self, alias: &str, standard: &str) -> Self {
+        self.insert(alias, standard);
+        self
+    }
+
+    /// Map `alias` to `standard`, replacing any previous mapping of `alias`
+    pub fn insert(&mut self, alias: &str, standard: &str) {
+        self.aliases.insert(alias.to_lowercase(), standard.to_string());
+    }
+
+    /// Stop mapping `alias`
+    pub fn remove(&mut self, alias: &str) {
+        self.aliases.remove(&alias.to_lowercase());
+    }
+
+    /// Standard name of a moment name, if it is an alias
+    pub fn standard_name(&self, name: &str) -> Option<&str> {
+        self.aliases.get(&name.to_lowercase()).map(String::as_str)
+    }
+}
+
+impl Default for NameAliases {
+    fn default() -> Self {
+        let mut names = Self::empty();
+        for (standard, aliases) in STANDARD_ALIASES {
+            for alias in *aliases {
+                names.insert(alias, standard);
+            }
+        }
+        names
+    }
+}
@@ -0,0 +1,106 @@
+//! Arithmetic on moments
+//!
+//! `+`, `-`, `*` and `/` work gate by gate between two moments of the same
+//! shape, giving a `Result` that fails on differing shapes, and between a
+//! moment and a scalar:
+//!
+//! ```text
+//! let zdr = (&dbzh - &dbzv)?;
+//! let corrected = &dbzh + 1.5;
+//! ```
+//!
+//! Only valid gates are combined; gates invalid in any operand are missing
+//! in the result.
+
+use std::ops::{Add, Div, Mul, Sub};
+
+use ndarray::{Array2, Zip};
+
+use super::MomentData;
+use crate::{RadishError, Result};
+
+impl MomentData {
+    /// Combine with another moment of the same shape, gate by gate
+    ///
+    /// Gates invalid in either moment are set to the fill value of this
+    /// moment, or NaN without one. The result has the name, units and
+    /// attributes of this moment, unpacked values and no valid range.
+    pub fn zip_valid(&self, other: &MomentData, f: impl Fn(f32, f32) -> f32) -> Result<MomentData> {
+        if self.shape() != other.shape() {
+            return Err(RadishError::General(format!(
+                "Cannot combine moment {} {:?} with moment {} {:?}",
+                self.name,
+                self.shape(),
+                other.name,
+                other.shape()
+            )));
+        }
+        let (a, b) = (self.valid_data(), other.valid_data());
+        let mut data = Array2::zeros(self.shape());
+        Zip::from(&mut data).and(&a).and(&b).for_each(|out, &a, &b| *out = f(a, b));
+        Ok(self.with_result(data))
+    }
+
+    /// Apply a function to every valid gate
+    ///
+    /// Invalid gates are set to the fill value of this moment, or NaN
+    /// without one; the result has unpacked values and no valid range.
+    pub fn map_valid(&self, f: impl Fn(f32) -> f32) -> MomentData {
+        self.with_result(self.valid_data().mapv(f))
+    }
+
+    /// Physical values with invalid gates as NaN
+    fn valid_data(&self) -> Array2<f32> {
+        let mut data = self.data().into_owned();
+        Zip::from(&mut data).and(&self.validity()).for_each(|v, &valid| {
+            if !valid {
+                *v = f32::NAN;
+            }
+        });
+        data
+    }
+
+    /// A copy holding `data`, with NaN gates set to the fill value
+    fn with_result(&self, data: Array2<f32>) -> MomentData {
+        let mut result = self.with_data(data);
+        result.valid_min = None;
+        result.valid_max = None;
+        if let Some(fill) = result.fill_value {
+            result.data_mut().mapv_inplace(|v| if v.is_nan() { fill } else { v });
+        }
+        result
+    }
+}
+
+macro_rules! moment_op {
+    ($trait:ident, $method:ident, $op:tt) => {
+        impl $trait<&MomentData> for &MomentData {
+            type Output = Result<MomentData>;
+
+            fn $method(self, rhs: &MomentData) -> Result<MomentData> {
+                self.zip_valid(rhs, |a, b| a $op b)
+            }
+        }
+
+        impl $trait<f32> for &MomentData {
+            type Output = MomentData;
+
+            fn $method(self, rhs: f32) -> MomentData {
+                self.map_valid(|v| v $op rhs)
+            }
+        }
+
+        impl $trait<f32> for MomentData {
+            type Output = MomentData;
+
+            fn $method(self, rhs: f32) -> MomentData {
+                (&self).$method(rhs)
+            }
+        }
+    };
+}
+
+moment_op!(Add, add, +);
+moment_op!(Sub, sub, -);
+moment_op!(Mul, mul, *);
+moment_op!(Div, div, /);
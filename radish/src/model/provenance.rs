@@ -0,0 +1,89 @@
+//! Provenance of derived moments
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+use serde::{Deserialize, Serialize};
+
+/// Attribute holding the ancestors as a JSON array, e.g. `["PHIDP", "DBZH"]`
+pub const ANCESTORS_ATTRIBUTE: &str = "ancestors";
+
+/// Attribute holding the algorithm identifier
+pub const ALGORITHM_ATTRIBUTE: &str = "algorithm";
+
+/// Attribute holding the version of the software that ran the algorithm
+pub const ALGORITHM_VERSION_ATTRIBUTE: &str = "algorithm_version";
+
+/// Attribute holding the hash of the algorithm parameters
+pub const PARAMETERS_HASH_ATTRIBUTE: &str = "parameters_hash";
+
+/// Which inputs and which algorithm produced a derived moment
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Provenance {
+    /// Moments the derived moment was computed from
+    pub ancestors: Vec<String>,
+
+    /// Algorithm identifier (e.g. "radish.transforms.phidp.process_phidp")
+    pub algorithm: String,
+
+    /// Version of the software that ran the algorithm
+    pub version: String,
+
+    /// Hash of the algorithm parameters ("fnv1a64:" followed by 16 hex digits)
+    pub parameters_hash: String,
+}
+
+impl Provenance {
+    /// Provenance of a moment computed by a radish algorithm
+    ///
+    /// The parameters hash is computed from the `Debug` representation of
+    /// `parameters`, so it identifies the exact options used and is stable
+    /// across runs and platforms.
+    pub fn new(algorithm: &str, ancestors: &[impl AsRef<str>], parameters: &impl Debug) -> Self {
+        Self {
+            ancestors: ancestors.iter().map(|a| a.as_ref().to_string()).collect(),
+            algorithm: algorithm.to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            parameters_hash: parameters_hash(parameters),
+        }
+    }
+
+    /// Encode as string attributes (as written by the writers)
+    pub fn to_attributes(&self) -> HashMap<String, String> {
+        HashMap::from([
+            (
+                ANCESTORS_ATTRIBUTE.to_string(),
+                serde_json::to_string(&self.ancestors).unwrap_or_default(),
+            ),
+            (ALGORITHM_ATTRIBUTE.to_string(), self.algorithm.clone()),
+            (ALGORITHM_VERSION_ATTRIBUTE.to_string(), self.version.clone()),
+            (PARAMETERS_HASH_ATTRIBUTE.to_string(), self.parameters_hash.clone()),
+        ])
+    }
+
+    /// Decode from string attributes; `None` unless the ancestors and algorithm are present
+    pub fn from_attributes(attributes: &HashMap<String, String>) -> Option<Self> {
+        let ancestors = serde_json::from_str(attributes.get(ANCESTORS_ATTRIBUTE)?).ok()?;
+        Some(Self {
+            ancestors,
+            algorithm: attributes.get(ALGORITHM_ATTRIBUTE)?.clone(),
+            version: attributes
+                .get(ALGORITHM_VERSION_ATTRIBUTE)
+                .cloned()
+                .unwrap_or_default(),
+            parameters_hash: attributes
+                .get(PARAMETERS_HASH_ATTRIBUTE)
+                .cloned()
+                .unwrap_or_default(),
+        })
+    }
+}
+
+/// Stable hash of a parameter set (FNV-1a over its `Debug` representation)
+fn parameters_hash(parameters: &impl Debug) -> String {
+    let text = format!("{:?}", parameters);
+    let hash = text.bytes().fold(0xcbf2_9ce4_8422_2325u64, |h, b| {
+        (h ^ b as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    });
+    format!("fnv1a64:{:016x}", hash)
+}
@@ -0,0 +1,124 @@
+//! Scan strategies
+//!
+//! Operational radars repeat a fixed scan strategy, e.g. a NEXRAD volume
+//! coverage pattern (VCP), whose elevations and sweep modes are known in
+//! advance. With the strategy declared on [`VolumeMetadata`](super::VolumeMetadata),
+//! volumes interrupted by a restart or truncated in transfer can be found
+//! by checking them against it.
+
+use serde::{Deserialize, Serialize};
+use radish_types::SweepMode;
+
+use super::{Attributes, VolumeData};
+
+/// Scan strategy of a volume: the sweeps it is expected to hold, in order
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScanStrategy {
+    /// Name (e.g. "VCP 212")
+    pub name: String,
+    /// Expected fixed angle of each sweep (degrees)
+    pub elevations: Vec<f64>,
+    /// Expected sweep mode of each sweep
+    pub sweep_modes: Vec<SweepMode>,
+}
+
+/// Elevations (degrees) of the NEXRAD VCPs, split cuts listed twice
+const NEXRAD_VCPS: &[(u32, &[f64])] = &[
+    (11, &[0.5, 0.5, 1.45, 1.45, 2.4, 3.35, 4.3, 5.25, 6.2, 7.5, 8.7, 10.0, 12.0, 14.0, 16.7, 19.5]),
+    (12, &[0.5, 0.5, 0.9, 0.9, 1.3, 1.3, 1.8, 2.4, 3.1, 4.0, 5.1, 6.4, 8.0, 10.0, 12.5, 15.6, 19.5]),
+    (21, &[0.5, 0.5, 1.45, 1.45, 2.4, 3.35, 4.3, 6.0, 9.9, 14.6, 19.5]),
+    (31, &[0.5, 0.5, 1.5, 1.5, 2.5, 2.5, 3.5, 4.5]),
+    (32, &[0.5, 0.5, 1.5, 1.5, 2.5, 3.5, 4.5]),
+    (35, &[0.5, 0.5, 0.9, 0.9, 1.3, 1.3, 1.8, 2.4, 3.1, 4.0, 5.1, 6.4]),
+    (212, &[0.5, 0.5, 0.9, 0.9, 1.3, 1.3, 1.8, 2.4, 3.1, 4.0, 5.1, 6.4, 8.0, 10.0, 12.5, 15.6, 19.5]),
+    (215, &[0.5, 0.5, 0.9, 0.9, 1.3, 1.3, 1.8, 2.4, 3.1, 4.0, 5.1, 6.4, 8.0, 10.0, 12.0, 14.0, 16.7, 19.5]),
+];
+
+impl ScanStrategy {
+    /// Strategy of PPI sweeps at the given elevations
+    pub fn ppi(name: &str, elevations: &[f64]) -> Self {
+        Self {
+            name: name.to_string(),
+            elevations: elevations.to_vec(),
+            sweep_modes: vec![SweepMode::Azimuth; elevations.len()],
+        }
+    }
+
+    /// Base strategy of a NEXRAD VCP, if known
+    ///
+    /// Split cuts are two sweeps at the same elevation. Supplemental SAILS
+    /// cuts, which depend on the site configuration, are not part of it.
+    pub fn nexrad_vcp(vcp: u32) -> Option<Self> {
+        NEXRAD_VCPS
+            .iter()
+            .find(|(number, _)| *number == vcp)
+            .map(|(number, elevations)| Self::ppi(&format!("VCP {}", number), elevations))
+    }
+
+    /// NEXRAD VCP named by the `vcp` attribute, or a `scan_name` such as "VCP 212"
+    pub fn from_attributes(attributes: &Attributes) -> Option<Self> {
+        let vcp = attributes.get("vcp").and_then(|v| v.as_i64()).or_else(|| {
+            let name = attributes.get("scan_name")?.as_str()?.trim().to_ascii_uppercase();
+            name.strip_prefix("VCP")?.trim_start_matches(['-', '_', ' ']).parse().ok()
+        })?;
+        Self::nexrad_vcp(u32::try_from(vcp).ok()?)
+    }
+
+    /// Number of expected sweeps
+    pub fn num_sweeps(&self) -> usize {
+        self.elevations.len()
+    }
+
+    /// Compare the sweeps of a volume with the strategy
+    ///
+    /// Expected sweeps are matched in order to the first unmatched sweep of
+    /// the volume with the same sweep mode and a fixed angle within
+    /// `angle_tolerance` degrees.
+    pub fn check(&self, volume: &VolumeData, angle_tolerance: f64) -> ScanCheck {
+        let mut matched = vec![false; volume.sweeps.len()];
+        let mut missing = Vec::new();
+        for (k, (&elevation, &mode)) in self.elevations.iter().zip(&self.sweep_modes).enumerate() {
+            let found = volume.sweeps.iter().enumerate().position(|(i, sweep)| {
+                !matched[i]
+                    && sweep.metadata.sweep_mode == mode
+                    && (sweep.metadata.fixed_angle - elevation).abs() <= angle_tolerance
+            });
+            match found {
+                Some(i) => matched[i] = true,
+                None => missing.push(k),
+            }
+        }
+        ScanCheck {
+            missing,
+            unexpected: (0..matched.len()).filter(|&i| !matched[i]).collect(),
+        }
+    }
+}
+
+/// Result of checking a volume against its scan strategy
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScanCheck {
+    /// Indices of the expected sweeps missing from the volume
+    pub missing: Vec<usize>,
+    /// Indices of the sweeps of the volume the strategy doesn't expect
+    /// (e.g. SAILS cuts)
+    pub unexpected: Vec<usize>,
+}
+
+impl ScanCheck {
+    /// Whether every expected sweep is present
+    pub fn is_complete(&self) -> bool {
+        self.missing.is_empty()
+    }
+}
+
+impl VolumeData {
+    /// Check the volume against its declared scan strategy
+    ///
+    /// `None` when the volume declares no strategy. Fixed angles match
+    /// within 0.25°.
+    pub fn check_scan_strategy(&self) -> Option<ScanCheck> {
+        let strategy = self.metadata.scan_strategy.as_ref()?;
+        Some(strategy.check(self, 0.25))
+    }
+}
@@ -0,0 +1,134 @@
+//! Selection of part of a volume
+//!
+//! [`VolumeData::subset`] returns a [`VolumeSubset`] builder choosing
+//! sweeps, a span of ranges, an azimuth sector and moments; `build` copies
+//! the selected part into a new, smaller volume.
+
+use super::{SweepData, VolumeData};
+use crate::{RadishError, Result};
+
+/// Builder for a subset of a volume
+///
+/// Criteria combine: a sweep is kept if it passes every sweep criterion,
+/// and within it the rays and gates passing every ray and gate criterion.
+#[derive(Debug, Clone)]
+pub struct VolumeSubset<'a> {
+    volume: &'a VolumeData,
+    sweeps: Option<Vec<usize>>,
+    elevations: Option<(f64, f64)>,
+    ranges: Option<(f64, f64)>,
+    sector: Option<(f32, f32)>,
+    moments: Option<Vec<String>>,
+}
+
+impl<'a> VolumeSubset<'a> {
+    /// A subset of `volume` keeping everything
+    pub fn new(volume: &'a VolumeData) -> Self {
+        Self {
+            volume,
+            sweeps: None,
+            elevations: None,
+            ranges: None,
+            sector: None,
+            moments: None,
+        }
+    }
+
+    /// Keep the sweeps of the given indices
+    pub fn sweeps(mut self, indices: &[usize]) -> Self {
+        self.sweeps = Some(indices.to_vec());
+        self
+    }
+
+    /// Keep sweeps whose fixed angle is within `min..=max` degrees
+    pub fn elevations(mut self, min: f64, max: f64) -> Self {
+        self.elevations = Some((min, max));
+        self
+    }
+
+    /// Keep gates whose range is within `min..=max` kilometers
+    pub fn range_km(mut self, min: f64, max: f64) -> Self {
+        self.ranges = Some((min * 1000.0, max * 1000.0));
+        self
+    }
+
+    /// Keep rays in the sector clockwise from `start` to `end` degrees, crossing north if needed
+    pub fn azimuth_sector(mut self, start: f32, end: f32) -> Self {
+        self.sector = Some((start.rem_euclid(360.0), end.rem_euclid(360.0)));
+        self
+    }
+
+    /// Keep the given moments
+    pub fn moments(mut self, names: &[&str]) -> Self {
+        self.moments = Some(names.iter().map(|n| n.to_string()).collect());
+        self
+    }
+
+    /// Copy the selection into a new volume
+    ///
+    /// Sweeps are renumbered and those left without rays or gates dropped;
+    /// the time coverage is that of the remaining rays when they have
+    /// times. Fails on a sweep index outside the volume.
+    pub fn build(&self) -> Result<VolumeData> {
+        let volume = self.volume;
+        if let Some(&index) = self.sweeps.iter().flatten().find(|&&i| i >= volume.num_sweeps()) {
+            return Err(RadishError::InvalidSweepIndex(index));
+        }
+
+        let mut sweeps = Vec::new();
+        for (index, sweep) in volume.sweeps.iter().enumerate() {
+            if self.sweeps.as_ref().is_some_and(|s| !s.contains(&index)) {
+                continue;
+            }
+            if let Some((min, max)) = self.elevations {
+                if !(min..=max).contains(&sweep.metadata.fixed_angle) {
+                    continue;
+                }
+            }
+            let sweep = self.select(sweep);
+            if sweep.num_rays() > 0 && sweep.num_gates() > 0 {
+                sweeps.push(sweep);
+            }
+        }
+
+        let mut subset = VolumeData::new(volume.metadata.clone(), sweeps);
+        subset.calibration = volume.calibration.clone();
+        subset.history = volume.history.clone();
+        subset.reindex_sweeps();
+        let start = subset.sweeps.iter().filter_map(SweepData::time_start).min();
+        let end = subset.sweeps.iter().filter_map(SweepData::time_end).max();
+        if let (Some(start), Some(end)) = (start, end) {
+            subset.metadata.time_coverage_start = start;
+            subset.metadata.time_coverage_end = end;
+        }
+        Ok(subset)
+    }
+
+    /// Rays, gates and moments of a sweep passing the criteria
+    fn select(&self, sweep: &SweepData) -> SweepData {
+        let coords = &sweep.coordinates;
+        let rays: Vec<usize> = (0..sweep.num_rays())
+            .filter(|&i| {
+                self.sector.is_none_or(|(start, end)| {
+                    (coords.azimuth[i] - start).rem_euclid(360.0) <= (end - start).rem_euclid(360.0)
+                })
+            })
+            .collect();
+        let gates = match self.ranges {
+            Some((min, max)) => {
+                let inside = |r: &f32| (min..=max).contains(&(*r as f64));
+                let first = coords.range.iter().position(inside).unwrap_or(coords.range.len());
+                let last = coords.range.iter().rposition(inside).map_or(first, |j| j + 1);
+                first..last.max(first)
+            }
+            None => 0..coords.range.len(),
+        };
+
+        let mut sweep = sweep.select(&rays, gates);
+        if let Some(moments) = &self.moments {
+            let names: Vec<&str> = moments.iter().map(String::as_str).collect();
+            sweep.filter_moments(&names);
+        }
+        sweep
+    }
+}
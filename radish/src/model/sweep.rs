@@ -1,10 +1,15 @@
-/// Sweep-level data structures
+//! Sweep-level data structures
 
+use chrono::{DateTime, TimeZone, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::ops::Range;
 use radish_types::{SweepMode, FollowMode, PrtMode};
 
+use super::moment::MomentMetadata;
+use super::names::{NameAliases, ORIGINAL_NAME_ATTRIBUTE};
 use super::{MomentData, Coordinates};
+use super::coordinates::{azimuth_distance, nearest_range_gate};
 
 /// Sweep data containing moments and coordinates
 #[derive(Debug, Clone)]
@@ -15,6 +20,10 @@ pub struct SweepData {
     pub moments: HashMap<String, MomentData>,
     /// Coordinate data
     pub coordinates: Coordinates,
+    /// Per-ray instrument parameters, when the format stores them
+    pub ray_metadata: Option<RayMetadata>,
+    /// Per-ray attitude and velocity of a moving platform, when the format stores them
+    pub platform_motion: Option<PlatformMotion>,
 }
 
 impl SweepData {
@@ -28,6 +37,8 @@ impl SweepData {
             metadata,
             moments,
             coordinates,
+            ray_metadata: None,
+            platform_motion: None,
         }
     }
 
@@ -51,6 +62,48 @@ impl SweepData {
         self.moments.retain(|k, _| moment_names.contains(&k.as_str()));
     }
 
+    /// The given rays, in the given order, and a span of gates of this sweep
+    pub fn select(&self, rays: &[usize], gates: Range<usize>) -> Self {
+        Self {
+            metadata: self.metadata.clone(),
+            moments: self
+                .moments
+                .iter()
+                .map(|(name, m)| (name.clone(), m.select(rays, gates.clone())))
+                .collect(),
+            coordinates: self.coordinates.select(rays, gates),
+            ray_metadata: self.ray_metadata.as_ref().map(|r| r.select(rays)),
+            platform_motion: self.platform_motion.as_ref().map(|m| m.select(rays)),
+        }
+    }
+
+    /// Rename moments to their standard names
+    ///
+    /// Each renamed moment records its original name in the
+    /// [`ORIGINAL_NAME_ATTRIBUTE`] attribute and gets the standard and long
+    /// names of the standard moment if it has none. A moment is not renamed
+    /// when the sweep already has a moment of its standard name.
+    pub fn standardize_names(&mut self, aliases: &NameAliases) {
+        let mut names: Vec<String> = self.moments.keys().cloned().collect();
+        names.sort();
+        for name in names {
+            let Some(standard) = aliases.standard_name(&name) else {
+                continue;
+            };
+            if standard == name || self.moments.contains_key(standard) {
+                continue;
+            }
+            let mut moment = self.moments.remove(&name).unwrap();
+            moment.name = standard.to_string();
+            moment.attributes.insert(ORIGINAL_NAME_ATTRIBUTE.to_string(), name.into());
+            if let Some(meta) = MomentMetadata::from_name(standard) {
+                moment.standard_name.get_or_insert_with(|| meta.standard_name.to_string());
+                moment.long_name.get_or_insert_with(|| meta.long_name.to_string());
+            }
+            self.moments.insert(standard.to_string(), moment);
+        }
+    }
+
     /// Number of rays in this sweep
     pub fn num_rays(&self) -> usize {
         self.coordinates.azimuth.len()
@@ -60,6 +113,194 @@ impl SweepData {
     pub fn num_gates(&self) -> usize {
         self.coordinates.range.len()
     }
+
+    /// Index of the ray nearest to an azimuth (degrees), `None` without rays
+    pub fn nearest_ray(&self, azimuth: f32) -> Option<usize> {
+        self.coordinates
+            .azimuth
+            .iter()
+            .enumerate()
+            .min_by(|a, b| azimuth_distance(*a.1, azimuth).total_cmp(&azimuth_distance(*b.1, azimuth)))
+            .map(|(i, _)| i)
+    }
+
+    /// Index of the gate containing a slant range (meters)
+    ///
+    /// `None` for ranges more than half a gate before the first or after
+    /// the last gate.
+    pub fn nearest_gate(&self, range: f32) -> Option<usize> {
+        nearest_range_gate(&self.coordinates.range, range)
+    }
+
+    /// Time of the earliest ray
+    ///
+    /// Rays without a valid time are ignored; `None` if no ray has one.
+    pub fn time_start(&self) -> Option<DateTime<Utc>> {
+        self.time_bounds().and_then(|(start, _)| epoch_to_datetime(start))
+    }
+
+    /// Time of the latest ray
+    pub fn time_end(&self) -> Option<DateTime<Utc>> {
+        self.time_bounds().and_then(|(_, end)| epoch_to_datetime(end))
+    }
+
+    /// Midpoint of the sweep time coverage
+    ///
+    /// Products derived from a single sweep should be timestamped with this
+    /// rather than with the volume time coverage.
+    pub fn time_mid(&self) -> Option<DateTime<Utc>> {
+        self.time_bounds()
+            .and_then(|(start, end)| epoch_to_datetime((start + end) / 2.0))
+    }
+
+    /// Nyquist velocity (m/s) of a ray
+    ///
+    /// The per-ray value when it is present and finite, otherwise the sweep
+    /// value.
+    pub fn ray_nyquist_velocity(&self, ray: usize) -> Option<f64> {
+        self.ray_metadata
+            .as_ref()
+            .and_then(|r| r.nyquist_velocity.as_ref())
+            .and_then(|v| v.get(ray).copied())
+            .filter(|v| v.is_finite())
+            .or(self.metadata.nyquist_velocity)
+    }
+
+    /// Earliest and latest valid ray times (seconds since the epoch)
+    fn time_bounds(&self) -> Option<(f64, f64)> {
+        self.coordinates
+            .epoch_times()
+            .into_iter()
+            .filter(|t| t.is_finite())
+            .fold(None, |bounds, t| match bounds {
+                None => Some((t, t)),
+                Some((start, end)) => Some((start.min(t), end.max(t))),
+            })
+    }
+}
+
+fn epoch_to_datetime(seconds: f64) -> Option<DateTime<Utc>> {
+    Utc.timestamp_micros((seconds * 1e6).round() as i64).single()
+}
+
+/// Instrument parameters that vary from ray to ray
+///
+/// Each array, when present, has one value per ray of the sweep. Missing
+/// floating point values are NaN.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RayMetadata {
+    /// Nyquist velocity (m/s)
+    pub nyquist_velocity: Option<Vec<f64>>,
+    /// Pulse repetition time (seconds)
+    pub prt: Option<Vec<f64>>,
+    /// Pulse width (seconds)
+    pub pulse_width: Option<Vec<f64>>,
+    /// Number of samples used to compute the moments
+    pub n_samples: Option<Vec<i32>>,
+}
+
+impl RayMetadata {
+    /// Whether no parameter is present
+    pub fn is_empty(&self) -> bool {
+        self.nyquist_velocity.is_none() && self.prt.is_none() && self.pulse_width.is_none() && self.n_samples.is_none()
+    }
+
+    /// Parameters of the given rays, in the given order
+    pub fn select(&self, rays: &[usize]) -> Self {
+        fn pick<T: Copy>(values: &Option<Vec<T>>, rays: &[usize]) -> Option<Vec<T>> {
+            values.as_ref().map(|v| rays.iter().map(|&i| v[i]).collect())
+        }
+        Self {
+            nyquist_velocity: pick(&self.nyquist_velocity, rays),
+            prt: pick(&self.prt, rays),
+            pulse_width: pick(&self.pulse_width, rays),
+            n_samples: pick(&self.n_samples, rays),
+        }
+    }
+
+    /// Check that every array has `num_rays` values
+    pub fn validate(&self, num_rays: usize) -> Result<(), String> {
+        let lengths = [
+            ("Nyquist velocity", self.nyquist_velocity.as_ref().map(Vec::len)),
+            ("PRT", self.prt.as_ref().map(Vec::len)),
+            ("Pulse width", self.pulse_width.as_ref().map(Vec::len)),
+            ("Sample count", self.n_samples.as_ref().map(Vec::len)),
+        ];
+        for (name, len) in lengths {
+            if let Some(len) = len.filter(|&len| len != num_rays) {
+                return Err(format!("{} length ({}) doesn't match number of rays ({})", name, len, num_rays));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Per-ray attitude and velocity of a moving platform
+///
+/// For radars on ships, vehicles and aircraft. Each array, when present,
+/// has one value per ray of the sweep; missing values are NaN. Angles follow
+/// the CfRadial conventions: heading clockwise from true north, roll positive
+/// with the starboard (right) side down, pitch positive with the bow (nose)
+/// up, and drift the angle from the heading to the track, positive clockwise.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PlatformMotion {
+    /// Heading (degrees)
+    pub heading: Option<Vec<f64>>,
+    /// Roll (degrees)
+    pub roll: Option<Vec<f64>>,
+    /// Pitch (degrees)
+    pub pitch: Option<Vec<f64>>,
+    /// Drift (degrees)
+    pub drift: Option<Vec<f64>>,
+    /// Eastward platform velocity (m/s)
+    pub velocity_east: Option<Vec<f64>>,
+    /// Northward platform velocity (m/s)
+    pub velocity_north: Option<Vec<f64>>,
+    /// Upward platform velocity (m/s)
+    pub velocity_up: Option<Vec<f64>>,
+}
+
+impl PlatformMotion {
+    /// Whether no value is present
+    pub fn is_empty(&self) -> bool {
+        self.arrays().iter().all(|(_, values)| values.is_none())
+    }
+
+    /// Motion of the given rays, in the given order
+    pub fn select(&self, rays: &[usize]) -> Self {
+        let pick = |values: &Option<Vec<f64>>| values.as_ref().map(|v| rays.iter().map(|&i| v[i]).collect());
+        Self {
+            heading: pick(&self.heading),
+            roll: pick(&self.roll),
+            pitch: pick(&self.pitch),
+            drift: pick(&self.drift),
+            velocity_east: pick(&self.velocity_east),
+            velocity_north: pick(&self.velocity_north),
+            velocity_up: pick(&self.velocity_up),
+        }
+    }
+
+    /// Check that every array has `num_rays` values
+    pub fn validate(&self, num_rays: usize) -> Result<(), String> {
+        for (name, values) in self.arrays() {
+            if let Some(len) = values.map(Vec::len).filter(|&len| len != num_rays) {
+                return Err(format!("{} length ({}) doesn't match number of rays ({})", name, len, num_rays));
+            }
+        }
+        Ok(())
+    }
+
+    fn arrays(&self) -> [(&'static str, Option<&Vec<f64>>); 7] {
+        [
+            ("Heading", self.heading.as_ref()),
+            ("Roll", self.roll.as_ref()),
+            ("Pitch", self.pitch.as_ref()),
+            ("Drift", self.drift.as_ref()),
+            ("Eastward velocity", self.velocity_east.as_ref()),
+            ("Northward velocity", self.velocity_north.as_ref()),
+            ("Upward velocity", self.velocity_up.as_ref()),
+        ]
+    }
 }
 
 /// Metadata for a single sweep
@@ -1,10 +1,13 @@
-/// Volume-level data structures
+//! Volume-level data structures
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
-use radish_types::PlatformType;
+use radish_types::{PlatformType, SweepMode};
 
-use super::{SweepData, SweepMetadata};
+use super::coordinates::azimuth_distance;
+use super::geometry::{geographic_to_cartesian, ground_to_antenna, GeoreferenceOptions};
+use super::{Attributes, History, NameAliases, ScanStrategy, SweepData, SweepMetadata, VolumeSubset};
+use crate::{RadishError, Result};
 
 /// Complete radar volume data
 #[derive(Debug, Clone)]
@@ -15,6 +18,8 @@ pub struct VolumeData {
     pub sweeps: Vec<SweepData>,
     /// Optional radar calibration data
     pub calibration: Option<RadarCalibration>,
+    /// Processing steps applied to the volume
+    pub history: History,
 }
 
 impl VolumeData {
@@ -24,6 +29,7 @@ impl VolumeData {
             metadata,
             sweeps,
             calibration: None,
+            history: History::default(),
         }
     }
 
@@ -37,12 +43,178 @@ impl VolumeData {
         self.sweeps.len()
     }
 
+    /// Rebuild sweep numbers, group names and fixed angles after sweeps
+    /// have been added, removed or reordered
+    pub fn reindex_sweeps(&mut self) {
+        for (i, sweep) in self.sweeps.iter_mut().enumerate() {
+            sweep.metadata.sweep_number = i as u32;
+        }
+        self.metadata.generate_sweep_names(self.sweeps.len());
+        self.metadata.sweep_fixed_angles = self
+            .sweeps
+            .iter()
+            .map(|s| s.metadata.fixed_angle)
+            .collect();
+    }
+
+    /// Sort sweeps by increasing fixed angle and renumber them
+    ///
+    /// The sort is stable, so repeated elevations keep their volume order;
+    /// sweeps with a NaN fixed angle go last. Remove repeated elevations with
+    /// [`deduplicate_sweeps`](crate::transforms::sweep_merge::deduplicate_sweeps).
+    pub fn sort_sweeps_by_angle(&mut self) {
+        self.sweeps.sort_by(|a, b| {
+            let (a, b) = (a.metadata.fixed_angle, b.metadata.fixed_angle);
+            a.is_nan().cmp(&b.is_nan()).then(a.total_cmp(&b))
+        });
+        self.reindex_sweeps();
+    }
+
     /// Filter moments across all sweeps
     pub fn filter_moments(&mut self, moment_names: &[&str]) {
         for sweep in &mut self.sweeps {
             sweep.filter_moments(moment_names);
         }
     }
+
+    /// Select sweeps, ranges, an azimuth sector and moments into a new volume
+    ///
+    /// See [`VolumeSubset`].
+    pub fn subset(&self) -> VolumeSubset<'_> {
+        VolumeSubset::new(self)
+    }
+
+    /// Add the sweeps of another volume of the same radar, with default limits
+    ///
+    /// See [`Self::merge_with_options`].
+    pub fn merge(&mut self, other: VolumeData) -> Result<()> {
+        self.merge_with_options(other, &VolumeMergeOptions::default())
+    }
+
+    /// Add the sweeps of another volume of the same radar
+    ///
+    /// For radars writing one file per sweep or per group of sweeps. The
+    /// volumes must have the same instrument name, nearby radar locations
+    /// and overlapping or close time coverages. Sweeps are sorted by start
+    /// time, sweeps without times last, and renumbered; the time coverage
+    /// spans both volumes. Metadata, calibration and attributes missing from
+    /// this volume are taken from `other`, and the processing histories are
+    /// combined.
+    pub fn merge_with_options(&mut self, other: VolumeData, options: &VolumeMergeOptions) -> Result<()> {
+        let (a, b) = (&self.metadata, &other.metadata);
+        let incompatible = |reason: String| RadishError::General(format!("Cannot merge volumes: {}", reason));
+        if a.instrument_name != b.instrument_name {
+            return Err(incompatible(format!(
+                "instrument '{}' differs from '{}'",
+                b.instrument_name, a.instrument_name
+            )));
+        }
+        let (x, y) = geographic_to_cartesian(b.longitude, b.latitude, a.longitude, a.latitude);
+        let distance = x.hypot(y);
+        if distance.is_nan() || distance > options.max_site_distance {
+            return Err(incompatible(format!("radar locations are {:.0} m apart", distance)));
+        }
+        let climb = (a.altitude - b.altitude).abs();
+        if climb.is_nan() || climb > options.max_altitude_difference {
+            return Err(incompatible(format!(
+                "radar altitudes {} m and {} m differ",
+                a.altitude, b.altitude
+            )));
+        }
+        let gap = (b.time_coverage_start - a.time_coverage_end).max(a.time_coverage_start - b.time_coverage_end);
+        if gap > options.max_time_gap {
+            return Err(incompatible(format!("time coverages are {} s apart", gap.num_seconds())));
+        }
+
+        let VolumeData { metadata, sweeps, calibration, history } = other;
+        let meta = &mut self.metadata;
+        meta.time_coverage_start = meta.time_coverage_start.min(metadata.time_coverage_start);
+        meta.time_coverage_end = meta.time_coverage_end.max(metadata.time_coverage_end);
+        if meta.institution.is_empty() {
+            meta.institution = metadata.institution;
+        }
+        meta.platform_type = meta.platform_type.or(metadata.platform_type);
+        meta.site_name = meta.site_name.take().or(metadata.site_name);
+        meta.altitude_agl = meta.altitude_agl.or(metadata.altitude_agl);
+        meta.frequency = meta.frequency.or(metadata.frequency);
+        meta.instrument_parameters = meta.instrument_parameters.take().or(metadata.instrument_parameters);
+        meta.scan_strategy = meta.scan_strategy.take().or(metadata.scan_strategy);
+        for (key, value) in metadata.attributes {
+            meta.attributes.entry(key).or_insert(value);
+        }
+        self.calibration = self.calibration.take().or(calibration);
+        self.history.extend(history);
+
+        self.sweeps.extend(sweeps);
+        // Stable sort: sweeps starting together keep their order
+        self.sweeps.sort_by_key(|s| s.time_start().map_or((1, None), |t| (0, Some(t))));
+        self.reindex_sweeps();
+        Ok(())
+    }
+
+    /// Rename moments of every sweep to their standard names
+    ///
+    /// See [`SweepData::standardize_names`].
+    pub fn standardize_names(&mut self, aliases: &NameAliases) {
+        for sweep in &mut self.sweeps {
+            sweep.standardize_names(aliases);
+        }
+    }
+
+    /// Value of a moment at a point, from the gate nearest to it
+    ///
+    /// The point (degrees, meters above sea level) is sampled by the PPI
+    /// sweep holding `moment` whose beam passes closest to its altitude
+    /// under the 4/3 earth model. `None` when no such sweep reaches the
+    /// point, the nearest ray is more than a degree away from it, or the gate
+    /// is missing.
+    pub fn value_at(&self, latitude: f64, longitude: f64, altitude: f64, moment: &str) -> Option<f32> {
+        let meta = &self.metadata;
+        let (x, y) = geographic_to_cartesian(longitude, latitude, meta.longitude, meta.latitude);
+        let distance = (x * x + y * y).sqrt();
+        let azimuth = x.atan2(y).to_degrees().rem_euclid(360.0) as f32;
+        let options = GeoreferenceOptions::default();
+
+        let (sweep, gate, _) = self
+            .sweeps
+            .iter()
+            .filter(|s| {
+                matches!(s.metadata.sweep_mode, SweepMode::Azimuth | SweepMode::Sector | SweepMode::ManualPpi)
+                    && s.get_moment(moment).is_some()
+            })
+            .filter_map(|s| {
+                let (range, height) = ground_to_antenna(distance, s.metadata.fixed_angle, &options);
+                let gate = s.nearest_gate(range as f32)?;
+                Some((s, gate, (meta.altitude + height - altitude).abs()))
+            })
+            .min_by(|a, b| a.2.total_cmp(&b.2))?;
+        let ray = sweep.nearest_ray(azimuth)?;
+        if azimuth_distance(sweep.coordinates.azimuth[ray], azimuth) > 1.0 {
+            return None;
+        }
+        sweep.moments[moment].valid_value(ray, gate)
+    }
+}
+
+/// Limits on the volumes [`VolumeData::merge_with_options`] combines
+#[derive(Debug, Clone)]
+pub struct VolumeMergeOptions {
+    /// Largest horizontal distance between the radar locations (meters)
+    pub max_site_distance: f64,
+    /// Largest difference between the radar altitudes (meters)
+    pub max_altitude_difference: f64,
+    /// Largest gap between the time coverages of the volumes
+    pub max_time_gap: Duration,
+}
+
+impl Default for VolumeMergeOptions {
+    fn default() -> Self {
+        Self {
+            max_site_distance: 100.0,
+            max_altitude_difference: 50.0,
+            max_time_gap: Duration::minutes(15),
+        }
+    }
 }
 
 /// Metadata for a radar volume
@@ -90,8 +262,14 @@ pub struct VolumeMetadata {
     /// Radar frequency (Hz)
     pub frequency: Option<f64>,
 
-    /// Additional attributes
-    pub attributes: std::collections::HashMap<String, String>,
+    /// Antenna and receiver characteristics
+    pub instrument_parameters: Option<InstrumentParameters>,
+
+    /// Scan strategy the volume was collected with
+    pub scan_strategy: Option<ScanStrategy>,
+
+    /// Additional attributes, as read from the file
+    pub attributes: Attributes,
 }
 
 impl VolumeMetadata {
@@ -119,7 +297,9 @@ impl VolumeMetadata {
             sweep_group_names: Vec::new(),
             sweep_fixed_angles: Vec::new(),
             frequency: None,
-            attributes: std::collections::HashMap::new(),
+            instrument_parameters: None,
+            scan_strategy: None,
+            attributes: Attributes::new(),
         }
     }
 
@@ -131,6 +311,44 @@ impl VolumeMetadata {
     }
 }
 
+/// Antenna and receiver characteristics of a radar
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct InstrumentParameters {
+    /// Half-power beam width (horizontal, degrees)
+    pub beam_width_h: Option<f64>,
+
+    /// Half-power beam width (vertical, degrees)
+    pub beam_width_v: Option<f64>,
+
+    /// Antenna gain (horizontal, dB)
+    pub antenna_gain_h: Option<f64>,
+
+    /// Antenna gain (vertical, dB)
+    pub antenna_gain_v: Option<f64>,
+
+    /// Radar constant (horizontal, dB), relating received power in dBm to
+    /// reflectivity as `dBZ = P + C + 20·log10(r / 1 km)`
+    pub radar_constant_h: Option<f64>,
+
+    /// Radar constant (vertical, dB)
+    pub radar_constant_v: Option<f64>,
+
+    /// Receiver bandwidth (Hz)
+    pub receiver_bandwidth: Option<f64>,
+}
+
+impl InstrumentParameters {
+    /// Whether no parameter is present
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Beam width (degrees), the horizontal one if known
+    pub fn beam_width(&self) -> Option<f64> {
+        self.beam_width_h.or(self.beam_width_v)
+    }
+}
+
 /// Radar calibration data
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RadarCalibration {
@@ -0,0 +1,324 @@
+//! Precipitation accumulation over a time series of rate grids
+//!
+//! Rain rate grids (mm/h) from successive volumes are integrated in time
+//! between scans. The rate over each interval between two scans is taken
+//! from the earlier scan, the later scan or their mean, and intervals longer
+//! than the allowed gap are either skipped or rejected. Accumulations are
+//! produced for fixed periods aligned to the epoch (e.g. hourly, on the
+//! hour) or as a single total over the whole series.
+//!
+//! With advection correction, the motion of the echoes between two scans is
+//! estimated and the interval is integrated over fields morphed at regular
+//! time steps, instead of over a rate held fixed in place; see
+//! `transforms::advection`.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, TimeZone, Utc};
+use ndarray::{Array2, Axis};
+
+use crate::transforms::advection::{grid_spacing, morph, motion_cells, AdvectionOptions};
+use crate::transforms::georeference::Projection;
+use crate::transforms::gridding::{grid_volume, GridOptions};
+use crate::transforms::qpe::RAIN_RATE;
+use crate::{GridField, GriddedData, RadishError, Result, VolumeData};
+
+/// Name of the accumulation field (mm)
+pub const ACCUMULATION: &str = "ACCUMULATION";
+
+/// Name of the field holding the time (seconds) with valid rates in the period
+pub const ACCUMULATION_DURATION: &str = "ACCUMULATION_DURATION";
+
+/// Rate used over the interval between two scans
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimeWeighting {
+    /// Rate of the earlier scan, held until the next one
+    Previous,
+    /// Rate of the later scan, representative of the preceding interval
+    Next,
+    /// Mean of the two rates (the valid one if only one is valid)
+    #[default]
+    Trapezoidal,
+}
+
+/// Handling of intervals longer than `AccumulationOptions::max_gap`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GapPolicy {
+    /// The interval contributes nothing and shows in the duration field
+    #[default]
+    Skip,
+    /// Accumulation fails
+    Error,
+}
+
+/// Options for precipitation accumulation
+#[derive(Debug, Clone)]
+pub struct AccumulationOptions {
+    /// Rate field (mm/h)
+    pub field: String,
+    /// Accumulation period (seconds), aligned to the epoch; one total over the series if `None`
+    pub period: Option<i64>,
+    /// Rate over each interval between scans
+    pub weighting: TimeWeighting,
+    /// Longest interval (seconds) between scans that is integrated
+    pub max_gap: f64,
+    /// Handling of longer intervals
+    pub gap_policy: GapPolicy,
+    /// Advection correction between scans (replaces `weighting`); none if `None`
+    pub advection: Option<AdvectionOptions>,
+}
+
+impl Default for AccumulationOptions {
+    fn default() -> Self {
+        Self {
+            field: RAIN_RATE.to_string(),
+            period: Some(3600),
+            weighting: TimeWeighting::default(),
+            max_gap: 900.0,
+            gap_policy: GapPolicy::default(),
+            advection: None,
+        }
+    }
+}
+
+/// Accumulation of one period
+#[derive(Debug, Clone)]
+struct PeriodSum {
+    start: f64,
+    end: f64,
+    total: Array2<f64>,
+    duration: Array2<f64>,
+}
+
+/// Time integration of a series of rate grids
+///
+/// Grids are added in time order with [`Accumulator::add`] and the
+/// accumulations are returned by [`Accumulator::finish`].
+#[derive(Debug, Clone)]
+pub struct Accumulator {
+    options: AccumulationOptions,
+    grid: Option<(Projection, Vec<f64>, Vec<f64>)>,
+    previous: Option<(f64, Array2<f32>)>,
+    periods: BTreeMap<i64, PeriodSum>,
+}
+
+impl Accumulator {
+    /// Create an empty accumulator
+    pub fn new(options: AccumulationOptions) -> Self {
+        Self {
+            options,
+            grid: None,
+            previous: None,
+            periods: BTreeMap::new(),
+        }
+    }
+
+    /// Add the next rate grid of the series
+    ///
+    /// All grids must share the projection and x/y axes of the first one and
+    /// be added in increasing time order. Only the first level is used.
+    pub fn add(&mut self, grid: &GriddedData) -> Result<()> {
+        let field = grid
+            .get_field(&self.options.field)
+            .ok_or_else(|| RadishError::MissingVariable(self.options.field.clone()))?;
+        match &self.grid {
+            Some((projection, x, y)) => {
+                if *projection != grid.projection || *x != grid.x || *y != grid.y {
+                    return Err(RadishError::InvalidFormat(
+                        "Rate grids of an accumulation must share the same grid".to_string(),
+                    ));
+                }
+            }
+            None => self.grid = Some((grid.projection, grid.x.clone(), grid.y.clone())),
+        }
+
+        let time = grid.time.timestamp_micros() as f64 / 1e6;
+        let rate = field
+            .data
+            .index_axis(Axis(0), 0)
+            .mapv(|v| if field.is_valid_value(v) { v } else { f32::NAN });
+
+        if let Some((previous_time, previous_rate)) = self.previous.take() {
+            let dt = time - previous_time;
+            if dt <= 0.0 {
+                return Err(RadishError::General(format!(
+                    "Rate grids must be added in time order ({} after {})",
+                    grid.time,
+                    timestamp(previous_time)
+                )));
+            }
+            if dt > self.options.max_gap {
+                if self.options.gap_policy == GapPolicy::Error {
+                    return Err(RadishError::General(format!(
+                        "Gap of {:.0} s before {} exceeds {:.0} s",
+                        dt, grid.time, self.options.max_gap
+                    )));
+                }
+            } else if let Some(advection) = self.options.advection.clone() {
+                self.integrate_advected(previous_time, time, &previous_rate, &rate, &advection);
+            } else {
+                let interval_rate = interval_rate(&previous_rate, &rate, self.options.weighting);
+                self.integrate(previous_time, time, &interval_rate);
+            }
+        }
+
+        self.previous = Some((time, rate));
+        Ok(())
+    }
+
+    /// Accumulation grids, one per period touched by the series, in time order
+    ///
+    /// Each grid holds `ACCUMULATION` (mm) and `ACCUMULATION_DURATION`
+    /// (seconds of valid rates); its time is the end of the period. Cells
+    /// without any valid rate in the period are NaN.
+    pub fn finish(self) -> Result<Vec<GriddedData>> {
+        let Some((projection, x, y)) = self.grid else {
+            return Ok(Vec::new());
+        };
+
+        self.periods
+            .into_values()
+            .map(|period| {
+                let mut grid = GriddedData::new(timestamp(period.end), projection, x.clone(), y.clone(), vec![0.0]);
+                grid.attributes
+                    .insert("accumulation_start".to_string(), timestamp(period.start).to_rfc3339());
+                grid.attributes
+                    .insert("accumulation_end".to_string(), timestamp(period.end).to_rfc3339());
+
+                let total = ndarray::Zip::from(&period.total)
+                    .and(&period.duration)
+                    .map_collect(|&t, &d| if d > 0.0 { t as f32 } else { f32::NAN });
+                let mut field = GridField::new(ACCUMULATION.to_string(), "mm".to_string(), total.insert_axis(Axis(0)));
+                field.standard_name = Some("lwe_thickness_of_precipitation_amount".to_string());
+                field.long_name = Some("Precipitation accumulation".to_string());
+                grid.add_field(field)?;
+
+                let duration = period.duration.mapv(|d| d as f32);
+                let mut field =
+                    GridField::new(ACCUMULATION_DURATION.to_string(), "s".to_string(), duration.insert_axis(Axis(0)));
+                field.long_name = Some("Time with valid rates in the accumulation period".to_string());
+                grid.add_field(field)?;
+                Ok(grid)
+            })
+            .collect()
+    }
+
+    /// Integrate `[start, end)` over fields morphed between two scans at regular time steps
+    fn integrate_advected(
+        &mut self,
+        start: f64,
+        end: f64,
+        previous: &Array2<f32>,
+        next: &Array2<f32>,
+        options: &AdvectionOptions,
+    ) {
+        let (dx, dy) = match &self.grid {
+            Some((projection, x, y)) => grid_spacing(*projection, x, y),
+            None => return,
+        };
+        let dt = end - start;
+        let (shift_x, shift_y) = motion_cells(previous.view(), next.view(), dt, dx, dy, options);
+
+        let steps = (dt / options.time_step).ceil().max(1.0) as usize;
+        let step = dt / steps as f64;
+        for k in 0..steps {
+            let alpha = ((k as f64 + 0.5) / steps as f64) as f32;
+            let rate = morph(previous, next, &shift_x, &shift_y, alpha);
+            self.integrate(start + k as f64 * step, start + (k + 1) as f64 * step, &rate);
+        }
+    }
+
+    /// Add a rate (mm/h) held over `[start, end)` to the periods it overlaps
+    fn integrate(&mut self, start: f64, end: f64, rate: &Array2<f32>) {
+        let mut t = start;
+        while t < end {
+            let (key, period_start, period_end) = match self.options.period {
+                Some(period) => {
+                    let key = (t / period as f64).floor() as i64;
+                    (key, (key * period) as f64, ((key + 1) * period) as f64)
+                }
+                None => (0, start, end),
+            };
+            let part_end = end.min(period_end);
+            let dt = part_end - t;
+
+            let sum = self.periods.entry(key).or_insert_with(|| PeriodSum {
+                start: period_start,
+                end: period_end,
+                total: Array2::zeros(rate.dim()),
+                duration: Array2::zeros(rate.dim()),
+            });
+            if self.options.period.is_none() {
+                sum.end = part_end;
+            }
+            ndarray::Zip::from(&mut sum.total)
+                .and(&mut sum.duration)
+                .and(rate)
+                .for_each(|total, duration, &r| {
+                    if !r.is_nan() {
+                        *total += r as f64 * dt / 3600.0;
+                        *duration += dt;
+                    }
+                });
+            t = part_end;
+        }
+    }
+}
+
+/// Accumulate a series of rate grids (sorted by time first)
+pub fn accumulate(grids: &[GriddedData], options: &AccumulationOptions) -> Result<Vec<GriddedData>> {
+    let mut order: Vec<&GriddedData> = grids.iter().collect();
+    order.sort_by_key(|g| g.time);
+
+    let mut accumulator = Accumulator::new(options.clone());
+    for grid in order {
+        accumulator.add(grid)?;
+    }
+    accumulator.finish()
+}
+
+/// Grid the rate moment of each volume and accumulate the grids
+///
+/// `grid_options.projection` must be set so that all volumes share the grid.
+pub fn accumulate_volumes(
+    volumes: &[VolumeData],
+    grid_options: &GridOptions,
+    options: &AccumulationOptions,
+) -> Result<Vec<GriddedData>> {
+    if grid_options.projection.is_none() {
+        return Err(RadishError::General(
+            "Accumulation needs a common grid projection".to_string(),
+        ));
+    }
+    let grid_options = GridOptions {
+        moments: vec![options.field.clone()],
+        ..grid_options.clone()
+    };
+    let grids = volumes
+        .iter()
+        .map(|v| grid_volume(v, &grid_options))
+        .collect::<Result<Vec<_>>>()?;
+    accumulate(&grids, options)
+}
+
+/// Rate (mm/h) held over the interval between two scans
+fn interval_rate(previous: &Array2<f32>, next: &Array2<f32>, weighting: TimeWeighting) -> Array2<f32> {
+    match weighting {
+        TimeWeighting::Previous => previous.clone(),
+        TimeWeighting::Next => next.clone(),
+        TimeWeighting::Trapezoidal => ndarray::Zip::from(previous).and(next).map_collect(|&a, &b| {
+            match (a.is_nan(), b.is_nan()) {
+                (false, false) => (a + b) / 2.0,
+                (false, true) => a,
+                (true, false) => b,
+                (true, true) => f32::NAN,
+            }
+        }),
+    }
+}
+
+fn timestamp(seconds: f64) -> DateTime<Utc> {
+    Utc.timestamp_micros((seconds * 1e6).round() as i64)
+        .single()
+        .unwrap_or_default()
+}
@@ -0,0 +1,246 @@
+//! Echo motion estimation and advection of fields between scans
+//!
+//! Motion is estimated by cross-correlation: the displacement (in whole
+//! grid cells) maximizing the normalized correlation between the earlier
+//! field and the shifted later field is searched within the distance a
+//! `max_speed` echo travels between the scans, over the whole grid or over
+//! blocks of cells. Intermediate fields are then obtained by morphing: each
+//! scan is advected toward the intermediate time and the two are blended
+//! linearly in time, which removes the "spokes" left in accumulations by
+//! echoes that move several cells between scans.
+
+use ndarray::{Array2, ArrayView2, Axis};
+
+use crate::transforms::georeference::Projection;
+use crate::transforms::qpe::RAIN_RATE;
+use crate::writers::motion::{MOTION_U, MOTION_V};
+use crate::{GridField, GriddedData, RadishError, Result};
+
+/// Meters per degree of latitude on the mean Earth sphere
+const METERS_PER_DEGREE: f64 = 111_195.0;
+
+/// Options for motion estimation
+#[derive(Debug, Clone)]
+pub struct AdvectionOptions {
+    /// Field tracked by `estimate_motion` (accumulations track their rate field)
+    pub field: String,
+    /// Largest echo speed (m/s) searched
+    pub max_speed: f64,
+    /// Size (cells) of the square blocks with their own motion; one motion for the grid if `None`
+    pub block_size: Option<usize>,
+    /// Values above this count as echo
+    pub echo_threshold: f32,
+    /// Blocks with a smaller fraction of echo cells take the motion of the whole grid
+    pub min_echo_fraction: f64,
+    /// Interval (seconds) between the morphed fields integrated by accumulations
+    pub time_step: f64,
+}
+
+impl Default for AdvectionOptions {
+    fn default() -> Self {
+        Self {
+            field: RAIN_RATE.to_string(),
+            max_speed: 40.0,
+            block_size: None,
+            echo_threshold: 0.1,
+            min_echo_fraction: 0.05,
+            time_step: 60.0,
+        }
+    }
+}
+
+/// Motion between two grids of a field, as `MOTION_U`/`MOTION_V` fields (m/s)
+///
+/// Both grids must share the projection and x/y axes; only the first level
+/// is used. The result is timed at `later` and can be written with
+/// `writers::write_motion_netcdf`.
+pub fn estimate_motion(earlier: &GriddedData, later: &GriddedData, options: &AdvectionOptions) -> Result<GriddedData> {
+    if earlier.projection != later.projection || earlier.x != later.x || earlier.y != later.y {
+        return Err(RadishError::InvalidFormat(
+            "Motion can only be estimated between grids on the same grid".to_string(),
+        ));
+    }
+    let dt = (later.time - earlier.time).num_milliseconds() as f64 / 1000.0;
+    if dt <= 0.0 {
+        return Err(RadishError::General("The later grid must be later than the earlier one".to_string()));
+    }
+    let level = |grid: &GriddedData| -> Result<Array2<f32>> {
+        let field = grid
+            .get_field(&options.field)
+            .ok_or_else(|| RadishError::MissingVariable(options.field.clone()))?;
+        Ok(field
+            .data
+            .index_axis(Axis(0), 0)
+            .mapv(|v| if field.is_valid_value(v) { v } else { f32::NAN }))
+    };
+    let (a, b) = (level(earlier)?, level(later)?);
+
+    let (dx, dy) = grid_spacing(earlier.projection, &earlier.x, &earlier.y);
+    let (shift_x, shift_y) = motion_cells(a.view(), b.view(), dt, dx, dy, options);
+
+    let mut grid = GriddedData::new(
+        later.time,
+        later.projection,
+        later.x.clone(),
+        later.y.clone(),
+        vec![0.0],
+    );
+    grid.attributes
+        .insert("motion_interval".to_string(), dt.to_string());
+    for (name, shift, spacing, axis) in [(MOTION_U, shift_x, dx, "x"), (MOTION_V, shift_y, dy, "y")] {
+        let speed = shift.mapv(|s| (s as f64 * spacing / dt) as f32);
+        let mut field = GridField::new(name.to_string(), "m/s".to_string(), speed.insert_axis(Axis(0)));
+        field.long_name = Some(format!("Echo motion along the grid {} axis", axis));
+        grid.add_field(field)?;
+    }
+    Ok(grid)
+}
+
+/// Displacement (cells) of each cell between two fields `dt` seconds apart
+///
+/// `dx` and `dy` are the grid spacings in meters. NaN counts as no echo.
+pub fn motion_cells(
+    earlier: ArrayView2<f32>,
+    later: ArrayView2<f32>,
+    dt: f64,
+    dx: f64,
+    dy: f64,
+    options: &AdvectionOptions,
+) -> (Array2<f32>, Array2<f32>) {
+    let (ny, nx) = earlier.dim();
+    let max_x = (options.max_speed * dt / dx.abs()).ceil() as isize;
+    let max_y = (options.max_speed * dt / dy.abs()).ceil() as isize;
+    let search = |y0: usize, y1: usize, x0: usize, x1: usize, min_echo_fraction: f64| {
+        best_shift(earlier, later, (y0, y1, x0, x1), (max_x, max_y), options.echo_threshold, min_echo_fraction)
+    };
+
+    let global = search(0, ny, 0, nx, 0.0).unwrap_or((0, 0));
+    let mut shift_x = Array2::from_elem((ny, nx), global.0 as f32);
+    let mut shift_y = Array2::from_elem((ny, nx), global.1 as f32);
+
+    if let Some(block) = options.block_size.filter(|&b| b > 0) {
+        for y0 in (0..ny).step_by(block) {
+            for x0 in (0..nx).step_by(block) {
+                let (y1, x1) = ((y0 + block).min(ny), (x0 + block).min(nx));
+                if let Some((sx, sy)) = search(y0, y1, x0, x1, options.min_echo_fraction) {
+                    shift_x.slice_mut(ndarray::s![y0..y1, x0..x1]).fill(sx as f32);
+                    shift_y.slice_mut(ndarray::s![y0..y1, x0..x1]).fill(sy as f32);
+                }
+            }
+        }
+    }
+    (shift_x, shift_y)
+}
+
+/// Field at fraction `alpha` (0-1) of the interval between two scans
+///
+/// `shift_x`/`shift_y` are the displacements (cells) over the whole
+/// interval. The earlier field is advected forward by `alpha` of the
+/// displacement and the later one backward by the rest, and the two are
+/// blended with weights `1 - alpha` and `alpha`; where only one of them is
+/// valid it is used alone.
+pub fn morph(
+    earlier: &Array2<f32>,
+    later: &Array2<f32>,
+    shift_x: &Array2<f32>,
+    shift_y: &Array2<f32>,
+    alpha: f32,
+) -> Array2<f32> {
+    let (ny, nx) = earlier.dim();
+    let sample = |field: &Array2<f32>, y: f32, x: f32| {
+        let (y, x) = (y.round(), x.round());
+        if y < 0.0 || x < 0.0 || y >= ny as f32 || x >= nx as f32 {
+            f32::NAN
+        } else {
+            field[[y as usize, x as usize]]
+        }
+    };
+
+    Array2::from_shape_fn((ny, nx), |(j, i)| {
+        let (sx, sy) = (shift_x[[j, i]], shift_y[[j, i]]);
+        let a = sample(earlier, j as f32 - alpha * sy, i as f32 - alpha * sx);
+        let b = sample(later, j as f32 + (1.0 - alpha) * sy, i as f32 + (1.0 - alpha) * sx);
+        match (a.is_nan(), b.is_nan()) {
+            (false, false) => (1.0 - alpha) * a + alpha * b,
+            (false, true) => a,
+            (true, false) => b,
+            (true, true) => f32::NAN,
+        }
+    })
+}
+
+/// Grid spacing (meters) along x and y
+pub(crate) fn grid_spacing(projection: Projection, x: &[f64], y: &[f64]) -> (f64, f64) {
+    let step = |axis: &[f64]| {
+        if axis.len() > 1 {
+            (axis[axis.len() - 1] - axis[0]) / (axis.len() - 1) as f64
+        } else {
+            1.0
+        }
+    };
+    let (dx, dy) = (step(x), step(y));
+    match projection {
+        Projection::Geographic => {
+            let lat = y.get(y.len() / 2).copied().unwrap_or(0.0);
+            (dx * METERS_PER_DEGREE * lat.to_radians().cos(), dy * METERS_PER_DEGREE)
+        }
+        _ => (dx, dy),
+    }
+}
+
+/// Shift maximizing the normalized correlation of a block of `earlier` with `later`
+///
+/// Shifts are tried in order of increasing length so that ties go to the
+/// smallest displacement. `None` if the block has no echo or a smaller
+/// fraction of echo cells than `min_echo_fraction`.
+fn best_shift(
+    earlier: ArrayView2<f32>,
+    later: ArrayView2<f32>,
+    (y0, y1, x0, x1): (usize, usize, usize, usize),
+    (max_x, max_y): (isize, isize),
+    echo_threshold: f32,
+    min_echo_fraction: f64,
+) -> Option<(isize, isize)> {
+    let value = |v: f32| if v.is_nan() { 0.0 } else { v as f64 };
+    let block = earlier.slice(ndarray::s![y0..y1, x0..x1]);
+    let echoes = block.iter().filter(|&&v| v > echo_threshold).count();
+    if echoes == 0 || (echoes as f64) < min_echo_fraction * block.len() as f64 {
+        return None;
+    }
+
+    let (ny, nx) = later.dim();
+    let mut shifts: Vec<(isize, isize)> = (-max_y..=max_y)
+        .flat_map(|sy| (-max_x..=max_x).map(move |sx| (sx, sy)))
+        .collect();
+    shifts.sort_by_key(|&(sx, sy)| (sx * sx + sy * sy, sy, sx));
+
+    let mut best: Option<((isize, isize), f64)> = None;
+    for (sx, sy) in shifts {
+        let (mut ab, mut aa, mut bb) = (0.0, 0.0, 0.0);
+        for j in y0..y1 {
+            let jj = j as isize + sy;
+            if jj < 0 || jj >= ny as isize {
+                continue;
+            }
+            for i in x0..x1 {
+                let ii = i as isize + sx;
+                if ii < 0 || ii >= nx as isize {
+                    continue;
+                }
+                let a = value(earlier[[j, i]]);
+                let b = value(later[[jj as usize, ii as usize]]);
+                ab += a * b;
+                aa += a * a;
+                bb += b * b;
+            }
+        }
+        if aa == 0.0 || bb == 0.0 {
+            continue;
+        }
+        let score = ab / (aa * bb).sqrt();
+        if best.is_none_or(|(_, s)| score > s) {
+            best = Some(((sx, sy), score));
+        }
+    }
+    best.map(|(shift, _)| shift)
+}
@@ -0,0 +1,353 @@
+//! Specific attenuation retrieval with the ZPHI method
+//!
+//! Along each ray, the rain path between the first and last rain gates
+//! `r1` and `r2` attenuates the signal by a total two-way amount
+//! proportional to the differential phase shift `ΔΦ = Φdp(r2) - Φdp(r1)`.
+//! Testud et al. (2000) distribute it along the path according to the
+//! measured reflectivity:
+//!
+//! `A(r) = Za(r)^b · C / (I(r1, r2) + C · I(r, r2))`,
+//! `C = 10^(0.1·b·α·ΔΦ) - 1`, `I(r, r2) = 0.46·b·∫ Za(s)^b ds`
+//!
+//! with `Za` the measured (attenuated) reflectivity in mm⁶/m³, `s` in km,
+//! `α = A/KDP` (dB/°) and `b` the exponent of `A = a·Z^b`. The result is
+//! the specific attenuation `A` in dB/km (the `AH` moment read by the R(A)
+//! rain rate estimator). Optionally the reflectivity is also corrected by
+//! the two-way path-integrated attenuation `2·∫A ds`.
+//!
+//! Oxygen and water vapour attenuate every beam, with or without rain. The
+//! gaseous correction models their specific attenuation as decreasing
+//! exponentially with height, `γ(h) = γ0·exp(-h/H)`, and integrates it
+//! along the beam, so the two-way loss grows with range and is largest for
+//! the lowest elevations. It is small at S band but worth removing from
+//! C and X band reflectivity before QPE.
+
+use ndarray::Array2;
+
+use crate::transforms::georeference::{beam_height, GeoreferenceOptions};
+use crate::{MomentData, Provenance, RadishError, Result, SweepData, VolumeData};
+
+/// Name of the specific attenuation moment
+pub const SPECIFIC_ATTENUATION: &str = "AH";
+
+/// Coefficients of the ZPHI method for a radar band
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ZphiCoefficients {
+    /// Ratio of specific attenuation to KDP (dB/°)
+    pub alpha: f64,
+    /// Exponent of the `A = a·Z^b` relation
+    pub b: f64,
+}
+
+impl ZphiCoefficients {
+    /// S band (Ryzhkov et al., 2014)
+    pub const S_BAND: Self = Self { alpha: 0.015, b: 0.78 };
+
+    /// C band (Bringi et al., 2001)
+    pub const C_BAND: Self = Self { alpha: 0.08, b: 0.78 };
+
+    /// X band (Park et al., 2005)
+    pub const X_BAND: Self = Self { alpha: 0.28, b: 0.78 };
+}
+
+/// Moment attribute recording the gaseous attenuation correction
+pub const GAS_ATTENUATION_ATTRIBUTE: &str = "gaseous_attenuation_corrected";
+
+/// Gaseous attenuation of a radar band in a standard atmosphere
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GasCoefficients {
+    /// One-way specific attenuation at sea level (dB/km)
+    pub specific_attenuation: f64,
+    /// Height (meters) over which the specific attenuation decreases by a factor e
+    pub scale_height: f64,
+}
+
+impl GasCoefficients {
+    /// S band (3 GHz)
+    pub const S_BAND: Self = Self { specific_attenuation: 0.007, scale_height: 3000.0 };
+
+    /// C band (5.6 GHz)
+    pub const C_BAND: Self = Self { specific_attenuation: 0.008, scale_height: 3000.0 };
+
+    /// X band (9.4 GHz)
+    pub const X_BAND: Self = Self { specific_attenuation: 0.011, scale_height: 3000.0 };
+}
+
+/// Options for gaseous attenuation correction
+#[derive(Debug, Clone)]
+pub struct GasAttenuationOptions {
+    /// Reflectivity moments (dBZ) to correct; missing ones are skipped
+    pub moments: Vec<String>,
+    /// Band coefficients
+    pub coefficients: GasCoefficients,
+    /// Beam propagation model
+    pub georeference: GeoreferenceOptions,
+}
+
+impl Default for GasAttenuationOptions {
+    fn default() -> Self {
+        Self {
+            moments: vec!["DBZH".to_string(), "DBZV".to_string()],
+            coefficients: GasCoefficients::C_BAND,
+            georeference: GeoreferenceOptions::default(),
+        }
+    }
+}
+
+/// Options for specific attenuation retrieval
+#[derive(Debug, Clone)]
+pub struct AttenuationOptions {
+    /// Measured reflectivity moment (dBZ)
+    pub reflectivity: String,
+    /// Processed differential phase moment (degrees), e.g. from `process_phidp`
+    pub phidp: String,
+    /// Correlation coefficient moment used to select rain gates (not used if missing)
+    pub rhohv: String,
+    /// Output moment name
+    pub output_moment: String,
+    /// Band coefficients
+    pub coefficients: ZphiCoefficients,
+    /// Minimum reflectivity (dBZ) of a rain gate
+    pub min_reflectivity: f32,
+    /// Minimum RHOHV of a rain gate
+    pub min_rhohv: f32,
+    /// Rays whose phase shift (degrees) is smaller than this have no attenuation
+    pub min_delta_phi: f32,
+    /// Also write the attenuation-corrected reflectivity under this name
+    pub corrected_reflectivity: Option<String>,
+}
+
+impl Default for AttenuationOptions {
+    fn default() -> Self {
+        Self {
+            reflectivity: "DBZH".to_string(),
+            phidp: "PHIDP_CORR".to_string(),
+            rhohv: "RHOHV".to_string(),
+            output_moment: SPECIFIC_ATTENUATION.to_string(),
+            coefficients: ZphiCoefficients::S_BAND,
+            min_reflectivity: 10.0,
+            min_rhohv: 0.9,
+            min_delta_phi: 1.0,
+            corrected_reflectivity: None,
+        }
+    }
+}
+
+/// Add the specific attenuation moment to every sweep with reflectivity and PHIDP
+pub fn compute_specific_attenuation(volume: &VolumeData, options: &AttenuationOptions) -> Result<VolumeData> {
+    let mut volume = volume.clone();
+    for sweep in &mut volume.sweeps {
+        if sweep.get_moment(&options.reflectivity).is_none() || sweep.get_moment(&options.phidp).is_none() {
+            continue;
+        }
+        let attenuation = specific_attenuation_sweep(sweep, options)?;
+        if let Some(name) = &options.corrected_reflectivity {
+            let corrected = correct_reflectivity(sweep, &attenuation, name, options)?;
+            sweep.moments.insert(name.clone(), corrected);
+        }
+        sweep.moments.insert(options.output_moment.clone(), attenuation);
+    }
+    volume.history.record("radish.transforms.attenuation.compute_specific_attenuation", options);
+    Ok(volume)
+}
+
+/// Specific attenuation (dB/km) of a sweep
+///
+/// Gates with valid reflectivity outside the rain path, and on rays
+/// without a significant phase shift, have no attenuation; gates without
+/// valid reflectivity are missing.
+pub fn specific_attenuation_sweep(sweep: &SweepData, options: &AttenuationOptions) -> Result<MomentData> {
+    let refl = sweep
+        .get_moment(&options.reflectivity)
+        .ok_or_else(|| RadishError::MissingVariable(options.reflectivity.clone()))?;
+    let phidp = sweep
+        .get_moment(&options.phidp)
+        .ok_or_else(|| RadishError::MissingVariable(options.phidp.clone()))?;
+    let rhohv = sweep.get_moment(&options.rhohv);
+    let ZphiCoefficients { alpha, b } = options.coefficients;
+    let spacing = gate_spacing_km(&sweep.coordinates.range);
+
+    let (num_rays, num_gates) = refl.shape();
+    let mut out = Array2::from_elem((num_rays, num_gates), f32::NAN);
+    for i in 0..num_rays {
+        let z: Vec<Option<f64>> = (0..num_gates)
+            .map(|j| refl.valid_value(i, j).map(|v| v as f64))
+            .collect();
+        let rain: Vec<bool> = (0..num_gates)
+            .map(|j| {
+                z[j].is_some_and(|v| v >= options.min_reflectivity as f64)
+                    && phidp.is_valid(i, j)
+                    && rhohv.is_none_or(|m| m.valid_value(i, j).is_some_and(|r| r >= options.min_rhohv))
+            })
+            .collect();
+        for j in 0..num_gates {
+            if z[j].is_some() {
+                out[[i, j]] = 0.0;
+            }
+        }
+        let (Some(r1), Some(r2)) = (rain.iter().position(|&r| r), rain.iter().rposition(|&r| r)) else {
+            continue;
+        };
+        let delta_phi = (phidp.value(i, r2) - phidp.value(i, r1)) as f64;
+        if r2 <= r1 || delta_phi < options.min_delta_phi as f64 {
+            continue;
+        }
+
+        // I(r, r2) for every gate of the path, integrating from r2 backwards
+        let za_b: Vec<f64> = (r1..=r2)
+            .map(|j| z[j].map_or(0.0, |v| 10f64.powf(0.1 * b * v)))
+            .collect();
+        let mut integral = vec![0.0; za_b.len() + 1];
+        for k in (0..za_b.len()).rev() {
+            integral[k] = integral[k + 1] + 0.46 * b * za_b[k] * spacing[r1 + k];
+        }
+        let c = 10f64.powf(0.1 * b * alpha * delta_phi) - 1.0;
+        for (k, j) in (r1..=r2).enumerate() {
+            if z[j].is_some() {
+                out[[i, j]] = (za_b[k] * c / (integral[0] + c * integral[k])) as f32;
+            }
+        }
+    }
+
+    let mut ancestors = vec![options.reflectivity.as_str(), options.phidp.as_str()];
+    if rhohv.is_some() {
+        ancestors.push(&options.rhohv);
+    }
+    let mut moment = MomentData::new(options.output_moment.clone(), "dB/km".to_string(), out);
+    moment.long_name = Some("Specific attenuation (horizontal channel)".to_string());
+    moment.provenance = Some(Provenance::new(
+        "radish.transforms.attenuation.specific_attenuation_sweep",
+        &ancestors,
+        options,
+    ));
+    Ok(moment)
+}
+
+/// Reflectivity corrected by the two-way path-integrated attenuation
+fn correct_reflectivity(
+    sweep: &SweepData,
+    attenuation: &MomentData,
+    name: &str,
+    options: &AttenuationOptions,
+) -> Result<MomentData> {
+    let refl = sweep
+        .get_moment(&options.reflectivity)
+        .ok_or_else(|| RadishError::MissingVariable(options.reflectivity.clone()))?;
+    let spacing = gate_spacing_km(&sweep.coordinates.range);
+    let mut corrected = refl.clone();
+    corrected.name = name.to_string();
+    let valid = refl.validity();
+    for ((mut row, a), valid) in corrected
+        .data_mut()
+        .rows_mut()
+        .into_iter()
+        .zip(attenuation.data().rows())
+        .zip(valid.rows())
+    {
+        let mut path = 0.0f64;
+        for (j, v) in row.iter_mut().enumerate() {
+            // Two-way attenuation up to the middle of the gate
+            let half = a[j].max(0.0) as f64 * spacing[j];
+            if a[j].is_finite() {
+                path += half;
+            }
+            if valid[j] {
+                *v += path as f32;
+            }
+            if a[j].is_finite() {
+                path += half;
+            }
+        }
+    }
+    corrected.provenance = Some(Provenance::new(
+        "radish.transforms.attenuation.correct_reflectivity",
+        &[&options.reflectivity, &options.output_moment],
+        options,
+    ));
+    Ok(corrected)
+}
+
+/// Two-way gaseous attenuation (dB) at each range (meters) along a beam
+///
+/// `altitude` is the height of the radar above mean sea level (meters).
+pub fn gaseous_attenuation(
+    range: &[f32],
+    elevation: f64,
+    altitude: f64,
+    coefficients: &GasCoefficients,
+    georeference: &GeoreferenceOptions,
+) -> Vec<f64> {
+    let specific = |r: f64| {
+        let height = altitude + beam_height(r, elevation, georeference);
+        coefficients.specific_attenuation * (-height / coefficients.scale_height).exp()
+    };
+    // Trapezoid rule from the antenna through the gate centers
+    let (mut previous, mut path) = (0.0, 0.0);
+    range
+        .iter()
+        .map(|&r| {
+            let r = r as f64;
+            path += (specific(previous) + specific(r)) / 2.0 * (r - previous) / 1000.0;
+            previous = r;
+            2.0 * path
+        })
+        .collect()
+}
+
+/// Correct reflectivity moments for two-way gaseous attenuation
+///
+/// Moments already carrying the [`GAS_ATTENUATION_ATTRIBUTE`] are not
+/// corrected again.
+pub fn correct_gaseous_attenuation(volume: &VolumeData, options: &GasAttenuationOptions) -> VolumeData {
+    let mut volume = volume.clone();
+    let altitude = volume.metadata.altitude;
+    for sweep in &mut volume.sweeps {
+        let coords = &sweep.coordinates;
+        for name in &options.moments {
+            let Some(moment) = sweep.moments.get_mut(name) else {
+                continue;
+            };
+            if moment.attributes.contains_key(GAS_ATTENUATION_ATTRIBUTE) {
+                continue;
+            }
+            let missing = moment.missing_value();
+            for (mut row, &elevation) in moment.data_mut().rows_mut().into_iter().zip(&coords.elevation) {
+                let loss = gaseous_attenuation(
+                    &coords.range,
+                    elevation as f64,
+                    altitude,
+                    &options.coefficients,
+                    &options.georeference,
+                );
+                for (v, l) in row.iter_mut().zip(loss) {
+                    if !v.is_nan() && *v != missing {
+                        *v += l as f32;
+                    }
+                }
+            }
+            moment.attributes.insert(GAS_ATTENUATION_ATTRIBUTE.to_string(), "true".into());
+            moment.provenance = Some(Provenance::new(
+                "radish.transforms.attenuation.correct_gaseous_attenuation",
+                &[name],
+                options,
+            ));
+        }
+    }
+    volume.history.record("radish.transforms.attenuation.correct_gaseous_attenuation", options);
+    volume
+}
+
+/// Length (km) of each gate, from the spacing of neighbouring gate centers
+fn gate_spacing_km(range: &[f32]) -> Vec<f64> {
+    let n = range.len();
+    (0..n)
+        .map(|j| {
+            let (lo, hi) = (j.saturating_sub(1), (j + 1).min(n - 1));
+            if hi > lo {
+                (range[hi] - range[lo]) as f64 / (hi - lo) as f64 / 1000.0
+            } else {
+                0.0
+            }
+        })
+        .collect()
+}
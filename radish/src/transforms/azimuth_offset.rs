@@ -0,0 +1,169 @@
+//! Pedestal azimuth offset estimation from ground clutter
+//!
+//! Ground clutter is fixed in space, so a systematic azimuth error of the
+//! pedestal (e.g. after maintenance) rotates the clutter pattern seen by the
+//! radar. The offset is estimated by circular cross-correlation, along
+//! azimuth, of the clutter frequencies of recent volumes with those of a
+//! reference clutter map, refined below the map resolution by a parabolic
+//! fit of the correlation peak.
+
+use crate::transforms::clutter::{ClutterMap, ClutterMapOptions};
+use crate::{RadishError, Result, VolumeData};
+
+/// Volume attribute recording the azimuth correction applied
+pub const AZIMUTH_OFFSET_ATTRIBUTE: &str = "azimuth_offset_applied";
+
+/// Options for azimuth offset estimation
+#[derive(Debug, Clone)]
+pub struct AzimuthOffsetOptions {
+    /// Clutter detection and bin layout (must match the reference map)
+    pub clutter: ClutterMapOptions,
+    /// Largest offset (degrees) searched in each direction
+    pub max_offset: f32,
+}
+
+impl Default for AzimuthOffsetOptions {
+    fn default() -> Self {
+        Self {
+            clutter: ClutterMapOptions::default(),
+            max_offset: 5.0,
+        }
+    }
+}
+
+/// Estimated azimuth offset
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AzimuthOffset {
+    /// Correction (degrees) to add to the measured azimuths
+    pub offset: f32,
+    /// Normalized correlation at the offset (0-1); low values mean little
+    /// common clutter and an unreliable estimate
+    pub correlation: f32,
+    /// Number of tilts common to both maps
+    pub tilts: usize,
+}
+
+/// Azimuth offset of `map` relative to `reference`
+///
+/// Both maps must have the same bin layout. Tilts are matched by elevation
+/// within `options.clutter.elevation_tolerance` and their correlations are
+/// pooled.
+pub fn estimate_azimuth_offset(
+    map: &ClutterMap,
+    reference: &ClutterMap,
+    options: &AzimuthOffsetOptions,
+) -> Result<AzimuthOffset> {
+    if map.azimuth_resolution != reference.azimuth_resolution || map.shape() != reference.shape() {
+        return Err(RadishError::General(
+            "Clutter maps to correlate must have the same bin layout".to_string(),
+        ));
+    }
+    let pairs: Vec<_> = map
+        .tilts
+        .iter()
+        .filter_map(|tilt| {
+            let k = reference.find_tilt(tilt.elevation, options.clutter.elevation_tolerance)?;
+            Some((tilt.frequency().mapv(zero_nan), reference.tilts[k].frequency().mapv(zero_nan)))
+        })
+        .collect();
+    if pairs.is_empty() {
+        return Err(RadishError::General(
+            "No common tilts between the clutter maps".to_string(),
+        ));
+    }
+
+    let (num_azimuth, _) = map.shape();
+    let max_shift = (options.max_offset / map.azimuth_resolution).ceil() as isize;
+    let norm: f64 = pairs
+        .iter()
+        .map(|(a, b)| (a.iter().map(|v| v * v).sum::<f64>() * b.iter().map(|v| v * v).sum::<f64>()).sqrt())
+        .sum();
+    if norm == 0.0 {
+        return Err(RadishError::General("No clutter in the maps to correlate".to_string()));
+    }
+
+    // Clutter seen at bin `a` lies at bin `a + shift` in the reference
+    let score = |shift: isize| -> f64 {
+        let mut sum = 0.0;
+        for (a, b) in &pairs {
+            for ((i, j), &v) in a.indexed_iter() {
+                if v != 0.0 {
+                    let k = (i as isize + shift).rem_euclid(num_azimuth as isize) as usize;
+                    sum += v * b[[k, j]];
+                }
+            }
+        }
+        sum / norm
+    };
+
+    let mut shifts: Vec<isize> = (-max_shift..=max_shift).collect();
+    shifts.sort_by_key(|s| s.abs());
+    let (best, peak) = shifts
+        .into_iter()
+        .map(|s| (s, score(s)))
+        .fold((0, f64::NEG_INFINITY), |best, (s, v)| if v > best.1 { (s, v) } else { best });
+
+    // Parabolic refinement of the peak
+    let (left, right) = (score(best - 1), score(best + 1));
+    let curvature = left - 2.0 * peak + right;
+    let fraction = if curvature < 0.0 {
+        (0.5 * (left - right) / curvature).clamp(-0.5, 0.5)
+    } else {
+        0.0
+    };
+
+    Ok(AzimuthOffset {
+        offset: ((best as f64 + fraction) * map.azimuth_resolution as f64) as f32,
+        correlation: peak as f32,
+        tilts: pairs.len(),
+    })
+}
+
+/// Azimuth offset of recent volumes relative to a reference clutter map
+pub fn estimate_volume_azimuth_offset<'a>(
+    volumes: impl IntoIterator<Item = &'a VolumeData>,
+    reference: &ClutterMap,
+    options: &AzimuthOffsetOptions,
+) -> Result<AzimuthOffset> {
+    let mut map = ClutterMap::new(&options.clutter);
+    for volume in volumes {
+        map.accumulate(volume, &options.clutter)?;
+    }
+    estimate_azimuth_offset(&map, reference, options)
+}
+
+/// Add `offset` degrees to every ray azimuth of a volume
+///
+/// Cached gate positions are dropped since they depend on the azimuths. The
+/// total correction applied is recorded in the `azimuth_offset_applied`
+/// volume attribute.
+pub fn apply_azimuth_offset(volume: &VolumeData, offset: f32) -> VolumeData {
+    let mut volume = volume.clone();
+    for sweep in &mut volume.sweeps {
+        for az in &mut sweep.coordinates.azimuth {
+            *az = (*az + offset).rem_euclid(360.0);
+        }
+        sweep.coordinates.clear_georeference();
+    }
+
+    let previous: f32 = volume
+        .metadata
+        .attributes
+        .get(AZIMUTH_OFFSET_ATTRIBUTE)
+        .and_then(|v| v.as_f64())
+        .map_or(0.0, |v| v as f32);
+    volume
+        .metadata
+        .attributes
+        .insert(AZIMUTH_OFFSET_ATTRIBUTE.to_string(), (previous + offset).to_string().into());
+    volume.history.record("radish.transforms.azimuth_offset.apply_azimuth_offset", &offset);
+    volume
+}
+
+fn zero_nan(v: f32) -> f64 {
+    if v.is_nan() {
+        0.0
+    } else {
+        v as f64
+    }
+}
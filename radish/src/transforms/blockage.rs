@@ -0,0 +1,151 @@
+//! Beam blockage from a digital elevation model
+//!
+//! Implements the geometric partial beam blockage model of Bech et al.
+//! (2003). The half-power cross-section of the beam is a disc of radius
+//! `a = r · tan(θ/2)` around the beam center; where terrain rises `y`
+//! meters above the beam center, the blocked fraction of the disc is
+//!
+//! `(y·√(a² - y²) + a²·asin(y/a) + π·a²/2) / (π·a²)`
+//!
+//! (0 for `y ≤ -a`, 1 for `y ≥ a`). Energy blocked at one range is lost to
+//! all farther gates, so the blockage of a gate is the largest blockage
+//! along its ray up to that gate. The result is stored as the `PBB` moment
+//! (0-1, with 1 for total blockage), which the coverage and mosaic
+//! transforms read to down-weight or skip blocked beams.
+
+use ndarray::{Array2, ArrayView2, Axis};
+
+use crate::transforms::georeference::{antenna_to_cartesian, cartesian_to_geographic, GeoreferenceOptions};
+use crate::{GriddedData, MomentData, Provenance, RadishError, Result, SweepData, VolumeData};
+
+/// Name of the beam blockage moment
+pub const BEAM_BLOCKAGE: &str = "PBB";
+
+/// Options for beam blockage computation
+#[derive(Debug, Clone)]
+pub struct BlockageOptions {
+    /// Elevation field of the terrain grid (meters MSL)
+    pub terrain_field: String,
+    /// Half-power beam width (degrees); from the instrument parameters if
+    /// `None`, or 1° without them
+    pub beam_width: Option<f64>,
+    /// Beam propagation model
+    pub georeference: GeoreferenceOptions,
+    /// Record provenance on the blockage moment
+    pub provenance: bool,
+}
+
+impl Default for BlockageOptions {
+    fn default() -> Self {
+        Self {
+            terrain_field: crate::io::geotiff::ELEVATION.to_string(),
+            beam_width: None,
+            georeference: GeoreferenceOptions::default(),
+            provenance: true,
+        }
+    }
+}
+
+/// Fraction of a beam of radius `a` blocked by terrain `y` meters above its center
+pub fn blocked_fraction(y: f64, a: f64) -> f64 {
+    if y <= -a {
+        0.0
+    } else if y >= a {
+        1.0
+    } else {
+        let a2 = a * a;
+        (y * (a2 - y * y).sqrt() + a2 * (y / a).asin() + std::f64::consts::PI * a2 / 2.0)
+            / (std::f64::consts::PI * a2)
+    }
+}
+
+/// Add the beam blockage moment to every sweep of a volume
+///
+/// `terrain` is a grid (e.g. from `io::read_geotiff_dem`) whose first level
+/// of `options.terrain_field` holds the terrain height in meters MSL. Gates
+/// over terrain outside the grid or without data are not blocked there.
+pub fn compute_blockage(volume: &VolumeData, terrain: &GriddedData, options: &BlockageOptions) -> Result<VolumeData> {
+    let mut out = volume.clone();
+    for sweep in &mut out.sweeps {
+        let blockage = sweep_blockage(sweep, volume, terrain, options)?;
+        let mut moment = MomentData::new(BEAM_BLOCKAGE.to_string(), "1".to_string(), blockage);
+        moment.long_name = Some("Cumulative beam blockage fraction".to_string());
+        if options.provenance {
+            moment = moment.with_provenance(Provenance::new(
+                "radish.transforms.blockage.compute_blockage",
+                &[] as &[&str],
+                options,
+            ));
+        }
+        sweep.moments.insert(BEAM_BLOCKAGE.to_string(), moment);
+    }
+    out.history.record("radish.transforms.blockage.compute_blockage", options);
+    Ok(out)
+}
+
+/// Cumulative beam blockage fraction of each gate of a sweep [rays × gates]
+pub fn sweep_blockage(
+    sweep: &SweepData,
+    volume: &VolumeData,
+    terrain: &GriddedData,
+    options: &BlockageOptions,
+) -> Result<Array2<f32>> {
+    sweep.coordinates.validate()?;
+    let field = terrain
+        .get_field(&options.terrain_field)
+        .ok_or_else(|| RadishError::MissingVariable(options.terrain_field.clone()))?;
+    let elevation_grid = field
+        .data
+        .index_axis(Axis(0), 0)
+        .mapv(|v| if field.is_valid_value(v) { v } else { f32::NAN });
+    let meta = &volume.metadata;
+    let beam_width = options
+        .beam_width
+        .or_else(|| meta.instrument_parameters.as_ref().and_then(|p| p.beam_width()))
+        .unwrap_or(1.0);
+    let half_width = (beam_width / 2.0).to_radians().tan();
+
+    let coords = &sweep.coordinates;
+    let mut blockage = Array2::zeros((coords.num_rays(), coords.num_gates()));
+    for (i, (&azimuth, &elevation)) in coords.azimuth.iter().zip(&coords.elevation).enumerate() {
+        let mut cumulative = 0.0f64;
+        for (j, &range) in coords.range.iter().enumerate() {
+            let (x, y, z) = antenna_to_cartesian(range as f64, azimuth as f64, elevation as f64, &options.georeference);
+            let (lon, lat) = cartesian_to_geographic(x, y, meta.longitude, meta.latitude);
+            let (tx, ty) = terrain.projection.forward(lon, lat);
+            let ground = bilinear(elevation_grid.view(), &terrain.x, &terrain.y, tx, ty);
+            if !ground.is_nan() {
+                let beam_center = meta.altitude + z;
+                let radius = range as f64 * half_width;
+                cumulative = cumulative.max(blocked_fraction(ground - beam_center, radius));
+            }
+            blockage[[i, j]] = cumulative as f32;
+        }
+    }
+    Ok(blockage)
+}
+
+/// Bilinear interpolation of a [y × x] grid, NaN outside it or next to missing values
+pub(crate) fn bilinear(grid: ArrayView2<f32>, x: &[f64], y: &[f64], px: f64, py: f64) -> f64 {
+    let position = |axis: &[f64], p: f64| -> Option<(usize, f64)> {
+        let n = axis.len();
+        if n < 2 {
+            return (n == 1).then_some((0, 0.0));
+        }
+        let step = (axis[n - 1] - axis[0]) / (n - 1) as f64;
+        let f = (p - axis[0]) / step;
+        if !(0.0..=(n - 1) as f64).contains(&f) {
+            return None;
+        }
+        let k = (f.floor() as usize).min(n - 2);
+        Some((k, f - k as f64))
+    };
+    let (Some((i, fx)), Some((j, fy))) = (position(x, px), position(y, py)) else {
+        return f64::NAN;
+    };
+    let value = |j: usize, i: usize| grid.get((j, i)).map_or(f64::NAN, |&v| v as f64);
+    let (i1, j1) = ((i + 1).min(x.len() - 1), (j + 1).min(y.len() - 1));
+    let lower = value(j, i) * (1.0 - fx) + value(j, i1) * fx;
+    let upper = value(j1, i) * (1.0 - fx) + value(j1, i1) * fx;
+    lower * (1.0 - fy) + upper * fy
+}
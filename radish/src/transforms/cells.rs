@@ -0,0 +1,413 @@
+//! Storm cell identification and tracking
+//!
+//! Cells are identified, as in TITAN (Dixon & Wiener 1993), as contiguous
+//! 3D regions of a reflectivity grid above a threshold (grid points sharing
+//! a face are connected), keeping those above a minimum volume. Each cell
+//! is summarized by its centroid, volume, projected area, maximum
+//! reflectivity and echo top.
+//!
+//! Cells are tracked between successive grids by overlap: the motion of the
+//! echo between the grids is estimated by cross-correlation (see
+//! `transforms::advection`), the footprints of the earlier cells are
+//! advected by it, and each later cell continues the track of the earlier
+//! cell it overlaps most. Cells without overlap fall back to the nearest
+//! forecast position within the distance a `max_speed` cell travels. A cell
+//! overlapping an already continued track starts a new track recording the
+//! split; merged cells end the other tracks.
+
+use std::collections::{HashSet, VecDeque};
+
+use chrono::{DateTime, Utc};
+use ndarray::{Array2, Array3};
+
+use crate::transforms::advection::{grid_spacing, motion_cells, AdvectionOptions};
+use crate::{GriddedData, RadishError, Result};
+
+/// Options for cell identification
+#[derive(Debug, Clone)]
+pub struct CellOptions {
+    /// Reflectivity field of the grid
+    pub field: String,
+    /// Reflectivity (dBZ) at or above which grid points belong to cells
+    pub threshold: f32,
+    /// Smallest cell volume (km³); on single-level grids, smallest area (km²)
+    pub min_volume: f64,
+}
+
+impl Default for CellOptions {
+    fn default() -> Self {
+        Self {
+            field: "DBZH".to_string(),
+            threshold: 35.0,
+            min_volume: 30.0,
+        }
+    }
+}
+
+/// Options for cell tracking
+#[derive(Debug, Clone)]
+pub struct TrackOptions {
+    /// Cell identification
+    pub cells: CellOptions,
+    /// Largest cell speed (m/s)
+    pub max_speed: f64,
+    /// Smallest overlap, as a fraction of the smaller footprint, that continues a track
+    pub min_overlap: f64,
+}
+
+impl Default for TrackOptions {
+    fn default() -> Self {
+        Self {
+            cells: CellOptions::default(),
+            max_speed: 30.0,
+            min_overlap: 0.1,
+        }
+    }
+}
+
+/// A storm cell identified in a grid
+#[derive(Debug, Clone)]
+pub struct StormCell {
+    /// Track the cell belongs to (assigned by [`CellTracker`])
+    pub track: Option<usize>,
+    /// Track this cell split from, if any
+    pub parent: Option<usize>,
+    /// Grid time
+    pub time: DateTime<Utc>,
+    /// Centroid x (grid units)
+    pub x: f64,
+    /// Centroid y (grid units)
+    pub y: f64,
+    /// Centroid height (meters MSL)
+    pub z: f64,
+    /// Centroid longitude (degrees)
+    pub longitude: f64,
+    /// Centroid latitude (degrees)
+    pub latitude: f64,
+    /// Volume (km³)
+    pub volume: f64,
+    /// Area of the projection on the ground (km²)
+    pub area: f64,
+    /// Maximum reflectivity (dBZ)
+    pub max_reflectivity: f32,
+    /// Height (meters MSL) of the highest level of the cell
+    pub echo_top: f64,
+    /// Motion (m/s) toward x and y since the previous grid of the track
+    pub velocity: Option<(f64, f64)>,
+    /// Grid columns (row, column) covered by the cell
+    pub footprint: Vec<(usize, usize)>,
+}
+
+/// The successive cells of a track
+#[derive(Debug, Clone)]
+pub struct Track {
+    /// Track identifier
+    pub id: usize,
+    /// Track this one split from, if any
+    pub parent: Option<usize>,
+    /// Cells of the track, in time order
+    pub cells: Vec<StormCell>,
+}
+
+impl Track {
+    /// Time of the first cell
+    pub fn start(&self) -> Option<DateTime<Utc>> {
+        self.cells.first().map(|c| c.time)
+    }
+
+    /// Time of the last cell
+    pub fn end(&self) -> Option<DateTime<Utc>> {
+        self.cells.last().map(|c| c.time)
+    }
+}
+
+/// Identify the storm cells of a reflectivity grid
+///
+/// Cells are returned largest first, without track assignments.
+pub fn identify_cells(grid: &GriddedData, options: &CellOptions) -> Result<Vec<StormCell>> {
+    let field = grid
+        .get_field(&options.field)
+        .ok_or_else(|| RadishError::MissingVariable(options.field.clone()))?;
+    let (nz, ny, nx) = field.shape();
+    let (dx, dy) = grid_spacing(grid.projection, &grid.x, &grid.y);
+    let cell_area = (dx * dy).abs() / 1e6;
+    let thickness = level_thickness(&grid.z);
+
+    let echo = field
+        .data
+        .mapv(|v| field.is_valid_value(v) && v >= options.threshold);
+    let mut labelled = Array3::from_elem((nz, ny, nx), false);
+    let mut cells = Vec::new();
+    let mut queue = VecDeque::new();
+    for start in ndarray::indices((nz, ny, nx)) {
+        if !echo[start] || labelled[start] {
+            continue;
+        }
+
+        // Flood fill of the face-connected region
+        labelled[start] = true;
+        queue.push_back(start);
+        let mut points = Vec::new();
+        while let Some((k, j, i)) = queue.pop_front() {
+            points.push((k, j, i));
+            let neighbours = [
+                (k.wrapping_sub(1), j, i),
+                (k + 1, j, i),
+                (k, j.wrapping_sub(1), i),
+                (k, j + 1, i),
+                (k, j, i.wrapping_sub(1)),
+                (k, j, i + 1),
+            ];
+            for n in neighbours {
+                if n.0 < nz && n.1 < ny && n.2 < nx && echo[n] && !labelled[n] {
+                    labelled[n] = true;
+                    queue.push_back(n);
+                }
+            }
+        }
+
+        let volume: f64 = points.iter().map(|&(k, _, _)| cell_area * thickness[k] / 1000.0).sum();
+        let footprint: HashSet<(usize, usize)> = points.iter().map(|&(_, j, i)| (j, i)).collect();
+        let area = footprint.len() as f64 * cell_area;
+        if (if nz > 1 { volume } else { area }) < options.min_volume {
+            continue;
+        }
+
+        let n = points.len() as f64;
+        let (x, y, z) = points.iter().fold((0.0, 0.0, 0.0), |(x, y, z), &(k, j, i)| {
+            (x + grid.x[i] / n, y + grid.y[j] / n, z + grid.z[k] / n)
+        });
+        let (longitude, latitude) = grid.projection.inverse(x, y);
+        let mut footprint: Vec<_> = footprint.into_iter().collect();
+        footprint.sort_unstable();
+        cells.push(StormCell {
+            track: None,
+            parent: None,
+            time: grid.time,
+            x,
+            y,
+            z,
+            longitude,
+            latitude,
+            volume,
+            area,
+            max_reflectivity: points.iter().map(|&p| field.data[p]).fold(f32::NEG_INFINITY, f32::max),
+            echo_top: points.iter().map(|&(k, _, _)| grid.z[k]).fold(f64::NEG_INFINITY, f64::max),
+            velocity: None,
+            footprint,
+        });
+    }
+
+    cells.sort_by(|a, b| b.volume.total_cmp(&a.volume).then(b.area.total_cmp(&a.area)));
+    Ok(cells)
+}
+
+/// Tracks storm cells through a time series of grids
+///
+/// Grids are added in time order with [`CellTracker::update`]; all must
+/// share the projection and x/y axes of the first one.
+#[derive(Debug, Clone)]
+pub struct CellTracker {
+    options: TrackOptions,
+    tracks: Vec<Track>,
+    previous: Option<Previous>,
+}
+
+#[derive(Debug, Clone)]
+struct Previous {
+    /// Time and axes of the previous grid, without its fields
+    grid: GriddedData,
+    composite: Array2<f32>,
+    cells: Vec<StormCell>,
+}
+
+impl CellTracker {
+    /// Create a tracker without tracks
+    pub fn new(options: TrackOptions) -> Self {
+        Self {
+            options,
+            tracks: Vec::new(),
+            previous: None,
+        }
+    }
+
+    /// Identify the cells of the next grid and continue or start their tracks
+    ///
+    /// Returns the cells of the grid with their track assignments.
+    pub fn update(&mut self, grid: &GriddedData) -> Result<Vec<StormCell>> {
+        let mut cells = identify_cells(grid, &self.options.cells)?;
+        let composite = column_maximum(grid, &self.options.cells.field)?;
+
+        let mut matches: Vec<Option<(usize, bool)>> = vec![None; cells.len()];
+        if let Some(previous) = &self.previous {
+            let dt = (grid.time - previous.grid.time).num_milliseconds() as f64 / 1000.0;
+            if previous.grid.projection != grid.projection || previous.grid.x != grid.x || previous.grid.y != grid.y {
+                return Err(RadishError::InvalidFormat(
+                    "Tracked grids must share the same grid".to_string(),
+                ));
+            }
+            if dt <= 0.0 {
+                return Err(RadishError::General(format!(
+                    "Grids must be tracked in time order ({} after {})",
+                    grid.time, previous.grid.time
+                )));
+            }
+            matches = self.match_cells(previous, &composite, &cells, dt);
+            for (cell, m) in cells.iter_mut().zip(&matches) {
+                if let Some((p, true)) = *m {
+                    let earlier = &previous.cells[p];
+                    cell.velocity = Some(((cell.x - earlier.x) / dt, (cell.y - earlier.y) / dt));
+                }
+            }
+        }
+
+        for (cell, m) in cells.iter_mut().zip(&matches) {
+            let previous_track = m.and_then(|(p, _)| self.previous.as_ref()?.cells[p].track);
+            match (m, previous_track) {
+                (Some((_, true)), Some(id)) => {
+                    cell.track = Some(id);
+                    cell.parent = self.tracks[id].parent;
+                    self.tracks[id].cells.push(cell.clone());
+                }
+                (split, parent) => {
+                    let id = self.tracks.len();
+                    let parent = split.and(parent);
+                    cell.track = Some(id);
+                    cell.parent = parent;
+                    self.tracks.push(Track {
+                        id,
+                        parent,
+                        cells: vec![cell.clone()],
+                    });
+                }
+            }
+        }
+
+        self.previous = Some(Previous {
+            grid: GriddedData::new(grid.time, grid.projection, grid.x.clone(), grid.y.clone(), grid.z.clone()),
+            composite,
+            cells: cells.clone(),
+        });
+        Ok(cells)
+    }
+
+    /// All tracks so far, by identifier
+    pub fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    /// The tracks, once the series is complete
+    pub fn finish(self) -> Vec<Track> {
+        self.tracks
+    }
+
+    /// Earlier cell matched by each later cell, with whether it continues its
+    /// track (`false` for a split off an already continued track)
+    fn match_cells(
+        &self,
+        previous: &Previous,
+        composite: &Array2<f32>,
+        cells: &[StormCell],
+        dt: f64,
+    ) -> Vec<Option<(usize, bool)>> {
+        let grid = &previous.grid;
+        let (dx, dy) = grid_spacing(grid.projection, &grid.x, &grid.y);
+        let advection = AdvectionOptions {
+            max_speed: self.options.max_speed,
+            echo_threshold: self.options.cells.threshold,
+            ..Default::default()
+        };
+        let (shift_x, shift_y) = motion_cells(previous.composite.view(), composite.view(), dt, dx, dy, &advection);
+        let (ny, nx) = composite.dim();
+        // Grid steps, in grid units
+        let (ux, uy) = (axis_step(&grid.x), axis_step(&grid.y));
+
+        // Candidate pairs scored by overlap, then by forecast distance
+        let mut candidates: Vec<(usize, usize, f64, f64)> = Vec::new();
+        for (p, earlier) in previous.cells.iter().enumerate() {
+            let (j0, i0) = earlier.footprint[0];
+            let (sx, sy) = (shift_x[[j0, i0]].round() as isize, shift_y[[j0, i0]].round() as isize);
+            let advected: HashSet<(usize, usize)> = earlier
+                .footprint
+                .iter()
+                .filter_map(|&(j, i)| {
+                    let (j, i) = (j as isize + sy, i as isize + sx);
+                    (j >= 0 && i >= 0 && (j as usize) < ny && (i as usize) < nx).then_some((j as usize, i as usize))
+                })
+                .collect();
+            let (fx, fy) = (earlier.x + sx as f64 * ux, earlier.y + sy as f64 * uy);
+
+            for (c, later) in cells.iter().enumerate() {
+                let common = later.footprint.iter().filter(|f| advected.contains(f)).count();
+                let overlap = common as f64 / earlier.footprint.len().min(later.footprint.len()).max(1) as f64;
+                let distance = ((later.x - fx) / ux * dx).hypot((later.y - fy) / uy * dy);
+                if overlap >= self.options.min_overlap || distance <= self.options.max_speed * dt {
+                    candidates.push((p, c, overlap, distance));
+                }
+            }
+        }
+        candidates.sort_by(|a, b| b.2.total_cmp(&a.2).then(a.3.total_cmp(&b.3)));
+
+        let mut matches = vec![None; cells.len()];
+        let mut continued = vec![false; previous.cells.len()];
+        for &(p, c, overlap, _) in &candidates {
+            if matches[c].is_some() {
+                continue;
+            }
+            if !continued[p] {
+                continued[p] = true;
+                matches[c] = Some((p, true));
+            } else if overlap >= self.options.min_overlap {
+                matches[c] = Some((p, false));
+            }
+        }
+        matches
+    }
+}
+
+/// Track the cells of a series of grids (sorted by time first)
+pub fn track_cells(grids: &[GriddedData], options: &TrackOptions) -> Result<Vec<Track>> {
+    let mut order: Vec<&GriddedData> = grids.iter().collect();
+    order.sort_by_key(|g| g.time);
+
+    let mut tracker = CellTracker::new(options.clone());
+    for grid in order {
+        tracker.update(grid)?;
+    }
+    Ok(tracker.finish())
+}
+
+/// Column maximum of a field, NaN where no level is valid
+fn column_maximum(grid: &GriddedData, name: &str) -> Result<Array2<f32>> {
+    let field = grid
+        .get_field(name)
+        .ok_or_else(|| RadishError::MissingVariable(name.to_string()))?;
+    let (_, ny, nx) = field.shape();
+    let mut maximum = Array2::from_elem((ny, nx), f32::NAN);
+    for ((_, j, i), &v) in field.data.indexed_iter() {
+        if field.is_valid_value(v) && (maximum[[j, i]].is_nan() || v > maximum[[j, i]]) {
+            maximum[[j, i]] = v;
+        }
+    }
+    Ok(maximum)
+}
+
+/// Spacing of a grid axis (grid units)
+fn axis_step(axis: &[f64]) -> f64 {
+    if axis.len() > 1 {
+        (axis[axis.len() - 1] - axis[0]) / (axis.len() - 1) as f64
+    } else {
+        1.0
+    }
+}
+
+/// Thickness (meters) of each level, between the midpoints to its neighbours
+fn level_thickness(z: &[f64]) -> Vec<f64> {
+    let n = z.len();
+    if n < 2 {
+        return vec![1000.0; n];
+    }
+    (0..n)
+        .map(|k| (z[(k + 1).min(n - 1)] - z[k.saturating_sub(1)]).abs() / if k == 0 || k == n - 1 { 1.0 } else { 2.0 })
+        .collect()
+}
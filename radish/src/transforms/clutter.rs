@@ -0,0 +1,344 @@
+//! Static ground clutter maps
+//!
+//! A clutter map records, for each tilt and polar bin, how often echo was
+//! observed in clear-air volumes. Ground targets return echo in nearly every
+//! volume while weather and biological echo come and go, so bins with a
+//! high clutter frequency can be masked in later volumes. Maps are built
+//! incrementally with [`ClutterMap::accumulate`] and persisted as NetCDF.
+
+use std::path::Path;
+
+use ndarray::{Array2, Array3, Axis};
+
+use crate::{MomentData, Provenance, RadishError, Result, SweepData, VolumeData};
+
+/// Name of the flag moment written by [`filter_clutter`]
+pub const CLUTTER_FLAG: &str = "CLUTTER_FLAG";
+
+/// Options for building and applying clutter maps
+#[derive(Debug, Clone)]
+pub struct ClutterMapOptions {
+    /// Power moment used to detect echo (typically uncorrected reflectivity)
+    pub moment: String,
+    /// Gates at or above this value (dBZ) count as echo
+    pub threshold: f32,
+    /// Azimuth bin size (degrees)
+    pub azimuth_resolution: f32,
+    /// Range bin size (meters)
+    pub range_resolution: f32,
+    /// Maximum range (meters) covered by the map
+    pub max_range: f32,
+    /// Sweeps within this many degrees of a map tilt share its bins
+    pub elevation_tolerance: f32,
+    /// Bins with echo in at least this fraction of observations are clutter
+    pub min_frequency: f32,
+    /// Moments masked by [`filter_clutter`] (all moments if empty)
+    pub moments: Vec<String>,
+}
+
+impl Default for ClutterMapOptions {
+    fn default() -> Self {
+        Self {
+            moment: "DBZH".to_string(),
+            threshold: 10.0,
+            azimuth_resolution: 1.0,
+            range_resolution: 250.0,
+            max_range: 100_000.0,
+            elevation_tolerance: 0.3,
+            min_frequency: 0.5,
+            moments: Vec::new(),
+        }
+    }
+}
+
+/// Echo statistics of one tilt of a clutter map
+#[derive(Debug, Clone)]
+pub struct ClutterTilt {
+    /// Nominal elevation (degrees)
+    pub elevation: f32,
+    /// Number of gates with echo in each bin [azimuth × range]
+    pub hits: Array2<u32>,
+    /// Number of gates observed in each bin [azimuth × range]
+    pub observations: Array2<u32>,
+}
+
+impl ClutterTilt {
+    /// Fraction of observations with echo in each bin (NaN where never observed)
+    pub fn frequency(&self) -> Array2<f32> {
+        let mut frequency = Array2::from_elem(self.hits.dim(), f32::NAN);
+        for ((idx, &hits), &n) in self.hits.indexed_iter().zip(self.observations.iter()) {
+            if n > 0 {
+                frequency[idx] = hits as f32 / n as f32;
+            }
+        }
+        frequency
+    }
+}
+
+/// Clutter statistics accumulated over many volumes
+#[derive(Debug, Clone)]
+pub struct ClutterMap {
+    /// Azimuth bin size (degrees)
+    pub azimuth_resolution: f32,
+    /// Range bin size (meters)
+    pub range_resolution: f32,
+    /// Echo threshold (dBZ) the statistics were built with
+    pub threshold: f32,
+    /// Number of volumes accumulated
+    pub num_volumes: usize,
+    /// Statistics per tilt, in order of first appearance
+    pub tilts: Vec<ClutterTilt>,
+}
+
+impl ClutterMap {
+    /// Create an empty map with the bin layout of `options`
+    pub fn new(options: &ClutterMapOptions) -> Self {
+        Self {
+            azimuth_resolution: options.azimuth_resolution,
+            range_resolution: options.range_resolution,
+            threshold: options.threshold,
+            num_volumes: 0,
+            tilts: Vec::new(),
+        }
+    }
+
+    /// Bin shape (azimuth bins, range bins) of `options`
+    fn bin_shape(options: &ClutterMapOptions) -> (usize, usize) {
+        (
+            (360.0 / options.azimuth_resolution).round().max(1.0) as usize,
+            (options.max_range / options.range_resolution).ceil().max(1.0) as usize,
+        )
+    }
+
+    /// Shape (azimuth bins, range bins) of every tilt
+    pub fn shape(&self) -> (usize, usize) {
+        self.tilts.first().map(|t| t.hits.dim()).unwrap_or((0, 0))
+    }
+
+    /// Index of the tilt within `tolerance` degrees of `elevation`
+    pub fn find_tilt(&self, elevation: f32, tolerance: f32) -> Option<usize> {
+        self.tilts
+            .iter()
+            .enumerate()
+            .map(|(k, t)| (k, (t.elevation - elevation).abs()))
+            .filter(|&(_, d)| d <= tolerance)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(k, _)| k)
+    }
+
+    /// Add the echo statistics of a clear-air volume
+    ///
+    /// Sweeps without `options.moment` are skipped; sweeps at an elevation
+    /// not yet in the map start a new tilt.
+    pub fn accumulate(&mut self, volume: &VolumeData, options: &ClutterMapOptions) -> Result<()> {
+        if options.azimuth_resolution != self.azimuth_resolution
+            || options.range_resolution != self.range_resolution
+        {
+            return Err(RadishError::General(
+                "Clutter map options do not match the map bin layout".to_string(),
+            ));
+        }
+        let shape = if self.tilts.is_empty() {
+            Self::bin_shape(options)
+        } else {
+            self.shape()
+        };
+
+        for sweep in &volume.sweeps {
+            let Some(moment) = sweep.get_moment(&options.moment) else {
+                continue;
+            };
+
+            let elevation = sweep.metadata.fixed_angle as f32;
+            let k = match self.find_tilt(elevation, options.elevation_tolerance) {
+                Some(k) => k,
+                None => {
+                    self.tilts.push(ClutterTilt {
+                        elevation,
+                        hits: Array2::zeros(shape),
+                        observations: Array2::zeros(shape),
+                    });
+                    self.tilts.len() - 1
+                }
+            };
+            let tilt = &mut self.tilts[k];
+
+            for_each_bin(sweep, self.azimuth_resolution, self.range_resolution, shape, |i, j, bin| {
+                tilt.observations[bin] += 1;
+                if moment.valid_value(i, j).is_some_and(|v| v >= self.threshold) {
+                    tilt.hits[bin] += 1;
+                }
+            });
+        }
+
+        self.num_volumes += 1;
+        Ok(())
+    }
+
+    /// Clutter mask of a sweep (true where the bin is clutter), if the map has its tilt
+    pub fn sweep_mask(&self, sweep: &SweepData, options: &ClutterMapOptions) -> Option<Array2<bool>> {
+        let k = self.find_tilt(sweep.metadata.fixed_angle as f32, options.elevation_tolerance)?;
+        let frequency = self.tilts[k].frequency();
+
+        let mut mask = Array2::from_elem((sweep.num_rays(), sweep.num_gates()), false);
+        for_each_bin(sweep, self.azimuth_resolution, self.range_resolution, self.shape(), |i, j, bin| {
+            mask[[i, j]] = frequency[bin] >= options.min_frequency;
+        });
+        Some(mask)
+    }
+
+    /// Save the map as a NetCDF file
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let (num_azimuth, num_range) = self.shape();
+        let mut file = netcdf::create(path.as_ref())?;
+
+        file.add_attribute("title", "radish ground clutter map")?;
+        file.add_attribute("azimuth_resolution", self.azimuth_resolution)?;
+        file.add_attribute("range_resolution", self.range_resolution)?;
+        file.add_attribute("threshold", self.threshold)?;
+        file.add_attribute("num_volumes", self.num_volumes as i32)?;
+
+        file.add_dimension("tilt", self.tilts.len())?;
+        file.add_dimension("azimuth", num_azimuth)?;
+        file.add_dimension("range", num_range)?;
+
+        let elevation: Vec<f32> = self.tilts.iter().map(|t| t.elevation).collect();
+        let mut var = file.add_variable::<f32>("elevation", &["tilt"])?;
+        var.put_attribute("units", "degrees")?;
+        var.put_values(&elevation, ..)?;
+
+        let hits: Vec<u32> = self.tilts.iter().flat_map(|t| t.hits.iter().copied()).collect();
+        file.add_variable::<u32>("hits", &["tilt", "azimuth", "range"])?
+            .put_values(&hits, ..)?;
+        let observations: Vec<u32> = self.tilts.iter().flat_map(|t| t.observations.iter().copied()).collect();
+        file.add_variable::<u32>("observations", &["tilt", "azimuth", "range"])?
+            .put_values(&observations, ..)?;
+
+        Ok(())
+    }
+
+    /// Load a map written by [`ClutterMap::save`]
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let file = netcdf::open(path.as_ref())?;
+
+        let attribute = |name: &str| {
+            crate::io::read_numeric_attribute::<f64>(file.attributes(), name)
+                .ok_or_else(|| RadishError::MissingAttribute(name.to_string()))
+        };
+        let dimension = |name: &str| {
+            file.dimension(name)
+                .map(|d| d.len())
+                .ok_or_else(|| RadishError::InvalidFormat(format!("Missing dimension {}", name)))
+        };
+        let variable = |name: &str| {
+            file.variable(name)
+                .ok_or_else(|| RadishError::MissingVariable(name.to_string()))
+        };
+
+        let shape = (dimension("tilt")?, dimension("azimuth")?, dimension("range")?);
+        let elevation: Vec<f32> = variable("elevation")?.get_values(..)?;
+        let counts = |name: &str| -> Result<Array3<u32>> {
+            let values: Vec<u32> = variable(name)?.get_values(..)?;
+            Array3::from_shape_vec(shape, values)
+                .map_err(|e| RadishError::InvalidFormat(format!("Invalid {} shape: {}", name, e)))
+        };
+        let hits = counts("hits")?;
+        let observations = counts("observations")?;
+
+        let tilts = elevation
+            .iter()
+            .enumerate()
+            .map(|(k, &elevation)| ClutterTilt {
+                elevation,
+                hits: hits.index_axis(Axis(0), k).to_owned(),
+                observations: observations.index_axis(Axis(0), k).to_owned(),
+            })
+            .collect();
+
+        Ok(Self {
+            azimuth_resolution: attribute("azimuth_resolution")? as f32,
+            range_resolution: attribute("range_resolution")? as f32,
+            threshold: attribute("threshold")? as f32,
+            num_volumes: attribute("num_volumes")? as usize,
+            tilts,
+        })
+    }
+}
+
+/// Build a clutter map from a set of clear-air volumes
+pub fn build_clutter_map<'a>(
+    volumes: impl IntoIterator<Item = &'a VolumeData>,
+    options: &ClutterMapOptions,
+) -> Result<ClutterMap> {
+    let mut map = ClutterMap::new(options);
+    for volume in volumes {
+        map.accumulate(volume, options)?;
+    }
+    Ok(map)
+}
+
+/// Mask clutter bins of a volume and add a `CLUTTER_FLAG` moment (1 for clutter, 0 elsewhere)
+///
+/// Sweeps at elevations not covered by the map are left unchanged and get
+/// no flag moment.
+pub fn filter_clutter(volume: &VolumeData, map: &ClutterMap, options: &ClutterMapOptions) -> Result<VolumeData> {
+    let mut volume = volume.clone();
+
+    for sweep in &mut volume.sweeps {
+        let Some(mask) = map.sweep_mask(sweep, options) else {
+            continue;
+        };
+
+        for (name, moment) in sweep.moments.iter_mut() {
+            if !options.moments.is_empty() && !options.moments.contains(name) {
+                continue;
+            }
+            let missing = moment.missing_value();
+            for (v, &clutter) in moment.data_mut().iter_mut().zip(mask.iter()) {
+                if clutter {
+                    *v = missing;
+                }
+            }
+        }
+
+        let mut flag = MomentData::new(
+            CLUTTER_FLAG.to_string(),
+            String::new(),
+            mask.mapv(|c| if c { 1.0 } else { 0.0 }),
+        );
+        flag.long_name = Some("Ground clutter map flag".to_string());
+        flag.provenance = Some(Provenance::new(
+            "radish.transforms.clutter.filter_clutter",
+            &[&options.moment],
+            &(options, map.num_volumes),
+        ));
+        sweep.moments.insert(CLUTTER_FLAG.to_string(), flag);
+    }
+
+    volume.history.record("radish.transforms.clutter.filter_clutter", options);
+    Ok(volume)
+}
+
+/// Call `f(ray, gate, bin)` for every gate of a sweep inside the map
+fn for_each_bin(
+    sweep: &SweepData,
+    azimuth_resolution: f32,
+    range_resolution: f32,
+    shape: (usize, usize),
+    mut f: impl FnMut(usize, usize, (usize, usize)),
+) {
+    let (num_azimuth, num_range) = shape;
+    for (i, &az) in sweep.coordinates.azimuth.iter().enumerate() {
+        let a = (az.rem_euclid(360.0) / azimuth_resolution) as usize % num_azimuth.max(1);
+        for (j, &r) in sweep.coordinates.range.iter().enumerate() {
+            if r < 0.0 {
+                continue;
+            }
+            let b = (r / range_resolution) as usize;
+            if b >= num_range {
+                break;
+            }
+            f(i, j, (a, b));
+        }
+    }
+}
@@ -0,0 +1,180 @@
+//! Vertical columns above a point
+//!
+//! For ground validation against disdrometers and gauges, the gates of each
+//! PPI sweep above a surface point are collected into a vertical profile.
+//! The profile either keeps one sample per sweep or is interpolated linearly
+//! in height onto requested levels.
+
+use std::collections::HashMap;
+
+use radish_types::SweepMode;
+
+use crate::transforms::georeference::{geographic_to_cartesian, ground_to_antenna, GeoreferenceOptions};
+use crate::transforms::mosaic::nearest_gate;
+use crate::model::coordinates::azimuth_distance;
+use crate::{Result, VolumeData};
+
+/// Options for column extraction
+#[derive(Debug, Clone)]
+pub struct ColumnOptions {
+    /// Moments to extract (all moments if empty)
+    pub moments: Vec<String>,
+    /// Levels (meters MSL) to interpolate onto; one sample per sweep if `None`
+    pub heights: Option<Vec<f64>>,
+    /// Maximum azimuth difference (degrees) between the point and the nearest ray
+    pub azimuth_tolerance: f32,
+    /// Beam propagation model
+    pub georeference: GeoreferenceOptions,
+}
+
+impl Default for ColumnOptions {
+    fn default() -> Self {
+        Self {
+            moments: Vec::new(),
+            heights: None,
+            azimuth_tolerance: 1.0,
+            georeference: GeoreferenceOptions::default(),
+        }
+    }
+}
+
+/// Vertical profile of moments above a point
+#[derive(Debug, Clone)]
+pub struct Column {
+    /// Longitude of the point (degrees)
+    pub longitude: f64,
+    /// Latitude of the point (degrees)
+    pub latitude: f64,
+    /// Great-circle distance (meters) from the radar
+    pub distance: f64,
+    /// Azimuth (degrees) from the radar
+    pub azimuth: f64,
+    /// Height (meters MSL) of each level, increasing
+    pub height: Vec<f64>,
+    /// Time (seconds since the epoch) of each level, NaN if unknown
+    pub time: Vec<f64>,
+    /// Moment values at each level, NaN where missing
+    pub moments: HashMap<String, Vec<f32>>,
+}
+
+impl Column {
+    /// Number of levels
+    pub fn len(&self) -> usize {
+        self.height.len()
+    }
+
+    /// Whether the column has no levels
+    pub fn is_empty(&self) -> bool {
+        self.height.is_empty()
+    }
+}
+
+/// Extract the column of moments above `(longitude, latitude)`
+///
+/// Every PPI sweep reaching the point contributes the gate nearest to it,
+/// at the beam height and ray time of that gate. With `options.heights`,
+/// values and times are interpolated linearly between the sweeps bracketing
+/// each level; levels outside the sampled heights, or next to a missing
+/// value, are NaN.
+pub fn extract_column(volume: &VolumeData, longitude: f64, latitude: f64, options: &ColumnOptions) -> Result<Column> {
+    let meta = &volume.metadata;
+    let (x, y) = geographic_to_cartesian(longitude, latitude, meta.longitude, meta.latitude);
+    let distance = (x * x + y * y).sqrt();
+    let azimuth = x.atan2(y).to_degrees().rem_euclid(360.0);
+
+    let names: Vec<String> = if options.moments.is_empty() {
+        let mut names: Vec<String> = volume
+            .sweeps
+            .iter()
+            .flat_map(|s| s.moments.keys().cloned())
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    } else {
+        options.moments.clone()
+    };
+
+    // One sample per sweep: (height, time, values)
+    let mut samples: Vec<(f64, f64, Vec<f32>)> = Vec::new();
+    for sweep in &volume.sweeps {
+        if !matches!(
+            sweep.metadata.sweep_mode,
+            SweepMode::Azimuth | SweepMode::Sector | SweepMode::ManualPpi
+        ) {
+            continue;
+        }
+        sweep.coordinates.validate()?;
+        let (range, height) = ground_to_antenna(distance, sweep.metadata.fixed_angle, &options.georeference);
+        let Some((ray, gate)) = nearest_gate(sweep, range as f32, azimuth as f32) else {
+            continue;
+        };
+        if azimuth_distance(sweep.coordinates.azimuth[ray], azimuth as f32) > options.azimuth_tolerance {
+            continue;
+        }
+
+        let values = names
+            .iter()
+            .map(|name| match sweep.get_moment(name) {
+                Some(m) => m.valid_value(ray, gate).unwrap_or(f32::NAN),
+                None => f32::NAN,
+            })
+            .collect();
+        let time = sweep.coordinates.epoch_time(ray);
+        samples.push((meta.altitude + height, time, values));
+    }
+    samples.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let (height, time, values): (Vec<f64>, Vec<f64>, Vec<Vec<f32>>) = match &options.heights {
+        None => {
+            let height = samples.iter().map(|s| s.0).collect();
+            let time = samples.iter().map(|s| s.1).collect();
+            let values = (0..names.len())
+                .map(|m| samples.iter().map(|s| s.2[m]).collect())
+                .collect();
+            (height, time, values)
+        }
+        Some(levels) => {
+            let mut levels = levels.clone();
+            levels.sort_by(f64::total_cmp);
+            let time = levels.iter().map(|&h| interpolate(&samples, h, |s| s.1)).collect();
+            let values = (0..names.len())
+                .map(|m| {
+                    levels
+                        .iter()
+                        .map(|&h| interpolate(&samples, h, |s| s.2[m] as f64) as f32)
+                        .collect()
+                })
+                .collect();
+            (levels, time, values)
+        }
+    };
+
+    Ok(Column {
+        longitude,
+        latitude,
+        distance,
+        azimuth,
+        height,
+        time,
+        moments: names.into_iter().zip(values).collect(),
+    })
+}
+
+/// Linear interpolation in height between the samples bracketing `h` (sorted by height)
+fn interpolate(samples: &[(f64, f64, Vec<f32>)], h: f64, value: impl Fn(&(f64, f64, Vec<f32>)) -> f64) -> f64 {
+    let upper = samples.partition_point(|s| s.0 < h);
+    if upper == samples.len() {
+        return f64::NAN;
+    }
+    if samples[upper].0 == h {
+        return value(&samples[upper]);
+    }
+    if upper == 0 {
+        return f64::NAN;
+    }
+
+    let (lo, hi) = (&samples[upper - 1], &samples[upper]);
+    let w = (h - lo.0) / (hi.0 - lo.0);
+    value(lo) + w * (value(hi) - value(lo))
+}
@@ -0,0 +1,196 @@
+//! Three-body scatter spike (TBSS) and sidelobe contamination flags
+//!
+//! Both artifacts are weak, spurious echoes caused by very strong cores
+//! (typically large hail):
+//!
+//! - A TBSS (hail spike) is a radial flare of weak reflectivity extending
+//!   down-range behind the core, roughly as far as the core is high,
+//!   produced by radiation scattered core → ground → core → radar.
+//! - Sidelobe contamination is weak echo at the same range as the core but
+//!   a few degrees off in azimuth, received through the antenna sidelobes.
+//!
+//! Both typically show low RHOHV, which is used as a confirming test when
+//! the moment is available. The detectors add 0/1 flag moments so that
+//! QPE and classification can exclude the flagged gates.
+
+use ndarray::Array2;
+
+use crate::transforms::georeference::{beam_height, GeoreferenceOptions};
+use crate::model::coordinates::azimuth_distance;
+use crate::{MomentData, Provenance, RadishError, Result, SweepData, VolumeData};
+
+/// Name of the TBSS flag moment
+pub const TBSS_FLAG: &str = "TBSS_FLAG";
+
+/// Name of the sidelobe contamination flag moment
+pub const SIDELOBE_FLAG: &str = "SIDELOBE_FLAG";
+
+/// Options for contamination detection
+#[derive(Debug, Clone)]
+pub struct ContaminationOptions {
+    /// Reflectivity moment
+    pub reflectivity: String,
+    /// Correlation coefficient moment used to confirm flags (skipped if missing)
+    pub rhohv: String,
+    /// Minimum reflectivity (dBZ) of a core able to cause artifacts
+    pub core_threshold: f32,
+    /// Artifacts must be weaker than this reflectivity (dBZ)
+    pub weak_threshold: f32,
+    /// Artifacts must have RHOHV below this value (when RHOHV is available)
+    pub rhohv_threshold: f32,
+    /// TBSS length as a multiple of the core height above the radar
+    pub tbss_length_factor: f64,
+    /// Sidelobe search half-width in azimuth (degrees)
+    pub sidelobe_azimuth_window: f32,
+    /// Minimum difference (dB) between core and contaminated gate for sidelobe flags
+    pub sidelobe_level: f32,
+}
+
+impl Default for ContaminationOptions {
+    fn default() -> Self {
+        Self {
+            reflectivity: "DBZH".to_string(),
+            rhohv: "RHOHV".to_string(),
+            core_threshold: 55.0,
+            weak_threshold: 25.0,
+            rhohv_threshold: 0.8,
+            tbss_length_factor: 1.0,
+            sidelobe_azimuth_window: 5.0,
+            sidelobe_level: 25.0,
+        }
+    }
+}
+
+/// Add `TBSS_FLAG` and `SIDELOBE_FLAG` moments to every sweep with reflectivity
+pub fn flag_contamination(volume: &VolumeData, options: &ContaminationOptions) -> Result<VolumeData> {
+    let mut volume = volume.clone();
+
+    for sweep in &mut volume.sweeps {
+        if sweep.get_moment(&options.reflectivity).is_none() {
+            continue;
+        }
+
+        let tbss = detect_tbss(sweep, options)?;
+        let sidelobe = detect_sidelobe(sweep, options)?;
+        let mut ancestors = vec![options.reflectivity.as_str()];
+        if sweep.get_moment(&options.rhohv).is_some() {
+            ancestors.push(&options.rhohv);
+        }
+        let provenance = Provenance::new("radish.transforms.contamination.flag_contamination", &ancestors, options);
+
+        sweep.moments.insert(
+            TBSS_FLAG.to_string(),
+            flag_moment(TBSS_FLAG, "Three-body scatter spike flag", tbss).with_provenance(provenance.clone()),
+        );
+        sweep.moments.insert(
+            SIDELOBE_FLAG.to_string(),
+            flag_moment(SIDELOBE_FLAG, "Sidelobe contamination flag", sidelobe).with_provenance(provenance),
+        );
+    }
+
+    volume.history.record("radish.transforms.contamination.flag_contamination", options);
+    Ok(volume)
+}
+
+/// Detect three-body scatter spikes in a sweep
+///
+/// For each ray, the farthest gate exceeding `core_threshold` is taken as
+/// the core. Weak (and, if available, poorly correlated) gates between the
+/// core and `tbss_length_factor` × core height further down-range are flagged.
+pub fn detect_tbss(sweep: &SweepData, options: &ContaminationOptions) -> Result<Array2<bool>> {
+    let refl = reflectivity(sweep, options)?;
+    let rhohv = sweep.get_moment(&options.rhohv);
+    let geo = GeoreferenceOptions::default();
+    let range = &sweep.coordinates.range;
+
+    let mut flags = Array2::from_elem(refl.shape(), false);
+    for (i, row) in refl.data().outer_iter().enumerate() {
+        let Some(core) = (0..row.len())
+            .rev()
+            .find(|&j| refl.is_valid(i, j) && row[j] >= options.core_threshold)
+        else {
+            continue;
+        };
+
+        let elevation = sweep.coordinates.elevation[i] as f64;
+        let core_height = beam_height(range[core] as f64, elevation, &geo);
+        let spike_end = range[core] as f64 + options.tbss_length_factor * core_height;
+
+        for j in core + 1..row.len() {
+            if range[j] as f64 > spike_end {
+                break;
+            }
+            flags[[i, j]] = is_artifact(refl, rhohv, i, j, row[j], options);
+        }
+    }
+
+    Ok(flags)
+}
+
+/// Detect sidelobe contamination in a sweep
+///
+/// A gate is flagged when a core at the same range gate lies within
+/// `sidelobe_azimuth_window` degrees, the gate is at least `sidelobe_level`
+/// dB weaker than that core, and it passes the weak echo/RHOHV tests.
+pub fn detect_sidelobe(sweep: &SweepData, options: &ContaminationOptions) -> Result<Array2<bool>> {
+    let refl = reflectivity(sweep, options)?;
+    let rhohv = sweep.get_moment(&options.rhohv);
+    let azimuth = &sweep.coordinates.azimuth;
+    let (num_rays, num_gates) = refl.shape();
+
+    let mut flags = Array2::from_elem((num_rays, num_gates), false);
+    for j in 0..num_gates {
+        let cores: Vec<(usize, f32)> = (0..num_rays)
+            .filter_map(|i| refl.valid_value(i, j).map(|v| (i, v)))
+            .filter(|&(_, v)| v >= options.core_threshold)
+            .collect();
+        if cores.is_empty() {
+            continue;
+        }
+
+        for i in 0..num_rays {
+            let v = refl.value(i, j);
+            let near_core = cores.iter().any(|&(k, core)| {
+                k != i
+                    && azimuth_distance(azimuth[i], azimuth[k]) <= options.sidelobe_azimuth_window
+                    && core - v >= options.sidelobe_level
+            });
+            if near_core {
+                flags[[i, j]] = is_artifact(refl, rhohv, i, j, v, options);
+            }
+        }
+    }
+
+    Ok(flags)
+}
+
+fn reflectivity<'a>(sweep: &'a SweepData, options: &ContaminationOptions) -> Result<&'a MomentData> {
+    sweep
+        .get_moment(&options.reflectivity)
+        .ok_or_else(|| RadishError::MissingVariable(options.reflectivity.clone()))
+}
+
+/// Weak echo with low RHOHV (when RHOHV is available)
+fn is_artifact(
+    refl: &MomentData,
+    rhohv: Option<&MomentData>,
+    i: usize,
+    j: usize,
+    v: f32,
+    options: &ContaminationOptions,
+) -> bool {
+    if !refl.is_valid(i, j) || v >= options.weak_threshold {
+        return false;
+    }
+    match rhohv {
+        Some(rho) => rho.valid_value(i, j).is_some_and(|r| r < options.rhohv_threshold),
+        None => true,
+    }
+}
+
+fn flag_moment(name: &str, long_name: &str, flags: Array2<bool>) -> MomentData {
+    let data = flags.mapv(|f| if f { 1.0 } else { 0.0 });
+    let mut moment = MomentData::new(name.to_string(), String::new(), data);
+    moment.long_name = Some(long_name.to_string());
+    moment
+}
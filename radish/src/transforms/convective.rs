@@ -0,0 +1,268 @@
+//! Convective/stratiform echo classification
+//!
+//! Implements the partitioning of Steiner et al. (1995), with the cosine
+//! peakedness criterion and weak-echo category of Yuter & Houze (1997) and
+//! Yuter et al. (2005) as options. A grid point is a convective center if
+//! its reflectivity exceeds an intensity threshold or sufficiently exceeds
+//! the background (the linear-Z mean within a fixed radius); points within
+//! a background-dependent radius of a center are convective, and remaining
+//! echo is stratiform.
+
+use ndarray::{Array2, Axis};
+
+use crate::{GridField, GriddedData, RadishError, Result};
+
+/// Name of the classification field
+pub const CONV_STRAT: &str = "CONV_STRAT";
+
+/// Classification value for grid points without echo
+pub const NO_ECHO: f32 = 0.0;
+
+/// Classification value for stratiform echo
+pub const STRATIFORM: f32 = 1.0;
+
+/// Classification value for convective echo
+pub const CONVECTIVE: f32 = 2.0;
+
+/// Classification value for weak echo (when a weak echo threshold is set)
+pub const WEAK_ECHO: f32 = 3.0;
+
+/// How much a point must exceed the background to be a convective center
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Peakedness {
+    /// Steiner et al. (1995): 10 - Zbg²/180 dB, between 10 and 0 dB
+    #[default]
+    Steiner,
+    /// Yuter & Houze (1997): a·cos(π·Zbg / 2b) dB, between a and 0 dB
+    Cosine { a: f32, b: f32 },
+}
+
+impl Peakedness {
+    /// Required excess over the background (dB) at background reflectivity `zbg` (dBZ)
+    pub fn threshold(&self, zbg: f32) -> f32 {
+        match *self {
+            Peakedness::Steiner => {
+                if zbg < 0.0 {
+                    10.0
+                } else {
+                    (10.0 - zbg * zbg / 180.0).max(0.0)
+                }
+            }
+            Peakedness::Cosine { a, b } => {
+                if zbg < 0.0 {
+                    a
+                } else if zbg >= b {
+                    0.0
+                } else {
+                    a * (std::f32::consts::PI * zbg / (2.0 * b)).cos()
+                }
+            }
+        }
+    }
+}
+
+/// Options for convective/stratiform classification
+#[derive(Debug, Clone)]
+pub struct ConvStratOptions {
+    /// Reflectivity field of the grid
+    pub field: String,
+    /// Height (meters MSL) of the level to classify; the nearest grid level is used
+    pub height: f64,
+    /// Radius (meters) over which the background reflectivity is averaged
+    pub background_radius: f64,
+    /// Reflectivity (dBZ) at or above which a point is always a convective center
+    pub intense_threshold: f32,
+    /// Peakedness criterion for convective centers
+    pub peakedness: Peakedness,
+    /// Minimum reflectivity (dBZ) considered echo
+    pub min_reflectivity: f32,
+    /// Non-convective echo below this reflectivity (dBZ) is classified as weak echo
+    pub weak_echo_threshold: Option<f32>,
+    /// Convective radius (meters) around centers with a weak background
+    pub min_convective_radius: f64,
+    /// Largest convective radius (meters)
+    pub max_convective_radius: f64,
+    /// Background reflectivity (dBZ) at which the convective radius starts to grow
+    pub radius_start_dbz: f32,
+    /// Background reflectivity increase (dB) per radius step
+    pub radius_step_dbz: f32,
+    /// Convective radius increase (meters) per step
+    pub radius_step: f64,
+}
+
+impl Default for ConvStratOptions {
+    fn default() -> Self {
+        // Steiner et al. (1995) parameters
+        Self {
+            field: "DBZH".to_string(),
+            height: 3000.0,
+            background_radius: 11_000.0,
+            intense_threshold: 40.0,
+            peakedness: Peakedness::default(),
+            min_reflectivity: 0.0,
+            weak_echo_threshold: None,
+            min_convective_radius: 1000.0,
+            max_convective_radius: 5000.0,
+            radius_start_dbz: 20.0,
+            radius_step_dbz: 5.0,
+            radius_step: 1000.0,
+        }
+    }
+}
+
+impl ConvStratOptions {
+    /// Convective radius (meters) around a center with background reflectivity `zbg`
+    pub fn convective_radius(&self, zbg: f32) -> f64 {
+        let steps = ((zbg - self.radius_start_dbz) / self.radius_step_dbz).floor() + 1.0;
+        let radius = self.min_convective_radius + steps.max(0.0) as f64 * self.radius_step;
+        radius.min(self.max_convective_radius)
+    }
+}
+
+/// Classify a grid and return a copy with the `CONV_STRAT` field added
+pub fn convective_stratiform(grid: &GriddedData, options: &ConvStratOptions) -> Result<GriddedData> {
+    let field = classify_convective_stratiform(grid, options)?;
+    let mut grid = grid.clone();
+    grid.add_field(field)?;
+    Ok(grid)
+}
+
+/// Compute the single-level `CONV_STRAT` classification field of a grid
+pub fn classify_convective_stratiform(grid: &GriddedData, options: &ConvStratOptions) -> Result<GridField> {
+    let field = grid
+        .get_field(&options.field)
+        .ok_or_else(|| RadishError::MissingVariable(options.field.clone()))?;
+    let (dx, dy) = grid_spacing(grid)?;
+
+    let level = nearest_level(&grid.z, options.height).min(field.shape().0.saturating_sub(1));
+    let refl: Array2<f32> = field.data.index_axis(Axis(0), level).mapv(|v| {
+        if field.is_valid_value(v) && v >= options.min_reflectivity {
+            v
+        } else {
+            f32::NAN
+        }
+    });
+    let (ny, nx) = refl.dim();
+
+    let background = background_reflectivity(&refl, &disk_offsets(options.background_radius, dx, dy));
+
+    let mut class = Array2::from_elem((ny, nx), NO_ECHO);
+    for ((j, i), &z) in refl.indexed_iter() {
+        if z.is_nan() {
+            continue;
+        }
+        class[[j, i]] = match options.weak_echo_threshold {
+            Some(weak) if z < weak => WEAK_ECHO,
+            _ => STRATIFORM,
+        };
+    }
+
+    // Convective centers and the area around them
+    for ((j, i), &z) in refl.indexed_iter() {
+        let zbg = background[[j, i]];
+        if z.is_nan() || zbg.is_nan() {
+            continue;
+        }
+        let is_center = z >= options.intense_threshold || z - zbg >= options.peakedness.threshold(zbg);
+        if !is_center {
+            continue;
+        }
+
+        for (oj, oi) in disk_offsets(options.convective_radius(zbg), dx, dy) {
+            let (jj, ii) = (j as isize + oj, i as isize + oi);
+            if jj < 0 || ii < 0 || jj >= ny as isize || ii >= nx as isize {
+                continue;
+            }
+            let (jj, ii) = (jj as usize, ii as usize);
+            if !refl[[jj, ii]].is_nan() {
+                class[[jj, ii]] = CONVECTIVE;
+            }
+        }
+    }
+
+    let mut out = GridField::new(CONV_STRAT.to_string(), String::new(), class.insert_axis(Axis(0)));
+    out.long_name = Some("Convective/stratiform echo classification".to_string());
+    out.attributes.insert(
+        "flag_values".to_string(),
+        format!("{} {} {} {}", NO_ECHO, STRATIFORM, CONVECTIVE, WEAK_ECHO),
+    );
+    out.attributes.insert(
+        "flag_meanings".to_string(),
+        "no_echo stratiform convective weak_echo".to_string(),
+    );
+    if let Some(height) = grid.z.get(level) {
+        out.attributes
+            .insert("classification_height".to_string(), height.to_string());
+    }
+    Ok(out)
+}
+
+/// Mean reflectivity (computed in linear Z) over a neighbourhood of each point
+fn background_reflectivity(refl: &Array2<f32>, offsets: &[(isize, isize)]) -> Array2<f32> {
+    let (ny, nx) = refl.dim();
+    let linear = refl.mapv(|v| if v.is_nan() { f32::NAN } else { 10f32.powf(v / 10.0) });
+
+    let mut background = Array2::from_elem((ny, nx), f32::NAN);
+    for j in 0..ny {
+        for i in 0..nx {
+            if refl[[j, i]].is_nan() {
+                continue;
+            }
+            let (sum, count) = offsets
+                .iter()
+                .filter_map(|&(oj, oi)| {
+                    let (jj, ii) = (j as isize + oj, i as isize + oi);
+                    if jj < 0 || ii < 0 || jj >= ny as isize || ii >= nx as isize {
+                        return None;
+                    }
+                    let v = linear[[jj as usize, ii as usize]];
+                    (!v.is_nan()).then_some(v)
+                })
+                .fold((0.0f64, 0usize), |(s, c), v| (s + v as f64, c + 1));
+            if count > 0 {
+                background[[j, i]] = (10.0 * (sum / count as f64).log10()) as f32;
+            }
+        }
+    }
+    background
+}
+
+/// Index offsets of all grid points within `radius` of the origin
+fn disk_offsets(radius: f64, dx: f64, dy: f64) -> Vec<(isize, isize)> {
+    let ri = (radius / dx).floor() as isize;
+    let rj = (radius / dy).floor() as isize;
+    let mut offsets = Vec::new();
+    for oj in -rj..=rj {
+        for oi in -ri..=ri {
+            let d2 = (oi as f64 * dx).powi(2) + (oj as f64 * dy).powi(2);
+            if d2 <= radius * radius {
+                offsets.push((oj, oi));
+            }
+        }
+    }
+    offsets
+}
+
+fn grid_spacing(grid: &GriddedData) -> Result<(f64, f64)> {
+    let spacing = |axis: &[f64]| {
+        if axis.len() > 1 {
+            Some((axis[axis.len() - 1] - axis[0]).abs() / (axis.len() - 1) as f64)
+        } else {
+            None
+        }
+    };
+    match (spacing(&grid.x), spacing(&grid.y)) {
+        (Some(dx), Some(dy)) if dx > 0.0 && dy > 0.0 => Ok((dx, dy)),
+        _ => Err(RadishError::InvalidFormat(
+            "Grid needs at least two distinct x and y coordinates".to_string(),
+        )),
+    }
+}
+
+fn nearest_level(z: &[f64], height: f64) -> usize {
+    z.iter()
+        .enumerate()
+        .min_by(|a, b| (a.1 - height).abs().total_cmp(&(b.1 - height).abs()))
+        .map(|(k, _)| k)
+        .unwrap_or(0)
+}
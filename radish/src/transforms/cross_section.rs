@@ -0,0 +1,327 @@
+//! Vertical cross-sections through radar volumes
+//!
+//! A pseudo-RHI gathers, from each PPI sweep of a volume, the ray nearest
+//! to a chosen azimuth and stacks them by elevation. The result is an RHI
+//! sweep (rays × gates = elevations × range) on the range axis of the
+//! sweep with the most gates, which the plotting and georeferencing code
+//! handles like any other sweep.
+//!
+//! General cross-sections follow a path of longitude/latitude waypoints,
+//! sampled at regular distances, and interpolate a gridded or polar volume
+//! onto height levels at each point: bilinearly in the horizontal and
+//! linearly in height for grids, and through the columns of
+//! [`extract_column`] for polar volumes. Slices are [height × distance].
+
+use std::collections::HashMap;
+
+use ndarray::{Array2, ArrayView1, Axis};
+use radish_types::SweepMode;
+
+use crate::transforms::blockage::bilinear;
+use crate::transforms::column::{extract_column, ColumnOptions};
+use crate::transforms::georeference::{GeoreferenceOptions, Projection};
+use crate::model::coordinates::{azimuth_distance, nearest_range_gate};
+use crate::{
+    Coordinates, GriddedData, MomentData, Provenance, RadishError, Result, SweepData, SweepMetadata, VolumeData,
+};
+
+/// Options for pseudo-RHI extraction
+#[derive(Debug, Clone)]
+pub struct PseudoRhiOptions {
+    /// Moments to extract (all moments if empty)
+    pub moments: Vec<String>,
+    /// Sweeps without a ray this close (degrees) to the azimuth are skipped
+    pub azimuth_tolerance: f32,
+}
+
+impl Default for PseudoRhiOptions {
+    fn default() -> Self {
+        Self {
+            moments: Vec::new(),
+            azimuth_tolerance: 1.0,
+        }
+    }
+}
+
+/// Options for cross-sections along a path
+#[derive(Debug, Clone)]
+pub struct CrossSectionOptions {
+    /// Moments or grid fields to sample (all if empty)
+    pub moments: Vec<String>,
+    /// Height levels (meters MSL)
+    pub heights: Vec<f64>,
+    /// Distance (meters) between samples along the path
+    pub spacing: f64,
+    /// Maximum azimuth difference (degrees) between a point and the nearest ray of a polar volume
+    pub azimuth_tolerance: f32,
+    /// Beam propagation model for polar volumes
+    pub georeference: GeoreferenceOptions,
+}
+
+impl Default for CrossSectionOptions {
+    fn default() -> Self {
+        Self {
+            moments: Vec::new(),
+            heights: (0..=60).map(|k| k as f64 * 250.0).collect(),
+            spacing: 1000.0,
+            azimuth_tolerance: 1.0,
+            georeference: GeoreferenceOptions::default(),
+        }
+    }
+}
+
+/// Vertical slices of moments along a path
+#[derive(Debug, Clone)]
+pub struct CrossSection {
+    /// Distance (meters) of each sample along the path
+    pub distance: Vec<f64>,
+    /// Longitude (degrees) of each sample
+    pub longitude: Vec<f64>,
+    /// Latitude (degrees) of each sample
+    pub latitude: Vec<f64>,
+    /// Height levels (meters MSL), increasing
+    pub height: Vec<f64>,
+    /// Slices [height × distance], NaN where missing
+    pub moments: HashMap<String, Array2<f32>>,
+}
+
+/// Sample points `(distance, longitude, latitude)` every `spacing` meters along a path
+///
+/// Segments are straight in an azimuthal equidistant projection centred on
+/// the first waypoint; the last waypoint is always included.
+pub fn sample_path(waypoints: &[(f64, f64)], spacing: f64) -> Result<Vec<(f64, f64, f64)>> {
+    if waypoints.len() < 2 || spacing <= 0.0 {
+        return Err(RadishError::General(
+            "A cross-section path needs at least two waypoints and a positive spacing".to_string(),
+        ));
+    }
+    let projection = Projection::aeqd(waypoints[0].0, waypoints[0].1);
+    let points: Vec<(f64, f64)> = waypoints.iter().map(|&(lon, lat)| projection.forward(lon, lat)).collect();
+
+    let mut samples = Vec::new();
+    let mut start = 0.0;
+    for (k, pair) in points.windows(2).enumerate() {
+        let (dx, dy) = (pair[1].0 - pair[0].0, pair[1].1 - pair[0].1);
+        let length = (dx * dx + dy * dy).sqrt();
+        let mut d = (start / spacing).ceil() * spacing;
+        while d < start + length {
+            let f = (d - start) / length;
+            let (lon, lat) = projection.inverse(pair[0].0 + f * dx, pair[0].1 + f * dy);
+            samples.push((d, lon, lat));
+            d += spacing;
+        }
+        start += length;
+        if k == points.len() - 2 {
+            let (lon, lat) = waypoints[k + 1];
+            samples.push((start, lon, lat));
+        }
+    }
+    Ok(samples)
+}
+
+/// Cross-section of a gridded volume along a path of `(longitude, latitude)` waypoints
+///
+/// Single-level fields are repeated at every height.
+pub fn cross_section_grid(
+    grid: &GriddedData,
+    waypoints: &[(f64, f64)],
+    options: &CrossSectionOptions,
+) -> Result<CrossSection> {
+    let mut section = empty_section(waypoints, options)?;
+    let names: Vec<String> = if options.moments.is_empty() {
+        let mut names: Vec<String> = grid.fields.keys().cloned().collect();
+        names.sort();
+        names
+    } else {
+        options.moments.clone()
+    };
+    let positions: Vec<(f64, f64)> = section
+        .longitude
+        .iter()
+        .zip(&section.latitude)
+        .map(|(&lon, &lat)| grid.projection.forward(lon, lat))
+        .collect();
+
+    for name in names {
+        let field = grid.get_field(&name).ok_or_else(|| RadishError::MissingVariable(name.clone()))?;
+        let levels: Vec<Array2<f32>> = field
+            .data
+            .axis_iter(Axis(0))
+            .map(|level| level.mapv(|v| if field.is_valid_value(v) { v } else { f32::NAN }))
+            .collect();
+        let mut slice = Array2::from_elem((section.height.len(), positions.len()), f32::NAN);
+        for (j, &(x, y)) in positions.iter().enumerate() {
+            let column: Vec<f64> = levels
+                .iter()
+                .map(|level| bilinear(level.view(), &grid.x, &grid.y, x, y))
+                .collect();
+            for (i, &h) in section.height.iter().enumerate() {
+                slice[[i, j]] = if column.len() == 1 {
+                    column[0] as f32
+                } else {
+                    interpolate_level(&grid.z, &column, h) as f32
+                };
+            }
+        }
+        section.moments.insert(name, slice);
+    }
+    Ok(section)
+}
+
+/// Cross-section of a polar volume along a path of `(longitude, latitude)` waypoints
+///
+/// Each sample is the column above the point, interpolated between the
+/// PPI sweeps bracketing each height.
+pub fn cross_section_volume(
+    volume: &VolumeData,
+    waypoints: &[(f64, f64)],
+    options: &CrossSectionOptions,
+) -> Result<CrossSection> {
+    let mut section = empty_section(waypoints, options)?;
+    let names: Vec<String> = if options.moments.is_empty() {
+        let mut names: Vec<String> = volume.sweeps.iter().flat_map(|s| s.moments.keys().cloned()).collect();
+        names.sort();
+        names.dedup();
+        names
+    } else {
+        options.moments.clone()
+    };
+    if let Some(missing) = names.iter().find(|n| volume.sweeps.iter().all(|s| s.get_moment(n).is_none())) {
+        return Err(RadishError::MissingVariable(missing.clone()));
+    }
+    let column_options = ColumnOptions {
+        moments: names.clone(),
+        heights: Some(section.height.clone()),
+        azimuth_tolerance: options.azimuth_tolerance,
+        georeference: options.georeference,
+    };
+
+    let shape = (section.height.len(), section.distance.len());
+    section.moments = names.iter().map(|n| (n.clone(), Array2::from_elem(shape, f32::NAN))).collect();
+    for (j, (&lon, &lat)) in section.longitude.iter().zip(&section.latitude).enumerate() {
+        let column = extract_column(volume, lon, lat, &column_options)?;
+        for (name, values) in column.moments {
+            if let Some(slice) = section.moments.get_mut(&name) {
+                slice.column_mut(j).assign(&ArrayView1::from(&values));
+            }
+        }
+    }
+    Ok(section)
+}
+
+/// Cross-section with the path samples and sorted height levels, and no moments yet
+fn empty_section(waypoints: &[(f64, f64)], options: &CrossSectionOptions) -> Result<CrossSection> {
+    let samples = sample_path(waypoints, options.spacing)?;
+    let mut height = options.heights.clone();
+    height.sort_by(f64::total_cmp);
+    Ok(CrossSection {
+        distance: samples.iter().map(|s| s.0).collect(),
+        longitude: samples.iter().map(|s| s.1).collect(),
+        latitude: samples.iter().map(|s| s.2).collect(),
+        height,
+        moments: HashMap::new(),
+    })
+}
+
+/// Linear interpolation of a column between the grid levels bracketing `h`, NaN outside them
+fn interpolate_level(levels: &[f64], column: &[f64], h: f64) -> f64 {
+    let upper = levels.partition_point(|&z| z < h);
+    if upper == levels.len() {
+        return f64::NAN;
+    }
+    if levels[upper] == h {
+        return column[upper];
+    }
+    if upper == 0 {
+        return f64::NAN;
+    }
+    let w = (h - levels[upper - 1]) / (levels[upper] - levels[upper - 1]);
+    column[upper - 1] + w * (column[upper] - column[upper - 1])
+}
+
+/// Extract a pseudo-RHI along `azimuth` (degrees) from the PPI sweeps of a volume
+///
+/// Rays are ordered by increasing elevation and keep their own azimuth,
+/// elevation and time. Gates beyond the range of a sweep, and moments a
+/// sweep does not have, are NaN.
+pub fn pseudo_rhi(volume: &VolumeData, azimuth: f32, options: &PseudoRhiOptions) -> Result<SweepData> {
+    // Nearest ray of each PPI sweep within the tolerance
+    let mut rays: Vec<(&SweepData, usize)> = volume
+        .sweeps
+        .iter()
+        .filter(|s| matches!(s.metadata.sweep_mode, SweepMode::Azimuth | SweepMode::Sector | SweepMode::ManualPpi))
+        .filter_map(|sweep| {
+            sweep
+                .coordinates
+                .azimuth
+                .iter()
+                .enumerate()
+                .map(|(i, &a)| (i, azimuth_distance(a, azimuth)))
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .filter(|&(_, d)| d <= options.azimuth_tolerance)
+                .map(|(i, _)| (sweep, i))
+        })
+        .collect();
+    if rays.is_empty() {
+        return Err(RadishError::General(format!(
+            "No PPI ray within {}° of azimuth {}",
+            options.azimuth_tolerance, azimuth
+        )));
+    }
+    rays.sort_by(|a, b| a.0.coordinates.elevation[a.1].total_cmp(&b.0.coordinates.elevation[b.1]));
+
+    let range = rays
+        .iter()
+        .map(|(s, _)| &s.coordinates.range)
+        .max_by_key(|r| r.len())
+        .cloned()
+        .unwrap_or_default();
+    let names: Vec<String> = if options.moments.is_empty() {
+        let mut names: Vec<String> = rays.iter().flat_map(|(s, _)| s.moments.keys().cloned()).collect();
+        names.sort();
+        names.dedup();
+        names
+    } else {
+        options.moments.clone()
+    };
+
+    // Gate of each ray nearest to each output range
+    let gates: Vec<Vec<Option<usize>>> = rays
+        .iter()
+        .map(|(sweep, _)| range.iter().map(|&r| nearest_range_gate(&sweep.coordinates.range, r)).collect())
+        .collect();
+
+    let mut moments = HashMap::new();
+    for name in &names {
+        let Some(template) = rays.iter().find_map(|(s, _)| s.get_moment(name)) else {
+            return Err(RadishError::MissingVariable(name.clone()));
+        };
+        let data = Array2::from_shape_fn((rays.len(), range.len()), |(k, j)| {
+            let (sweep, ray) = rays[k];
+            match (sweep.get_moment(name), gates[k][j]) {
+                (Some(m), Some(g)) => m.valid_value(ray, g).unwrap_or(f32::NAN),
+                _ => f32::NAN,
+            }
+        });
+        let mut moment = MomentData::new(name.clone(), template.units.clone(), data);
+        moment.long_name = template.long_name.clone();
+        moment.provenance = Some(Provenance::new(
+            "radish.transforms.cross_section.pseudo_rhi",
+            &[name],
+            &(azimuth, options),
+        ));
+        moments.insert(name.clone(), moment);
+    }
+
+    let coordinates = Coordinates::new(
+        rays.iter().map(|(s, i)| s.coordinates.epoch_time(*i)).collect(),
+        range,
+        rays.iter().map(|(s, i)| s.coordinates.azimuth[*i]).collect(),
+        rays.iter().map(|(s, i)| s.coordinates.elevation[*i]).collect(),
+    );
+    Ok(SweepData::new(
+        SweepMetadata::new(0, SweepMode::Elevation, azimuth as f64),
+        moments,
+        coordinates,
+    ))
+}
@@ -0,0 +1,259 @@
+//! Velocity dealiasing
+//!
+//! Doppler velocities are only measured modulo twice the Nyquist velocity.
+//! This module unfolds them using an environmental wind profile (from a
+//! sounding or a VAD retrieval) as the first guess, following the initial
+//! step of the 4D Doppler Dealiasing algorithm (James & Houze, 2001). Gates
+//! that cannot be matched to the first guess with confidence are resolved
+//! in a second pass from already dealiased neighbours.
+
+use ndarray::Array2;
+
+use crate::transforms::georeference::{beam_height, GeoreferenceOptions};
+use crate::{MomentData, Provenance, RadishError, Result, SweepData, VolumeData};
+
+/// Horizontal wind profile used as a first guess
+#[derive(Debug, Clone)]
+pub struct WindProfile {
+    /// Heights above mean sea level (meters), increasing
+    pub height: Vec<f64>,
+    /// Eastward wind component (m/s)
+    pub u: Vec<f64>,
+    /// Northward wind component (m/s)
+    pub v: Vec<f64>,
+}
+
+impl WindProfile {
+    /// Create a profile from heights and u/v components
+    pub fn new(height: Vec<f64>, u: Vec<f64>, v: Vec<f64>) -> Result<Self> {
+        if height.len() != u.len() || height.len() != v.len() {
+            return Err(RadishError::InvalidFormat(
+                "Wind profile height, u and v must have the same length".to_string(),
+            ));
+        }
+        if height.is_empty() {
+            return Err(RadishError::InvalidFormat("Wind profile is empty".to_string()));
+        }
+        if height.windows(2).any(|w| w[1] < w[0]) {
+            return Err(RadishError::InvalidFormat(
+                "Wind profile heights must be increasing".to_string(),
+            ));
+        }
+        Ok(Self { height, u, v })
+    }
+
+    /// Create a profile from wind speed (m/s) and meteorological direction (degrees from)
+    pub fn from_speed_direction(height: Vec<f64>, speed: &[f64], direction: &[f64]) -> Result<Self> {
+        let u = speed
+            .iter()
+            .zip(direction)
+            .map(|(s, d)| -s * d.to_radians().sin())
+            .collect();
+        let v = speed
+            .iter()
+            .zip(direction)
+            .map(|(s, d)| -s * d.to_radians().cos())
+            .collect();
+        Self::new(height, u, v)
+    }
+
+    /// Wind components at a height (meters MSL), linearly interpolated and
+    /// held constant beyond the ends of the profile
+    pub fn wind_at(&self, height: f64) -> (f64, f64) {
+        let n = self.height.len();
+        if height <= self.height[0] {
+            return (self.u[0], self.v[0]);
+        }
+        if height >= self.height[n - 1] {
+            return (self.u[n - 1], self.v[n - 1]);
+        }
+
+        let k = self.height.partition_point(|&h| h <= height);
+        let (h0, h1) = (self.height[k - 1], self.height[k]);
+        let w = if h1 > h0 { (height - h0) / (h1 - h0) } else { 0.0 };
+        (
+            self.u[k - 1] + w * (self.u[k] - self.u[k - 1]),
+            self.v[k - 1] + w * (self.v[k] - self.v[k - 1]),
+        )
+    }
+
+    /// Radial velocity (m/s) the profile implies at a gate
+    pub fn radial_velocity(&self, height: f64, azimuth: f64, elevation: f64) -> f64 {
+        let (u, v) = self.wind_at(height);
+        let az = azimuth.to_radians();
+        (u * az.sin() + v * az.cos()) * elevation.to_radians().cos()
+    }
+}
+
+/// Options for profile-constrained dealiasing
+#[derive(Debug, Clone)]
+pub struct DealiasOptions {
+    /// Input velocity moment
+    pub moment: String,
+    /// Output moment name for the dealiased velocity
+    pub output_moment: String,
+    /// Nyquist velocity (m/s) overriding the sweep and per-ray metadata
+    pub nyquist_velocity: Option<f64>,
+    /// Maximum difference from the first guess, as a fraction of the Nyquist
+    /// velocity, for a gate to be unfolded in the first pass
+    pub first_guess_threshold: f64,
+    /// Maximum difference from the neighbour mean, as a fraction of the
+    /// Nyquist velocity, for a gate to be unfolded in the second pass
+    pub neighbour_threshold: f64,
+    /// Half-width of the neighbourhood window (gates and rays) used in the second pass
+    pub window: usize,
+    /// Maximum number of second pass iterations
+    pub max_iterations: usize,
+}
+
+impl Default for DealiasOptions {
+    fn default() -> Self {
+        Self {
+            moment: "VRADH".to_string(),
+            output_moment: "VRADDH".to_string(),
+            nyquist_velocity: None,
+            first_guess_threshold: 0.5,
+            neighbour_threshold: 0.6,
+            window: 2,
+            max_iterations: 10,
+        }
+    }
+}
+
+/// Dealias every sweep in a volume using an environmental wind profile as first guess
+///
+/// Adds `options.output_moment` to each sweep containing `options.moment`.
+/// Gates that cannot be resolved are set to the moment's missing value.
+pub fn dealias_with_profile(
+    volume: &VolumeData,
+    profile: &WindProfile,
+    options: &DealiasOptions,
+) -> Result<VolumeData> {
+    let mut volume = volume.clone();
+    let radar_altitude = volume.metadata.altitude;
+
+    for sweep in &mut volume.sweeps {
+        if sweep.get_moment(&options.moment).is_none() {
+            continue;
+        }
+        let dealiased = dealias_sweep_with_profile(sweep, profile, radar_altitude, options)?;
+        sweep.moments.insert(options.output_moment.clone(), dealiased);
+    }
+
+    volume.history.record("radish.transforms.dealias.dealias_with_profile", &(options, profile));
+    Ok(volume)
+}
+
+/// Dealias a single sweep, returning the dealiased velocity moment
+pub fn dealias_sweep_with_profile(
+    sweep: &SweepData,
+    profile: &WindProfile,
+    radar_altitude: f64,
+    options: &DealiasOptions,
+) -> Result<MomentData> {
+    let moment = sweep
+        .get_moment(&options.moment)
+        .ok_or_else(|| RadishError::MissingVariable(options.moment.clone()))?;
+    // The options override the file; per-ray values override the sweep value
+    let nyquist = (0..sweep.num_rays())
+        .map(|i| {
+            options
+                .nyquist_velocity
+                .or_else(|| sweep.ray_nyquist_velocity(i))
+                .ok_or_else(|| RadishError::MissingAttribute("nyquist_velocity".to_string()))
+        })
+        .collect::<Result<Vec<f64>>>()?;
+
+    let geo = GeoreferenceOptions::default();
+    let coords = &sweep.coordinates;
+    let (num_rays, num_gates) = moment.shape();
+
+    // Pass 1: unfold against the profile first guess
+    let mut out = Array2::from_elem((num_rays, num_gates), f64::NAN);
+    let mut pending = Vec::new();
+    for i in 0..num_rays {
+        let az = coords.azimuth[i] as f64;
+        let el = coords.elevation[i] as f64;
+        for j in 0..num_gates {
+            let Some(v) = moment.valid_value(i, j) else {
+                continue;
+            };
+
+            let height = radar_altitude + beam_height(coords.range[j] as f64, el, &geo);
+            let guess = profile.radial_velocity(height, az, el);
+            let unfolded = unfold(v as f64, guess, nyquist[i]);
+            if (unfolded - guess).abs() <= options.first_guess_threshold * nyquist[i] {
+                out[[i, j]] = unfolded;
+            } else {
+                pending.push((i, j));
+            }
+        }
+    }
+
+    // Pass 2: resolve remaining gates from dealiased neighbours
+    for _ in 0..options.max_iterations {
+        let mut resolved_any = false;
+        pending.retain(|&(i, j)| {
+            let Some(reference) = neighbour_mean(&out, i, j, options.window) else {
+                return true;
+            };
+            let unfolded = unfold(moment.value(i, j) as f64, reference, nyquist[i]);
+            if (unfolded - reference).abs() <= options.neighbour_threshold * nyquist[i] {
+                out[[i, j]] = unfolded;
+                resolved_any = true;
+                false
+            } else {
+                true
+            }
+        });
+        if !resolved_any || pending.is_empty() {
+            break;
+        }
+    }
+
+    let missing = moment.missing_value();
+    let data = out.mapv(|v| if v.is_nan() { missing } else { v as f32 });
+
+    let mut dealiased = MomentData::new(options.output_moment.clone(), moment.units.clone(), data);
+    dealiased.standard_name = moment.standard_name.clone();
+    dealiased.long_name = Some(format!(
+        "{} (dealiased)",
+        moment.long_name.as_deref().unwrap_or(&moment.name)
+    ));
+    dealiased.fill_value = moment.physical_fill_value();
+    dealiased.provenance = Some(Provenance::new(
+        "radish.transforms.dealias.dealias_sweep_with_profile",
+        &[&options.moment],
+        &(options, profile),
+    ));
+    Ok(dealiased)
+}
+
+/// Unfold `v` by the number of Nyquist intervals that brings it closest to `reference`
+pub fn unfold(v: f64, reference: f64, nyquist: f64) -> f64 {
+    let interval = 2.0 * nyquist;
+    let n = ((reference - v) / interval).round();
+    v + n * interval
+}
+
+/// Mean of the already dealiased gates around (i, j), wrapping in azimuth
+fn neighbour_mean(data: &Array2<f64>, i: usize, j: usize, window: usize) -> Option<f64> {
+    let (num_rays, num_gates) = data.dim();
+    let mut sum = 0.0;
+    let mut count = 0usize;
+
+    for di in 0..=2 * window {
+        let ii = (i as isize + di as isize - window as isize).rem_euclid(num_rays as isize) as usize;
+        let j0 = j.saturating_sub(window);
+        let j1 = (j + window).min(num_gates - 1);
+        for jj in j0..=j1 {
+            let v = data[[ii, jj]];
+            if !v.is_nan() {
+                sum += v;
+                count += 1;
+            }
+        }
+    }
+
+    (count > 0).then(|| sum / count as f64)
+}
@@ -0,0 +1,417 @@
+//! Derived moments defined by expressions
+//!
+//! QC and retrieval pipelines often need simple gate-by-gate combinations
+//! of existing moments: linear reflectivity `10^(DBZH/10)`, a differential
+//! reflectivity `DBZH - DBZV`, a thresholded copy of a moment. Instead of
+//! writing ndarray code for each, [`DerivedMoments`] holds named
+//! definitions as expressions over moment names, evaluated for a sweep
+//! only when asked for.
+//!
+//! Expressions are made of:
+//!
+//! - numbers (`10`, `0.5`, `1e-3`) and moment names (`DBZH`, `RHOHV`)
+//! - `+`, `-`, `*`, `/`, `^` (power, right associative) and unary minus,
+//!   with the usual precedence, and parentheses
+//! - the functions `log10`, `ln`, `exp`, `sqrt`, `abs`, `min` and `max`
+//!
+//! A definition may use other definitions. A gate is missing in the result
+//! when any input is missing there, or when the expression is not finite.
+
+use std::collections::HashMap;
+
+use ndarray::Array2;
+
+use crate::{MomentData, Provenance, RadishError, Result, SweepData, VolumeData};
+
+/// Functions callable from expressions, with their number of arguments
+const FUNCTIONS: &[(&str, usize)] = &[
+    ("log10", 1),
+    ("ln", 1),
+    ("exp", 1),
+    ("sqrt", 1),
+    ("abs", 1),
+    ("min", 2),
+    ("max", 2),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Number(f64),
+    Moment(String),
+    Negate(Box<Expr>),
+    Binary(char, Box<Expr>, Box<Expr>),
+    Call(&'static str, Vec<Expr>),
+}
+
+impl Expr {
+    /// Moment names used by the expression, in order of first use
+    fn inputs(&self, names: &mut Vec<String>) {
+        match self {
+            Expr::Number(_) => {}
+            Expr::Moment(name) => {
+                if !names.contains(name) {
+                    names.push(name.clone());
+                }
+            }
+            Expr::Negate(e) => e.inputs(names),
+            Expr::Binary(_, a, b) => {
+                a.inputs(names);
+                b.inputs(names);
+            }
+            Expr::Call(_, args) => args.iter().for_each(|a| a.inputs(names)),
+        }
+    }
+
+    /// Value at a gate, given the values of the inputs there
+    fn eval(&self, value: &impl Fn(&str) -> f64) -> f64 {
+        match self {
+            Expr::Number(v) => *v,
+            Expr::Moment(name) => value(name),
+            Expr::Negate(e) => -e.eval(value),
+            Expr::Binary(op, a, b) => {
+                let (a, b) = (a.eval(value), b.eval(value));
+                match op {
+                    '+' => a + b,
+                    '-' => a - b,
+                    '*' => a * b,
+                    '/' => a / b,
+                    _ => a.powf(b),
+                }
+            }
+            Expr::Call(function, args) => {
+                let a = args[0].eval(value);
+                match *function {
+                    "log10" => a.log10(),
+                    "ln" => a.ln(),
+                    "exp" => a.exp(),
+                    "sqrt" => a.sqrt(),
+                    "abs" => a.abs(),
+                    "min" => a.min(args[1].eval(value)),
+                    _ => a.max(args[1].eval(value)),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Name(String),
+    Symbol(char),
+}
+
+/// Recursive descent parser over the tokens of an expression
+struct Parser<'a> {
+    expression: &'a str,
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(expression: &'a str) -> Result<Self> {
+        let mut parser = Self {
+            expression,
+            tokens: Vec::new(),
+            position: 0,
+        };
+        let mut chars = expression.char_indices().peekable();
+        while let Some(&(start, c)) = chars.peek() {
+            if c.is_whitespace() {
+                chars.next();
+            } else if c.is_ascii_digit() || c == '.' {
+                let mut end = start;
+                while let Some(&(i, c)) = chars.peek() {
+                    let exponent_sign = (c == '+' || c == '-') && expression[..i].ends_with(['e', 'E']);
+                    if !(c.is_ascii_digit() || c == '.' || c == 'e' || c == 'E' || exponent_sign) {
+                        break;
+                    }
+                    end = i + c.len_utf8();
+                    chars.next();
+                }
+                let text = &expression[start..end];
+                let value = text
+                    .parse()
+                    .map_err(|_| parser.error(&format!("invalid number '{}'", text)))?;
+                parser.tokens.push(Token::Number(value));
+            } else if c.is_alphabetic() || c == '_' {
+                let mut end = start;
+                while let Some(&(i, c)) = chars.peek() {
+                    if !(c.is_alphanumeric() || c == '_') {
+                        break;
+                    }
+                    end = i + c.len_utf8();
+                    chars.next();
+                }
+                parser.tokens.push(Token::Name(expression[start..end].to_string()));
+            } else if "+-*/^(),".contains(c) {
+                parser.tokens.push(Token::Symbol(c));
+                chars.next();
+            } else {
+                return Err(parser.error(&format!("unexpected '{}'", c)));
+            }
+        }
+        Ok(parser)
+    }
+
+    fn error(&self, message: &str) -> RadishError {
+        RadishError::InvalidFormat(format!("Expression '{}': {}", self.expression, message))
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn eat(&mut self, symbol: char) -> bool {
+        if self.peek() == Some(&Token::Symbol(symbol)) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, symbol: char) -> Result<()> {
+        if self.eat(symbol) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", symbol)))
+        }
+    }
+
+    fn parse(mut self) -> Result<Expr> {
+        let expr = self.sum()?;
+        match self.peek() {
+            None => Ok(expr),
+            Some(token) => Err(self.error(&format!("unexpected {:?}", token))),
+        }
+    }
+
+    fn sum(&mut self) -> Result<Expr> {
+        let mut expr = self.product()?;
+        loop {
+            let op = if self.eat('+') {
+                '+'
+            } else if self.eat('-') {
+                '-'
+            } else {
+                return Ok(expr);
+            };
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.product()?));
+        }
+    }
+
+    fn product(&mut self) -> Result<Expr> {
+        let mut expr = self.unary()?;
+        loop {
+            let op = if self.eat('*') {
+                '*'
+            } else if self.eat('/') {
+                '/'
+            } else {
+                return Ok(expr);
+            };
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        if self.eat('-') {
+            return Ok(Expr::Negate(Box::new(self.unary()?)));
+        }
+        let base = self.atom()?;
+        if self.eat('^') {
+            return Ok(Expr::Binary('^', Box::new(base), Box::new(self.unary()?)));
+        }
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> Result<Expr> {
+        let token = self.peek().cloned();
+        self.position += 1;
+        match token {
+            Some(Token::Number(v)) => Ok(Expr::Number(v)),
+            Some(Token::Symbol('(')) => {
+                let expr = self.sum()?;
+                self.expect(')')?;
+                Ok(expr)
+            }
+            Some(Token::Name(name)) if self.eat('(') => {
+                let &(function, arity) = FUNCTIONS
+                    .iter()
+                    .find(|(f, _)| *f == name)
+                    .ok_or_else(|| self.error(&format!("unknown function '{}'", name)))?;
+                let mut args = vec![self.sum()?];
+                while self.eat(',') {
+                    args.push(self.sum()?);
+                }
+                self.expect(')')?;
+                if args.len() != arity {
+                    return Err(self.error(&format!("{} takes {} argument(s), not {}", function, arity, args.len())));
+                }
+                Ok(Expr::Call(function, args))
+            }
+            Some(Token::Name(name)) => Ok(Expr::Moment(name)),
+            Some(token) => Err(self.error(&format!("unexpected {:?}", token))),
+            None => Err(self.error("unexpected end")),
+        }
+    }
+}
+
+/// A moment defined by an expression over other moments
+#[derive(Debug, Clone, PartialEq)]
+pub struct DerivedMoment {
+    /// Name of the derived moment
+    pub name: String,
+    /// Units of the derived moment
+    pub units: String,
+    /// Expression, as written
+    pub expression: String,
+    expr: Expr,
+}
+
+impl DerivedMoment {
+    /// Parse a definition, failing with `InvalidFormat` on a malformed expression
+    pub fn new(name: &str, units: &str, expression: &str) -> Result<Self> {
+        Ok(Self {
+            name: name.to_string(),
+            units: units.to_string(),
+            expression: expression.to_string(),
+            expr: Parser::new(expression)?.parse()?,
+        })
+    }
+
+    /// Moment names used by the expression, in order of first use
+    pub fn inputs(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        self.expr.inputs(&mut names);
+        names
+    }
+}
+
+/// Set of derived moment definitions
+///
+/// Definitions are kept in the order they were made; redefining a name
+/// replaces its definition in place.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DerivedMoments {
+    definitions: Vec<DerivedMoment>,
+}
+
+impl DerivedMoments {
+    /// A set without definitions
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a definition, failing with `InvalidFormat` on a malformed expression
+    pub fn define(mut self, name: &str, units: &str, expression: &str) -> Result<Self> {
+        self.insert(DerivedMoment::new(name, units, expression)?);
+        Ok(self)
+    }
+
+    /// Add a parsed definition
+    pub fn insert(&mut self, definition: DerivedMoment) {
+        match self.definitions.iter_mut().find(|d| d.name == definition.name) {
+            Some(existing) => *existing = definition,
+            None => self.definitions.push(definition),
+        }
+    }
+
+    /// Definition of a derived moment
+    pub fn get(&self, name: &str) -> Option<&DerivedMoment> {
+        self.definitions.iter().find(|d| d.name == name)
+    }
+
+    /// Names of the derived moments, in order of definition
+    pub fn names(&self) -> Vec<&str> {
+        self.definitions.iter().map(|d| d.name.as_str()).collect()
+    }
+
+    /// Evaluate a derived moment for a sweep
+    ///
+    /// Inputs are taken from the sweep's moments, or else evaluated from
+    /// their own definitions. Fails with `MissingVariable` for an undefined
+    /// name or an input found in neither, and with `General` for
+    /// definitions that depend on themselves.
+    pub fn evaluate(&self, sweep: &SweepData, name: &str) -> Result<MomentData> {
+        self.evaluate_with(sweep, name, &mut Vec::new())
+    }
+
+    fn evaluate_with(&self, sweep: &SweepData, name: &str, stack: &mut Vec<String>) -> Result<MomentData> {
+        let definition = self
+            .get(name)
+            .ok_or_else(|| RadishError::MissingVariable(name.to_string()))?;
+        if stack.iter().any(|n| n == name) {
+            return Err(RadishError::General(format!(
+                "Derived moment '{}' depends on itself ({} -> {})",
+                name,
+                stack.join(" -> "),
+                name
+            )));
+        }
+        stack.push(name.to_string());
+
+        let inputs = definition.inputs();
+        let mut values: HashMap<&str, Array2<f32>> = HashMap::new();
+        for input in &inputs {
+            let moment = match sweep.get_moment(input) {
+                Some(moment) => moment.clone(),
+                None if self.get(input).is_some() => self.evaluate_with(sweep, input, stack)?,
+                None => return Err(RadishError::MissingVariable(input.clone())),
+            };
+            let (num_rays, num_gates) = moment.shape();
+            let data = Array2::from_shape_fn((num_rays, num_gates), |(i, j)| {
+                moment.valid_value(i, j).unwrap_or(f32::NAN)
+            });
+            values.insert(input, data);
+        }
+        stack.pop();
+
+        let shape = (sweep.num_rays(), sweep.num_gates());
+        if let Some((input, _)) = values.iter().find(|(_, v)| v.dim() != shape) {
+            return Err(RadishError::General(format!(
+                "Moment '{}' does not match the sweep shape for derived moment '{}'",
+                input, name
+            )));
+        }
+        let data = Array2::from_shape_fn(shape, |(i, j)| {
+            let v = definition.expr.eval(&|input| values[input][[i, j]] as f64);
+            if v.is_finite() {
+                v as f32
+            } else {
+                f32::NAN
+            }
+        });
+
+        let mut moment = MomentData::new(name.to_string(), definition.units.clone(), data);
+        moment.provenance = Some(Provenance::new(
+            "radish.transforms.derived",
+            &inputs,
+            &definition.expression,
+        ));
+        Ok(moment)
+    }
+
+    /// Add every derived moment to the sweeps of a volume
+    ///
+    /// Definitions are evaluated in order, so later ones may use earlier
+    /// results. A sweep missing the inputs of a definition is left without
+    /// that moment; other errors fail the whole volume.
+    pub fn apply(&self, volume: &VolumeData) -> Result<VolumeData> {
+        let mut volume = volume.clone();
+        for sweep in &mut volume.sweeps {
+            for definition in &self.definitions {
+                match self.evaluate(sweep, &definition.name) {
+                    Ok(moment) => {
+                        sweep.moments.insert(definition.name.clone(), moment);
+                    }
+                    Err(RadishError::MissingVariable(_)) => {}
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+        volume.history.record("radish.transforms.derived.DerivedMoments::apply", self);
+        Ok(volume)
+    }
+}
@@ -0,0 +1,180 @@
+//! Dual-frequency ratio (DFR) from co-located radars
+//!
+//! The DFR is the difference (dB) between the reflectivity measured at a
+//! lower and at a higher frequency, e.g. Ka and W band. Outside Rayleigh
+//! scattering the higher frequency sees less reflectivity, so the DFR is
+//! related to the characteristic particle size. The second volume is first
+//! regridded onto the polar grid of the reference volume: the nearest sweep
+//! in fixed angle, the nearest ray in azimuth and linear interpolation in
+//! range.
+
+use ndarray::Array2;
+
+use crate::transforms::georeference::geographic_to_cartesian;
+use crate::model::coordinates::azimuth_distance;
+use crate::{MomentData, Provenance, RadishError, Result, SweepData, VolumeData};
+
+/// Name of the dual-frequency ratio moment
+pub const DFR: &str = "DFR";
+
+/// Options for dual-frequency ratio computation
+#[derive(Debug, Clone)]
+pub struct DfrOptions {
+    /// Reflectivity moment of the reference volume
+    pub reflectivity: String,
+    /// Reflectivity moment of the other volume (same name as the reference if `None`)
+    pub other_reflectivity: Option<String>,
+    /// Output moment name
+    pub output_moment: String,
+    /// Maximum fixed angle difference (degrees) between matched sweeps
+    pub elevation_tolerance: f64,
+    /// Maximum azimuth difference (degrees) between matched rays
+    pub azimuth_tolerance: f32,
+    /// Maximum time difference (seconds) between matched rays (unchecked if `None`)
+    pub max_time_difference: Option<f64>,
+    /// Maximum distance (meters) between the two radar sites
+    pub max_site_distance: f64,
+}
+
+impl Default for DfrOptions {
+    fn default() -> Self {
+        Self {
+            reflectivity: "DBZH".to_string(),
+            other_reflectivity: None,
+            output_moment: DFR.to_string(),
+            elevation_tolerance: 0.25,
+            azimuth_tolerance: 1.0,
+            max_time_difference: Some(60.0),
+            max_site_distance: 100.0,
+        }
+    }
+}
+
+/// Add a DFR moment to each sweep of `reference` with a matching sweep in `other`
+///
+/// The DFR is the lower-frequency minus the higher-frequency reflectivity
+/// when both volumes report their frequency, and reference minus other
+/// otherwise. Gates without a matching gate in `other` are missing.
+pub fn dual_frequency_ratio(reference: &VolumeData, other: &VolumeData, options: &DfrOptions) -> Result<VolumeData> {
+    let (a, b) = (&reference.metadata, &other.metadata);
+    let (dx, dy) = geographic_to_cartesian(b.longitude, b.latitude, a.longitude, a.latitude);
+    let separation = (dx * dx + dy * dy + (b.altitude - a.altitude).powi(2)).sqrt();
+    if separation > options.max_site_distance {
+        return Err(RadishError::General(format!(
+            "Radars are {:.0} m apart, more than the {:.0} m allowed for DFR",
+            separation, options.max_site_distance
+        )));
+    }
+    let sign = match (a.frequency, b.frequency) {
+        (Some(fa), Some(fb)) if fa > fb => -1.0,
+        _ => 1.0,
+    };
+
+    let other_name = options.other_reflectivity.as_ref().unwrap_or(&options.reflectivity);
+    let mut volume = reference.clone();
+    for sweep in &mut volume.sweeps {
+        let Some(refl) = sweep.get_moment(&options.reflectivity) else {
+            continue;
+        };
+        let Some(source) = matching_sweep(other, sweep.metadata.fixed_angle, options.elevation_tolerance) else {
+            continue;
+        };
+        if source.get_moment(other_name).is_none() {
+            continue;
+        }
+
+        let regridded = regrid_polar(source, other_name, sweep, options)?;
+        let mut dfr = Array2::from_elem(refl.shape(), f32::NAN);
+        for ((idx, &z), &z_other) in refl.data().indexed_iter().zip(regridded.iter()) {
+            if refl.is_valid(idx.0, idx.1) && !z_other.is_nan() {
+                dfr[idx] = sign * (z - z_other);
+            }
+        }
+
+        let mut moment = MomentData::new(options.output_moment.clone(), "dB".to_string(), dfr);
+        moment.long_name = Some("Dual-frequency ratio".to_string());
+        moment.provenance = Some(Provenance::new(
+            "radish.transforms.dfr.dual_frequency_ratio",
+            &[&options.reflectivity, other_name],
+            options,
+        ));
+        sweep.moments.insert(options.output_moment.clone(), moment);
+    }
+
+    volume.history.record("radish.transforms.dfr.dual_frequency_ratio", options);
+    Ok(volume)
+}
+
+/// Regrid a moment of `source` onto the rays and gates of `target`
+///
+/// Each target ray takes the nearest source ray in azimuth (within
+/// `azimuth_tolerance` and, if set, `max_time_difference`), interpolated
+/// linearly in range between valid gates. Unmatched gates are NaN.
+pub fn regrid_polar(source: &SweepData, moment: &str, target: &SweepData, options: &DfrOptions) -> Result<Array2<f32>> {
+    let data = source
+        .get_moment(moment)
+        .ok_or_else(|| RadishError::MissingVariable(moment.to_string()))?;
+    let src = &source.coordinates;
+    let dst = &target.coordinates;
+
+    let mut out = Array2::from_elem((dst.num_rays(), dst.num_gates()), f32::NAN);
+    for (i, &az) in dst.azimuth.iter().enumerate() {
+        let nearest = src
+            .azimuth
+            .iter()
+            .enumerate()
+            .filter(|&(k, _)| match (options.max_time_difference, dst.time.get(i), src.time.get(k)) {
+                (Some(max), Some(t0), Some(t1)) => (t0 - t1).abs() <= max,
+                _ => true,
+            })
+            .map(|(k, &a)| (k, azimuth_distance(a, az)))
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+        let Some((k, distance)) = nearest else {
+            continue;
+        };
+        if distance > options.azimuth_tolerance {
+            continue;
+        }
+
+        let row = data.data();
+        let row = row.row(k);
+        for (j, &r) in dst.range.iter().enumerate() {
+            out[[i, j]] = interpolate_range(&src.range, |g| row[g], |v| data.is_valid_value(v), r);
+        }
+    }
+
+    Ok(out)
+}
+
+/// Sweep of a volume closest in fixed angle, if within `tolerance` degrees
+fn matching_sweep(volume: &VolumeData, fixed_angle: f64, tolerance: f64) -> Option<&SweepData> {
+    volume
+        .sweeps
+        .iter()
+        .map(|s| (s, (s.metadata.fixed_angle - fixed_angle).abs()))
+        .filter(|&(_, d)| d <= tolerance)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(s, _)| s)
+}
+
+/// Linear interpolation of a ray at range `r` (NaN outside the gates or next to invalid gates)
+fn interpolate_range(ranges: &[f32], value: impl Fn(usize) -> f32, valid: impl Fn(f32) -> bool, r: f32) -> f32 {
+    let upper = ranges.partition_point(|&g| g < r);
+    if upper == 0 {
+        return match ranges.first() {
+            Some(&g) if g == r && valid(value(0)) => value(0),
+            _ => f32::NAN,
+        };
+    }
+    if upper == ranges.len() {
+        return f32::NAN;
+    }
+
+    let (lo, hi) = (upper - 1, upper);
+    let (a, b) = (value(lo), value(hi));
+    if !valid(a) || !valid(b) {
+        return f32::NAN;
+    }
+    let w = (r - ranges[lo]) / (ranges[hi] - ranges[lo]);
+    a + w * (b - a)
+}
@@ -0,0 +1,114 @@
+//! Coarsening of sweeps in range and azimuth
+//!
+//! Climatological processing rarely needs the native resolution, so blocks
+//! of consecutive gates (e.g. four 250 m gates into one 1 km gate) and/or
+//! rays are averaged into one. Moments in decibel units (dBZ, dB) are
+//! averaged in the linear domain, `10·log10(mean(10^(v/10)))`, so that a
+//! block's reflectivity matches its mean power rather than being biased
+//! low. Blocks with too few valid values are missing. The new gate range
+//! and ray angles are the means over each block.
+
+use std::collections::HashMap;
+
+use ndarray::Array2;
+
+use crate::transforms::ray_cleanup::{average_ray_coordinates, first_ray_metadata};
+use crate::{RadishError, Result, SweepData, VolumeData};
+
+/// Options for downsampling
+#[derive(Debug, Clone)]
+pub struct DownsampleOptions {
+    /// Number of consecutive gates averaged into one
+    pub gate_factor: usize,
+    /// Number of consecutive rays averaged into one
+    pub ray_factor: usize,
+    /// Fewest valid values, as a fraction of the block, for a valid average
+    pub min_valid_fraction: f32,
+    /// Average moments whose units start with "dB" in the linear domain
+    pub linear_db: bool,
+}
+
+impl Default for DownsampleOptions {
+    fn default() -> Self {
+        Self {
+            gate_factor: 4,
+            ray_factor: 1,
+            min_valid_fraction: 0.5,
+            linear_db: true,
+        }
+    }
+}
+
+/// Downsample every sweep of a volume
+pub fn downsample(volume: &VolumeData, options: &DownsampleOptions) -> Result<VolumeData> {
+    let mut out = volume.clone();
+    for sweep in &mut out.sweeps {
+        *sweep = downsample_sweep(sweep, options)?;
+    }
+    out.history.record("radish.transforms.downsample.downsample", options);
+    Ok(out)
+}
+
+/// Downsample a sweep
+///
+/// A trailing partial block of gates or rays is averaged on its own.
+pub fn downsample_sweep(sweep: &SweepData, options: &DownsampleOptions) -> Result<SweepData> {
+    if options.gate_factor == 0 || options.ray_factor == 0 {
+        return Err(RadishError::General("Downsampling factors must be at least 1".to_string()));
+    }
+    sweep.coordinates.validate()?;
+    let coords = &sweep.coordinates;
+    let rays: Vec<Vec<usize>> = (0..coords.num_rays())
+        .collect::<Vec<_>>()
+        .chunks(options.ray_factor)
+        .map(<[usize]>::to_vec)
+        .collect();
+    let gates: Vec<Vec<usize>> = (0..coords.num_gates())
+        .collect::<Vec<_>>()
+        .chunks(options.gate_factor)
+        .map(<[usize]>::to_vec)
+        .collect();
+
+    let mut coordinates = average_ray_coordinates(coords, &rays);
+    coordinates.range = gates
+        .iter()
+        .map(|g| g.iter().map(|&j| coords.range[j]).sum::<f32>() / g.len() as f32)
+        .collect();
+    if let Some(n) = &mut coordinates.n_gates_per_ray {
+        n.iter_mut().for_each(|n| *n = n.div_ceil(options.gate_factor));
+    }
+
+    let mut moments = HashMap::new();
+    for (name, moment) in &sweep.moments {
+        let logarithmic = options.linear_db && moment.units.starts_with("dB");
+        let missing = moment.missing_value();
+        let data = Array2::from_shape_fn((rays.len(), gates.len()), |(k, l)| {
+            let size = rays[k].len() * gates[l].len();
+            let (mut sum, mut count) = (0.0f64, 0usize);
+            for &i in &rays[k] {
+                for &j in &gates[l] {
+                    if let Some(v) = moment.valid_value(i, j) {
+                        sum += if logarithmic { 10f64.powf(v as f64 / 10.0) } else { v as f64 };
+                        count += 1;
+                    }
+                }
+            }
+            if count == 0 || (count as f32) < options.min_valid_fraction * size as f32 {
+                missing
+            } else if logarithmic {
+                (10.0 * (sum / count as f64).log10()) as f32
+            } else {
+                (sum / count as f64) as f32
+            }
+        });
+        moments.insert(name.clone(), moment.with_data(data));
+    }
+
+    let mut metadata = sweep.metadata.clone();
+    if let Some(resolution) = metadata.ray_angle_resolution.as_mut() {
+        *resolution *= options.ray_factor as f64;
+    }
+    let mut out = SweepData::new(metadata, moments, coordinates);
+    (out.ray_metadata, out.platform_motion) = first_ray_metadata(sweep, &rays);
+    Ok(out)
+}
@@ -0,0 +1,176 @@
+//! Correction of dual-PRF velocity unfolding errors
+//!
+//! Dual-PRF and staggered-PRT scans extend the Nyquist velocity to
+//! `Ve = N·Vh = (N+1)·Vl`, where `Vh` and `Vl` are the Nyquist velocities of
+//! the high and low PRF and `(N+1)/N` is the PRF ratio. Wherever a ray's
+//! velocity is aliased in its own PRF but the combination is wrong, the
+//! extended velocity is off by an even multiple of that PRF's Nyquist
+//! velocity, `2k·Vh` or `2k·Vl`, producing isolated outliers rather than
+//! folded regions. Following Joe & May (2003), each gate is compared with
+//! the median of its neighbours; an outlier is replaced by the candidate
+//! `v + 2k·Va` closest to the median if that candidate agrees with it.
+//! Only sweeps whose `prt_mode` is dual or staggered are corrected.
+
+use radish_types::{PrtMode, SweepMode};
+
+use crate::transforms::dealias::unfold;
+use crate::{MomentData, Provenance, RadishError, Result, SweepData, VolumeData};
+
+/// Options for dual-PRF error correction
+#[derive(Debug, Clone)]
+pub struct DualPrfOptions {
+    /// Velocity moment (m/s)
+    pub moment: String,
+    /// Output moment name; the input moment is replaced if `None`
+    pub output_moment: Option<String>,
+    /// Extended Nyquist velocity (m/s) overriding the sweep metadata
+    pub nyquist_velocity: Option<f64>,
+    /// Ratio of the high to the low PRF of `PrtMode::Dual` sweeps
+    ///
+    /// Staggered sweeps use the ratio of their mode (e.g. 3/2 for 2/3).
+    pub dual_prf_ratio: f64,
+    /// Whether the first ray uses the high PRF, rays alternating after it;
+    /// both PRFs are tried at every ray if `None`
+    pub first_ray_high_prf: Option<bool>,
+    /// Half-width of the neighbourhood window (gates and rays)
+    pub window: usize,
+    /// Fewest valid neighbours for a gate to be checked
+    pub min_neighbours: usize,
+    /// Deviation from the neighbour median, as a fraction of the low PRF
+    /// Nyquist velocity, above which a gate is an outlier
+    pub threshold: f64,
+}
+
+impl Default for DualPrfOptions {
+    fn default() -> Self {
+        Self {
+            moment: "VRADH".to_string(),
+            output_moment: None,
+            nyquist_velocity: None,
+            dual_prf_ratio: 4.0 / 3.0,
+            first_ray_high_prf: None,
+            window: 1,
+            min_neighbours: 4,
+            threshold: 1.0,
+        }
+    }
+}
+
+/// Correct dual-PRF errors in every dual-PRF or staggered-PRT sweep of a volume
+///
+/// Sweeps with a fixed or unknown PRT mode, or without the velocity moment,
+/// are left unchanged.
+pub fn correct_dual_prf(volume: &VolumeData, options: &DualPrfOptions) -> Result<VolumeData> {
+    let mut volume = volume.clone();
+    for sweep in &mut volume.sweeps {
+        if prf_ratio(sweep.metadata.prt_mode, options).is_none() || sweep.get_moment(&options.moment).is_none() {
+            continue;
+        }
+        let corrected = correct_dual_prf_sweep(sweep, options)?;
+        sweep.moments.insert(corrected.name.clone(), corrected);
+    }
+    volume.history.record("radish.transforms.dual_prf.correct_dual_prf", options);
+    Ok(volume)
+}
+
+/// Velocity moment of a sweep with dual-PRF errors corrected
+///
+/// Outliers without a candidate close enough to the neighbour median keep
+/// their value.
+pub fn correct_dual_prf_sweep(sweep: &SweepData, options: &DualPrfOptions) -> Result<MomentData> {
+    let moment = sweep
+        .get_moment(&options.moment)
+        .ok_or_else(|| RadishError::MissingVariable(options.moment.clone()))?;
+    let ratio = prf_ratio(sweep.metadata.prt_mode, options).ok_or_else(|| {
+        RadishError::Unsupported(format!(
+            "Sweep {} is not a dual-PRF or staggered-PRT sweep",
+            sweep.metadata.sweep_number
+        ))
+    })?;
+    if ratio <= 1.0 {
+        return Err(RadishError::General(format!("PRF ratio {} must be greater than 1", ratio)));
+    }
+    let extended = options
+        .nyquist_velocity
+        .or(sweep.metadata.nyquist_velocity)
+        .ok_or_else(|| RadishError::MissingAttribute("nyquist_velocity".to_string()))?;
+    // Ve = N·Vh with ratio (N+1)/N
+    let n = 1.0 / (ratio - 1.0);
+    let (high, low) = (extended / n, extended / (n + 1.0));
+    let max_multiple = (extended / low).ceil() as i32;
+    let tolerance = options.threshold * low;
+
+    let (num_rays, num_gates) = moment.shape();
+    let wrap = matches!(sweep.metadata.sweep_mode, SweepMode::Azimuth | SweepMode::ManualPpi);
+    let mut out = moment.data().into_owned();
+    let mut neighbours = Vec::new();
+    for i in 0..num_rays {
+        let nyquists: &[f64] = match options.first_ray_high_prf {
+            Some(first_high) if (i % 2 == 0) == first_high => &[high],
+            Some(_) => &[low],
+            None => &[high, low],
+        };
+        for j in 0..num_gates {
+            let Some(v) = moment.valid_value(i, j) else {
+                continue;
+            };
+            let v = v as f64;
+            neighbours.clear();
+            for di in -(options.window as isize)..=options.window as isize {
+                let mut ii = i as isize + di;
+                if wrap {
+                    ii = ii.rem_euclid(num_rays as isize);
+                } else if !(0..num_rays as isize).contains(&ii) {
+                    continue;
+                }
+                let ii = ii as usize;
+                for jj in j.saturating_sub(options.window)..=(j + options.window).min(num_gates - 1) {
+                    if (ii, jj) == (i, j) {
+                        continue;
+                    }
+                    if let Some(w) = moment.valid_value(ii, jj) {
+                        // Neighbours folded across the extended interval are brought next to the gate
+                        neighbours.push(unfold(w as f64, v, extended));
+                    }
+                }
+            }
+            if neighbours.len() < options.min_neighbours.max(1) {
+                continue;
+            }
+            neighbours.sort_by(f64::total_cmp);
+            let reference = neighbours[neighbours.len() / 2];
+            if (v - reference).abs() <= tolerance {
+                continue;
+            }
+            let best = nyquists
+                .iter()
+                .flat_map(|&va| (-max_multiple..=max_multiple).map(move |k| v + 2.0 * k as f64 * va))
+                .min_by(|a, b| (a - reference).abs().total_cmp(&(b - reference).abs()));
+            if let Some(c) = best.filter(|c| (c - reference).abs() <= tolerance) {
+                out[[i, j]] = unfold(c, 0.0, extended) as f32;
+            }
+        }
+    }
+
+    let mut corrected = moment.with_data(out);
+    if let Some(name) = &options.output_moment {
+        corrected.name = name.clone();
+    }
+    corrected.provenance = Some(Provenance::new(
+        "radish.transforms.dual_prf.correct_dual_prf_sweep",
+        &[&options.moment],
+        options,
+    ));
+    Ok(corrected)
+}
+
+/// Ratio of the high to the low PRF of a PRT mode, `None` for fixed or unknown PRT
+fn prf_ratio(mode: Option<PrtMode>, options: &DualPrfOptions) -> Option<f64> {
+    match mode? {
+        PrtMode::Fixed => None,
+        PrtMode::Dual => Some(options.dual_prf_ratio),
+        PrtMode::Staggered2_3 => Some(3.0 / 2.0),
+        PrtMode::Staggered3_4 => Some(4.0 / 3.0),
+        PrtMode::Staggered4_5 => Some(5.0 / 4.0),
+    }
+}
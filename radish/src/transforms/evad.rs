@@ -0,0 +1,214 @@
+//! Extended velocity-azimuth display (EVAD) wind retrieval
+//!
+//! Within a layer, the wind is assumed linear in the horizontal,
+//! `u = u0 + ux·x + uy·y`, `v = v0 + vx·x + vy·y`, so the radial velocity
+//! of a gate at azimuth φ, elevation θ and horizontal distance `R = r·cosθ`
+//! is
+//!
+//! `Vr = cosθ·(u0·sinφ + v0·cosφ) + (R·cosθ/2)·(D - St·cos2φ + Sh·sin2φ) + W·sinθ`
+//!
+//! with divergence `D = ux + vy`, stretching deformation `St = ux - vy`,
+//! shearing deformation `Sh = uy + vx` and `W = w + Vt` the vertical motion
+//! of the scatterers (air motion plus fall speed, negative downwards). A
+//! single sweep cannot separate `D` from `W`, but gates of the same layer
+//! seen at several elevations can: following Matejka & Srivastava (1991),
+//! the six parameters of each height layer are fitted by least squares to
+//! every gate of the volume in that layer, and their standard errors follow
+//! from the residual variance.
+
+use crate::transforms::dealias::WindProfile;
+use crate::transforms::georeference::{beam_height, GeoreferenceOptions};
+use crate::transforms::sun::solve;
+use crate::{RadishError, Result, VolumeData};
+
+/// Options for EVAD retrieval
+#[derive(Debug, Clone)]
+pub struct EvadOptions {
+    /// Radial velocity moment (m/s), dealiased if the winds exceed the Nyquist velocity
+    pub moment: String,
+    /// Depth (meters) of the height layers
+    pub layer_depth: f64,
+    /// Top (meters above the radar) of the highest layer
+    pub max_height: f64,
+    /// Sweeps with fixed angles outside this range (degrees) are not used
+    pub elevation_range: (f64, f64),
+    /// Gates closer than this (meters) are not used
+    pub min_range: f32,
+    /// Gates farther than this (meters) are not used
+    pub max_range: f32,
+    /// Fewest gates for a layer to be retrieved
+    pub min_points: usize,
+    /// Beam propagation model
+    pub georeference: GeoreferenceOptions,
+}
+
+impl Default for EvadOptions {
+    fn default() -> Self {
+        Self {
+            moment: "VRADH".to_string(),
+            layer_depth: 500.0,
+            max_height: 10_000.0,
+            elevation_range: (0.5, 45.0),
+            min_range: 2000.0,
+            max_range: 60_000.0,
+            min_points: 100,
+            georeference: GeoreferenceOptions::default(),
+        }
+    }
+}
+
+/// Kinematic profile retrieved by EVAD, one value per height layer
+///
+/// Layers without enough gates, or whose fit is singular, are NaN.
+#[derive(Debug, Clone)]
+pub struct EvadProfile {
+    /// Layer centers (meters above mean sea level)
+    pub height: Vec<f64>,
+    /// Eastward wind component (m/s)
+    pub u: Vec<f64>,
+    /// Northward wind component (m/s)
+    pub v: Vec<f64>,
+    /// Horizontal divergence (1/s)
+    pub divergence: Vec<f64>,
+    /// Stretching deformation (1/s)
+    pub stretching_deformation: Vec<f64>,
+    /// Shearing deformation (1/s)
+    pub shearing_deformation: Vec<f64>,
+    /// Vertical velocity of the scatterers (m/s, air motion plus fall speed)
+    pub vertical_velocity: Vec<f64>,
+    /// Standard errors of u, v, divergence, stretching and shearing
+    /// deformation and vertical velocity, in that order
+    pub errors: Vec<[f64; 6]>,
+    /// RMS residual (m/s) of the fit
+    pub rms_residual: Vec<f64>,
+    /// Number of gates in each layer
+    pub points: Vec<usize>,
+}
+
+impl EvadProfile {
+    /// Horizontal wind of the retrieved layers, e.g. as a dealiasing first guess
+    pub fn to_wind_profile(&self) -> Result<WindProfile> {
+        let layers: Vec<usize> = (0..self.height.len()).filter(|&k| self.u[k].is_finite()).collect();
+        WindProfile::new(
+            layers.iter().map(|&k| self.height[k]).collect(),
+            layers.iter().map(|&k| self.u[k]).collect(),
+            layers.iter().map(|&k| self.v[k]).collect(),
+        )
+    }
+}
+
+/// Normal equations of one layer
+#[derive(Clone, Default)]
+struct LayerSums {
+    ata: [[f64; 6]; 6],
+    atb: [f64; 6],
+    btb: f64,
+    points: usize,
+}
+
+/// Retrieve wind, divergence, deformation and vertical motion profiles from a volume
+pub fn evad(volume: &VolumeData, options: &EvadOptions) -> Result<EvadProfile> {
+    if options.layer_depth <= 0.0 || options.max_height <= 0.0 {
+        return Err(RadishError::General(
+            "EVAD layer depth and maximum height must be positive".to_string(),
+        ));
+    }
+    let num_layers = (options.max_height / options.layer_depth).ceil() as usize;
+    let mut layers = vec![LayerSums::default(); num_layers];
+    let (min_el, max_el) = options.elevation_range;
+    let mut found = false;
+
+    for sweep in &volume.sweeps {
+        let Some(moment) = sweep.get_moment(&options.moment) else {
+            continue;
+        };
+        if !(min_el..=max_el).contains(&sweep.metadata.fixed_angle) {
+            continue;
+        }
+        found = true;
+        let coords = &sweep.coordinates;
+        for (i, row) in moment.data().rows().into_iter().enumerate() {
+            let (azimuth, elevation) = (coords.azimuth[i] as f64, coords.elevation[i] as f64);
+            if !azimuth.is_finite() || !elevation.is_finite() {
+                continue;
+            }
+            for (j, &vr) in row.iter().enumerate() {
+                let r = coords.range[j];
+                if !moment.is_valid(i, j) || r < options.min_range || r > options.max_range {
+                    continue;
+                }
+                let height = beam_height(r as f64, elevation, &options.georeference);
+                let k = (height / options.layer_depth).floor();
+                if k < 0.0 || k >= num_layers as f64 {
+                    continue;
+                }
+                let (az, el) = (azimuth.to_radians(), elevation.to_radians());
+                let half = r as f64 * el.cos() * el.cos() / 2.0;
+                let a = [
+                    el.cos() * az.sin(),
+                    el.cos() * az.cos(),
+                    half,
+                    -half * (2.0 * az).cos(),
+                    half * (2.0 * az).sin(),
+                    el.sin(),
+                ];
+                let layer = &mut layers[k as usize];
+                for ((row, b), ap) in layer.ata.iter_mut().zip(&mut layer.atb).zip(a) {
+                    for (value, aq) in row.iter_mut().zip(a) {
+                        *value += ap * aq;
+                    }
+                    *b += ap * vr as f64;
+                }
+                layer.btb += (vr as f64).powi(2);
+                layer.points += 1;
+            }
+        }
+    }
+    if !found {
+        return Err(RadishError::MissingVariable(options.moment.clone()));
+    }
+
+    let mut profile = EvadProfile {
+        height: (0..num_layers)
+            .map(|k| volume.metadata.altitude + (k as f64 + 0.5) * options.layer_depth)
+            .collect(),
+        u: vec![f64::NAN; num_layers],
+        v: vec![f64::NAN; num_layers],
+        divergence: vec![f64::NAN; num_layers],
+        stretching_deformation: vec![f64::NAN; num_layers],
+        shearing_deformation: vec![f64::NAN; num_layers],
+        vertical_velocity: vec![f64::NAN; num_layers],
+        errors: vec![[f64::NAN; 6]; num_layers],
+        rms_residual: vec![f64::NAN; num_layers],
+        points: layers.iter().map(|l| l.points).collect(),
+    };
+    for (k, layer) in layers.iter().enumerate() {
+        if layer.points < options.min_points.max(7) {
+            continue;
+        }
+        let Some(x) = solve(layer.ata, layer.atb) else {
+            continue;
+        };
+        // Residual sum of squares from the normal equations
+        let rss = (layer.btb - x.iter().zip(&layer.atb).map(|(p, b)| p * b).sum::<f64>()).max(0.0);
+        let variance = rss / (layer.points - 6) as f64;
+        let mut errors = [f64::NAN; 6];
+        for (p, error) in errors.iter_mut().enumerate() {
+            let mut unit = [0.0; 6];
+            unit[p] = 1.0;
+            if let Some(column) = solve(layer.ata, unit) {
+                *error = (variance * column[p]).sqrt();
+            }
+        }
+        let [u, v, div, stretch, shear, w] = x;
+        profile.u[k] = u;
+        profile.v[k] = v;
+        profile.divergence[k] = div;
+        profile.stretching_deformation[k] = stretch;
+        profile.shearing_deformation[k] = shear;
+        profile.vertical_velocity[k] = w;
+        profile.errors[k] = errors;
+        profile.rms_residual[k] = (rss / layer.points as f64).sqrt();
+    }
+    Ok(profile)
+}
@@ -0,0 +1,182 @@
+//! Gate filters: per-sweep masks of excluded gates
+//!
+//! A [`GateFilter`] records which gates of a volume are excluded by a set
+//! of quality criteria. Criteria are added one at a time (thresholds on a
+//! moment, invalid values, arbitrary masks) and the filter is then applied
+//! to any moments, replacing excluded gates by their missing value or
+//! excluding them through the moments' validity masks. QC transforms build
+//! filters so that their criteria can be combined before censoring.
+
+use ndarray::Array2;
+
+use crate::{RadishError, Result, VolumeData};
+
+/// Excluded gates of each sweep of a volume
+#[derive(Debug, Clone, PartialEq)]
+pub struct GateFilter {
+    excluded: Vec<Array2<bool>>,
+}
+
+impl GateFilter {
+    /// A filter excluding no gate of `volume`
+    pub fn new(volume: &VolumeData) -> Self {
+        Self {
+            excluded: volume
+                .sweeps
+                .iter()
+                .map(|s| Array2::from_elem((s.num_rays(), s.num_gates()), false))
+                .collect(),
+        }
+    }
+
+    /// Number of sweeps of the filter
+    pub fn num_sweeps(&self) -> usize {
+        self.excluded.len()
+    }
+
+    /// Mask of the excluded gates of a sweep [rays × gates]
+    pub fn excluded(&self, sweep: usize) -> Option<&Array2<bool>> {
+        self.excluded.get(sweep)
+    }
+
+    /// Whether a gate is excluded
+    pub fn is_excluded(&self, sweep: usize, ray: usize, gate: usize) -> bool {
+        self.excluded
+            .get(sweep)
+            .and_then(|m| m.get((ray, gate)))
+            .copied()
+            .unwrap_or(false)
+    }
+
+    /// Total number of excluded gates
+    pub fn num_excluded(&self) -> usize {
+        self.excluded.iter().map(|m| m.iter().filter(|&&e| e).count()).sum()
+    }
+
+    /// Exclude the gates where a moment satisfies `predicate`
+    ///
+    /// Sweeps without the moment are left unchanged; it is an error if no
+    /// sweep has it. The predicate sees invalid values as NaN.
+    pub fn exclude_where(&mut self, volume: &VolumeData, moment: &str, predicate: impl Fn(f32) -> bool) -> Result<()> {
+        self.check(volume)?;
+        let mut found = false;
+        for (mask, sweep) in self.excluded.iter_mut().zip(&volume.sweeps) {
+            let Some(m) = sweep.get_moment(moment) else {
+                continue;
+            };
+            found = true;
+            ndarray::Zip::indexed(mask).for_each(|(i, j), e| {
+                if predicate(m.valid_value(i, j).unwrap_or(f32::NAN)) {
+                    *e = true;
+                }
+            });
+        }
+        if found {
+            Ok(())
+        } else {
+            Err(RadishError::MissingVariable(moment.to_string()))
+        }
+    }
+
+    /// Exclude the gates where a moment is below `value`
+    pub fn exclude_below(&mut self, volume: &VolumeData, moment: &str, value: f32) -> Result<()> {
+        self.exclude_where(volume, moment, |v| v < value)
+    }
+
+    /// Exclude the gates where a moment is above `value`
+    pub fn exclude_above(&mut self, volume: &VolumeData, moment: &str, value: f32) -> Result<()> {
+        self.exclude_where(volume, moment, |v| v > value)
+    }
+
+    /// Exclude the gates where a moment is missing or invalid
+    pub fn exclude_invalid(&mut self, volume: &VolumeData, moment: &str) -> Result<()> {
+        self.exclude_where(volume, moment, f32::is_nan)
+    }
+
+    /// Exclude the gates of a sweep where `mask` is true
+    pub fn exclude_mask(&mut self, sweep: usize, mask: &Array2<bool>) -> Result<()> {
+        let excluded = self
+            .excluded
+            .get_mut(sweep)
+            .ok_or(RadishError::InvalidSweepIndex(sweep))?;
+        if excluded.dim() != mask.dim() {
+            return Err(RadishError::General(format!(
+                "Mask shape {:?} does not match sweep {} shape {:?}",
+                mask.dim(),
+                sweep,
+                excluded.dim()
+            )));
+        }
+        ndarray::Zip::from(excluded).and(mask).for_each(|e, &m| *e |= m);
+        Ok(())
+    }
+
+    /// Exclude every gate excluded by another filter of the same volume
+    pub fn union(&mut self, other: &GateFilter) -> Result<()> {
+        for (k, mask) in other.excluded.iter().enumerate() {
+            self.exclude_mask(k, mask)?;
+        }
+        Ok(())
+    }
+
+    /// Replace the excluded gates of `moments` (all moments if empty) by their missing value
+    pub fn apply(&self, volume: &VolumeData, moments: &[String]) -> Result<VolumeData> {
+        self.check(volume)?;
+        let mut out = volume.clone();
+        for (mask, sweep) in self.excluded.iter().zip(&mut out.sweeps) {
+            for (name, moment) in sweep.moments.iter_mut() {
+                if !moments.is_empty() && !moments.contains(name) {
+                    continue;
+                }
+                let missing = moment.missing_value();
+                ndarray::Zip::from(moment.data_mut()).and(mask).for_each(|v, &e| {
+                    if e {
+                        *v = missing;
+                    }
+                });
+            }
+        }
+        out.history.record(
+            "radish.transforms.gatefilter.GateFilter::apply",
+            &(moments, self.num_excluded()),
+        );
+        Ok(out)
+    }
+
+    /// Exclude the gates of `moments` (all moments if empty) through their validity masks
+    ///
+    /// Unlike [`Self::apply`], the values of excluded gates are kept.
+    pub fn apply_mask(&self, volume: &VolumeData, moments: &[String]) -> Result<VolumeData> {
+        self.check(volume)?;
+        let mut out = volume.clone();
+        for (mask, sweep) in self.excluded.iter().zip(&mut out.sweeps) {
+            for (name, moment) in sweep.moments.iter_mut() {
+                if moments.is_empty() || moments.contains(name) {
+                    moment.exclude(mask)?;
+                }
+            }
+        }
+        out.history.record(
+            "radish.transforms.gatefilter.GateFilter::apply_mask",
+            &(moments, self.num_excluded()),
+        );
+        Ok(out)
+    }
+
+    /// Check that the filter matches the sweeps of a volume
+    fn check(&self, volume: &VolumeData) -> Result<()> {
+        let matches = self.excluded.len() == volume.sweeps.len()
+            && self
+                .excluded
+                .iter()
+                .zip(&volume.sweeps)
+                .all(|(m, s)| m.dim() == (s.num_rays(), s.num_gates()));
+        if matches {
+            Ok(())
+        } else {
+            Err(RadishError::General(
+                "Gate filter does not match the volume sweeps".to_string(),
+            ))
+        }
+    }
+}
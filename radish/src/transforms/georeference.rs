@@ -1,12 +1,51 @@
-/// Georeferencing utilities (stub for future implementation)
+//! Georeferencing utilities
+//!
+//! Attaches per-gate Cartesian and geographic coordinates to sweeps and
+//! volumes. The beam geometry and map projections live in
+//! [`crate::model::geometry`] and are re-exported here.
 
-use crate::{Result, VolumeData};
+use crate::{Result, SweepData, VolumeData};
 
-/// Georeference radar data (placeholder)
+pub use crate::model::geometry::*;
+
+/// Georeference a single sweep in place, storing x/y/z on its coordinates
 ///
-/// This will convert polar coordinates (azimuth, elevation, range) to
-/// geographic coordinates (latitude, longitude, altitude).
+/// Cached longitude, latitude and altitude are dropped, as they belong to
+/// the previous x/y/z.
+pub fn georeference_sweep(sweep: &mut SweepData, options: &GeoreferenceOptions) -> Result<()> {
+    sweep.coordinates.validate()?;
+
+    let (x, y, z) = compute_gate_xyz(&sweep.coordinates, options);
+    sweep.coordinates.clear_georeference();
+    sweep.coordinates.gate_x = Some(x);
+    sweep.coordinates.gate_y = Some(y);
+    sweep.coordinates.gate_z = Some(z);
+    Ok(())
+}
+
+/// Georeference radar data
+///
+/// Returns a copy of the volume with per-gate x/y/z (meters relative to the
+/// radar), longitude, latitude and altitude attached to every sweep's
+/// coordinates, using the standard 4/3 effective Earth radius model. For
+/// lazy, on-demand computation use [`Coordinates::gate_xyz`] and
+/// [`Coordinates::gate_geographic`] instead.
 pub fn georeference(volume: &VolumeData) -> Result<VolumeData> {
-    // TODO: Implement georeferencing
-    Ok(volume.clone())
+    georeference_with_options(volume, &GeoreferenceOptions::default())
+}
+
+/// Georeference radar data with custom propagation model parameters
+pub fn georeference_with_options(
+    volume: &VolumeData,
+    options: &GeoreferenceOptions,
+) -> Result<VolumeData> {
+    let mut volume = volume.clone();
+    let meta = &volume.metadata;
+    let (lon, lat, alt) = (meta.longitude, meta.latitude, meta.altitude);
+    for sweep in &mut volume.sweeps {
+        georeference_sweep(sweep, options)?;
+        sweep.coordinates.gate_geographic(lon, lat, alt);
+    }
+    volume.history.record("radish.transforms.georeference.georeference_with_options", options);
+    Ok(volume)
 }
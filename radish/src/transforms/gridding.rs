@@ -0,0 +1,323 @@
+//! Interpolation of radar volumes onto regular Cartesian grids
+//!
+//! Every valid gate of the volume is georeferenced and projected into the
+//! grid projection; each grid point then combines the gates within its
+//! radius of influence (ROI) using the selected weighting function.
+
+use std::collections::HashMap;
+
+use ndarray::Array3;
+
+use crate::transforms::georeference::{compute_gate_xyz, GeoreferenceOptions, Projection};
+use crate::{GridField, GriddedData, RadishError, Result, VolumeData};
+
+/// Grid attribute holding the longitude (degrees) of the radar a grid was built from
+pub const RADAR_LONGITUDE: &str = "radar_longitude";
+
+/// Grid attribute holding the latitude (degrees) of the radar a grid was built from
+pub const RADAR_LATITUDE: &str = "radar_latitude";
+
+/// Weighting of gates within the radius of influence
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Weighting {
+    /// Value of the nearest gate
+    Nearest,
+    /// Cressman: (R² - d²) / (R² + d²)
+    Cressman,
+    /// Barnes: exp(-4 d² / R²)
+    #[default]
+    Barnes,
+}
+
+/// Radius of influence of a grid point
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RadiusOfInfluence {
+    /// Fixed radius (meters)
+    Constant(f64),
+    /// Radius growing with distance from the radar to match the beam width:
+    /// `max(min_radius, distance * beam_width)`, with the beam width in degrees
+    DistanceBeam { beam_width: f64, min_radius: f64 },
+}
+
+impl RadiusOfInfluence {
+    /// Radius (meters) at a distance (meters) from the radar
+    pub fn radius(&self, distance: f64) -> f64 {
+        match *self {
+            RadiusOfInfluence::Constant(r) => r,
+            RadiusOfInfluence::DistanceBeam { beam_width, min_radius } => {
+                (distance * beam_width.to_radians()).max(min_radius)
+            }
+        }
+    }
+}
+
+/// Options for gridding a volume
+#[derive(Debug, Clone)]
+pub struct GridOptions {
+    /// Grid projection; azimuthal equidistant centered on the radar if `None`
+    pub projection: Option<Projection>,
+    /// Grid x limits (projected units) of the first and last column
+    pub x_limits: (f64, f64),
+    /// Grid y limits (projected units) of the first and last row
+    pub y_limits: (f64, f64),
+    /// Grid level limits (meters above mean sea level)
+    pub z_limits: (f64, f64),
+    /// Grid shape (nz, ny, nx)
+    pub shape: (usize, usize, usize),
+    /// Moments to grid (all moments if empty)
+    pub moments: Vec<String>,
+    /// Gate weighting function
+    pub weighting: Weighting,
+    /// Radius of influence
+    pub roi: RadiusOfInfluence,
+}
+
+impl Default for GridOptions {
+    fn default() -> Self {
+        Self {
+            projection: None,
+            x_limits: (-150_000.0, 150_000.0),
+            y_limits: (-150_000.0, 150_000.0),
+            z_limits: (500.0, 15_500.0),
+            shape: (31, 301, 301),
+            moments: Vec::new(),
+            weighting: Weighting::default(),
+            roi: RadiusOfInfluence::DistanceBeam {
+                beam_width: 1.0,
+                min_radius: 500.0,
+            },
+        }
+    }
+}
+
+/// Evenly spaced axis from `limits.0` to `limits.1` with `n` points
+pub fn grid_axis(limits: (f64, f64), n: usize) -> Vec<f64> {
+    match n {
+        0 => Vec::new(),
+        1 => vec![limits.0],
+        _ => {
+            let step = (limits.1 - limits.0) / (n - 1) as f64;
+            (0..n).map(|i| limits.0 + i as f64 * step).collect()
+        }
+    }
+}
+
+/// Interpolate a volume onto a regular grid
+///
+/// Grid levels are heights above mean sea level. Grid points with no valid
+/// gate within their radius of influence are NaN.
+pub fn grid_volume(volume: &VolumeData, options: &GridOptions) -> Result<GriddedData> {
+    let (nz, ny, nx) = options.shape;
+    if nx == 0 || ny == 0 || nz == 0 {
+        return Err(RadishError::General("Grid shape must be non-empty".to_string()));
+    }
+
+    let meta = &volume.metadata;
+    let radar_projection = Projection::aeqd(meta.longitude, meta.latitude);
+    let projection = options.projection.unwrap_or(radar_projection);
+    let (radar_x, radar_y) = projection.forward(meta.longitude, meta.latitude);
+
+    let names: Vec<String> = if options.moments.is_empty() {
+        let mut names: Vec<String> = volume
+            .sweeps
+            .iter()
+            .flat_map(|s| s.moments.keys().cloned())
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    } else {
+        options.moments.clone()
+    };
+
+    let gates = collect_gates(volume, &names, &radar_projection, &projection)?;
+
+    let x = grid_axis(options.x_limits, nx);
+    let y = grid_axis(options.y_limits, ny);
+    let z = grid_axis(options.z_limits, nz);
+
+    // Bucket gates for neighbour searches
+    let max_distance = gates
+        .points
+        .iter()
+        .map(|p| ((p[0] - radar_x).powi(2) + (p[1] - radar_y).powi(2)).sqrt())
+        .fold(0.0, f64::max);
+    let cell = options.roi.radius(max_distance).max(1.0);
+    let index = GateIndex::new(&gates.points, cell);
+
+    let mut data: Vec<Array3<f32>> = names
+        .iter()
+        .map(|_| Array3::from_elem((nz, ny, nx), f32::NAN))
+        .collect();
+    let mut sums = vec![0.0f64; names.len()];
+    let mut weights = vec![0.0f64; names.len()];
+    let mut nearest = vec![(f64::INFINITY, f32::NAN); names.len()];
+
+    for (k, &gz) in z.iter().enumerate() {
+        for (j, &gy) in y.iter().enumerate() {
+            for (i, &gx) in x.iter().enumerate() {
+                let distance = ((gx - radar_x).powi(2) + (gy - radar_y).powi(2)).sqrt();
+                let roi = options.roi.radius(distance);
+                let roi2 = roi * roi;
+
+                sums.fill(0.0);
+                weights.fill(0.0);
+                nearest.fill((f64::INFINITY, f32::NAN));
+
+                index.for_each_near([gx, gy, gz], roi, |g| {
+                    let p = gates.points[g];
+                    let d2 = (p[0] - gx).powi(2) + (p[1] - gy).powi(2) + (p[2] - gz).powi(2);
+                    if d2 > roi2 {
+                        return;
+                    }
+                    let w = match options.weighting {
+                        Weighting::Nearest => 1.0,
+                        Weighting::Cressman => (roi2 - d2) / (roi2 + d2),
+                        Weighting::Barnes => (-4.0 * d2 / roi2).exp(),
+                    };
+                    for (m, values) in gates.values.iter().enumerate() {
+                        let v = values[g];
+                        if v.is_nan() {
+                            continue;
+                        }
+                        sums[m] += w * v as f64;
+                        weights[m] += w;
+                        if d2 < nearest[m].0 {
+                            nearest[m] = (d2, v);
+                        }
+                    }
+                });
+
+                for m in 0..names.len() {
+                    data[m][[k, j, i]] = match options.weighting {
+                        Weighting::Nearest => nearest[m].1,
+                        _ if weights[m] > 0.0 => (sums[m] / weights[m]) as f32,
+                        _ => f32::NAN,
+                    };
+                }
+            }
+        }
+    }
+
+    let mut grid = GriddedData::new(meta.time_coverage_start, projection, x, y, z);
+    grid.attributes
+        .insert("instrument_name".to_string(), meta.instrument_name.clone());
+    grid.attributes
+        .insert(RADAR_LONGITUDE.to_string(), meta.longitude.to_string());
+    grid.attributes
+        .insert(RADAR_LATITUDE.to_string(), meta.latitude.to_string());
+    for (name, values) in names.iter().zip(data) {
+        let template = volume.sweeps.iter().find_map(|s| s.get_moment(name));
+        let mut field = GridField::new(
+            name.clone(),
+            template.map(|t| t.units.clone()).unwrap_or_default(),
+            values,
+        );
+        field.standard_name = template.and_then(|t| t.standard_name.clone());
+        field.long_name = template.and_then(|t| t.long_name.clone());
+        grid.add_field(field)?;
+    }
+
+    Ok(grid)
+}
+
+/// Projected gate positions and per-moment values (NaN where invalid)
+struct Gates {
+    points: Vec<[f64; 3]>,
+    values: Vec<Vec<f32>>,
+}
+
+fn collect_gates(
+    volume: &VolumeData,
+    names: &[String],
+    radar_projection: &Projection,
+    projection: &Projection,
+) -> Result<Gates> {
+    let geo = GeoreferenceOptions::default();
+    let reproject = radar_projection != projection;
+    let mut gates = Gates {
+        points: Vec::new(),
+        values: vec![Vec::new(); names.len()],
+    };
+
+    for sweep in &volume.sweeps {
+        sweep.coordinates.validate()?;
+        let moments: Vec<_> = names.iter().map(|n| sweep.get_moment(n)).collect();
+        if moments.iter().all(|m| m.is_none()) {
+            continue;
+        }
+
+        let (gate_x, gate_y, gate_z) = match (
+            &sweep.coordinates.gate_x,
+            &sweep.coordinates.gate_y,
+            &sweep.coordinates.gate_z,
+        ) {
+            (Some(x), Some(y), Some(z)) => (x.clone(), y.clone(), z.clone()),
+            _ => compute_gate_xyz(&sweep.coordinates, &geo),
+        };
+
+        for ((idx, &x), (&y, &z)) in gate_x.indexed_iter().zip(gate_y.iter().zip(gate_z.iter())) {
+            let values: Vec<f32> = moments
+                .iter()
+                .map(|m| match m {
+                    Some(m) => m.valid_value(idx.0, idx.1).unwrap_or(f32::NAN),
+                    None => f32::NAN,
+                })
+                .collect();
+            if values.iter().all(|v| v.is_nan()) {
+                continue;
+            }
+
+            let (px, py) = if reproject {
+                radar_projection.transform_to(projection, x as f64, y as f64)
+            } else {
+                (x as f64, y as f64)
+            };
+            gates.points.push([px, py, z as f64 + volume.metadata.altitude]);
+            for (column, v) in gates.values.iter_mut().zip(values) {
+                column.push(v);
+            }
+        }
+    }
+
+    Ok(gates)
+}
+
+/// Uniform bucket index over gate positions
+struct GateIndex {
+    cell: f64,
+    buckets: HashMap<(i64, i64, i64), Vec<usize>>,
+}
+
+impl GateIndex {
+    fn new(points: &[[f64; 3]], cell: f64) -> Self {
+        let mut buckets: HashMap<(i64, i64, i64), Vec<usize>> = HashMap::new();
+        for (g, p) in points.iter().enumerate() {
+            buckets.entry(Self::key(p, cell)).or_default().push(g);
+        }
+        Self { cell, buckets }
+    }
+
+    fn key(p: &[f64; 3], cell: f64) -> (i64, i64, i64) {
+        (
+            (p[0] / cell).floor() as i64,
+            (p[1] / cell).floor() as i64,
+            (p[2] / cell).floor() as i64,
+        )
+    }
+
+    /// Call `f` for every gate in buckets overlapping a sphere around `p`
+    fn for_each_near(&self, p: [f64; 3], radius: f64, mut f: impl FnMut(usize)) {
+        let reach = (radius / self.cell).ceil() as i64;
+        let (cx, cy, cz) = Self::key(&p, self.cell);
+        for bx in cx - reach..=cx + reach {
+            for by in cy - reach..=cy + reach {
+                for bz in cz - reach..=cz + reach {
+                    if let Some(bucket) = self.buckets.get(&(bx, by, bz)) {
+                        bucket.iter().for_each(|&g| f(g));
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,195 @@
+//! Hail detection products
+//!
+//! The hail differential reflectivity (Aydin et al. 1986) compares the
+//! reflectivity with the largest value rain could produce for the measured
+//! ZDR, `HDR = Z - f(ZDR)` (dB), where
+//!
+//! `f(ZDR) = 27` for `ZDR ≤ 0`, `19·ZDR + 27` for `0 < ZDR ≤ 1.74`, `60` above.
+//!
+//! Positive HDR indicates hail. The maximum estimated size of hail (Witt et
+//! al. 1998) integrates the hail kinetic energy flux
+//! `E = 5e-6 · 10^(0.084·Z) · W(Z)` above the freezing level into the
+//! severe hail index `SHI = 0.1 · ∫ W_T(H) · E dH`, weighting reflectivity
+//! from 0 at 40 dBZ to 1 at 50 dBZ and height from 0 at the freezing level
+//! to 1 at the -20 °C level; `MESH = 2.54 · SHI^0.5` (mm).
+
+use ndarray::{Array2, Axis};
+
+use crate::{GridField, GriddedData, MomentData, Provenance, RadishError, Result, VolumeData};
+
+/// Name of the hail differential reflectivity moment (dB)
+pub const HDR: &str = "HDR";
+
+/// Name of the severe hail index field (J/m/s)
+pub const SHI: &str = "SHI";
+
+/// Name of the maximum estimated size of hail field (mm)
+pub const MESH: &str = "MESH";
+
+/// Options for the hail differential reflectivity
+#[derive(Debug, Clone)]
+pub struct HdrOptions {
+    /// Reflectivity moment (dBZ)
+    pub reflectivity: String,
+    /// Differential reflectivity moment (dB)
+    pub zdr: String,
+    /// Output moment name
+    pub output_moment: String,
+}
+
+impl Default for HdrOptions {
+    fn default() -> Self {
+        Self {
+            reflectivity: "DBZH".to_string(),
+            zdr: "ZDR".to_string(),
+            output_moment: HDR.to_string(),
+        }
+    }
+}
+
+/// Options for MESH
+#[derive(Debug, Clone)]
+pub struct MeshOptions {
+    /// Reflectivity field (dBZ)
+    pub field: String,
+    /// Reflectivity (dBZ) below which no hail energy is counted
+    pub lower_reflectivity: f32,
+    /// Reflectivity (dBZ) above which hail energy is fully counted
+    pub upper_reflectivity: f32,
+}
+
+impl Default for MeshOptions {
+    fn default() -> Self {
+        Self {
+            field: "DBZH".to_string(),
+            lower_reflectivity: 40.0,
+            upper_reflectivity: 50.0,
+        }
+    }
+}
+
+/// Largest reflectivity (dBZ) of rain with a given ZDR (dB)
+pub fn rain_reflectivity_limit(zdr: f32) -> f32 {
+    if zdr <= 0.0 {
+        27.0
+    } else if zdr <= 1.74 {
+        19.0 * zdr + 27.0
+    } else {
+        60.0
+    }
+}
+
+/// Add the hail differential reflectivity to every sweep with reflectivity and ZDR
+pub fn hail_differential_reflectivity(volume: &VolumeData, options: &HdrOptions) -> Result<VolumeData> {
+    let mut volume = volume.clone();
+    let mut found = false;
+    for sweep in &mut volume.sweeps {
+        let (Some(z), Some(zdr)) = (sweep.get_moment(&options.reflectivity), sweep.get_moment(&options.zdr)) else {
+            continue;
+        };
+        found = true;
+        let data = Array2::from_shape_fn(z.shape(), |idx| {
+            if let (Some(zh), Some(dr)) = (z.valid_value(idx.0, idx.1), zdr.valid_value(idx.0, idx.1)) {
+                zh - rain_reflectivity_limit(dr)
+            } else {
+                f32::NAN
+            }
+        });
+        let mut moment = MomentData::new(options.output_moment.clone(), "dB".to_string(), data);
+        moment.long_name = Some("Hail differential reflectivity".to_string());
+        moment.provenance = Some(Provenance::new(
+            "radish.transforms.hail.hail_differential_reflectivity",
+            &[&options.reflectivity, &options.zdr],
+            options,
+        ));
+        sweep.moments.insert(options.output_moment.clone(), moment);
+    }
+    if !found {
+        return Err(RadishError::MissingVariable(format!("{} and {}", options.reflectivity, options.zdr)));
+    }
+    volume.history.record("radish.transforms.hail.hail_differential_reflectivity", options);
+    Ok(volume)
+}
+
+/// Severe hail index (J/m/s) of a column of (height, dBZ) samples sorted by height
+///
+/// Heights are meters above mean sea level; NaN samples carry no hail
+/// energy. The index is NaN if no sample is valid.
+pub fn column_shi(samples: &[(f64, f32)], freezing_level: f64, minus20_level: f64, options: &MeshOptions) -> f32 {
+    if samples.iter().all(|(_, z)| z.is_nan()) {
+        return f32::NAN;
+    }
+    let (lower, upper) = (options.lower_reflectivity as f64, options.upper_reflectivity as f64);
+    let integrand = |&(h, dbz): &(f64, f32)| {
+        let z = dbz as f64;
+        if dbz.is_nan() || z <= lower || h <= freezing_level {
+            return 0.0;
+        }
+        let w_z = ((z - lower) / (upper - lower)).min(1.0);
+        let w_t = ((h - freezing_level) / (minus20_level - freezing_level)).min(1.0);
+        w_t * 5e-6 * 10f64.powf(0.084 * z) * w_z
+    };
+    let shi: f64 = samples
+        .windows(2)
+        .map(|pair| (integrand(&pair[0]) + integrand(&pair[1])) / 2.0 * (pair[1].0 - pair[0].0))
+        .sum();
+    (0.1 * shi) as f32
+}
+
+/// SHI and MESH of a reflectivity grid, as a single-level grid
+///
+/// `freezing_level` and `minus20_level` are the heights (meters MSL) of the
+/// 0 °C and -20 °C isotherms, e.g. from a sounding or model analysis. The
+/// result holds the `SHI` and `MESH` fields on the x/y axes of the grid.
+pub fn mesh_grid(
+    grid: &GriddedData,
+    freezing_level: f64,
+    minus20_level: f64,
+    options: &MeshOptions,
+) -> Result<GriddedData> {
+    if minus20_level <= freezing_level {
+        return Err(RadishError::General(format!(
+            "The -20 °C level ({} m) must be above the freezing level ({} m)",
+            minus20_level, freezing_level
+        )));
+    }
+    let field = grid
+        .get_field(&options.field)
+        .ok_or_else(|| RadishError::MissingVariable(options.field.clone()))?;
+    let (nz, ny, nx) = field.shape();
+
+    let mut order: Vec<usize> = (0..nz).collect();
+    order.sort_by(|&a, &b| grid.z[a].total_cmp(&grid.z[b]));
+    let mut shi = Array2::from_elem((ny, nx), f32::NAN);
+    let mut samples = Vec::with_capacity(nz);
+    for j in 0..ny {
+        for i in 0..nx {
+            samples.clear();
+            samples.extend(order.iter().map(|&k| {
+                let v = field.data[[k, j, i]];
+                (grid.z[k], if field.is_valid_value(v) { v } else { f32::NAN })
+            }));
+            shi[[j, i]] = column_shi(&samples, freezing_level, minus20_level, options);
+        }
+    }
+    let mesh = shi.mapv(|s| 2.54 * s.sqrt());
+
+    let base = order.first().map_or(0.0, |&k| grid.z[k]);
+    let mut out = GriddedData::new(grid.time, grid.projection, grid.x.clone(), grid.y.clone(), vec![base]);
+    out.attributes = grid.attributes.clone();
+    for (name, units, long_name, data) in [
+        (SHI, "J m-1 s-1", "Severe hail index", shi),
+        (MESH, "mm", "Maximum estimated size of hail", mesh),
+    ] {
+        let mut field = GridField::new(name.to_string(), units.to_string(), data.insert_axis(Axis(0)));
+        field.long_name = Some(long_name.to_string());
+        field
+            .attributes
+            .insert("freezing_level".to_string(), freezing_level.to_string());
+        field
+            .attributes
+            .insert("minus20_level".to_string(), minus20_level.to_string());
+        out.add_field(field)?;
+    }
+    Ok(out)
+}
@@ -1,14 +1,108 @@
-/// Transformation utilities for radar data
-///
-/// This module will contain functions for:
-/// - Georeferencing (converting polar to geographic coordinates)
-/// - Velocity dealiasing
-/// - Quality control and filtering
-/// - Attenuation correction
-/// - KDP calculation
-///
-/// To be implemented in future phases.
+//! Transformation utilities for radar data
+//!
+//! This module contains functions for:
+//! - Georeferencing (converting polar to radar-relative Cartesian coordinates)
+//! - Beam blockage from digital elevation models
+//! - Re-indexing rays onto uniform azimuth grids, removal of
+//!   antenna-transition and duplicate rays, and range/azimuth downsampling
+//! - NEXRAD volume restructuring (split-cut recombination, SAILS cuts)
+//! - Gridding volumes onto regular Cartesian grids
+//! - Vertical columns above a point for ground validation
+//! - Vertical cross-sections (pseudo-RHIs and slices along paths)
+//! - Multi-radar mosaics (maximum, nearest radar, coverage-aware blending)
+//! - Convective/stratiform echo classification
+//! - Storm cell identification and tracking
+//! - Velocity dealiasing and dual-PRF error correction
+//! - Volume wind, divergence and deformation profiles (EVAD)
+//! - Low-level radial divergence, azimuthal shear and shear-line candidates
+//! - Dual-frequency ratio from co-located radars
+//! - Quality control and filtering (gate filters, SNR thresholding, RFI
+//!   spikes, TBSS and sidelobe flags, static ground clutter maps)
+//! - Pedestal azimuth offset estimation from ground clutter patterns
+//! - Platform motion correction of ship, vehicle and airborne radars
+//! - Sun monitoring: solar spikes, antenna pointing and receiver calibration
+//! - ZDR bias estimation from vertically pointing scans
+//! - Reflectivity calibration monitoring from Z–ZDR–KDP self-consistency
+//! - Specific attenuation retrieval (ZPHI) and attenuation correction,
+//!   including two-way gaseous attenuation
+//! - PHIDP processing and KDP calculation
+//! - Derived moments from expressions over existing moments
+//! - Quantitative precipitation estimation (rain and snowfall rates)
+//! - Vertically integrated liquid (VIL) and VIL density
+//! - Hail detection (HDR and MESH)
+//! - Precipitation accumulation over time series of rate grids, with
+//!   advection correction from cross-correlation echo motion
 
+pub mod accumulation;
+pub mod advection;
+pub mod attenuation;
+pub mod azimuth_offset;
+pub mod blockage;
+pub mod cells;
+pub mod clutter;
+pub mod column;
+pub mod contamination;
+pub mod convective;
+pub mod cross_section;
+pub mod dealias;
+pub mod derived;
+pub mod dfr;
+pub mod downsample;
+pub mod dual_prf;
+pub mod evad;
+pub mod gatefilter;
 pub mod georeference;
+pub mod gridding;
+pub mod hail;
+pub mod mosaic;
+pub mod motion_correction;
+pub mod phidp;
+pub mod qpe;
+pub mod ray_cleanup;
+pub mod reindex;
+pub mod rfi;
+pub mod sails;
+pub mod self_consistency;
+pub mod shear;
+pub mod snr;
+pub mod sun;
+pub mod sweep_merge;
+pub mod vil;
+pub mod zdr_bias;
 
+pub use accumulation::{accumulate, AccumulationOptions, Accumulator, GapPolicy, TimeWeighting};
+pub use advection::{estimate_motion, AdvectionOptions};
+pub use attenuation::{compute_specific_attenuation, correct_gaseous_attenuation, AttenuationOptions, GasAttenuationOptions, GasCoefficients, ZphiCoefficients, SPECIFIC_ATTENUATION};
+pub use azimuth_offset::{apply_azimuth_offset, estimate_azimuth_offset, AzimuthOffset, AzimuthOffsetOptions};
+pub use blockage::{compute_blockage, BlockageOptions, BEAM_BLOCKAGE};
+pub use cells::{identify_cells, track_cells, CellOptions, CellTracker, StormCell, Track, TrackOptions};
+pub use clutter::{build_clutter_map, filter_clutter, ClutterMap, ClutterMapOptions};
+pub use column::{extract_column, Column, ColumnOptions};
+pub use contamination::{flag_contamination, ContaminationOptions};
+pub use convective::{convective_stratiform, ConvStratOptions};
+pub use cross_section::{cross_section_grid, cross_section_volume, pseudo_rhi, CrossSection, CrossSectionOptions, PseudoRhiOptions};
+pub use dealias::{dealias_with_profile, DealiasOptions, WindProfile};
+pub use derived::{DerivedMoment, DerivedMoments};
+pub use dfr::{dual_frequency_ratio, DfrOptions};
+pub use downsample::{downsample, downsample_sweep, DownsampleOptions};
+pub use dual_prf::{correct_dual_prf, correct_dual_prf_sweep, DualPrfOptions};
+pub use evad::{evad, EvadOptions, EvadProfile};
+pub use gatefilter::GateFilter;
 pub use georeference::*;
+pub use gridding::{grid_volume, GridOptions};
+pub use hail::{hail_differential_reflectivity, mesh_grid, HdrOptions, MeshOptions};
+pub use mosaic::{composite_grids, coverage_grid, coverage_weight, mosaic_volumes, CompositeMethod, CoverageOptions, MosaicOptions};
+pub use motion_correction::{correct_platform_motion, MotionCorrectionOptions};
+pub use phidp::{compute_kdp, process_phidp, KdpOptions, PhidpOptions};
+pub use qpe::{estimate_rain_rate, estimate_snowfall, PhaseInput, RainEstimator, RainRateOptions, SnowfallOptions};
+pub use ray_cleanup::{clean_rays, clean_sweep_rays, DuplicatePolicy, RayCleanupOptions};
+pub use reindex::{reindex_azimuth, reindex_sweep, DuplicateRays, ReindexOptions};
+pub use rfi::{detect_rfi, flag_rfi, remove_rfi, RfiOptions, RfiReport};
+pub use sails::{restructure_sails, sails_sweeps, SailsOptions, SailsPolicy};
+pub use self_consistency::{self_consistency_bias, KdpRelation, SelfConsistencyOptions, SelfConsistencyReport};
+pub use shear::{shear_line_grid, shear_products, ShearOptions};
+pub use snr::{compute_snr, snr_gate_filter, threshold_snr, SnrOptions};
+pub use sun::{detect_sun_hits, fit_sun, solar_position, sun_scan, SunHit, SunOptions, SunScanReport};
+pub use sweep_merge::{deduplicate_sweeps, merge_duplicate_sweeps, merge_split_cuts, merge_sweeps, KeepSweep, MergePolicy, SplitCutGrid, SplitCutOptions, SweepMergeOptions};
+pub use vil::{vil_grid, vil_volume, VilOptions};
+pub use zdr_bias::{apply_zdr_correction, estimate_zdr_bias, ZdrBiasEstimate, ZdrBiasOptions, ZdrBiasTracker};
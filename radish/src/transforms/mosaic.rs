@@ -0,0 +1,498 @@
+//! Multi-radar mosaics
+//!
+//! Grids of several radars on a common projection and grid are merged into
+//! a single mosaic by taking the maximum, the value of the nearest radar or
+//! a weighted blend. Each composited field comes with a per-cell record of
+//! the radar that provided it and of the number of radars that had data.
+//!
+//! Blending weights either decay with the distance from the radar or come
+//! from the quality of its coverage, following operational compositing
+//! practice (e.g. Zhang et al., 2005, as used in MRMS): the lowest beam that
+//! is not substantially blocked is used, and its weight decays with the beam
+//! height above ground and with the distance from the radar, scaled down by
+//! any remaining partial blockage.
+
+use ndarray::{Array2, Array3, Axis};
+use radish_types::SweepMode;
+
+use crate::transforms::georeference::{ground_to_antenna, GeoreferenceOptions, Projection};
+use crate::transforms::gridding::{grid_volume, GridOptions, RADAR_LATITUDE, RADAR_LONGITUDE};
+use crate::{GridField, GriddedData, RadishError, Result, SweepData, VolumeData};
+
+/// Name of the coverage weight field
+pub const COVERAGE_WEIGHT: &str = "COVERAGE_WEIGHT";
+
+/// Name of the field holding the height (m) of the lowest usable beam above ground
+pub const BEAM_HEIGHT_AGL: &str = "BEAM_HEIGHT_AGL";
+
+/// Name of the field holding the blockage fraction of the lowest usable beam
+pub const BEAM_BLOCKAGE: &str = "BEAM_BLOCKAGE";
+
+/// Suffix of the per-field mosaic provenance field holding the index of the
+/// source radar (the one with the largest weight when blending)
+pub const SOURCE_SUFFIX: &str = "_SOURCE";
+
+/// Suffix of the per-field mosaic provenance field holding the number of
+/// radars with valid data in the cell
+pub const COUNT_SUFFIX: &str = "_COUNT";
+
+/// Mosaic attribute holding the instrument names of the sources as a JSON
+/// array, in the order of the `_SOURCE` indices
+pub const MOSAIC_SOURCES: &str = "mosaic_sources";
+
+/// How the values of overlapping radars are combined
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CompositeMethod {
+    /// Largest value (e.g. composite reflectivity)
+    #[default]
+    Maximum,
+    /// Value of the radar closest to the grid column
+    Nearest,
+    /// Weighted mean, with weights from `MosaicOptions::weight_field` when
+    /// the grids have it and otherwise `exp(-d²/L²)` in the distance `d`
+    /// from the radar
+    Weighted,
+}
+
+/// Options for compositing grids of several radars
+#[derive(Debug, Clone)]
+pub struct MosaicOptions {
+    /// Combination method
+    pub method: CompositeMethod,
+    /// Fields to composite (all fields except the weight field if empty)
+    pub fields: Vec<String>,
+    /// Grid field holding per-cell weights for blending (e.g. `COVERAGE_WEIGHT`)
+    pub weight_field: Option<String>,
+    /// Distance scale L (meters) of the distance weight exp(-d²/L²)
+    pub distance_scale: f64,
+    /// Cells farther than this (meters) from a radar take no data from it
+    pub max_range: f64,
+    /// Add the `_SOURCE` and `_COUNT` provenance fields
+    pub provenance: bool,
+}
+
+impl Default for MosaicOptions {
+    fn default() -> Self {
+        Self {
+            method: CompositeMethod::default(),
+            fields: Vec::new(),
+            weight_field: None,
+            distance_scale: 50_000.0,
+            max_range: 300_000.0,
+            provenance: true,
+        }
+    }
+}
+
+/// Name of the field recording the source radar of a composited field
+pub fn source_field_name(field: &str) -> String {
+    format!("{}{}", field, SOURCE_SUFFIX)
+}
+
+/// Name of the field recording the number of radars of a composited field
+pub fn count_field_name(field: &str) -> String {
+    format!("{}{}", field, COUNT_SUFFIX)
+}
+
+/// Options for coverage-aware mosaic weights
+#[derive(Debug, Clone)]
+pub struct CoverageOptions {
+    /// Moment holding the beam blockage fraction (0-1); no blockage if absent
+    pub blockage_moment: String,
+    /// Beams blocked by more than this fraction are not used
+    pub max_blockage: f32,
+    /// Height scale H (meters) of the weight exp(-h²/H²) on beam height above ground
+    pub height_scale: f64,
+    /// Distance scale L (meters) of the weight exp(-d²/L²) on distance from the radar
+    pub distance_scale: f64,
+    /// Grid columns farther than this (meters) from the radar get no weight
+    pub max_range: f64,
+    /// Beam propagation model
+    pub georeference: GeoreferenceOptions,
+}
+
+impl Default for CoverageOptions {
+    fn default() -> Self {
+        Self {
+            blockage_moment: "PBB".to_string(),
+            max_blockage: 0.5,
+            height_scale: 2000.0,
+            distance_scale: 50_000.0,
+            max_range: 300_000.0,
+            georeference: GeoreferenceOptions::default(),
+        }
+    }
+}
+
+/// The beam a radar contributes to a grid column
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BeamCoverage {
+    /// Index of the sweep in the volume
+    pub sweep: usize,
+    /// Fixed angle of the sweep (degrees)
+    pub elevation: f64,
+    /// Slant range to the column (meters)
+    pub range: f64,
+    /// Beam height above the radar (meters)
+    pub height: f64,
+    /// Blockage fraction of the beam at the column
+    pub blockage: f32,
+}
+
+/// Weight of a contribution from its beam height above ground, distance and blockage
+///
+/// `exp(-h²/H²) · exp(-d²/L²) · (1 - blockage)`, or 0 if the beam is blocked
+/// beyond `max_blockage` or the column is beyond `max_range`.
+pub fn coverage_weight(height_agl: f64, distance: f64, blockage: f32, options: &CoverageOptions) -> f32 {
+    if blockage > options.max_blockage || distance > options.max_range || height_agl.is_nan() {
+        return 0.0;
+    }
+    let height_term = (-(height_agl.max(0.0) / options.height_scale).powi(2)).exp();
+    let distance_term = (-(distance / options.distance_scale).powi(2)).exp();
+    (height_term * distance_term) as f32 * (1.0 - blockage.clamp(0.0, 1.0))
+}
+
+/// Lowest PPI sweep whose beam is usable above a point
+///
+/// The point is given by its great-circle distance (meters) and azimuth
+/// (degrees) from the radar. Sweeps are tried in order of increasing fixed
+/// angle; a sweep is usable if it reaches the distance within its range
+/// limits and is blocked by no more than `max_blockage` there.
+pub fn lowest_usable_beam(
+    volume: &VolumeData,
+    ground_range: f64,
+    azimuth: f64,
+    options: &CoverageOptions,
+) -> Option<BeamCoverage> {
+    let mut order: Vec<usize> = (0..volume.sweeps.len())
+        .filter(|&k| {
+            matches!(
+                volume.sweeps[k].metadata.sweep_mode,
+                SweepMode::Azimuth | SweepMode::Sector | SweepMode::ManualPpi
+            )
+        })
+        .collect();
+    order.sort_by(|&a, &b| {
+        volume.sweeps[a]
+            .metadata
+            .fixed_angle
+            .total_cmp(&volume.sweeps[b].metadata.fixed_angle)
+    });
+
+    order.into_iter().find_map(|k| {
+        let sweep = &volume.sweeps[k];
+        let elevation = sweep.metadata.fixed_angle;
+        let (range, height) = ground_to_antenna(ground_range, elevation, &options.georeference);
+        let (ray, gate) = nearest_gate(sweep, range as f32, azimuth as f32)?;
+
+        let blockage = sweep
+            .get_moment(&options.blockage_moment)
+            .map(|m| m.valid_value(ray, gate).unwrap_or(0.0))
+            .unwrap_or(0.0);
+        (blockage <= options.max_blockage).then_some(BeamCoverage {
+            sweep: k,
+            elevation,
+            range,
+            height,
+            blockage,
+        })
+    })
+}
+
+/// Coverage of a radar on the columns of a grid
+///
+/// Returns a single-level grid on the x/y axes and projection of `template`
+/// with the `COVERAGE_WEIGHT`, `BEAM_HEIGHT_AGL` and `BEAM_BLOCKAGE` fields.
+/// Heights above ground use `terrain` (meters MSL, on the template grid) when
+/// given, and otherwise assume the ground is at the radar altitude. Columns
+/// without a usable beam have zero weight and NaN height and blockage.
+pub fn coverage_grid(
+    volume: &VolumeData,
+    template: &GriddedData,
+    terrain: Option<&GridField>,
+    options: &CoverageOptions,
+) -> Result<GriddedData> {
+    let meta = &volume.metadata;
+    let radar_projection = Projection::aeqd(meta.longitude, meta.latitude);
+    let (_, ny, nx) = template.shape();
+    if let Some(t) = terrain {
+        let (_, ty, tx) = t.shape();
+        if (ty, tx) != (ny, nx) {
+            return Err(RadishError::InvalidFormat(format!(
+                "Terrain shape {:?} does not match grid shape {:?}",
+                t.shape(),
+                template.shape()
+            )));
+        }
+    }
+
+    let mut weight = Array2::<f32>::zeros((ny, nx));
+    let mut height_agl = Array2::from_elem((ny, nx), f32::NAN);
+    let mut blockage = Array2::from_elem((ny, nx), f32::NAN);
+
+    for j in 0..ny {
+        for i in 0..nx {
+            let (lon, lat) = template.lonlat(i, j);
+            let (x, y) = radar_projection.forward(lon, lat);
+            let distance = (x * x + y * y).sqrt();
+            if distance > options.max_range {
+                continue;
+            }
+            let azimuth = x.atan2(y).to_degrees().rem_euclid(360.0);
+            let Some(beam) = lowest_usable_beam(volume, distance, azimuth, options) else {
+                continue;
+            };
+
+            let ground = match terrain {
+                Some(t) if t.is_valid_value(t.data[[0, j, i]]) => t.data[[0, j, i]] as f64,
+                _ => meta.altitude,
+            };
+            let agl = meta.altitude + beam.height - ground;
+
+            weight[[j, i]] = coverage_weight(agl, distance, beam.blockage, options);
+            height_agl[[j, i]] = agl as f32;
+            blockage[[j, i]] = beam.blockage;
+        }
+    }
+
+    let mut grid = GriddedData::new(
+        meta.time_coverage_start,
+        template.projection,
+        template.x.clone(),
+        template.y.clone(),
+        vec![0.0],
+    );
+    grid.attributes
+        .insert("instrument_name".to_string(), meta.instrument_name.clone());
+    grid.attributes
+        .insert(RADAR_LONGITUDE.to_string(), meta.longitude.to_string());
+    grid.attributes
+        .insert(RADAR_LATITUDE.to_string(), meta.latitude.to_string());
+
+    for (name, units, long_name, data) in [
+        (COVERAGE_WEIGHT, "", "Mosaic coverage weight", weight),
+        (BEAM_HEIGHT_AGL, "m", "Height of the lowest usable beam above ground", height_agl),
+        (BEAM_BLOCKAGE, "", "Blockage fraction of the lowest usable beam", blockage),
+    ] {
+        let mut field = GridField::new(name.to_string(), units.to_string(), data.insert_axis(Axis(0)));
+        field.long_name = Some(long_name.to_string());
+        grid.add_field(field)?;
+    }
+
+    Ok(grid)
+}
+
+/// Composite grids of several radars into a mosaic
+///
+/// All grids must share the projection and axes of the first one (e.g. from
+/// `grid_volume` with a common `GridOptions::projection`). The radar of each
+/// grid is located from its `radar_longitude`/`radar_latitude` attributes,
+/// which are needed for nearest-radar compositing, for distance weights and
+/// for the `max_range` limit. Ties go to the radar listed first, so the
+/// result does not depend on anything but the order of `grids`.
+///
+/// With `options.provenance`, each composited field `F` is accompanied by
+/// `F_SOURCE`, the index in `grids` of the radar that provided the value (the
+/// largest weight when blending), and `F_COUNT`, the number of radars with
+/// valid data. The `mosaic_sources` attribute lists the instrument names.
+pub fn composite_grids(grids: &[GriddedData], options: &MosaicOptions) -> Result<GriddedData> {
+    let first = grids
+        .first()
+        .ok_or_else(|| RadishError::General("No grids to composite".to_string()))?;
+    let instrument = |g: &GriddedData| g.attributes.get("instrument_name").cloned().unwrap_or_default();
+    for grid in &grids[1..] {
+        if grid.projection != first.projection || grid.x != first.x || grid.y != first.y || grid.z != first.z {
+            return Err(RadishError::InvalidFormat(format!(
+                "Grid of {} is not on the mosaic grid",
+                instrument(grid)
+            )));
+        }
+    }
+
+    let (nz, ny, nx) = first.shape();
+    let distances = grids
+        .iter()
+        .map(radar_distances)
+        .collect::<Result<Vec<_>>>()?;
+    for (grid, distance) in grids.iter().zip(&distances) {
+        let has_weights = options
+            .weight_field
+            .as_ref()
+            .is_some_and(|w| grid.get_field(w).is_some());
+        let needs_distance = match options.method {
+            CompositeMethod::Maximum => false,
+            CompositeMethod::Nearest => true,
+            CompositeMethod::Weighted => !has_weights,
+        };
+        if needs_distance && distance.is_none() {
+            return Err(RadishError::MissingAttribute(format!(
+                "{} of grid {}",
+                RADAR_LONGITUDE,
+                instrument(grid)
+            )));
+        }
+    }
+
+    let names: Vec<String> = if options.fields.is_empty() {
+        let mut names: Vec<String> = grids
+            .iter()
+            .flat_map(|g| g.fields.keys().cloned())
+            .filter(|n| options.weight_field.as_ref() != Some(n))
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    } else {
+        options.fields.clone()
+    };
+
+    let time = grids.iter().map(|g| g.time).max().unwrap_or(first.time);
+    let mut mosaic = GriddedData::new(time, first.projection, first.x.clone(), first.y.clone(), first.z.clone());
+    let sources: Vec<String> = grids.iter().map(instrument).collect();
+    mosaic.attributes.insert(
+        MOSAIC_SOURCES.to_string(),
+        serde_json::to_string(&sources).unwrap_or_default(),
+    );
+    mosaic.attributes.insert(
+        "mosaic_method".to_string(),
+        format!("{:?}", options.method).to_lowercase(),
+    );
+
+    for name in &names {
+        let fields: Vec<Option<&GridField>> = grids.iter().map(|g| g.get_field(name)).collect();
+        let Some(template) = fields.iter().flatten().next() else {
+            continue;
+        };
+        let levels = fields.iter().flatten().map(|f| f.shape().0).max().unwrap_or(1).min(nz);
+
+        let mut value = Array3::from_elem((levels, ny, nx), f32::NAN);
+        let mut source = Array3::from_elem((levels, ny, nx), f32::NAN);
+        let mut count = Array3::<f32>::zeros((levels, ny, nx));
+
+        for k in 0..levels {
+            for j in 0..ny {
+                for i in 0..nx {
+                    let mut best: Option<(usize, f64, f32)> = None;
+                    let (mut sum, mut weight_sum, mut n) = (0.0f64, 0.0f64, 0usize);
+
+                    for (r, field) in fields.iter().enumerate() {
+                        let Some(field) = field else {
+                            continue;
+                        };
+                        let v = field.data[[k.min(field.shape().0 - 1), j, i]];
+                        if !field.is_valid_value(v) {
+                            continue;
+                        }
+                        let distance = distances[r].as_ref().map(|d| d[[j, i]]);
+                        if distance.is_some_and(|d| d > options.max_range) {
+                            continue;
+                        }
+
+                        let key = match options.method {
+                            CompositeMethod::Maximum => v as f64,
+                            CompositeMethod::Nearest => -distance.unwrap_or(f64::INFINITY),
+                            CompositeMethod::Weighted => {
+                                let w = blend_weight(&grids[r], k, j, i, distance, options);
+                                if w <= 0.0 {
+                                    continue;
+                                }
+                                sum += w * v as f64;
+                                weight_sum += w;
+                                w
+                            }
+                        };
+                        n += 1;
+                        if best.is_none_or(|(_, best_key, _)| key > best_key) {
+                            best = Some((r, key, v));
+                        }
+                    }
+
+                    let Some((r, _, v)) = best else {
+                        continue;
+                    };
+                    value[[k, j, i]] = match options.method {
+                        CompositeMethod::Weighted => (sum / weight_sum) as f32,
+                        _ => v,
+                    };
+                    source[[k, j, i]] = r as f32;
+                    count[[k, j, i]] = n as f32;
+                }
+            }
+        }
+
+        let mut field = GridField::new(name.clone(), template.units.clone(), value);
+        field.standard_name = template.standard_name.clone();
+        field.long_name = template.long_name.clone();
+        mosaic.add_field(field)?;
+
+        if options.provenance {
+            let mut field = GridField::new(source_field_name(name), String::new(), source);
+            field.long_name = Some(format!("Index of the mosaic source radar of {}", name));
+            mosaic.add_field(field)?;
+            let mut field = GridField::new(count_field_name(name), String::new(), count);
+            field.long_name = Some(format!("Number of radars with valid {}", name));
+            mosaic.add_field(field)?;
+        }
+    }
+
+    Ok(mosaic)
+}
+
+/// Grid each volume on a common grid and composite the grids
+///
+/// `grid_options.projection` must be set so that all radars share the grid.
+pub fn mosaic_volumes(
+    volumes: &[VolumeData],
+    grid_options: &GridOptions,
+    options: &MosaicOptions,
+) -> Result<GriddedData> {
+    if grid_options.projection.is_none() {
+        return Err(RadishError::General(
+            "A mosaic needs a common grid projection".to_string(),
+        ));
+    }
+    let grids = volumes
+        .iter()
+        .map(|v| grid_volume(v, grid_options))
+        .collect::<Result<Vec<_>>>()?;
+    composite_grids(&grids, options)
+}
+
+/// Great-circle distance (meters) from the radar of a grid to each column, if the radar is known
+fn radar_distances(grid: &GriddedData) -> Result<Option<Array2<f64>>> {
+    let coordinate = |key: &str| {
+        grid.attributes
+            .get(key)
+            .map(|v| {
+                v.parse::<f64>()
+                    .map_err(|_| RadishError::Conversion(format!("Invalid {}: {}", key, v)))
+            })
+            .transpose()
+    };
+    let (Some(lon), Some(lat)) = (coordinate(RADAR_LONGITUDE)?, coordinate(RADAR_LATITUDE)?) else {
+        return Ok(None);
+    };
+
+    let radar = Projection::aeqd(lon, lat);
+    let (_, ny, nx) = grid.shape();
+    Ok(Some(Array2::from_shape_fn((ny, nx), |(j, i)| {
+        let (lon, lat) = grid.lonlat(i, j);
+        let (x, y) = radar.forward(lon, lat);
+        (x * x + y * y).sqrt()
+    })))
+}
+
+/// Blending weight of a radar in a cell: its weight field, or the distance weight
+fn blend_weight(grid: &GriddedData, k: usize, j: usize, i: usize, distance: Option<f64>, options: &MosaicOptions) -> f64 {
+    if let Some(field) = options.weight_field.as_ref().and_then(|w| grid.get_field(w)) {
+        let w = field.data[[k.min(field.shape().0 - 1), j, i]];
+        return if field.is_valid_value(w) { w.max(0.0) as f64 } else { 0.0 };
+    }
+    distance.map_or(0.0, |d| (-(d / options.distance_scale).powi(2)).exp())
+}
+
+/// Nearest (ray, gate) of a sweep to a slant range and azimuth, if within the sweep
+pub(crate) fn nearest_gate(sweep: &SweepData, range: f32, azimuth: f32) -> Option<(usize, usize)> {
+    let gate = sweep.nearest_gate(range)?;
+    Some((sweep.nearest_ray(azimuth)?, gate))
+}
@@ -0,0 +1,182 @@
+//! Platform motion correction for radars on ships, vehicles and aircraft
+//!
+//! Radars on moving platforms measure their pointing angles relative to the
+//! platform and their radial velocities relative to the moving antenna.
+//! With the per-ray [`PlatformMotion`](crate::PlatformMotion) of the
+//! sweeps, the beam direction is rotated from the platform frame into the
+//! earth frame by the roll, pitch and heading of the platform,
+//!
+//! `b_earth = H(heading) · P(pitch) · R(roll) · b_platform`
+//!
+//! and the platform velocity along the beam is added back to the radial
+//! velocity (positive away from the radar),
+//!
+//! `V = V_measured + (u_east, v_north, w_up) · b_earth`
+//!
+//! folded into the Nyquist interval of the ray when it is known. The
+//! correction is keyed off the [`PlatformType`] of the volume: fixed radars
+//! (or volumes without a platform type) are returned unchanged, and
+//! satellite radars are not supported.
+
+use ndarray::Zip;
+use radish_types::PlatformType;
+
+use crate::{Provenance, RadishError, Result, SweepData, VolumeData};
+
+/// Volume attribute recording that platform motion was corrected
+pub const MOTION_CORRECTED_ATTRIBUTE: &str = "platform_motion_corrected";
+
+/// Options for platform motion correction
+#[derive(Debug, Clone)]
+pub struct MotionCorrectionOptions {
+    /// Radial velocity moments corrected for the platform velocity
+    pub velocity_moments: Vec<String>,
+    /// Rotate azimuths and elevations from the platform into the earth
+    /// frame; off for files whose angles are already earth-relative
+    pub correct_angles: bool,
+}
+
+impl Default for MotionCorrectionOptions {
+    fn default() -> Self {
+        Self {
+            velocity_moments: vec!["VRADH".to_string()],
+            correct_angles: true,
+        }
+    }
+}
+
+/// Correct the pointing angles and radial velocities of a moving radar
+///
+/// Rays without a heading keep their angles, and rays without horizontal
+/// platform velocity keep their radial velocities; missing roll, pitch and
+/// vertical velocity count as zero. Volumes already corrected, as recorded
+/// in the [`MOTION_CORRECTED_ATTRIBUTE`] attribute, are returned unchanged.
+pub fn correct_platform_motion(volume: &VolumeData, options: &MotionCorrectionOptions) -> Result<VolumeData> {
+    let mut volume = volume.clone();
+    match volume.metadata.platform_type {
+        None | Some(PlatformType::Fixed) => return Ok(volume),
+        Some(PlatformType::Satellite) => {
+            return Err(RadishError::Unsupported(
+                "Platform motion correction of satellite radars".to_string(),
+            ))
+        }
+        Some(PlatformType::Vehicle | PlatformType::Ship | PlatformType::Aircraft) => {}
+    }
+    if volume.metadata.attributes.contains_key(MOTION_CORRECTED_ATTRIBUTE) {
+        return Ok(volume);
+    }
+
+    for sweep in &mut volume.sweeps {
+        correct_sweep(sweep, options)?;
+    }
+    volume
+        .metadata
+        .attributes
+        .insert(MOTION_CORRECTED_ATTRIBUTE.to_string(), "true".into());
+    volume.history.record("radish.transforms.motion_correction.correct_platform_motion", options);
+    Ok(volume)
+}
+
+fn correct_sweep(sweep: &mut SweepData, options: &MotionCorrectionOptions) -> Result<()> {
+    let Some(motion) = sweep.platform_motion.clone() else {
+        return Ok(());
+    };
+    let num_rays = sweep.num_rays();
+    motion.validate(num_rays).map_err(RadishError::General)?;
+
+    if options.correct_angles {
+        let coords = &mut sweep.coordinates;
+        for i in 0..num_rays {
+            let Some(heading) = value(&motion.heading, i) else {
+                continue;
+            };
+            let roll = value(&motion.roll, i).unwrap_or(0.0);
+            let pitch = value(&motion.pitch, i).unwrap_or(0.0);
+            let (azimuth, elevation) = platform_to_earth(
+                coords.azimuth[i] as f64,
+                coords.elevation[i] as f64,
+                heading,
+                roll,
+                pitch,
+            );
+            coords.azimuth[i] = azimuth as f32;
+            coords.elevation[i] = elevation as f32;
+        }
+        coords.clear_georeference();
+    }
+
+    // Platform velocity along each (earth-relative) beam
+    let beam_velocity: Vec<Option<f64>> = (0..num_rays)
+        .map(|i| {
+            let (east, north) = (value(&motion.velocity_east, i)?, value(&motion.velocity_north, i)?);
+            let up = value(&motion.velocity_up, i).unwrap_or(0.0);
+            let azimuth = (sweep.coordinates.azimuth[i] as f64).to_radians();
+            let elevation = (sweep.coordinates.elevation[i] as f64).to_radians();
+            Some(
+                east * elevation.cos() * azimuth.sin()
+                    + north * elevation.cos() * azimuth.cos()
+                    + up * elevation.sin(),
+            )
+        })
+        .collect();
+    let nyquist: Vec<Option<f64>> = (0..num_rays).map(|i| sweep.ray_nyquist_velocity(i)).collect();
+
+    for name in &options.velocity_moments {
+        let Some(moment) = sweep.moments.get(name) else {
+            continue;
+        };
+        let missing = moment.missing_value();
+        let mut data = moment.data().into_owned();
+        Zip::indexed(&mut data)
+            .and(&moment.validity())
+            .for_each(|(i, _), v, &valid| {
+                if !valid {
+                    *v = missing;
+                } else if let Some(platform) = beam_velocity[i] {
+                    let corrected = *v as f64 + platform;
+                    *v = match nyquist[i].filter(|&n| n > 0.0) {
+                        Some(n) => (corrected + n).rem_euclid(2.0 * n) - n,
+                        None => corrected,
+                    } as f32;
+                }
+            });
+        let mut corrected = moment.with_data(data).with_provenance(Provenance::new(
+            "radish.transforms.motion_correction.correct_platform_motion",
+            &[name],
+            options,
+        ));
+        corrected.valid_min = None;
+        corrected.valid_max = None;
+        sweep.moments.insert(name.clone(), corrected);
+    }
+    Ok(())
+}
+
+/// Earth-relative azimuth and elevation (degrees) of a beam pointing at
+/// `azimuth` (clockwise from the bow) and `elevation` (above the deck) of a
+/// platform with the given attitude (degrees)
+pub fn platform_to_earth(azimuth: f64, elevation: f64, heading: f64, roll: f64, pitch: f64) -> (f64, f64) {
+    let (az, el) = (azimuth.to_radians(), elevation.to_radians());
+    let (heading, roll, pitch) = (heading.to_radians(), roll.to_radians(), pitch.to_radians());
+    // Forward, starboard and up components in the platform frame
+    let (forward, starboard, up) = (el.cos() * az.cos(), el.cos() * az.sin(), el.sin());
+    // Roll about the forward axis, starboard side down
+    let (starboard, up) = (
+        starboard * roll.cos() + up * roll.sin(),
+        up * roll.cos() - starboard * roll.sin(),
+    );
+    // Pitch about the starboard axis, bow up
+    let (forward, up) = (forward * pitch.cos() - up * pitch.sin(), up * pitch.cos() + forward * pitch.sin());
+    // Heading about the vertical
+    let north = forward * heading.cos() - starboard * heading.sin();
+    let east = forward * heading.sin() + starboard * heading.cos();
+    (
+        east.atan2(north).to_degrees().rem_euclid(360.0),
+        up.clamp(-1.0, 1.0).asin().to_degrees(),
+    )
+}
+
+/// Value of a per-ray array, `None` when absent or NaN
+fn value(values: &Option<Vec<f64>>, ray: usize) -> Option<f64> {
+    values.as_ref().and_then(|v| v.get(ray).copied()).filter(|v| v.is_finite())
+}
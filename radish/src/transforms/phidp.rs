@@ -0,0 +1,361 @@
+//! Differential phase (PHIDP) processing
+//!
+//! Raw PHIDP starts at an arbitrary system phase offset, wraps around at
+//! ±180° (or ±90° on systems reporting a 180° interval), and is noisy. This
+//! pipeline removes the system offset, unfolds the wraps along each ray,
+//! and applies reflectivity-adaptive smoothing, producing a PHIDP suitable
+//! for KDP estimation and attenuation correction.
+//!
+//! KDP is estimated from processed PHIDP as half its range derivative,
+//! `KDP = ½·dΦDP/dr` (°/km), the slope being a least-squares fit over a
+//! window of gates centred on each gate.
+
+use ndarray::Array2;
+
+use crate::{MomentData, Provenance, RadishError, Result, SweepData, VolumeData};
+
+/// Options for PHIDP processing
+#[derive(Debug, Clone)]
+pub struct PhidpOptions {
+    /// Raw differential phase moment
+    pub moment: String,
+    /// Correlation coefficient moment used to select meteorological gates
+    pub rhohv: String,
+    /// Reflectivity moment used to adapt the smoothing window
+    pub reflectivity: String,
+    /// Output moment name
+    pub output_moment: String,
+    /// System phase offset (degrees); estimated from the data if `None`
+    pub system_phase: Option<f32>,
+    /// Wrap interval (degrees, 180 or 360); detected from the data range if `None`
+    pub wrap: Option<f32>,
+    /// Minimum RHOHV for a gate to be treated as rain when estimating the offset
+    pub min_rhohv: f32,
+    /// Number of consecutive rain gates at the start of a ray used for the offset
+    pub offset_gates: usize,
+    /// Number of previous gates used as reference when unfolding
+    pub unfold_reference_gates: usize,
+    /// Median filter window (gates) applied before smoothing
+    pub median_window: usize,
+    /// Averaging window (gates) in heavy precipitation
+    pub short_window: usize,
+    /// Averaging window (gates) in light precipitation
+    pub long_window: usize,
+    /// Reflectivity (dBZ) above which the short window is used
+    pub heavy_rain_threshold: f32,
+}
+
+impl Default for PhidpOptions {
+    fn default() -> Self {
+        Self {
+            moment: "PHIDP".to_string(),
+            rhohv: "RHOHV".to_string(),
+            reflectivity: "DBZH".to_string(),
+            output_moment: "PHIDP_CORR".to_string(),
+            system_phase: None,
+            wrap: None,
+            min_rhohv: 0.9,
+            offset_gates: 10,
+            unfold_reference_gates: 5,
+            median_window: 5,
+            short_window: 9,
+            long_window: 25,
+            heavy_rain_threshold: 40.0,
+        }
+    }
+}
+
+/// Process PHIDP in every sweep of a volume
+///
+/// Adds `options.output_moment` to each sweep with a PHIDP moment. The
+/// system phase used for each sweep is stored in the output moment's
+/// `system_phidp` attribute.
+pub fn process_phidp(volume: &VolumeData, options: &PhidpOptions) -> Result<VolumeData> {
+    let mut volume = volume.clone();
+    let calibrated = volume
+        .calibration
+        .as_ref()
+        .and_then(|c| c.system_phidp)
+        .map(|p| p as f32);
+
+    for sweep in &mut volume.sweeps {
+        if sweep.get_moment(&options.moment).is_none() {
+            continue;
+        }
+        let mut sweep_options = options.clone();
+        sweep_options.system_phase = options.system_phase.or(calibrated);
+
+        let processed = process_sweep_phidp(sweep, &sweep_options)?;
+        sweep.moments.insert(options.output_moment.clone(), processed);
+    }
+
+    volume.history.record("radish.transforms.phidp.process_phidp", options);
+    Ok(volume)
+}
+
+/// Options for KDP estimation
+#[derive(Debug, Clone)]
+pub struct KdpOptions {
+    /// Processed differential phase moment (degrees), e.g. from `process_phidp`
+    pub moment: String,
+    /// Output moment name
+    pub output_moment: String,
+    /// Fit window (gates)
+    pub window: usize,
+    /// Minimum number of valid gates in the window for a KDP estimate
+    pub min_gates: usize,
+}
+
+impl Default for KdpOptions {
+    fn default() -> Self {
+        Self {
+            moment: "PHIDP_CORR".to_string(),
+            output_moment: "KDP".to_string(),
+            window: 9,
+            min_gates: 5,
+        }
+    }
+}
+
+/// Estimate KDP in every sweep of a volume
+///
+/// Adds `options.output_moment` to each sweep with the processed PHIDP
+/// moment.
+pub fn compute_kdp(volume: &VolumeData, options: &KdpOptions) -> Result<VolumeData> {
+    let mut volume = volume.clone();
+    for sweep in &mut volume.sweeps {
+        if sweep.get_moment(&options.moment).is_none() {
+            continue;
+        }
+        let kdp = sweep_kdp(sweep, options)?;
+        sweep.moments.insert(options.output_moment.clone(), kdp);
+    }
+    volume.history.record("radish.transforms.phidp.compute_kdp", options);
+    Ok(volume)
+}
+
+/// Estimate KDP for a single sweep, returning the KDP moment
+pub fn sweep_kdp(sweep: &SweepData, options: &KdpOptions) -> Result<MomentData> {
+    let phidp = sweep
+        .get_moment(&options.moment)
+        .ok_or_else(|| RadishError::MissingVariable(options.moment.clone()))?;
+    let (num_rays, num_gates) = phidp.shape();
+    // Gate ranges in km
+    let range: Vec<f64> = sweep.coordinates.range.iter().map(|&r| r as f64 / 1000.0).collect();
+    let half = options.window / 2;
+
+    let out = Array2::from_shape_fn((num_rays, num_gates), |(i, j)| {
+        if !phidp.is_valid(i, j) {
+            return f32::NAN;
+        }
+        let gates: Vec<(f64, f64)> = (j.saturating_sub(half)..(j + half + 1).min(num_gates))
+            .filter_map(|k| Some((range[k], phidp.valid_value(i, k)? as f64)))
+            .collect();
+        if gates.len() < options.min_gates.max(2) {
+            return f32::NAN;
+        }
+        let n = gates.len() as f64;
+        let mean_r = gates.iter().map(|g| g.0).sum::<f64>() / n;
+        let mean_phi = gates.iter().map(|g| g.1).sum::<f64>() / n;
+        let (covariance, variance) = gates.iter().fold((0.0, 0.0), |(c, v), &(r, phi)| {
+            (c + (r - mean_r) * (phi - mean_phi), v + (r - mean_r).powi(2))
+        });
+        if variance > 0.0 {
+            (0.5 * covariance / variance) as f32
+        } else {
+            f32::NAN
+        }
+    });
+
+    let mut moment = MomentData::new(options.output_moment.clone(), "degrees/km".to_string(), out);
+    moment.standard_name = Some("specific_differential_phase_hv".to_string());
+    moment.long_name = Some("Specific differential phase".to_string());
+    moment.provenance = Some(Provenance::new(
+        "radish.transforms.phidp.sweep_kdp",
+        &[&options.moment],
+        options,
+    ));
+    Ok(moment)
+}
+
+/// Process PHIDP for a single sweep, returning the processed moment
+pub fn process_sweep_phidp(sweep: &SweepData, options: &PhidpOptions) -> Result<MomentData> {
+    let phidp = sweep
+        .get_moment(&options.moment)
+        .ok_or_else(|| RadishError::MissingVariable(options.moment.clone()))?;
+    let refl = sweep.get_moment(&options.reflectivity);
+
+    let mut ancestors = vec![options.moment.as_str()];
+    if refl.is_some() {
+        ancestors.push(&options.reflectivity);
+    }
+    if options.system_phase.is_none() && sweep.get_moment(&options.rhohv).is_some() {
+        ancestors.push(&options.rhohv);
+    }
+
+    let wrap = options.wrap.unwrap_or_else(|| detect_wrap(phidp));
+    let offset = options
+        .system_phase
+        .or_else(|| estimate_system_phase(sweep, options))
+        .unwrap_or(0.0);
+
+    let (num_rays, num_gates) = phidp.shape();
+    let missing = phidp.missing_value();
+    let mut out = Array2::from_elem((num_rays, num_gates), missing);
+
+    let data = phidp.data();
+    for i in 0..num_rays {
+        let row = data.row(i);
+        let valid: Vec<bool> = (0..num_gates).map(|j| phidp.is_valid(i, j)).collect();
+        let mut values: Vec<f32> = row.iter().map(|&v| v - offset).collect();
+
+        unfold_ray(&mut values, &valid, wrap, options.unfold_reference_gates);
+
+        let refl_row: Option<Vec<f32>> =
+            refl.map(|m| (0..num_gates).map(|j| m.valid_value(i, j).unwrap_or(f32::NAN)).collect());
+        let smoothed = smooth_ray(&values, &valid, refl_row.as_deref(), options);
+
+        for j in 0..num_gates {
+            if valid[j] {
+                out[[i, j]] = smoothed[j];
+            }
+        }
+    }
+
+    let mut moment = MomentData::new(options.output_moment.clone(), "degrees".to_string(), out);
+    moment.standard_name = Some("differential_phase_hv".to_string());
+    moment.long_name = Some("Processed differential propagation phase".to_string());
+    moment.fill_value = phidp.physical_fill_value();
+    moment
+        .attributes
+        .insert("system_phidp".to_string(), offset.to_string().into());
+    moment.provenance = Some(Provenance::new(
+        "radish.transforms.phidp.process_sweep_phidp",
+        &ancestors,
+        options,
+    ));
+    Ok(moment)
+}
+
+/// Estimate the system phase offset (degrees) of a sweep
+///
+/// For each ray the median of the first `offset_gates` consecutive rain
+/// gates (RHOHV ≥ `min_rhohv`) is taken; the sweep estimate is the median
+/// over rays. Returns `None` if no ray has enough rain gates.
+pub fn estimate_system_phase(sweep: &SweepData, options: &PhidpOptions) -> Option<f32> {
+    let phidp = sweep.get_moment(&options.moment)?;
+    let rhohv = sweep.get_moment(&options.rhohv);
+
+    let mut ray_offsets = Vec::new();
+    for i in 0..phidp.shape().0 {
+        let mut run: Vec<f32> = Vec::with_capacity(options.offset_gates);
+        for j in 0..phidp.shape().1 {
+            let v = phidp
+                .valid_value(i, j)
+                .filter(|_| rhohv.is_none_or(|r| r.valid_value(i, j).is_some_and(|rho| rho >= options.min_rhohv)));
+
+            if let Some(v) = v {
+                run.push(v);
+                if run.len() == options.offset_gates {
+                    break;
+                }
+            } else {
+                run.clear();
+            }
+        }
+
+        if run.len() == options.offset_gates && !run.is_empty() {
+            ray_offsets.push(median(&mut run));
+        }
+    }
+
+    (!ray_offsets.is_empty()).then(|| median(&mut ray_offsets))
+}
+
+/// Detect whether PHIDP is reported over a 180° or a 360° interval
+fn detect_wrap(phidp: &MomentData) -> f32 {
+    let (min, max) = (
+        phidp.min_valid().unwrap_or(f32::INFINITY),
+        phidp.max_valid().unwrap_or(f32::NEG_INFINITY),
+    );
+
+    if max - min <= 181.0 {
+        180.0
+    } else {
+        360.0
+    }
+}
+
+/// Unfold wraps along a ray in place
+///
+/// Each valid gate is shifted by multiples of `wrap` to lie within half a
+/// wrap of the median of the previous `reference_gates` unfolded values.
+pub fn unfold_ray(values: &mut [f32], valid: &[bool], wrap: f32, reference_gates: usize) {
+    let mut history: Vec<f32> = Vec::new();
+
+    for (v, &ok) in values.iter_mut().zip(valid) {
+        if !ok {
+            continue;
+        }
+
+        let reference = if history.is_empty() {
+            0.0
+        } else {
+            let start = history.len().saturating_sub(reference_gates.max(1));
+            median(&mut history[start..].to_vec())
+        };
+
+        *v += ((reference - *v) / wrap).round() * wrap;
+        history.push(*v);
+    }
+}
+
+/// Median filter followed by a reflectivity-adaptive moving average
+fn smooth_ray(values: &[f32], valid: &[bool], refl: Option<&[f32]>, options: &PhidpOptions) -> Vec<f32> {
+    let n = values.len();
+    let half_median = options.median_window / 2;
+
+    let filtered: Vec<f32> = (0..n)
+        .map(|j| {
+            if !valid[j] {
+                return values[j];
+            }
+            let mut window: Vec<f32> = (j.saturating_sub(half_median)..(j + half_median + 1).min(n))
+                .filter(|&k| valid[k])
+                .map(|k| values[k])
+                .collect();
+            median(&mut window)
+        })
+        .collect();
+
+    (0..n)
+        .map(|j| {
+            if !valid[j] {
+                return filtered[j];
+            }
+            let heavy = refl.is_some_and(|z| z[j] >= options.heavy_rain_threshold);
+            let half = if heavy {
+                options.short_window / 2
+            } else {
+                options.long_window / 2
+            };
+
+            let (sum, count) = (j.saturating_sub(half)..(j + half + 1).min(n))
+                .filter(|&k| valid[k])
+                .fold((0.0f32, 0usize), |(s, c), k| (s + filtered[k], c + 1));
+            sum / count as f32
+        })
+        .collect()
+}
+
+fn median(values: &mut [f32]) -> f32 {
+    values.sort_by(|a, b| a.total_cmp(b));
+    let n = values.len();
+    if n == 0 {
+        f32::NAN
+    } else if n % 2 == 1 {
+        values[n / 2]
+    } else {
+        (values[n / 2 - 1] + values[n / 2]) / 2.0
+    }
+}
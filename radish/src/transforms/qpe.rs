@@ -0,0 +1,588 @@
+//! Quantitative precipitation estimation (QPE)
+//!
+//! Rain rates (mm/h) are estimated with a Z-R power law, with the
+//! polarimetric R(KDP) and R(A) relations, or with a blend of the three
+//! choosing per gate the estimator best suited to the echo: R(KDP) in heavy
+//! rain, R(A) where specific attenuation is available and R(Z) elsewhere.
+//! The estimator used at each gate is recorded in a flag moment.
+//!
+//! Optionally each rain rate comes with uncertainty companions: the spread
+//! (standard deviation) of the rates given by every estimator whose input
+//! is valid at the gate, and a quality index from 0 to 1 combining their
+//! agreement with an optional input quality moment (e.g. one minus the beam
+//! blockage fraction).
+//!
+//! Snowfall rates (liquid equivalent, mm/h) are estimated with S(Z) power
+//! laws and, where KDP is significant, the S(KDP, Z) relation of Bukovčić
+//! et al. (2018). Each gate is classified as dry snow, wet snow or rain
+//! from a temperature or melting layer input and the relation for its
+//! phase is applied, since a single (rain) relation applied to all winter
+//! echo is badly biased.
+
+use ndarray::Array2;
+
+use crate::transforms::georeference::{compute_gate_xyz, GeoreferenceOptions};
+use crate::{MomentData, Provenance, RadishError, Result, SweepData, VolumeData};
+
+/// Name of the rain rate moment
+pub const RAIN_RATE: &str = "RATE";
+
+/// Name of the moment recording the rain rate estimator of each gate
+pub const RATE_ESTIMATOR: &str = "RATE_ESTIMATOR";
+
+/// Name of the moment holding the spread of the rain rate estimators at each gate
+pub const RATE_SPREAD: &str = "RATE_SPREAD";
+
+/// Name of the rain rate quality index moment
+pub const RATE_QUALITY: &str = "RATE_QUALITY";
+
+/// Quality index agreement factor of gates where a single estimator has valid input
+const SINGLE_ESTIMATOR_AGREEMENT: f32 = 0.5;
+
+/// Name of the snowfall rate moment
+pub const SNOW_RATE: &str = "SNOW_RATE";
+
+/// Power law `Z = a·R^b` between linear reflectivity (mm⁶ m⁻³) and a rate (mm/h)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PowerLaw {
+    pub a: f64,
+    pub b: f64,
+}
+
+impl PowerLaw {
+    /// Create a power law `Z = a·R^b`
+    pub const fn new(a: f64, b: f64) -> Self {
+        Self { a, b }
+    }
+
+    /// Rate (mm/h) for a reflectivity in dBZ
+    pub fn rate(&self, dbz: f32) -> f32 {
+        let z = 10f64.powf(dbz as f64 / 10.0);
+        (z / self.a).powf(1.0 / self.b) as f32
+    }
+}
+
+/// Power law `R = c·X^α` between a polarimetric variable and a rate (mm/h)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateRelation {
+    pub coefficient: f64,
+    pub exponent: f64,
+}
+
+impl RateRelation {
+    /// R(KDP) at S band, KDP in °/km (Ryzhkov et al., 2005)
+    pub const KDP_S_BAND: Self = Self::new(44.0, 0.822);
+
+    /// R(A) at S band, A in dB/km (Ryzhkov et al., 2014)
+    pub const ATTENUATION_S_BAND: Self = Self::new(4120.0, 1.03);
+
+    /// Create a relation `R = c·X^α`
+    pub const fn new(coefficient: f64, exponent: f64) -> Self {
+        Self { coefficient, exponent }
+    }
+
+    /// Rate (mm/h); negative inputs give negative rates of the same magnitude
+    pub fn rate(&self, x: f32) -> f32 {
+        (self.coefficient * (x.abs() as f64).powf(self.exponent)) as f32 * x.signum()
+    }
+}
+
+/// Rain rate estimator
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RainEstimator {
+    /// Z-R power law
+    Reflectivity,
+    /// R(KDP)
+    Kdp,
+    /// R(A) from specific attenuation
+    SpecificAttenuation,
+    /// Per-gate choice between R(KDP), R(A) and R(Z)
+    #[default]
+    Blended,
+}
+
+impl RainEstimator {
+    /// Value of the estimator in the `RATE_ESTIMATOR` moment (0 for `Blended`)
+    pub fn code(&self) -> f32 {
+        match self {
+            RainEstimator::Blended => 0.0,
+            RainEstimator::Reflectivity => 1.0,
+            RainEstimator::Kdp => 2.0,
+            RainEstimator::SpecificAttenuation => 3.0,
+        }
+    }
+}
+
+/// Options for rain rate estimation
+#[derive(Debug, Clone)]
+pub struct RainRateOptions {
+    /// Reflectivity moment (dBZ)
+    pub reflectivity: String,
+    /// Specific differential phase moment (°/km)
+    pub kdp: String,
+    /// Specific attenuation moment (dB/km)
+    pub specific_attenuation: String,
+    /// Estimator
+    pub estimator: RainEstimator,
+    /// Z-R relation
+    pub z_r: PowerLaw,
+    /// R(KDP) relation
+    pub kdp_relation: RateRelation,
+    /// R(A) relation
+    pub attenuation_relation: RateRelation,
+    /// Blended: minimum reflectivity (dBZ) for R(KDP)
+    pub kdp_min_reflectivity: f32,
+    /// Blended: minimum KDP (°/km) for R(KDP)
+    pub min_kdp: f32,
+    /// Blended: minimum specific attenuation (dB/km) for R(A)
+    pub min_attenuation: f32,
+    /// Reflectivity (dBZ) at which R(Z) is capped, to limit hail contamination
+    pub max_reflectivity: f32,
+    /// Gates below this reflectivity (dBZ) have no rain
+    pub min_reflectivity: f32,
+    /// Also add `RATE_SPREAD` and `RATE_QUALITY` moments
+    pub uncertainty: bool,
+    /// Quality moment (0 to 1) scaling `RATE_QUALITY`, e.g. one minus the beam blockage fraction
+    pub quality: Option<String>,
+    /// Output moment name
+    pub output_moment: String,
+}
+
+impl Default for RainRateOptions {
+    fn default() -> Self {
+        Self {
+            reflectivity: "DBZH".to_string(),
+            kdp: "KDP".to_string(),
+            specific_attenuation: "AH".to_string(),
+            estimator: RainEstimator::default(),
+            // WSR-88D default convective relation
+            z_r: PowerLaw::new(300.0, 1.4),
+            kdp_relation: RateRelation::KDP_S_BAND,
+            attenuation_relation: RateRelation::ATTENUATION_S_BAND,
+            kdp_min_reflectivity: 40.0,
+            min_kdp: 0.3,
+            min_attenuation: 0.001,
+            max_reflectivity: 53.0,
+            min_reflectivity: 5.0,
+            uncertainty: false,
+            quality: None,
+            output_moment: RAIN_RATE.to_string(),
+        }
+    }
+}
+
+/// Snowfall relation `S = c·KDP^α·Z^β` (KDP in °/km, Z linear)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KdpSnowRelation {
+    pub coefficient: f64,
+    pub kdp_exponent: f64,
+    pub z_exponent: f64,
+}
+
+/// Bukovčić et al. (2018), S band
+impl Default for KdpSnowRelation {
+    fn default() -> Self {
+        Self {
+            coefficient: 1.48,
+            kdp_exponent: 0.615,
+            z_exponent: 0.33,
+        }
+    }
+}
+
+impl KdpSnowRelation {
+    /// Snowfall rate (mm/h) for KDP (°/km) and reflectivity (dBZ)
+    pub fn rate(&self, kdp: f32, dbz: f32) -> f32 {
+        let z = 10f64.powf(dbz as f64 / 10.0);
+        (self.coefficient * (kdp as f64).powf(self.kdp_exponent) * z.powf(self.z_exponent)) as f32
+    }
+}
+
+/// Precipitation phase of a gate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnowPhase {
+    DrySnow,
+    WetSnow,
+    Rain,
+}
+
+/// Input used to decide the precipitation phase of each gate
+#[derive(Debug, Clone, PartialEq)]
+pub enum PhaseInput {
+    /// A single temperature (°C) for all gates, e.g. from a surface station
+    Temperature(f64),
+    /// A moment holding the temperature (°C) of each gate, e.g. from a model
+    TemperatureMoment(String),
+    /// Melting layer bottom and top heights (meters MSL): dry snow above the
+    /// top, wet snow within the layer and rain below it
+    MeltingLayer { bottom: f64, top: f64 },
+}
+
+/// Options for snowfall rate estimation
+#[derive(Debug, Clone)]
+pub struct SnowfallOptions {
+    /// Reflectivity moment (dBZ)
+    pub reflectivity: String,
+    /// Specific differential phase moment (°/km); only Z is used if absent
+    pub kdp: String,
+    /// Phase input
+    pub phase: PhaseInput,
+    /// Z-S relation for dry snow
+    pub dry_snow: PowerLaw,
+    /// Z-S relation for wet snow
+    pub wet_snow: PowerLaw,
+    /// S(KDP, Z) relation for dry snow (Z-S only if `None`)
+    pub kdp_relation: Option<KdpSnowRelation>,
+    /// Minimum KDP (°/km) for the S(KDP, Z) relation to be used
+    pub min_kdp: f32,
+    /// Temperature (°C) above which snow is wet
+    pub wet_snow_temperature: f64,
+    /// Temperature (°C) above which precipitation is rain
+    pub rain_temperature: f64,
+    /// Gates below this reflectivity (dBZ) have no snowfall
+    pub min_reflectivity: f32,
+    /// Output moment name
+    pub output_moment: String,
+}
+
+impl Default for SnowfallOptions {
+    fn default() -> Self {
+        Self {
+            reflectivity: "DBZH".to_string(),
+            kdp: "KDP".to_string(),
+            phase: PhaseInput::Temperature(-5.0),
+            // WSR-88D default snow relation
+            dry_snow: PowerLaw::new(75.0, 2.0),
+            // Sekhon & Srivastava (1970)
+            wet_snow: PowerLaw::new(1780.0, 2.21),
+            kdp_relation: Some(KdpSnowRelation::default()),
+            min_kdp: 0.1,
+            wet_snow_temperature: -1.0,
+            rain_temperature: 1.0,
+            min_reflectivity: 0.0,
+            output_moment: SNOW_RATE.to_string(),
+        }
+    }
+}
+
+impl SnowfallOptions {
+    /// Phase for a temperature (°C)
+    pub fn phase_for_temperature(&self, temperature: f64) -> SnowPhase {
+        if temperature > self.rain_temperature {
+            SnowPhase::Rain
+        } else if temperature > self.wet_snow_temperature {
+            SnowPhase::WetSnow
+        } else {
+            SnowPhase::DrySnow
+        }
+    }
+}
+
+/// Add rain rate and `RATE_ESTIMATOR` moments to every sweep with reflectivity
+///
+/// Rates are in mm/h. Gates whose estimator input is missing are set to the
+/// missing value; gates below `min_reflectivity` have no rain. With
+/// `uncertainty`, `RATE_SPREAD` and `RATE_QUALITY` moments are added too.
+pub fn estimate_rain_rate(volume: &VolumeData, options: &RainRateOptions) -> Result<VolumeData> {
+    let mut volume = volume.clone();
+
+    for sweep in &mut volume.sweeps {
+        if sweep.get_moment(&options.reflectivity).is_none() {
+            continue;
+        }
+        let (rate, estimator) = rain_rate_sweep(sweep, options)?;
+        if options.uncertainty {
+            let (spread, quality) = rain_rate_uncertainty_sweep(sweep, &rate, options)?;
+            sweep.moments.insert(RATE_SPREAD.to_string(), spread);
+            sweep.moments.insert(RATE_QUALITY.to_string(), quality);
+        }
+        sweep.moments.insert(options.output_moment.clone(), rate);
+        sweep.moments.insert(RATE_ESTIMATOR.to_string(), estimator);
+    }
+
+    volume.history.record("radish.transforms.qpe.estimate_rain_rate", options);
+    Ok(volume)
+}
+
+/// Rain rate and estimator moments of a single sweep
+pub fn rain_rate_sweep(sweep: &SweepData, options: &RainRateOptions) -> Result<(MomentData, MomentData)> {
+    let refl = sweep
+        .get_moment(&options.reflectivity)
+        .ok_or_else(|| RadishError::MissingVariable(options.reflectivity.clone()))?;
+    let kdp = sweep.get_moment(&options.kdp);
+    let attenuation = sweep.get_moment(&options.specific_attenuation);
+    let required = match options.estimator {
+        RainEstimator::Kdp => Some((&options.kdp, kdp)),
+        RainEstimator::SpecificAttenuation => Some((&options.specific_attenuation, attenuation)),
+        _ => None,
+    };
+    if let Some((name, None)) = required {
+        return Err(RadishError::MissingVariable(name.clone()));
+    }
+
+    let value = |m: Option<&MomentData>, idx: (usize, usize)| m.and_then(|m| m.valid_value(idx.0, idx.1));
+
+    let shape = refl.shape();
+    let mut rate = Array2::from_elem(shape, f32::NAN);
+    let mut used = Array2::from_elem(shape, f32::NAN);
+    for (idx, z) in refl.indexed_valid_values() {
+        if z < options.min_reflectivity {
+            rate[idx] = 0.0;
+            used[idx] = RainEstimator::Reflectivity.code();
+            continue;
+        }
+
+        let (k, a) = (value(kdp, idx), value(attenuation, idx));
+        let estimator = match options.estimator {
+            RainEstimator::Blended => {
+                if z >= options.kdp_min_reflectivity && k.is_some_and(|k| k >= options.min_kdp) {
+                    RainEstimator::Kdp
+                } else if a.is_some_and(|a| a >= options.min_attenuation) {
+                    RainEstimator::SpecificAttenuation
+                } else {
+                    RainEstimator::Reflectivity
+                }
+            }
+            estimator => estimator,
+        };
+        let r = match estimator {
+            RainEstimator::Kdp => k.map(|k| options.kdp_relation.rate(k).max(0.0)),
+            RainEstimator::SpecificAttenuation => a.map(|a| options.attenuation_relation.rate(a).max(0.0)),
+            _ => Some(options.z_r.rate(z.min(options.max_reflectivity))),
+        };
+        if let Some(r) = r {
+            rate[idx] = r;
+            used[idx] = estimator.code();
+        }
+    }
+
+    let mut ancestors = vec![options.reflectivity.as_str()];
+    if kdp.is_some() && matches!(options.estimator, RainEstimator::Kdp | RainEstimator::Blended) {
+        ancestors.push(&options.kdp);
+    }
+    if attenuation.is_some() && matches!(options.estimator, RainEstimator::SpecificAttenuation | RainEstimator::Blended) {
+        ancestors.push(&options.specific_attenuation);
+    }
+    let provenance = Provenance::new("radish.transforms.qpe.rain_rate_sweep", &ancestors, options);
+
+    let mut rate = MomentData::new(options.output_moment.clone(), "mm/h".to_string(), rate);
+    rate.long_name = Some("Rain rate".to_string());
+    rate.standard_name = Some("rainfall_rate".to_string());
+    rate.provenance = Some(provenance.clone());
+
+    let mut estimator = MomentData::new(RATE_ESTIMATOR.to_string(), String::new(), used);
+    estimator.long_name = Some("Rain rate estimator".to_string());
+    estimator
+        .attributes
+        .insert("flag_values".to_string(), vec![1i64, 2, 3].into());
+    estimator.attributes.insert(
+        "flag_meanings".to_string(),
+        "reflectivity kdp specific_attenuation".into(),
+    );
+    estimator.provenance = Some(provenance);
+
+    Ok((rate, estimator))
+}
+
+/// Rain rate spread and quality index moments of a single sweep
+///
+/// `rate` is the sweep's rain rate from [`rain_rate_sweep`]; gates where it
+/// is missing are missing in both moments. The spread (mm/h) is the
+/// standard deviation of the R(Z), R(KDP) and R(A) rates of the gate, using
+/// R(KDP) and R(A) where their input reaches `min_kdp` and
+/// `min_attenuation`, and is missing where fewer than two estimators apply.
+/// The quality index is `1 / (1 + spread / mean rate)`, 0.5 where a single
+/// estimator applies and 1 below `min_reflectivity`, times the `quality`
+/// moment if given; it is missing where that moment is.
+pub fn rain_rate_uncertainty_sweep(
+    sweep: &SweepData,
+    rate: &MomentData,
+    options: &RainRateOptions,
+) -> Result<(MomentData, MomentData)> {
+    let refl = sweep
+        .get_moment(&options.reflectivity)
+        .ok_or_else(|| RadishError::MissingVariable(options.reflectivity.clone()))?;
+    let input_quality = match &options.quality {
+        Some(name) => Some(
+            sweep
+                .get_moment(name)
+                .ok_or_else(|| RadishError::MissingVariable(name.clone()))?,
+        ),
+        None => None,
+    };
+    if rate.shape() != refl.shape() {
+        return Err(RadishError::General(format!(
+            "Rain rate shape {:?} does not match reflectivity {:?}",
+            rate.shape(),
+            refl.shape()
+        )));
+    }
+    let kdp = sweep.get_moment(&options.kdp);
+    let attenuation = sweep.get_moment(&options.specific_attenuation);
+    let value = |m: Option<&MomentData>, idx: (usize, usize)| m.and_then(|m| m.valid_value(idx.0, idx.1));
+
+    let shape = refl.shape();
+    let mut spread = Array2::from_elem(shape, f32::NAN);
+    let mut quality = Array2::from_elem(shape, f32::NAN);
+    for (idx, z) in refl.indexed_valid_values() {
+        if rate.valid_value(idx.0, idx.1).is_none() {
+            continue;
+        }
+        // Gates without the input quality have a spread but no quality index
+        let scale = match input_quality {
+            Some(q) => q.valid_value(idx.0, idx.1).map(|q| q.clamp(0.0, 1.0)),
+            None => Some(1.0),
+        };
+        if z < options.min_reflectivity {
+            spread[idx] = 0.0;
+            quality[idx] = scale.unwrap_or(f32::NAN);
+            continue;
+        }
+
+        let mut rates = vec![options.z_r.rate(z.min(options.max_reflectivity))];
+        if let Some(k) = value(kdp, idx).filter(|&k| k >= options.min_kdp) {
+            rates.push(options.kdp_relation.rate(k));
+        }
+        if let Some(a) = value(attenuation, idx).filter(|&a| a >= options.min_attenuation) {
+            rates.push(options.attenuation_relation.rate(a));
+        }
+
+        let agreement = if rates.len() < 2 {
+            SINGLE_ESTIMATOR_AGREEMENT
+        } else {
+            let n = rates.len() as f32;
+            let mean = rates.iter().sum::<f32>() / n;
+            let sd = (rates.iter().map(|r| (r - mean).powi(2)).sum::<f32>() / (n - 1.0)).sqrt();
+            spread[idx] = sd;
+            if mean > 0.0 {
+                1.0 / (1.0 + sd / mean)
+            } else {
+                1.0
+            }
+        };
+        quality[idx] = scale.map_or(f32::NAN, |scale| agreement * scale);
+    }
+
+    let mut ancestors = vec![options.reflectivity.as_str()];
+    if kdp.is_some() {
+        ancestors.push(&options.kdp);
+    }
+    if attenuation.is_some() {
+        ancestors.push(&options.specific_attenuation);
+    }
+    if let Some(name) = &options.quality {
+        ancestors.push(name);
+    }
+    let provenance = Provenance::new("radish.transforms.qpe.rain_rate_uncertainty_sweep", &ancestors, options);
+
+    let mut spread = MomentData::new(RATE_SPREAD.to_string(), "mm/h".to_string(), spread);
+    spread.long_name = Some("Standard deviation of rain rate estimators".to_string());
+    spread.provenance = Some(provenance.clone());
+
+    let mut quality = MomentData::new(RATE_QUALITY.to_string(), String::new(), quality);
+    quality.long_name = Some("Rain rate quality index".to_string());
+    quality.valid_min = Some(0.0);
+    quality.valid_max = Some(1.0);
+    quality.provenance = Some(provenance);
+
+    Ok((spread, quality))
+}
+
+/// Add a snowfall rate moment to every sweep with reflectivity
+///
+/// Rates are liquid equivalent (mm/h). Gates classified as rain, and gates
+/// with missing reflectivity, are set to the missing value.
+pub fn estimate_snowfall(volume: &VolumeData, options: &SnowfallOptions) -> Result<VolumeData> {
+    let mut volume = volume.clone();
+    let altitude = volume.metadata.altitude;
+
+    for sweep in &mut volume.sweeps {
+        if sweep.get_moment(&options.reflectivity).is_none() {
+            continue;
+        }
+        let rate = snowfall_sweep(sweep, altitude, options)?;
+        sweep.moments.insert(options.output_moment.clone(), rate);
+    }
+
+    volume.history.record("radish.transforms.qpe.estimate_snowfall", options);
+    Ok(volume)
+}
+
+/// Snowfall rate moment of a single sweep of a radar at `altitude` (meters MSL)
+pub fn snowfall_sweep(sweep: &SweepData, altitude: f64, options: &SnowfallOptions) -> Result<MomentData> {
+    let refl = sweep
+        .get_moment(&options.reflectivity)
+        .ok_or_else(|| RadishError::MissingVariable(options.reflectivity.clone()))?;
+    let kdp = sweep.get_moment(&options.kdp);
+    let phase = gate_phases(sweep, altitude, options)?;
+
+    let (num_rays, num_gates) = refl.shape();
+    let mut rate = Array2::from_elem((num_rays, num_gates), f32::NAN);
+    for ((idx, &z), &phase) in refl.data().indexed_iter().zip(phase.iter()) {
+        let Some(phase) = phase else {
+            continue;
+        };
+        if !refl.is_valid(idx.0, idx.1) || phase == SnowPhase::Rain {
+            continue;
+        }
+        if z < options.min_reflectivity {
+            rate[idx] = 0.0;
+            continue;
+        }
+
+        let kdp_value = kdp.and_then(|m| m.valid_value(idx.0, idx.1).filter(|&k| k >= options.min_kdp));
+        rate[idx] = match (phase, kdp_value, &options.kdp_relation) {
+            (SnowPhase::DrySnow, Some(k), Some(relation)) => relation.rate(k, z),
+            (SnowPhase::DrySnow, _, _) => options.dry_snow.rate(z),
+            _ => options.wet_snow.rate(z),
+        };
+    }
+
+    let mut moment = MomentData::new(options.output_moment.clone(), "mm/h".to_string(), rate);
+    moment.long_name = Some("Liquid equivalent snowfall rate".to_string());
+    moment.standard_name = Some("lwe_snowfall_rate".to_string());
+
+    let mut ancestors = vec![options.reflectivity.as_str()];
+    if kdp.is_some() && options.kdp_relation.is_some() {
+        ancestors.push(&options.kdp);
+    }
+    if let PhaseInput::TemperatureMoment(name) = &options.phase {
+        ancestors.push(name);
+    }
+    moment.provenance = Some(Provenance::new("radish.transforms.qpe.snowfall_sweep", &ancestors, options));
+    Ok(moment)
+}
+
+/// Phase of each gate (`None` where the phase input is missing)
+fn gate_phases(sweep: &SweepData, altitude: f64, options: &SnowfallOptions) -> Result<Array2<Option<SnowPhase>>> {
+    let shape = (sweep.num_rays(), sweep.num_gates());
+    match &options.phase {
+        PhaseInput::Temperature(t) => Ok(Array2::from_elem(shape, Some(options.phase_for_temperature(*t)))),
+        PhaseInput::TemperatureMoment(name) => {
+            let temperature = sweep
+                .get_moment(name)
+                .ok_or_else(|| RadishError::MissingVariable(name.clone()))?;
+            Ok(Array2::from_shape_fn(shape, |(i, j)| {
+                temperature
+                    .valid_value(i, j)
+                    .map(|t| options.phase_for_temperature(t as f64))
+            }))
+        }
+        PhaseInput::MeltingLayer { bottom, top } => {
+            let height = match &sweep.coordinates.gate_z {
+                Some(z) => z.clone(),
+                None => compute_gate_xyz(&sweep.coordinates, &GeoreferenceOptions::default()).2,
+            };
+            Ok(height.mapv(|z| {
+                let z = z as f64 + altitude;
+                Some(if z >= *top {
+                    SnowPhase::DrySnow
+                } else if z >= *bottom {
+                    SnowPhase::WetSnow
+                } else {
+                    SnowPhase::Rain
+                })
+            }))
+        }
+    }
+}
@@ -0,0 +1,166 @@
+//! Removal of antenna-transition and duplicate rays
+//!
+//! Rays recorded while the antenna moves from one sweep to the next point
+//! at the wrong fixed angle, and a sweep that rotates slightly more than a
+//! full circle (or an RHI that re-scans its end) contains rays at the same
+//! scan angle twice. Both confuse gridding and ray-based algorithms. The
+//! cleanup drops flagged transition rays, then groups the remaining rays
+//! whose scan angle (azimuth for PPIs, elevation for RHIs) lies within a
+//! tolerance of an earlier ray and reduces each group to one ray according
+//! to a [`DuplicatePolicy`]. Coordinates and every moment are subset
+//! together, in the original ray order.
+
+use radish_types::SweepMode;
+
+use crate::transforms::reindex::average_rays;
+use crate::model::coordinates::azimuth_distance;
+use crate::{Coordinates, PlatformMotion, RayMetadata, Result, SweepData, VolumeData};
+
+/// How rays at the same scan angle are reduced to one
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum DuplicatePolicy {
+    /// Keep the earliest ray
+    #[default]
+    KeepFirst,
+    /// Keep the latest ray
+    KeepLast,
+    /// Average the valid values of the rays, gate by gate
+    Average,
+}
+
+/// Options for ray cleanup
+#[derive(Debug, Clone)]
+pub struct RayCleanupOptions {
+    /// Drop rays flagged as antenna transition
+    pub drop_transitions: bool,
+    /// Handling of rays at the same scan angle
+    pub duplicates: DuplicatePolicy,
+    /// Rays closer than this (degrees) are duplicates; half the median ray spacing if `None`
+    pub angle_tolerance: Option<f32>,
+}
+
+impl Default for RayCleanupOptions {
+    fn default() -> Self {
+        Self {
+            drop_transitions: true,
+            duplicates: DuplicatePolicy::default(),
+            angle_tolerance: None,
+        }
+    }
+}
+
+/// Remove antenna-transition and duplicate rays from every sweep of a volume
+pub fn clean_rays(volume: &VolumeData, options: &RayCleanupOptions) -> Result<VolumeData> {
+    let mut out = volume.clone();
+    for sweep in &mut out.sweeps {
+        *sweep = clean_sweep_rays(sweep, options)?;
+    }
+    out.history.record("radish.transforms.ray_cleanup.clean_rays", options);
+    Ok(out)
+}
+
+/// Remove antenna-transition and duplicate rays from a sweep
+pub fn clean_sweep_rays(sweep: &SweepData, options: &RayCleanupOptions) -> Result<SweepData> {
+    sweep.coordinates.validate()?;
+    let coords = &sweep.coordinates;
+    let rhi = matches!(sweep.metadata.sweep_mode, SweepMode::Elevation | SweepMode::ManualRhi);
+    let angles = if rhi { &coords.elevation } else { &coords.azimuth };
+    let distance = |a: f32, b: f32| if rhi { (a - b).abs() } else { azimuth_distance(a, b) };
+
+    let kept: Vec<usize> = (0..coords.num_rays())
+        .filter(|&i| {
+            !(options.drop_transitions && coords.antenna_transition.as_ref().is_some_and(|t| t[i]))
+                && angles[i].is_finite()
+        })
+        .collect();
+    let tolerance = options.angle_tolerance.unwrap_or_else(|| {
+        let mut spacing: Vec<f32> = kept.windows(2).map(|w| distance(angles[w[0]], angles[w[1]])).collect();
+        spacing.sort_by(f32::total_cmp);
+        spacing.get(spacing.len() / 2).map_or(0.0, |s| s / 2.0)
+    });
+
+    // Groups of rays at the same scan angle, in order of their first ray
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    for &i in &kept {
+        match groups.iter_mut().find(|g| distance(angles[g[0]], angles[i]) < tolerance) {
+            Some(group) => group.push(i),
+            None => groups.push(vec![i]),
+        }
+    }
+    if options.duplicates != DuplicatePolicy::Average {
+        for group in &mut groups {
+            let ray = match options.duplicates {
+                DuplicatePolicy::KeepLast => group[group.len() - 1],
+                _ => group[0],
+            };
+            *group = vec![ray];
+        }
+    }
+
+    let coordinates = average_ray_coordinates(coords, &groups);
+    let mut out = SweepData::new(sweep.metadata.clone(), average_rays(sweep, &groups), coordinates);
+    (out.ray_metadata, out.platform_motion) = first_ray_metadata(sweep, &groups);
+    Ok(out)
+}
+
+/// Per-ray instrument parameters and platform motion of the first ray of each non-empty group of rays
+pub(crate) fn first_ray_metadata(
+    sweep: &SweepData,
+    groups: &[Vec<usize>],
+) -> (Option<RayMetadata>, Option<PlatformMotion>) {
+    let first: Vec<usize> = groups.iter().map(|g| g[0]).collect();
+    (
+        sweep.ray_metadata.as_ref().map(|r| r.select(&first)),
+        sweep.platform_motion.as_ref().map(|m| m.select(&first)),
+    )
+}
+
+/// Coordinates with one ray per non-empty group of rays
+///
+/// Times and elevations are averaged, azimuths averaged across north, and
+/// a ray is an antenna transition if any ray of its group is and has the
+/// gates of the longest ray of its group.
+pub(crate) fn average_ray_coordinates(coords: &Coordinates, groups: &[Vec<usize>]) -> Coordinates {
+    let time = groups
+        .iter()
+        .map(|g| g.iter().map(|&i| coords.time[i]).sum::<f64>() / g.len() as f64)
+        .collect();
+    // Mean azimuth across north: average the offsets from the first ray
+    let azimuth = groups
+        .iter()
+        .map(|g| {
+            let first = coords.azimuth[g[0]];
+            let offset = g
+                .iter()
+                .map(|&i| {
+                    let d = coords.azimuth[i] - first;
+                    d - 360.0 * (d / 360.0).round()
+                })
+                .sum::<f32>()
+                / g.len() as f32;
+            (first + offset).rem_euclid(360.0)
+        })
+        .collect();
+    let elevation = groups
+        .iter()
+        .map(|g| g.iter().map(|&i| coords.elevation[i]).sum::<f32>() / g.len() as f32)
+        .collect();
+    let mut coordinates = Coordinates::new(time, coords.range.clone(), azimuth, elevation);
+    coordinates.time_reference = coords.time_reference;
+    if let Some(transition) = &coords.antenna_transition {
+        coordinates.antenna_transition = Some(groups.iter().map(|g| g.iter().any(|&i| transition[i])).collect());
+    }
+    coordinates.n_gates_per_ray = group_gates_per_ray(coords, groups);
+    coordinates
+}
+
+/// Gate count of the longest ray of each group of rays of a ragged sweep, 0 for empty groups
+pub(crate) fn group_gates_per_ray(coords: &Coordinates, groups: &[Vec<usize>]) -> Option<Vec<usize>> {
+    coords.n_gates_per_ray.as_ref()?;
+    Some(
+        groups
+            .iter()
+            .map(|g| g.iter().map(|&i| coords.ray_gates(i)).max().unwrap_or(0))
+            .collect(),
+    )
+}
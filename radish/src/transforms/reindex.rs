@@ -0,0 +1,155 @@
+//! Re-indexing of PPI rays onto a uniform azimuth grid
+//!
+//! Rays of an antenna sweep are rarely exactly one resolution apart: the
+//! first and last rays overlap, rays are missing after dropouts and the
+//! azimuths drift. Gridding and machine learning pipelines want a fixed
+//! number of rays at fixed azimuths, so each ray is assigned to the bin
+//! whose center is nearest to it, duplicate rays in a bin are reduced with
+//! a [`DuplicateRays`] policy, and empty bins are filled with the missing
+//! value of each moment.
+
+use std::collections::HashMap;
+
+use ndarray::Array2;
+use radish_types::SweepMode;
+
+use crate::transforms::ray_cleanup::group_gates_per_ray;
+use crate::model::coordinates::azimuth_distance;
+use crate::{Coordinates, MomentData, RadishError, Result, SweepData, VolumeData};
+
+/// How rays falling in the same azimuth bin are combined
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum DuplicateRays {
+    /// Keep the ray nearest to the bin center
+    #[default]
+    Nearest,
+    /// Average the valid values of all rays in the bin, gate by gate
+    Average,
+}
+
+/// Options for azimuth re-indexing
+#[derive(Debug, Clone)]
+pub struct ReindexOptions {
+    /// Azimuth spacing of the grid (degrees); must divide 360
+    pub resolution: f32,
+    /// Center of the first azimuth bin (degrees)
+    pub first_azimuth: f32,
+    /// Handling of several rays in one bin
+    pub duplicates: DuplicateRays,
+}
+
+impl Default for ReindexOptions {
+    fn default() -> Self {
+        Self {
+            resolution: 1.0,
+            first_azimuth: 0.5,
+            duplicates: DuplicateRays::default(),
+        }
+    }
+}
+
+/// Re-index the full-circle PPI sweeps of a volume onto a uniform azimuth grid
+///
+/// Sector scans, RHIs and other sweeps are left unchanged.
+pub fn reindex_azimuth(volume: &VolumeData, options: &ReindexOptions) -> Result<VolumeData> {
+    let mut out = volume.clone();
+    for sweep in &mut out.sweeps {
+        if matches!(sweep.metadata.sweep_mode, SweepMode::Azimuth | SweepMode::ManualPpi) {
+            *sweep = reindex_sweep(sweep, options)?;
+        }
+    }
+    out.history.record("radish.transforms.reindex.reindex_azimuth", options);
+    Ok(out)
+}
+
+/// Re-index the rays of a sweep onto a uniform azimuth grid
+///
+/// The sweep gets `360 / resolution` rays in increasing azimuth. Rays of an
+/// empty bin have a NaN time, the fixed angle as elevation and the missing
+/// value of each moment. Per-ray instrument parameters are not carried
+/// over.
+pub fn reindex_sweep(sweep: &SweepData, options: &ReindexOptions) -> Result<SweepData> {
+    let bins = 360.0 / options.resolution;
+    if !bins.is_finite() || bins < 1.0 || (bins - bins.round()).abs() > 1e-3 {
+        return Err(RadishError::General(format!(
+            "Azimuth resolution {} does not divide 360°",
+            options.resolution
+        )));
+    }
+    let num_bins = bins.round() as usize;
+    let center = |k: usize| (options.first_azimuth + k as f32 * options.resolution).rem_euclid(360.0);
+
+    // Rays of each bin
+    let mut members: Vec<Vec<usize>> = vec![Vec::new(); num_bins];
+    for (i, &az) in sweep.coordinates.azimuth.iter().enumerate() {
+        if az.is_finite() {
+            let k = ((az - options.first_azimuth) / options.resolution).round() as i64;
+            members[k.rem_euclid(num_bins as i64) as usize].push(i);
+        }
+    }
+    if options.duplicates == DuplicateRays::Nearest {
+        for (k, rays) in members.iter_mut().enumerate() {
+            let c = center(k);
+            if let Some(&nearest) = rays.iter().min_by(|&&a, &&b| {
+                azimuth_distance(sweep.coordinates.azimuth[a], c)
+                    .total_cmp(&azimuth_distance(sweep.coordinates.azimuth[b], c))
+            }) {
+                *rays = vec![nearest];
+            }
+        }
+    }
+
+    let coords = &sweep.coordinates;
+    let mean = |rays: &[usize], value: &dyn Fn(usize) -> f64| {
+        rays.iter().map(|&i| value(i)).sum::<f64>() / rays.len() as f64
+    };
+    let time = members
+        .iter()
+        .map(|rays| if rays.is_empty() { f64::NAN } else { mean(rays, &|i| coords.time[i]) })
+        .collect();
+    let elevation = members
+        .iter()
+        .map(|rays| {
+            if rays.is_empty() {
+                sweep.metadata.fixed_angle as f32
+            } else {
+                mean(rays, &|i| coords.elevation[i] as f64) as f32
+            }
+        })
+        .collect();
+    let azimuth = (0..num_bins).map(center).collect();
+
+    let moments = average_rays(sweep, &members);
+
+    let mut metadata = sweep.metadata.clone();
+    metadata.rays_are_indexed = Some(true);
+    metadata.ray_angle_resolution = Some(options.resolution as f64);
+    let mut coordinates = Coordinates::new(time, coords.range.clone(), azimuth, elevation);
+    coordinates.time_reference = coords.time_reference;
+    coordinates.n_gates_per_ray = group_gates_per_ray(coords, &members);
+    Ok(SweepData::new(metadata, moments, coordinates))
+}
+
+/// Moments of a sweep with one ray per group of rays, averaging valid values gate by gate
+///
+/// Gates without a valid value in the group, and empty groups, get the
+/// missing value of the moment.
+pub(crate) fn average_rays(sweep: &SweepData, groups: &[Vec<usize>]) -> HashMap<String, MomentData> {
+    let mut moments = HashMap::new();
+    for (name, moment) in &sweep.moments {
+        let missing = moment.missing_value();
+        let data = Array2::from_shape_fn((groups.len(), sweep.num_gates()), |(k, j)| {
+            let (sum, count) = groups[k]
+                .iter()
+                .filter_map(|&i| moment.valid_value(i, j))
+                .fold((0.0f64, 0usize), |(s, n), v| (s + v as f64, n + 1));
+            if count == 0 {
+                missing
+            } else {
+                (sum / count as f64) as f32
+            }
+        });
+        moments.insert(name.clone(), moment.with_data(data));
+    }
+    moments
+}
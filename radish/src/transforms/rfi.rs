@@ -0,0 +1,257 @@
+//! Radio frequency interference (RFI) spike detection
+//!
+//! External emitters (e.g. 5G and WiFi transmitters near C-band) show up as
+//! narrow spokes of elevated power along entire rays. Because the power is
+//! received noise rather than a target, reflectivity along the spoke grows
+//! like 20·log10(range): after removing that range dependence the echo is
+//! nearly flat. Spikes are also fixed in azimuth, so a real interferer is
+//! seen at the same azimuth in several sweeps of the volume.
+
+use ndarray::Array2;
+
+use crate::model::coordinates::azimuth_distance;
+use crate::{MomentData, Provenance, RadishError, Result, SweepData, VolumeData};
+
+/// Name of the flag moment written by [`flag_rfi`]
+pub const RFI_FLAG: &str = "RFI_FLAG";
+
+/// Options for RFI spike detection
+#[derive(Debug, Clone)]
+pub struct RfiOptions {
+    /// Power moment to inspect (typically uncorrected reflectivity)
+    pub moment: String,
+    /// Gates closer than this range (meters) are ignored, since precipitation dominates there
+    pub min_range: f32,
+    /// Minimum fraction of valid gates beyond `min_range` for a ray to be a candidate
+    pub min_valid_fraction: f32,
+    /// Maximum standard deviation (dB) of range-corrected power along a candidate ray
+    pub max_power_std: f32,
+    /// Minimum number of sweeps in which a spike must appear at the same azimuth
+    pub min_sweeps: usize,
+    /// Azimuth tolerance (degrees) when matching spikes across sweeps
+    pub azimuth_tolerance: f32,
+}
+
+impl Default for RfiOptions {
+    fn default() -> Self {
+        Self {
+            moment: "DBZH".to_string(),
+            min_range: 20_000.0,
+            min_valid_fraction: 0.7,
+            max_power_std: 3.0,
+            min_sweeps: 2,
+            azimuth_tolerance: 1.5,
+        }
+    }
+}
+
+/// Result of RFI detection over a volume
+#[derive(Debug, Clone, Default)]
+pub struct RfiReport {
+    /// Indices of flagged rays for each sweep
+    pub flagged_rays: Vec<Vec<usize>>,
+    /// Azimuths (degrees) of confirmed interference spikes
+    pub spike_azimuths: Vec<f32>,
+}
+
+impl RfiReport {
+    /// Total number of flagged rays in the volume
+    pub fn num_flagged(&self) -> usize {
+        self.flagged_rays.iter().map(|r| r.len()).sum()
+    }
+}
+
+/// Detect interference spikes in a volume
+pub fn detect_rfi(volume: &VolumeData, options: &RfiOptions) -> Result<RfiReport> {
+    let candidates: Vec<Vec<usize>> = volume
+        .sweeps
+        .iter()
+        .map(|sweep| candidate_rays(sweep, options))
+        .collect::<Result<_>>()?;
+
+    // Group candidate azimuths across sweeps and keep those seen often enough
+    let mut candidate_azimuths: Vec<f32> = candidates
+        .iter()
+        .zip(volume.sweeps.iter())
+        .flat_map(|(rays, sweep)| rays.iter().map(move |&i| sweep.coordinates.azimuth[i]))
+        .collect();
+    candidate_azimuths.sort_by(|a, b| a.total_cmp(b));
+
+    let min_sweeps = options.min_sweeps.min(volume.num_sweeps()).max(1);
+    let mut spike_azimuths: Vec<f32> = Vec::new();
+    for &az in &candidate_azimuths {
+        if spike_azimuths
+            .iter()
+            .any(|&s| azimuth_distance(s, az) <= options.azimuth_tolerance)
+        {
+            continue;
+        }
+
+        let sweeps_with_spike = candidates
+            .iter()
+            .zip(volume.sweeps.iter())
+            .filter(|(rays, sweep)| {
+                rays.iter().any(|&i| {
+                    azimuth_distance(sweep.coordinates.azimuth[i], az) <= options.azimuth_tolerance
+                })
+            })
+            .count();
+
+        if sweeps_with_spike >= min_sweeps {
+            spike_azimuths.push(az);
+        }
+    }
+
+    let flagged_rays = candidates
+        .iter()
+        .zip(volume.sweeps.iter())
+        .map(|(rays, sweep)| {
+            rays.iter()
+                .copied()
+                .filter(|&i| {
+                    spike_azimuths.iter().any(|&s| {
+                        azimuth_distance(sweep.coordinates.azimuth[i], s) <= options.azimuth_tolerance
+                    })
+                })
+                .collect()
+        })
+        .collect();
+
+    Ok(RfiReport {
+        flagged_rays,
+        spike_azimuths,
+    })
+}
+
+/// Detect interference and add an `RFI_FLAG` moment (1 on flagged rays, 0 elsewhere)
+pub fn flag_rfi(volume: &VolumeData, options: &RfiOptions) -> Result<(VolumeData, RfiReport)> {
+    let report = detect_rfi(volume, options)?;
+    let mut volume = volume.clone();
+
+    for (sweep, rays) in volume.sweeps.iter_mut().zip(report.flagged_rays.iter()) {
+        let mut flag = Array2::zeros((sweep.num_rays(), sweep.num_gates()));
+        for &ray in rays {
+            flag.row_mut(ray).fill(1.0);
+        }
+
+        let mut moment = MomentData::new(RFI_FLAG.to_string(), String::new(), flag);
+        moment.long_name = Some("Radio frequency interference flag".to_string());
+        moment.provenance = Some(Provenance::new("radish.transforms.rfi.flag_rfi", &[&options.moment], options));
+        sweep.moments.insert(RFI_FLAG.to_string(), moment);
+    }
+
+    volume.history.record("radish.transforms.rfi.flag_rfi", options);
+    Ok((volume, report))
+}
+
+/// Replace flagged rays by interpolating between the nearest unflagged neighbours
+///
+/// Interpolation is linear in azimuth and done independently for each of
+/// `moments` (all moments if empty). Gates where either neighbour is
+/// missing are set to the moment's missing value.
+pub fn interpolate_rfi_rays(volume: &mut VolumeData, report: &RfiReport, moments: &[&str]) {
+    for (sweep, rays) in volume.sweeps.iter_mut().zip(report.flagged_rays.iter()) {
+        if rays.is_empty() {
+            continue;
+        }
+        interpolate_sweep_rays(sweep, rays, moments);
+    }
+}
+
+/// Detect interference and interpolate across the affected rays in one step
+pub fn remove_rfi(volume: &VolumeData, options: &RfiOptions) -> Result<(VolumeData, RfiReport)> {
+    let report = detect_rfi(volume, options)?;
+    let mut volume = volume.clone();
+    interpolate_rfi_rays(&mut volume, &report, &[]);
+    volume.history.record("radish.transforms.rfi.remove_rfi", options);
+    Ok((volume, report))
+}
+
+/// Rays in a sweep that look like interference on their own
+pub(crate) fn candidate_rays(sweep: &SweepData, options: &RfiOptions) -> Result<Vec<usize>> {
+    let moment = sweep
+        .get_moment(&options.moment)
+        .ok_or_else(|| RadishError::MissingVariable(options.moment.clone()))?;
+
+    let far_gates: Vec<usize> = sweep
+        .coordinates
+        .range
+        .iter()
+        .enumerate()
+        .filter(|(_, &r)| r >= options.min_range && r > 0.0)
+        .map(|(j, _)| j)
+        .collect();
+    if far_gates.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut rays = Vec::new();
+    for (i, row) in moment.data().outer_iter().enumerate() {
+        let corrected: Vec<f32> = far_gates
+            .iter()
+            .filter(|&&j| moment.is_valid(i, j))
+            .map(|&j| row[j] - 20.0 * (sweep.coordinates.range[j] / 1000.0).log10())
+            .collect();
+
+        let fraction = corrected.len() as f32 / far_gates.len() as f32;
+        if fraction < options.min_valid_fraction || corrected.len() < 2 {
+            continue;
+        }
+
+        let mean = corrected.iter().sum::<f32>() / corrected.len() as f32;
+        let var = corrected.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / corrected.len() as f32;
+        if var.sqrt() <= options.max_power_std {
+            rays.push(i);
+        }
+    }
+
+    Ok(rays)
+}
+
+fn interpolate_sweep_rays(sweep: &mut SweepData, rays: &[usize], moments: &[&str]) {
+    let num_rays = sweep.num_rays();
+    let flagged: Vec<bool> = (0..num_rays).map(|i| rays.contains(&i)).collect();
+    if flagged.iter().all(|&f| f) {
+        return;
+    }
+
+    let azimuth = sweep.coordinates.azimuth.clone();
+    for (name, moment) in sweep.moments.iter_mut() {
+        if !moments.is_empty() && !moments.contains(&name.as_str()) {
+            continue;
+        }
+
+        let original = moment.data().into_owned();
+        let missing = moment.missing_value();
+        let valid = moment.validity();
+        let data = moment.data_mut();
+        for &ray in rays {
+            let prev = (1..num_rays)
+                .map(|k| (ray + num_rays - k) % num_rays)
+                .find(|&k| !flagged[k])
+                .unwrap_or(ray);
+            let next = (1..num_rays)
+                .map(|k| (ray + k) % num_rays)
+                .find(|&k| !flagged[k])
+                .unwrap_or(ray);
+
+            let d_prev = azimuth_distance(azimuth[ray], azimuth[prev]);
+            let d_next = azimuth_distance(azimuth[ray], azimuth[next]);
+            let w_next = if d_prev + d_next > 0.0 {
+                d_prev / (d_prev + d_next)
+            } else {
+                0.5
+            };
+
+            for j in 0..original.ncols() {
+                let a = original[[prev, j]];
+                let b = original[[next, j]];
+                data[[ray, j]] = if valid[[prev, j]] && valid[[next, j]] {
+                    a * (1.0 - w_next) + b * w_next
+                } else {
+                    missing
+                };
+            }
+        }
+    }
+}
@@ -0,0 +1,132 @@
+//! Handling of SAILS and MESO-SAILS supplemental low-level sweeps
+//!
+//! With SAILS (Supplemental Adaptive Intra-Volume Low-Level Scan), a NEXRAD
+//! interrupts its climb through the volume coverage pattern to rescan the
+//! lowest elevation, up to three times per volume with MESO-SAILS. A sweep
+//! is taken as a SAILS cut when its fixed angle matches the lowest angle of
+//! the volume and a higher elevation has already been scanned. Depending on
+//! the [`SailsPolicy`], these cuts are kept in time order, dropped, or split
+//! off into supplemental volumes so that each volume climbs monotonically.
+
+use crate::{RadishError, Result, SweepData, VolumeData};
+
+/// Volume attribute numbering the SAILS cut of a supplemental volume (1, 2, ...)
+pub const SAILS_CUT_ATTRIBUTE: &str = "sails_cut";
+
+/// What to do with SAILS cuts
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SailsPolicy {
+    /// Keep every sweep, ordered by start time
+    #[default]
+    Keep,
+    /// Remove the SAILS cuts
+    Drop,
+    /// Move each run of consecutive SAILS cuts into a supplemental volume
+    Split,
+}
+
+/// Options for SAILS handling
+#[derive(Debug, Clone)]
+pub struct SailsOptions {
+    /// Handling of SAILS cuts
+    pub policy: SailsPolicy,
+    /// Fixed angles within this much (degrees) of the lowest angle are low-level sweeps
+    pub angle_tolerance: f64,
+}
+
+impl Default for SailsOptions {
+    fn default() -> Self {
+        Self {
+            policy: SailsPolicy::default(),
+            angle_tolerance: 0.2,
+        }
+    }
+}
+
+/// Whether each sweep of a volume is a SAILS cut
+pub fn sails_sweeps(volume: &VolumeData, angle_tolerance: f64) -> Vec<bool> {
+    let lowest = volume
+        .sweeps
+        .iter()
+        .map(|s| s.metadata.fixed_angle)
+        .fold(f64::INFINITY, f64::min);
+    let mut climbed = false;
+    volume
+        .sweeps
+        .iter()
+        .map(|sweep| {
+            let low = (sweep.metadata.fixed_angle - lowest).abs() <= angle_tolerance;
+            climbed |= !low;
+            low && climbed
+        })
+        .collect()
+}
+
+/// Restructure a volume according to the SAILS policy
+///
+/// The first volume returned is the main volume; with
+/// [`SailsPolicy::Split`] it is followed by one supplemental volume per run
+/// of SAILS cuts, in scan order, each tagged with [`SAILS_CUT_ATTRIBUTE`].
+/// Sweep metadata and time coverage of every returned volume are rebuilt.
+pub fn restructure_sails(volume: &VolumeData, options: &SailsOptions) -> Result<Vec<VolumeData>> {
+    if volume.sweeps.is_empty() {
+        return Err(RadishError::General("Volume has no sweeps".to_string()));
+    }
+    let sails = sails_sweeps(volume, options.angle_tolerance);
+    let make_volume = |sweeps: Vec<SweepData>| {
+        let mut out = VolumeData::new(volume.metadata.clone(), sweeps);
+        out.calibration = volume.calibration.clone();
+        out.history = volume.history.clone();
+        out.history.record("radish.transforms.sails.restructure_sails", options);
+        out.reindex_sweeps();
+        if let Some(start) = out.sweeps.iter().filter_map(SweepData::time_start).min() {
+            out.metadata.time_coverage_start = start;
+        }
+        if let Some(end) = out.sweeps.iter().filter_map(SweepData::time_end).max() {
+            out.metadata.time_coverage_end = end;
+        }
+        out
+    };
+
+    match options.policy {
+        SailsPolicy::Keep => {
+            let mut sweeps = volume.sweeps.clone();
+            // Stable, so sweeps with the same start time keep their scan order
+            sweeps.sort_by_key(|s| s.time_start());
+            Ok(vec![make_volume(sweeps)])
+        }
+        SailsPolicy::Drop => {
+            let sweeps = volume
+                .sweeps
+                .iter()
+                .zip(&sails)
+                .filter(|(_, &s)| !s)
+                .map(|(sweep, _)| sweep.clone())
+                .collect();
+            Ok(vec![make_volume(sweeps)])
+        }
+        SailsPolicy::Split => {
+            let mut main = Vec::new();
+            let mut runs: Vec<Vec<SweepData>> = Vec::new();
+            for (i, sweep) in volume.sweeps.iter().enumerate() {
+                if !sails[i] {
+                    main.push(sweep.clone());
+                } else if i > 0 && sails[i - 1] {
+                    runs.last_mut().expect("run started at previous sweep").push(sweep.clone());
+                } else {
+                    runs.push(vec![sweep.clone()]);
+                }
+            }
+            let mut volumes = vec![make_volume(main)];
+            for (n, run) in runs.into_iter().enumerate() {
+                let mut supplemental = make_volume(run);
+                supplemental
+                    .metadata
+                    .attributes
+                    .insert(SAILS_CUT_ATTRIBUTE.to_string(), (n as i64 + 1).into());
+                volumes.push(supplemental);
+            }
+            Ok(volumes)
+        }
+    }
+}
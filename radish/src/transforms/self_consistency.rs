@@ -0,0 +1,180 @@
+//! Reflectivity calibration from Z–ZDR–KDP self-consistency
+//!
+//! In rain, the specific differential phase follows from reflectivity and
+//! differential reflectivity, `KDP = c · Zh^a · Zdr^b` (linear units), so
+//! the differential phase shift along a rain path can be predicted as
+//! `ΔΦ = 2·∫KDP dr` and compared with the measured PHIDP. A reflectivity
+//! biased by `δ` dB scales the prediction by `10^(a·δ/10)`, so over all
+//! rain segments of a volume
+//!
+//! `δ = 10/a · log10(ΣΔΦ_predicted / ΣΔΦ_measured)`.
+//!
+//! Segments are runs of consecutive rain gates (moderate reflectivity,
+//! high RHOHV) with a significant measured phase shift. The estimate is a
+//! monitoring quantity: tracked over many volumes it reveals calibration
+//! drift, and the relation coefficients should be tuned to the band and
+//! climate of the radar.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{RadishError, Result, SweepData, VolumeData};
+
+/// Coefficients of the `KDP = c · Zh^a · Zdr^b` relation (°/km, mm⁶/m³, linear ZDR)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KdpRelation {
+    /// Multiplicative coefficient `c`
+    pub coefficient: f64,
+    /// Reflectivity exponent `a`
+    pub z_exponent: f64,
+    /// Differential reflectivity exponent `b`
+    pub zdr_exponent: f64,
+}
+
+impl KdpRelation {
+    /// S band rain
+    pub const S_BAND: Self = Self { coefficient: 3.7e-5, z_exponent: 1.0, zdr_exponent: -0.5 };
+
+    /// KDP (°/km) of rain with the given reflectivity (dBZ) and ZDR (dB)
+    pub fn kdp(&self, reflectivity: f64, zdr: f64) -> f64 {
+        self.coefficient * 10f64.powf(0.1 * self.z_exponent * reflectivity) * 10f64.powf(0.1 * self.zdr_exponent * zdr)
+    }
+}
+
+/// Options for self-consistency calibration
+#[derive(Debug, Clone)]
+pub struct SelfConsistencyOptions {
+    /// Reflectivity moment (dBZ)
+    pub reflectivity: String,
+    /// Differential reflectivity moment (dB)
+    pub zdr: String,
+    /// Processed differential phase moment (degrees), e.g. from `process_phidp`
+    pub phidp: String,
+    /// Correlation coefficient moment used to select rain gates (not used if missing)
+    pub rhohv: String,
+    /// KDP relation of the radar band
+    pub relation: KdpRelation,
+    /// Reflectivity range (dBZ) of rain gates; the upper limit excludes hail
+    pub reflectivity_range: (f32, f32),
+    /// Minimum RHOHV of rain gates
+    pub min_rhohv: f32,
+    /// Fewest consecutive rain gates in a segment
+    pub min_gates: usize,
+    /// Minimum measured phase shift (degrees) of a segment
+    pub min_delta_phi: f32,
+    /// Fewest segments for a bias estimate
+    pub min_segments: usize,
+}
+
+impl Default for SelfConsistencyOptions {
+    fn default() -> Self {
+        Self {
+            reflectivity: "DBZH".to_string(),
+            zdr: "ZDR".to_string(),
+            phidp: "PHIDP_CORR".to_string(),
+            rhohv: "RHOHV".to_string(),
+            relation: KdpRelation::S_BAND,
+            reflectivity_range: (20.0, 50.0),
+            min_rhohv: 0.98,
+            min_gates: 10,
+            min_delta_phi: 4.0,
+            min_segments: 10,
+        }
+    }
+}
+
+/// Self-consistency result for one volume
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SelfConsistencyReport {
+    /// Start time of the volume
+    pub time: DateTime<Utc>,
+    /// Reflectivity bias (dB, positive if the radar reads too high), if enough segments were found
+    pub bias: Option<f64>,
+    /// Number of rain segments used
+    pub segments: usize,
+    /// Total measured phase shift (degrees) over the segments
+    pub measured_phase: f64,
+    /// Total phase shift (degrees) predicted from Z and ZDR over the segments
+    pub predicted_phase: f64,
+}
+
+/// Estimate the reflectivity calibration bias of a volume
+///
+/// Sweeps without reflectivity, ZDR or PHIDP are skipped; the volume must
+/// contain at least one sweep with all three.
+pub fn self_consistency_bias(volume: &VolumeData, options: &SelfConsistencyOptions) -> Result<SelfConsistencyReport> {
+    let mut report = SelfConsistencyReport {
+        time: volume.metadata.time_coverage_start,
+        bias: None,
+        segments: 0,
+        measured_phase: 0.0,
+        predicted_phase: 0.0,
+    };
+    let mut found = false;
+    for sweep in &volume.sweeps {
+        if let Some((segments, measured, predicted)) = sweep_segments(sweep, options) {
+            found = true;
+            report.segments += segments;
+            report.measured_phase += measured;
+            report.predicted_phase += predicted;
+        }
+    }
+    if !found {
+        return Err(RadishError::MissingVariable(format!(
+            "{}, {} and {}",
+            options.reflectivity, options.zdr, options.phidp
+        )));
+    }
+    if report.segments >= options.min_segments.max(1) && report.measured_phase > 0.0 {
+        report.bias = Some(
+            10.0 / options.relation.z_exponent * (report.predicted_phase / report.measured_phase).log10(),
+        );
+    }
+    Ok(report)
+}
+
+/// Number of rain segments of a sweep and their measured and predicted phase shifts
+fn sweep_segments(sweep: &SweepData, options: &SelfConsistencyOptions) -> Option<(usize, f64, f64)> {
+    let refl = sweep.get_moment(&options.reflectivity)?;
+    let zdr = sweep.get_moment(&options.zdr)?;
+    let phidp = sweep.get_moment(&options.phidp)?;
+    let rhohv = sweep.get_moment(&options.rhohv);
+    let range = &sweep.coordinates.range;
+    let (min_z, max_z) = options.reflectivity_range;
+
+    let (num_rays, num_gates) = refl.shape();
+    let (mut segments, mut measured, mut predicted) = (0usize, 0.0, 0.0);
+    for i in 0..num_rays {
+        let rain = |j: usize| {
+            refl.valid_value(i, j).is_some_and(|z| (min_z..=max_z).contains(&z))
+                && zdr.is_valid(i, j)
+                && phidp.is_valid(i, j)
+                && rhohv.is_none_or(|m| m.valid_value(i, j).is_some_and(|r| r >= options.min_rhohv))
+        };
+        let mut j = 0;
+        while j < num_gates {
+            if !rain(j) {
+                j += 1;
+                continue;
+            }
+            let start = j;
+            while j < num_gates && rain(j) {
+                j += 1;
+            }
+            let end = j - 1;
+            let delta_phi = (phidp.value(i, end) - phidp.value(i, start)) as f64;
+            if end + 1 - start < options.min_gates.max(2) || delta_phi < options.min_delta_phi as f64 {
+                continue;
+            }
+            // Two-way phase from the gate centers of the segment, trapezoid rule in km
+            let kdp = |k: usize| options.relation.kdp(refl.value(i, k) as f64, zdr.value(i, k) as f64);
+            let phase: f64 = (start..end)
+                .map(|k| (kdp(k) + kdp(k + 1)) * (range[k + 1] - range[k]) as f64 / 1000.0)
+                .sum();
+            segments += 1;
+            measured += delta_phi;
+            predicted += phase;
+        }
+    }
+    Some((segments, measured, predicted))
+}
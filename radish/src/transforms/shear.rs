@@ -0,0 +1,219 @@
+//! Low-level radial divergence and azimuthal shear
+//!
+//! Gust fronts, microburst outflows and wind shift lines show up in the
+//! lowest velocity sweep as bands of radial convergence or azimuthal shear.
+//! Following the linear least squares derivatives (LLSD) approach of Smith
+//! & Elmore (2004), the radial divergence `∂Vr/∂r` is the slope of a line
+//! fitted to the velocities along a short stretch of the ray, and the
+//! azimuthal shear `(1/r)·∂Vr/∂φ` the slope across neighbouring rays at the
+//! same range. Gates with strong convergence or shear are shear-line
+//! candidates, which are also gathered onto a Cartesian grid centered on
+//! the radar for display alongside aviation products.
+
+use std::collections::HashMap;
+
+use ndarray::{Array2, Array3};
+use radish_types::SweepMode;
+
+use crate::transforms::georeference::{antenna_to_cartesian, GeoreferenceOptions, Projection};
+use crate::transforms::gridding::{grid_axis, RADAR_LATITUDE, RADAR_LONGITUDE};
+use crate::{GridField, GriddedData, MomentData, Provenance, RadishError, Result, SweepData, VolumeData};
+
+/// Name of the radial divergence moment (1/s)
+pub const RADIAL_DIVERGENCE: &str = "RDIV";
+
+/// Name of the azimuthal shear moment (1/s)
+pub const AZIMUTHAL_SHEAR: &str = "AZSHEAR";
+
+/// Name of the shear-line candidate moment and grid field (1 for candidates, 0 otherwise)
+pub const SHEAR_LINE: &str = "SHEAR_LINE";
+
+/// Options for shear products
+#[derive(Debug, Clone)]
+pub struct ShearOptions {
+    /// Radial velocity moment (m/s), dealiased
+    pub moment: String,
+    /// Half-width (gates) of the radial derivative window
+    pub gate_window: usize,
+    /// Half-width (rays) of the azimuthal derivative window
+    pub ray_window: usize,
+    /// Fewest valid gates in a window for a derivative
+    pub min_points: usize,
+    /// Gates with a radial divergence at or below minus this value (1/s) are candidates
+    pub convergence_threshold: f32,
+    /// Gates with an absolute azimuthal shear at or above this value (1/s) are candidates
+    pub shear_threshold: f32,
+    /// Grid x and y limits (meters from the radar) of the first and last column and row
+    pub grid_limits: (f64, f64),
+    /// Number of grid columns and rows
+    pub grid_size: usize,
+    /// Beam propagation model
+    pub georeference: GeoreferenceOptions,
+}
+
+impl Default for ShearOptions {
+    fn default() -> Self {
+        Self {
+            moment: "VRADH".to_string(),
+            gate_window: 3,
+            ray_window: 1,
+            min_points: 3,
+            convergence_threshold: 2e-3,
+            shear_threshold: 4e-3,
+            grid_limits: (-60_000.0, 60_000.0),
+            grid_size: 121,
+            georeference: GeoreferenceOptions::default(),
+        }
+    }
+}
+
+/// Radial divergence, azimuthal shear and shear-line candidates of the lowest velocity sweep
+///
+/// The returned sweep has the metadata and coordinates of the lowest PPI
+/// sweep holding `options.moment`, with the `RDIV`, `AZSHEAR` and
+/// `SHEAR_LINE` moments.
+pub fn shear_products(volume: &VolumeData, options: &ShearOptions) -> Result<SweepData> {
+    let sweep = volume
+        .sweeps
+        .iter()
+        .filter(|s| {
+            matches!(s.metadata.sweep_mode, SweepMode::Azimuth | SweepMode::Sector | SweepMode::ManualPpi)
+                && s.get_moment(&options.moment).is_some()
+        })
+        .min_by(|a, b| a.metadata.fixed_angle.total_cmp(&b.metadata.fixed_angle))
+        .ok_or_else(|| RadishError::MissingVariable(options.moment.clone()))?;
+    sweep.coordinates.validate()?;
+    let velocity = &sweep.moments[&options.moment];
+    let coords = &sweep.coordinates;
+    let (num_rays, num_gates) = velocity.shape();
+    let full_circle = sweep.metadata.sweep_mode != SweepMode::Sector;
+    let value = |i: usize, j: usize| velocity.valid_value(i, j).map(|v| v as f64);
+
+    let mut divergence = Array2::from_elem((num_rays, num_gates), f32::NAN);
+    let mut shear = Array2::from_elem((num_rays, num_gates), f32::NAN);
+    for i in 0..num_rays {
+        for j in 0..num_gates {
+            if value(i, j).is_none() {
+                continue;
+            }
+            let along: Vec<(f64, f64)> = (j.saturating_sub(options.gate_window)..=(j + options.gate_window).min(num_gates - 1))
+                .filter_map(|k| value(i, k).map(|v| (coords.range[k] as f64, v)))
+                .collect();
+            divergence[[i, j]] = slope(&along, options.min_points) as f32;
+
+            // Arc length (meters) of each neighbouring ray from this one, at this range
+            let r = coords.range[j] as f64;
+            let across: Vec<(f64, f64)> = (-(options.ray_window as isize)..=options.ray_window as isize)
+                .filter_map(|d| {
+                    let mut k = i as isize + d;
+                    if full_circle {
+                        k = k.rem_euclid(num_rays as isize);
+                    } else if !(0..num_rays as isize).contains(&k) {
+                        return None;
+                    }
+                    let k = k as usize;
+                    let offset = (coords.azimuth[k] - coords.azimuth[i] + 540.0).rem_euclid(360.0) - 180.0;
+                    value(k, j).map(|v| (r * (offset as f64).to_radians(), v))
+                })
+                .collect();
+            shear[[i, j]] = slope(&across, options.min_points) as f32;
+        }
+    }
+    let candidates = Array2::from_shape_fn((num_rays, num_gates), |idx| {
+        let (d, s) = (divergence[idx], shear[idx]);
+        if d.is_nan() && s.is_nan() {
+            f32::NAN
+        } else if d <= -options.convergence_threshold || s.abs() >= options.shear_threshold {
+            1.0
+        } else {
+            0.0
+        }
+    });
+
+    let mut moments = HashMap::new();
+    for (name, units, long_name, data) in [
+        (RADIAL_DIVERGENCE, "s-1", "Radial divergence", divergence),
+        (AZIMUTHAL_SHEAR, "s-1", "Azimuthal shear", shear),
+        (SHEAR_LINE, "1", "Gust front and shear line candidates", candidates),
+    ] {
+        let mut moment = MomentData::new(name.to_string(), units.to_string(), data);
+        moment.long_name = Some(long_name.to_string());
+        moment.provenance = Some(Provenance::new(
+            "radish.transforms.shear.shear_products",
+            &[&options.moment],
+            options,
+        ));
+        moments.insert(name.to_string(), moment);
+    }
+    Ok(SweepData::new(sweep.metadata.clone(), moments, coords.clone()))
+}
+
+/// Shear-line candidates of the lowest velocity sweep on a Cartesian grid
+///
+/// The single-level grid uses an azimuthal equidistant projection centered
+/// on the radar. A cell is 1 if any gate falling in it is a candidate, 0 if
+/// it has gates but no candidate, and NaN without gates.
+pub fn shear_line_grid(volume: &VolumeData, options: &ShearOptions) -> Result<GriddedData> {
+    if options.grid_size == 0 {
+        return Err(RadishError::General("Shear grid must have at least one cell".to_string()));
+    }
+    let sweep = shear_products(volume, options)?;
+    let candidates = &sweep.moments[SHEAR_LINE];
+    let axis = grid_axis(options.grid_limits, options.grid_size);
+    let n = options.grid_size;
+    let spacing = if n > 1 { axis[1] - axis[0] } else { f64::INFINITY };
+
+    let mut flags = Array3::from_elem((1, n, n), f32::NAN);
+    let coords = &sweep.coordinates;
+    for i in 0..coords.num_rays() {
+        for j in 0..coords.num_gates() {
+            let flag = candidates.value(i, j);
+            if flag.is_nan() {
+                continue;
+            }
+            let (x, y, _) = antenna_to_cartesian(
+                coords.range[j] as f64,
+                coords.azimuth[i] as f64,
+                coords.elevation[i] as f64,
+                &options.georeference,
+            );
+            let (col, row) = (((x - axis[0]) / spacing).round(), ((y - axis[0]) / spacing).round());
+            if !(0.0..n as f64).contains(&col) || !(0.0..n as f64).contains(&row) {
+                continue;
+            }
+            let cell = &mut flags[[0, row as usize, col as usize]];
+            *cell = if cell.is_nan() { flag } else { cell.max(flag) };
+        }
+    }
+
+    let meta = &volume.metadata;
+    let projection = Projection::aeqd(meta.longitude, meta.latitude);
+    let mut grid = GriddedData::new(meta.time_coverage_start, projection, axis.clone(), axis, vec![meta.altitude]);
+    grid.attributes
+        .insert(RADAR_LONGITUDE.to_string(), meta.longitude.to_string());
+    grid.attributes
+        .insert(RADAR_LATITUDE.to_string(), meta.latitude.to_string());
+    let mut field = GridField::new(SHEAR_LINE.to_string(), "1".to_string(), flags);
+    field.long_name = Some("Gust front and shear line candidates".to_string());
+    grid.add_field(field)?;
+    Ok(grid)
+}
+
+/// Least squares slope of `(x, y)` points, NaN with fewer than `min_points` points
+fn slope(points: &[(f64, f64)], min_points: usize) -> f64 {
+    if points.len() < min_points.max(2) {
+        return f64::NAN;
+    }
+    let n = points.len() as f64;
+    let (mx, my) = points
+        .iter()
+        .fold((0.0, 0.0), |(sx, sy), (x, y)| (sx + x / n, sy + y / n));
+    let (sxy, sxx) = points
+        .iter()
+        .fold((0.0, 0.0), |(sxy, sxx), (x, y)| (sxy + (x - mx) * (y - my), sxx + (x - mx).powi(2)));
+    if sxx > 0.0 {
+        sxy / sxx
+    } else {
+        f64::NAN
+    }
+}
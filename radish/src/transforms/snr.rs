@@ -0,0 +1,134 @@
+//! Signal-to-noise ratio computation and thresholding
+//!
+//! The reflectivity equivalent of the receiver noise grows with range as
+//! `Z_noise(r) = Z_noise(1 km) + 20·log10(r / 1 km)`, so that
+//!
+//! `SNR = DBZH - Z_noise(1 km) - 20·log10(r / 1 km)` (dB)
+//!
+//! where `Z_noise(1 km)` is the `base_dbz_1km_h` of the radar calibration
+//! (the reflectivity at 1 km giving an SNR of 0 dB) unless given in the
+//! options. Without it, the noise power of the calibration is converted
+//! with the radar constant of the instrument parameters,
+//! `Z_noise(1 km) = noise_power_h + radar_constant_h`. Gates with SNR below
+//! a threshold are excluded through a [`GateFilter`] and censored in the
+//! other moments.
+
+use ndarray::Array2;
+
+use crate::transforms::gatefilter::GateFilter;
+use crate::{MomentData, Provenance, RadishError, Result, VolumeData};
+
+/// Name of the SNR moment
+pub const SNR: &str = "SNRH";
+
+/// Options for SNR computation and thresholding
+#[derive(Debug, Clone)]
+pub struct SnrOptions {
+    /// Reflectivity moment (dBZ)
+    pub reflectivity: String,
+    /// SNR moment, read if present and written otherwise
+    pub snr_moment: String,
+    /// Noise-equivalent reflectivity at 1 km (dBZ); from the calibration and instrument parameters if `None`
+    pub noise_dbz_1km: Option<f64>,
+    /// Gates with SNR below this value (dB) are censored
+    pub threshold: f32,
+    /// Moments censored (all except the SNR moment if empty)
+    pub moments: Vec<String>,
+}
+
+impl Default for SnrOptions {
+    fn default() -> Self {
+        Self {
+            reflectivity: "DBZH".to_string(),
+            snr_moment: SNR.to_string(),
+            noise_dbz_1km: None,
+            threshold: 3.0,
+            moments: Vec::new(),
+        }
+    }
+}
+
+/// Add the SNR moment to the sweeps that have reflectivity and no SNR moment
+pub fn compute_snr(volume: &VolumeData, options: &SnrOptions) -> Result<VolumeData> {
+    let mut volume = volume.clone();
+    let pending = volume
+        .sweeps
+        .iter()
+        .any(|s| s.get_moment(&options.snr_moment).is_none() && s.get_moment(&options.reflectivity).is_some());
+    if !pending {
+        return Ok(volume);
+    }
+    let calibration = volume.calibration.as_ref();
+    let radar_constant = volume.metadata.instrument_parameters.as_ref().and_then(|p| p.radar_constant_h);
+    let noise = options
+        .noise_dbz_1km
+        .or_else(|| calibration.and_then(|c| c.base_dbz_1km_h))
+        .or_else(|| Some(calibration?.noise_power_h? + radar_constant?))
+        .ok_or_else(|| RadishError::MissingAttribute("base_dbz_1km_h".to_string()))?;
+
+    for sweep in &mut volume.sweeps {
+        if sweep.get_moment(&options.snr_moment).is_some() {
+            continue;
+        }
+        let Some(dbz) = sweep.get_moment(&options.reflectivity) else {
+            continue;
+        };
+        let range = &sweep.coordinates.range;
+        let snr = Array2::from_shape_fn(dbz.shape(), |(i, j)| {
+            match dbz.valid_value(i, j) {
+                Some(v) if range[j] > 0.0 => (v as f64 - noise - 20.0 * (range[j] as f64 / 1000.0).log10()) as f32,
+                _ => f32::NAN,
+            }
+        });
+
+        let mut moment = MomentData::new(options.snr_moment.clone(), "dB".to_string(), snr);
+        moment.long_name = Some("Signal-to-noise ratio (horizontal channel)".to_string());
+        moment.provenance = Some(Provenance::new(
+            "radish.transforms.snr.compute_snr",
+            &[&options.reflectivity],
+            &(noise, options),
+        ));
+        sweep.moments.insert(options.snr_moment.clone(), moment);
+    }
+    volume.history.record("radish.transforms.snr.compute_snr", options);
+    Ok(volume)
+}
+
+/// Gate filter excluding gates with SNR below the threshold or without SNR
+///
+/// The SNR is read from `options.snr_moment` where present and computed
+/// otherwise.
+pub fn snr_gate_filter(volume: &VolumeData, options: &SnrOptions) -> Result<GateFilter> {
+    let with_snr = compute_snr(volume, options)?;
+    let mut filter = GateFilter::new(&with_snr);
+    filter.exclude_where(&with_snr, &options.snr_moment, |v| v.is_nan() || v < options.threshold)?;
+    Ok(filter)
+}
+
+/// Censor gates with SNR below the threshold
+///
+/// Returns the volume with the SNR moment added (if missing) and the
+/// censored moments set to their missing value at excluded gates.
+pub fn threshold_snr(volume: &VolumeData, options: &SnrOptions) -> Result<VolumeData> {
+    let with_snr = compute_snr(volume, options)?;
+    let filter = snr_gate_filter(&with_snr, options)?;
+    let moments: Vec<String> = if options.moments.is_empty() {
+        let mut names: Vec<String> = with_snr
+            .sweeps
+            .iter()
+            .flat_map(|s| s.moments.keys().cloned())
+            .filter(|name| *name != options.snr_moment)
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    } else {
+        options.moments.clone()
+    };
+    if moments.is_empty() {
+        return Ok(with_snr);
+    }
+    let mut censored = filter.apply(&with_snr, &moments)?;
+    censored.history.record("radish.transforms.snr.threshold_snr", options);
+    Ok(censored)
+}
@@ -0,0 +1,287 @@
+//! Solar interference monitoring and antenna calibration
+//!
+//! When the antenna sweeps across the sun the receiver sees solar noise
+//! along the whole ray, which looks like an interference spike (see
+//! [`crate::transforms::rfi`]) pointing at the sun. Collecting such hits over
+//! one or more volumes and fitting the solar power as a function of the
+//! pointing offset from the computed sun position gives the antenna
+//! pointing bias and the received solar power (Huuskonen and Holleman,
+//! 2007). With `x = Δaz·cos(el_sun)` and `y = Δel` (degrees), the power in
+//! dB follows the paraboloid
+//!
+//! `P(x, y) = a + b·x + c·y + d·x² + e·y²`
+//!
+//! whose vertex is the pointing bias and whose curvature gives the width of
+//! the sun image convolved with the antenna pattern. The hit power is the
+//! range-corrected reflectivity (dBZ at 1 km); it is converted to dBm with
+//! the noise power and noise-equivalent reflectivity of the radar
+//! calibration where available, and compared with its sun power.
+
+use chrono::{DateTime, Utc};
+
+use crate::model::coordinates::azimuth_distance;
+use crate::transforms::rfi::{candidate_rays, RfiOptions};
+use crate::{RadishError, Result, VolumeData};
+
+/// Options for sun monitoring
+#[derive(Debug, Clone)]
+pub struct SunOptions {
+    /// Spike detection; `spikes.moment` is the power moment
+    pub spikes: RfiOptions,
+    /// Spikes farther than this (degrees) from the sun in azimuth or elevation are ignored
+    pub max_offset: f64,
+    /// Correct the sun elevation for atmospheric refraction
+    pub refraction: bool,
+    /// Fewest hits for a fit
+    pub min_hits: usize,
+}
+
+impl Default for SunOptions {
+    fn default() -> Self {
+        Self {
+            spikes: RfiOptions {
+                min_sweeps: 1,
+                ..Default::default()
+            },
+            max_offset: 2.0,
+            refraction: true,
+            min_hits: 10,
+        }
+    }
+}
+
+/// A ray hitting the sun
+#[derive(Debug, Clone, PartialEq)]
+pub struct SunHit {
+    /// Time of the ray
+    pub time: DateTime<Utc>,
+    /// Ray azimuth (degrees)
+    pub azimuth: f64,
+    /// Ray elevation (degrees)
+    pub elevation: f64,
+    /// Sun azimuth (degrees)
+    pub sun_azimuth: f64,
+    /// Apparent sun elevation (degrees)
+    pub sun_elevation: f64,
+    /// Mean range-corrected power of the ray (dBZ at 1 km)
+    pub power: f64,
+    /// Received power (dBm), if the radar calibration allows converting it
+    pub power_dbm: Option<f64>,
+}
+
+impl SunHit {
+    /// Pointing offset from the sun (degrees): azimuth scaled by cos(elevation), elevation
+    pub fn offset(&self) -> (f64, f64) {
+        let mut daz = self.azimuth - self.sun_azimuth;
+        daz -= 360.0 * (daz / 360.0).round();
+        (daz * self.sun_elevation.to_radians().cos(), self.elevation - self.sun_elevation)
+    }
+}
+
+/// Result of fitting the solar hits
+#[derive(Debug, Clone)]
+pub struct SunScanReport {
+    /// Hits used in the fit
+    pub hits: Vec<SunHit>,
+    /// Antenna azimuth bias (degrees, antenna minus sun, along the azimuth axis)
+    pub azimuth_bias: f64,
+    /// Antenna elevation bias (degrees, antenna minus sun)
+    pub elevation_bias: f64,
+    /// Full width at half power of the fitted pattern in azimuth (degrees)
+    pub azimuth_width: f64,
+    /// Full width at half power of the fitted pattern in elevation (degrees)
+    pub elevation_width: f64,
+    /// Peak solar power (dBZ at 1 km)
+    pub peak_power: f64,
+    /// Peak solar power (dBm), if the hits could be converted
+    pub peak_power_dbm: Option<f64>,
+    /// Peak power minus the sun power of the calibration (dB)
+    pub power_bias: Option<f64>,
+    /// Root-mean-square residual of the fit (dB)
+    pub residual: f64,
+}
+
+/// Sun azimuth and elevation (degrees) seen from a location
+///
+/// Low-precision solar ephemeris (about 0.01° over 1950-2050) without
+/// refraction.
+pub fn solar_position(time: DateTime<Utc>, latitude: f64, longitude: f64) -> (f64, f64) {
+    let n = time.timestamp_micros() as f64 / 86_400e6 + 2_440_587.5 - 2_451_545.0;
+    let mean_longitude = 280.460 + 0.985_647_4 * n;
+    let anomaly = (357.528 + 0.985_600_3 * n).to_radians();
+    let ecliptic_longitude =
+        (mean_longitude + 1.915 * anomaly.sin() + 0.020 * (2.0 * anomaly).sin()).to_radians();
+    let obliquity = (23.439 - 0.000_000_4 * n).to_radians();
+
+    let right_ascension = (obliquity.cos() * ecliptic_longitude.sin()).atan2(ecliptic_longitude.cos());
+    let declination = (obliquity.sin() * ecliptic_longitude.sin()).asin();
+    let sidereal = (280.460_618_37 + 360.985_647_366_29 * n).to_radians();
+    let hour_angle = sidereal + longitude.to_radians() - right_ascension;
+
+    let lat = latitude.to_radians();
+    let elevation = (lat.sin() * declination.sin() + lat.cos() * declination.cos() * hour_angle.cos()).asin();
+    let azimuth = (-hour_angle.sin() * declination.cos())
+        .atan2(declination.sin() * lat.cos() - declination.cos() * lat.sin() * hour_angle.cos());
+    (azimuth.to_degrees().rem_euclid(360.0), elevation.to_degrees())
+}
+
+/// Atmospheric refraction (degrees) at a true elevation (degrees), after Sæmundsson (1986)
+pub fn refraction(elevation: f64) -> f64 {
+    if elevation < -1.0 {
+        return 0.0;
+    }
+    1.02 / (elevation + 10.3 / (elevation + 5.11)).to_radians().tan() / 60.0
+}
+
+/// Rays of a volume hitting the sun
+pub fn detect_sun_hits(volume: &VolumeData, options: &SunOptions) -> Result<Vec<SunHit>> {
+    let meta = &volume.metadata;
+    let to_dbm = volume
+        .calibration
+        .as_ref()
+        .and_then(|c| Some(c.noise_power_h? - c.base_dbz_1km_h?));
+
+    let mut hits = Vec::new();
+    for sweep in &volume.sweeps {
+        let Some(moment) = sweep.get_moment(&options.spikes.moment) else {
+            continue;
+        };
+        let coords = &sweep.coordinates;
+        for i in candidate_rays(sweep, &options.spikes)? {
+            let time = coords
+                .ray_datetime(i)
+                .filter(|t| t.timestamp() > 0)
+                .unwrap_or(meta.time_coverage_start);
+            let (sun_azimuth, mut sun_elevation) = solar_position(time, meta.latitude, meta.longitude);
+            if options.refraction {
+                sun_elevation += refraction(sun_elevation);
+            }
+            let (azimuth, elevation) = (coords.azimuth[i] as f64, coords.elevation[i] as f64);
+            if azimuth_distance(azimuth as f32, sun_azimuth as f32) as f64 * sun_elevation.to_radians().cos()
+                > options.max_offset
+                || (elevation - sun_elevation).abs() > options.max_offset
+            {
+                continue;
+            }
+
+            let powers: Vec<f64> = coords
+                .range
+                .iter()
+                .enumerate()
+                .filter(|&(_, &r)| r >= options.spikes.min_range && r > 0.0)
+                .filter_map(|(j, &r)| moment.valid_value(i, j).map(|v| v as f64 - 20.0 * (r as f64 / 1000.0).log10()))
+                .collect();
+            let power = powers.iter().sum::<f64>() / powers.len() as f64;
+            hits.push(SunHit {
+                time,
+                azimuth,
+                elevation,
+                sun_azimuth,
+                sun_elevation,
+                power,
+                power_dbm: to_dbm.map(|offset| power + offset),
+            });
+        }
+    }
+    Ok(hits)
+}
+
+/// Fit the solar power model to hits
+pub fn fit_sun(hits: Vec<SunHit>, options: &SunOptions) -> Result<SunScanReport> {
+    if hits.len() < options.min_hits.max(5) {
+        return Err(RadishError::General(format!(
+            "Too few sun hits for a fit: {} (need {})",
+            hits.len(),
+            options.min_hits.max(5)
+        )));
+    }
+
+    // Normal equations of P = a + b·x + c·y + d·x² + e·y²
+    let mut ata = [[0.0f64; 5]; 5];
+    let mut atb = [0.0f64; 5];
+    for hit in &hits {
+        let (x, y) = hit.offset();
+        let row = [1.0, x, y, x * x, y * y];
+        for k in 0..5 {
+            for l in 0..5 {
+                ata[k][l] += row[k] * row[l];
+            }
+            atb[k] += row[k] * hit.power;
+        }
+    }
+    let [a, b, c, d, e] = solve(ata, atb)
+        .ok_or_else(|| RadishError::General("Sun hits do not constrain the fit".to_string()))?;
+    if d >= 0.0 || e >= 0.0 {
+        return Err(RadishError::General("Sun hits do not show a power peak".to_string()));
+    }
+
+    let residual = (hits
+        .iter()
+        .map(|hit| {
+            let (x, y) = hit.offset();
+            (hit.power - (a + b * x + c * y + d * x * x + e * y * y)).powi(2)
+        })
+        .sum::<f64>()
+        / hits.len() as f64)
+        .sqrt();
+
+    // A Gaussian beam drops by 10·log10(2) dB at half width: 40·log10(2) / width² = -curvature
+    let half_power = 40.0 * std::f64::consts::LOG10_2;
+    let peak_power = a - b * b / (4.0 * d) - c * c / (4.0 * e);
+    let offsets: Vec<f64> = hits.iter().filter_map(|h| Some(h.power_dbm? - h.power)).collect();
+    let peak_power_dbm = (!offsets.is_empty()).then(|| peak_power + offsets.iter().sum::<f64>() / offsets.len() as f64);
+    Ok(SunScanReport {
+        hits,
+        azimuth_bias: -b / (2.0 * d),
+        elevation_bias: -c / (2.0 * e),
+        azimuth_width: (-half_power / d).sqrt(),
+        elevation_width: (-half_power / e).sqrt(),
+        peak_power,
+        peak_power_dbm,
+        power_bias: None,
+        residual,
+    })
+}
+
+/// Detect and fit the sun hits of one or more volumes
+///
+/// The power bias is relative to the sun power of the calibration of the
+/// last volume that has one.
+pub fn sun_scan<'a>(volumes: impl IntoIterator<Item = &'a VolumeData>, options: &SunOptions) -> Result<SunScanReport> {
+    let mut hits = Vec::new();
+    let mut reference = None;
+    for volume in volumes {
+        hits.extend(detect_sun_hits(volume, options)?);
+        if let Some(power) = volume.calibration.as_ref().and_then(|c| c.sun_power_h) {
+            reference = Some(power);
+        }
+    }
+    let mut report = fit_sun(hits, options)?;
+    report.power_bias = report.peak_power_dbm.zip(reference).map(|(p, r)| p - r);
+    Ok(report)
+}
+
+/// Solve a small linear system by Gaussian elimination with partial pivoting
+pub(crate) fn solve<const N: usize>(mut a: [[f64; N]; N], mut b: [f64; N]) -> Option<[f64; N]> {
+    for k in 0..N {
+        let pivot = (k..N).max_by(|&i, &j| a[i][k].abs().total_cmp(&a[j][k].abs()))?;
+        if a[pivot][k].abs() < 1e-12 {
+            return None;
+        }
+        a.swap(k, pivot);
+        b.swap(k, pivot);
+        let pivot_row = a[k];
+        for i in k + 1..N {
+            let f = a[i][k] / pivot_row[k];
+            for (v, p) in a[i][k..].iter_mut().zip(&pivot_row[k..]) {
+                *v -= f * p;
+            }
+            b[i] -= f * b[k];
+        }
+    }
+    let mut x = [0.0; N];
+    for k in (0..N).rev() {
+        x[k] = (b[k] - (k + 1..N).map(|j| a[k][j] * x[j]).sum::<f64>()) / a[k][k];
+    }
+    Some(x)
+}
@@ -0,0 +1,370 @@
+//! Merging of sweeps that share the same scan geometry
+//!
+//! Split cuts, SAILS and other scan strategies revisit the same elevation
+//! several times in one volume. These helpers combine such duplicate
+//! geometry sweeps into one, gate by gate, according to a [`MergePolicy`].
+//!
+//! NEXRAD split cuts are a special case: the low elevations are scanned
+//! first at a low PRF for reflectivity (surveillance) and then at a high
+//! PRF for velocity (Doppler), usually with different gate spacings and
+//! maximum ranges. [`merge_split_cuts`] recombines each pair into one sweep
+//! holding the moments of both, resampled onto one range grid.
+
+use std::collections::{BTreeSet, HashMap};
+
+use ndarray::Array2;
+
+use crate::model::coordinates::azimuth_distance;
+use crate::{RadishError, Result, SweepData, VolumeData};
+
+/// How values from overlapping sweeps are combined at each gate
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum MergePolicy {
+    /// Take the most recent valid value
+    #[default]
+    Latest,
+    /// Take the value from the sweep with the highest quality index at that gate
+    HighestQuality {
+        /// Quality index moment (higher is better)
+        quality_moment: String,
+    },
+    /// Average all valid values
+    Average,
+}
+
+/// Which sweep of a group of repeated elevations [`deduplicate_sweeps`] keeps
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KeepSweep {
+    /// The first sweep in volume order
+    First,
+    /// The last sweep in volume order, usually the most recent
+    Last,
+    /// A merge of all sweeps of the group, following the merge policy
+    #[default]
+    Merge,
+}
+
+/// Options for merging duplicate geometry sweeps
+#[derive(Debug, Clone)]
+pub struct SweepMergeOptions {
+    /// Gate combination policy
+    pub policy: MergePolicy,
+    /// Sweeps whose fixed angles differ by at most this much (degrees) are duplicates
+    pub angle_tolerance: f64,
+    /// Maximum azimuth difference (degrees) for rays to be matched
+    pub azimuth_tolerance: f32,
+}
+
+impl Default for SweepMergeOptions {
+    fn default() -> Self {
+        Self {
+            policy: MergePolicy::default(),
+            angle_tolerance: 0.1,
+            azimuth_tolerance: 0.5,
+        }
+    }
+}
+
+/// Range grid of a recombined split cut
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SplitCutGrid {
+    /// Rays and gates of the surveillance sweep (longest range)
+    #[default]
+    Surveillance,
+    /// Rays and gates of the Doppler sweep
+    Doppler,
+}
+
+/// Options for split-cut recombination
+#[derive(Debug, Clone)]
+pub struct SplitCutOptions {
+    /// Moment whose presence marks a Doppler sweep
+    pub velocity_moment: String,
+    /// Grid of the merged sweep
+    pub grid: SplitCutGrid,
+    /// Sweeps whose fixed angles differ by at most this much (degrees) form a split cut
+    pub angle_tolerance: f64,
+    /// Maximum azimuth difference (degrees) for rays to be matched
+    pub azimuth_tolerance: f32,
+}
+
+impl Default for SplitCutOptions {
+    fn default() -> Self {
+        Self {
+            velocity_moment: "VRADH".to_string(),
+            grid: SplitCutGrid::default(),
+            angle_tolerance: 0.1,
+            azimuth_tolerance: 0.5,
+        }
+    }
+}
+
+/// Merge each surveillance sweep with the next Doppler sweep at the same elevation
+///
+/// A surveillance sweep lacks the velocity moment and is followed, possibly
+/// after other sweeps, by a Doppler sweep of the same mode and fixed angle.
+/// The merged sweep takes the position of the surveillance sweep, and the
+/// volume's sweep metadata is rebuilt. Sweeps that are not part of a split
+/// cut are kept as they are.
+pub fn merge_split_cuts(volume: &VolumeData, options: &SplitCutOptions) -> VolumeData {
+    let is_doppler = |s: &SweepData| s.get_moment(&options.velocity_moment).is_some();
+    let mut partner: Vec<Option<usize>> = vec![None; volume.sweeps.len()];
+    let mut used = vec![false; volume.sweeps.len()];
+    for (i, sweep) in volume.sweeps.iter().enumerate() {
+        if is_doppler(sweep) {
+            continue;
+        }
+        let doppler = (i + 1..volume.sweeps.len()).find(|&k| {
+            let other = &volume.sweeps[k];
+            !used[k]
+                && is_doppler(other)
+                && other.metadata.sweep_mode == sweep.metadata.sweep_mode
+                && (other.metadata.fixed_angle - sweep.metadata.fixed_angle).abs() <= options.angle_tolerance
+        });
+        if let Some(k) = doppler {
+            partner[i] = Some(k);
+            used[k] = true;
+        }
+    }
+
+    let mut sweeps = Vec::with_capacity(volume.sweeps.len());
+    for (i, sweep) in volume.sweeps.iter().enumerate() {
+        if used[i] {
+            continue;
+        }
+        match partner[i] {
+            Some(k) => sweeps.push(merge_split_cut(sweep, &volume.sweeps[k], options)),
+            None => sweeps.push(sweep.clone()),
+        }
+    }
+
+    let mut merged = VolumeData::new(volume.metadata.clone(), sweeps);
+    merged.calibration = volume.calibration.clone();
+    merged.history = volume.history.clone();
+    merged.reindex_sweeps();
+    merged.history.record("radish.transforms.sweep_merge.merge_split_cuts", options);
+    merged
+}
+
+/// Merge a surveillance sweep and a Doppler sweep at the same elevation
+///
+/// Moments of the other sweep are matched to the grid sweep by nearest
+/// azimuth and range; a moment present in both comes from the surveillance
+/// sweep. The merged sweep keeps the surveillance metadata except for the
+/// PRF, PRT mode and Nyquist velocity of the Doppler sweep.
+pub fn merge_split_cut(surveillance: &SweepData, doppler: &SweepData, options: &SplitCutOptions) -> SweepData {
+    let (base, other) = match options.grid {
+        SplitCutGrid::Surveillance => (surveillance, doppler),
+        SplitCutGrid::Doppler => (doppler, surveillance),
+    };
+    let rays = match_rays(base, other, options.azimuth_tolerance);
+    let gates = match_gates(base, other);
+
+    let mut moments = base.moments.clone();
+    for (name, moment) in &other.moments {
+        if options.grid == SplitCutGrid::Surveillance && moments.contains_key(name) {
+            continue;
+        }
+        let missing = moment.missing_value();
+        let data = Array2::from_shape_fn((base.num_rays(), base.num_gates()), |(i, j)| match (rays[i], gates[j]) {
+            (Some(si), Some(sj)) => moment.value(si, sj),
+            _ => missing,
+        });
+        moments.insert(name.clone(), moment.with_data(data));
+    }
+
+    let mut metadata = surveillance.metadata.clone();
+    metadata.prf = doppler.metadata.prf;
+    metadata.prt_mode = doppler.metadata.prt_mode;
+    metadata.nyquist_velocity = doppler.metadata.nyquist_velocity;
+    SweepData::new(metadata, moments, base.coordinates.clone())
+}
+
+/// Merge every group of same-mode sweeps with matching fixed angles
+///
+/// The merged sweep takes the position of the first sweep of its group and
+/// the volume's sweep metadata is rebuilt. Same as [`deduplicate_sweeps`]
+/// with [`KeepSweep::Merge`].
+pub fn merge_duplicate_sweeps(volume: &VolumeData, options: &SweepMergeOptions) -> Result<VolumeData> {
+    deduplicate_sweeps(volume, KeepSweep::Merge, options)
+}
+
+/// Keep one sweep of every group of same-mode sweeps with matching fixed angles
+///
+/// Operational scan strategies revisit the lowest tilts within a volume,
+/// while gridding and other products expect each elevation once. The kept
+/// or merged sweep takes the position of the first sweep of its group and
+/// the volume's sweep metadata is rebuilt; only `angle_tolerance` and, for
+/// [`KeepSweep::Merge`], the merge options are used.
+pub fn deduplicate_sweeps(volume: &VolumeData, keep: KeepSweep, options: &SweepMergeOptions) -> Result<VolumeData> {
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    for (i, sweep) in volume.sweeps.iter().enumerate() {
+        let existing = groups.iter_mut().find(|g| {
+            let first = &volume.sweeps[g[0]].metadata;
+            first.sweep_mode == sweep.metadata.sweep_mode
+                && (first.fixed_angle - sweep.metadata.fixed_angle).abs() <= options.angle_tolerance
+        });
+        match existing {
+            Some(group) => group.push(i),
+            None => groups.push(vec![i]),
+        }
+    }
+
+    let mut sweeps = Vec::with_capacity(groups.len());
+    for group in groups {
+        match keep {
+            _ if group.len() == 1 => sweeps.push(volume.sweeps[group[0]].clone()),
+            KeepSweep::First => sweeps.push(volume.sweeps[group[0]].clone()),
+            KeepSweep::Last => sweeps.push(volume.sweeps[group[group.len() - 1]].clone()),
+            KeepSweep::Merge => {
+                let members: Vec<&SweepData> = group.iter().map(|&i| &volume.sweeps[i]).collect();
+                sweeps.push(merge_sweeps(&members, options)?);
+            }
+        }
+    }
+
+    let mut merged = VolumeData::new(volume.metadata.clone(), sweeps);
+    merged.calibration = volume.calibration.clone();
+    merged.history = volume.history.clone();
+    merged.reindex_sweeps();
+    merged.history.record("radish.transforms.sweep_merge.deduplicate_sweeps", &(keep, options));
+    Ok(merged)
+}
+
+/// Merge sweeps sharing the same geometry into one
+///
+/// The most recent sweep provides the output rays and gates; rays and gates
+/// of the other sweeps are matched to it by nearest azimuth and range.
+/// Moments present in any input sweep appear in the output.
+pub fn merge_sweeps(sweeps: &[&SweepData], options: &SweepMergeOptions) -> Result<SweepData> {
+    if sweeps.is_empty() {
+        return Err(RadishError::General("No sweeps to merge".to_string()));
+    }
+
+    // Order newest first so that `Latest` only needs the first valid value
+    let mut ordered: Vec<&SweepData> = sweeps.to_vec();
+    ordered.sort_by(|a, b| sweep_time(b).total_cmp(&sweep_time(a)));
+    let base = ordered[0];
+
+    let ray_maps: Vec<Vec<Option<usize>>> = ordered
+        .iter()
+        .map(|s| match_rays(base, s, options.azimuth_tolerance))
+        .collect();
+    let gate_maps: Vec<Vec<Option<usize>>> = ordered.iter().map(|s| match_gates(base, s)).collect();
+
+    let names: BTreeSet<&String> = ordered.iter().flat_map(|s| s.moments.keys()).collect();
+    let (num_rays, num_gates) = (base.num_rays(), base.num_gates());
+
+    let mut moments = HashMap::new();
+    for name in names {
+        let template = ordered
+            .iter()
+            .find_map(|s| s.get_moment(name))
+            .expect("moment name collected from sweeps");
+        let missing = template.missing_value();
+        let mut data = Array2::from_elem((num_rays, num_gates), missing);
+
+        for i in 0..num_rays {
+            for j in 0..num_gates {
+                // (value, quality) for every sweep that has a valid value here
+                let mut candidates: Vec<(f32, f32)> = Vec::new();
+                for (k, sweep) in ordered.iter().enumerate() {
+                    let (Some(si), Some(sj)) = (ray_maps[k][i], gate_maps[k][j]) else {
+                        continue;
+                    };
+                    let Some(moment) = sweep.get_moment(name) else {
+                        continue;
+                    };
+                    let Some(v) = moment.valid_value(si, sj) else {
+                        continue;
+                    };
+                    let quality = match &options.policy {
+                        MergePolicy::HighestQuality { quality_moment } => sweep
+                            .get_moment(quality_moment)
+                            .map(|q| q.value(si, sj))
+                            .filter(|q| !q.is_nan())
+                            .unwrap_or(f32::NEG_INFINITY),
+                        _ => 0.0,
+                    };
+                    candidates.push((v, quality));
+                }
+
+                if candidates.is_empty() {
+                    continue;
+                }
+                data[[i, j]] = match options.policy {
+                    MergePolicy::Latest => candidates[0].0,
+                    MergePolicy::HighestQuality { .. } => {
+                        // Ties go to the most recent sweep
+                        candidates
+                            .iter()
+                            .rev()
+                            .max_by(|a, b| a.1.total_cmp(&b.1))
+                            .map(|c| c.0)
+                            .unwrap_or(missing)
+                    }
+                    MergePolicy::Average => {
+                        candidates.iter().map(|c| c.0).sum::<f32>() / candidates.len() as f32
+                    }
+                };
+            }
+        }
+
+        let mut moment = template.clone();
+        moment.set_data(data);
+        moments.insert(name.clone(), moment);
+    }
+
+    Ok(SweepData::new(base.metadata.clone(), moments, base.coordinates.clone()))
+}
+
+/// Mean ray time of a sweep, used to order duplicate sweeps
+fn sweep_time(sweep: &SweepData) -> f64 {
+    let time = sweep.coordinates.epoch_times();
+    if time.is_empty() {
+        return f64::NEG_INFINITY;
+    }
+    time.iter().sum::<f64>() / time.len() as f64
+}
+
+/// For each ray of `base`, the nearest ray of `other` within the tolerance
+pub(crate) fn match_rays(base: &SweepData, other: &SweepData, tolerance: f32) -> Vec<Option<usize>> {
+    base.coordinates
+        .azimuth
+        .iter()
+        .map(|&az| {
+            other
+                .coordinates
+                .azimuth
+                .iter()
+                .enumerate()
+                .map(|(k, &a)| (k, azimuth_distance(az, a)))
+                .filter(|&(_, d)| d <= tolerance)
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(k, _)| k)
+        })
+        .collect()
+}
+
+/// For each gate of `base`, the nearest gate of `other` within half a gate spacing
+pub(crate) fn match_gates(base: &SweepData, other: &SweepData) -> Vec<Option<usize>> {
+    let range = &other.coordinates.range;
+    let spacing = if range.len() > 1 {
+        (range[range.len() - 1] - range[0]) / (range.len() - 1) as f32
+    } else {
+        f32::INFINITY
+    };
+
+    base.coordinates
+        .range
+        .iter()
+        .map(|&r| {
+            let k = range.partition_point(|&x| x < r);
+            [k.checked_sub(1), (k < range.len()).then_some(k)]
+                .into_iter()
+                .flatten()
+                .min_by(|&a, &b| (range[a] - r).abs().total_cmp(&(range[b] - r).abs()))
+                .filter(|&k| (range[k] - r).abs() <= spacing / 2.0 + f32::EPSILON)
+        })
+        .collect()
+}
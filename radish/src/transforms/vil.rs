@@ -0,0 +1,225 @@
+//! Vertically integrated liquid (VIL) and VIL density
+//!
+//! VIL (Greene & Clark 1972) integrates the liquid water content implied by
+//! reflectivity through each column, `VIL = Σ 3.44e-6 · Z̄^(4/7) · Δh`
+//! (kg/m²), with Z̄ the mean linear reflectivity (mm⁶/m³) of each layer
+//! between successive samples. Reflectivity is capped to limit the
+//! contribution of hail. VIL density (Amburn & Wolf 1997) divides VIL by
+//! the echo top height, `VILD = VIL / ET · 1000` (g/m³); high densities
+//! flag storms likely to produce large hail.
+//!
+//! Columns are taken from the levels of a grid, or from the PPI sweeps of a
+//! polar volume above each gate of its lowest sweep.
+
+use ndarray::{Array2, Axis};
+
+use crate::transforms::georeference::{ground_range, ground_to_antenna, GeoreferenceOptions};
+use crate::model::coordinates::{azimuth_distance, nearest_range_gate};
+use crate::{GridField, GriddedData, MomentData, RadishError, Result, SweepData, VolumeData};
+use radish_types::SweepMode;
+
+/// Name of the VIL product (kg/m²)
+pub const VIL: &str = "VIL";
+
+/// Name of the VIL density product (g/m³)
+pub const VIL_DENSITY: &str = "VILD";
+
+/// Options for VIL computation
+#[derive(Debug, Clone)]
+pub struct VilOptions {
+    /// Reflectivity field or moment (dBZ)
+    pub field: String,
+    /// Reflectivity (dBZ) above which values are capped, to limit hail contamination
+    pub max_reflectivity: f32,
+    /// Reflectivity (dBZ) below which samples contribute no liquid water
+    pub min_reflectivity: f32,
+    /// Reflectivity (dBZ) defining the echo top used for VIL density
+    pub echo_top_threshold: f32,
+    /// Largest azimuth difference (degrees) between rays stacked in a polar column
+    pub azimuth_tolerance: f32,
+    /// Beam propagation model for polar volumes
+    pub georeference: GeoreferenceOptions,
+}
+
+impl Default for VilOptions {
+    fn default() -> Self {
+        Self {
+            field: "DBZH".to_string(),
+            max_reflectivity: 56.0,
+            min_reflectivity: 0.0,
+            echo_top_threshold: 18.0,
+            azimuth_tolerance: 1.0,
+            georeference: GeoreferenceOptions::default(),
+        }
+    }
+}
+
+/// VIL (kg/m²) and VIL density (g/m³) of a column of (height, dBZ) samples sorted by height
+///
+/// Heights are meters above mean sea level. Invalid samples are NaN and
+/// break the integration; the VIL density is NaN without an echo top.
+pub fn column_vil(samples: &[(f64, f32)], options: &VilOptions) -> (f32, f32) {
+    let water = |dbz: f32| {
+        if dbz.is_nan() || dbz < options.min_reflectivity {
+            0.0
+        } else {
+            10f64.powf(dbz.min(options.max_reflectivity) as f64 / 10.0)
+        }
+    };
+
+    let mut vil = 0.0;
+    let mut valid = false;
+    for pair in samples.windows(2) {
+        let ((h0, z0), (h1, z1)) = (pair[0], pair[1]);
+        if z0.is_nan() || z1.is_nan() {
+            continue;
+        }
+        valid = true;
+        let z = (water(z0) + water(z1)) / 2.0;
+        vil += 3.44e-6 * z.powf(4.0 / 7.0) * (h1 - h0);
+    }
+    if !valid {
+        return (f32::NAN, f32::NAN);
+    }
+
+    let echo_top = samples
+        .iter()
+        .rev()
+        .find(|(_, z)| *z >= options.echo_top_threshold)
+        .map(|(h, _)| *h);
+    let density = match echo_top {
+        Some(top) if top > 0.0 => (vil / top * 1000.0) as f32,
+        _ => f32::NAN,
+    };
+    (vil as f32, density)
+}
+
+/// VIL and VIL density of a reflectivity grid, as a single-level grid
+///
+/// The result holds the `VIL` and `VILD` fields on the x/y axes of the
+/// grid, at its lowest level.
+pub fn vil_grid(grid: &GriddedData, options: &VilOptions) -> Result<GriddedData> {
+    let field = grid
+        .get_field(&options.field)
+        .ok_or_else(|| RadishError::MissingVariable(options.field.clone()))?;
+    let (nz, ny, nx) = field.shape();
+
+    let mut order: Vec<usize> = (0..nz).collect();
+    order.sort_by(|&a, &b| grid.z[a].total_cmp(&grid.z[b]));
+    let mut vil = Array2::from_elem((ny, nx), f32::NAN);
+    let mut density = Array2::from_elem((ny, nx), f32::NAN);
+    let mut samples = Vec::with_capacity(nz);
+    for j in 0..ny {
+        for i in 0..nx {
+            samples.clear();
+            samples.extend(order.iter().map(|&k| {
+                let v = field.data[[k, j, i]];
+                (grid.z[k], if field.is_valid_value(v) { v } else { f32::NAN })
+            }));
+            (vil[[j, i]], density[[j, i]]) = column_vil(&samples, options);
+        }
+    }
+
+    let base = order.first().map_or(0.0, |&k| grid.z[k]);
+    let mut out = GriddedData::new(grid.time, grid.projection, grid.x.clone(), grid.y.clone(), vec![base]);
+    out.attributes = grid.attributes.clone();
+    for (name, units, long_name, data) in [
+        (VIL, "kg m-2", "Vertically integrated liquid", vil),
+        (VIL_DENSITY, "g m-3", "VIL density", density),
+    ] {
+        let mut field = GridField::new(name.to_string(), units.to_string(), data.insert_axis(Axis(0)));
+        field.long_name = Some(long_name.to_string());
+        out.add_field(field)?;
+    }
+    Ok(out)
+}
+
+/// VIL and VIL density of a polar volume, on the rays and gates of its lowest PPI sweep
+///
+/// The column above each gate of the lowest sweep samples every PPI sweep
+/// at the same ground range, on the ray closest in azimuth (within
+/// `options.azimuth_tolerance`). The returned sweep holds the `VIL` and
+/// `VILD` moments with the metadata and coordinates of the lowest sweep.
+pub fn vil_volume(volume: &VolumeData, options: &VilOptions) -> Result<SweepData> {
+    let mut sweeps: Vec<&SweepData> = volume
+        .sweeps
+        .iter()
+        .filter(|s| {
+            matches!(s.metadata.sweep_mode, SweepMode::Azimuth | SweepMode::Sector | SweepMode::ManualPpi)
+                && s.get_moment(&options.field).is_some()
+        })
+        .collect();
+    sweeps.sort_by(|a, b| a.metadata.fixed_angle.total_cmp(&b.metadata.fixed_angle));
+    let base = *sweeps
+        .first()
+        .ok_or_else(|| RadishError::MissingVariable(options.field.clone()))?;
+    base.coordinates.validate()?;
+    let (num_rays, num_gates) = (base.num_rays(), base.num_gates());
+    let altitude = volume.metadata.altitude;
+
+    // Ray of each sweep closest in azimuth to each base ray
+    let rays: Vec<Vec<Option<usize>>> = sweeps
+        .iter()
+        .map(|sweep| {
+            base.coordinates
+                .azimuth
+                .iter()
+                .map(|&az| {
+                    sweep
+                        .coordinates
+                        .azimuth
+                        .iter()
+                        .enumerate()
+                        .map(|(i, &a)| (i, azimuth_distance(a, az)))
+                        .min_by(|a, b| a.1.total_cmp(&b.1))
+                        .filter(|&(_, d)| d <= options.azimuth_tolerance)
+                        .map(|(i, _)| i)
+                })
+                .collect()
+        })
+        .collect();
+
+    let mut vil = Array2::from_elem((num_rays, num_gates), f32::NAN);
+    let mut density = Array2::from_elem((num_rays, num_gates), f32::NAN);
+    let mut samples = Vec::with_capacity(sweeps.len());
+    for j in 0..num_gates {
+        let distance = ground_range(
+            base.coordinates.range[j] as f64,
+            base.metadata.fixed_angle,
+            &options.georeference,
+        );
+        // Gate and sample height of each sweep at this ground range
+        let gates: Vec<Option<(usize, f64)>> = sweeps
+            .iter()
+            .map(|sweep| {
+                let (range, height) = ground_to_antenna(distance, sweep.metadata.fixed_angle, &options.georeference);
+                nearest_range_gate(&sweep.coordinates.range, range as f32).map(|g| (g, altitude + height))
+            })
+            .collect();
+
+        for i in 0..num_rays {
+            samples.clear();
+            for (k, sweep) in sweeps.iter().enumerate() {
+                let (Some(ray), Some((gate, height))) = (rays[k][i], gates[k]) else {
+                    continue;
+                };
+                let moment = &sweep.moments[&options.field];
+                samples.push((height, moment.valid_value(ray, gate).unwrap_or(f32::NAN)));
+            }
+            (vil[[i, j]], density[[i, j]]) = column_vil(&samples, options);
+        }
+    }
+
+    let mut moments = std::collections::HashMap::new();
+    for (name, units, long_name, data) in [
+        (VIL, "kg m-2", "Vertically integrated liquid", vil),
+        (VIL_DENSITY, "g m-3", "VIL density", density),
+    ] {
+        let mut moment = MomentData::new(name.to_string(), units.to_string(), data);
+        moment.long_name = Some(long_name.to_string());
+        moments.insert(name.to_string(), moment);
+    }
+    let mut coordinates = base.coordinates.clone();
+    coordinates.clear_georeference();
+    Ok(SweepData::new(base.metadata.clone(), moments, coordinates))
+}
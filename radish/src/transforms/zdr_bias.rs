@@ -0,0 +1,228 @@
+//! ZDR bias estimation from vertically pointing ("birdbath") scans
+//!
+//! Raindrops seen from directly below are circular on average, so the
+//! intrinsic ZDR of light rain at vertical incidence is 0 dB and the median
+//! ZDR measured during a full antenna rotation at 90° elevation is the
+//! system ZDR bias. Gates are restricted to light rain (moderate
+//! reflectivity, high RHOHV) between a minimum range, which excludes the
+//! near field of the antenna, and a maximum range below the melting layer.
+//!
+//! Estimates are collected over time in a [`ZdrBiasTracker`], persisted as
+//! a small JSON file, and the correction applied to later volumes is the
+//! median of the recent estimates.
+
+use std::path::Path;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{Provenance, RadishError, Result, SweepData, VolumeData};
+use radish_types::SweepMode;
+
+/// Moment attribute recording the ZDR bias (dB) removed by [`apply_zdr_correction`]
+pub const ZDR_BIAS_ATTRIBUTE: &str = "zdr_bias_correction";
+
+/// Options for ZDR bias estimation
+#[derive(Debug, Clone)]
+pub struct ZdrBiasOptions {
+    /// Differential reflectivity moment
+    pub zdr: String,
+    /// Reflectivity moment
+    pub reflectivity: String,
+    /// Correlation coefficient moment (the test is skipped if missing)
+    pub rhohv: String,
+    /// Sweeps at or above this fixed angle (degrees) count as vertically pointing
+    pub min_elevation: f64,
+    /// Light rain reflectivity range (dBZ)
+    pub reflectivity_range: (f32, f32),
+    /// Minimum RHOHV of light rain gates
+    pub min_rhohv: f32,
+    /// Gates closer than this (meters) are in the near field of the antenna
+    pub min_range: f32,
+    /// Gates farther than this (meters) may be in or above the melting layer
+    pub max_range: f32,
+    /// Fewest light rain gates for an estimate
+    pub min_gates: usize,
+    /// Estimates within this period (seconds) before a volume form its correction
+    pub averaging_period: i64,
+    /// Most estimates kept by a tracker
+    pub max_history: usize,
+}
+
+impl Default for ZdrBiasOptions {
+    fn default() -> Self {
+        Self {
+            zdr: "ZDR".to_string(),
+            reflectivity: "DBZH".to_string(),
+            rhohv: "RHOHV".to_string(),
+            min_elevation: 88.0,
+            reflectivity_range: (10.0, 35.0),
+            min_rhohv: 0.98,
+            min_range: 500.0,
+            max_range: 3000.0,
+            min_gates: 500,
+            averaging_period: 86_400,
+            max_history: 1000,
+        }
+    }
+}
+
+/// ZDR bias measured on one vertically pointing sweep
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ZdrBiasEstimate {
+    /// Start time of the sweep
+    pub time: DateTime<Utc>,
+    /// Median ZDR (dB) of the light rain gates
+    pub bias: f64,
+    /// Standard deviation (dB) of the ZDR of those gates
+    pub spread: f64,
+    /// Number of light rain gates
+    pub gates: usize,
+}
+
+/// Whether a sweep points vertically
+pub fn is_vertical_sweep(sweep: &SweepData, options: &ZdrBiasOptions) -> bool {
+    sweep.metadata.sweep_mode == SweepMode::VerticalPointing || sweep.metadata.fixed_angle >= options.min_elevation
+}
+
+/// ZDR bias from a vertically pointing sweep, if it has enough light rain
+pub fn sweep_zdr_bias(sweep: &SweepData, options: &ZdrBiasOptions) -> Option<ZdrBiasEstimate> {
+    let zdr = sweep.get_moment(&options.zdr)?;
+    let dbz = sweep.get_moment(&options.reflectivity)?;
+    let rhohv = sweep.get_moment(&options.rhohv);
+    let (min_z, max_z) = options.reflectivity_range;
+
+    let mut values = Vec::new();
+    for ((i, j), v) in zdr.indexed_valid_values() {
+        let range = sweep.coordinates.range[j];
+        if range < options.min_range || range > options.max_range {
+            continue;
+        }
+        if !dbz.valid_value(i, j).is_some_and(|z| (min_z..=max_z).contains(&z)) {
+            continue;
+        }
+        if rhohv.is_some_and(|rho| !rho.valid_value(i, j).is_some_and(|r| r >= options.min_rhohv)) {
+            continue;
+        }
+        values.push(v as f64);
+    }
+    if values.len() < options.min_gates.max(1) {
+        return None;
+    }
+
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let spread = (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n).sqrt();
+    Some(ZdrBiasEstimate {
+        time: sweep.time_start().unwrap_or_default(),
+        bias: median(&mut values),
+        spread,
+        gates: values.len(),
+    })
+}
+
+/// ZDR bias estimates from the vertically pointing sweeps of a volume
+///
+/// Sweeps without enough light rain give no estimate.
+pub fn estimate_zdr_bias(volume: &VolumeData, options: &ZdrBiasOptions) -> Vec<ZdrBiasEstimate> {
+    volume
+        .sweeps
+        .iter()
+        .filter(|s| is_vertical_sweep(s, options))
+        .filter_map(|s| {
+            let mut estimate = sweep_zdr_bias(s, options)?;
+            if s.time_start().is_none() {
+                estimate.time = volume.metadata.time_coverage_start;
+            }
+            Some(estimate)
+        })
+        .collect()
+}
+
+/// History of ZDR bias estimates, persisted between runs
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ZdrBiasTracker {
+    /// Estimates in time order
+    pub estimates: Vec<ZdrBiasEstimate>,
+}
+
+impl ZdrBiasTracker {
+    /// Load a tracker saved with [`ZdrBiasTracker::save`], or an empty one if the file does not exist
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let text = std::fs::read_to_string(path)?;
+        serde_json::from_str(&text)
+            .map_err(|e| RadishError::InvalidFormat(format!("Invalid ZDR bias state {}: {}", path.display(), e)))
+    }
+
+    /// Save the tracker as JSON
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let text = serde_json::to_string_pretty(self)
+            .map_err(|e| RadishError::General(format!("Cannot serialize ZDR bias state: {}", e)))?;
+        std::fs::write(path, text)?;
+        Ok(())
+    }
+
+    /// Add the estimates of the vertically pointing sweeps of a volume
+    ///
+    /// Returns the new estimates; the oldest estimates beyond
+    /// `options.max_history` are dropped.
+    pub fn update(&mut self, volume: &VolumeData, options: &ZdrBiasOptions) -> Vec<ZdrBiasEstimate> {
+        let new = estimate_zdr_bias(volume, options);
+        self.estimates.extend(new.iter().copied());
+        self.estimates.sort_by_key(|e| e.time);
+        let excess = self.estimates.len().saturating_sub(options.max_history);
+        self.estimates.drain(..excess);
+        new
+    }
+
+    /// Bias (dB) to correct at `time`: median of the estimates in the averaging period before it
+    pub fn bias_at(&self, time: DateTime<Utc>, options: &ZdrBiasOptions) -> Option<f64> {
+        let start = time - Duration::seconds(options.averaging_period);
+        let mut recent: Vec<f64> = self
+            .estimates
+            .iter()
+            .filter(|e| e.time > start && e.time <= time)
+            .map(|e| e.bias)
+            .collect();
+        (!recent.is_empty()).then(|| median(&mut recent))
+    }
+}
+
+/// Subtract a ZDR bias (dB) from the ZDR moment of every sweep
+///
+/// The bias is recorded in the moment attributes and as the calibration
+/// ZDR correction of the volume.
+pub fn apply_zdr_correction(volume: &VolumeData, bias: f64, options: &ZdrBiasOptions) -> VolumeData {
+    let mut volume = volume.clone();
+    for sweep in &mut volume.sweeps {
+        let Some(zdr) = sweep.get_moment_mut(&options.zdr) else {
+            continue;
+        };
+        let missing = zdr.missing_value();
+        zdr.data_mut()
+            .mapv_inplace(|v| if v.is_nan() || v == missing { v } else { v - bias as f32 });
+        zdr.attributes.insert(ZDR_BIAS_ATTRIBUTE.to_string(), bias.to_string().into());
+        zdr.provenance = Some(Provenance::new(
+            "radish.transforms.zdr_bias.apply_zdr_correction",
+            &[&options.zdr],
+            &bias,
+        ));
+    }
+    volume.calibration.get_or_insert_with(Default::default).zdr_correction = Some(bias);
+    volume.history.record("radish.transforms.zdr_bias.apply_zdr_correction", &(bias, options));
+    volume
+}
+
+fn median(values: &mut [f64]) -> f64 {
+    values.sort_by(f64::total_cmp);
+    let n = values.len();
+    if n % 2 == 1 {
+        values[n / 2]
+    } else {
+        (values[n / 2 - 1] + values[n / 2]) / 2.0
+    }
+}
@@ -0,0 +1,334 @@
+//! MATLAB v7.3 (.mat) export
+//!
+//! MATLAB v7.3 files are HDF5 files with a 512 byte user block holding the
+//! MAT-file header. Every dataset and group carries a `MATLAB_class` attribute
+//! so MATLAB can map it back onto a native type.
+//!
+//! Volumes are written as a single top-level struct named `volume`:
+//!
+//! ```text
+//! volume
+//! ├── instrument_name        char
+//! ├── institution            char
+//! ├── site_name              char (empty if unknown)
+//! ├── latitude               double (degrees North)
+//! ├── longitude              double (degrees East)
+//! ├── altitude               double (meters above MSL)
+//! ├── frequency              double (Hz, NaN if unknown)
+//! ├── time_coverage_start    char (ISO 8601)
+//! ├── time_coverage_end      char (ISO 8601)
+//! ├── sweep_fixed_angles     double [1 × nsweeps]
+//! ├── attributes             struct (see below)
+//! └── sweep_0 … sweep_N      struct (see below)
+//! ```
+//!
+//! Each sweep struct (also the top-level `sweep` struct written by
+//! [`write_sweep_mat`]) contains:
+//!
+//! ```text
+//! sweep_k
+//! ├── sweep_number           double
+//! ├── sweep_mode             char (CfRadial name, e.g. "azimuth_surveillance")
+//! ├── fixed_angle            double (degrees)
+//! ├── time                   double [nrays × 1] (seconds since 1970-01-01 UTC)
+//! ├── azimuth                single [nrays × 1] (degrees)
+//! ├── elevation              single [nrays × 1] (degrees)
+//! ├── range                  single [1 × ngates] (meters)
+//! └── moments                struct
+//!     └── <NAME>             struct
+//!         ├── data           single [nrays × ngates], missing gates are NaN
+//!         ├── units          char
+//!         ├── standard_name  char
+//!         ├── long_name      char
+//!         └── attributes     struct
+//! ```
+//!
+//! The `attributes` structs hold the additional attributes of the volume or
+//! moment: text as char, numbers as double scalars, numeric arrays as
+//! double row vectors and text arrays as char joined by newlines. The
+//! `history` attribute of the volume also lists its processing steps, one
+//! per line (see [`VolumeData::history_attribute`]).
+//!
+//! Moment names that are not valid MATLAB identifiers have invalid characters
+//! replaced with `_` (and are prefixed with `m_` if they don't start with a letter).
+
+use std::fs::OpenOptions;
+use std::io::{Seek, SeekFrom, Write};
+use std::path::Path;
+
+use chrono::Utc;
+use hdf5::types::FixedAscii;
+use ndarray::{Array2, ArrayView2};
+
+use crate::model::{AttributeValue, Attributes, HISTORY_ATTRIBUTE};
+use crate::{MomentData, RadishError, Result, SweepData, VolumeData};
+
+/// Size of the HDF5 user block reserved for the MAT-file header
+const USERBLOCK_SIZE: u64 = 512;
+
+/// Length of the descriptive text at the start of the MAT-file header
+const HEADER_TEXT_LEN: usize = 116;
+
+/// Write a complete volume to a MATLAB v7.3 file as the struct `volume`
+pub fn write_volume_mat(volume: &VolumeData, path: &Path) -> Result<()> {
+    let file = create_mat_file(path)?;
+
+    let root = file.create_group("volume")?;
+    set_matlab_class(&root, b"struct")?;
+
+    let metadata = &volume.metadata;
+    write_char(&root, "instrument_name", &metadata.instrument_name)?;
+    write_char(&root, "institution", &metadata.institution)?;
+    write_char(&root, "site_name", metadata.site_name.as_deref().unwrap_or(""))?;
+    write_scalar(&root, "latitude", metadata.latitude)?;
+    write_scalar(&root, "longitude", metadata.longitude)?;
+    write_scalar(&root, "altitude", metadata.altitude)?;
+    write_scalar(&root, "frequency", metadata.frequency.unwrap_or(f64::NAN))?;
+    write_char(&root, "time_coverage_start", &metadata.time_coverage_start.to_rfc3339())?;
+    write_char(&root, "time_coverage_end", &metadata.time_coverage_end.to_rfc3339())?;
+    write_row_vector(&root, "sweep_fixed_angles", &metadata.sweep_fixed_angles)?;
+    let mut attributes = metadata.attributes.clone();
+    if let Some(history) = volume.history_attribute() {
+        attributes.insert(HISTORY_ATTRIBUTE.to_string(), history.into());
+    }
+    write_attributes(&root, &attributes)?;
+
+    for (i, sweep) in volume.sweeps.iter().enumerate() {
+        let group = root.create_group(&format!("sweep_{}", i))?;
+        write_sweep_group(&group, sweep)?;
+    }
+
+    drop(file);
+    write_mat_header(path)
+}
+
+/// Write a single sweep to a MATLAB v7.3 file as the struct `sweep`
+pub fn write_sweep_mat(sweep: &SweepData, path: &Path) -> Result<()> {
+    let file = create_mat_file(path)?;
+
+    let group = file.create_group("sweep")?;
+    write_sweep_group(&group, sweep)?;
+
+    drop(file);
+    write_mat_header(path)
+}
+
+/// Convert a moment name into a valid MATLAB struct field name
+pub fn matlab_field_name(name: &str) -> String {
+    let mut field: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' })
+        .collect();
+
+    if !field.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        field.insert_str(0, "m_");
+    }
+
+    // MATLAB truncates field names longer than namelengthmax (63)
+    field.truncate(63);
+    field
+}
+
+fn create_mat_file(path: &Path) -> Result<hdf5::File> {
+    let file = hdf5::File::with_options()
+        .with_fcpl(|p| p.userblock(USERBLOCK_SIZE))
+        .create(path)?;
+    Ok(file)
+}
+
+/// Write the 128 byte MAT-file header into the reserved user block
+fn write_mat_header(path: &Path) -> Result<()> {
+    let text = format!(
+        "MATLAB 7.3 MAT-file, Platform: radish, Created on: {} HDF5 schema 1.00 .",
+        Utc::now().format("%a %b %e %H:%M:%S %Y")
+    );
+
+    let mut header = vec![b' '; HEADER_TEXT_LEN];
+    let len = text.len().min(HEADER_TEXT_LEN);
+    header[..len].copy_from_slice(&text.as_bytes()[..len]);
+    // Subsystem data offset (unused), version 0x0200 and endian indicator
+    header.extend_from_slice(&[0u8; 8]);
+    header.extend_from_slice(&[0x00, 0x02]);
+    header.extend_from_slice(b"IM");
+
+    let mut file = OpenOptions::new().write(true).open(path)?;
+    file.seek(SeekFrom::Start(0))?;
+    file.write_all(&header)?;
+    Ok(())
+}
+
+fn write_sweep_group(group: &hdf5::Group, sweep: &SweepData) -> Result<()> {
+    set_matlab_class(group, b"struct")?;
+
+    write_scalar(group, "sweep_number", sweep.metadata.sweep_number as f64)?;
+    write_char(group, "sweep_mode", sweep.metadata.sweep_mode.cf_name())?;
+    write_scalar(group, "fixed_angle", sweep.metadata.fixed_angle)?;
+
+    let coords = &sweep.coordinates;
+    write_column_vector(group, "time", &coords.epoch_times())?;
+    write_column_vector(group, "azimuth", &coords.azimuth)?;
+    write_column_vector(group, "elevation", &coords.elevation)?;
+    write_row_vector(group, "range", &coords.range)?;
+
+    let moments = group.create_group("moments")?;
+    set_matlab_class(&moments, b"struct")?;
+
+    let mut names: Vec<&String> = sweep.moments.keys().collect();
+    names.sort();
+    for name in names {
+        let moment = &sweep.moments[name];
+        let moment_group = moments.create_group(&matlab_field_name(name))?;
+        write_moment_group(&moment_group, moment)?;
+    }
+
+    Ok(())
+}
+
+fn write_moment_group(group: &hdf5::Group, moment: &MomentData) -> Result<()> {
+    set_matlab_class(group, b"struct")?;
+
+    let mut data = moment.data().into_owned();
+    moment_missing_to_nan(moment, &mut data);
+    write_matrix(group, "data", data.view())?;
+
+    write_char(group, "units", &moment.units)?;
+    write_char(group, "standard_name", moment.standard_name.as_deref().unwrap_or(""))?;
+    write_char(group, "long_name", moment.long_name.as_deref().unwrap_or(""))?;
+    write_attributes(group, &moment.attributes)?;
+
+    // Derived moments carry their provenance as char fields (ancestors as a JSON array)
+    if let Some(provenance) = &moment.provenance {
+        let mut attributes: Vec<_> = provenance.to_attributes().into_iter().collect();
+        attributes.sort();
+        for (key, value) in attributes {
+            write_char(group, &key, &value)?;
+        }
+    }
+    Ok(())
+}
+
+/// Write additional attributes as the fields of an `attributes` struct
+fn write_attributes(group: &hdf5::Group, attributes: &Attributes) -> Result<()> {
+    let group = group.create_group("attributes")?;
+    set_matlab_class(&group, b"struct")?;
+
+    let mut names: Vec<&String> = attributes.keys().collect();
+    names.sort();
+    for name in names {
+        let field = matlab_field_name(name);
+        match &attributes[name] {
+            AttributeValue::Text(s) => write_char(&group, &field, s)?,
+            AttributeValue::Int(v) => write_scalar(&group, &field, *v as f64)?,
+            AttributeValue::Float(v) => write_scalar(&group, &field, *v)?,
+            AttributeValue::Texts(v) => write_char(&group, &field, &v.join("\n"))?,
+            AttributeValue::Ints(v) => {
+                let values: Vec<f64> = v.iter().map(|&x| x as f64).collect();
+                write_row_vector(&group, &field, &values)?
+            }
+            AttributeValue::Floats(v) => write_row_vector(&group, &field, v)?,
+        }
+    }
+    Ok(())
+}
+
+/// Replace fill and out-of-range values with NaN, MATLAB's missing value
+fn moment_missing_to_nan(moment: &MomentData, data: &mut Array2<f32>) {
+    data.mapv_inplace(|v| if moment.is_valid_value(v) { v } else { f32::NAN });
+}
+
+/// Write a 2D matrix
+///
+/// MATLAB is column-major, so the data is transposed before writing to keep
+/// the [rays × gates] orientation when loaded.
+fn write_matrix(group: &hdf5::Group, name: &str, data: ArrayView2<f32>) -> Result<()> {
+    let (nrays, ngates) = data.dim();
+    let transposed: Vec<f32> = data.t().iter().copied().collect();
+    write_dataset(group, name, (ngates, nrays), &transposed, b"single")
+}
+
+fn write_scalar(group: &hdf5::Group, name: &str, value: f64) -> Result<()> {
+    write_dataset(group, name, (1, 1), &[value], b"double")
+}
+
+/// Write a [1 × n] row vector
+fn write_row_vector<T: MatNumeric>(group: &hdf5::Group, name: &str, values: &[T]) -> Result<()> {
+    if values.is_empty() {
+        return write_empty(group, name, T::MATLAB_CLASS);
+    }
+    write_dataset(group, name, (values.len(), 1), values, T::MATLAB_CLASS)
+}
+
+/// Write a [n × 1] column vector
+fn write_column_vector<T: MatNumeric>(group: &hdf5::Group, name: &str, values: &[T]) -> Result<()> {
+    if values.is_empty() {
+        return write_empty(group, name, T::MATLAB_CLASS);
+    }
+    write_dataset(group, name, (1, values.len()), values, T::MATLAB_CLASS)
+}
+
+/// Create a 2D dataset from row-major values and tag it with its MATLAB class
+///
+/// HDF5 dimensions are the reverse of the MATLAB dimensions.
+fn write_dataset<T: hdf5::H5Type, const N: usize>(
+    group: &hdf5::Group,
+    name: &str,
+    shape: (usize, usize),
+    values: &[T],
+    class: &[u8; N],
+) -> Result<()> {
+    let dataset = group.new_dataset::<T>().shape(shape).create(name)?;
+    dataset.write_raw(values)?;
+    set_matlab_class(&dataset, class)?;
+    Ok(())
+}
+
+/// Write a [1 × n] char array
+///
+/// MATLAB stores characters as UTF-16 code units with `MATLAB_int_decode = 2`.
+fn write_char(group: &hdf5::Group, name: &str, value: &str) -> Result<()> {
+    let units: Vec<u16> = value.encode_utf16().collect();
+    if units.is_empty() {
+        return write_empty(group, name, b"char");
+    }
+
+    write_dataset(group, name, (units.len(), 1), &units, b"char")?;
+    group
+        .dataset(name)?
+        .new_attr::<i32>()
+        .create("MATLAB_int_decode")?
+        .write_scalar(&2i32)?;
+    Ok(())
+}
+
+/// Write an empty array, which MATLAB encodes as its dimensions plus `MATLAB_empty`
+fn write_empty<const N: usize>(group: &hdf5::Group, name: &str, class: &[u8; N]) -> Result<()> {
+    let dims = [0u64, 0u64];
+    let dataset = group.new_dataset::<u64>().shape(2).create(name)?;
+    dataset.write_raw(&dims[..])?;
+    set_matlab_class(&dataset, class)?;
+    dataset.new_attr::<u8>().create("MATLAB_empty")?.write_scalar(&1u8)?;
+    Ok(())
+}
+
+fn set_matlab_class<const N: usize>(location: &hdf5::Location, class: &[u8; N]) -> Result<()> {
+    let value = FixedAscii::<N>::from_ascii(class)
+        .map_err(|e| RadishError::Conversion(e.to_string()))?;
+    location
+        .new_attr::<FixedAscii<N>>()
+        .create("MATLAB_class")?
+        .write_scalar(&value)?;
+    Ok(())
+}
+
+/// Numeric types with a MATLAB class name
+trait MatNumeric: hdf5::H5Type {
+    const MATLAB_CLASS: &'static [u8; 6];
+}
+
+impl MatNumeric for f32 {
+    const MATLAB_CLASS: &'static [u8; 6] = b"single";
+}
+
+impl MatNumeric for f64 {
+    const MATLAB_CLASS: &'static [u8; 6] = b"double";
+}
@@ -0,0 +1,14 @@
+//! Writers for exporting radar data to other formats
+//!
+//! Writers are the counterpart of the backends: they take the common data
+//! model and serialize it into a specific file format.
+
+pub mod matlab;
+pub mod motion;
+pub mod naming;
+pub mod odim;
+
+pub use matlab::{write_volume_mat, write_sweep_mat};
+pub use motion::{write_motion_netcdf, MotionWriterOptions};
+pub use naming::FilenameTemplate;
+pub use odim::write_odim;
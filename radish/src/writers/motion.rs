@@ -0,0 +1,211 @@
+//! CF-compliant NetCDF export of motion vector fields
+//!
+//! Motion fields (e.g. from echo tracking for advection) are grids holding
+//! the x and y components of the echo motion in m/s. They are written as:
+//!
+//! ```text
+//! dimensions: time = 1, y, x
+//! time(time)           double, seconds since 1970-01-01T00:00:00Z
+//! x(x), y(y)           double, projected coordinates (or lon/lat degrees)
+//! lat(y, x), lon(y, x) double, auxiliary coordinates for projected grids
+//! projection           int, CF grid mapping of the grid projection
+//! u(time, y, x)        float, motion along x (m s-1), NaN where unknown
+//! v(time, y, x)        float, motion along y (m s-1), NaN where unknown
+//! ```
+//!
+//! so that nowcasting tools can read them with any CF-aware library.
+
+use std::path::Path;
+
+use crate::transforms::georeference::{Projection, EARTH_RADIUS};
+use crate::{GriddedData, RadishError, Result};
+
+/// Default name of the x motion component field
+pub const MOTION_U: &str = "MOTION_U";
+
+/// Default name of the y motion component field
+pub const MOTION_V: &str = "MOTION_V";
+
+/// Options for writing motion fields
+#[derive(Debug, Clone)]
+pub struct MotionWriterOptions {
+    /// Grid field holding the x component (m/s)
+    pub u_field: String,
+    /// Grid field holding the y component (m/s)
+    pub v_field: String,
+    /// Period (seconds) the motion was estimated over, written as `time_bnds`
+    pub interval: Option<f64>,
+    /// Deflate level for the motion variables (no compression if `None`)
+    pub compression: Option<i32>,
+}
+
+impl Default for MotionWriterOptions {
+    fn default() -> Self {
+        Self {
+            u_field: MOTION_U.to_string(),
+            v_field: MOTION_V.to_string(),
+            interval: None,
+            compression: Some(4),
+        }
+    }
+}
+
+/// Write the u/v motion fields of a grid to a CF NetCDF file
+///
+/// Only the first level of each field is written. The grid time is the
+/// valid time of the motion; with `interval` set it is the end of the
+/// `time_bnds` period.
+pub fn write_motion_netcdf(grid: &GriddedData, path: &Path, options: &MotionWriterOptions) -> Result<()> {
+    let field = |name: &str| {
+        grid.get_field(name)
+            .ok_or_else(|| RadishError::MissingVariable(name.to_string()))
+    };
+    let u = field(&options.u_field)?;
+    let v = field(&options.v_field)?;
+    let (_, ny, nx) = grid.shape();
+
+    let mut file = netcdf::create(path)?;
+    file.add_attribute("Conventions", "CF-1.8")?;
+    file.add_attribute("title", "Radar echo motion vectors")?;
+    file.add_attribute("source", "radish")?;
+    file.add_attribute("history", format!("{} created by radish", chrono::Utc::now().to_rfc3339()))?;
+    // Sorted so that the file layout does not depend on hash map order
+    let mut attributes: Vec<_> = grid.attributes.iter().collect();
+    attributes.sort();
+    for (key, value) in attributes {
+        if !matches!(key.as_str(), "Conventions" | "title" | "history") {
+            file.add_attribute(key, value.as_str())?;
+        }
+    }
+
+    file.add_dimension("time", 1)?;
+    file.add_dimension("y", ny)?;
+    file.add_dimension("x", nx)?;
+
+    let time = grid.time.timestamp_micros() as f64 / 1e6;
+    let mut var = file.add_variable::<f64>("time", &["time"])?;
+    var.put_attribute("standard_name", "time")?;
+    var.put_attribute("units", "seconds since 1970-01-01T00:00:00Z")?;
+    var.put_attribute("calendar", "standard")?;
+    if options.interval.is_some() {
+        var.put_attribute("bounds", "time_bnds")?;
+    }
+    var.put_values(&[time], ..)?;
+
+    if let Some(interval) = options.interval {
+        file.add_dimension("nv", 2)?;
+        let mut var = file.add_variable::<f64>("time_bnds", &["time", "nv"])?;
+        var.put_values(&[time - interval, time], ..)?;
+    }
+
+    let geographic = grid.projection == Projection::Geographic;
+    let axes = [
+        ("x", &grid.x, "projection_x_coordinate", "longitude", "degrees_east"),
+        ("y", &grid.y, "projection_y_coordinate", "latitude", "degrees_north"),
+    ];
+    for (name, values, standard_name, geo_name, geo_units) in axes {
+        let mut var = file.add_variable::<f64>(name, &[name])?;
+        if geographic {
+            var.put_attribute("standard_name", geo_name)?;
+            var.put_attribute("units", geo_units)?;
+        } else {
+            var.put_attribute("standard_name", standard_name)?;
+            var.put_attribute("units", "m")?;
+        }
+        var.put_attribute("axis", name.to_uppercase())?;
+        var.put_values(values.as_slice(), ..)?;
+    }
+
+    if !geographic {
+        let mut lat = Vec::with_capacity(ny * nx);
+        let mut lon = Vec::with_capacity(ny * nx);
+        for iy in 0..ny {
+            for ix in 0..nx {
+                let (lo, la) = grid.lonlat(ix, iy);
+                lon.push(lo);
+                lat.push(la);
+            }
+        }
+        for (name, values, units) in [("lat", &lat, "degrees_north"), ("lon", &lon, "degrees_east")] {
+            let mut var = file.add_variable::<f64>(name, &["y", "x"])?;
+            var.put_attribute("standard_name", if name == "lat" { "latitude" } else { "longitude" })?;
+            var.put_attribute("units", units)?;
+            var.put_values(values.as_slice(), ..)?;
+        }
+    }
+
+    write_grid_mapping(&mut file, &grid.projection)?;
+
+    for (name, source, axis) in [("u", u, "x"), ("v", v, "y")] {
+        let values: Vec<f32> = source
+            .data
+            .index_axis(ndarray::Axis(0), 0)
+            .iter()
+            .map(|&value| if source.is_valid_value(value) { value } else { f32::NAN })
+            .collect();
+
+        let mut var = file.add_variable::<f32>(name, &["time", "y", "x"])?;
+        if let Some(level) = options.compression {
+            var.set_compression(level, true)?;
+        }
+        var.set_fill_value(f32::NAN)?;
+        var.put_attribute("long_name", format!("Echo motion along the grid {} axis", axis))?;
+        var.put_attribute("units", "m s-1")?;
+        var.put_attribute("grid_mapping", "projection")?;
+        if !geographic {
+            var.put_attribute("coordinates", "lat lon")?;
+        }
+        var.put_values(&values, ..)?;
+    }
+
+    Ok(())
+}
+
+/// Add the CF grid mapping variable `projection`
+fn write_grid_mapping(file: &mut netcdf::FileMut, projection: &Projection) -> Result<()> {
+    let mut var = file.add_variable::<i32>("projection", &[])?;
+
+    match *projection {
+        Projection::Geographic => {
+            var.put_attribute("grid_mapping_name", "latitude_longitude")?;
+        }
+        Projection::Aeqd { lon_0, lat_0 } => {
+            var.put_attribute("grid_mapping_name", "azimuthal_equidistant")?;
+            var.put_attribute("longitude_of_projection_origin", lon_0)?;
+            var.put_attribute("latitude_of_projection_origin", lat_0)?;
+            var.put_attribute("false_easting", 0.0)?;
+            var.put_attribute("false_northing", 0.0)?;
+            var.put_attribute("earth_radius", EARTH_RADIUS)?;
+            return Ok(());
+        }
+        Projection::Utm { zone, north } => {
+            var.put_attribute("grid_mapping_name", "transverse_mercator")?;
+            var.put_attribute("longitude_of_central_meridian", zone as f64 * 6.0 - 183.0)?;
+            var.put_attribute("latitude_of_projection_origin", 0.0)?;
+            var.put_attribute("scale_factor_at_central_meridian", 0.9996)?;
+            var.put_attribute("false_easting", 500_000.0)?;
+            var.put_attribute("false_northing", if north { 0.0 } else { 10_000_000.0 })?;
+        }
+        Projection::WebMercator => {
+            var.put_attribute("grid_mapping_name", "mercator")?;
+            var.put_attribute("longitude_of_projection_origin", 0.0)?;
+            var.put_attribute("standard_parallel", 0.0)?;
+            var.put_attribute("false_easting", 0.0)?;
+            var.put_attribute("false_northing", 0.0)?;
+            var.put_attribute("earth_radius", 6_378_137.0)?;
+            return Ok(());
+        }
+        Projection::Laea { lon_0, lat_0, x_0, y_0 } => {
+            var.put_attribute("grid_mapping_name", "lambert_azimuthal_equal_area")?;
+            var.put_attribute("longitude_of_projection_origin", lon_0)?;
+            var.put_attribute("latitude_of_projection_origin", lat_0)?;
+            var.put_attribute("false_easting", x_0)?;
+            var.put_attribute("false_northing", y_0)?;
+        }
+    }
+
+    // WGS84 ellipsoid
+    var.put_attribute("semi_major_axis", 6_378_137.0)?;
+    var.put_attribute("inverse_flattening", 298.257_223_563)?;
+    Ok(())
+}
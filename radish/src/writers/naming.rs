@@ -0,0 +1,156 @@
+//! Output filename templates
+//!
+//! Templates such as `{site}/{start:%Y%m%d}/{site}_{start:%Y%m%d_%H%M%S}_{vcp}.nc`
+//! name output files from volume metadata, so that converted files follow
+//! the naming conventions of an archive without renaming afterwards.
+//!
+//! Placeholders are:
+//!
+//! - `{site}`: instrument name
+//! - `{institution}`: institution
+//! - `{start}`, `{end}`: time coverage, formatted with an optional strftime
+//!   format after a colon (default `%Y%m%dT%H%M%SZ`)
+//! - `{volume}`: volume number
+//! - `{sweeps}`: number of sweeps
+//! - any other name: the volume attribute of that name (e.g. `{vcp}`)
+//!
+//! `{{` and `}}` produce literal braces. Path separators and whitespace in
+//! substituted values are replaced by `_`, so only the template itself
+//! creates directories.
+
+use std::path::{Path, PathBuf};
+
+use chrono::format::{Item, StrftimeItems};
+
+use crate::{RadishError, Result, VolumeMetadata};
+
+/// Time format used when a time placeholder has none
+pub const DEFAULT_TIME_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Literal(String),
+    Field { name: String, format: Option<String> },
+}
+
+/// A parsed filename template
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilenameTemplate {
+    template: String,
+    segments: Vec<Segment>,
+}
+
+impl FilenameTemplate {
+    /// Parse a template, checking its braces and time formats
+    pub fn parse(template: &str) -> Result<Self> {
+        let invalid = |message: &str| RadishError::InvalidFormat(format!("Filename template '{}': {}", template, message));
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '}' => return Err(invalid("unmatched '}'")),
+                '{' => {
+                    let mut placeholder = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => placeholder.push(c),
+                            None => return Err(invalid("unclosed '{'")),
+                        }
+                    }
+                    let (name, format) = match placeholder.split_once(':') {
+                        Some((name, format)) => (name.trim(), Some(format.to_string())),
+                        None => (placeholder.trim(), None),
+                    };
+                    if name.is_empty() {
+                        return Err(invalid("empty placeholder"));
+                    }
+                    if let Some(format) = &format {
+                        if !matches!(name, "start" | "end") {
+                            return Err(invalid(&format!("only times take a format, not '{}'", name)));
+                        }
+                        if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+                            return Err(invalid(&format!("invalid time format '{}'", format)));
+                        }
+                    }
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(Segment::Field {
+                        name: name.to_string(),
+                        format,
+                    });
+                }
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+
+        Ok(Self {
+            template: template.to_string(),
+            segments,
+        })
+    }
+
+    /// The template string
+    pub fn as_str(&self) -> &str {
+        &self.template
+    }
+
+    /// Render the template for a volume
+    ///
+    /// Fails with `MissingAttribute` for a placeholder that is neither built
+    /// in nor a volume attribute.
+    pub fn render(&self, metadata: &VolumeMetadata) -> Result<String> {
+        let mut out = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => out.push_str(text),
+                Segment::Field { name, format } => {
+                    let format = format.as_deref().unwrap_or(DEFAULT_TIME_FORMAT);
+                    let value = match name.as_str() {
+                        "site" => metadata.instrument_name.clone(),
+                        "institution" => metadata.institution.clone(),
+                        "start" => metadata.time_coverage_start.format(format).to_string(),
+                        "end" => metadata.time_coverage_end.format(format).to_string(),
+                        "volume" => metadata.volume_number.to_string(),
+                        "sweeps" => metadata.sweep_group_names.len().to_string(),
+                        other => metadata
+                            .attributes
+                            .get(other)
+                            .map(|v| v.to_string())
+                            .ok_or_else(|| RadishError::MissingAttribute(other.to_string()))?,
+                    };
+                    out.push_str(&sanitize(&value));
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    /// Render the template for a volume as a path under `directory`
+    pub fn render_path(&self, directory: &Path, metadata: &VolumeMetadata) -> Result<PathBuf> {
+        Ok(directory.join(self.render(metadata)?))
+    }
+}
+
+/// Replace path separators and whitespace so a value stays one path component
+fn sanitize(value: &str) -> String {
+    value
+        .trim()
+        .chars()
+        .map(|c| if c == '/' || c == '\\' || c.is_whitespace() { '_' } else { c })
+        .collect()
+}
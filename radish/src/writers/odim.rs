@@ -0,0 +1,217 @@
+//! OPERA ODIM_H5 polar volume (PVOL) export
+//!
+//! Volumes are written following ODIM_H5 v2.3, one `datasetN` group per
+//! sweep and one `dataM` group per moment:
+//!
+//! ```text
+//! /                    Conventions = "ODIM_H5/V2_3"
+//! ├── what             object = "PVOL", version, date, time, source
+//! ├── where            lon, lat, height
+//! ├── how              software, sw_version, beamwH, beamwV, wavelength
+//! └── dataset1 … N
+//!     ├── what         product = "SCAN", startdate, starttime, enddate, endtime
+//!     ├── where        elangle, nbins, rstart, rscale, nrays, a1gate
+//!     ├── how          elangles, startazA, stopazA
+//!     └── data1 … M
+//!         ├── what     quantity, gain, offset, nodata, undetect
+//!         └── data     float [nrays × nbins]
+//! ```
+//!
+//! Rays are written in order of azimuth, as ODIM expects, with `a1gate`
+//! pointing at the first ray in time. Moments are written unpacked as
+//! 32-bit floats (`gain` 1, `offset` 0) under their radish names, which are
+//! the ODIM quantity names for moments read with standard names. Invalid
+//! gates are written as `nodata`; radish doesn't tell undetected echo from
+//! missing data, so no gate is written as `undetect`. Only PPI-type sweeps
+//! can be written.
+
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use hdf5::types::VarLenAscii;
+use ndarray::Array2;
+use radish_types::SweepMode;
+
+use crate::{RadishError, Result, SweepData, VolumeData};
+
+/// ODIM convention version written to the root `Conventions` attribute
+const CONVENTIONS: &str = "ODIM_H5/V2_3";
+
+/// `nodata` value of written moments
+pub const ODIM_NODATA: f64 = -9999.0;
+
+/// `undetect` value of written moments, never present in the data
+pub const ODIM_UNDETECT: f64 = -9998.0;
+
+/// Write a volume to an ODIM_H5 polar volume file
+///
+/// Fails with `Unsupported` for volumes with RHI or other non-PPI sweeps.
+pub fn write_odim(volume: &VolumeData, path: &Path) -> Result<()> {
+    if let Some(sweep) = volume.sweeps.iter().find(|s| !is_ppi(s.metadata.sweep_mode)) {
+        return Err(RadishError::Unsupported(format!(
+            "ODIM_H5 polar volumes of {} sweeps",
+            sweep.metadata.sweep_mode.cf_name()
+        )));
+    }
+
+    let file = hdf5::File::create(path)?;
+    write_str(&file, "Conventions", CONVENTIONS)?;
+
+    let metadata = &volume.metadata;
+    let what = file.create_group("what")?;
+    write_str(&what, "object", "PVOL")?;
+    write_str(&what, "version", "H5rad 2.3")?;
+    write_date_time(&what, "date", "time", metadata.time_coverage_start)?;
+    write_str(&what, "source", &source(volume))?;
+
+    let location = file.create_group("where")?;
+    write_f64(&location, "lon", metadata.longitude)?;
+    write_f64(&location, "lat", metadata.latitude)?;
+    write_f64(&location, "height", metadata.altitude)?;
+
+    let how = file.create_group("how")?;
+    write_str(&how, "software", "radish")?;
+    write_str(&how, "sw_version", env!("CARGO_PKG_VERSION"))?;
+    if let Some(parameters) = &metadata.instrument_parameters {
+        if let Some(beam_width) = parameters.beam_width_h {
+            write_f64(&how, "beamwH", beam_width)?;
+        }
+        if let Some(beam_width) = parameters.beam_width_v {
+            write_f64(&how, "beamwV", beam_width)?;
+        }
+    }
+    if let Some(frequency) = metadata.frequency.filter(|f| *f > 0.0) {
+        // Wavelength in cm
+        write_f64(&how, "wavelength", 299_792_458.0 / frequency * 100.0)?;
+    }
+
+    for (i, sweep) in volume.sweeps.iter().enumerate() {
+        let group = file.create_group(&format!("dataset{}", i + 1))?;
+        write_sweep(&group, sweep, volume)?;
+    }
+    Ok(())
+}
+
+fn write_sweep(group: &hdf5::Group, sweep: &SweepData, volume: &VolumeData) -> Result<()> {
+    let coords = &sweep.coordinates;
+    let (nrays, nbins) = (sweep.num_rays(), sweep.num_gates());
+
+    // Rays in order of azimuth; a1gate is the first ray in time
+    let mut order: Vec<usize> = (0..nrays).collect();
+    order.sort_by(|&a, &b| coords.azimuth[a].total_cmp(&coords.azimuth[b]));
+    let first = (0..nrays)
+        .filter(|&i| coords.time[i].is_finite())
+        .min_by(|&a, &b| coords.time[a].total_cmp(&coords.time[b]))
+        .unwrap_or(0);
+    let a1gate = order.iter().position(|&i| i == first).unwrap_or(0);
+
+    let what = group.create_group("what")?;
+    write_str(&what, "product", "SCAN")?;
+    let start = sweep.time_start().unwrap_or(volume.metadata.time_coverage_start);
+    let end = sweep.time_end().unwrap_or(volume.metadata.time_coverage_end);
+    write_date_time(&what, "startdate", "starttime", start)?;
+    write_date_time(&what, "enddate", "endtime", end)?;
+
+    let rscale = match coords.range.as_slice() {
+        [first, second, ..] => (second - first) as f64,
+        _ => 0.0,
+    };
+    let rstart = coords.range.first().map_or(0.0, |&r| (r as f64 - rscale / 2.0) / 1000.0);
+    let location = group.create_group("where")?;
+    write_f64(&location, "elangle", sweep.metadata.fixed_angle)?;
+    write_i64(&location, "nbins", nbins as i64)?;
+    write_f64(&location, "rstart", rstart)?;
+    write_f64(&location, "rscale", rscale)?;
+    write_i64(&location, "nrays", nrays as i64)?;
+    write_i64(&location, "a1gate", a1gate as i64)?;
+
+    // Ray edges half a nominal ray width either side of the ray azimuths
+    let half_width = if nrays > 0 { 180.0 / nrays as f64 } else { 0.0 };
+    let azimuths: Vec<f64> = order.iter().map(|&i| coords.azimuth[i] as f64).collect();
+    let how = group.create_group("how")?;
+    write_f64_array(&how, "elangles", &order.iter().map(|&i| coords.elevation[i] as f64).collect::<Vec<_>>())?;
+    write_f64_array(
+        &how,
+        "startazA",
+        &azimuths.iter().map(|a| (a - half_width).rem_euclid(360.0)).collect::<Vec<_>>(),
+    )?;
+    write_f64_array(
+        &how,
+        "stopazA",
+        &azimuths.iter().map(|a| (a + half_width).rem_euclid(360.0)).collect::<Vec<_>>(),
+    )?;
+
+    let mut names: Vec<&String> = sweep.moments.keys().collect();
+    names.sort();
+    for (j, name) in names.into_iter().enumerate() {
+        let moment = &sweep.moments[name];
+        let data_group = group.create_group(&format!("data{}", j + 1))?;
+
+        let what = data_group.create_group("what")?;
+        write_str(&what, "quantity", name)?;
+        write_f64(&what, "gain", 1.0)?;
+        write_f64(&what, "offset", 0.0)?;
+        write_f64(&what, "nodata", ODIM_NODATA)?;
+        write_f64(&what, "undetect", ODIM_UNDETECT)?;
+
+        let data = moment.data();
+        let validity = moment.validity();
+        let values = Array2::from_shape_fn((nrays, nbins), |(row, gate)| {
+            let ray = order[row];
+            if validity[[ray, gate]] {
+                data[[ray, gate]]
+            } else {
+                ODIM_NODATA as f32
+            }
+        });
+        data_group
+            .new_dataset::<f32>()
+            .shape((nrays, nbins))
+            .create("data")?
+            .write_raw(values.as_slice().expect("standard layout"))?;
+    }
+    Ok(())
+}
+
+/// Whether a sweep mode is a PPI, the only scan an ODIM `SCAN` product holds
+fn is_ppi(mode: SweepMode) -> bool {
+    matches!(mode, SweepMode::Azimuth | SweepMode::Sector | SweepMode::ManualPpi)
+}
+
+/// ODIM `source`: the `source` attribute read from an ODIM file, or the
+/// instrument name as place
+fn source(volume: &VolumeData) -> String {
+    match volume.metadata.attributes.get("source").and_then(|s| s.as_str()) {
+        Some(source) if source.contains(':') => source.to_string(),
+        _ => format!("PLC:{}", volume.metadata.instrument_name),
+    }
+}
+
+/// Write a timestamp as ODIM `YYYYMMDD` and `HHMMSS` attributes
+fn write_date_time(location: &hdf5::Location, date: &str, time: &str, value: DateTime<Utc>) -> Result<()> {
+    write_str(location, date, &value.format("%Y%m%d").to_string())?;
+    write_str(location, time, &value.format("%H%M%S").to_string())
+}
+
+/// Write an ASCII string attribute, replacing other characters with `?`
+fn write_str(location: &hdf5::Location, name: &str, value: &str) -> Result<()> {
+    let ascii: String = value.chars().map(|c| if c.is_ascii() { c } else { '?' }).collect();
+    let value = VarLenAscii::from_ascii(&ascii).map_err(|e| RadishError::Conversion(e.to_string()))?;
+    location.new_attr::<VarLenAscii>().create(name)?.write_scalar(&value)?;
+    Ok(())
+}
+
+fn write_f64(location: &hdf5::Location, name: &str, value: f64) -> Result<()> {
+    location.new_attr::<f64>().create(name)?.write_scalar(&value)?;
+    Ok(())
+}
+
+fn write_i64(location: &hdf5::Location, name: &str, value: i64) -> Result<()> {
+    location.new_attr::<i64>().create(name)?.write_scalar(&value)?;
+    Ok(())
+}
+
+fn write_f64_array(location: &hdf5::Location, name: &str, values: &[f64]) -> Result<()> {
+    location.new_attr::<f64>().shape(values.len()).create(name)?.write_raw(values)?;
+    Ok(())
+}
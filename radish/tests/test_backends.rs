@@ -0,0 +1,675 @@
+//! Tests for format backends using small synthetic files
+
+use radish::backends::{CinradBackend, CinradSite, RadarBackend};
+
+/// Build one legacy SA/SB radial record
+fn sa_record(state: u16, elevation_number: u16, azimuth: f32, elevation: f32, ms_of_day: u32) -> Vec<u8> {
+    let mut record = vec![0u8; 2432];
+    let mut put_u16 = |offset: usize, value: u16| record[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+    let angle = |deg: f32| (deg / 180.0 * 4096.0 * 8.0).round() as u16;
+
+    put_u16(14, 1);
+    put_u16(32, 19_000); // days since 1969-12-31
+    put_u16(34, 4600); // unambiguous range, 0.1 km
+    put_u16(36, angle(azimuth));
+    put_u16(40, state);
+    put_u16(42, angle(elevation));
+    put_u16(44, elevation_number);
+    put_u16(46, 0); // first reflectivity gate (m)
+    put_u16(48, 0); // first Doppler gate (m)
+    put_u16(50, 1000); // reflectivity gate spacing (m)
+    put_u16(52, 250); // Doppler gate spacing (m)
+    put_u16(54, 4); // reflectivity gates
+    put_u16(56, 8); // Doppler gates
+    put_u16(70, 2); // 0.5 m/s velocity resolution
+    put_u16(88, 2700); // Nyquist velocity, 0.01 m/s
+    record[28..32].copy_from_slice(&ms_of_day.to_le_bytes());
+
+    // 20 dBZ everywhere except a no-data gate; +5 m/s velocity
+    record[128..132].copy_from_slice(&[106, 106, 0, 106]);
+    record[588..596].fill(139);
+    record[1508..1516].fill(10);
+    record
+}
+
+#[test]
+fn test_cinrad_legacy_sa() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("Z_RADR_I_Z9999_20220101000000_O_DOR_SA_CAP.bin");
+
+    let mut bytes = Vec::new();
+    bytes.extend(sa_record(3, 1, 0.0, 0.5, 1000));
+    bytes.extend(sa_record(1, 1, 90.0, 0.5, 2000));
+    bytes.extend(sa_record(0, 2, 0.0, 1.5, 3000));
+    bytes.extend(sa_record(4, 2, 90.0, 1.5, 4000));
+    std::fs::write(&path, bytes).unwrap();
+
+    let backend = CinradBackend::new();
+    assert!(backend.can_read(&path));
+
+    let metadata = backend.scan_file(&path).unwrap();
+    assert_eq!(metadata.instrument_name, "Z9999");
+    assert_eq!(metadata.sweep_fixed_angles, vec![0.5, 1.5]);
+    assert!(metadata.latitude.is_nan());
+
+    let sweep = backend.read_sweep(&path, 1).unwrap();
+    assert_eq!(sweep.num_rays(), 2);
+    // Reflectivity (1 km) is placed on the 250 m Doppler grid
+    assert_eq!(sweep.num_gates(), 13);
+    assert_eq!(sweep.metadata.nyquist_velocity, Some(27.0));
+
+    let dbzh = sweep.get_moment("DBZH").unwrap();
+    assert_eq!(dbzh.data()[[0, 0]], 20.0);
+    assert_eq!(dbzh.data()[[0, 4]], 20.0);
+    assert!(dbzh.data()[[0, 8]].is_nan());
+    let vradh = sweep.get_moment("VRADH").unwrap();
+    assert_eq!(vradh.data()[[1, 7]], 5.0);
+    assert!(vradh.data()[[1, 8]].is_nan());
+
+    let site = CinradSite {
+        code: "Z9999".to_string(),
+        name: None,
+        latitude: 30.0,
+        longitude: 120.0,
+        altitude: 50.0,
+    };
+    let volume = CinradBackend::with_site(site).read_volume(&path).unwrap();
+    assert_eq!(volume.num_sweeps(), 2);
+    assert_eq!(volume.metadata.latitude, 30.0);
+    let time = &volume.sweeps[0].coordinates.time;
+    assert!((time[1] - time[0] - 1.0).abs() < 1e-6);
+}
+
+#[test]
+fn test_auto_backend_from_contents() {
+    use radish::backends::auto_backend;
+
+    let dir = tempfile::tempdir().unwrap();
+    let mut cinrad = Vec::new();
+    cinrad.extend(sa_record(3, 1, 0.0, 0.5, 1000));
+    cinrad.extend(sa_record(4, 1, 90.0, 0.5, 2000));
+    let backend_for = |name: &str, bytes: &[u8]| {
+        let path = dir.path().join(name);
+        std::fs::write(&path, bytes).unwrap();
+        auto_backend(&path).map(|backend| backend.name().to_string())
+    };
+
+    // Contents take precedence over the extension
+    assert_eq!(backend_for("volume", &cinrad).unwrap(), "cinrad");
+    assert_eq!(backend_for("misnamed.nc", &cinrad).unwrap(), "cinrad");
+    assert_eq!(backend_for("volume.dat", b"CDF\x01\0\0\0\0").unwrap(), "cfradial1");
+    let mut hdf5 = vec![0u8; 512];
+    hdf5.extend(b"\x89HDF\r\n\x1a\n");
+    assert_eq!(backend_for("volume.h5", &hdf5).unwrap(), "cfradial1");
+
+    // Unrecognized contents fall back to the extension
+    assert_eq!(backend_for("corrupt.bin", b"not a radar file").unwrap(), "cinrad");
+    assert!(backend_for("notes.txt", b"not a radar file").is_err());
+}
+
+#[test]
+fn test_cinrad_standard_truncated() {
+    use radish::RadishError;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("truncated.bin");
+
+    // Generic, site and task headers claiming far more cuts than the file holds
+    let mut bytes = vec![0u8; 32 + 128 + 256];
+    bytes[..4].copy_from_slice(b"RSTM");
+    bytes[32..37].copy_from_slice(b"Z9999");
+    bytes[32 + 128 + 176..32 + 128 + 180].copy_from_slice(&i32::MAX.to_le_bytes());
+    std::fs::write(&path, bytes).unwrap();
+
+    let result = CinradBackend::new().scan_file(&path);
+    assert!(matches!(result, Err(RadishError::InvalidFormat(_))));
+}
+
+#[test]
+fn test_cinrad_malformed_gate_geometry() {
+    use radish::RadishError;
+
+    let read = |offset: usize, value: u16| {
+        let mut bytes = Vec::new();
+        for (state, azimuth) in [(3, 0.0), (4, 90.0)] {
+            let mut record = sa_record(state, 1, azimuth, 0.5, 1000);
+            record[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+            bytes.extend(record);
+        }
+        CinradBackend::new().read_volume_bytes(&bytes, None)
+    };
+
+    // 1 m reflectivity gates spread the velocity gates over a huge grid
+    assert!(matches!(read(50, 1), Err(RadishError::InvalidFormat(_))));
+    // So does a first velocity gate far beyond the reflectivity
+    assert!(matches!(read(48, u16::MAX), Err(RadishError::InvalidFormat(_))));
+    // Unchanged geometry still reads
+    assert!(read(50, 1000).is_ok());
+}
+
+#[test]
+fn test_lazy_volume() {
+    use radish::LazyVolume;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("Z_RADR_I_Z9999_20220101000000_O_DOR_SA_CAP.bin");
+    let mut bytes = Vec::new();
+    bytes.extend(sa_record(3, 1, 0.0, 0.5, 1000));
+    bytes.extend(sa_record(1, 1, 90.0, 0.5, 2000));
+    bytes.extend(sa_record(0, 2, 0.0, 1.5, 3000));
+    bytes.extend(sa_record(4, 2, 90.0, 1.5, 4000));
+    std::fs::write(&path, bytes).unwrap();
+
+    let lazy = LazyVolume::open(&path).unwrap();
+    assert_eq!(lazy.num_sweeps(), 2);
+    assert_eq!(lazy.sweep_descriptors()[1].fixed_angle, Some(1.5));
+    assert!(!lazy.is_loaded(0) && !lazy.is_loaded(1));
+
+    let index = lazy.find_sweep(1.5, 0.1).unwrap();
+    let sweep = lazy.get_sweep(index).unwrap();
+    assert_eq!(sweep.num_rays(), 2);
+    assert!(lazy.is_loaded(1) && !lazy.is_loaded(0));
+    // Later accesses return the cached sweep
+    assert!(std::ptr::eq(sweep, lazy.get_sweep(1).unwrap()));
+    assert!(lazy.get_sweep(2).is_err());
+
+    let volume = lazy.into_volume().unwrap();
+    assert_eq!(volume.num_sweeps(), 2);
+    assert_eq!(volume.sweeps[0].metadata.fixed_angle, 0.5);
+}
+
+#[test]
+fn test_read_from_memory() {
+    use radish::backends::auto_backend_bytes;
+    use radish::LazyVolume;
+
+    let mut bytes = Vec::new();
+    bytes.extend(sa_record(3, 1, 0.0, 0.5, 1000));
+    bytes.extend(sa_record(1, 1, 90.0, 0.5, 2000));
+    bytes.extend(sa_record(0, 2, 0.0, 1.5, 3000));
+    bytes.extend(sa_record(4, 2, 90.0, 1.5, 4000));
+    let name = "Z_RADR_I_Z9999_20220101000000_O_DOR_SA_CAP.bin";
+
+    let backend = auto_backend_bytes(&bytes).unwrap();
+    assert_eq!(backend.name(), "cinrad");
+    assert_eq!(backend.scan_bytes(&bytes, Some(name)).unwrap().instrument_name, "Z9999");
+    assert_eq!(backend.scan_bytes(&bytes, None).unwrap().instrument_name, "CINRAD");
+
+    // Same volume as reading the file
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join(name);
+    std::fs::write(&path, &bytes).unwrap();
+    let volume = backend.read_volume_bytes(&bytes, Some(name)).unwrap();
+    let from_file = backend.read_volume(&path).unwrap();
+    assert_eq!(volume.num_sweeps(), from_file.num_sweeps());
+    let bits = |v: &radish::VolumeData| v.sweeps[1].moments["DBZH"].data().mapv(f32::to_bits);
+    assert_eq!(bits(&volume), bits(&from_file));
+    assert_eq!(backend.read_sweep_bytes(&bytes, None, 1).unwrap().num_rays(), 2);
+    assert!(backend.read_sweep_bytes(&bytes, None, 2).is_err());
+
+    let lazy = LazyVolume::from_bytes(bytes, Some(name.to_string())).unwrap();
+    assert!(lazy.path().is_none());
+    assert_eq!(lazy.metadata().instrument_name, "Z9999");
+    assert_eq!(lazy.get_sweep(1).unwrap().metadata.fixed_angle, 1.5);
+
+    assert!(auto_backend_bytes(b"not a radar file").is_err());
+}
+
+/// Build a ustar archive of regular files
+#[cfg(feature = "archive")]
+fn tar_archive(members: &[(&str, Vec<u8>)]) -> Vec<u8> {
+    let mut archive = Vec::new();
+    for (name, data) in members {
+        let mut header = [0u8; 512];
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        header[100..107].copy_from_slice(b"0000644");
+        header[124..135].copy_from_slice(format!("{:011o}", data.len()).as_bytes());
+        header[156] = b'0';
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[148..156].fill(b' ');
+        let checksum: u32 = header.iter().map(|&b| b as u32).sum();
+        header[148..155].copy_from_slice(format!("{:06o}\0", checksum).as_bytes());
+        archive.extend(header);
+        archive.extend(data);
+        archive.resize(archive.len().div_ceil(512) * 512, 0);
+    }
+    archive.resize(archive.len() + 1024, 0);
+    archive
+}
+
+/// Build a zip archive of one member, `data` being stored or deflated per `method`
+#[cfg(feature = "archive")]
+fn zip_archive(name: &str, data: &[u8], method: u16, uncompressed_size: u32) -> Vec<u8> {
+    let name = name.as_bytes();
+    let entry = |signature: u32, central: bool| {
+        let mut record = signature.to_le_bytes().to_vec();
+        if central {
+            record.extend([20, 0]);
+        }
+        record.extend([20, 0, 0, 0]);
+        record.extend(method.to_le_bytes());
+        record.extend([0; 8]);
+        record.extend((data.len() as u32).to_le_bytes());
+        record.extend(uncompressed_size.to_le_bytes());
+        record.extend((name.len() as u16).to_le_bytes());
+        record.extend([0, 0]);
+        if central {
+            record.extend([0; 14]);
+        }
+        record.extend(name);
+        record
+    };
+    let mut zip = entry(0x0403_4b50, false);
+    zip.extend(data);
+    let directory = entry(0x0201_4b50, true);
+    let offset = zip.len() as u32;
+    zip.extend(&directory);
+    zip.extend(0x0605_4b50u32.to_le_bytes());
+    zip.extend([0, 0, 0, 0, 1, 0, 1, 0]);
+    zip.extend((directory.len() as u32).to_le_bytes());
+    zip.extend(offset.to_le_bytes());
+    zip.extend([0, 0]);
+    zip
+}
+
+#[cfg(feature = "archive")]
+#[test]
+fn test_archive_members() {
+    use flate2::write::GzEncoder;
+    use radish::io::archive::{read_archive_volumes, ArchiveFormat, ArchiveReader};
+    use std::io::Write;
+
+    let gzip = |bytes: &[u8]| {
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(bytes).unwrap();
+        encoder.finish().unwrap()
+    };
+    let mut volume = Vec::new();
+    volume.extend(sa_record(3, 1, 0.0, 0.5, 1000));
+    volume.extend(sa_record(4, 1, 90.0, 0.5, 2000));
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("HAS012345.tar.gz");
+    let tar = tar_archive(&[
+        ("README.txt", b"NCEI order".to_vec()),
+        ("Z_RADR_I_Z9999_20220101000000_O_DOR_SA_CAP.bin.gz", gzip(&volume)),
+        ("Z_RADR_I_Z9999_20220101000600_O_DOR_SA_CAP.bin", volume.clone()),
+    ]);
+    std::fs::write(&path, gzip(&tar)).unwrap();
+
+    assert_eq!(ArchiveFormat::detect(&path).unwrap(), Some(ArchiveFormat::TarGz));
+    let members: Vec<_> = ArchiveReader::open(&path).unwrap().map(|m| m.unwrap()).collect();
+    assert_eq!(members.len(), 3);
+    assert_eq!(members[1].name, "Z_RADR_I_Z9999_20220101000000_O_DOR_SA_CAP.bin");
+    assert_eq!(members[1].data, volume);
+
+    let volumes = read_archive_volumes(&path).unwrap();
+    assert_eq!(volumes.len(), 2);
+    assert_eq!(volumes[0].1.num_sweeps(), 1);
+    assert_eq!(volumes[1].1.sweeps[0].num_rays(), 2);
+
+    // Stored zip members, located through the central directory
+    let path = dir.path().join("volumes.zip");
+    std::fs::write(&path, zip_archive("volume.bin", &volume, 0, volume.len() as u32)).unwrap();
+
+    assert_eq!(ArchiveFormat::detect(&path).unwrap(), Some(ArchiveFormat::Zip));
+    let members: Vec<_> = ArchiveReader::open(&path).unwrap().map(|m| m.unwrap()).collect();
+    assert_eq!(members.len(), 1);
+    assert_eq!(members[0].data, volume);
+}
+
+#[cfg(feature = "archive")]
+#[test]
+fn test_archive_untrusted_sizes() {
+    use flate2::write::DeflateEncoder;
+    use radish::io::archive::ArchiveReader;
+    use radish::RadishError;
+    use std::io::Write;
+
+    // One member with its size field replaced and the checksum redone
+    let with_size = |size: [u8; 12]| {
+        let mut tar = tar_archive(&[("member.bin", b"abc".to_vec())]);
+        tar[124..136].copy_from_slice(&size);
+        tar[148..156].fill(b' ');
+        let checksum: u32 = tar[..512].iter().map(|&b| b as u32).sum();
+        tar[148..155].copy_from_slice(format!("{:06o}\0", checksum).as_bytes());
+        tar
+    };
+    let dir = tempfile::tempdir().unwrap();
+    let read = |tar: Vec<u8>| {
+        let path = dir.path().join("sizes.tar");
+        std::fs::write(&path, tar).unwrap();
+        ArchiveReader::open(&path).unwrap().next().unwrap()
+    };
+
+    // Base-256 sizes, including the bits of the first byte
+    let mut size = [0u8; 12];
+    size[0] = 0x80;
+    size[11] = 3;
+    assert_eq!(read(with_size(size)).unwrap().data, b"abc");
+    size[0] = 0x81;
+    assert!(matches!(read(with_size(size)), Err(RadishError::InvalidFormat(_))));
+
+    // A size far beyond the archive fails without reserving it
+    let mut size = [0u8; 12];
+    size[0] = 0x80;
+    size[6] = 1;
+    assert!(matches!(read(with_size(size)), Err(RadishError::InvalidFormat(_))));
+
+    // Deflated zip members may not inflate beyond their declared size
+    let mut encoder = DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(&[0u8; 10_000]).unwrap();
+    let deflated = encoder.finish().unwrap();
+    let path = dir.path().join("bomb.zip");
+    let read_zip = |uncompressed_size: u32| {
+        std::fs::write(&path, zip_archive("member.bin", &deflated, 8, uncompressed_size)).unwrap();
+        ArchiveReader::open(&path).unwrap().next().unwrap()
+    };
+    assert_eq!(read_zip(10_000).unwrap().data.len(), 10_000);
+    assert!(matches!(read_zip(100), Err(RadishError::InvalidFormat(_))));
+}
+
+/// Build a single-message GRIB2 file on a 3 × 2 lat/lon grid with simple packing
+#[cfg(feature = "grib")]
+fn grib2_message(values: &[u16]) -> Vec<u8> {
+    let section = |number: u8, body: &[u8]| {
+        let mut s = ((body.len() + 5) as u32).to_be_bytes().to_vec();
+        s.push(number);
+        s.extend_from_slice(body);
+        s
+    };
+
+    // Identification: reference time 2024-05-01 12:30:00
+    let mut s1 = vec![0u8; 16];
+    s1[7..9].copy_from_slice(&2024u16.to_be_bytes());
+    s1[9..14].copy_from_slice(&[5, 1, 12, 30, 0]);
+
+    // Grid template 3.0: Ni=3, Nj=2, La1=40N, Lo1=260E, 0.01° spacing, north to south
+    let mut s3 = vec![0u8; 67];
+    s3[1..5].copy_from_slice(&6u32.to_be_bytes());
+    s3[25..29].copy_from_slice(&3u32.to_be_bytes());
+    s3[29..33].copy_from_slice(&2u32.to_be_bytes());
+    s3[41..45].copy_from_slice(&40_000_000u32.to_be_bytes());
+    s3[45..49].copy_from_slice(&260_000_000u32.to_be_bytes());
+    s3[58..62].copy_from_slice(&10_000u32.to_be_bytes());
+    s3[62..66].copy_from_slice(&10_000u32.to_be_bytes());
+
+    // Product template 4.0: category 10, number 0, 500 m MSL
+    let mut s4 = vec![0u8; 29];
+    s4[4] = 10;
+    s4[17] = 102;
+    s4[19..23].copy_from_slice(&500u32.to_be_bytes());
+
+    // Simple packing: Y = (-1000 + X) / 10, 16 bits
+    let mut s5 = vec![0u8; 16];
+    s5[0..4].copy_from_slice(&(values.len() as u32).to_be_bytes());
+    s5[6..10].copy_from_slice(&(-1000.0f32).to_bits().to_be_bytes());
+    s5[12..14].copy_from_slice(&1u16.to_be_bytes());
+    s5[14] = 16;
+
+    let data: Vec<u8> = values.iter().flat_map(|v| v.to_be_bytes()).collect();
+    let body: Vec<u8> = [
+        section(1, &s1),
+        section(3, &s3),
+        section(4, &s4),
+        section(5, &s5),
+        section(6, &[255]),
+        section(7, &data),
+    ]
+    .concat();
+
+    let mut message = b"GRIB\0\0\xd1\x02".to_vec();
+    message.extend_from_slice(&((16 + body.len() + 4) as u64).to_be_bytes());
+    message.extend(body);
+    message.extend_from_slice(b"7777");
+    message
+}
+
+#[cfg(feature = "grib")]
+#[test]
+fn test_mrms_grib2_simple_packing() {
+    use radish::io::read_mrms_grib2;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("MRMS_MergedReflectivityQC_00.50_20240501-123000.grib2");
+    // Top row 10, 20, 30 dBZ; bottom row -99 (no coverage), 0, 5 dBZ
+    std::fs::write(&path, grib2_message(&[1100, 1200, 1300, 10, 1000, 1050])).unwrap();
+
+    let grid = read_mrms_grib2(&path).unwrap();
+    assert_eq!(grid.shape(), (1, 2, 3));
+    assert_eq!(grid.z, vec![500.0]);
+    assert_eq!(grid.time.to_rfc3339(), "2024-05-01T12:30:00+00:00");
+    assert!((grid.x[0] + 100.0).abs() < 1e-9);
+    assert!((grid.y[0] - 39.99).abs() < 1e-9);
+
+    let field = grid.get_field("MergedReflectivityQC").unwrap();
+    assert_eq!(field.attributes["grib2_parameter"], "209-10-0");
+    // Rows are stored north to south and flipped so that y increases
+    assert!(field.data[[0, 0, 0]].is_nan());
+    assert_eq!(field.data[[0, 0, 2]], 5.0);
+    assert_eq!(field.data[[0, 1, 1]], 20.0);
+}
+
+#[test]
+fn test_unit_normalization() {
+    use radish::io::units::{record_conversions, unit_conversion, Quantity};
+    use std::collections::HashMap;
+
+    assert_eq!(unit_conversion("range", Some("meters"), Quantity::Length).unwrap(), None);
+    assert_eq!(unit_conversion("range", None, Quantity::Length).unwrap(), None);
+    assert!(unit_conversion("range", Some("furlongs"), Quantity::Length).is_err());
+
+    let km = unit_conversion("range", Some("km"), Quantity::Length).unwrap().unwrap();
+    let mut range = vec![0.25, 1.5];
+    km.convert_slice(&mut range);
+    assert_eq!(range, vec![250.0, 1500.0]);
+
+    let rad = unit_conversion("azimuth", Some("radians"), Quantity::Angle).unwrap().unwrap();
+    assert!((rad.convert(std::f64::consts::FRAC_PI_2) - 90.0).abs() < 1e-12);
+
+    let mut attributes = HashMap::new();
+    record_conversions(&mut attributes, &[km, rad]);
+    assert_eq!(attributes["unit_conversions"], "range: km -> m; azimuth: radians -> degrees");
+}
+
+#[test]
+fn test_cf_time_units() {
+    use chrono::{TimeZone, Utc};
+    use radish::io::units::parse_time_units;
+    use radish::Coordinates;
+
+    let reference = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+    for units in [
+        "seconds since 2024-05-01T12:00:00Z",
+        "seconds since 2024-05-01 12:00:00 UTC",
+        "s since 2024-05-01T12:00:00.000Z",
+        "seconds since 2024-05-01 12:00",
+    ] {
+        assert_eq!(parse_time_units(units).unwrap(), (1.0, reference), "{}", units);
+    }
+    assert_eq!(
+        parse_time_units("days since 2024-05-01").unwrap(),
+        (86400.0, Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap())
+    );
+    assert!(parse_time_units("seconds").is_err());
+    assert!(parse_time_units("fortnights since 2024-05-01").is_err());
+
+    let mut coords = Coordinates::new(vec![0.5, 1.0, f64::NAN], vec![0.0], vec![0.0; 3], vec![0.5; 3]);
+    coords.time_reference = Some(reference);
+    let times = coords.ray_datetimes();
+    assert_eq!(times[0], Some(reference + chrono::Duration::milliseconds(500)));
+    assert_eq!(times[2], None);
+    assert_eq!(coords.epoch_time(1), reference.timestamp() as f64 + 1.0);
+    assert_eq!(coords.time_units(), "seconds since 2024-05-01T12:00:00Z");
+
+    coords.set_time_reference(None);
+    assert_eq!(coords.time[1], reference.timestamp() as f64 + 1.0);
+    assert_eq!(coords.ray_datetimes(), times);
+}
+
+#[test]
+fn test_sounding_profile() {
+    use radish::io::sounding::parse_sounding;
+
+    let text = "# Station 72357\n\
+                height temperature speed direction rh\n\
+                3000 -6.0 15 270 40\n\
+                350 20.0 5 180 80\n\
+                1500 8.0 nan 225 60\n\
+                6000 -26.0 30 270\n";
+    let profile = parse_sounding(text).unwrap();
+    assert_eq!(profile.height, vec![350.0, 1500.0, 3000.0, 6000.0]);
+    assert!((profile.temperature_at(2250.0) - 1.0).abs() < 1e-9);
+    assert!(profile.temperature_at(100.0).is_nan());
+    assert!((profile.freezing_level().unwrap() - (1500.0 + 1500.0 * 8.0 / 14.0)).abs() < 1e-9);
+    assert!((profile.isotherm_height(-20.0).unwrap() - 5100.0).abs() < 1e-9);
+    assert_eq!(profile.isotherm_height(-40.0), None);
+
+    // Southerly wind at the surface; the level without speed is left out of the wind profile
+    assert!(profile.v[0] > 4.99 && profile.u[0].abs() < 1e-9);
+    let wind = profile.wind_profile().unwrap();
+    assert_eq!(wind.height, vec![350.0, 3000.0, 6000.0]);
+    assert!((wind.u[2] - 30.0).abs() < 1e-9);
+
+    // Comma separated with u/v columns and empty fields
+    let profile = parse_sounding("height,temperature,u,v\n0,10,,\n1000,,2,3\n").unwrap();
+    assert!(profile.u[0].is_nan() && profile.temperature[1].is_nan());
+    assert_eq!(profile.v[1], 3.0);
+    assert!(parse_sounding("temperature\n10\n").is_err());
+}
+
+#[test]
+fn test_typed_attributes() {
+    use radish::io::attribute_value;
+    use radish::AttributeValue;
+
+    assert_eq!(attribute_value(netcdf::AttrValue::Str("NEXRAD".to_string())), Some("NEXRAD".into()));
+    assert_eq!(attribute_value(netcdf::AttrValue::Short(-3)), Some(AttributeValue::Int(-3)));
+    assert_eq!(attribute_value(netcdf::AttrValue::Float(0.5)), Some(AttributeValue::Float(0.5)));
+    assert_eq!(
+        attribute_value(netcdf::AttrValue::Uints(vec![1, 2])),
+        Some(AttributeValue::Ints(vec![1, 2]))
+    );
+    assert_eq!(attribute_value(netcdf::AttrValue::Ulonglong(u64::MAX)), None);
+
+    let vcp = AttributeValue::Int(212);
+    assert_eq!(vcp, "212");
+    assert_eq!(vcp.as_f64(), Some(212.0));
+    assert_eq!(vcp.as_str(), None);
+    assert_eq!(AttributeValue::Text(" 1.5".to_string()).as_f64(), Some(1.5));
+    assert_eq!(AttributeValue::Floats(vec![0.5, 1.0]).to_string(), "0.5 1");
+}
+
+#[test]
+fn test_volume_cache_roundtrip() {
+    use ndarray::Array2;
+    use radish::io::{decode_volume, encode_volume, read_volume_cache, write_volume_cache};
+    use radish::model::{MomentData, MomentStorage, Pattern, RayMetadata, VolumeBuilder};
+    use radish::RadishError;
+
+    let mut volume = VolumeBuilder::new()
+        .elevations(&[0.5, 1.5])
+        .moment("DBZH", "dBZ", Pattern::RangeRamp { offset: 10.0, slope: 0.5 })
+        .build();
+    volume.metadata.latitude = f64::NAN;
+    volume.metadata.attributes.insert("vcp".to_string(), 212i64.into());
+    volume.metadata.attributes.insert("gains".to_string(), vec![0.5, 1.0].into());
+
+    let sweep = &mut volume.sweeps[0];
+    let shape = sweep.moments["DBZH"].shape();
+    let mut packed = MomentData::from_storage(
+        "VRADH".to_string(),
+        "m/s".to_string(),
+        MomentStorage::I16(Array2::from_elem(shape, 40)),
+    );
+    packed.scale_factor = Some(0.5);
+    packed.add_offset = Some(-10.0);
+    let mut mask = Array2::from_elem(shape, true);
+    mask[[0, 1]] = false;
+    packed.set_mask(Some(mask)).unwrap();
+    sweep.moments.insert("VRADH".to_string(), packed);
+    sweep.ray_metadata = Some(RayMetadata {
+        nyquist_velocity: Some(vec![f64::NAN; shape.0]),
+        ..Default::default()
+    });
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("volume.rvc");
+    write_volume_cache(&volume, &path).unwrap();
+    let cached = read_volume_cache(&path).unwrap();
+
+    assert!(cached.metadata.latitude.is_nan());
+    assert_eq!(cached.metadata.attributes["vcp"], volume.metadata.attributes["vcp"]);
+    assert_eq!(cached.metadata.attributes["gains"], volume.metadata.attributes["gains"]);
+    assert_eq!(cached.sweeps.len(), 2);
+    let (original, restored) = (&volume.sweeps[0], &cached.sweeps[0]);
+    assert_eq!(restored.coordinates.azimuth, original.coordinates.azimuth);
+    assert_eq!(restored.coordinates.range, original.coordinates.range);
+    assert_eq!(restored.moments["DBZH"].data(), original.moments["DBZH"].data());
+    let vr = &restored.moments["VRADH"];
+    assert!(vr.as_packed().is_packed());
+    assert_eq!(vr.mask(), original.moments["VRADH"].mask());
+    assert_eq!(vr.valid_value(0, 0), Some(10.0));
+    assert_eq!(vr.valid_value(0, 1), None);
+    assert!(restored.ray_metadata.as_ref().unwrap().nyquist_velocity.as_ref().unwrap()[0].is_nan());
+
+    let bytes = encode_volume(&volume).unwrap();
+    assert!(matches!(decode_volume(&bytes[..bytes.len() - 1]), Err(RadishError::InvalidFormat(_))));
+    assert!(matches!(decode_volume(b"not a cache"), Err(RadishError::InvalidFormat(_))));
+    let mut newer = bytes.clone();
+    newer[8] += 1;
+    assert!(matches!(decode_volume(&newer), Err(RadishError::Unsupported(_))));
+}
+
+/// Write a single-sweep CfRadial1 file with a moment of each storage type
+fn cfradial1_file(path: &std::path::Path) -> radish::Result<()> {
+    let (num_rays, num_gates) = (4, 3);
+    let mut file = netcdf::create(path)?;
+    file.add_dimension("time", num_rays)?;
+    file.add_dimension("range", num_gates)?;
+    file.add_dimension("sweep", 1)?;
+
+    file.add_variable::<f64>("time", &["time"])?.put_values(&[0.0, 1.0, 2.0, 3.0], ..)?;
+    file.add_variable::<f32>("range", &["range"])?.put_values(&[125.0, 375.0, 625.0], ..)?;
+    file.add_variable::<f32>("azimuth", &["time"])?.put_values(&[0.0, 90.0, 180.0, 270.0], ..)?;
+    file.add_variable::<f32>("elevation", &["time"])?.put_values(&[0.5; 4], ..)?;
+    file.add_variable::<i32>("sweep_number", &["sweep"])?.put_values(&[0], ..)?;
+    file.add_variable::<i32>("sweep_start_ray_index", &["sweep"])?.put_values(&[0], ..)?;
+    file.add_variable::<i32>("sweep_end_ray_index", &["sweep"])?.put_values(&[3], ..)?;
+    file.add_variable::<f32>("fixed_angle", &["sweep"])?.put_values(&[0.5], ..)?;
+    file.add_string_variable("sweep_mode", &["sweep"])?.put_string("azimuth_surveillance", 0)?;
+
+    let gates = num_rays * num_gates;
+    file.add_variable::<u8>("UBYTE", &["time", "range"])?.put_values(&vec![10u8; gates], ..)?;
+    file.add_variable::<i8>("BYTE", &["time", "range"])?.put_values(&vec![-10i8; gates], ..)?;
+    file.add_variable::<i16>("SHORT", &["time", "range"])?.put_values(&vec![-1000i16; gates], ..)?;
+    file.add_variable::<f32>("FLOAT", &["time", "range"])?.put_values(&vec![1.5f32; gates], ..)?;
+    file.add_variable::<f64>("DOUBLE", &["time", "range"])?.put_values(&vec![2.5f64; gates], ..)?;
+    file.add_variable::<i32>("INT", &["time", "range"])?.put_values(&vec![7i32; gates], ..)?;
+    Ok(())
+}
+
+#[test]
+fn test_cfradial1_storage_from_variable_type() {
+    use radish::backends::CfRadial1Backend;
+    use radish::model::MomentStorage;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("types.nc");
+    cfradial1_file(&path).unwrap();
+    let sweep = CfRadial1Backend::new().read_sweep(&path, 0).unwrap();
+
+    let storage = |name: &str| sweep.moments[name].as_packed();
+    assert!(matches!(storage("UBYTE"), MomentStorage::U8(_)));
+    assert!(matches!(storage("BYTE"), MomentStorage::I16(_)));
+    assert!(matches!(storage("SHORT"), MomentStorage::I16(_)));
+    assert!(matches!(storage("FLOAT"), MomentStorage::F32(_)));
+    assert!(matches!(storage("DOUBLE"), MomentStorage::F64(_)));
+    assert_eq!(sweep.moments["UBYTE"].valid_value(0, 0), Some(10.0));
+    assert_eq!(sweep.moments["BYTE"].valid_value(0, 0), Some(-10.0));
+    assert_eq!(sweep.moments["SHORT"].valid_value(1, 2), Some(-1000.0));
+    assert_eq!(sweep.moments["FLOAT"].valid_value(3, 0), Some(1.5));
+    assert_eq!(sweep.moments["DOUBLE"].valid_value(2, 1), Some(2.5));
+    // Other integer types have no storage variant and are not read
+    assert!(!sweep.moments.contains_key("INT"));
+}
@@ -0,0 +1,547 @@
+//! Basic tests for radish core library
+
+use radish::{
+    model::{VolumeMetadata, SweepMetadata, MomentData, Coordinates},
+    backends::{CfRadial1Backend, RadarBackend},
+};
+use chrono::Utc;
+use ndarray::Array2;
+use radish_types::SweepMode;
+
+#[test]
+fn test_volume_metadata_creation() {
+    let metadata = VolumeMetadata::new(
+        "TEST_RADAR".to_string(),
+        40.0,
+        -105.0,
+        1000.0,
+        Utc::now(),
+        Utc::now(),
+    );
+
+    assert_eq!(metadata.instrument_name, "TEST_RADAR");
+    assert_eq!(metadata.latitude, 40.0);
+    assert_eq!(metadata.longitude, -105.0);
+    assert_eq!(metadata.altitude, 1000.0);
+}
+
+#[test]
+fn test_sweep_metadata_creation() {
+    let metadata = SweepMetadata::new(0, SweepMode::Azimuth, 0.5);
+
+    assert_eq!(metadata.sweep_number, 0);
+    assert_eq!(metadata.sweep_mode, SweepMode::Azimuth);
+    assert_eq!(metadata.fixed_angle, 0.5);
+}
+
+#[test]
+fn test_volume_merge() {
+    use chrono::TimeZone;
+    use radish::{SweepData, VolumeData};
+    use std::collections::HashMap;
+
+    let t0 = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+    let volume = |offset: i64, angles: &[f64]| {
+        let start = t0 + chrono::Duration::seconds(offset);
+        let sweeps = angles
+            .iter()
+            .enumerate()
+            .map(|(k, &angle)| {
+                let time = (start.timestamp() + 20 * k as i64) as f64;
+                let coords = Coordinates::new(vec![time, time + 10.0], vec![0.0], vec![0.0, 1.0], vec![angle as f32; 2]);
+                SweepData::new(SweepMetadata::new(k as u32, SweepMode::Azimuth, angle), HashMap::new(), coords)
+            })
+            .collect();
+        let end = start + chrono::Duration::seconds(20 * angles.len() as i64);
+        let mut volume = VolumeData::new(VolumeMetadata::new("KTLX".to_string(), 35.33, -97.28, 370.0, start, end), sweeps);
+        volume.reindex_sweeps();
+        volume
+    };
+
+    let mut merged = volume(60, &[1.5, 2.4]);
+    merged.merge(volume(0, &[0.5, 0.9])).unwrap();
+    assert_eq!(merged.num_sweeps(), 4);
+    assert_eq!(merged.metadata.sweep_fixed_angles, vec![0.5, 0.9, 1.5, 2.4]);
+    assert_eq!(merged.metadata.sweep_group_names[3], "sweep_3");
+    assert_eq!(merged.sweeps[2].metadata.sweep_number, 2);
+    assert_eq!(merged.metadata.time_coverage_start, t0);
+    assert_eq!(merged.metadata.time_coverage_end, t0 + chrono::Duration::seconds(100));
+
+    let mut other = volume(0, &[0.5]);
+    other.metadata.latitude += 0.01;
+    assert!(merged.merge(other).is_err());
+    let mut other = volume(0, &[0.5]);
+    other.metadata.instrument_name = "KOUN".to_string();
+    assert!(merged.merge(other).is_err());
+    assert!(merged.merge(volume(3600, &[0.5])).is_err());
+    assert_eq!(merged.num_sweeps(), 4);
+}
+
+#[test]
+fn test_volume_subset() {
+    use radish::{SweepData, VolumeData};
+    use std::collections::HashMap;
+
+    let sweeps = [0.5, 1.5, 2.5]
+        .iter()
+        .enumerate()
+        .map(|(k, &angle)| {
+            let azimuth: Vec<f32> = (0..36).map(|i| i as f32 * 10.0).collect();
+            let time: Vec<f64> = (0..36).map(|i| (100 * k + i) as f64).collect();
+            let range: Vec<f32> = (0..100).map(|j| 500.0 + j as f32 * 1000.0).collect();
+            let coords = Coordinates::new(time, range, azimuth, vec![angle as f32; 36]);
+            let mut moments = HashMap::new();
+            for name in ["DBZH", "VRADH"] {
+                let data = Array2::from_shape_fn((36, 100), |(i, j)| (i * 100 + j) as f32);
+                moments.insert(name.to_string(), MomentData::new(name.to_string(), "".to_string(), data));
+            }
+            SweepData::new(SweepMetadata::new(k as u32, SweepMode::Azimuth, angle), moments, coords)
+        })
+        .collect();
+    let mut volume = VolumeData::new(
+        VolumeMetadata::new("TEST".to_string(), 40.0, -105.0, 1600.0, Utc::now(), Utc::now()),
+        sweeps,
+    );
+    volume.reindex_sweeps();
+
+    let subset = volume
+        .subset()
+        .elevations(1.0, 3.0)
+        .range_km(10.0, 20.0)
+        .azimuth_sector(350.0, 20.0)
+        .moments(&["DBZH"])
+        .build()
+        .unwrap();
+    assert_eq!(subset.num_sweeps(), 2);
+    assert_eq!(subset.metadata.sweep_fixed_angles, vec![1.5, 2.5]);
+    let sweep = &subset.sweeps[0];
+    assert_eq!(sweep.metadata.sweep_number, 0);
+    assert_eq!(sweep.coordinates.azimuth, vec![0.0, 10.0, 20.0, 350.0]);
+    assert_eq!(sweep.coordinates.range.first(), Some(&10_500.0));
+    assert_eq!(sweep.coordinates.range.last(), Some(&19_500.0));
+    assert_eq!(sweep.moment_names(), vec!["DBZH"]);
+    assert_eq!(sweep.moments["DBZH"].shape(), (4, 10));
+    assert_eq!(sweep.moments["DBZH"].value(3, 0), 3510.0);
+    assert_eq!(subset.metadata.time_coverage_start.timestamp(), 100);
+    assert_eq!(subset.metadata.time_coverage_end.timestamp(), 235);
+
+    let subset = volume.subset().sweeps(&[2]).build().unwrap();
+    assert_eq!(subset.num_sweeps(), 1);
+    assert_eq!(subset.sweeps[0].metadata.fixed_angle, 2.5);
+    assert_eq!(subset.sweeps[0].moments.len(), 2);
+    assert!(volume.subset().sweeps(&[3]).build().is_err());
+    assert_eq!(volume.subset().range_km(500.0, 600.0).build().unwrap().num_sweeps(), 0);
+}
+
+#[test]
+fn test_synthetic_volume_builder() {
+    use radish::model::{Pattern, SweepBuilder, VolumeBuilder};
+
+    let volume = VolumeBuilder::new()
+        .elevations(&[0.5, 1.5, 2.5])
+        .sweep(SweepBuilder::new(0.0).rays(360, 1.0, 0.5).gates(200, 500.0, 250.0).nyquist_velocity(10.0))
+        .moment("DBZH", "dBZ", Pattern::Cell { x: 0.0, y: 50_000.0, peak: 60.0, radius: 5_000.0, background: 0.0 })
+        .moment("VRADH", "m/s", Pattern::UniformWind { speed: 15.0, direction: 180.0 })
+        .moment("RANGE_KM", "km", Pattern::function(|_, _, r| r / 1000.0))
+        .build();
+
+    assert_eq!(volume.num_sweeps(), 3);
+    assert_eq!(volume.metadata.sweep_fixed_angles, vec![0.5, 1.5, 2.5]);
+    assert_eq!((volume.metadata.time_coverage_end - volume.metadata.time_coverage_start).num_seconds(), 36);
+    let sweep = &volume.sweeps[1];
+    assert_eq!((sweep.num_rays(), sweep.num_gates()), (360, 200));
+    assert!(sweep.coordinates.validate().is_ok());
+    assert_eq!(sweep.time_start(), Some(volume.metadata.time_coverage_start + chrono::Duration::seconds(12)));
+    assert_eq!(sweep.metadata.nyquist_velocity, Some(10.0));
+
+    // Peak of the cell due north at 50 km
+    let dbz = &volume.sweeps[0].moments["DBZH"];
+    assert!(dbz.value(0, 99) > 59.0 && dbz.value(180, 99) < 1.0);
+    assert_eq!(dbz.standard_name.as_deref(), Some("equivalent_reflectivity_factor"));
+    // A 15 m/s southerly folds to -5 m/s looking north and 5 m/s looking south
+    let vel = &volume.sweeps[0].moments["VRADH"];
+    assert!((vel.value(0, 0) + 5.0).abs() < 0.01);
+    assert!((vel.value(180, 0) - 5.0).abs() < 0.01);
+    assert_eq!(volume.sweeps[0].moments["RANGE_KM"].value(3, 1), 0.75);
+
+    let rhi = SweepBuilder::new(90.0).sweep_mode(SweepMode::Elevation).rays(90, 1.0, 0.0).build();
+    assert_eq!(rhi.coordinates.azimuth[10], 90.0);
+    assert_eq!(rhi.coordinates.elevation[10], 10.0);
+}
+
+#[test]
+fn test_nearest_ray_gate_and_value_at() {
+    use radish::model::{Pattern, SweepBuilder, VolumeBuilder};
+    use radish::transforms::georeference::cartesian_to_geographic;
+
+    let volume = VolumeBuilder::new()
+        .location(35.0, -97.0, 300.0)
+        .elevations(&[0.5, 4.5])
+        .sweep(SweepBuilder::new(0.0).gates(200, 500.0, 250.0))
+        .moment("ELEV", "degrees", Pattern::function(|_, el, _| el))
+        .moment("RANGE_KM", "km", Pattern::function(|_, _, r| r / 1000.0))
+        .build();
+
+    let sweep = &volume.sweeps[0];
+    assert_eq!(sweep.nearest_ray(359.9), Some(359));
+    assert_eq!(sweep.nearest_ray(-179.0), Some(180));
+    assert_eq!(sweep.nearest_gate(800.0), Some(1));
+    assert_eq!(sweep.nearest_gate(100_100.0), None);
+
+    // 30 km east of the radar, near the ground and about 2.4 km up
+    let (lon, lat) = cartesian_to_geographic(30_000.0, 0.0, -97.0, 35.0);
+    assert_eq!(volume.value_at(lat, lon, 350.0, "ELEV"), Some(0.5));
+    assert_eq!(volume.value_at(lat, lon, 2_700.0, "ELEV"), Some(4.5));
+    let range = volume.value_at(lat, lon, 350.0, "RANGE_KM").unwrap();
+    assert!((range - 30.0).abs() <= 0.25);
+    assert_eq!(volume.value_at(lat, lon, 350.0, "DBZH"), None);
+    let (far_lon, far_lat) = cartesian_to_geographic(150_000.0, 0.0, -97.0, 35.0);
+    assert_eq!(volume.value_at(far_lat, far_lon, 350.0, "ELEV"), None);
+}
+
+#[test]
+fn test_memory_usage() {
+    use radish::model::{Pattern, SweepBuilder, VolumeBuilder};
+
+    let mut volume = VolumeBuilder::new()
+        .elevations(&[0.5, 1.5])
+        .sweep(SweepBuilder::new(0.0).rays(360, 1.0, 0.5).gates(100, 250.0, 125.0))
+        .moment("DBZH", "dBZ", Pattern::Constant(20.0))
+        .moment("VRADH", "m/s", Pattern::Constant(5.0))
+        .build();
+    let mut packed = MomentData::from_storage(
+        "DBZH".to_string(),
+        "dBZ".to_string(),
+        radish::MomentStorage::U8(Array2::from_elem((360, 100), 104)),
+    );
+    packed.scale_factor = Some(0.5);
+    packed.add_offset = Some(-32.0);
+    volume.sweeps[0].moments.insert("DBZH".to_string(), packed);
+
+    let report = volume.memory_usage();
+    assert_eq!(report.sweeps.len(), 2);
+    let sweep = &report.sweeps[0];
+    assert_eq!(sweep.coordinate_bytes, 360 * 8 + 100 * 4 + 2 * 360 * 4);
+    let dbz = &sweep.moments[0];
+    assert_eq!((dbz.name.as_str(), dbz.packed), ("DBZH", true));
+    assert_eq!((dbz.stored_bytes, dbz.decoded_bytes), (36_000, 144_000));
+    assert_eq!(sweep.moments[1].stored_bytes, 144_000);
+    assert_eq!(report.decoded_bytes() - report.total_bytes(), 108_000);
+    assert_eq!(report.moment_totals()[0], ("VRADH".to_string(), 288_000));
+    assert!(report.to_string().contains("DBZH: 35.2 KiB stored (packed)"));
+}
+
+#[test]
+fn test_scan_strategy_check() {
+    use radish::model::{Attributes, ScanStrategy, VolumeBuilder};
+
+    let vcp = ScanStrategy::nexrad_vcp(32).unwrap();
+    assert_eq!(vcp.name, "VCP 32");
+    assert_eq!(vcp.num_sweeps(), 7);
+    assert!(ScanStrategy::nexrad_vcp(999).is_none());
+
+    let mut attributes = Attributes::new();
+    attributes.insert("scan_name".to_string(), "vcp-212".into());
+    assert_eq!(ScanStrategy::from_attributes(&attributes).unwrap().name, "VCP 212");
+    attributes.insert("vcp".to_string(), 32i64.into());
+    assert_eq!(ScanStrategy::from_attributes(&attributes), Some(vcp.clone()));
+
+    // The Doppler half of the 1.5° split cut is missing; a SAILS cut is added
+    let mut volume = VolumeBuilder::new().elevations(&[0.5, 0.5, 1.5, 2.5, 0.5, 3.5, 4.5]).build();
+    assert!(volume.check_scan_strategy().is_none());
+    volume.metadata.scan_strategy = Some(vcp);
+    let check = volume.check_scan_strategy().unwrap();
+    assert!(!check.is_complete());
+    assert_eq!(check.missing, vec![3]);
+    assert_eq!(check.unexpected, vec![4]);
+
+    volume.sweeps.remove(4);
+    volume.sweeps[3].metadata.fixed_angle = 1.5;
+    let check = volume.check_scan_strategy().unwrap();
+    assert_eq!(check.missing, vec![4]);
+    assert!(check.unexpected.is_empty());
+}
+
+#[test]
+fn test_moment_copy_on_write() {
+    use radish::model::{Pattern, VolumeBuilder};
+
+    let volume = VolumeBuilder::new()
+        .elevations(&[0.5])
+        .moment("DBZH", "dBZ", Pattern::Constant(20.0))
+        .build();
+    let copy = volume.clone();
+    let (original, shared) = (&volume.sweeps[0].moments["DBZH"], &copy.sweeps[0].moments["DBZH"]);
+    assert!(shared.shares_data_with(original));
+
+    // Writing to a clone copies its values and leaves the original alone
+    let mut modified = shared.clone();
+    modified.data_mut()[[0, 0]] = -10.0;
+    assert!(!modified.shares_data_with(original));
+    assert_eq!(original.data()[[0, 0]], 20.0);
+    assert_eq!(modified.data()[[0, 0]], -10.0);
+
+    // Masks are shared the same way
+    let mut masked = original.clone();
+    masked.set_mask(Some(Array2::from_elem(masked.shape(), true))).unwrap();
+    let mut excluded = masked.clone();
+    excluded.exclude(&Array2::from_elem(masked.shape(), true)).unwrap();
+    assert!(masked.is_valid(0, 0));
+    assert!(!excluded.is_valid(0, 0));
+    assert!(excluded.shares_data_with(original));
+
+    // Values move out of unshared moments and are copied out of shared ones
+    assert_eq!(modified.into_data()[[0, 0]], -10.0);
+    assert_eq!(shared.clone().into_data(), *original.data());
+}
+
+#[test]
+fn test_moment_data_creation() {
+    let data = Array2::zeros((360, 1000));
+    let moment = MomentData::new("DBZH".to_string(), "dBZ".to_string(), data);
+
+    assert_eq!(moment.name, "DBZH");
+    assert_eq!(moment.units, "dBZ");
+    assert_eq!(moment.shape(), (360, 1000));
+}
+
+#[test]
+fn test_packed_moment_storage() {
+    use radish::MomentStorage;
+
+    let raw = Array2::from_shape_vec((2, 3), vec![0u8, 2, 64, 255, 100, 1]).unwrap();
+    let mut moment = MomentData::from_storage("DBZH".to_string(), "dBZ".to_string(), MomentStorage::U8(raw.clone()));
+    moment.scale_factor = Some(0.5);
+    moment.add_offset = Some(-32.0);
+    moment.fill_value = Some(0.0);
+
+    assert!(moment.is_packed());
+    assert_eq!(moment.shape(), (2, 3));
+    assert_eq!(moment.value(0, 2), 0.0);
+    assert_eq!(moment.value(1, 0), 95.5);
+    let data = moment.data();
+    assert_eq!(data[[0, 1]], -31.0);
+    // Fill gates decode to NaN, so a 0 dBZ gate is not taken for a fill gate
+    assert!(data[[0, 0]].is_nan());
+    assert!(moment.is_valid_value(data[[0, 2]]));
+    assert_eq!(moment.missing_value().to_bits(), f32::NAN.to_bits());
+    assert_eq!(moment.as_packed(), &MomentStorage::U8(raw));
+
+    let mut unpacked = moment.clone();
+    unpacked.data_mut()[[1, 2]] = 10.0;
+    assert!(!unpacked.is_packed());
+    assert_eq!(unpacked.scale_factor, None);
+    assert_eq!(unpacked.value(0, 1), -31.0);
+    assert_eq!(unpacked.value(1, 2), 10.0);
+
+    let derived = moment.with_data(Array2::zeros((2, 3)));
+    assert_eq!(derived.fill_value, None);
+    assert!(derived.is_valid_value(derived.value(1, 1)));
+}
+
+#[test]
+fn test_moment_validity_mask() {
+    let data = Array2::from_shape_vec((2, 3), vec![1.0, -9999.0, 3.0, f32::NAN, 5.0, 60.0]).unwrap();
+    let mut moment = MomentData::new("DBZH".to_string(), "dBZ".to_string(), data);
+    moment.fill_value = Some(-9999.0);
+    moment.valid_max = Some(50.0);
+    assert!(moment.mask().is_none());
+    assert_eq!(moment.count_valid(), 3);
+
+    moment.update_mask();
+    assert_eq!(moment.mask().unwrap().iter().filter(|&&m| m).count(), 3);
+    let mut excluded = Array2::from_elem((2, 3), false);
+    excluded[[0, 2]] = true;
+    moment.exclude(&excluded).unwrap();
+    assert!(moment.exclude(&Array2::from_elem((3, 3), false)).is_err());
+
+    // Excluded gates keep their values but are skipped
+    assert_eq!(moment.value(0, 2), 3.0);
+    assert_eq!(moment.valid_value(0, 2), None);
+    assert_eq!(moment.valid_values().collect::<Vec<_>>(), vec![1.0, 5.0]);
+    assert_eq!(moment.indexed_valid_values().last(), Some(((1, 1), 5.0)));
+    assert_eq!(moment.min_valid(), Some(1.0));
+    assert_eq!(moment.max_valid(), Some(5.0));
+    assert_eq!(moment.mean_valid(), Some(3.0));
+
+    moment.mask_invalid(-1.0);
+    assert_eq!(moment.data().row(0).to_vec(), vec![1.0, -1.0, -1.0]);
+    moment.set_data(Array2::zeros((2, 3)));
+    assert!(moment.mask().is_none());
+    assert!(moment.set_mask(Some(Array2::from_elem((2, 2), true))).is_err());
+}
+
+#[test]
+fn test_ragged_sweep() {
+    let rays = vec![vec![1.0, 2.0, 3.0], vec![4.0], vec![5.0, 6.0]];
+    let moment = MomentData::from_ragged_rays("DBZH".to_string(), "dBZ".to_string(), &rays);
+    assert_eq!(moment.shape(), (3, 3));
+    assert!(moment.is_valid(0, 2));
+    assert!(!moment.is_valid(1, 1));
+    assert!(moment.value(1, 1).is_nan());
+    assert_eq!(moment.valid_value(2, 1), Some(6.0));
+    assert_eq!(moment.count_valid(), 6);
+    assert_eq!(moment.ragged_rays(&[3, 1, 2]).unwrap(), rays);
+    assert!(moment.ragged_rays(&[3, 1]).is_err());
+
+    let mut coords = Coordinates::new(
+        vec![0.0, 1.0, 2.0],
+        vec![100.0, 200.0, 300.0],
+        vec![0.0, 1.0, 2.0],
+        vec![0.5; 3],
+    );
+    assert!(!coords.is_ragged());
+    assert_eq!(coords.ray_gates(1), 3);
+    coords.n_gates_per_ray = Some(rays.iter().map(Vec::len).collect());
+    assert!(coords.validate().is_ok());
+    assert!(coords.is_ragged());
+    assert_eq!(coords.ray_gates(1), 1);
+    assert_eq!(coords.recorded_gates(), moment.validity());
+
+    coords.n_gates_per_ray = Some(vec![3, 4, 2]);
+    assert!(coords.validate().is_err());
+    coords.n_gates_per_ray = Some(vec![3, 1]);
+    assert!(coords.validate().is_err());
+}
+
+#[test]
+fn test_convert_units() {
+    use radish_types::{Unit, UnitQuantity};
+
+    assert_eq!(Unit::from_symbol("m s-1"), Some(Unit::MetersPerSecond));
+    assert_eq!(Unit::Knots.quantity(), UnitQuantity::Speed);
+    assert!((Unit::Kilometers.convert(1.5, Unit::Meters).unwrap() - 1500.0).abs() < 1e-9);
+    assert!((Unit::InchesPerHour.convert(1.0, Unit::MillimetersPerHour).unwrap() - 25.4).abs() < 1e-9);
+    assert!(Unit::Dbz.convert(10.0, Unit::Knots).is_none());
+
+    let data = Array2::from_shape_vec((1, 3), vec![10.0, -9999.0, 20.0]).unwrap();
+    let mut velocity = MomentData::new("VRADH".to_string(), "m/s".to_string(), data);
+    velocity.fill_value = Some(-9999.0);
+    velocity.valid_max = Some(50.0);
+    velocity.convert_units(Unit::Knots).unwrap();
+    assert_eq!(velocity.units, "kt");
+    assert!((velocity.value(0, 0) - 19.438).abs() < 1e-3);
+    assert_eq!(velocity.value(0, 1), -9999.0);
+    assert!((velocity.valid_max.unwrap() - 97.19).abs() < 1e-2);
+    velocity.convert_units(Unit::MetersPerSecond).unwrap();
+    assert!((velocity.value(0, 2) - 20.0).abs() < 1e-4);
+    assert!(velocity.convert_units(Unit::Dbz).is_err());
+
+    let data = Array2::from_shape_vec((1, 2), vec![20.0, f32::NAN]).unwrap();
+    let mut dbz = MomentData::new("DBZH".to_string(), "dBZ".to_string(), data);
+    dbz.convert_units(Unit::ReflectivityLinear).unwrap();
+    assert!((dbz.value(0, 0) - 100.0).abs() < 1e-3);
+    assert!(dbz.value(0, 1).is_nan());
+    assert_eq!(dbz.unit(), Some(Unit::ReflectivityLinear));
+
+    let mut unknown = MomentData::new("X".to_string(), "furlongs".to_string(), Array2::zeros((1, 1)));
+    assert!(unknown.convert_units(Unit::Meters).is_err());
+}
+
+#[test]
+fn test_moment_arithmetic() {
+    let mut dbzh = MomentData::new("DBZH".to_string(), "dBZ".to_string(), Array2::from_elem((2, 3), 30.0));
+    dbzh.fill_value = Some(-9999.0);
+    dbzh.valid_max = Some(40.0);
+    dbzh.data_mut()[[0, 0]] = -9999.0;
+    let mut dbzv = MomentData::new("DBZV".to_string(), "dBZ".to_string(), Array2::from_elem((2, 3), 28.0));
+    dbzv.data_mut()[[1, 2]] = f32::NAN;
+
+    let zdr = (&dbzh - &dbzv).unwrap();
+    assert_eq!(zdr.value(0, 1), 2.0);
+    assert_eq!(zdr.value(0, 0), -9999.0);
+    assert_eq!(zdr.value(1, 2), -9999.0);
+    assert_eq!(zdr.count_valid(), 4);
+
+    // Scalars apply to valid gates only, and the valid range no longer holds
+    let corrected = &dbzh + 15.0;
+    assert_eq!(corrected.value(1, 1), 45.0);
+    assert!(corrected.is_valid(1, 1));
+    assert_eq!(corrected.value(0, 0), -9999.0);
+    assert_eq!((corrected.name.as_str(), corrected.units.as_str()), ("DBZH", "dBZ"));
+    assert_eq!((dbzv * 2.0 / 4.0).value(0, 0), 14.0);
+
+    let other = MomentData::new("X".to_string(), "1".to_string(), Array2::zeros((3, 3)));
+    assert!((&dbzh * &other).is_err());
+}
+
+#[test]
+fn test_standardize_names() {
+    use radish::model::{NameAliases, ORIGINAL_NAME_ATTRIBUTE};
+    use radish::SweepData;
+    use std::collections::HashMap;
+
+    let moment = |name: &str| MomentData::new(name.to_string(), "".to_string(), Array2::zeros((1, 1)));
+    let mut moments = HashMap::new();
+    for name in ["REF", "corrected_velocity", "VEL", "ZDR", "FOO"] {
+        moments.insert(name.to_string(), moment(name));
+    }
+    let coords = Coordinates::new(vec![0.0], vec![0.0], vec![0.0], vec![0.5]);
+    let mut sweep = SweepData::new(SweepMetadata::new(0, SweepMode::Azimuth, 0.5), moments, coords);
+
+    let aliases = NameAliases::default().with_alias("FOO", "SQI");
+    assert_eq!(aliases.standard_name("ref"), Some("DBZH"));
+    sweep.standardize_names(&aliases);
+
+    let dbzh = sweep.get_moment("DBZH").unwrap();
+    assert_eq!(dbzh.name, "DBZH");
+    assert_eq!(dbzh.attributes[ORIGINAL_NAME_ATTRIBUTE], "REF");
+    assert_eq!(dbzh.standard_name.as_deref(), Some("equivalent_reflectivity_factor"));
+    // Only the first of two aliases of VRADH is renamed
+    assert_eq!(sweep.get_moment("VRADH").unwrap().attributes[ORIGINAL_NAME_ATTRIBUTE], "VEL");
+    assert!(sweep.get_moment("corrected_velocity").is_some());
+    assert!(!sweep.get_moment("ZDR").unwrap().attributes.contains_key(ORIGINAL_NAME_ATTRIBUTE));
+    assert_eq!(sweep.get_moment("SQI").unwrap().attributes[ORIGINAL_NAME_ATTRIBUTE], "FOO");
+    assert_eq!(sweep.moments.len(), 5);
+}
+
+#[test]
+fn test_coordinates_validation() {
+    let coords = Coordinates::new(
+        vec![0.0; 360],
+        vec![0.0; 1000],
+        vec![0.0; 360],
+        vec![0.0; 360],
+    );
+
+    assert!(coords.validate().is_ok());
+    assert_eq!(coords.num_rays(), 360);
+    assert_eq!(coords.num_gates(), 1000);
+}
+
+#[test]
+fn test_coordinates_validation_fails() {
+    let coords = Coordinates::new(
+        vec![0.0; 360],
+        vec![0.0; 1000],
+        vec![0.0; 350],  // Wrong size
+        vec![0.0; 360],
+    );
+
+    assert!(coords.validate().is_err());
+}
+
+#[test]
+fn test_sweep_time_coverage() {
+    use radish::model::SweepData;
+    use std::collections::HashMap;
+
+    let coords = Coordinates::new(
+        vec![1000.5, f64::NAN, 1010.5, 1005.0],
+        vec![0.0; 10],
+        vec![0.0; 4],
+        vec![0.0; 4],
+    );
+    let sweep = SweepData::new(SweepMetadata::new(0, SweepMode::Azimuth, 0.5), HashMap::new(), coords);
+
+    assert_eq!(sweep.time_start().unwrap().timestamp_millis(), 1_000_500);
+    assert_eq!(sweep.time_end().unwrap().timestamp_millis(), 1_010_500);
+    assert_eq!(sweep.time_mid().unwrap().timestamp_millis(), 1_005_500);
+}
+
+#[test]
+fn test_backend_name() {
+    let backend = CfRadial1Backend::new();
+    assert_eq!(backend.name(), "cfradial1");
+    assert!(backend.supported_extensions().contains(&"nc"));
+}
@@ -0,0 +1,121 @@
+//! Tests for approximate comparison utilities
+
+use ndarray::Array2;
+use radish::compare::{first_mismatch, Tolerance, ToleranceSpec};
+use radish::MomentData;
+
+#[test]
+fn test_tolerance_spec_parsing() {
+    let spec: ToleranceSpec = "*=1e-6, DBZH=0.5, ZDR=0.01:0.001".parse().unwrap();
+    assert_eq!(spec.default, Tolerance::absolute(1e-6));
+    assert_eq!(spec.for_moment("DBZH"), Tolerance::absolute(0.5));
+    assert_eq!(spec.for_moment("ZDR"), Tolerance { absolute: 0.01, relative: 0.001 });
+    assert_eq!(spec.for_moment("VRADH"), spec.default);
+
+    assert!("DBZH".parse::<ToleranceSpec>().is_err());
+    assert!("DBZH=-1".parse::<ToleranceSpec>().is_err());
+}
+
+#[test]
+fn test_int16_packing_round_trip() {
+    let scale = 0.01f32;
+    let data = Array2::from_shape_fn((3, 5), |(i, j)| (i * 5 + j) as f32 * 1.2345 - 10.0);
+    let mut original = MomentData::new("DBZH".to_string(), "dBZ".to_string(), data);
+    original.data_mut()[[1, 1]] = f32::NAN;
+
+    // Pack to int16 and back, as a writer/reader pair would
+    let mut unpacked = original.clone();
+    unpacked.data_mut().mapv_inplace(|v| {
+        if v.is_nan() {
+            f32::NAN
+        } else {
+            ((v / scale).round() as i16) as f32 * scale
+        }
+    });
+
+    let exact = first_mismatch(&unpacked, &original, &Tolerance::exact()).unwrap();
+    assert!(exact.is_some());
+
+    let packed = Tolerance::from_packing(scale);
+    assert_eq!(first_mismatch(&unpacked, &original, &packed).unwrap(), None);
+
+    unpacked.data_mut()[[2, 4]] += scale;
+    assert_eq!(first_mismatch(&unpacked, &original, &packed).unwrap(), Some((2, 4)));
+}
+
+#[test]
+fn test_first_mismatch_uses_mask() {
+    let data = Array2::from_elem((2, 3), 5.0f32);
+    let expected = MomentData::new("DBZH".to_string(), "dBZ".to_string(), data.clone());
+    let mut actual = MomentData::new("DBZH".to_string(), "dBZ".to_string(), data);
+
+    let mut mask = Array2::from_elem((2, 3), true);
+    mask[[1, 2]] = false;
+    actual.set_mask(Some(mask)).unwrap();
+    assert_eq!(first_mismatch(&actual, &expected, &Tolerance::exact()).unwrap(), Some((1, 2)));
+
+    let mut masked = expected.clone();
+    masked.set_mask(actual.mask().cloned()).unwrap();
+    masked.data_mut()[[1, 2]] = -999.0;
+    assert_eq!(first_mismatch(&actual, &masked, &Tolerance::exact()).unwrap(), None);
+}
+
+#[test]
+fn test_diff_volumes() {
+    use radish::compare::diff_volumes;
+    use radish::model::{Pattern, SweepBuilder, VolumeBuilder};
+
+    let builder = VolumeBuilder::new()
+        .elevations(&[0.5, 1.5])
+        .sweep(SweepBuilder::new(0.0).rays(36, 10.0, 0.0).gates(20, 1000.0, 500.0))
+        .moment("DBZH", "dBZ", Pattern::RangeRamp { offset: 10.0, slope: 1.0 })
+        .moment("VRADH", "m/s", Pattern::UniformWind { speed: 10.0, direction: 270.0 });
+    let expected = builder.build();
+    let spec = ToleranceSpec::new(Tolerance::absolute(0.01));
+
+    let report = diff_volumes(&expected, &expected, &spec);
+    assert!(report.is_close(), "{}", report);
+    assert_eq!(report.moments.len(), 4);
+
+    let mut actual = expected.clone();
+    actual.metadata.instrument_name = "OTHER".to_string();
+    actual.sweeps[1].coordinates.azimuth[3] += 0.5;
+    actual.sweeps[0].moments.remove("VRADH");
+    let dbz = actual.sweeps[1].moments.get_mut("DBZH").unwrap();
+    dbz.data_mut()[[2, 2]] += 1.0;
+    dbz.data_mut()[[2, 3]] = f32::NAN;
+
+    let report = diff_volumes(&actual, &expected, &spec);
+    assert!(!report.is_close());
+    let fields: Vec<&str> = report.fields.iter().map(|f| f.field.as_str()).collect();
+    assert_eq!(fields, vec!["instrument_name", "sweep_1.azimuth"]);
+    assert_eq!(report.missing_moments.len(), 1);
+    assert!(report.missing_moments[0].missing_from_actual);
+    let dbz = report.moments.iter().find(|m| m.sweep == 1 && m.name == "DBZH").unwrap();
+    assert_eq!((dbz.exceeding, dbz.validity_mismatches, dbz.compared), (1, 1, 36 * 20 - 1));
+    assert!((dbz.max_abs_diff - 1.0).abs() < 1e-5);
+    assert!(report.to_string().contains("sweep_1.DBZH: 1 of 719 gates beyond tolerance"));
+}
+
+#[test]
+fn test_diff_volumes_azimuth_across_north() {
+    use radish::compare::diff_volumes;
+    use radish::model::{Pattern, SweepBuilder, VolumeBuilder};
+
+    let expected = VolumeBuilder::new()
+        .sweep(SweepBuilder::new(0.5).rays(36, 10.0, 0.0).gates(10, 1000.0, 500.0))
+        .moment("DBZH", "dBZ", Pattern::Constant(20.0))
+        .build();
+    let spec = ToleranceSpec::new(Tolerance::absolute(0.01));
+
+    // The first ray is at north: 359.9995° and 0° are the same direction
+    let mut actual = expected.clone();
+    actual.sweeps[0].coordinates.azimuth[0] = 359.9995;
+    let report = diff_volumes(&actual, &expected, &spec);
+    assert!(report.is_close(), "{}", report);
+
+    actual.sweeps[0].coordinates.azimuth[0] = 359.5;
+    let report = diff_volumes(&actual, &expected, &spec);
+    let fields: Vec<&str> = report.fields.iter().map(|f| f.field.as_str()).collect();
+    assert_eq!(fields, vec!["sweep_0.azimuth"]);
+}
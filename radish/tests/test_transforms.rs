@@ -0,0 +1,2104 @@
+//! Tests for radar data transforms
+
+use radish::transforms::georeference::{antenna_to_cartesian, GeoreferenceOptions};
+use radish::Coordinates;
+
+#[test]
+fn test_antenna_to_cartesian() {
+    let options = GeoreferenceOptions::default();
+
+    // Due north at zero elevation: beam rises roughly r² / (2 Re)
+    let (x, y, z) = antenna_to_cartesian(100_000.0, 0.0, 0.0, &options);
+    assert!(x.abs() < 1e-6);
+    assert!((y - 100_000.0).abs() < 10.0);
+    assert!((z - 588.6).abs() < 1.0);
+
+    // Due east
+    let (x, y, _) = antenna_to_cartesian(50_000.0, 90.0, 0.5, &options);
+    assert!(x > 49_900.0);
+    assert!(y.abs() < 1e-6);
+}
+
+#[test]
+fn test_lazy_gate_xyz() {
+    let mut coords = Coordinates::new(
+        vec![0.0; 4],
+        vec![0.0, 1000.0, 2000.0],
+        vec![0.0, 90.0, 180.0, 270.0],
+        vec![1.0; 4],
+    );
+
+    assert!(!coords.is_georeferenced());
+    let (x, y, z) = coords.gate_xyz();
+    assert_eq!(x.shape(), &[4, 3]);
+    assert!(y[[2, 2]] < -1990.0);
+    assert!(z[[0, 2]] > 30.0);
+    assert!(coords.is_georeferenced());
+}
+
+#[test]
+fn test_cached_gate_geographic() {
+    let mut coords = Coordinates::new(
+        vec![0.0; 4],
+        vec![0.0, 1000.0, 2000.0],
+        vec![0.0, 90.0, 180.0, 270.0],
+        vec![1.0; 4],
+    );
+
+    assert!(!coords.is_geolocated());
+    let (lon, lat, alt) = coords.gate_geographic(-97.46, 35.24, 370.0);
+    assert_eq!(lon.shape(), &[4, 3]);
+    assert!((lon[[0, 0]] + 97.46).abs() < 1e-9 && (lat[[0, 0]] - 35.24).abs() < 1e-9);
+    assert!(lat[[0, 2]] > 35.24 && lat[[2, 2]] < 35.24);
+    assert!(lon[[1, 2]] > -97.46);
+    assert!(alt[[0, 2]] > 400.0);
+    assert!(coords.is_georeferenced() && coords.is_geolocated());
+
+    coords.clear_georeference();
+    assert!(!coords.is_georeferenced() && !coords.is_geolocated());
+}
+
+#[test]
+fn test_projection_round_trips() {
+    use radish::transforms::georeference::Projection;
+
+    let (lon, lat) = (-97.46, 35.24);
+    for projection in [
+        Projection::aeqd(-97.0, 35.0),
+        Projection::utm_for(lon, lat),
+        Projection::WebMercator,
+        Projection::Laea { lon_0: 10.0, lat_0: 52.0, x_0: 0.0, y_0: 0.0 },
+    ] {
+        let (x, y) = projection.forward(lon, lat);
+        let (lon2, lat2) = projection.inverse(x, y);
+        assert!((lon - lon2).abs() < 1e-6, "{:?}", projection);
+        assert!((lat - lat2).abs() < 1e-6, "{:?}", projection);
+    }
+
+    // Known UTM coordinate: zone 14N, roughly 640 km easting near Norman, OK
+    let (x, y) = Projection::utm_for(lon, lat).forward(lon, lat);
+    assert!((x - 640_200.0).abs() < 1_000.0);
+    assert!((y - 3_900_900.0).abs() < 2_000.0);
+}
+
+#[test]
+fn test_projection_from_proj4() {
+    use radish::transforms::georeference::Projection;
+
+    // ETRS89-LAEA (EPSG:3035), as used in OPERA composites
+    let laea = Projection::from_proj4(
+        "+proj=laea +lat_0=52 +lon_0=10 +x_0=4321000 +y_0=3210000 +ellps=GRS80 +units=m +no_defs",
+    )
+    .unwrap();
+    // EPSG guidance note 7-2 example: 50°N 5°E
+    let (x, y) = laea.forward(5.0, 50.0);
+    assert!((x - 3_962_799.45).abs() < 0.1, "{}", x);
+    assert!((y - 2_999_718.85).abs() < 0.1, "{}", y);
+
+    assert_eq!(
+        Projection::from_proj4("+proj=utm +zone=33 +south").unwrap(),
+        Projection::Utm { zone: 33, north: false }
+    );
+    assert_eq!(Projection::from_proj4("+proj=longlat +ellps=WGS84").unwrap(), Projection::Geographic);
+    assert!(Projection::from_proj4("+proj=stere +lat_0=90").is_err());
+}
+
+#[test]
+fn test_rfi_spike_detection() {
+    use ndarray::Array2;
+    use radish::transforms::rfi::{remove_rfi, RfiOptions};
+    use radish::{MomentData, SweepData, SweepMetadata, VolumeData, VolumeMetadata};
+    use radish_types::SweepMode;
+    use std::collections::HashMap;
+
+    let range: Vec<f32> = (0..200).map(|j| 250.0 + 500.0 * j as f32).collect();
+    let azimuth: Vec<f32> = (0..360).map(|i| i as f32).collect();
+
+    let sweeps = (0..2)
+        .map(|s| {
+            // Speckle noise everywhere, a noise-like spoke at 45°
+            let data = Array2::from_shape_fn((360, 200), |(i, j)| {
+                if i == 45 {
+                    -20.0 + 20.0 * (range[j] / 1000.0).log10()
+                } else if (i + j) % 3 == 0 {
+                    10.0
+                } else {
+                    -9999.0
+                }
+            });
+            let mut moment = MomentData::new("DBZH".to_string(), "dBZ".to_string(), data);
+            moment.fill_value = Some(-9999.0);
+
+            let mut moments = HashMap::new();
+            moments.insert("DBZH".to_string(), moment);
+            let coords = radish::Coordinates::new(vec![0.0; 360], range.clone(), azimuth.clone(), vec![0.5; 360]);
+            SweepData::new(SweepMetadata::new(s, SweepMode::Azimuth, 0.5), moments, coords)
+        })
+        .collect();
+
+    let metadata = VolumeMetadata::new("TEST".to_string(), 0.0, 0.0, 0.0, chrono::Utc::now(), chrono::Utc::now());
+    let volume = VolumeData::new(metadata, sweeps);
+
+    let (cleaned, report) = remove_rfi(&volume, &RfiOptions::default()).unwrap();
+    assert_eq!(report.flagged_rays, vec![vec![45], vec![45]]);
+    assert_eq!(report.spike_azimuths, vec![45.0]);
+    assert_eq!(cleaned.sweeps[0].moments["DBZH"].data()[[45, 100]], -9999.0);
+}
+
+#[test]
+fn test_unfold_and_profile() {
+    use radish::transforms::dealias::{unfold, WindProfile};
+
+    // 25 m/s true velocity aliased into a 10 m/s Nyquist interval
+    assert!((unfold(5.0, 22.0, 10.0) - 25.0).abs() < 1e-9);
+    assert!((unfold(-8.0, -7.0, 10.0) + 8.0).abs() < 1e-9);
+
+    // Westerly wind of 20 m/s seen looking east
+    let profile = WindProfile::from_speed_direction(vec![0.0, 10_000.0], &[20.0, 20.0], &[270.0, 270.0]).unwrap();
+    let vr = profile.radial_velocity(1_000.0, 90.0, 0.0);
+    assert!((vr - 20.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_per_ray_nyquist() {
+    use ndarray::Array2;
+    use radish::transforms::dealias::{dealias_sweep_with_profile, DealiasOptions, WindProfile};
+    use radish::{MomentData, RayMetadata, SweepData, SweepMetadata};
+    use radish_types::SweepMode;
+    use std::collections::HashMap;
+
+    // 25 m/s looking east, aliased with Nyquist velocities of 10 and 20 m/s
+    let data = Array2::from_shape_vec((2, 2), vec![5.0, 5.0, -15.0, -15.0]).unwrap();
+    let mut moments = HashMap::new();
+    moments.insert("VRADH".to_string(), MomentData::new("VRADH".to_string(), "m/s".to_string(), data));
+    let coords = Coordinates::new(vec![0.0, 1.0], vec![1_000.0, 2_000.0], vec![90.0, 90.0], vec![0.0, 0.0]);
+    let mut sweep = SweepData::new(SweepMetadata::new(0, SweepMode::Azimuth, 0.0), moments, coords);
+    let profile = WindProfile::from_speed_direction(vec![0.0, 10_000.0], &[25.0, 25.0], &[270.0, 270.0]).unwrap();
+    assert!(dealias_sweep_with_profile(&sweep, &profile, 0.0, &DealiasOptions::default()).is_err());
+
+    let rays = RayMetadata {
+        nyquist_velocity: Some(vec![10.0, 20.0]),
+        n_samples: Some(vec![32, 64]),
+        ..Default::default()
+    };
+    assert!(rays.validate(2).is_ok());
+    assert!(rays.validate(3).is_err());
+    assert_eq!(rays.select(&[1]).n_samples, Some(vec![64]));
+    assert!(rays.select(&[1]).prt.is_none());
+    sweep.ray_metadata = Some(rays);
+    sweep.metadata.nyquist_velocity = Some(30.0);
+    assert_eq!(sweep.ray_nyquist_velocity(1), Some(20.0));
+
+    let dealiased = dealias_sweep_with_profile(&sweep, &profile, 0.0, &DealiasOptions::default()).unwrap();
+    assert!(dealiased.data().iter().all(|&v| (v - 25.0).abs() < 1e-4));
+
+    // A missing per-ray value falls back to the sweep value
+    sweep.ray_metadata.as_mut().unwrap().nyquist_velocity = Some(vec![10.0, f64::NAN]);
+    assert_eq!(sweep.ray_nyquist_velocity(1), Some(30.0));
+}
+
+#[test]
+fn test_evad() {
+    use ndarray::Array2;
+    use radish::transforms::evad::{evad, EvadOptions};
+    use radish::{Coordinates, MomentData, SweepData, SweepMetadata, VolumeData, VolumeMetadata};
+    use radish_types::SweepMode;
+    use std::collections::HashMap;
+
+    // Linear wind field: u = 10, v = 5 m/s, D = 1e-4, St = 2e-5, Sh = -3e-5 1/s, W = -2 m/s
+    let (u, v, div, stretch, shear, w) = (10.0, 5.0, 1e-4, 2e-5, -3e-5, -2.0);
+    let range: Vec<f32> = (0..160).map(|j| 2125.0 + j as f32 * 250.0).collect();
+    let make_sweep = |n: u32, elevation: f64| {
+        let (az, el) = ((0..72).map(|i| i as f64 * 5.0).collect::<Vec<_>>(), elevation.to_radians());
+        let vr = Array2::from_shape_fn((72, range.len()), |(i, j)| {
+            let phi = az[i].to_radians();
+            let half = range[j] as f64 * el.cos() * el.cos() / 2.0;
+            (el.cos() * (u * phi.sin() + v * phi.cos())
+                + half * (div - stretch * (2.0 * phi).cos() + shear * (2.0 * phi).sin())
+                + w * el.sin()) as f32
+        });
+        let mut moments = HashMap::new();
+        moments.insert("VRADH".to_string(), MomentData::new("VRADH".to_string(), "m/s".to_string(), vr));
+        let coords = Coordinates::new(vec![0.0; 72], range.clone(), az.iter().map(|&a| a as f32).collect(), vec![elevation as f32; 72]);
+        SweepData::new(SweepMetadata::new(n, SweepMode::Azimuth, elevation), moments, coords)
+    };
+    let sweeps = [1.0, 3.0, 6.0, 10.0, 15.0, 25.0].iter().enumerate().map(|(n, &e)| make_sweep(n as u32, e)).collect();
+    let metadata = VolumeMetadata::new("TEST".to_string(), 0.0, 0.0, 100.0, chrono::Utc::now(), chrono::Utc::now());
+    let volume = VolumeData::new(metadata, sweeps);
+
+    let profile = evad(&volume, &EvadOptions { max_range: 20_000.0, ..Default::default() }).unwrap();
+    assert_eq!(profile.height.len(), 20);
+    assert_eq!(profile.height[2], 1350.0);
+    let k = 2;
+    assert!(profile.points[k] >= 100);
+    assert!((profile.u[k] - u).abs() < 1e-3 && (profile.v[k] - v).abs() < 1e-3);
+    assert!((profile.divergence[k] - div).abs() < 1e-7, "{}", profile.divergence[k]);
+    assert!((profile.stretching_deformation[k] - stretch).abs() < 1e-7);
+    assert!((profile.shearing_deformation[k] - shear).abs() < 1e-7);
+    assert!((profile.vertical_velocity[k] - w).abs() < 1e-2);
+    assert!(profile.rms_residual[k] < 1e-3 && profile.errors[k].iter().all(|e| e.is_finite()));
+
+    // Layers above the highest sampled gate are missing and left out of the wind profile
+    assert!(profile.u[19].is_nan());
+    let wind = profile.to_wind_profile().unwrap();
+    assert!(wind.height.len() < 20);
+    assert!((wind.u[0] - u).abs() < 1e-3);
+}
+
+#[test]
+fn test_shear_products() {
+    use ndarray::Array2;
+    use radish::transforms::shear::{shear_line_grid, shear_products, ShearOptions, AZIMUTHAL_SHEAR, RADIAL_DIVERGENCE, SHEAR_LINE};
+    use radish::{Coordinates, MomentData, SweepData, SweepMetadata, VolumeData, VolumeMetadata};
+    use radish_types::SweepMode;
+    use std::collections::HashMap;
+
+    // Outflow toward the radar converging on a gust front at 20 km, and a
+    // 10 m/s velocity jump across azimuths 180° and 270°
+    let range: Vec<f32> = (0..160).map(|j| 125.0 + j as f32 * 250.0).collect();
+    let vr = Array2::from_shape_fn((360, 160), |(i, j)| {
+        let r = range[j];
+        let front = if (19_000.0..21_000.0).contains(&r) { -15.0 * (r - 19_000.0) / 2000.0 } else if r >= 21_000.0 { -15.0 } else { 0.0 };
+        front + if (180..270).contains(&i) { 10.0 } else { 0.0 }
+    });
+    let make_sweep = |angle: f64| {
+        let mut moments = HashMap::new();
+        moments.insert("VRADH".to_string(), MomentData::new("VRADH".to_string(), "m/s".to_string(), vr.clone()));
+        let coords = Coordinates::new(vec![0.0; 360], range.clone(), (0..360).map(|i| i as f32 + 0.5).collect(), vec![angle as f32; 360]);
+        SweepData::new(SweepMetadata::new(0, SweepMode::Azimuth, angle), moments, coords)
+    };
+    let metadata = VolumeMetadata::new("TEST".to_string(), 40.0, -105.0, 1600.0, chrono::Utc::now(), chrono::Utc::now());
+    let volume = VolumeData::new(metadata, vec![make_sweep(1.5), make_sweep(0.5)]);
+
+    let options = ShearOptions::default();
+    let sweep = shear_products(&volume, &options).unwrap();
+    assert_eq!(sweep.metadata.fixed_angle, 0.5);
+    let divergence = &sweep.moments[RADIAL_DIVERGENCE].data();
+    assert!((divergence[[10, 79]] + 7.5e-3).abs() < 1e-5, "{}", divergence[[10, 79]]);
+    assert!(divergence[[10, 20]].abs() < 1e-9);
+    // Across the 180° boundary at 10 km: 10 m/s over two rays of 1° (≈ 349 m)
+    let shear = &sweep.moments[AZIMUTHAL_SHEAR].data();
+    assert!(shear[[180, 40]] > 1e-2);
+    assert!(shear[[100, 40]].abs() < 1e-9);
+    let candidates = &sweep.moments[SHEAR_LINE].data();
+    assert_eq!((candidates[[10, 79]], candidates[[10, 20]], candidates[[179, 40]]), (1.0, 0.0, 1.0));
+
+    let grid = shear_line_grid(&volume, &options).unwrap();
+    let flags = &grid.get_field(SHEAR_LINE).unwrap().data;
+    assert_eq!(flags.shape(), &[1, 121, 121]);
+    // Due north at 20 km is on the front, due north at 10 km is not
+    assert_eq!(flags[[0, 80, 60]], 1.0);
+    assert_eq!(flags[[0, 70, 60]], 0.0);
+    assert!(flags[[0, 0, 0]].is_nan());
+}
+
+#[test]
+fn test_dual_prf_correction() {
+    use ndarray::Array2;
+    use radish::transforms::dual_prf::{correct_dual_prf, DualPrfOptions};
+    use radish::{Coordinates, MomentData, SweepData, SweepMetadata, VolumeData, VolumeMetadata};
+    use radish_types::{PrtMode, SweepMode};
+    use std::collections::HashMap;
+
+    // Smooth wind field with a 4:3 dual-PRF extended Nyquist of 30 m/s (Vh = 10, Vl = 7.5)
+    let truth = |i: usize, j: usize| 20.0 * (i as f32 * 10f32.to_radians()).sin() + 0.2 * j as f32;
+    let mut vel = Array2::from_shape_fn((36, 20), |(i, j)| truth(i, j));
+    vel[[4, 5]] += 20.0;
+    vel[[9, 10]] -= 15.0;
+    vel[[20, 12]] = f32::NAN;
+    let make_sweep = |mode: Option<PrtMode>| {
+        let mut moments = HashMap::new();
+        moments.insert("VRADH".to_string(), MomentData::new("VRADH".to_string(), "m/s".to_string(), vel.clone()));
+        let azimuth = (0..36).map(|i| i as f32 * 10.0).collect();
+        let coords = Coordinates::new(vec![0.0; 36], (0..20).map(|j| j as f32 * 250.0).collect(), azimuth, vec![0.5; 36]);
+        let mut metadata = SweepMetadata::new(0, SweepMode::Azimuth, 0.5);
+        metadata.prt_mode = mode;
+        metadata.nyquist_velocity = Some(30.0);
+        SweepData::new(metadata, moments, coords)
+    };
+    let metadata = VolumeMetadata::new("TEST".to_string(), 0.0, 0.0, 0.0, chrono::Utc::now(), chrono::Utc::now());
+    let volume = VolumeData::new(metadata, vec![make_sweep(Some(PrtMode::Dual)), make_sweep(Some(PrtMode::Fixed))]);
+
+    let out = correct_dual_prf(&volume, &DualPrfOptions::default()).unwrap();
+    let corrected = &out.sweeps[0].moments["VRADH"];
+    assert!((corrected.data()[[4, 5]] - truth(4, 5)).abs() < 1e-3);
+    assert!((corrected.data()[[9, 10]] - truth(9, 10)).abs() < 1e-3);
+    assert!(corrected.data()[[20, 12]].is_nan());
+    assert_eq!(corrected.data()[[15, 3]], vel[[15, 3]]);
+    assert!(corrected.provenance.is_some());
+
+    // Fixed-PRT sweeps are left alone
+    assert_eq!(out.sweeps[1].moments["VRADH"].data()[[4, 5]], vel[[4, 5]]);
+}
+
+#[test]
+fn test_merge_duplicate_sweeps() {
+    use ndarray::Array2;
+    use radish::transforms::sweep_merge::{merge_duplicate_sweeps, MergePolicy, SweepMergeOptions};
+    use radish::{Coordinates, MomentData, SweepData, SweepMetadata, VolumeData, VolumeMetadata};
+    use radish_types::SweepMode;
+    use std::collections::HashMap;
+
+    let make_sweep = |angle: f64, time: f64, value: f32| {
+        let mut moment = MomentData::new("DBZH".to_string(), "dBZ".to_string(), Array2::from_elem((4, 3), value));
+        moment.fill_value = Some(-9999.0);
+        let mut moments = HashMap::new();
+        moments.insert("DBZH".to_string(), moment);
+        let coords = Coordinates::new(vec![time; 4], vec![100.0, 200.0, 300.0], vec![0.0, 90.0, 180.0, 270.0], vec![angle as f32; 4]);
+        SweepData::new(SweepMetadata::new(0, SweepMode::Azimuth, angle), moments, coords)
+    };
+
+    let metadata = VolumeMetadata::new("TEST".to_string(), 0.0, 0.0, 0.0, chrono::Utc::now(), chrono::Utc::now());
+    let volume = VolumeData::new(
+        metadata,
+        vec![make_sweep(0.5, 0.0, 10.0), make_sweep(1.5, 10.0, 0.0), make_sweep(0.5, 20.0, 20.0)],
+    );
+
+    let latest = merge_duplicate_sweeps(&volume, &SweepMergeOptions::default()).unwrap();
+    assert_eq!(latest.num_sweeps(), 2);
+    assert_eq!(latest.metadata.sweep_fixed_angles, vec![0.5, 1.5]);
+    assert_eq!(latest.sweeps[0].moments["DBZH"].data()[[0, 0]], 20.0);
+
+    let options = SweepMergeOptions { policy: MergePolicy::Average, ..Default::default() };
+    let average = merge_duplicate_sweeps(&volume, &options).unwrap();
+    assert_eq!(average.sweeps[0].moments["DBZH"].data()[[2, 1]], 15.0);
+}
+
+#[test]
+fn test_sort_and_deduplicate_sweeps() {
+    use ndarray::Array2;
+    use radish::transforms::sweep_merge::{deduplicate_sweeps, KeepSweep, SweepMergeOptions};
+    use radish::{Coordinates, MomentData, SweepData, SweepMetadata, VolumeData, VolumeMetadata};
+    use radish_types::SweepMode;
+    use std::collections::HashMap;
+
+    let make_sweep = |angle: f64, time: f64, value: f32| {
+        let moment = MomentData::new("DBZH".to_string(), "dBZ".to_string(), Array2::from_elem((4, 3), value));
+        let mut moments = HashMap::new();
+        moments.insert("DBZH".to_string(), moment);
+        let coords = Coordinates::new(vec![time; 4], vec![100.0, 200.0, 300.0], vec![0.0, 90.0, 180.0, 270.0], vec![angle as f32; 4]);
+        SweepData::new(SweepMetadata::new(0, SweepMode::Azimuth, angle), moments, coords)
+    };
+    let metadata = VolumeMetadata::new("TEST".to_string(), 0.0, 0.0, 0.0, chrono::Utc::now(), chrono::Utc::now());
+    let mut volume = VolumeData::new(
+        metadata,
+        vec![
+            make_sweep(0.5, 0.0, 10.0),
+            make_sweep(1.5, 10.0, 0.0),
+            make_sweep(f64::NAN, 15.0, 0.0),
+            make_sweep(0.5, 20.0, 20.0),
+            make_sweep(0.9, 30.0, 0.0),
+        ],
+    );
+
+    volume.sort_sweeps_by_angle();
+    let angles = &volume.metadata.sweep_fixed_angles;
+    assert_eq!(angles[..4], [0.5, 0.5, 0.9, 1.5]);
+    assert!(angles[4].is_nan());
+    assert_eq!(volume.sweeps[1].moments["DBZH"].value(0, 0), 20.0);
+    assert_eq!(volume.sweeps[3].metadata.sweep_number, 3);
+
+    let options = SweepMergeOptions::default();
+    let first = deduplicate_sweeps(&volume, KeepSweep::First, &options).unwrap();
+    assert_eq!(first.num_sweeps(), 4);
+    assert_eq!(first.sweeps[0].moments["DBZH"].value(0, 0), 10.0);
+    let last = deduplicate_sweeps(&volume, KeepSweep::Last, &options).unwrap();
+    assert_eq!(last.sweeps[0].moments["DBZH"].value(0, 0), 20.0);
+    assert_eq!(last.metadata.sweep_fixed_angles[..3], [0.5, 0.9, 1.5]);
+}
+
+#[test]
+fn test_derived_moments() {
+    use radish::model::{Pattern, VolumeBuilder};
+    use radish::transforms::derived::{DerivedMoment, DerivedMoments};
+    use radish::RadishError;
+
+    let mut volume = VolumeBuilder::new()
+        .elevations(&[0.5, 1.5])
+        .sweep(radish::model::SweepBuilder::new(0.5).rays(4, 90.0, 0.0).gates(3, 1000.0, 500.0))
+        .moment("DBZH", "dBZ", Pattern::Constant(30.0))
+        .moment("DBZV", "dBZ", Pattern::Constant(28.5))
+        .build();
+    volume.sweeps[1].moments.remove("DBZV");
+    volume.sweeps[0].moments.get_mut("DBZH").unwrap().data_mut()[[1, 2]] = f32::NAN;
+
+    let derived = DerivedMoments::new()
+        .define("ZDR", "dB", "DBZH - DBZV")
+        .unwrap()
+        .define("DBZ_LIN", "mm6 m-3", "10^(DBZH/10)")
+        .unwrap()
+        .define("LOG_CHECK", "dBZ", "10 * log10(DBZ_LIN) - -max(0, -1e-3)")
+        .unwrap();
+    assert_eq!(derived.names(), ["ZDR", "DBZ_LIN", "LOG_CHECK"]);
+    assert_eq!(derived.get("ZDR").unwrap().inputs(), ["DBZH", "DBZV"]);
+
+    // Lazy evaluation of a definition using another one
+    let check = derived.evaluate(&volume.sweeps[0], "LOG_CHECK").unwrap();
+    assert!((check.value(0, 0) - 30.0).abs() < 1e-4);
+    assert!(!check.is_valid(1, 2));
+    assert_eq!(check.provenance.as_ref().unwrap().ancestors, ["DBZ_LIN"]);
+    assert!(matches!(
+        derived.evaluate(&volume.sweeps[1], "ZDR"),
+        Err(RadishError::MissingVariable(name)) if name == "DBZV"
+    ));
+
+    let applied = derived.apply(&volume).unwrap();
+    let zdr = &applied.sweeps[0].moments["ZDR"];
+    assert_eq!(zdr.units, "dB");
+    assert_eq!(zdr.value(3, 0), 1.5);
+    assert!(applied.sweeps[1].get_moment("ZDR").is_none());
+    assert!((applied.sweeps[1].moments["DBZ_LIN"].value(0, 0) - 1000.0).abs() < 1e-2);
+
+    for bad in ["DBZH +", "(DBZH", "foo(DBZH)", "min(DBZH)", "DBZH $ 2"] {
+        assert!(matches!(DerivedMoment::new("X", "1", bad), Err(RadishError::InvalidFormat(_))));
+    }
+    let cyclic = DerivedMoments::new().define("A", "1", "B + 1").unwrap().define("B", "1", "A * 2").unwrap();
+    assert!(matches!(cyclic.evaluate(&volume.sweeps[0], "A"), Err(RadishError::General(_))));
+}
+
+#[test]
+fn test_merge_split_cuts() {
+    use ndarray::Array2;
+    use radish::transforms::sweep_merge::{merge_split_cuts, SplitCutGrid, SplitCutOptions};
+    use radish::{Coordinates, MomentData, SweepData, SweepMetadata, VolumeData, VolumeMetadata};
+    use radish_types::SweepMode;
+    use std::collections::HashMap;
+
+    // Surveillance: 1 km gates to 8 km; Doppler: 250 m gates to 4 km, rays offset by 0.2°
+    let make_sweep = |angle: f64, name: &str, spacing: f32, gates: usize, offset: f32| {
+        let data = Array2::from_shape_fn((4, gates), |(i, j)| (i * 100 + j) as f32);
+        let mut moment = MomentData::new(name.to_string(), "unit".to_string(), data);
+        moment.fill_value = Some(-9999.0);
+        let mut moments = HashMap::new();
+        moments.insert(name.to_string(), moment);
+        let range = (0..gates).map(|j| (j as f32 + 0.5) * spacing).collect();
+        let azimuth = (0..4).map(|i| i as f32 * 90.0 + offset).collect();
+        let coords = Coordinates::new(vec![0.0; 4], range, azimuth, vec![angle as f32; 4]);
+        let mut metadata = SweepMetadata::new(0, SweepMode::Azimuth, angle);
+        metadata.nyquist_velocity = (name == "VRADH").then_some(28.0);
+        SweepData::new(metadata, moments, coords)
+    };
+    let metadata = VolumeMetadata::new("TEST".to_string(), 0.0, 0.0, 0.0, chrono::Utc::now(), chrono::Utc::now());
+    let volume = VolumeData::new(
+        metadata,
+        vec![
+            make_sweep(0.5, "DBZH", 1000.0, 8, 0.0),
+            make_sweep(0.5, "VRADH", 250.0, 16, 0.2),
+            make_sweep(1.5, "DBZH", 1000.0, 8, 0.0),
+            make_sweep(1.5, "VRADH", 250.0, 16, 0.2),
+            make_sweep(2.4, "VRADH", 250.0, 16, 0.0),
+        ],
+    );
+
+    let merged = merge_split_cuts(&volume, &SplitCutOptions::default());
+    assert_eq!(merged.num_sweeps(), 3);
+    assert_eq!(merged.metadata.sweep_fixed_angles, vec![0.5, 1.5, 2.4]);
+    let sweep = &merged.sweeps[0];
+    assert_eq!(sweep.num_gates(), 8);
+    assert_eq!(sweep.metadata.nyquist_velocity, Some(28.0));
+    // The 1 km gate centered at 2.5 km matches the 250 m gate centered at 2.375 km
+    assert_eq!(sweep.moments["VRADH"].data()[[1, 2]], 109.0);
+    assert_eq!(sweep.moments["VRADH"].data()[[1, 6]], -9999.0);
+    assert_eq!(sweep.moments["DBZH"].data()[[1, 6]], 106.0);
+
+    // On the Doppler grid, reflectivity is repeated over the finer gates
+    let options = SplitCutOptions { grid: SplitCutGrid::Doppler, ..Default::default() };
+    let doppler = merge_split_cuts(&volume, &options);
+    let dbzh = &doppler.sweeps[1].moments["DBZH"];
+    assert_eq!(dbzh.shape(), (4, 16));
+    assert_eq!(dbzh.data().row(3).to_vec()[4..8], [301.0; 4]);
+}
+
+#[test]
+fn test_sails_restructuring() {
+    use ndarray::Array2;
+    use radish::transforms::sails::{restructure_sails, sails_sweeps, SailsOptions, SailsPolicy, SAILS_CUT_ATTRIBUTE};
+    use radish::{Coordinates, MomentData, SweepData, SweepMetadata, VolumeData, VolumeMetadata};
+    use radish_types::SweepMode;
+    use std::collections::HashMap;
+
+    let make_sweep = |angle: f64, time: f64| {
+        let mut moments = HashMap::new();
+        moments.insert("DBZH".to_string(), MomentData::new("DBZH".to_string(), "dBZ".to_string(), Array2::zeros((4, 3))));
+        let coords = Coordinates::new(vec![time; 4], vec![100.0, 200.0, 300.0], vec![0.0, 90.0, 180.0, 270.0], vec![angle as f32; 4]);
+        SweepData::new(SweepMetadata::new(0, SweepMode::Azimuth, angle), moments, coords)
+    };
+    // 0.5 (split cut), 0.9, 1.3, SAILS 0.5 (split cut), 1.8, 2.4, SAILS 0.5
+    let angles = [0.5, 0.5, 0.9, 1.3, 0.5, 0.5, 1.8, 2.4, 0.5];
+    let sweeps = angles.iter().enumerate().map(|(i, &a)| make_sweep(a, 1.7e9 + 30.0 * i as f64)).collect();
+    let metadata = VolumeMetadata::new("TEST".to_string(), 0.0, 0.0, 0.0, chrono::Utc::now(), chrono::Utc::now());
+    let volume = VolumeData::new(metadata, sweeps);
+
+    let flags = sails_sweeps(&volume, 0.2);
+    assert_eq!(flags, vec![false, false, false, false, true, true, false, false, true]);
+
+    let kept = restructure_sails(&volume, &SailsOptions::default()).unwrap();
+    assert_eq!(kept.len(), 1);
+    assert_eq!(kept[0].num_sweeps(), 9);
+
+    let options = SailsOptions { policy: SailsPolicy::Drop, ..Default::default() };
+    let dropped = restructure_sails(&volume, &options).unwrap();
+    assert_eq!(dropped[0].metadata.sweep_fixed_angles, vec![0.5, 0.5, 0.9, 1.3, 1.8, 2.4]);
+
+    let options = SailsOptions { policy: SailsPolicy::Split, ..Default::default() };
+    let split = restructure_sails(&volume, &options).unwrap();
+    assert_eq!(split.len(), 3);
+    assert_eq!(split[0].num_sweeps(), 6);
+    assert_eq!(split[1].metadata.sweep_fixed_angles, vec![0.5, 0.5]);
+    assert_eq!(split[1].metadata.attributes[SAILS_CUT_ATTRIBUTE], "1");
+    assert_eq!(split[2].metadata.attributes[SAILS_CUT_ATTRIBUTE], "2");
+    assert_eq!(split[2].metadata.time_coverage_start.timestamp(), 1_700_000_240);
+    assert_eq!(split[2].sweeps[0].metadata.sweep_number, 0);
+}
+
+#[test]
+fn test_phidp_unfold_ray() {
+    use radish::transforms::phidp::unfold_ray;
+
+    // Monotonic phase crossing the +180 wrap
+    let mut values = vec![150.0, 165.0, 175.0, -175.0, -160.0, -150.0];
+    let valid = vec![true; values.len()];
+    unfold_ray(&mut values, &valid, 360.0, 3);
+    assert_eq!(values, vec![150.0, 165.0, 175.0, 185.0, 200.0, 210.0]);
+}
+
+#[test]
+fn test_compute_kdp() {
+    use ndarray::Array2;
+    use radish::transforms::phidp::{compute_kdp, KdpOptions};
+    use radish::{Coordinates, MomentData, SweepData, SweepMetadata, VolumeData, VolumeMetadata};
+    use radish_types::SweepMode;
+    use std::collections::HashMap;
+
+    // PHIDP rising 2°/km along every ray, with a gap
+    let range: Vec<f32> = (0..40).map(|j| 125.0 + 250.0 * j as f32).collect();
+    let mut phidp = Array2::from_shape_fn((4, 40), |(_, j)| 10.0 + 2.0 * range[j] / 1000.0);
+    phidp[[0, 20]] = f32::NAN;
+    let mut moments = HashMap::new();
+    moments.insert(
+        "PHIDP_CORR".to_string(),
+        MomentData::new("PHIDP_CORR".to_string(), "degrees".to_string(), phidp),
+    );
+    let coords = Coordinates::new(vec![0.0; 4], range, vec![0.0, 90.0, 180.0, 270.0], vec![0.5; 4]);
+    let sweep = SweepData::new(SweepMetadata::new(0, SweepMode::Azimuth, 0.5), moments, coords);
+    let metadata = VolumeMetadata::new("TEST".to_string(), 35.0, -97.0, 300.0, chrono::Utc::now(), chrono::Utc::now());
+    let volume = VolumeData::new(metadata, vec![sweep]);
+
+    let result = compute_kdp(&volume, &KdpOptions::default()).unwrap();
+    let kdp = result.sweeps[0].get_moment("KDP").unwrap();
+    assert_eq!(kdp.units, "degrees/km");
+    assert!((kdp.value(1, 20) - 1.0).abs() < 1e-3);
+    assert!((kdp.value(0, 19) - 1.0).abs() < 1e-3);
+    assert!(!kdp.is_valid(0, 20));
+}
+
+#[test]
+fn test_grid_volume() {
+    use ndarray::Array2;
+    use radish::transforms::gridding::{grid_volume, GridOptions, RadiusOfInfluence, Weighting};
+    use radish::{Coordinates, MomentData, SweepData, SweepMetadata, VolumeData, VolumeMetadata};
+    use radish_types::SweepMode;
+    use std::collections::HashMap;
+
+    let azimuth: Vec<f32> = (0..360).map(|a| a as f32).collect();
+    let range: Vec<f32> = (0..100).map(|j| 125.0 + 250.0 * j as f32).collect();
+    let mut moments = HashMap::new();
+    moments.insert(
+        "DBZH".to_string(),
+        MomentData::new("DBZH".to_string(), "dBZ".to_string(), Array2::from_elem((360, 100), 30.0)),
+    );
+    let coords = Coordinates::new(vec![0.0; 360], range, azimuth, vec![0.5; 360]);
+    let sweep = SweepData::new(SweepMetadata::new(0, SweepMode::Azimuth, 0.5), moments, coords);
+    let metadata = VolumeMetadata::new("TEST".to_string(), 35.0, -97.0, 300.0, chrono::Utc::now(), chrono::Utc::now());
+    let volume = VolumeData::new(metadata, vec![sweep]);
+
+    let options = GridOptions {
+        x_limits: (-20_000.0, 20_000.0),
+        y_limits: (-20_000.0, 20_000.0),
+        z_limits: (400.0, 400.0),
+        shape: (1, 41, 41),
+        weighting: Weighting::Cressman,
+        roi: RadiusOfInfluence::Constant(1000.0),
+        ..Default::default()
+    };
+    let grid = grid_volume(&volume, &options).unwrap();
+    assert_eq!(grid.shape(), (1, 41, 41));
+
+    let dbzh = grid.get_field("DBZH").unwrap();
+    assert_eq!(dbzh.units, "dBZ");
+    assert!((dbzh.data[[0, 20, 25]] - 30.0).abs() < 1e-4);
+    // Beyond the last gate
+    assert!(dbzh.data[[0, 0, 0]].is_nan());
+}
+
+#[test]
+fn test_convective_stratiform() {
+    use ndarray::Array3;
+    use radish::transforms::convective::{convective_stratiform, ConvStratOptions, CONVECTIVE, CONV_STRAT, NO_ECHO, STRATIFORM};
+    use radish::transforms::georeference::Projection;
+    use radish::{GridField, GriddedData};
+
+    let axis: Vec<f64> = (0..41).map(|i| (i as f64 - 20.0) * 1000.0).collect();
+    let mut grid = GriddedData::new(chrono::Utc::now(), Projection::aeqd(0.0, 0.0), axis.clone(), axis, vec![3000.0]);
+
+    let mut data = Array3::from_elem((1, 41, 41), 20.0f32);
+    data[[0, 20, 20]] = 45.0;
+    data[[0, 0, 0]] = f32::NAN;
+    grid.add_field(GridField::new("DBZH".to_string(), "dBZ".to_string(), data)).unwrap();
+
+    let classified = convective_stratiform(&grid, &ConvStratOptions::default()).unwrap();
+    let class = &classified.get_field(CONV_STRAT).unwrap().data;
+    assert_eq!(class[[0, 20, 20]], CONVECTIVE);
+    // Background of ~20 dBZ gives a 2 km convective radius
+    assert_eq!(class[[0, 20, 22]], CONVECTIVE);
+    assert_eq!(class[[0, 20, 24]], STRATIFORM);
+    assert_eq!(class[[0, 0, 0]], NO_ECHO);
+}
+
+#[test]
+fn test_clutter_map() {
+    use ndarray::Array2;
+    use radish::transforms::clutter::{build_clutter_map, filter_clutter, ClutterMapOptions, CLUTTER_FLAG};
+    use radish::{Coordinates, MomentData, SweepData, SweepMetadata, VolumeData, VolumeMetadata};
+    use radish_types::SweepMode;
+    use std::collections::HashMap;
+
+    // Clutter at ray 10, gate 4 in every volume; weather at ray 200, gate 20 in one
+    let volume = |weather: bool| {
+        let mut data = Array2::from_elem((360, 40), f32::NAN);
+        data[[10, 4]] = 45.0;
+        if weather {
+            data[[200, 20]] = 30.0;
+        }
+        let mut moments = HashMap::new();
+        moments.insert("DBZH".to_string(), MomentData::new("DBZH".to_string(), "dBZ".to_string(), data));
+        let azimuth: Vec<f32> = (0..360).map(|a| a as f32 + 0.5).collect();
+        let range: Vec<f32> = (0..40).map(|j| 125.0 + 250.0 * j as f32).collect();
+        let coords = Coordinates::new(vec![0.0; 360], range, azimuth, vec![0.5; 360]);
+        let sweep = SweepData::new(SweepMetadata::new(0, SweepMode::Azimuth, 0.5), moments, coords);
+        let metadata = VolumeMetadata::new("TEST".to_string(), 0.0, 0.0, 0.0, chrono::Utc::now(), chrono::Utc::now());
+        VolumeData::new(metadata, vec![sweep])
+    };
+
+    let options = ClutterMapOptions::default();
+    let clear_air = [volume(false), volume(true), volume(false), volume(false)];
+    let map = build_clutter_map(&clear_air, &options).unwrap();
+    assert_eq!(map.num_volumes, 4);
+    assert_eq!(map.tilts.len(), 1);
+    let frequency = map.tilts[0].frequency();
+    assert_eq!(frequency[[10, 4]], 1.0);
+    assert_eq!(frequency[[200, 20]], 0.25);
+
+    let filtered = filter_clutter(&volume(true), &map, &options).unwrap();
+    let sweep = &filtered.sweeps[0];
+    assert!(sweep.moments["DBZH"].data()[[10, 4]].is_nan());
+    assert_eq!(sweep.moments["DBZH"].data()[[200, 20]], 30.0);
+    assert_eq!(sweep.moments[CLUTTER_FLAG].data()[[10, 4]], 1.0);
+    assert_eq!(sweep.moments[CLUTTER_FLAG].data().sum(), 1.0);
+}
+
+#[test]
+fn test_azimuth_offset() {
+    use ndarray::Array2;
+    use radish::transforms::azimuth_offset::{
+        apply_azimuth_offset, estimate_volume_azimuth_offset, AzimuthOffsetOptions, AZIMUTH_OFFSET_ATTRIBUTE,
+    };
+    use radish::transforms::clutter::build_clutter_map;
+    use radish::{Coordinates, MomentData, SweepData, SweepMetadata, VolumeData, VolumeMetadata};
+    use radish_types::SweepMode;
+    use std::collections::HashMap;
+
+    // Irregular clutter targets, seen by a pedestal reading `error` degrees off
+    let volume = |error: f32| {
+        let mut data = Array2::from_elem((360, 40), f32::NAN);
+        for (ray, gate) in [(10, 4), (11, 4), (47, 12), (123, 7), (200, 30), (201, 31), (305, 2)] {
+            data[[ray, gate]] = 45.0;
+        }
+        let mut moments = HashMap::new();
+        moments.insert("DBZH".to_string(), MomentData::new("DBZH".to_string(), "dBZ".to_string(), data));
+        let azimuth: Vec<f32> = (0..360).map(|a| (a as f32 + 0.5 + error).rem_euclid(360.0)).collect();
+        let range: Vec<f32> = (0..40).map(|j| 125.0 + 250.0 * j as f32).collect();
+        let coords = Coordinates::new(vec![0.0; 360], range, azimuth, vec![0.5; 360]);
+        let sweep = SweepData::new(SweepMetadata::new(0, SweepMode::Azimuth, 0.5), moments, coords);
+        let metadata = VolumeMetadata::new("TEST".to_string(), 0.0, 0.0, 0.0, chrono::Utc::now(), chrono::Utc::now());
+        VolumeData::new(metadata, vec![sweep])
+    };
+
+    let options = AzimuthOffsetOptions::default();
+    let reference = build_clutter_map(&[volume(0.0)], &options.clutter).unwrap();
+    let misaligned = volume(-3.0);
+
+    let offset = estimate_volume_azimuth_offset([&misaligned], &reference, &options).unwrap();
+    assert!((offset.offset - 3.0).abs() < 0.1);
+    assert!(offset.correlation > 0.9);
+    assert_eq!(offset.tilts, 1);
+
+    let corrected = apply_azimuth_offset(&misaligned, offset.offset);
+    assert!((corrected.sweeps[0].coordinates.azimuth[10] - 10.5).abs() < 0.1);
+    assert!(corrected.metadata.attributes.contains_key(AZIMUTH_OFFSET_ATTRIBUTE));
+    let offset = estimate_volume_azimuth_offset([&corrected], &reference, &options).unwrap();
+    assert!(offset.offset.abs() < 0.1);
+}
+
+#[test]
+fn test_platform_motion_correction() {
+    use ndarray::Array2;
+    use radish::transforms::motion_correction::{
+        correct_platform_motion, platform_to_earth, MotionCorrectionOptions, MOTION_CORRECTED_ATTRIBUTE,
+    };
+    use radish::{Coordinates, MomentData, PlatformMotion, SweepData, SweepMetadata, VolumeData, VolumeMetadata};
+    use radish_types::{PlatformType, SweepMode};
+    use std::collections::HashMap;
+
+    let (az, el) = platform_to_earth(0.0, 0.0, 90.0, 0.0, 0.0);
+    assert!((az - 90.0).abs() < 1e-9 && el.abs() < 1e-9);
+    // Rolled 10° starboard down, a beam to starboard points 10° down
+    let (az, el) = platform_to_earth(90.0, 0.0, 0.0, 10.0, 0.0);
+    assert!((az - 90.0).abs() < 1e-9 && (el + 10.0).abs() < 1e-9);
+    // Pitched 5° bow up, a beam over the bow points 5° up
+    let (_, el) = platform_to_earth(0.0, 0.0, 0.0, 0.0, 5.0);
+    assert!((el - 5.0).abs() < 1e-9);
+
+    // Ship heading east at 5 m/s; beams over the bow and the stern, 0 m/s measured
+    let mut moments = HashMap::new();
+    moments.insert(
+        "VRADH".to_string(),
+        MomentData::new("VRADH".to_string(), "m/s".to_string(), Array2::zeros((2, 3))),
+    );
+    let coords = Coordinates::new(vec![0.0; 2], vec![1000.0, 2000.0, 3000.0], vec![0.0, 180.0], vec![0.0; 2]);
+    let mut sweep = SweepData::new(SweepMetadata::new(0, SweepMode::Azimuth, 0.0), moments, coords);
+    sweep.platform_motion = Some(PlatformMotion {
+        heading: Some(vec![90.0, f64::NAN]),
+        velocity_east: Some(vec![5.0; 2]),
+        velocity_north: Some(vec![0.0; 2]),
+        ..Default::default()
+    });
+    let metadata = VolumeMetadata::new("SHIP".to_string(), 0.0, 0.0, 0.0, chrono::Utc::now(), chrono::Utc::now());
+    let mut volume = VolumeData::new(metadata, vec![sweep]);
+
+    // Fixed radars are left as they are
+    let unchanged = correct_platform_motion(&volume, &MotionCorrectionOptions::default()).unwrap();
+    assert_eq!(unchanged.sweeps[0].coordinates.azimuth, vec![0.0, 180.0]);
+
+    volume.metadata.platform_type = Some(PlatformType::Ship);
+    let corrected = correct_platform_motion(&volume, &MotionCorrectionOptions::default()).unwrap();
+    let sweep = &corrected.sweeps[0];
+    // The second ray has no heading and keeps its azimuth (due south)
+    assert!((sweep.coordinates.azimuth[0] - 90.0).abs() < 1e-4);
+    assert_eq!(sweep.coordinates.azimuth[1], 180.0);
+    let velocity = &sweep.moments["VRADH"];
+    assert!((velocity.data()[[0, 0]] - 5.0).abs() < 1e-4);
+    assert!(velocity.data()[[1, 2]].abs() < 1e-4);
+    assert!(velocity.provenance.is_some());
+    assert!(corrected.metadata.attributes.contains_key(MOTION_CORRECTED_ATTRIBUTE));
+
+    // Corrected volumes are not corrected twice
+    let again = correct_platform_motion(&corrected, &MotionCorrectionOptions::default()).unwrap();
+    assert_eq!(again.sweeps[0].moments["VRADH"].data(), velocity.data());
+
+    volume.metadata.platform_type = Some(PlatformType::Satellite);
+    assert!(correct_platform_motion(&volume, &MotionCorrectionOptions::default()).is_err());
+}
+
+#[test]
+fn test_snr_threshold() {
+    use ndarray::Array2;
+    use radish::model::{InstrumentParameters, RadarCalibration};
+    use radish::transforms::gatefilter::GateFilter;
+    use radish::transforms::snr::{compute_snr, snr_gate_filter, threshold_snr, SnrOptions, SNR};
+    use radish::{Coordinates, MomentData, SweepData, SweepMetadata, VolumeData, VolumeMetadata};
+    use radish_types::SweepMode;
+    use std::collections::HashMap;
+
+    // -5 dBZ at 1, 2, 5 and 10 km with -20 dBZ noise at 1 km: SNR 15, 9, 1 and -5 dB
+    let sweep = |snr: Option<f32>| {
+        let mut moments = HashMap::new();
+        moments.insert(
+            "DBZH".to_string(),
+            MomentData::new("DBZH".to_string(), "dBZ".to_string(), Array2::from_elem((2, 4), -5.0)),
+        );
+        let mut vel = MomentData::new("VRADH".to_string(), "m/s".to_string(), Array2::from_elem((2, 4), 3.0));
+        vel.fill_value = Some(-9999.0);
+        moments.insert("VRADH".to_string(), vel);
+        if let Some(snr) = snr {
+            moments.insert(
+                SNR.to_string(),
+                MomentData::new(SNR.to_string(), "dB".to_string(), Array2::from_elem((2, 4), snr)),
+            );
+        }
+        let coords = Coordinates::new(vec![0.0; 2], vec![1000.0, 2000.0, 5000.0, 10_000.0], vec![0.0, 180.0], vec![0.5; 2]);
+        SweepData::new(SweepMetadata::new(0, SweepMode::Azimuth, 0.5), moments, coords)
+    };
+    let metadata = VolumeMetadata::new("TEST".to_string(), 0.0, 0.0, 0.0, chrono::Utc::now(), chrono::Utc::now());
+    let mut volume = VolumeData::new(metadata, vec![sweep(None), sweep(Some(1.0))]);
+
+    // No noise level in the options or calibration
+    assert!(threshold_snr(&volume, &SnrOptions::default()).is_err());
+    // Noise power and radar constant give the noise level
+    volume.calibration = Some(RadarCalibration {
+        noise_power_h: Some(-110.0),
+        ..Default::default()
+    });
+    volume.metadata.instrument_parameters = Some(InstrumentParameters {
+        radar_constant_h: Some(90.0),
+        ..Default::default()
+    });
+    let with_snr = compute_snr(&volume, &SnrOptions::default()).unwrap();
+    assert!((with_snr.sweeps[0].moments[SNR].data()[[0, 0]] - 15.0).abs() < 1e-4);
+    volume.calibration = Some(RadarCalibration {
+        base_dbz_1km_h: Some(-20.0),
+        ..Default::default()
+    });
+
+    let censored = threshold_snr(&volume, &SnrOptions::default()).unwrap();
+    let computed = &censored.sweeps[0];
+    assert!((computed.moments[SNR].data()[[0, 1]] - (15.0 - 20.0 * 2f32.log10())).abs() < 1e-4);
+    assert!(computed.moments[SNR].provenance.is_some());
+    assert_eq!(computed.moments["VRADH"].data().row(0).to_vec(), vec![3.0, 3.0, -9999.0, -9999.0]);
+    assert!(computed.moments["DBZH"].data()[[1, 3]].is_nan());
+    // The SNR moment present in the second sweep is read, not recomputed
+    let read = &censored.sweeps[1];
+    assert!(read.moments[SNR].provenance.is_none());
+    assert_eq!(read.moments["VRADH"].data().sum(), -9999.0 * 8.0);
+
+    // Filters combine with other criteria
+    let mut filter = snr_gate_filter(&volume, &SnrOptions::default()).unwrap();
+    assert_eq!(filter.num_excluded(), 12);
+    let mut other = GateFilter::new(&volume);
+    other.exclude_mask(0, &Array2::from_elem((2, 4), true)).unwrap();
+    filter.union(&other).unwrap();
+    assert_eq!(filter.num_excluded(), 16);
+    assert!(filter.exclude_below(&volume, "KDP", 0.0).is_err());
+
+    // Masking keeps the values of excluded gates
+    let masked = filter.apply_mask(&volume, &["VRADH".to_string()]).unwrap();
+    let vel = &masked.sweeps[1].moments["VRADH"];
+    assert_eq!(vel.data().sum(), 3.0 * 8.0);
+    assert_eq!(vel.count_valid(), 0);
+    assert!(masked.sweeps[1].moments["DBZH"].mask().is_none());
+}
+
+#[test]
+fn test_zdr_bias() {
+    use chrono::{Duration, TimeZone, Utc};
+    use ndarray::Array2;
+    use radish::transforms::zdr_bias::{
+        apply_zdr_correction, estimate_zdr_bias, ZdrBiasOptions, ZdrBiasTracker, ZDR_BIAS_ATTRIBUTE,
+    };
+    use radish::{Coordinates, MomentData, SweepData, SweepMetadata, VolumeData, VolumeMetadata};
+    use radish_types::SweepMode;
+    use std::collections::HashMap;
+
+    // 36 rays × 20 gates every 200 m; light rain with ZDR 0.3 dB except in
+    // heavy rain beyond 2 km and low RHOHV on the first ray
+    let sweep = |time: f64, mode: SweepMode, angle: f64, zdr: f32| {
+        let range: Vec<f32> = (1..=20).map(|k| k as f32 * 200.0).collect();
+        let dbz = Array2::from_shape_fn((36, 20), |(_, j)| if range[j] > 2000.0 { 45.0 } else { 20.0 });
+        let rhohv = Array2::from_shape_fn((36, 20), |(i, _)| if i == 0 { 0.9 } else { 0.99 });
+        let zdr = Array2::from_shape_fn((36, 20), |(i, j)| if i == 0 || range[j] > 2000.0 { 2.0 } else { zdr });
+        let mut moments = HashMap::new();
+        for (name, data) in [("DBZH", dbz), ("RHOHV", rhohv), ("ZDR", zdr)] {
+            moments.insert(name.to_string(), MomentData::new(name.to_string(), "".to_string(), data));
+        }
+        let azimuth = (0..36).map(|k| k as f32 * 10.0).collect();
+        let coords = Coordinates::new(vec![time; 36], range, azimuth, vec![angle as f32; 36]);
+        SweepData::new(SweepMetadata::new(0, mode, angle), moments, coords)
+    };
+    let volume = |time: chrono::DateTime<Utc>, zdr: f32| {
+        let metadata = VolumeMetadata::new("TEST".to_string(), 0.0, 0.0, 0.0, time, time);
+        let t = time.timestamp() as f64;
+        VolumeData::new(
+            metadata,
+            vec![sweep(t, SweepMode::Azimuth, 0.5, 5.0), sweep(t, SweepMode::VerticalPointing, 90.0, zdr)],
+        )
+    };
+    let options = ZdrBiasOptions { min_gates: 100, ..Default::default() };
+    let start = Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap();
+
+    // Only the birdbath sweep and its light rain gates count (35 rays × 8 gates from 600 m to 2 km)
+    let estimates = estimate_zdr_bias(&volume(start, 0.3), &options);
+    assert_eq!(estimates.len(), 1);
+    assert!((estimates[0].bias - 0.3).abs() < 1e-6);
+    assert_eq!(estimates[0].gates, 35 * 8);
+    assert_eq!(estimates[0].time, start);
+    assert!(estimate_zdr_bias(&volume(start, 0.3), &ZdrBiasOptions::default()).is_empty());
+
+    // Tracked over time and persisted between runs
+    let path = std::env::temp_dir().join(format!("radish_zdr_bias_{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let mut tracker = ZdrBiasTracker::load(&path).unwrap();
+    assert!(tracker.estimates.is_empty());
+    for (hours, zdr) in [(0, 0.3), (6, 0.5), (12, 0.4), (36, 0.6)] {
+        tracker.update(&volume(start + Duration::hours(hours), zdr), &options);
+    }
+    tracker.save(&path).unwrap();
+    let tracker = ZdrBiasTracker::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(tracker.estimates.len(), 4);
+    assert!((tracker.bias_at(start + Duration::hours(13), &options).unwrap() - 0.4).abs() < 1e-6);
+    assert!((tracker.bias_at(start + Duration::hours(40), &options).unwrap() - 0.6).abs() < 1e-6);
+    assert!(tracker.bias_at(start - Duration::hours(1), &options).is_none());
+
+    // Applied to a later volume
+    let corrected = apply_zdr_correction(&volume(start, 0.3), 0.4, &options);
+    let zdr = &corrected.sweeps[0].moments["ZDR"];
+    assert!((zdr.data()[[1, 0]] - 4.6).abs() < 1e-6);
+    assert_eq!(zdr.attributes[ZDR_BIAS_ATTRIBUTE], "0.4");
+    assert!(zdr.provenance.is_some());
+    assert_eq!(corrected.calibration.unwrap().zdr_correction, Some(0.4));
+}
+
+#[test]
+fn test_self_consistency_bias() {
+    use ndarray::Array2;
+    use radish::transforms::self_consistency::{self_consistency_bias, KdpRelation, SelfConsistencyOptions};
+    use radish::{Coordinates, MomentData, SweepData, SweepMetadata, VolumeData, VolumeMetadata};
+    use radish_types::SweepMode;
+    use std::collections::HashMap;
+
+    // Rain from 5 to 20 km whose PHIDP matches the S band relation, then reflectivity reading 2 dB high
+    let relation = KdpRelation::S_BAND;
+    let range: Vec<f32> = (0..120).map(|j| 125.0 + j as f32 * 250.0).collect();
+    let rain = |j: usize| (20..80).contains(&j);
+    let true_z = |i: usize, j: usize| 35.0 + 10.0 * ((i + j) as f32 / 9.0).sin();
+    let dbzh = Array2::from_shape_fn((20, 120), |(i, j)| if rain(j) { true_z(i, j) + 2.0 } else { 5.0 });
+    let zdr = Array2::from_elem((20, 120), 1.0f32);
+    let mut phidp = Array2::zeros((20, 120));
+    for i in 0..20 {
+        for j in 1..120 {
+            let kdp = |k: usize| if rain(k) { relation.kdp(true_z(i, k) as f64, 1.0) } else { 0.0 };
+            let step = if rain(j - 1) && rain(j) { (kdp(j - 1) + kdp(j)) * 0.25 } else { 0.0 };
+            phidp[[i, j]] = phidp[[i, j - 1]] + step as f32;
+        }
+    }
+    let mut moments = HashMap::new();
+    moments.insert("DBZH".to_string(), MomentData::new("DBZH".to_string(), "dBZ".to_string(), dbzh));
+    moments.insert("ZDR".to_string(), MomentData::new("ZDR".to_string(), "dB".to_string(), zdr));
+    moments.insert("PHIDP_CORR".to_string(), MomentData::new("PHIDP_CORR".to_string(), "degrees".to_string(), phidp));
+    let coords = Coordinates::new(vec![0.0; 20], range, (0..20).map(|i| i as f32).collect(), vec![0.5; 20]);
+    let sweep = SweepData::new(SweepMetadata::new(0, SweepMode::Azimuth, 0.5), moments, coords);
+    let metadata = VolumeMetadata::new("TEST".to_string(), 0.0, 0.0, 0.0, chrono::Utc::now(), chrono::Utc::now());
+    let volume = VolumeData::new(metadata, vec![sweep]);
+
+    let report = self_consistency_bias(&volume, &SelfConsistencyOptions::default()).unwrap();
+    assert_eq!(report.segments, 20);
+    assert!(report.measured_phase > 20.0 * 4.0);
+    assert!((report.bias.unwrap() - 2.0).abs() < 0.01, "{:?}", report.bias);
+
+    // Too few segments give no estimate
+    let options = SelfConsistencyOptions { min_segments: 50, ..Default::default() };
+    assert!(self_consistency_bias(&volume, &options).unwrap().bias.is_none());
+}
+
+#[test]
+fn test_sun_scan() {
+    use chrono::{TimeZone, Utc};
+    use ndarray::Array2;
+    use radish::model::RadarCalibration;
+    use radish::transforms::sun::{refraction, solar_position, sun_scan, SunOptions};
+    use radish::{Coordinates, MomentData, SweepData, SweepMetadata, VolumeData, VolumeMetadata};
+    use radish_types::SweepMode;
+    use std::collections::HashMap;
+
+    // Equinox noon on the equator and Greenwich: sun near the zenith
+    let (_, elevation) = solar_position(Utc.with_ymd_and_hms(2024, 3, 20, 12, 0, 0).unwrap(), 0.0, 0.0);
+    assert!(elevation > 85.0);
+
+    // Early morning scan with the antenna pointing 0.3° clockwise and 0.2° low of the sun
+    let time = Utc.with_ymd_and_hms(2024, 6, 1, 5, 0, 0).unwrap();
+    let (sun_azimuth, sun_elevation) = solar_position(time, 45.0, 0.0);
+    let sun_elevation = sun_elevation + refraction(sun_elevation);
+    assert!(sun_elevation > 3.0 && sun_azimuth > 45.0 && sun_azimuth < 90.0);
+    let (bias_x, bias_y) = (0.3, -0.2);
+    let half_power = 40.0 * std::f64::consts::LOG10_2;
+
+    let range: Vec<f32> = (25..=100).map(|k| k as f32 * 1000.0).collect();
+    let sweeps = (-4..=4)
+        .map(|k| {
+            let elevation = sun_elevation + k as f64 * 0.3;
+            let azimuth: Vec<f32> = (0..720).map(|a| a as f32 * 0.5).collect();
+            let dbz = Array2::from_shape_fn((720, range.len()), |(i, j)| {
+                let mut daz = azimuth[i] as f64 - sun_azimuth;
+                daz -= 360.0 * (daz / 360.0).round();
+                let (x, y) = (daz * sun_elevation.to_radians().cos(), elevation - sun_elevation);
+                if x.abs() > 1.5 || y.abs() > 1.5 {
+                    return f32::NAN;
+                }
+                let power = 10.0 - half_power * ((x - bias_x).powi(2) + (y - bias_y).powi(2));
+                (power + 20.0 * (range[j] as f64 / 1000.0).log10()) as f32
+            });
+            let mut moments = HashMap::new();
+            moments.insert("DBZH".to_string(), MomentData::new("DBZH".to_string(), "dBZ".to_string(), dbz));
+            let coords = Coordinates::new(vec![time.timestamp() as f64; 720], range.clone(), azimuth, vec![elevation as f32; 720]);
+            SweepData::new(SweepMetadata::new(0, SweepMode::Azimuth, elevation), moments, coords)
+        })
+        .collect();
+    let metadata = VolumeMetadata::new("TEST".to_string(), 45.0, 0.0, 100.0, time, time);
+    let mut volume = VolumeData::new(metadata, sweeps);
+    volume.calibration = Some(RadarCalibration {
+        noise_power_h: Some(-110.0),
+        base_dbz_1km_h: Some(-20.0),
+        sun_power_h: Some(-81.0),
+        ..Default::default()
+    });
+
+    let report = sun_scan([&volume], &SunOptions::default()).unwrap();
+    assert!(report.hits.len() > 20);
+    assert!((report.azimuth_bias - bias_x).abs() < 0.01);
+    assert!((report.elevation_bias - bias_y).abs() < 0.01);
+    assert!((report.azimuth_width - 1.0).abs() < 0.01);
+    assert!((report.peak_power - 10.0).abs() < 0.01);
+    assert!((report.peak_power_dbm.unwrap() + 80.0).abs() < 0.01);
+    assert!((report.power_bias.unwrap() - 1.0).abs() < 0.01);
+    assert!(report.residual < 0.01);
+
+    // No sun in the data
+    let mut quiet = volume.clone();
+    for sweep in &mut quiet.sweeps {
+        sweep.moments.get_mut("DBZH").unwrap().data_mut().fill(f32::NAN);
+    }
+    assert!(sun_scan([&quiet], &SunOptions::default()).is_err());
+}
+
+#[test]
+fn test_pseudo_rhi() {
+    use ndarray::Array2;
+    use radish::transforms::cross_section::{pseudo_rhi, PseudoRhiOptions};
+    use radish::{Coordinates, MomentData, SweepData, SweepMetadata, VolumeData, VolumeMetadata};
+    use radish_types::SweepMode;
+    use std::collections::HashMap;
+
+    // DBZH encodes elevation and ray index; the 1.5° sweep is shorter
+    let sweep = |mode: SweepMode, elevation: f32, gates: usize| {
+        let data = Array2::from_shape_fn((360, gates), |(i, _)| elevation * 100.0 + i as f32);
+        let mut moments = HashMap::new();
+        moments.insert("DBZH".to_string(), MomentData::new("DBZH".to_string(), "dBZ".to_string(), data));
+        let range = (0..gates).map(|j| 500.0 + j as f32 * 1000.0).collect();
+        let azimuth = (0..360).map(|a| a as f32 + 0.5).collect();
+        let coords = Coordinates::new(vec![elevation as f64; 360], range, azimuth, vec![elevation; 360]);
+        SweepData::new(SweepMetadata::new(0, mode, elevation as f64), moments, coords)
+    };
+    let metadata = VolumeMetadata::new("TEST".to_string(), 0.0, 0.0, 0.0, chrono::Utc::now(), chrono::Utc::now());
+    let volume = VolumeData::new(
+        metadata,
+        vec![
+            sweep(SweepMode::Azimuth, 2.5, 10),
+            sweep(SweepMode::Azimuth, 0.5, 10),
+            sweep(SweepMode::Elevation, 45.0, 10),
+            sweep(SweepMode::Azimuth, 1.5, 6),
+        ],
+    );
+
+    let rhi = pseudo_rhi(&volume, 90.2, &PseudoRhiOptions::default()).unwrap();
+    assert_eq!(rhi.metadata.sweep_mode, SweepMode::Elevation);
+    assert_eq!(rhi.coordinates.elevation, vec![0.5, 1.5, 2.5]);
+    assert_eq!(rhi.coordinates.azimuth, vec![90.5; 3]);
+    assert_eq!(rhi.coordinates.range.len(), 10);
+    let dbzh = &rhi.moments["DBZH"];
+    assert_eq!(dbzh.data().column(0).to_vec(), vec![140.0, 240.0, 340.0]);
+    assert_eq!(dbzh.data()[[2, 9]], 340.0);
+    assert!(dbzh.data()[[1, 9]].is_nan());
+    assert!(dbzh.provenance.is_some());
+
+    let options = PseudoRhiOptions {
+        azimuth_tolerance: 0.1,
+        ..Default::default()
+    };
+    assert!(pseudo_rhi(&volume, 90.2, &options).is_err());
+}
+
+#[test]
+fn test_cross_section() {
+    use ndarray::{Array2, Array3};
+    use radish::transforms::column::{extract_column, ColumnOptions};
+    use radish::transforms::cross_section::{cross_section_grid, cross_section_volume, sample_path, CrossSectionOptions};
+    use radish::transforms::georeference::Projection;
+    use radish::{Coordinates, GridField, GriddedData, MomentData, SweepData, SweepMetadata, VolumeData, VolumeMetadata};
+    use radish_types::SweepMode;
+    use std::collections::HashMap;
+
+    // West-east path through a grid whose field is linear in x and z
+    let projection = Projection::aeqd(10.0, 45.0);
+    let axis: Vec<f64> = (-5..=5).map(|k| k as f64 * 10_000.0).collect();
+    let mut grid = GriddedData::new(chrono::Utc::now(), projection, axis.clone(), axis.clone(), vec![0.0, 1000.0, 2000.0]);
+    let data = Array3::from_shape_fn((3, 11, 11), |(k, _, i)| (k as f64 * 10.0 + axis[i] / 10_000.0) as f32);
+    grid.add_field(GridField::new("DBZH".to_string(), "dBZ".to_string(), data)).unwrap();
+    let waypoints = [projection.inverse(-20_000.0, 0.0), projection.inverse(0.0, 0.0), projection.inverse(15_000.0, 0.0)];
+
+    let path = sample_path(&waypoints, 10_000.0).unwrap();
+    assert_eq!(path.len(), 5);
+    assert!((path[4].0 - 35_000.0).abs() < 1.0);
+    assert!(sample_path(&waypoints[..1], 10_000.0).is_err());
+
+    let options = CrossSectionOptions {
+        heights: vec![1500.0, 500.0, 3000.0],
+        spacing: 5000.0,
+        ..Default::default()
+    };
+    let section = cross_section_grid(&grid, &waypoints, &options).unwrap();
+    assert_eq!(section.height, vec![500.0, 1500.0, 3000.0]);
+    assert_eq!(section.distance.len(), 8);
+    let dbzh = &section.moments["DBZH"];
+    assert_eq!(dbzh.dim(), (3, 8));
+    for (j, &d) in section.distance.iter().enumerate() {
+        let x = (d - 20_000.0) / 10_000.0;
+        assert!((dbzh[[0, j]] as f64 - (5.0 + x)).abs() < 1e-3);
+        assert!((dbzh[[1, j]] as f64 - (15.0 + x)).abs() < 1e-3);
+        assert!(dbzh[[2, j]].is_nan());
+    }
+    assert!(cross_section_grid(&grid, &waypoints, &CrossSectionOptions { moments: vec!["VRADH".to_string()], ..options.clone() }).is_err());
+
+    // Polar volume: each sample is the interpolated column above it
+    let sweep = |elevation: f32, value: f32| {
+        let mut moments = HashMap::new();
+        moments.insert(
+            "DBZH".to_string(),
+            MomentData::new("DBZH".to_string(), "dBZ".to_string(), Array2::from_elem((360, 100), value)),
+        );
+        let range = (0..100).map(|j| 250.0 + j as f32 * 500.0).collect();
+        let coords = Coordinates::new(vec![0.0; 360], range, (0..360).map(|a| a as f32).collect(), vec![elevation; 360]);
+        SweepData::new(SweepMetadata::new(0, SweepMode::Azimuth, elevation as f64), moments, coords)
+    };
+    let metadata = VolumeMetadata::new("TEST".to_string(), 45.0, 10.0, 0.0, chrono::Utc::now(), chrono::Utc::now());
+    let volume = VolumeData::new(metadata, vec![sweep(0.5, 10.0), sweep(4.0, 20.0)]);
+    let waypoints = [projection.inverse(5_000.0, 20_000.0), projection.inverse(25_000.0, 20_000.0)];
+    let section = cross_section_volume(&volume, &waypoints, &options).unwrap();
+    let column_options = ColumnOptions { heights: Some(section.height.clone()), ..Default::default() };
+    for (j, (&lon, &lat)) in section.longitude.iter().zip(&section.latitude).enumerate() {
+        let column = extract_column(&volume, lon, lat, &column_options).unwrap();
+        for (i, &v) in column.moments["DBZH"].iter().enumerate() {
+            let s = section.moments["DBZH"][[i, j]];
+            assert!(v == s || (v.is_nan() && s.is_nan()));
+        }
+    }
+    assert!(section.moments["DBZH"].iter().any(|v| (10.0..20.0).contains(v)));
+}
+
+#[test]
+fn test_reindex_azimuth() {
+    use ndarray::Array2;
+    use radish::transforms::reindex::{reindex_azimuth, DuplicateRays, ReindexOptions};
+    use radish::{Coordinates, MomentData, SweepData, SweepMetadata, VolumeData, VolumeMetadata};
+    use radish_types::SweepMode;
+    use std::collections::HashMap;
+
+    // Rays every degree from 0.5°, without the ray at 10.5° and with two
+    // extra rays at 0.3° and 0.9° at the end of the sweep
+    let mut azimuth: Vec<f32> = (0..360).map(|a| a as f32 + 0.5).filter(|&a| a != 10.5).collect();
+    azimuth.extend([0.3, 0.9]);
+    let n = azimuth.len();
+    let values: Vec<f32> = azimuth.iter().map(|&a| a * 10.0).collect();
+    let mut dbzh = MomentData::new(
+        "DBZH".to_string(),
+        "dBZ".to_string(),
+        Array2::from_shape_fn((n, 3), |(i, _)| values[i]),
+    );
+    dbzh.fill_value = Some(-9999.0);
+    let mut moments = HashMap::new();
+    moments.insert("DBZH".to_string(), dbzh);
+    let coords = Coordinates::new((0..n).map(|i| i as f64).collect(), vec![100.0, 200.0, 300.0], azimuth, vec![0.5; n]);
+    let sweep = SweepData::new(SweepMetadata::new(0, SweepMode::Azimuth, 0.5), moments, coords);
+    let rhi = SweepData::new(SweepMetadata::new(1, SweepMode::Elevation, 90.0), HashMap::new(), Coordinates::new(vec![0.0], vec![100.0], vec![90.0], vec![10.0]));
+    let metadata = VolumeMetadata::new("TEST".to_string(), 0.0, 0.0, 0.0, chrono::Utc::now(), chrono::Utc::now());
+    let volume = VolumeData::new(metadata, vec![sweep, rhi]);
+
+    let nearest = reindex_azimuth(&volume, &ReindexOptions::default()).unwrap();
+    let sweep = &nearest.sweeps[0];
+    assert_eq!(sweep.num_rays(), 360);
+    assert_eq!(sweep.coordinates.azimuth[..3], [0.5, 1.5, 2.5]);
+    assert_eq!(sweep.metadata.rays_are_indexed, Some(true));
+    let dbzh = &sweep.moments["DBZH"];
+    assert_eq!(dbzh.data()[[0, 0]], 5.0);
+    assert_eq!(dbzh.data()[[10, 2]], -9999.0);
+    assert!(sweep.coordinates.time[10].is_nan());
+    assert_eq!(sweep.coordinates.time[0], 0.0);
+    assert_eq!(nearest.sweeps[1].num_rays(), 1);
+
+    let options = ReindexOptions {
+        resolution: 0.5,
+        first_azimuth: 0.0,
+        duplicates: DuplicateRays::Average,
+    };
+    let averaged = reindex_azimuth(&volume, &options).unwrap();
+    let dbzh = &averaged.sweeps[0].moments["DBZH"];
+    assert_eq!(dbzh.data().nrows(), 720);
+    // 0.5° bin centred on 0.5°: rays at 0.5° and 0.3°
+    assert!((dbzh.data()[[1, 0]] - 4.0).abs() < 1e-4);
+    assert_eq!(dbzh.data()[[2, 0]], 9.0);
+    assert!(reindex_azimuth(&volume, &ReindexOptions { resolution: 0.7, ..Default::default() }).is_err());
+}
+
+#[test]
+fn test_ray_cleanup() {
+    use ndarray::Array2;
+    use radish::transforms::ray_cleanup::{clean_rays, DuplicatePolicy, RayCleanupOptions};
+    use radish::{Coordinates, MomentData, SweepData, SweepMetadata, VolumeData, VolumeMetadata};
+    use radish_types::SweepMode;
+    use std::collections::HashMap;
+
+    // Two transition rays, a full circle and two rays overlapping its start
+    let mut azimuth = vec![350.2, 355.2];
+    azimuth.extend((0..360).map(|a| a as f32 + 0.5));
+    azimuth.extend([0.7, 1.3]);
+    let n = azimuth.len();
+    let mut moments = HashMap::new();
+    moments.insert(
+        "DBZH".to_string(),
+        MomentData::new("DBZH".to_string(), "dBZ".to_string(), Array2::from_shape_fn((n, 2), |(i, _)| i as f32)),
+    );
+    let mut elevation = vec![0.5; n];
+    elevation[..2].fill(2.0);
+    let mut coords = Coordinates::new((0..n).map(|i| i as f64).collect(), vec![100.0, 200.0], azimuth, elevation);
+    coords.antenna_transition = Some((0..n).map(|i| i < 2).collect());
+    assert!(coords.validate().is_ok());
+    let sweep = SweepData::new(SweepMetadata::new(0, SweepMode::Azimuth, 0.5), moments, coords);
+    let metadata = VolumeMetadata::new("TEST".to_string(), 0.0, 0.0, 0.0, chrono::Utc::now(), chrono::Utc::now());
+    let volume = VolumeData::new(metadata, vec![sweep]);
+
+    let first = clean_rays(&volume, &RayCleanupOptions::default()).unwrap();
+    let sweep = &first.sweeps[0];
+    assert_eq!(sweep.num_rays(), 360);
+    assert_eq!(sweep.coordinates.azimuth[0], 0.5);
+    assert_eq!(sweep.coordinates.elevation, vec![0.5; 360]);
+    assert_eq!(sweep.moments["DBZH"].data()[[0, 0]], 2.0);
+    assert!(sweep.coordinates.antenna_transition.as_ref().unwrap().iter().all(|&t| !t));
+
+    let last = clean_rays(&volume, &RayCleanupOptions { duplicates: DuplicatePolicy::KeepLast, ..Default::default() }).unwrap();
+    assert_eq!(last.sweeps[0].moments["DBZH"].data().column(0).to_vec()[..3], [362.0, 363.0, 4.0]);
+    assert_eq!(last.sweeps[0].coordinates.time[1], 363.0);
+
+    let options = RayCleanupOptions {
+        drop_transitions: false,
+        duplicates: DuplicatePolicy::Average,
+        angle_tolerance: Some(0.4),
+    };
+    let averaged = clean_rays(&volume, &options).unwrap();
+    let sweep = &averaged.sweeps[0];
+    assert_eq!(sweep.num_rays(), 360);
+    // The transition rays start the groups of 350.5° and 355.5°
+    assert!((sweep.coordinates.azimuth[0] - 350.35).abs() < 1e-3);
+    assert_eq!(sweep.moments["DBZH"].data()[[2, 0]], (2.0 + 362.0) / 2.0);
+    assert_eq!(sweep.coordinates.antenna_transition.as_ref().unwrap().iter().filter(|&&t| t).count(), 2);
+}
+
+#[test]
+fn test_downsample() {
+    use ndarray::Array2;
+    use radish::transforms::downsample::{downsample, DownsampleOptions};
+    use radish::{Coordinates, MomentData, SweepData, SweepMetadata, VolumeData, VolumeMetadata};
+    use radish_types::SweepMode;
+    use std::collections::HashMap;
+
+    // 4 rays × 10 gates of 250 m; reflectivity alternates 10 and 20 dBZ
+    let dbzh = Array2::from_shape_fn((4, 10), |(i, j)| if i == 3 && j >= 4 { f32::NAN } else if j % 2 == 0 { 10.0 } else { 20.0 });
+    let vel = Array2::from_shape_fn((4, 10), |(_, j)| j as f32);
+    let mut moments = HashMap::new();
+    moments.insert("DBZH".to_string(), MomentData::new("DBZH".to_string(), "dBZ".to_string(), dbzh));
+    moments.insert("VRADH".to_string(), MomentData::new("VRADH".to_string(), "m/s".to_string(), vel));
+    let range = (0..10).map(|j| 125.0 + j as f32 * 250.0).collect();
+    let coords = Coordinates::new(vec![0.0, 1.0, 2.0, 3.0], range, vec![359.5, 0.5, 1.5, 2.5], vec![0.5; 4]);
+    let mut metadata = SweepMetadata::new(0, SweepMode::Azimuth, 0.5);
+    metadata.ray_angle_resolution = Some(1.0);
+    let sweep = SweepData::new(metadata, moments, coords);
+    let metadata = VolumeMetadata::new("TEST".to_string(), 0.0, 0.0, 0.0, chrono::Utc::now(), chrono::Utc::now());
+    let volume = VolumeData::new(metadata, vec![sweep]);
+
+    let options = DownsampleOptions { ray_factor: 2, ..Default::default() };
+    let coarse = downsample(&volume, &options).unwrap();
+    let sweep = &coarse.sweeps[0];
+    assert_eq!(sweep.coordinates.range, vec![500.0, 1500.0, 2250.0]);
+    assert!(sweep.coordinates.azimuth[0].abs() < 1e-4);
+    assert_eq!(sweep.coordinates.azimuth[1], 2.0);
+    assert_eq!(sweep.coordinates.time, vec![0.5, 2.5]);
+    assert_eq!(sweep.metadata.ray_angle_resolution, Some(2.0));
+
+    // Mean power of 10 and 100 mm⁶/m³
+    let dbzh = &sweep.moments["DBZH"];
+    assert_eq!(dbzh.data().dim(), (2, 3));
+    assert!((dbzh.data()[[0, 0]] - 10.0 * 55f32.log10()).abs() < 1e-4);
+    // Second ray pair beyond 1 km: half of the gates valid, then a quarter of the trailing block
+    assert!((dbzh.data()[[1, 1]] - 10.0 * 55f32.log10()).abs() < 1e-4);
+    assert!(dbzh.data()[[1, 2]].is_finite());
+    let strict = downsample(&volume, &DownsampleOptions { ray_factor: 2, min_valid_fraction: 0.75, ..Default::default() }).unwrap();
+    assert!(strict.sweeps[0].moments["DBZH"].data()[[1, 1]].is_nan());
+    assert_eq!(sweep.moments["VRADH"].data().row(0).to_vec(), vec![1.5, 5.5, 8.5]);
+
+    let arithmetic = downsample(&volume, &DownsampleOptions { linear_db: false, ..Default::default() }).unwrap();
+    assert_eq!(arithmetic.sweeps[0].moments["DBZH"].data()[[0, 0]], 15.0);
+    assert!(downsample(&volume, &DownsampleOptions { gate_factor: 0, ..Default::default() }).is_err());
+}
+
+#[test]
+fn test_specific_attenuation() {
+    use ndarray::Array2;
+    use radish::transforms::attenuation::{compute_specific_attenuation, AttenuationOptions, ZphiCoefficients, SPECIFIC_ATTENUATION};
+    use radish::{Coordinates, MomentData, SweepData, SweepMetadata, VolumeData, VolumeMetadata};
+    use radish_types::SweepMode;
+    use std::collections::HashMap;
+
+    // Ray 0: rain from 10 to 30 km with PHIDP rising by 20°; ray 1: no phase shift
+    let range: Vec<f32> = (0..400).map(|j| 50.0 + j as f32 * 100.0).collect();
+    let rain = |j: usize| (100..300).contains(&j);
+    let dbzh = Array2::from_shape_fn((2, 400), |(_, j)| if rain(j) { 40.0 + (j % 7) as f32 } else if j < 350 { 5.0 } else { f32::NAN });
+    let phidp = Array2::from_shape_fn((2, 400), |(i, j)| if i == 1 { 0.0 } else { 20.0 * ((j as f32 - 100.0) / 200.0).clamp(0.0, 1.0) });
+    let mut moments = HashMap::new();
+    moments.insert("DBZH".to_string(), MomentData::new("DBZH".to_string(), "dBZ".to_string(), dbzh));
+    moments.insert("PHIDP_CORR".to_string(), MomentData::new("PHIDP_CORR".to_string(), "degrees".to_string(), phidp));
+    let coords = Coordinates::new(vec![0.0; 2], range, vec![0.0, 1.0], vec![0.5; 2]);
+    let sweep = SweepData::new(SweepMetadata::new(0, SweepMode::Azimuth, 0.5), moments, coords);
+    let metadata = VolumeMetadata::new("TEST".to_string(), 0.0, 0.0, 0.0, chrono::Utc::now(), chrono::Utc::now());
+    let volume = VolumeData::new(metadata, vec![sweep]);
+
+    let options = AttenuationOptions {
+        coefficients: ZphiCoefficients::C_BAND,
+        corrected_reflectivity: Some("DBZH_CORR".to_string()),
+        ..Default::default()
+    };
+    let out = compute_specific_attenuation(&volume, &options).unwrap();
+    let sweep = &out.sweeps[0];
+    let a = &sweep.moments[SPECIFIC_ATTENUATION];
+    assert_eq!(a.units, "dB/km");
+    assert!(a.provenance.is_some());
+
+    // The one-way path-integrated attenuation matches α·ΔΦ/2
+    let pia: f32 = a.data().row(0).iter().filter(|v| v.is_finite()).sum::<f32>() * 0.1;
+    assert!((pia - 0.08 * 20.0 / 2.0).abs() < 0.02, "{}", pia);
+    assert!(a.data().row(0).iter().take(300).skip(100).all(|&v| v > 0.0));
+    assert_eq!(a.data()[[0, 50]], 0.0);
+    assert!(a.data()[[0, 380]].is_nan());
+    assert!(a.data().row(1).iter().all(|&v| v == 0.0 || v.is_nan()));
+
+    // Corrected reflectivity recovers the two-way attenuation beyond the rain
+    let corrected = &sweep.moments["DBZH_CORR"];
+    assert!((corrected.data()[[0, 320]] - 5.0 - 2.0 * pia).abs() < 0.01);
+    assert_eq!(corrected.data()[[1, 320]], 5.0);
+    assert_eq!(corrected.data()[[0, 50]], 5.0);
+}
+
+#[test]
+fn test_gaseous_attenuation() {
+    use ndarray::Array2;
+    use radish::transforms::attenuation::{correct_gaseous_attenuation, gaseous_attenuation, GasAttenuationOptions, GasCoefficients};
+    use radish::transforms::georeference::GeoreferenceOptions;
+    use radish::{Coordinates, MomentData, SweepData, SweepMetadata, VolumeData, VolumeMetadata};
+    use radish_types::SweepMode;
+    use std::collections::HashMap;
+
+    let range: Vec<f32> = (0..400).map(|j| 125.0 + j as f32 * 250.0).collect();
+    let geo = GeoreferenceOptions::default();
+
+    // Vertical beam through an exponential atmosphere: 2·γ0·H·(1 - exp(-r/H))
+    let c_band = GasCoefficients::C_BAND;
+    let vertical = gaseous_attenuation(&range, 90.0, 0.0, &c_band, &geo);
+    let expected = 2.0 * c_band.specific_attenuation * 3.0 * (1.0 - (-range[399] as f64 / 3000.0).exp());
+    assert!((vertical[399] - expected).abs() < 1e-4);
+
+    // Loss grows with range and frequency and shrinks with elevation
+    let low = gaseous_attenuation(&range, 0.5, 0.0, &c_band, &geo);
+    assert!(low.windows(2).all(|w| w[1] > w[0]));
+    assert!(low[399] > 1.0 && low[399] < 1.6, "{}", low[399]);
+    assert!(gaseous_attenuation(&range, 5.0, 0.0, &c_band, &geo)[399] < low[399]);
+    assert!(gaseous_attenuation(&range, 0.5, 0.0, &GasCoefficients::X_BAND, &geo)[399] > low[399]);
+
+    let mut dbzh = Array2::from_elem((2, 400), 20.0f32);
+    dbzh[[1, 10]] = -9999.0;
+    let mut moment = MomentData::new("DBZH".to_string(), "dBZ".to_string(), dbzh);
+    moment.fill_value = Some(-9999.0);
+    let mut moments = HashMap::new();
+    moments.insert("DBZH".to_string(), moment);
+    let coords = Coordinates::new(vec![0.0; 2], range, vec![0.0, 1.0], vec![0.5; 2]);
+    let sweep = SweepData::new(SweepMetadata::new(0, SweepMode::Azimuth, 0.5), moments, coords);
+    let metadata = VolumeMetadata::new("TEST".to_string(), 0.0, 0.0, 0.0, chrono::Utc::now(), chrono::Utc::now());
+    let volume = VolumeData::new(metadata, vec![sweep]);
+
+    let corrected = correct_gaseous_attenuation(&volume, &GasAttenuationOptions::default());
+    let data = &corrected.sweeps[0].moments["DBZH"].data();
+    assert!((data[[0, 399]] as f64 - 20.0 - low[399]).abs() < 1e-4);
+    assert_eq!(data[[1, 10]], -9999.0);
+
+    // A corrected volume is not corrected twice
+    let twice = correct_gaseous_attenuation(&corrected, &GasAttenuationOptions::default());
+    assert_eq!(twice.sweeps[0].moments["DBZH"].data(), *data);
+}
+
+#[test]
+fn test_hail_products() {
+    use ndarray::{Array2, Array3};
+    use radish::transforms::georeference::Projection;
+    use radish::transforms::hail::{hail_differential_reflectivity, mesh_grid, HdrOptions, MeshOptions, HDR, MESH, SHI};
+    use radish::{Coordinates, GridField, GriddedData, MomentData, SweepData, SweepMetadata, VolumeData, VolumeMetadata};
+    use radish_types::SweepMode;
+    use std::collections::HashMap;
+
+    let mut moments = HashMap::new();
+    moments.insert(
+        "DBZH".to_string(),
+        MomentData::new("DBZH".to_string(), "dBZ".to_string(), Array2::from_shape_vec((1, 3), vec![50.0, 30.0, 55.0]).unwrap()),
+    );
+    moments.insert(
+        "ZDR".to_string(),
+        MomentData::new("ZDR".to_string(), "dB".to_string(), Array2::from_shape_vec((1, 3), vec![0.5, 2.0, f32::NAN]).unwrap()),
+    );
+    let coords = Coordinates::new(vec![0.0], vec![100.0, 200.0, 300.0], vec![0.0], vec![0.5]);
+    let sweep = SweepData::new(SweepMetadata::new(0, SweepMode::Azimuth, 0.5), moments, coords);
+    let metadata = VolumeMetadata::new("TEST".to_string(), 0.0, 0.0, 0.0, chrono::Utc::now(), chrono::Utc::now());
+    let volume = VolumeData::new(metadata, vec![sweep]);
+    let hdr = hail_differential_reflectivity(&volume, &HdrOptions::default()).unwrap();
+    let hdr = &hdr.sweeps[0].moments[HDR];
+    assert!((hdr.data()[[0, 0]] - 13.5).abs() < 1e-4);
+    assert_eq!(hdr.data()[[0, 1]], -30.0);
+    assert!(hdr.data()[[0, 2]].is_nan());
+    assert!(hail_differential_reflectivity(&volume, &HdrOptions { zdr: "ZDR_CORR".to_string(), ..Default::default() }).is_err());
+
+    // Columns of 60 dBZ, 30 dBZ and no data every kilometer up to 10 km
+    let z: Vec<f64> = (0..=10).map(|k| k as f64 * 1000.0).collect();
+    let mut grid = GriddedData::new(chrono::Utc::now(), Projection::aeqd(0.0, 0.0), vec![0.0, 1000.0, 2000.0], vec![0.0], z);
+    let data = Array3::from_shape_fn((11, 1, 3), |(_, _, i)| [60.0, 30.0, f32::NAN][i]);
+    grid.add_field(GridField::new("DBZH".to_string(), "dBZ".to_string(), data)).unwrap();
+    let hail = mesh_grid(&grid, 3000.0, 6000.0, &MeshOptions::default()).unwrap();
+    assert_eq!(hail.shape(), (1, 1, 3));
+    // Height weights 0, 1/3, 2/3 and 1 from 3 to 6 km integrate to 5.5 km
+    let energy = 5e-6 * 10f64.powf(0.084 * 60.0);
+    let shi = 0.1 * energy * 5500.0;
+    assert!((hail.fields[SHI].data[[0, 0, 0]] as f64 - shi).abs() < 1e-3 * shi);
+    assert!((hail.fields[MESH].data[[0, 0, 0]] as f64 - 2.54 * shi.sqrt()).abs() < 0.05);
+    assert_eq!(hail.fields[MESH].data[[0, 0, 1]], 0.0);
+    assert!(hail.fields[MESH].data[[0, 0, 2]].is_nan());
+    assert!(mesh_grid(&grid, 6000.0, 3000.0, &MeshOptions::default()).is_err());
+}
+
+#[test]
+fn test_mosaic_coverage_weights() {
+    use ndarray::Array2;
+    use radish::transforms::mosaic::{coverage_weight, lowest_usable_beam, CoverageOptions};
+    use radish::{Coordinates, MomentData, SweepData, SweepMetadata, VolumeData, VolumeMetadata};
+    use radish_types::SweepMode;
+    use std::collections::HashMap;
+
+    // 0.5° sweep blocked by 80% between azimuths 90 and 180, 1.5° sweep clear
+    let sweep = |number: u32, elevation: f64, blocked: bool| {
+        let mut pbb = Array2::<f32>::zeros((360, 400));
+        if blocked {
+            pbb.slice_mut(ndarray::s![90..180, ..]).fill(0.8);
+        }
+        let mut moments = HashMap::new();
+        moments.insert("PBB".to_string(), MomentData::new("PBB".to_string(), String::new(), pbb));
+        let azimuth: Vec<f32> = (0..360).map(|a| a as f32 + 0.5).collect();
+        let range: Vec<f32> = (0..400).map(|j| 125.0 + 250.0 * j as f32).collect();
+        let coords = Coordinates::new(vec![0.0; 360], range, azimuth, vec![elevation as f32; 360]);
+        SweepData::new(SweepMetadata::new(number, SweepMode::Azimuth, elevation), moments, coords)
+    };
+    let metadata = VolumeMetadata::new("TEST".to_string(), 35.0, -97.0, 300.0, chrono::Utc::now(), chrono::Utc::now());
+    let volume = VolumeData::new(metadata, vec![sweep(1, 1.5, false), sweep(0, 0.5, true)]);
+
+    let options = CoverageOptions::default();
+    let clear = lowest_usable_beam(&volume, 50_000.0, 45.0, &options).unwrap();
+    assert_eq!(clear.elevation, 0.5);
+    let blocked = lowest_usable_beam(&volume, 50_000.0, 120.0, &options).unwrap();
+    assert_eq!(blocked.elevation, 1.5);
+    assert!(blocked.height > clear.height);
+    assert!(lowest_usable_beam(&volume, 150_000.0, 45.0, &options).is_none());
+
+    // Lower, closer and less blocked beams weigh more
+    let w = coverage_weight(clear.height, 50_000.0, 0.0, &options);
+    assert!(w > coverage_weight(blocked.height, 50_000.0, 0.0, &options));
+    assert!(w > coverage_weight(clear.height, 80_000.0, 0.0, &options));
+    assert!(w > coverage_weight(clear.height, 50_000.0, 0.3, &options));
+    assert_eq!(coverage_weight(clear.height, 50_000.0, 0.8, &options), 0.0);
+}
+
+#[test]
+fn test_mosaic_composite() {
+    use ndarray::Array3;
+    use radish::transforms::georeference::Projection;
+    use radish::transforms::mosaic::{composite_grids, CompositeMethod, MosaicOptions};
+    use radish::{GridField, GriddedData};
+
+    let grid = |name: &str, lon: f64, values: [f32; 3]| {
+        let mut grid = GriddedData::new(chrono::Utc::now(), Projection::Geographic, vec![-98.0, -97.0, -96.0], vec![35.0], vec![0.0]);
+        grid.attributes.insert("instrument_name".to_string(), name.to_string());
+        grid.attributes.insert("radar_longitude".to_string(), lon.to_string());
+        grid.attributes.insert("radar_latitude".to_string(), "35".to_string());
+        let data = Array3::from_shape_vec((1, 1, 3), values.to_vec()).unwrap();
+        grid.add_field(GridField::new("DBZH".to_string(), "dBZ".to_string(), data)).unwrap();
+        grid
+    };
+    let grids = [grid("WEST", -98.0, [10.0, 20.0, f32::NAN]), grid("EAST", -96.0, [30.0, 15.0, 25.0])];
+
+    let maximum = composite_grids(&grids, &MosaicOptions::default()).unwrap();
+    let field = |g: &GriddedData, name: &str| g.get_field(name).unwrap().data.iter().copied().collect::<Vec<f32>>();
+    assert_eq!(field(&maximum, "DBZH"), vec![30.0, 20.0, 25.0]);
+    assert_eq!(field(&maximum, "DBZH_SOURCE"), vec![1.0, 0.0, 1.0]);
+    assert_eq!(field(&maximum, "DBZH_COUNT"), vec![2.0, 2.0, 1.0]);
+    assert_eq!(maximum.attributes["mosaic_sources"], r#"["WEST","EAST"]"#);
+
+    let options = MosaicOptions { method: CompositeMethod::Nearest, ..Default::default() };
+    let nearest = composite_grids(&grids, &options).unwrap();
+    let values = field(&nearest, "DBZH");
+    assert_eq!((values[0], values[2]), (10.0, 25.0));
+
+    // Equidistant radars blend equally
+    let options = MosaicOptions { method: CompositeMethod::Weighted, ..Default::default() };
+    let weighted = composite_grids(&grids, &options).unwrap();
+    assert!((field(&weighted, "DBZH")[1] - 17.5).abs() < 1e-3);
+}
+
+#[test]
+fn test_extract_column() {
+    use ndarray::Array2;
+    use radish::transforms::column::{extract_column, ColumnOptions};
+    use radish::{Coordinates, MomentData, SweepData, SweepMetadata, VolumeData, VolumeMetadata};
+    use radish_types::SweepMode;
+    use std::collections::HashMap;
+
+    // Reflectivity of ten times the elevation, scanned at t0 + 10 s per sweep
+    let sweep = |number: u32, elevation: f64| {
+        let mut moments = HashMap::new();
+        let data = Array2::from_elem((360, 200), 10.0 * elevation as f32);
+        moments.insert("DBZH".to_string(), MomentData::new("DBZH".to_string(), "dBZ".to_string(), data));
+        let azimuth: Vec<f32> = (0..360).map(|a| a as f32 + 0.5).collect();
+        let range: Vec<f32> = (0..200).map(|j| 125.0 + 250.0 * j as f32).collect();
+        let time = vec![1000.0 + 10.0 * number as f64; 360];
+        let coords = Coordinates::new(time, range, azimuth, vec![elevation as f32; 360]);
+        SweepData::new(SweepMetadata::new(number, SweepMode::Azimuth, elevation), moments, coords)
+    };
+    let metadata = VolumeMetadata::new("TEST".to_string(), 35.0, -97.0, 300.0, chrono::Utc::now(), chrono::Utc::now());
+    let volume = VolumeData::new(metadata, vec![sweep(0, 0.5), sweep(1, 1.5), sweep(2, 2.5)]);
+
+    // 20 km north of the radar
+    let column = extract_column(&volume, -97.0, 35.18, &ColumnOptions::default()).unwrap();
+    assert_eq!(column.len(), 3);
+    assert!((column.distance - 20_000.0).abs() < 100.0);
+    assert!(column.height.windows(2).all(|h| h[0] < h[1]));
+    assert_eq!(column.moments["DBZH"], vec![5.0, 15.0, 25.0]);
+    assert_eq!(column.time, vec![1000.0, 1010.0, 1020.0]);
+
+    // Halfway between the two lowest beams, and below the lowest one
+    let middle = (column.height[0] + column.height[1]) / 2.0;
+    let options = ColumnOptions {
+        heights: Some(vec![100.0, middle]),
+        ..Default::default()
+    };
+    let interpolated = extract_column(&volume, -97.0, 35.18, &options).unwrap();
+    assert!(interpolated.moments["DBZH"][0].is_nan());
+    assert!((interpolated.moments["DBZH"][1] - 10.0).abs() < 1e-3);
+    assert!((interpolated.time[1] - 1005.0).abs() < 1e-6);
+}
+
+#[test]
+fn test_rain_rate() {
+    use ndarray::{arr2, Array2};
+    use radish::transforms::qpe::{
+        estimate_rain_rate, RainEstimator, RainRateOptions, RAIN_RATE, RATE_ESTIMATOR, RATE_QUALITY, RATE_SPREAD,
+    };
+    use radish::{Coordinates, MomentData, SweepData, SweepMetadata, VolumeData, VolumeMetadata};
+    use radish_types::SweepMode;
+    use std::collections::HashMap;
+
+    let moment = |name: &str, data: Array2<f32>| (name.to_string(), MomentData::new(name.to_string(), String::new(), data));
+    let moments: HashMap<_, _> = [
+        moment("DBZH", arr2(&[[20.0, 45.0, 45.0, 2.0]])),
+        moment("KDP", arr2(&[[0.1, 1.0, f32::NAN, 0.0]])),
+        moment("AH", arr2(&[[0.01, 0.05, f32::NAN, 0.0]])),
+    ]
+    .into_iter()
+    .collect();
+    let coords = Coordinates::new(vec![0.0], vec![1000.0, 2000.0, 3000.0, 4000.0], vec![0.0], vec![0.5]);
+    let sweep = SweepData::new(SweepMetadata::new(0, SweepMode::Azimuth, 0.5), moments, coords);
+    let metadata = VolumeMetadata::new("TEST".to_string(), 0.0, 0.0, 0.0, chrono::Utc::now(), chrono::Utc::now());
+    let volume = VolumeData::new(metadata, vec![sweep]);
+
+    // Blended: R(A) in light rain, R(KDP) in heavy rain, R(Z) without polarimetric data
+    let out = estimate_rain_rate(&volume, &RainRateOptions::default()).unwrap();
+    let sweep = &out.sweeps[0];
+    let estimator: Vec<f32> = sweep.moments[RATE_ESTIMATOR].data().iter().copied().collect();
+    assert_eq!(estimator, vec![3.0, 2.0, 1.0, 1.0]);
+    let rate = &sweep.moments[RAIN_RATE].data();
+    assert!((rate[[0, 0]] - 4120.0 * 0.01f32.powf(1.03)).abs() < 1e-2);
+    assert!((rate[[0, 1]] - 44.0).abs() < 1e-3);
+    // Z = 300 R^1.4 at 45 dBZ
+    assert!((rate[[0, 2]] - (10f32.powf(4.5) / 300.0).powf(1.0 / 1.4)).abs() < 1e-2);
+    assert_eq!(rate[[0, 3]], 0.0);
+    assert_eq!(sweep.moments[RAIN_RATE].provenance.as_ref().unwrap().ancestors, vec!["DBZH", "KDP", "AH"]);
+
+    // A single estimator leaves gates without its input missing
+    let options = RainRateOptions {
+        estimator: RainEstimator::Kdp,
+        ..Default::default()
+    };
+    let rate = &estimate_rain_rate(&volume, &options).unwrap().sweeps[0].moments[RAIN_RATE];
+    assert!(rate.data()[[0, 2]].is_nan());
+
+    // Spread of R(Z) and R(A) in light rain, of all three in heavy rain
+    let mut volume = volume;
+    let qi = MomentData::new("QI".to_string(), String::new(), arr2(&[[1.0, 0.5, 1.0, f32::NAN]]));
+    volume.sweeps[0].moments.insert("QI".to_string(), qi);
+    let options = RainRateOptions {
+        uncertainty: true,
+        quality: Some("QI".to_string()),
+        ..Default::default()
+    };
+    let out = estimate_rain_rate(&volume, &options).unwrap();
+    let sweep = &out.sweeps[0];
+    let spread = sweep.moments[RATE_SPREAD].data();
+    let quality = sweep.moments[RATE_QUALITY].data();
+    let r_z = |dbz: f32| (10f32.powf(dbz / 10.0) / 300.0).powf(1.0 / 1.4);
+    let r_a = |a: f32| 4120.0 * a.powf(1.03);
+    let light = [r_z(20.0), r_a(0.01)];
+    let light_sd = (light[0] - light[1]).abs() / 2f32.sqrt();
+    assert!((spread[[0, 0]] - light_sd).abs() < 1e-3);
+    assert!((quality[[0, 0]] - 1.0 / (1.0 + light_sd / (light[0] + light[1]) * 2.0)).abs() < 1e-4);
+    let heavy = [r_z(45.0), 44.0, r_a(0.05)];
+    let mean = heavy.iter().sum::<f32>() / 3.0;
+    let heavy_sd = (heavy.iter().map(|r| (r - mean).powi(2)).sum::<f32>() / 2.0).sqrt();
+    assert!((spread[[0, 1]] - heavy_sd).abs() < 1e-2);
+    assert!((quality[[0, 1]] - 0.5 / (1.0 + heavy_sd / mean)).abs() < 1e-4);
+    // A single estimator has no spread; no rain is certain; missing quality input is missing
+    assert!(spread[[0, 2]].is_nan());
+    assert_eq!(quality[[0, 2]], 0.5);
+    assert_eq!((spread[[0, 3]], quality[[0, 3]].is_nan()), (0.0, true));
+    let provenance = sweep.moments[RATE_QUALITY].provenance.as_ref().unwrap();
+    assert_eq!(provenance.ancestors, vec!["DBZH", "KDP", "AH", "QI"]);
+}
+
+#[test]
+fn test_accumulation() {
+    use chrono::TimeZone;
+    use ndarray::Array3;
+    use radish::transforms::accumulation::{accumulate, AccumulationOptions, GapPolicy, ACCUMULATION, ACCUMULATION_DURATION};
+    use radish::transforms::georeference::Projection;
+    use radish::{GridField, GriddedData};
+
+    // 6 mm/h at 00:50, 01:00 and 01:10, then a scan after a 50 minute gap
+    let grid = |hour: u32, minute: u32| {
+        let time = chrono::Utc.with_ymd_and_hms(2024, 6, 1, hour, minute, 0).unwrap();
+        let mut grid = GriddedData::new(time, Projection::Geographic, vec![0.0, 1.0], vec![0.0], vec![0.0]);
+        let rate = Array3::from_shape_vec((1, 1, 2), vec![6.0, f32::NAN]).unwrap();
+        grid.add_field(GridField::new("RATE".to_string(), "mm/h".to_string(), rate)).unwrap();
+        grid
+    };
+    let grids = [grid(1, 0), grid(0, 50), grid(1, 10), grid(2, 0)];
+
+    let hourly = accumulate(&grids, &AccumulationOptions::default()).unwrap();
+    assert_eq!(hourly.len(), 2);
+    assert_eq!(hourly[0].time, chrono::Utc.with_ymd_and_hms(2024, 6, 1, 1, 0, 0).unwrap());
+    for grid in &hourly {
+        let total = &grid.get_field(ACCUMULATION).unwrap().data;
+        assert!((total[[0, 0, 0]] - 1.0).abs() < 1e-6);
+        assert!(total[[0, 0, 1]].is_nan());
+        assert_eq!(grid.get_field(ACCUMULATION_DURATION).unwrap().data[[0, 0, 0]], 600.0);
+    }
+
+    let options = AccumulationOptions {
+        period: None,
+        ..Default::default()
+    };
+    let total = accumulate(&grids, &options).unwrap();
+    assert_eq!(total.len(), 1);
+    assert!((total[0].get_field(ACCUMULATION).unwrap().data[[0, 0, 0]] - 2.0).abs() < 1e-6);
+
+    let options = AccumulationOptions {
+        gap_policy: GapPolicy::Error,
+        ..Default::default()
+    };
+    assert!(accumulate(&grids, &options).is_err());
+}
+
+#[test]
+fn test_advection() {
+    use chrono::TimeZone;
+    use ndarray::Array3;
+    use radish::transforms::accumulation::{accumulate, AccumulationOptions, ACCUMULATION};
+    use radish::transforms::advection::{estimate_motion, AdvectionOptions};
+    use radish::transforms::georeference::Projection;
+    use radish::writers::motion::{MOTION_U, MOTION_V};
+    use radish::{GridField, GriddedData};
+
+    // A 10 mm/h cell moving 4 km east in 5 minutes on a 1 km grid
+    let grid = |minute: u32, column: usize| {
+        let time = chrono::Utc.with_ymd_and_hms(2024, 6, 1, 0, minute, 0).unwrap();
+        let axis: Vec<f64> = (0..20).map(|i| i as f64 * 1000.0).collect();
+        let mut grid = GriddedData::new(time, Projection::aeqd(0.0, 0.0), axis.clone(), axis, vec![0.0]);
+        let mut rate = Array3::<f32>::zeros((1, 20, 20));
+        rate.slice_mut(ndarray::s![0, 8..11, column..column + 3]).fill(10.0);
+        grid.add_field(GridField::new("RATE".to_string(), "mm/h".to_string(), rate)).unwrap();
+        grid
+    };
+    let (earlier, later) = (grid(0, 4), grid(5, 8));
+
+    let motion = estimate_motion(&earlier, &later, &AdvectionOptions::default()).unwrap();
+    assert!((motion.get_field(MOTION_U).unwrap().data[[0, 0, 0]] - 4000.0 / 300.0).abs() < 1e-3);
+    assert_eq!(motion.get_field(MOTION_V).unwrap().data[[0, 0, 0]], 0.0);
+
+    // Without advection the cell leaves two separate maxima; with it, the
+    // swath between them is filled
+    let options = AccumulationOptions {
+        period: None,
+        ..Default::default()
+    };
+    let total = |options: &AccumulationOptions| {
+        accumulate(&[earlier.clone(), later.clone()], options).unwrap()[0]
+            .get_field(ACCUMULATION)
+            .unwrap()
+            .data
+            .clone()
+    };
+    let plain = total(&options);
+    let advected = total(&AccumulationOptions {
+        advection: Some(AdvectionOptions::default()),
+        ..options
+    });
+    assert_eq!(plain[[0, 9, 7]], 0.0);
+    assert!(advected[[0, 9, 7]] > 0.0);
+    // The rain volume is conserved away from the edges
+    assert!((plain.sum() - advected.sum()).abs() < 1e-3 * plain.sum());
+}
+
+#[test]
+fn test_vil() {
+    use ndarray::{Array2, Array3};
+    use radish::transforms::georeference::Projection;
+    use radish::transforms::vil::{vil_grid, vil_volume, VilOptions, VIL, VIL_DENSITY};
+    use radish::{Coordinates, GridField, GriddedData, MomentData, SweepData, SweepMetadata, VolumeData, VolumeMetadata};
+    use radish_types::SweepMode;
+    use std::collections::HashMap;
+
+    // 40 dBZ from 1 to 5 km: 3.44e-6 · (10^4)^(4/7) · 4000 m
+    let expected = 3.44e-6 * 1e4f64.powf(4.0 / 7.0) * 4000.0;
+    let axis = vec![0.0, 1000.0];
+    let mut grid = GriddedData::new(
+        chrono::Utc::now(),
+        Projection::aeqd(0.0, 0.0),
+        axis.clone(),
+        axis,
+        vec![1000.0, 3000.0, 5000.0, 7000.0],
+    );
+    let mut dbz = Array3::from_elem((4, 2, 2), 40.0);
+    dbz.slice_mut(ndarray::s![3, .., ..]).fill(f32::NAN);
+    dbz[[3, 1, 1]] = -5.0;
+    grid.add_field(GridField::new("DBZH".to_string(), "dBZ".to_string(), dbz)).unwrap();
+
+    let product = vil_grid(&grid, &VilOptions::default()).unwrap();
+    assert_eq!(product.z, vec![1000.0]);
+    let vil = &product.get_field(VIL).unwrap().data;
+    assert!((vil[[0, 0, 0]] as f64 - expected).abs() < 1e-4);
+    let density = &product.get_field(VIL_DENSITY).unwrap().data;
+    assert!((density[[0, 0, 0]] as f64 - expected / 5000.0 * 1000.0).abs() < 1e-4);
+    // A weak echo above the top extends the integration but not the echo top
+    assert!(vil[[0, 1, 1]] > vil[[0, 0, 0]]);
+    assert!((density[[0, 1, 1]] - vil[[0, 1, 1]] / 5.0).abs() < 1e-4);
+
+    // Uniform reflectivity in three tilts: VIL grows with the depth sampled
+    let sweep = |number: u32, angle: f64| {
+        let mut moments = HashMap::new();
+        moments.insert(
+            "DBZH".to_string(),
+            MomentData::new("DBZH".to_string(), "dBZ".to_string(), Array2::from_elem((360, 50), 40.0)),
+        );
+        let azimuth: Vec<f32> = (0..360).map(|a| a as f32 + 0.5).collect();
+        let range: Vec<f32> = (0..50).map(|j| 500.0 + 1000.0 * j as f32).collect();
+        let coords = Coordinates::new(vec![0.0; 360], range, azimuth, vec![angle as f32; 360]);
+        SweepData::new(SweepMetadata::new(number, SweepMode::Azimuth, angle), moments, coords)
+    };
+    let metadata = VolumeMetadata::new("TEST".to_string(), 0.0, 0.0, 0.0, chrono::Utc::now(), chrono::Utc::now());
+    let volume = VolumeData::new(metadata, vec![sweep(1, 4.5), sweep(0, 0.5), sweep(2, 9.5)]);
+
+    let product = vil_volume(&volume, &VilOptions::default()).unwrap();
+    assert_eq!(product.metadata.fixed_angle, 0.5);
+    let vil = &product.moments[VIL].data();
+    assert_eq!(vil.dim(), (360, 50));
+    assert!(vil[[0, 10]] > 0.0);
+    assert!(vil[[0, 20]] > vil[[0, 10]]);
+    // Past the range of the highest tilt only two tilts are stacked
+    assert!(vil[[90, 49]] > 0.0);
+}
+
+#[test]
+fn test_storm_cells() {
+    use chrono::TimeZone;
+    use ndarray::Array3;
+    use radish::transforms::cells::{identify_cells, track_cells, CellOptions, TrackOptions};
+    use radish::transforms::georeference::Projection;
+    use radish::{GridField, GriddedData};
+
+    // A 6 × 6 km cell 3 km deep moving 3 km east every 5 minutes, a
+    // stationary cell, a speck below the minimum volume and, in the last
+    // grid, a new cell
+    let grid = |step: usize| {
+        let time = chrono::Utc.with_ymd_and_hms(2024, 6, 1, 0, 5 * step as u32, 0).unwrap();
+        let axis: Vec<f64> = (0..40).map(|i| i as f64 * 1000.0).collect();
+        let mut grid = GriddedData::new(time, Projection::aeqd(0.0, 0.0), axis.clone(), axis, vec![1000.0, 2000.0, 3000.0]);
+        let mut dbz = Array3::from_elem((3, 40, 40), 10.0);
+        let x0 = 5 + 3 * step;
+        dbz.slice_mut(ndarray::s![.., 5..11, x0..x0 + 6]).fill(45.0);
+        dbz[[0, 7, x0 + 2]] = 60.0;
+        dbz.slice_mut(ndarray::s![..2, 25..31, 25..31]).fill(40.0);
+        dbz[[0, 35, 5]] = 50.0;
+        if step == 2 {
+            dbz.slice_mut(ndarray::s![.., 30..36, 5..11]).fill(38.0);
+        }
+        grid.add_field(GridField::new("DBZH".to_string(), "dBZ".to_string(), dbz)).unwrap();
+        grid
+    };
+    let grids: Vec<_> = (0..3).map(grid).collect();
+
+    let cells = identify_cells(&grids[0], &CellOptions::default()).unwrap();
+    assert_eq!(cells.len(), 2);
+    assert!((cells[0].volume - 108.0).abs() < 1e-6);
+    assert!((cells[0].area - 36.0).abs() < 1e-6);
+    assert_eq!(cells[0].max_reflectivity, 60.0);
+    assert_eq!(cells[0].echo_top, 3000.0);
+    assert!((cells[0].x - 7500.0).abs() < 1e-6);
+    assert_eq!(cells[1].echo_top, 2000.0);
+
+    let tracks = track_cells(&grids, &TrackOptions::default()).unwrap();
+    assert_eq!(tracks.len(), 3);
+    let moving = &tracks[0];
+    assert_eq!(moving.cells.len(), 3);
+    let (u, v) = moving.cells[2].velocity.unwrap();
+    assert!((u - 10.0).abs() < 1e-6);
+    assert_eq!(v, 0.0);
+    assert_eq!(tracks[1].cells.len(), 3);
+    assert_eq!(tracks[1].cells[2].velocity, Some((0.0, 0.0)));
+    assert_eq!(tracks[2].start(), Some(grids[2].time));
+    assert_eq!(tracks[2].parent, None);
+}
+
+/// Build a little-endian, single-strip int16 GeoTIFF on a geographic grid
+fn geotiff(width: u16, height: u16, scale: f64, corner: (f64, f64), pixels: &[i16]) -> Vec<u8> {
+    let entries = 11u32;
+    let extra = 8 + 2 + 12 * entries + 4;
+    let (scale_at, tie_at, nodata_at) = (extra, extra + 24, extra + 72);
+    let pixels_at = nodata_at + 8;
+
+    let mut out = b"II*\0".to_vec();
+    out.extend(8u32.to_le_bytes());
+    out.extend((entries as u16).to_le_bytes());
+    let mut entry = |tag: u16, kind: u16, count: u32, value: u32| {
+        out.extend(tag.to_le_bytes());
+        out.extend(kind.to_le_bytes());
+        out.extend(count.to_le_bytes());
+        out.extend(value.to_le_bytes());
+    };
+    entry(256, 3, 1, width as u32);
+    entry(257, 3, 1, height as u32);
+    entry(258, 3, 1, 16);
+    entry(259, 3, 1, 1);
+    entry(273, 4, 1, pixels_at);
+    entry(277, 3, 1, 1);
+    entry(278, 3, 1, height as u32);
+    entry(339, 3, 1, 2);
+    entry(33550, 12, 3, scale_at);
+    entry(33922, 12, 6, tie_at);
+    entry(42113, 2, 7, nodata_at);
+    out.extend(0u32.to_le_bytes());
+    for v in [scale, scale, 0.0, 0.0, 0.0, 0.0, corner.0, corner.1, 0.0] {
+        out.extend(v.to_le_bytes());
+    }
+    out.extend(b"-32768\0\0");
+    for v in pixels {
+        out.extend(v.to_le_bytes());
+    }
+    out
+}
+
+#[test]
+fn test_beam_blockage() {
+    use ndarray::Array2;
+    use radish::io::geotiff::{read_geotiff_dem, ELEVATION};
+    use radish::model::InstrumentParameters;
+    use radish::transforms::blockage::{blocked_fraction, compute_blockage, BlockageOptions, BEAM_BLOCKAGE};
+    use radish::{Coordinates, MomentData, SweepData, SweepMetadata, VolumeData, VolumeMetadata};
+    use radish_types::SweepMode;
+    use std::collections::HashMap;
+
+    assert_eq!(blocked_fraction(-100.0, 50.0), 0.0);
+    assert!((blocked_fraction(0.0, 50.0) - 0.5).abs() < 1e-12);
+    assert_eq!(blocked_fraction(60.0, 50.0), 1.0);
+
+    // Flat terrain at sea level with a 1000 m north-south ridge 10 km east
+    // of the radar and a no-data pixel
+    let (width, height) = (101u16, 101u16);
+    let mut pixels = vec![0i16; width as usize * height as usize];
+    for row in 0..height as usize {
+        for col in 59..61 {
+            pixels[row * width as usize + col] = 1000;
+        }
+    }
+    pixels[0] = -32768;
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("dem.tif");
+    std::fs::write(&path, geotiff(width, height, 0.01, (-0.505, 0.505), &pixels)).unwrap();
+
+    let dem = read_geotiff_dem(&path).unwrap();
+    assert_eq!(dem.shape(), (1, 101, 101));
+    assert!((dem.x[0] + 0.5).abs() < 1e-9);
+    assert!((dem.y[100] - 0.5).abs() < 1e-9);
+    let elevation = &dem.get_field(ELEVATION).unwrap().data;
+    assert!(elevation[[0, 100, 0]].is_nan());
+    assert_eq!(elevation[[0, 50, 60]], 1000.0);
+
+    let mut moments = HashMap::new();
+    moments.insert(
+        "DBZH".to_string(),
+        MomentData::new("DBZH".to_string(), "dBZ".to_string(), Array2::zeros((4, 40))),
+    );
+    let azimuth = vec![0.0, 90.0, 180.0, 270.0];
+    let range: Vec<f32> = (0..40).map(|j| 250.0 + 500.0 * j as f32).collect();
+    let coords = Coordinates::new(vec![0.0; 4], range, azimuth, vec![0.5; 4]);
+    let sweep = SweepData::new(SweepMetadata::new(0, SweepMode::Azimuth, 0.5), moments, coords);
+    let metadata = VolumeMetadata::new("TEST".to_string(), 0.0, 0.0, 100.0, chrono::Utc::now(), chrono::Utc::now());
+    let mut volume = VolumeData::new(metadata, vec![sweep]);
+
+    let blocked = compute_blockage(&volume, &dem, &BlockageOptions::default()).unwrap();
+    let pbb = &blocked.sweeps[0].moments[BEAM_BLOCKAGE];
+    assert!(pbb.provenance.is_some());
+    // Unblocked before the ridge, totally blocked from it on, never to the west
+    assert_eq!(pbb.data()[[1, 10]], 0.0);
+    assert_eq!(pbb.data()[[1, 30]], 1.0);
+    assert_eq!(pbb.data()[[1, 39]], 1.0);
+    assert_eq!(pbb.data().row(3).sum(), 0.0);
+
+    // A 20° beam from the instrument parameters is only partly blocked by the ridge
+    volume.metadata.instrument_parameters = Some(InstrumentParameters {
+        beam_width_h: Some(20.0),
+        ..Default::default()
+    });
+    let wide = compute_blockage(&volume, &dem, &BlockageOptions::default()).unwrap();
+    let partial = wide.sweeps[0].moments[BEAM_BLOCKAGE].data()[[1, 30]];
+    assert!(partial > 0.0 && partial < 1.0);
+}
+
+#[test]
+fn test_snowfall_rate() {
+    use ndarray::Array2;
+    use radish::transforms::qpe::{estimate_snowfall, PhaseInput, SnowfallOptions, SNOW_RATE};
+    use radish::{Coordinates, MomentData, SweepData, SweepMetadata, VolumeData, VolumeMetadata};
+    use radish_types::SweepMode;
+    use std::collections::HashMap;
+
+    let mut moments = HashMap::new();
+    moments.insert(
+        "DBZH".to_string(),
+        MomentData::new("DBZH".to_string(), "dBZ".to_string(), Array2::from_elem((4, 3), 25.0)),
+    );
+    let mut temperature = Array2::from_elem((4, 3), -10.0);
+    temperature[[1, 0]] = 0.0;
+    temperature[[2, 0]] = 5.0;
+    moments.insert("TEMP".to_string(), MomentData::new("TEMP".to_string(), "degC".to_string(), temperature));
+    let coords = Coordinates::new(vec![0.0; 4], vec![1000.0, 2000.0, 3000.0], vec![0.0, 90.0, 180.0, 270.0], vec![0.5; 4]);
+    let sweep = SweepData::new(SweepMetadata::new(0, SweepMode::Azimuth, 0.5), moments, coords);
+    let metadata = VolumeMetadata::new("TEST".to_string(), 0.0, 0.0, 0.0, chrono::Utc::now(), chrono::Utc::now());
+    let volume = VolumeData::new(metadata, vec![sweep]);
+
+    let options = SnowfallOptions {
+        phase: PhaseInput::TemperatureMoment("TEMP".to_string()),
+        ..Default::default()
+    };
+    let rate = &estimate_snowfall(&volume, &options).unwrap().sweeps[0].moments[SNOW_RATE];
+    // Dry snow: Z = 75 S², 25 dBZ → S ≈ 2.05 mm/h
+    assert!((rate.data()[[0, 0]] - (10f32.powf(2.5) / 75.0).sqrt()).abs() < 1e-3);
+    // Wet snow uses its own relation; rain gates have no snowfall
+    assert!(rate.data()[[1, 0]] > 0.0 && rate.data()[[1, 0]] != rate.data()[[0, 0]]);
+    assert!(rate.data()[[2, 0]].is_nan());
+
+    let provenance = rate.provenance.as_ref().unwrap();
+    assert_eq!(provenance.ancestors, vec!["DBZH", "TEMP"]);
+    assert_eq!(provenance.algorithm, "radish.transforms.qpe.snowfall_sweep");
+}
+
+#[test]
+fn test_moment_provenance() {
+    use radish::Provenance;
+
+    let options = ("PHIDP", 10, 0.5);
+    let provenance = Provenance::new("test.algorithm", &["PHIDP", "DBZH"], &options);
+    // The parameters hash is stable and depends on the parameters
+    assert_eq!(provenance.parameters_hash, Provenance::new("other", &["KDP"], &options).parameters_hash);
+    assert_ne!(provenance.parameters_hash, Provenance::new("test.algorithm", &["PHIDP"], &("PHIDP", 11, 0.5)).parameters_hash);
+    assert!(provenance.parameters_hash.starts_with("fnv1a64:"));
+
+    let attributes = provenance.to_attributes();
+    assert_eq!(attributes["ancestors"], r#"["PHIDP","DBZH"]"#);
+    assert_eq!(Provenance::from_attributes(&attributes), Some(provenance));
+}
+
+#[test]
+fn test_dual_frequency_ratio() {
+    use ndarray::Array2;
+    use radish::transforms::dfr::{dual_frequency_ratio, DfrOptions, DFR};
+    use radish::{Coordinates, MomentData, SweepData, SweepMetadata, VolumeData, VolumeMetadata};
+    use radish_types::SweepMode;
+    use std::collections::HashMap;
+
+    let volume = |frequency: f64, azimuth_offset: f32, range_start: f32, dbz: Array2<f32>| {
+        let (num_rays, num_gates) = dbz.dim();
+        let mut moments = HashMap::new();
+        moments.insert("DBZH".to_string(), MomentData::new("DBZH".to_string(), "dBZ".to_string(), dbz));
+        let azimuth: Vec<f32> = (0..num_rays).map(|a| a as f32 * 10.0 + azimuth_offset).collect();
+        let range: Vec<f32> = (0..num_gates).map(|j| range_start + 100.0 * j as f32).collect();
+        let coords = Coordinates::new(vec![0.0; num_rays], range, azimuth, vec![1.0; num_rays]);
+        let sweep = SweepData::new(SweepMetadata::new(0, SweepMode::Azimuth, 1.0), moments, coords);
+        let mut metadata = VolumeMetadata::new("TEST".to_string(), 40.0, -105.0, 1600.0, chrono::Utc::now(), chrono::Utc::now());
+        metadata.frequency = Some(frequency);
+        VolumeData::new(metadata, vec![sweep])
+    };
+
+    // W band (94 GHz) on a grid offset by half a gate and 0.5° from Ka band (35 GHz)
+    let ka = volume(35e9, 0.0, 100.0, Array2::from_elem((36, 10), 20.0));
+    let w_dbz = Array2::from_shape_fn((36, 10), |(_, j)| 10.0 + j as f32);
+    let w = volume(94e9, 0.5, 50.0, w_dbz);
+
+    let options = DfrOptions::default();
+    let dfr = &dual_frequency_ratio(&ka, &w, &options).unwrap().sweeps[0].moments[DFR];
+    // W interpolated to 100 m: 10.5 dBZ
+    assert!((dfr.data()[[0, 0]] - 9.5).abs() < 1e-4);
+    assert!(dfr.data()[[0, 9]].is_nan());
+
+    // Ka minus W regardless of which volume is the reference
+    let dfr = &dual_frequency_ratio(&w, &ka, &options).unwrap().sweeps[0].moments[DFR];
+    assert!(dfr.data()[[0, 0]].is_nan());
+    assert!((dfr.data()[[0, 1]] - 9.0).abs() < 1e-4);
+}
+
+#[test]
+fn test_processing_history() {
+    use radish::model::{Pattern, VolumeBuilder, HISTORY_ATTRIBUTE};
+    use radish::transforms::georeference::georeference;
+    use radish::transforms::snr::{compute_snr, SnrOptions};
+
+    let mut volume = VolumeBuilder::new()
+        .elevations(&[0.5])
+        .moment("DBZH", "dBZ", Pattern::Constant(20.0))
+        .build();
+    assert!(volume.history.is_empty());
+    assert!(volume.history_attribute().is_none());
+
+    let options = SnrOptions {
+        noise_dbz_1km: Some(-20.0),
+        ..Default::default()
+    };
+    let processed = compute_snr(&georeference(&volume).unwrap(), &options).unwrap();
+    let operations: Vec<&str> = processed.history.entries().iter().map(|e| e.operation.as_str()).collect();
+    assert_eq!(
+        operations,
+        vec![
+            "radish.transforms.georeference.georeference_with_options",
+            "radish.transforms.snr.compute_snr"
+        ]
+    );
+    let snr = &processed.history.entries()[1];
+    assert!(snr.parameters.contains("noise_dbz_1km: Some(-20.0)"));
+    assert_eq!(snr.version, env!("CARGO_PKG_VERSION"));
+    // Nothing left to compute: no step is recorded
+    assert_eq!(compute_snr(&processed, &options).unwrap().history.len(), 2);
+
+    // The history read from the file comes first
+    volume.metadata.attributes.insert(HISTORY_ATTRIBUTE.to_string(), "2020-01-01: RadxConvert\n".into());
+    volume.history = processed.history.clone();
+    let history = volume.history_attribute().unwrap();
+    let lines: Vec<&str> = history.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0], "2020-01-01: RadxConvert");
+    assert!(lines[2].contains(": radish ") && lines[2].ends_with(&snr.parameters));
+}
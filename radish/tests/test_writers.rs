@@ -0,0 +1,189 @@
+//! Tests for format writers
+
+use radish::writers::matlab::matlab_field_name;
+
+#[test]
+fn test_matlab_field_name() {
+    assert_eq!(matlab_field_name("DBZH"), "DBZH");
+    assert_eq!(matlab_field_name("corrected-reflectivity"), "corrected_reflectivity");
+    assert_eq!(matlab_field_name("1st_moment"), "m_1st_moment");
+    assert_eq!(matlab_field_name(&"X".repeat(80)).len(), 63);
+}
+
+#[test]
+fn test_motion_writer_requires_fields() {
+    use radish::transforms::georeference::Projection;
+    use radish::writers::motion::{write_motion_netcdf, MotionWriterOptions};
+    use radish::{GriddedData, RadishError};
+
+    let grid = GriddedData::new(chrono::Utc::now(), Projection::aeqd(0.0, 0.0), vec![0.0], vec![0.0], vec![0.0]);
+    let path = std::env::temp_dir().join("radish_motion_missing.nc");
+    let result = write_motion_netcdf(&grid, &path, &MotionWriterOptions::default());
+    assert!(matches!(result, Err(RadishError::MissingVariable(name)) if name == "MOTION_U"));
+    assert!(!path.exists());
+}
+
+#[test]
+fn test_filename_template() {
+    use chrono::TimeZone;
+    use radish::writers::naming::FilenameTemplate;
+    use radish::{RadishError, VolumeMetadata};
+
+    let start = chrono::Utc.with_ymd_and_hms(2024, 5, 20, 21, 3, 7).unwrap();
+    let mut metadata = VolumeMetadata::new("KTLX".to_string(), 35.3, -97.3, 370.0, start, start);
+    metadata.attributes.insert("vcp".to_string(), 212i64.into());
+
+    let template = FilenameTemplate::parse("{site}/{start:%Y%m%d}/{site}_{start:%Y%m%d_%H%M%S}_{vcp}.nc").unwrap();
+    assert_eq!(template.render(&metadata).unwrap(), "KTLX/20240520/KTLX_20240520_210307_212.nc");
+    let template = FilenameTemplate::parse("{{{site}}}_{start}").unwrap();
+    assert_eq!(template.render(&metadata).unwrap(), "{KTLX}_20240520T210307Z");
+
+    metadata.instrument_name = "TEST SITE/2".to_string();
+    assert_eq!(FilenameTemplate::parse("{site}.nc").unwrap().render(&metadata).unwrap(), "TEST_SITE_2.nc");
+    assert!(matches!(
+        FilenameTemplate::parse("{scan}.nc").unwrap().render(&metadata),
+        Err(RadishError::MissingAttribute(name)) if name == "scan"
+    ));
+
+    assert!(FilenameTemplate::parse("{site").is_err());
+    assert!(FilenameTemplate::parse("{site:%Y}").is_err());
+    assert!(FilenameTemplate::parse("{start:%Q}").is_err());
+}
+
+#[test]
+fn test_odim_writer_requires_ppi() {
+    use radish::model::{Pattern, SweepBuilder, VolumeBuilder};
+    use radish::writers::odim::write_odim;
+    use radish::RadishError;
+    use radish_types::SweepMode;
+
+    let volume = VolumeBuilder::new()
+        .elevations(&[0.5, 1.5])
+        .sweep(SweepBuilder::new(0.0).rays(360, 1.0, 0.5).gates(100, 250.0, 125.0))
+        .moment("DBZH", "dBZ", Pattern::Constant(20.0))
+        .build();
+    let mut rhi = volume.clone();
+    rhi.sweeps[1].metadata.sweep_mode = SweepMode::Elevation;
+
+    let path = std::env::temp_dir().join("radish_odim_rhi.h5");
+    let result = write_odim(&rhi, &path);
+    assert!(matches!(result, Err(RadishError::Unsupported(message)) if message.contains("elevation_surveillance")));
+    assert!(!path.exists());
+}
+
+/// Volume with a masked gate, for writer round trips
+fn round_trip_volume() -> radish::VolumeData {
+    use radish::model::{Pattern, SweepBuilder, VolumeBuilder};
+
+    let mut volume = VolumeBuilder::new()
+        .elevations(&[0.5, 1.5])
+        .sweep(SweepBuilder::new(0.0).rays(36, 10.0, 5.0).gates(20, 250.0, 125.0))
+        .moment("DBZH", "dBZ", Pattern::RangeRamp { offset: 10.0, slope: 0.37 })
+        .moment("VRADH", "m/s", Pattern::Constant(-3.25))
+        .build();
+    let dbzh = volume.sweeps[0].moments.get_mut("DBZH").unwrap();
+    let mut mask = ndarray::Array2::from_elem(dbzh.shape(), true);
+    mask[[3, 7]] = false;
+    dbzh.set_mask(Some(mask)).unwrap();
+    volume
+}
+
+/// Moment read back from a written file, missing gates as `fill`
+fn read_back(like: &radish::MomentData, data: ndarray::Array2<f32>, fill: Option<f32>) -> radish::MomentData {
+    let mut moment = radish::MomentData::new(like.name.clone(), like.units.clone(), data);
+    moment.fill_value = fill;
+    moment.update_mask();
+    moment
+}
+
+#[test]
+fn test_hdf5_odim_round_trip() {
+    use hdf5::types::VarLenAscii;
+    use radish::compare::{first_mismatch, ToleranceSpec};
+    use radish::writers::odim::{write_odim, ODIM_NODATA};
+
+    let volume = round_trip_volume();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("volume.h5");
+    write_odim(&volume, &path).unwrap();
+
+    let tolerances: ToleranceSpec = "*=0".parse().unwrap();
+    let file = hdf5::File::open(&path).unwrap();
+    for (i, sweep) in volume.sweeps.iter().enumerate() {
+        // The builder's rays are already in azimuth order, as ODIM writes them
+        assert!(sweep.coordinates.azimuth.windows(2).all(|w| w[0] < w[1]));
+        for j in 0..sweep.moments.len() {
+            let group = file.group(&format!("dataset{}/data{}", i + 1, j + 1)).unwrap();
+            let quantity = group.group("what").unwrap().attr("quantity").unwrap().read_scalar::<VarLenAscii>().unwrap();
+            let expected = &sweep.moments[quantity.as_str()];
+            let dataset = group.dataset("data").unwrap();
+            let data = ndarray::Array2::from_shape_vec(expected.shape(), dataset.read_raw::<f32>().unwrap()).unwrap();
+            let actual = read_back(expected, data, Some(ODIM_NODATA as f32));
+
+            let tolerance = tolerances.for_moment(&expected.name);
+            assert_eq!(first_mismatch(&actual, expected, &tolerance).unwrap(), None, "{}", expected.name);
+        }
+    }
+}
+
+#[test]
+fn test_hdf5_matlab_round_trip() {
+    use radish::compare::{first_mismatch, ToleranceSpec};
+    use radish::writers::matlab::write_volume_mat;
+
+    let volume = round_trip_volume();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("volume.mat");
+    write_volume_mat(&volume, &path).unwrap();
+
+    let tolerances: ToleranceSpec = "*=0".parse().unwrap();
+    let file = hdf5::File::open(&path).unwrap();
+    for (i, sweep) in volume.sweeps.iter().enumerate() {
+        for (name, expected) in &sweep.moments {
+            let dataset = file.dataset(&format!("volume/sweep_{}/moments/{}/data", i, name)).unwrap();
+            // MATLAB matrices are stored column-major, [gates × rays] in HDF5
+            let (nrays, ngates) = expected.shape();
+            assert_eq!(dataset.shape(), vec![ngates, nrays]);
+            let data = ndarray::Array2::from_shape_vec((ngates, nrays), dataset.read_raw::<f32>().unwrap())
+                .unwrap()
+                .reversed_axes();
+            let actual = read_back(expected, data, None);
+
+            assert_eq!(first_mismatch(&actual, expected, &tolerances.for_moment(name)).unwrap(), None, "{}", name);
+        }
+    }
+}
+
+#[test]
+fn test_netcdf_motion_round_trip() {
+    use ndarray::{Array2, Array3};
+    use radish::compare::{first_mismatch, ToleranceSpec};
+    use radish::transforms::georeference::Projection;
+    use radish::writers::motion::{write_motion_netcdf, MotionWriterOptions, MOTION_U, MOTION_V};
+    use radish::{GridField, GriddedData, MomentData};
+
+    let (nx, ny) = (6, 4);
+    let x: Vec<f64> = (0..nx).map(|i| i as f64 * 1000.0).collect();
+    let y: Vec<f64> = (0..ny).map(|j| j as f64 * 1000.0).collect();
+    let mut grid = GriddedData::new(chrono::Utc::now(), Projection::aeqd(35.0, -97.0), x, y, vec![0.0]);
+    let mut u = Array3::from_shape_fn((1, ny, nx), |(_, j, i)| 0.25 * i as f32 - 0.1 * j as f32);
+    u[[0, 2, 3]] = f32::NAN;
+    let v = Array3::from_shape_fn((1, ny, nx), |(_, j, i)| 1.5 + 0.01 * (i * j) as f32);
+    grid.add_field(GridField::new(MOTION_U.to_string(), "m s-1".to_string(), u)).unwrap();
+    grid.add_field(GridField::new(MOTION_V.to_string(), "m s-1".to_string(), v)).unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("motion.nc");
+    write_motion_netcdf(&grid, &path, &MotionWriterOptions::default()).unwrap();
+
+    let tolerances: ToleranceSpec = "*=1e-6".parse().unwrap();
+    let file = netcdf::open(&path).unwrap();
+    for (variable, field) in [("u", MOTION_U), ("v", MOTION_V)] {
+        let values = file.variable(variable).unwrap().get_values::<f32, _>(..).unwrap();
+        let actual = MomentData::new(field.to_string(), "m s-1".to_string(), Array2::from_shape_vec((ny, nx), values).unwrap());
+        let level = grid.get_field(field).unwrap().data.index_axis(ndarray::Axis(0), 0).to_owned();
+        let expected = MomentData::new(field.to_string(), "m s-1".to_string(), level);
+
+        assert_eq!(first_mismatch(&actual, &expected, &tolerances.for_moment(field)).unwrap(), None, "{}", field);
+    }
+}
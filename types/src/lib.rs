@@ -1,4 +1,4 @@
-/// Common types and constants shared across the radish ecosystem
+//! Common types and constants shared across the radish ecosystem
 
 use serde::{Deserialize, Serialize};
 
@@ -27,6 +27,24 @@ pub enum SweepMode {
     VerticalPointing,
 }
 
+impl SweepMode {
+    /// CfRadial `sweep_mode` name (e.g. "azimuth_surveillance")
+    pub fn cf_name(&self) -> &'static str {
+        match self {
+            SweepMode::Azimuth => "azimuth_surveillance",
+            SweepMode::Elevation => "elevation_surveillance",
+            SweepMode::Sector => "sector",
+            SweepMode::Coplane => "coplane",
+            SweepMode::Pointing => "pointing",
+            SweepMode::ManualPpi => "manual_ppi",
+            SweepMode::ManualRhi => "manual_rhi",
+            SweepMode::Idle => "idle",
+            SweepMode::Calibration => "calibration",
+            SweepMode::VerticalPointing => "vertical_pointing",
+        }
+    }
+}
+
 /// Follow mode enumeration
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum FollowMode {
@@ -44,6 +62,20 @@ pub enum FollowMode {
     Manual,
 }
 
+impl FollowMode {
+    /// CfRadial `follow_mode` name (e.g. "sun")
+    pub fn cf_name(&self) -> &'static str {
+        match self {
+            FollowMode::None => "none",
+            FollowMode::Sun => "sun",
+            FollowMode::Vehicle => "vehicle",
+            FollowMode::Aircraft => "aircraft",
+            FollowMode::Target => "target",
+            FollowMode::Manual => "manual",
+        }
+    }
+}
+
 /// PRT (Pulse Repetition Time) mode
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum PrtMode {
@@ -59,6 +91,17 @@ pub enum PrtMode {
     Dual,
 }
 
+impl PrtMode {
+    /// CfRadial `prt_mode` name; all staggered ratios are "staggered"
+    pub fn cf_name(&self) -> &'static str {
+        match self {
+            PrtMode::Fixed => "fixed",
+            PrtMode::Staggered2_3 | PrtMode::Staggered3_4 | PrtMode::Staggered4_5 => "staggered",
+            PrtMode::Dual => "dual",
+        }
+    }
+}
+
 /// Platform type
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum PlatformType {
@@ -74,6 +117,130 @@ pub enum PlatformType {
     Satellite,
 }
 
+impl PlatformType {
+    /// CfRadial `platform_type` name (e.g. "fixed")
+    pub fn cf_name(&self) -> &'static str {
+        match self {
+            PlatformType::Fixed => "fixed",
+            PlatformType::Vehicle => "vehicle",
+            PlatformType::Ship => "ship",
+            PlatformType::Aircraft => "aircraft",
+            PlatformType::Satellite => "satellite",
+        }
+    }
+}
+
+/// Physical unit of a moment, for unit conversion
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Unit {
+    /// Meters per second
+    MetersPerSecond,
+    /// Knots
+    Knots,
+    /// Meters
+    Meters,
+    /// Kilometers
+    Kilometers,
+    /// Logarithmic reflectivity factor
+    Dbz,
+    /// Linear reflectivity factor (mm⁶/m³)
+    ReflectivityLinear,
+    /// Millimeters per hour
+    MillimetersPerHour,
+    /// Inches per hour
+    InchesPerHour,
+}
+
+/// Quantity measured by a [`Unit`]; units convert only within a quantity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum UnitQuantity {
+    /// Velocities
+    Speed,
+    /// Distances
+    Length,
+    /// Reflectivity factors
+    Reflectivity,
+    /// Precipitation rates
+    RainRate,
+}
+
+impl Unit {
+    /// Units symbol, as written to the `units` attribute
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Unit::MetersPerSecond => "m/s",
+            Unit::Knots => "kt",
+            Unit::Meters => "m",
+            Unit::Kilometers => "km",
+            Unit::Dbz => "dBZ",
+            Unit::ReflectivityLinear => "mm6 m-3",
+            Unit::MillimetersPerHour => "mm/h",
+            Unit::InchesPerHour => "in/h",
+        }
+    }
+
+    /// Parse a `units` attribute, accepting common spellings
+    pub fn from_symbol(units: &str) -> Option<Self> {
+        match units.trim() {
+            "m/s" | "m s-1" | "m s^-1" | "meters per second" | "meters/second" => Some(Unit::MetersPerSecond),
+            "kt" | "kts" | "knot" | "knots" => Some(Unit::Knots),
+            "m" | "meter" | "meters" | "metre" | "metres" => Some(Unit::Meters),
+            "km" | "kilometer" | "kilometers" | "kilometre" | "kilometres" => Some(Unit::Kilometers),
+            "dBZ" | "dBz" | "dbz" => Some(Unit::Dbz),
+            "mm6 m-3" | "mm6/m3" | "mm^6/m^3" | "mm6 m^-3" => Some(Unit::ReflectivityLinear),
+            "mm/h" | "mm/hr" | "mm h-1" | "mm hr-1" => Some(Unit::MillimetersPerHour),
+            "in/h" | "in/hr" | "in h-1" | "in hr-1" => Some(Unit::InchesPerHour),
+            _ => None,
+        }
+    }
+
+    /// Quantity the unit measures
+    pub fn quantity(&self) -> UnitQuantity {
+        match self {
+            Unit::MetersPerSecond | Unit::Knots => UnitQuantity::Speed,
+            Unit::Meters | Unit::Kilometers => UnitQuantity::Length,
+            Unit::Dbz | Unit::ReflectivityLinear => UnitQuantity::Reflectivity,
+            Unit::MillimetersPerHour | Unit::InchesPerHour => UnitQuantity::RainRate,
+        }
+    }
+
+    /// Convert a value in this unit to `target`, `None` if they measure different quantities
+    pub fn convert(&self, value: f64, target: Unit) -> Option<f64> {
+        (self.quantity() == target.quantity()).then(|| target.base_to(self.to_base(value)))
+    }
+
+    /// Value in the base unit of the quantity (m/s, m, mm⁶/m³, mm/h)
+    fn to_base(self, value: f64) -> f64 {
+        match self {
+            Unit::Knots => value * KNOT,
+            Unit::Kilometers => value * 1000.0,
+            Unit::Dbz => 10f64.powf(value / 10.0),
+            Unit::InchesPerHour => value * 25.4,
+            Unit::MetersPerSecond | Unit::Meters | Unit::ReflectivityLinear | Unit::MillimetersPerHour => value,
+        }
+    }
+
+    /// Value in this unit of a value in the base unit
+    fn base_to(self, value: f64) -> f64 {
+        match self {
+            Unit::Knots => value / KNOT,
+            Unit::Kilometers => value / 1000.0,
+            Unit::Dbz => 10.0 * value.log10(),
+            Unit::InchesPerHour => value / 25.4,
+            Unit::MetersPerSecond | Unit::Meters | Unit::ReflectivityLinear | Unit::MillimetersPerHour => value,
+        }
+    }
+}
+
+impl std::fmt::Display for Unit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.symbol())
+    }
+}
+
+/// One knot (m/s)
+const KNOT: f64 = 1852.0 / 3600.0;
+
 /// CfRadial2 standard moment names and metadata
 pub mod moments {
     /// Reflectivity (Horizontal)